@@ -0,0 +1,14 @@
+-- スレッドごとの既読位置テーブルの追加
+-- スレッド削除時は ON DELETE CASCADE により既読位置も削除される
+CREATE TABLE thread_read_positions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    thread_id UUID NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    last_read_comment_id UUID REFERENCES comments(id) ON DELETE SET NULL,
+    last_read_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT user_thread_read_position_unique UNIQUE(user_id, thread_id)
+);
+
+CREATE INDEX idx_thread_read_positions_thread_id ON thread_read_positions(thread_id);
@@ -0,0 +1,14 @@
+-- 管理者フラグとユーザーの一時的な投稿禁止（ミュート）テーブルの追加
+-- 有効なミュートは、ユーザーごとの最新の行のうち期限が未来のもの
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE user_mutes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    muted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_mutes_user_id_created_at ON user_mutes(user_id, created_at DESC);
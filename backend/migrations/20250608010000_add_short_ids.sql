@@ -0,0 +1,32 @@
+-- スレッドとコメントに短い公開ID（ランダムな64bit値のbase62表現）を追加
+-- 内部的な識別子は引き続きUUIDを使用する
+CREATE OR REPLACE FUNCTION generate_short_id() RETURNS VARCHAR AS $$
+DECLARE
+    alphabet CONSTANT TEXT := '0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz';
+    value NUMERIC := floor(random() * 4294967296)::NUMERIC * 4294967296 + floor(random() * 4294967296)::NUMERIC;
+    result TEXT := '';
+BEGIN
+    LOOP
+        result := substr(alphabet, (value % 62)::INT + 1, 1) || result;
+        value := floor(value / 62);
+        EXIT WHEN value = 0;
+    END LOOP;
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql VOLATILE;
+
+-- 既存のスレッドに短いIDを付与
+ALTER TABLE threads ADD COLUMN short_id VARCHAR(11);
+UPDATE threads SET short_id = generate_short_id();
+ALTER TABLE threads
+    ALTER COLUMN short_id SET NOT NULL,
+    ALTER COLUMN short_id SET DEFAULT generate_short_id(),
+    ADD CONSTRAINT threads_short_id_key UNIQUE (short_id);
+
+-- 既存のコメントに短いIDを付与
+ALTER TABLE comments ADD COLUMN short_id VARCHAR(11);
+UPDATE comments SET short_id = generate_short_id();
+ALTER TABLE comments
+    ALTER COLUMN short_id SET NOT NULL,
+    ALTER COLUMN short_id SET DEFAULT generate_short_id(),
+    ADD CONSTRAINT comments_short_id_key UNIQUE (short_id);
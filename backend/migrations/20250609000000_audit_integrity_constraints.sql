@@ -0,0 +1,30 @@
+-- 一意制約・参照整合性の監査
+-- users.username / users.email、votes(user_id, thread_id)、thread_read_positions(user_id, thread_id)
+-- はテーブル作成時から一意制約を持つため変更しない
+-- ここではアプリケーション側の事前チェックでしか担保していなかった制約を追加する
+
+-- OAuth連携: 同じユーザーが同じプロバイダーを重複して連携しないようにする
+-- 既存の重複は最も新しい連携のみを残す
+DELETE FROM oauth_accounts a
+USING oauth_accounts b
+WHERE a.user_id = b.user_id
+  AND a.provider = b.provider
+  AND (a.created_at, a.id) < (b.created_at, b.id);
+
+ALTER TABLE oauth_accounts
+    ADD CONSTRAINT oauth_accounts_user_id_provider_key UNIQUE (user_id, provider);
+
+-- コメント: 親コメントは同じスレッドに属していなければならない
+-- 既存の不整合なリプライはトップレベルのコメントに戻す
+UPDATE comments c
+SET parent_id = NULL
+FROM comments p
+WHERE c.parent_id = p.id
+  AND c.thread_id <> p.thread_id;
+
+ALTER TABLE comments
+    ADD CONSTRAINT comments_id_thread_id_key UNIQUE (id, thread_id);
+
+ALTER TABLE comments
+    ADD CONSTRAINT comments_parent_thread_fkey
+    FOREIGN KEY (parent_id, thread_id) REFERENCES comments(id, thread_id) ON DELETE CASCADE;
@@ -0,0 +1,28 @@
+-- メール認証トークンを別テーブルで管理する
+-- 再送信しても以前に送ったリンクは有効期限まで使えるようにし、使用時に消費済みにする
+-- トークンはハッシュ化して保存し、送信先のメールアドレスを記録してメールアドレス変更後の古いリンクを無効にする
+CREATE TABLE email_verification_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(255) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 再送信のクールダウン判定で、ユーザーごとの直近の送信履歴を引くためのインデックス
+CREATE INDEX idx_email_verification_tokens_user_id_created_at
+    ON email_verification_tokens(user_id, created_at DESC);
+
+-- 既存の未使用トークンを移行する（ハッシュはアプリケーション側と同じSHA-256のBase64）
+INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+SELECT id, email, encode(sha256(convert_to(verification_token, 'UTF8')), 'base64'), verification_token_expires_at
+FROM users
+WHERE verification_token IS NOT NULL
+  AND verification_token_expires_at IS NOT NULL
+  AND email_verified_at IS NULL;
+
+UPDATE users
+SET verification_token = NULL, verification_token_expires_at = NULL
+WHERE verification_token IS NOT NULL;
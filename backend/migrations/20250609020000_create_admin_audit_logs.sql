@@ -0,0 +1,17 @@
+-- 管理者操作の監査ログテーブルの追加
+-- 検索条件（実行者・操作・対象・期間）ごとにインデックスを用意し、いずれも新しい順に取得できるようにする
+-- 保持期間を過ぎた行はアーカイブ（圧縮NDJSON）に書き出した後で削除する
+CREATE TABLE admin_audit_logs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id UUID,
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_admin_audit_logs_created_at ON admin_audit_logs(created_at DESC, id DESC);
+CREATE INDEX idx_admin_audit_logs_actor_id ON admin_audit_logs(actor_id, created_at DESC);
+CREATE INDEX idx_admin_audit_logs_action ON admin_audit_logs(action, created_at DESC);
+CREATE INDEX idx_admin_audit_logs_target ON admin_audit_logs(target_type, target_id, created_at DESC);
@@ -0,0 +1,10 @@
+-- ユーザーごとの設定（一覧の並び順の既定値など）
+-- 未設定の項目はNULLとし、サーバー側の既定値を使う
+CREATE TABLE user_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    default_sort VARCHAR(10) CHECK (default_sort IN ('new', 'top')),
+    default_window VARCHAR(10) CHECK (default_window IN ('day', 'week', 'month', 'year', 'all')),
+    default_comment_sort VARCHAR(10) CHECK (default_comment_sort IN ('old', 'new')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
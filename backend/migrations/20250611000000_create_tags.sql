@@ -0,0 +1,28 @@
+-- スレッドのタグと、名前変更・統合で使われなくなった旧スラッグの転送先
+CREATE TABLE tags (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    slug VARCHAR(64) NOT NULL UNIQUE,
+    name VARCHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE thread_tags (
+    thread_id UUID NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (thread_id, tag_id)
+);
+
+CREATE INDEX idx_thread_tags_tag_id ON thread_tags(tag_id);
+
+-- 旧スラッグは転送先のタグへ引き継がれ、チェーンにならないよう常に現在のタグを指す
+CREATE TABLE tag_slug_redirects (
+    old_slug VARCHAR(64) PRIMARY KEY,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    reason VARCHAR(10) NOT NULL CHECK (reason IN ('rename', 'merge')),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tag_slug_redirects_tag_id ON tag_slug_redirects(tag_id);
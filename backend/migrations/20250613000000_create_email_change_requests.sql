@@ -0,0 +1,22 @@
+-- メールアドレス変更の確認と取り消し
+-- 変更は新しいアドレスで確認されるまで pending_email に保持し、現在のアドレスはそのまま使う
+-- 旧アドレスには取り消し用のリンクを送り、確認前は変更の中止、確認後は旧アドレスへの復元に使う
+ALTER TABLE users ADD COLUMN pending_email VARCHAR(255);
+
+CREATE TABLE email_change_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    old_email VARCHAR(255) NOT NULL,
+    new_email VARCHAR(255) NOT NULL,
+    confirm_token_hash VARCHAR(255) NOT NULL UNIQUE,
+    confirm_expires_at TIMESTAMPTZ NOT NULL,
+    revert_token_hash VARCHAR(255) NOT NULL UNIQUE,
+    revert_expires_at TIMESTAMPTZ NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    reverted_at TIMESTAMPTZ,
+    -- 同じユーザーが新たに変更をリクエストした場合、確認前の古いリクエストは無効になる
+    superseded_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_change_requests_user_id ON email_change_requests(user_id, created_at DESC);
@@ -0,0 +1,25 @@
+-- ユーザーの権限（一般・モデレーター・管理者）の追加
+-- 既存の管理者（is_admin）は admin に移行する
+CREATE TYPE user_role AS ENUM ('user', 'moderator', 'admin');
+
+ALTER TABLE users ADD COLUMN role user_role NOT NULL DEFAULT 'user';
+
+UPDATE users SET role = 'admin' WHERE is_admin;
+
+ALTER TABLE users DROP COLUMN is_admin;
+
+-- モデレーターが他のユーザーの投稿を削除した記録
+-- 投稿は削除されるため、対象のIDと投稿者のみを残す
+CREATE TABLE moderation_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    moderator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id UUID NOT NULL,
+    target_author_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_moderation_log_created_at ON moderation_log(created_at DESC);
+CREATE INDEX idx_moderation_log_moderator_id ON moderation_log(moderator_id, created_at DESC);
@@ -0,0 +1,13 @@
+-- スレッド作成時にタイトル・本文の部分一致で自動的にタグを付けるルール
+CREATE TABLE tag_rules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    pattern VARCHAR(100) NOT NULL,
+    match_field VARCHAR(10) NOT NULL CHECK (match_field IN ('title', 'content')),
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tag_rules_tag_id ON tag_rules(tag_id);
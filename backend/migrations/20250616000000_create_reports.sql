@@ -0,0 +1,18 @@
+-- ユーザーによるスパム・迷惑行為の通報
+-- 対象はスレッドまたはコメントで、同じユーザーが同じ対象を通報できるのは1回のみ
+CREATE TABLE reports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type VARCHAR(10) NOT NULL CHECK (target_type IN ('thread', 'comment')),
+    target_id UUID NOT NULL,
+    reason VARCHAR(10) NOT NULL CHECK (reason IN ('spam', 'abuse', 'other')),
+    detail TEXT,
+    status VARCHAR(10) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved')),
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT reports_reporter_target_key UNIQUE (reporter_id, target_type, target_id)
+);
+
+CREATE INDEX idx_reports_status_created_at ON reports(status, created_at);
+CREATE INDEX idx_reports_target ON reports(target_type, target_id);
@@ -0,0 +1,17 @@
+-- 最終アクティビティ（スレッドの作成または最新のコメント）の日時
+-- 「最近の動きがある順」の並び替えでコメントを集計しないよう、コメントの作成・削除時に更新する
+ALTER TABLE threads ADD COLUMN last_activity_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE threads t
+SET last_activity_at = GREATEST(
+    t.created_at,
+    COALESCE((SELECT MAX(c.created_at) FROM comments c WHERE c.thread_id = t.id), t.created_at)
+);
+
+CREATE INDEX idx_threads_last_activity_at ON threads(last_activity_at DESC);
+
+-- ユーザー設定の既定の並び順にも指定できるようにする
+ALTER TABLE user_settings DROP CONSTRAINT user_settings_default_sort_check;
+ALTER TABLE user_settings
+    ADD CONSTRAINT user_settings_default_sort_check
+    CHECK (default_sort IN ('new', 'top', 'active'));
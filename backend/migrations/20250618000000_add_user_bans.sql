@@ -0,0 +1,6 @@
+-- 管理者によるユーザーの利用停止
+-- banned_at が設定されていれば利用停止中で、banned_until が NULL の場合は無期限
+ALTER TABLE users
+    ADD COLUMN banned_at TIMESTAMPTZ,
+    ADD COLUMN banned_until TIMESTAMPTZ,
+    ADD COLUMN ban_reason TEXT;
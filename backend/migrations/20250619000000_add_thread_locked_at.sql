@@ -0,0 +1,2 @@
+-- スレッドのロック（ロック中は新しいコメントの投稿・既存コメントの編集ができない）
+ALTER TABLE threads ADD COLUMN locked_at TIMESTAMPTZ;
@@ -0,0 +1,32 @@
+-- 保存した検索条件と、新しく一致したスレッドの通知
+-- バックグラウンドのジョブが last_run_at 以降に作成されたスレッドを検索し、実行した時刻まで last_run_at を進める
+CREATE TABLE saved_searches (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    query VARCHAR(100) NOT NULL,
+    notify BOOLEAN NOT NULL DEFAULT FALSE,
+    email BOOLEAN NOT NULL DEFAULT FALSE,
+    last_run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT saved_searches_user_query_key UNIQUE (user_id, query)
+);
+
+CREATE INDEX idx_saved_searches_notify ON saved_searches(last_run_at) WHERE notify;
+
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(30) NOT NULL CHECK (kind IN ('saved_search_match')),
+    thread_id UUID REFERENCES threads(id) ON DELETE CASCADE,
+    saved_search_id UUID REFERENCES saved_searches(id) ON DELETE CASCADE,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- 同じ検索条件で同じスレッドを二度通知しない
+    CONSTRAINT notifications_saved_search_thread_key UNIQUE (saved_search_id, thread_id)
+);
+
+CREATE INDEX idx_notifications_user_created_at ON notifications(user_id, created_at DESC);
+
+-- スレッドの全文検索（タイトルと本文）
+CREATE INDEX idx_threads_search ON threads
+    USING GIN (to_tsvector('simple', title || ' ' || COALESCE(content, '')));
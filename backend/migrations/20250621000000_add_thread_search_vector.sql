@@ -0,0 +1,28 @@
+-- スレッドの全文検索用の列
+-- 既存の行を書き換えるとテーブルをロックするため、列は NULL のまま追加し、
+-- 既存のスレッドは `reindex-search` コマンドでバッチごとに埋める。
+-- 新しく作成・編集されたスレッドはトリガーで設定するため、バックフィル中でもすぐに検索できる。
+-- インデックスはロックを避けるため、同じコマンドが migrations/concurrent/ の SQL で作成する。
+ALTER TABLE threads ADD COLUMN search_vector tsvector;
+
+CREATE OR REPLACE FUNCTION threads_search_vector_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector = to_tsvector('simple', NEW.title || ' ' || COALESCE(NEW.content, ''));
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER threads_search_vector_update BEFORE INSERT OR UPDATE OF title, content ON threads
+    FOR EACH ROW EXECUTE FUNCTION threads_search_vector_update();
+
+-- バックフィルで search_vector だけを更新した場合は updated_at を変えない
+DROP TRIGGER update_threads_updated_at ON threads;
+CREATE TRIGGER update_threads_updated_at BEFORE UPDATE ON threads
+    FOR EACH ROW
+    WHEN (
+        OLD.search_vector IS NOT DISTINCT FROM NEW.search_vector
+        OR OLD.title IS DISTINCT FROM NEW.title
+        OR OLD.content IS DISTINCT FROM NEW.content
+    )
+    EXECUTE FUNCTION update_updated_at_column();
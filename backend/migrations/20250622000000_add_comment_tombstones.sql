@@ -0,0 +1,10 @@
+-- コメントのトムストーン（返信を残したまま本文を隠す）
+-- deleted_at: 投稿者が返信のあるコメントを削除した日時（本文は空にする）
+-- removed_by_moderator: モデレーターが理由を付けて非表示にしたコメント（本文は確認用に残す）
+ALTER TABLE comments
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN removed_by_moderator BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN removal_reason VARCHAR(20)
+        CHECK (removal_reason IN ('spam', 'abuse', 'off_topic', 'personal_info', 'other')),
+    ADD CONSTRAINT comments_removal_reason_required_check
+        CHECK (removed_by_moderator = (removal_reason IS NOT NULL));
@@ -0,0 +1,5 @@
+-- スレッドのコメント数の多いユーザーの集計用
+-- 削除・非表示にされたコメントは数えないため、部分インデックスにする
+CREATE INDEX idx_comments_thread_contributors
+    ON comments (thread_id, user_id, created_at)
+    WHERE deleted_at IS NULL AND NOT removed_by_moderator;
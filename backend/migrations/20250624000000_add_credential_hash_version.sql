@@ -0,0 +1,15 @@
+-- パスワードハッシュの形式のバージョン
+-- 1: バージョン管理を始める前の形式（ソルトを salt 列にも保存している。Argon2 のパラメーターが現在と異なる場合がある）
+-- 2: 現在の形式（既定のパラメーターの Argon2id。ソルトはハッシュの文字列にのみ含まれ、salt 列は使わない）
+-- 既存の行は 1 とし、以降に保存するハッシュは 2 とする（定数の既定値のため、テーブルを書き換えずに追加できる）
+ALTER TABLE user_credentials
+    ADD COLUMN hash_version SMALLINT NOT NULL DEFAULT 1,
+    -- 次のログイン時にハッシュを作り直す（`minwada-api migrate-credentials` が設定する）
+    ADD COLUMN needs_rehash BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE user_credentials
+    ALTER COLUMN hash_version SET DEFAULT 2,
+    ALTER COLUMN salt DROP NOT NULL;
+
+-- 旧形式のまま残っているアカウントを数える・移行するため
+CREATE INDEX idx_user_credentials_legacy_hash ON user_credentials (user_id) WHERE hash_version < 2;
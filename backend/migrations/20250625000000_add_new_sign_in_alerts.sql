@@ -0,0 +1,24 @@
+-- 初めての端末からのログインの通知と、厳格モードでの端末の確認
+-- 端末はリフレッシュトークンに保存した User-Agent と IP アドレスの組み合わせで判別する
+
+-- アプリ内の通知にログインした端末の情報を残す
+ALTER TABLE notifications DROP CONSTRAINT notifications_kind_check;
+ALTER TABLE notifications
+    ADD CONSTRAINT notifications_kind_check CHECK (kind IN ('saved_search_match', 'new_sign_in')),
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN ip_address VARCHAR(45);
+
+-- NEW_DEVICE_CONFIRMATION が有効な場合に、メールで端末を確認するためのトークン
+-- マジックリンクと同様にハッシュ化して保存し、一度だけ使用できる
+CREATE TABLE device_confirmation_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(255) NOT NULL UNIQUE,
+    user_agent TEXT,
+    ip_address VARCHAR(45),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_device_confirmation_tokens_user_id ON device_confirmation_tokens(user_id);
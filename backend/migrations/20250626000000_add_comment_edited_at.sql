@@ -0,0 +1,2 @@
+-- コメントの編集日時（投稿者が本文を編集したときのみ設定する。updated_at は他の変更でも更新される）
+ALTER TABLE comments ADD COLUMN edited_at TIMESTAMPTZ;
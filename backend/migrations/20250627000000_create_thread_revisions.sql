@@ -0,0 +1,14 @@
+-- スレッドの編集履歴（更新される前のタイトル・本文）
+-- edited_by: この版を置き換えた更新を行ったユーザー
+-- created_at: 更新された日時（スレッドごとに新しい方から 20 件まで残す）
+CREATE TABLE thread_revisions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    thread_id UUID NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    title VARCHAR(300) NOT NULL,
+    content TEXT,
+    edited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX idx_thread_revisions_thread_id_created_at
+    ON thread_revisions (thread_id, created_at);
@@ -0,0 +1,11 @@
+-- あとで読むために保存したスレッド（ブックマーク）
+-- スレッド・ユーザーの削除時は ON DELETE CASCADE によりブックマークも削除される
+CREATE TABLE bookmarks (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    thread_id UUID NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, thread_id)
+);
+
+-- ユーザーごとの一覧（新しく保存した順）
+CREATE INDEX idx_bookmarks_user_created_at ON bookmarks(user_id, created_at DESC);
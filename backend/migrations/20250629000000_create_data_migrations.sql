@@ -0,0 +1,18 @@
+-- データの移行（`data-migrate` コマンド）の進捗
+-- version: 移行ごとの番号（Rust のコードに登録した番号で、一度使った番号は変えない）
+-- cursor: 最後に処理した行のキー（中断した場合は次回この続きから処理する）
+-- processed: これまでに処理した行数
+-- completed_at: 処理する行が無くなった日時（NULL の間は未完了）
+CREATE TABLE data_migrations (
+    version BIGINT PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    cursor TEXT,
+    processed BIGINT NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- スレッドのURL用のスラッグ（作成時のタイトルから生成し、編集しても変えない）
+-- 列を追加する前からあるスレッドは `data-migrate` で埋めるまで NULL のまま
+ALTER TABLE threads ADD COLUMN slug VARCHAR(64);
@@ -0,0 +1,12 @@
+-- ユーザーのフォロー関係（follower_id が followee_id をフォローしている）
+-- どちらかのユーザーの削除時は ON DELETE CASCADE によりフォローも削除される
+CREATE TABLE follows (
+    follower_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (follower_id, followee_id),
+    CHECK (follower_id <> followee_id)
+);
+
+-- フォロワーの一覧（新しくフォローされた順）
+CREATE INDEX idx_follows_followee_created_at ON follows(followee_id, created_at DESC);
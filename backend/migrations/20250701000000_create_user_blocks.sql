@@ -0,0 +1,9 @@
+-- ユーザーのブロック（blocker_id のユーザーには blocked_id のユーザーの投稿を表示・通知しない）
+-- どちらかのユーザーの削除時は ON DELETE CASCADE によりブロックも削除される
+CREATE TABLE user_blocks (
+    blocker_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (blocker_id, blocked_id),
+    CHECK (blocker_id <> blocked_id)
+);
@@ -0,0 +1,18 @@
+-- 通報への対応結果と、通報したユーザーへの結果の通知
+-- outcome: actioned（対応した）/ no_violation（違反なし）
+ALTER TABLE reports
+    ADD COLUMN outcome VARCHAR(20) CHECK (outcome IN ('actioned', 'no_violation'));
+
+-- 通知には対象と結果のみを残し、対応したモデレーターは含めない
+ALTER TABLE notifications DROP CONSTRAINT notifications_kind_check;
+ALTER TABLE notifications
+    ADD CONSTRAINT notifications_kind_check
+        CHECK (kind IN ('saved_search_match', 'new_sign_in', 'report_resolved')),
+    ADD COLUMN report_target_type VARCHAR(10),
+    ADD COLUMN report_target_id UUID,
+    ADD COLUMN report_outcome VARCHAR(20);
+
+-- 同じ対象の対応結果は1人に1回だけ通知する
+CREATE UNIQUE INDEX idx_notifications_report_resolved
+    ON notifications(user_id, report_target_type, report_target_id)
+    WHERE kind = 'report_resolved';
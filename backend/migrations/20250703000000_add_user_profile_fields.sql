@@ -0,0 +1,5 @@
+-- プロフィールの自己紹介とウェブサイト
+-- bio は Markdown のまま保存し、表示用の HTML はレスポンスを作るときに作成する
+ALTER TABLE users
+    ADD COLUMN bio TEXT,
+    ADD COLUMN website_url TEXT;
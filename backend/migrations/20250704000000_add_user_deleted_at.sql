@@ -0,0 +1,4 @@
+-- 退会したユーザーの匿名化
+-- 退会時はユーザーの行を残したまま個人情報を消去し、deleted_at を設定する
+-- スレッド・コメントは他のユーザーの議論を残すため削除せず、投稿者を [deleted] として表示する
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;
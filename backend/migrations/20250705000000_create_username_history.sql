@@ -0,0 +1,12 @@
+-- ユーザー名の変更履歴（変更前のユーザー名を記録する）
+-- 古いユーザー名でのプロフィールの取得を現在のユーザーに案内し、
+-- 変更から30日間は他のユーザーが古いユーザー名を使えないようにする
+CREATE TABLE username_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    username VARCHAR(50) NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_username_history_username ON username_history(username, changed_at DESC);
+CREATE INDEX idx_username_history_user_id ON username_history(user_id, changed_at DESC);
@@ -0,0 +1,33 @@
+-- ユーザー名・メールアドレスの一意性を大文字・小文字を区別せずに判定する
+-- 表示用の大文字・小文字は入力されたまま保存し、比較のみ LOWER() で行う
+
+-- 既存データに大文字・小文字違いの重複がある場合は、どのアカウントを残すかを自動では決められないため
+-- 重複している値を一覧にしてマイグレーションを中止する（運用者が統合・変更してから再実行する）
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT string_agg(value, ', ' ORDER BY value) INTO duplicates
+    FROM (
+        SELECT 'username ' || LOWER(username) AS value
+        FROM users
+        GROUP BY LOWER(username)
+        HAVING COUNT(*) > 1
+        UNION ALL
+        SELECT 'email ' || LOWER(email)
+        FROM users
+        GROUP BY LOWER(email)
+        HAVING COUNT(*) > 1
+    ) d;
+
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'Resolve case-insensitive duplicate users before migrating: %', duplicates;
+    END IF;
+END $$;
+
+CREATE UNIQUE INDEX users_username_lower_key ON users (LOWER(username));
+CREATE UNIQUE INDEX users_email_lower_key ON users (LOWER(email));
+
+-- 変更前のユーザー名も大文字・小文字を区別せずに検索する
+DROP INDEX idx_username_history_username;
+CREATE INDEX idx_username_history_username ON username_history (LOWER(username), changed_at DESC);
@@ -0,0 +1,2 @@
+-- 管理者がパスワードの再設定を必須にしたユーザー（再設定が完了するとfalseに戻す）
+ALTER TABLE users ADD COLUMN must_reset_password BOOLEAN NOT NULL DEFAULT false;
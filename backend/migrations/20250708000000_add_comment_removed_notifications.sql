@@ -0,0 +1,9 @@
+-- モデレーターがコメントを非表示にしたことの投稿者への通知
+-- 理由の分類と、通知した時点の投稿ルールのページのURLを残す
+ALTER TABLE notifications DROP CONSTRAINT notifications_kind_check;
+ALTER TABLE notifications
+    ADD CONSTRAINT notifications_kind_check
+        CHECK (kind IN ('saved_search_match', 'new_sign_in', 'report_resolved', 'comment_removed')),
+    ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    ADD COLUMN removal_reason VARCHAR(20),
+    ADD COLUMN rules_url TEXT;
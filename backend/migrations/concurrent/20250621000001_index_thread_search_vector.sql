@@ -0,0 +1,4 @@
+-- CREATE INDEX CONCURRENTLY はトランザクション内で実行できないため、sqlx のマイグレーションには含めず、
+-- `reindex-search` コマンドがバックフィルの後にトランザクションの外で1文ずつ実行する。
+-- 途中で失敗した場合は無効なインデックスが残るため、コマンドは無効なインデックスを削除してから作り直す。
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_threads_search_vector ON threads USING GIN (search_vector)
@@ -0,0 +1,2 @@
+-- 検索は search_vector 列を使うため、式インデックスは不要になった
+DROP INDEX CONCURRENTLY IF EXISTS idx_threads_search
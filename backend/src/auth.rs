@@ -2,47 +2,215 @@
 
 pub mod jwt {
     use chrono::{Duration, Utc};
-    use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-
-    use crate::{error::AppError, models::auth::Claims};
-
-    pub fn create_jwt_token(
-        user_id: &str,
-        username: &str,
-        email: &str,
-        secret: &str,
-        expires_in_minutes: i64,
-    ) -> Result<String, AppError> {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::minutes(expires_in_minutes))
-            .expect("Valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user_id.to_string(),
-            username: username.to_string(),
-            email: email.to_string(),
-            exp: expiration,
-            iat: Utc::now().timestamp() as usize,
-            iss: "minwada".to_string(),
-        };
+    use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+
+    use crate::{
+        config::Config,
+        error::AppError,
+        models::{auth::Claims, UserRole},
+    };
+
+    /// アクセストークンの有効期間（分）
+    pub const ACCESS_TOKEN_EXPIRES_IN_MINUTES: i64 = 15;
+
+    /// 有効期限・発行時刻の検証で許容する時計のずれ（秒）
+    pub const LEEWAY_SECONDS: u64 = 30;
+
+    /// JWTの発行と検証
+    ///
+    /// 署名鍵・発行者・対象者を保持し、起動時に検証済みの `Config` から一度だけ作成し、`AppState` に保持して共有します。
+    pub struct JwtService {
+        encoding_key: EncodingKey,
+        decoding_key: DecodingKey,
+        issuer: String,
+        audience: String,
+        validation: Validation,
+    }
+
+    impl JwtService {
+        pub fn new(secret: &str, issuer: &str, audience: &str) -> Self {
+            let mut validation = Validation::new(Algorithm::HS256);
+            validation.leeway = LEEWAY_SECONDS;
+            validation.set_issuer(&[issuer]);
+            validation.set_audience(&[audience]);
+            validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+
+            Self {
+                encoding_key: EncodingKey::from_secret(secret.as_ref()),
+                decoding_key: DecodingKey::from_secret(secret.as_ref()),
+                issuer: issuer.to_string(),
+                audience: audience.to_string(),
+                validation,
+            }
+        }
+
+        pub fn from_config(config: &Config) -> Self {
+            Self::new(
+                config.jwt_secret.expose(),
+                &config.jwt_issuer,
+                &config.jwt_audience,
+            )
+        }
 
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret.as_ref()),
-        )
-        .map_err(AppError::Jwt)
+        /// このデプロイ向けのクレームを作成する
+        pub fn claims(
+            &self,
+            user_id: &str,
+            username: &str,
+            email: &str,
+            role: UserRole,
+            session_id: &str,
+            expires_in_minutes: i64,
+        ) -> Claims {
+            let now = Utc::now();
+            let expiration = now
+                .checked_add_signed(Duration::minutes(expires_in_minutes))
+                .expect("Valid timestamp")
+                .timestamp() as usize;
+
+            Claims {
+                sub: user_id.to_string(),
+                username: username.to_string(),
+                email: email.to_string(),
+                exp: expiration,
+                iat: now.timestamp() as usize,
+                iss: self.issuer.clone(),
+                aud: self.audience.clone(),
+                sid: Some(session_id.to_string()),
+                role,
+            }
+        }
+
+        pub fn encode(&self, claims: &Claims) -> Result<String, AppError> {
+            encode(&Header::default(), claims, &self.encoding_key).map_err(AppError::Jwt)
+        }
+
+        /// 署名・有効期限・発行者・対象者を検証する
+        pub fn decode(&self, token: &str) -> Result<Claims, AppError> {
+            decode::<Claims>(token, &self.decoding_key, &self.validation)
+                .map(|data| data.claims)
+                .map_err(AppError::Jwt)
+        }
     }
 
-    pub fn verify_jwt_token(token: &str, secret: &str) -> Result<Claims, AppError> {
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret.as_ref()),
-            &Validation::default(),
-        )
-        .map(|data| data.claims)
-        .map_err(AppError::Jwt)
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const SECRET: &str = "test-secret";
+
+        fn service() -> JwtService {
+            JwtService::new(SECRET, "minwada", "minwada-api")
+        }
+
+        fn claims_expiring_in(service: &JwtService, seconds: i64) -> Claims {
+            let mut claims = service.claims(
+                "user-id",
+                "user",
+                "user@example.com",
+                UserRole::User,
+                "sid",
+                15,
+            );
+            claims.exp = (Utc::now().timestamp() + seconds) as usize;
+            claims
+        }
+
+        #[test]
+        fn test_発行したトークンを検証できる() {
+            // 発行したトークンを検証できることを確認
+            let service = service();
+            let token = service
+                .encode(&service.claims(
+                    "user-id",
+                    "user",
+                    "user@example.com",
+                    UserRole::User,
+                    "sid",
+                    15,
+                ))
+                .unwrap();
+
+            let claims = service.decode(&token).unwrap();
+            assert_eq!(claims.sub, "user-id");
+            assert_eq!(claims.iss, "minwada");
+            assert_eq!(claims.aud, "minwada-api");
+            assert_eq!(claims.sid.as_deref(), Some("sid"));
+        }
+
+        #[test]
+        fn test_期限切れでも許容範囲内なら受け付ける() {
+            // 期限切れでも許容範囲内のトークンは受け付けることを確認
+            let service = service();
+            let token = service.encode(&claims_expiring_in(&service, -10)).unwrap();
+
+            assert!(service.decode(&token).is_ok());
+        }
+
+        #[test]
+        fn test_許容範囲を超えて期限切れのトークンは拒否される() {
+            // 許容範囲を超えて期限切れのトークンは拒否されることを確認
+            let service = service();
+            let token = service
+                .encode(&claims_expiring_in(&service, -(LEEWAY_SECONDS as i64) - 30))
+                .unwrap();
+
+            assert!(matches!(service.decode(&token), Err(AppError::Jwt(_))));
+        }
+
+        #[test]
+        fn test_発行者が異なるトークンは拒否される() {
+            // 発行者が異なるトークンは拒否されることを確認
+            let other = JwtService::new(SECRET, "other-deployment", "minwada-api");
+            let token = other
+                .encode(&other.claims(
+                    "user-id",
+                    "user",
+                    "user@example.com",
+                    UserRole::User,
+                    "sid",
+                    15,
+                ))
+                .unwrap();
+
+            assert!(matches!(service().decode(&token), Err(AppError::Jwt(_))));
+        }
+
+        #[test]
+        fn test_対象者が異なるトークンは拒否される() {
+            // 対象者が異なるトークンは拒否されることを確認
+            let other = JwtService::new(SECRET, "minwada", "other-api");
+            let token = other
+                .encode(&other.claims(
+                    "user-id",
+                    "user",
+                    "user@example.com",
+                    UserRole::User,
+                    "sid",
+                    15,
+                ))
+                .unwrap();
+
+            assert!(matches!(service().decode(&token), Err(AppError::Jwt(_))));
+        }
+
+        #[test]
+        fn test_署名鍵が異なるトークンは拒否される() {
+            // 署名鍵が異なるトークンは拒否されることを確認
+            let other = JwtService::new("another-secret", "minwada", "minwada-api");
+            let token = other
+                .encode(&other.claims(
+                    "user-id",
+                    "user",
+                    "user@example.com",
+                    UserRole::User,
+                    "sid",
+                    15,
+                ))
+                .unwrap();
+
+            assert!(matches!(service().decode(&token), Err(AppError::Jwt(_))));
+        }
     }
 }
 
@@ -51,18 +219,24 @@ pub mod password {
         password_hash::{
             rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
         },
-        Argon2,
+        Algorithm, Argon2, Params, Version,
     };
 
     use crate::error::AppError;
 
-    pub fn hash_password(password: &str) -> Result<(String, String), AppError> {
+    /// 現在のハッシュの形式（user_credentials.hash_version）
+    ///
+    /// 1 はバージョン管理を始める前の形式で、次のログイン時に作り直します。
+    pub const HASH_VERSION: i16 = 2;
+
+    /// パスワードをハッシュ化する（ソルトはハッシュの文字列に含まれる）
+    pub fn hash_password(password: &str) -> Result<String, AppError> {
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
 
         let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
 
-        Ok((password_hash.to_string(), salt.to_string()))
+        Ok(password_hash.to_string())
     }
 
     pub fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
@@ -72,4 +246,51 @@ pub mod password {
             .verify_password(password.as_bytes(), &parsed_hash)
             .is_ok())
     }
+
+    /// 現在と同じアルゴリズム・パラメーターのハッシュか（作り直さずに使い続けられるか）
+    pub fn is_current_hash(hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        let Ok(params) = Params::try_from(&parsed) else {
+            return false;
+        };
+        let current = Params::default();
+
+        parsed.algorithm == Algorithm::Argon2id.ident()
+            && parsed.version == Some(Version::V0x13.into())
+            && params.m_cost() == current.m_cost()
+            && params.t_cost() == current.t_cost()
+            && params.p_cost() == current.p_cost()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_既定のパラメーターのargon2idだけを現在の形式とみなす() {
+            // 既定のパラメーターのargon2idだけを現在の形式とみなすことを確認
+            assert!(is_current_hash(&hash_password("password123").unwrap()));
+
+            // 反復回数が異なる
+            let params =
+                Params::new(Params::DEFAULT_M_COST, 1, Params::DEFAULT_P_COST, None).unwrap();
+            let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password(b"password123", &SaltString::generate(&mut OsRng))
+                .unwrap()
+                .to_string();
+            assert!(!is_current_hash(&weak));
+            assert!(verify_password("password123", &weak).unwrap());
+
+            // アルゴリズムが異なる
+            let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+                .hash_password(b"password123", &SaltString::generate(&mut OsRng))
+                .unwrap()
+                .to_string();
+            assert!(!is_current_hash(&argon2i));
+
+            assert!(!is_current_hash("password_hash_value"));
+        }
+    }
 }
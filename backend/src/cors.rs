@@ -0,0 +1,277 @@
+// CORSの設定
+//
+// 許可するオリジンは CORS_ORIGINS にカンマ区切りで指定します。
+// プレビュー環境のように毎回サブドメインが変わるオリジンは `https://*.vercel.app` のように指定できます。
+use axum::http::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    HeaderName, HeaderValue, Method,
+};
+use serde::{Serialize, Serializer};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::middleware::REQUEST_ID_HEADER;
+
+/// 許可するオリジン1件分
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OriginPattern {
+    /// `https://example.com` のように完全一致で照合する
+    Exact(String),
+    /// `https://*.vercel.app` の `*` に1つのラベル（`.` を含まない）が入るものを許可する
+    Subdomain { scheme: String, suffix: String },
+}
+
+/// 許可するオリジンの一覧（CORS_ORIGINS）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsOrigins {
+    patterns: Vec<OriginPattern>,
+}
+
+impl CorsOrigins {
+    /// カンマ区切りのオリジンを読み込む
+    ///
+    /// オリジンは `http(s)://ホスト[:ポート]` の形式で、パスや末尾の `/` は含められません。
+    /// ワイルドカードはホストの先頭のラベル（`https://*.example.com`）にのみ使えます。
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let patterns = value
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(parse_origin)
+            .collect::<Result<Vec<_>, _>>()?;
+        if patterns.is_empty() {
+            return Err("at least one origin is required".to_string());
+        }
+
+        Ok(Self { patterns })
+    }
+
+    pub fn allows(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        self.patterns.iter().any(|pattern| match pattern {
+            OriginPattern::Exact(allowed) => origin == *allowed,
+            OriginPattern::Subdomain { scheme, suffix } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                .is_some_and(is_label),
+        })
+    }
+}
+
+// 設定の出力用に、読み込んだときと同じ形式のオリジンの一覧にする
+impl Serialize for CorsOrigins {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.patterns.iter().map(|pattern| match pattern {
+            OriginPattern::Exact(origin) => origin.clone(),
+            OriginPattern::Subdomain { scheme, suffix } => format!("{}*{}", scheme, suffix),
+        }))
+    }
+}
+
+fn parse_origin(origin: &str) -> Result<OriginPattern, String> {
+    let invalid = |reason: &str| format!("invalid origin `{}`: {}", origin, reason);
+    let origin = origin.to_ascii_lowercase();
+
+    let (scheme, host) = origin
+        .split_once("://")
+        .ok_or_else(|| invalid("expected http(s)://host[:port]"))?;
+    if scheme != "http" && scheme != "https" {
+        return Err(invalid("scheme must be http or https"));
+    }
+    if host.is_empty() || host.contains(['/', '?', '#', '@']) {
+        return Err(invalid("must not contain a path, query or credentials"));
+    }
+
+    match host.strip_prefix("*.") {
+        Some(suffix) => {
+            if suffix.contains('*') || !suffix.contains('.') {
+                return Err(invalid(
+                    "wildcard is only allowed as the first label of a domain like https://*.example.com",
+                ));
+            }
+            Ok(OriginPattern::Subdomain {
+                scheme: format!("{}://", scheme),
+                suffix: format!(".{}", suffix),
+            })
+        }
+        None if host.contains('*') => Err(invalid(
+            "wildcard is only allowed as the first label of a domain like https://*.example.com",
+        )),
+        None => {
+            // ヘッダーの値として送れない文字を含む場合は起動時にエラーにする
+            HeaderValue::from_str(&origin).map_err(|_| invalid("contains invalid characters"))?;
+            Ok(OriginPattern::Exact(origin))
+        }
+    }
+}
+
+/// ワイルドカードに当てはめるサブドメインのラベル
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+}
+
+/// 設定したオリジンからの認証情報付きのリクエストを許可するCORSのレイヤー
+pub fn layer(origins: CorsOrigins) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origin.to_str().is_ok_and(|origin| origins.allows(origin))
+        }))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+        .allow_credentials(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::patch,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn origins() -> CorsOrigins {
+        CorsOrigins::parse("https://minwada.example.com, https://*.vercel.app").unwrap()
+    }
+
+    #[test]
+    fn test_完全一致のオリジンを許可する() {
+        // 完全一致のオリジンを許可することを確認
+        let origins = origins();
+
+        assert!(origins.allows("https://minwada.example.com"));
+        assert!(origins.allows("https://MINWADA.example.com"));
+        assert!(!origins.allows("http://minwada.example.com"));
+        assert!(!origins.allows("https://minwada.example.com:8443"));
+        assert!(!origins.allows("https://evil.example.com"));
+    }
+
+    #[test]
+    fn test_ワイルドカードは1つのサブドメインにのみ一致する() {
+        // ワイルドカードは1つのサブドメインにのみ一致することを確認
+        let origins = origins();
+
+        assert!(origins.allows("https://minwada-git-feature-x.vercel.app"));
+        assert!(!origins.allows("https://vercel.app"));
+        assert!(!origins.allows("https://.vercel.app"));
+        assert!(!origins.allows("https://a.b.vercel.app"));
+        assert!(!origins.allows("http://preview.vercel.app"));
+        assert!(!origins.allows("https://preview.vercel.app.evil.com"));
+        assert!(!origins.allows("https://evilvercel.app"));
+    }
+
+    #[test]
+    fn test_不正なオリジンは起動時にエラーになる() {
+        // 不正なオリジンは起動時にエラーになることを確認
+        for value in [
+            "",
+            " , ",
+            "localhost:3000",
+            "ftp://example.com",
+            "https://example.com/",
+            "https://example.com/app",
+            "https://*",
+            "https://*.com.*",
+            "https://app.*.example.com",
+            "https://*example.com",
+        ] {
+            assert!(CorsOrigins::parse(value).is_err(), "{:?}", value);
+        }
+        assert!(CorsOrigins::parse("http://localhost:3000").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_プレビュー環境からのpatchのプリフライトを許可する() {
+        // プレビュー環境からのPATCHのプリフライトを許可することを確認
+        let app = Router::new()
+            .route("/api/resource", patch(|| async { "ok" }))
+            .layer(layer(origins()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/api/resource")
+                    .header(header::ORIGIN, "https://pr-12.vercel.app")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://pr-12.vercel.app"
+        );
+        assert!(headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .contains("PATCH"));
+    }
+
+    #[tokio::test]
+    async fn test_許可していないオリジンにはcorsヘッダーを返さない() {
+        // 許可していないオリジンにはCORSヘッダーを返さないことを確認
+        let app = Router::new()
+            .route("/api/resource", patch(|| async { "ok" }))
+            .layer(layer(origins()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::PATCH)
+                    .uri("/api/resource")
+                    .header(header::ORIGIN, "https://evil.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_リクエストidのヘッダーを公開する() {
+        // リクエストIDのヘッダーを公開することを確認
+        let app = Router::new()
+            .route("/api/resource", patch(|| async { "ok" }))
+            .layer(layer(origins()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::PATCH)
+                    .uri("/api/resource")
+                    .header(header::ORIGIN, "https://minwada.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS],
+            REQUEST_ID_HEADER
+        );
+    }
+}
@@ -0,0 +1,221 @@
+// データベース接続プールの作成
+//
+// eager モードでは docker compose などでデータベースの起動が遅れても落ちないよう、
+// DB_CONNECT_RETRY 秒まで指数バックオフで接続を再試行する。
+// lazy モードでは起動時に接続せず、最初のクエリで接続する。
+use std::{error::Error, future::Future, str::FromStr, time::Duration};
+
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use tokio::time::Instant;
+
+use crate::config::{Config, DbConnectMode};
+
+/// 最初の再試行までの待ち時間（以降は試行ごとに2倍）
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// 再試行の待ち時間の上限
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// 1回の接続試行を待つ時間の上限
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 設定に従ってプールを作成する
+pub async fn connect(config: &Config) -> Result<PgPool, Box<dyn Error>> {
+    let options = PgConnectOptions::from_str(config.database_url.expose())?;
+    let pool_options = PgPoolOptions::new()
+        .max_connections(config.db_max_connections)
+        .min_connections(config.db_min_connections);
+
+    match config.db_connect_mode {
+        DbConnectMode::Lazy => Ok(pool_options.connect_lazy_with(options)),
+        DbConnectMode::Eager => {
+            let max_wait = Duration::from_secs(config.db_connect_retry_seconds);
+            connect_with_retry(pool_options, options, max_wait).await
+        }
+    }
+}
+
+/// 接続できるまで max_wait まで再試行する
+pub async fn connect_with_retry(
+    pool_options: PgPoolOptions,
+    options: PgConnectOptions,
+    max_wait: Duration,
+) -> Result<PgPool, Box<dyn Error>> {
+    let target = format!("{}:{}", options.get_host(), options.get_port());
+
+    retry_with_backoff(max_wait, |attempt| {
+        let pool_options = pool_options.clone().acquire_timeout(ATTEMPT_TIMEOUT);
+        let options = options.clone();
+        let target = target.as_str();
+        async move {
+            let result = pool_options.connect_with(options).await;
+            if let Err(err) = &result {
+                tracing::warn!(attempt, "Database at {} is not reachable yet: {}", target, err);
+            }
+            result
+        }
+    })
+    .await
+    .map_err(|(err, attempts)| {
+        format!(
+            "Could not connect to the database at {} after {} attempts within {}s (DB_CONNECT_RETRY): {}",
+            target,
+            attempts,
+            max_wait.as_secs(),
+            err
+        )
+        .into()
+    })
+}
+
+/// 成功するか max_wait を過ぎるまで、待ち時間を倍にしながら attempt を繰り返す
+///
+/// 失敗した場合は最後のエラーと試行回数を返します。
+pub async fn retry_with_backoff<T, E, F, Fut>(
+    max_wait: Duration,
+    mut attempt: F,
+) -> Result<T, (E, u32)>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let deadline = Instant::now() + max_wait;
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let err = match attempt(attempts).await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err((err, attempts));
+        }
+
+        tokio::time::sleep(delay.min(deadline - now)).await;
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[tokio::test]
+    async fn test_成功するまで待ち時間を倍にしながら再試行する() {
+        // 成功するまで待ち時間を倍にしながら再試行することを確認
+        let start = Instant::now();
+        let mut attempted_at = Vec::new();
+
+        let result = retry_with_backoff(Duration::from_secs(30), |attempt| {
+            attempted_at.push(start.elapsed());
+            async move {
+                if attempt < 4 {
+                    Err("unreachable")
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result, Ok(4));
+        // 250ms, 500ms, 1s と待ち時間が倍になる
+        let gaps: Vec<Duration> = attempted_at.windows(2).map(|w| w[1] - w[0]).collect();
+        assert_eq!(gaps.len(), 3);
+        for (gap, expected) in gaps.iter().zip([250, 500, 1000]) {
+            assert!(*gap >= Duration::from_millis(expected), "gap {:?}", gap);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_上限時間を過ぎると最後のエラーを返す() {
+        // 上限時間を過ぎると最後のエラーを返すことを確認
+        let start = Instant::now();
+
+        let result: Result<(), _> =
+            retry_with_backoff(Duration::from_secs(1), |attempt| async move {
+                Err(format!("attempt {}", attempt))
+            })
+            .await;
+
+        let (err, attempts) = result.unwrap_err();
+        assert_eq!(err, format!("attempt {}", attempts));
+        // 250ms, 500ms, 250ms（上限までの残り）待ってから最後の試行
+        assert_eq!(attempts, 4);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    /// 空いているポートを確保して番号だけを返す（この時点では接続できない）
+    async fn unused_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// port で待ち受け、受け付けた接続をテスト用データベースへ中継する
+    async fn spawn_proxy(port: u16, upstream: String) {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                let upstream = upstream.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut outbound) = TcpStream::connect(upstream).await {
+                        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                    }
+                });
+            }
+        });
+    }
+
+    #[sqlx::test]
+    async fn test_起動後に到達可能になったデータベースに接続できる(
+        pool: PgPool,
+    ) {
+        // 最初は接続できないアドレスで再試行を始め、途中でデータベースへの中継を開始しても接続できることを確認
+        let upstream = pool.connect_options();
+        let upstream_addr = format!("{}:{}", upstream.get_host(), upstream.get_port());
+        let port = unused_port().await;
+        let options = (*upstream).clone().host("127.0.0.1").port(port);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(600)).await;
+            spawn_proxy(port, upstream_addr).await;
+        });
+
+        let connected = connect_with_retry(
+            PgPoolOptions::new().max_connections(1),
+            options,
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("should connect once the proxy is listening");
+
+        let one = sqlx::query_scalar::<_, i32>("SELECT 1")
+            .fetch_one(&connected)
+            .await
+            .unwrap();
+        assert_eq!(one, 1);
+    }
+
+    #[tokio::test]
+    async fn test_接続できないまま上限時間を過ぎると分かりやすいエラーになる() {
+        // 接続できないまま上限時間を過ぎると分かりやすいエラーになることを確認
+        let port = unused_port().await;
+        let options = PgConnectOptions::new().host("127.0.0.1").port(port);
+
+        let err = connect_with_retry(PgPoolOptions::new(), options, Duration::from_millis(500))
+            .await
+            .unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains(&format!("127.0.0.1:{}", port)));
+        assert!(message.contains("DB_CONNECT_RETRY"));
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use std::error::Error;
+
+use super::{dry_run::DryRunSender, EmailMessage, EmailSender};
+
+/// 送信を許可する宛先の一覧（EMAIL_ALLOWLIST）
+///
+/// `@` を含まないパターンはドメイン（`example.com`）として、
+/// 含むパターンは `*` をワイルドカードにしたアドレス（`qa+*@example.com`）として照合します。
+/// 大文字・小文字は区別しません。
+#[derive(Debug, Clone, Default)]
+pub struct EmailAllowlist {
+    patterns: Vec<String>,
+}
+
+impl EmailAllowlist {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.iter().map(|p| p.to_lowercase()).collect(),
+        }
+    }
+
+    pub fn allows(&self, address: &str) -> bool {
+        let address = address.trim().to_lowercase();
+        let Some((_, domain)) = address.rsplit_once('@') else {
+            return false;
+        };
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains('@') {
+                wildcard_match(pattern, &address)
+            } else {
+                domain == pattern.as_str()
+            }
+        })
+    }
+}
+
+/// `*` を任意の文字列として照合する
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // ワイルドカードを含まない場合は完全一致
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// 許可された宛先にのみ送信し、それ以外はログへ出力する送信者（EMAIL_MODE=allowlist）
+pub struct AllowlistSender {
+    allowlist: EmailAllowlist,
+    inner: Box<dyn EmailSender>,
+    dry_run: DryRunSender,
+}
+
+impl AllowlistSender {
+    pub fn new(allowlist: EmailAllowlist, inner: Box<dyn EmailSender>) -> Self {
+        Self {
+            allowlist,
+            inner,
+            dry_run: DryRunSender::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSender for AllowlistSender {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.allowlist.allows(&message.to) {
+            self.inner.send_email(message).await
+        } else {
+            tracing::info!(to = %message.to, "Recipient is not in EMAIL_ALLOWLIST");
+            self.dry_run.send_email(message).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(patterns: &[&str]) -> EmailAllowlist {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        EmailAllowlist::new(&patterns)
+    }
+
+    #[test]
+    fn test_ドメインのパターンはそのドメインの宛先のみ許可する() {
+        // ドメインのパターンはそのドメインの宛先のみ許可することを確認
+        let allowlist = allowlist(&["example.com"]);
+
+        assert!(allowlist.allows("user@example.com"));
+        assert!(allowlist.allows("User@Example.COM"));
+        // サブドメインや似たドメインは許可しない
+        assert!(!allowlist.allows("user@mail.example.com"));
+        assert!(!allowlist.allows("user@notexample.com"));
+        assert!(!allowlist.allows("example.com"));
+    }
+
+    #[test]
+    fn test_アドレスのパターンはワイルドカードで照合する() {
+        // アドレスのパターンはワイルドカードで照合することを確認
+        let allowlist = allowlist(&["qa+*@minwada.dev", "owner@example.org"]);
+
+        assert!(allowlist.allows("qa+staging@minwada.dev"));
+        assert!(allowlist.allows("qa+@minwada.dev"));
+        assert!(allowlist.allows("owner@example.org"));
+        assert!(!allowlist.allows("dev@minwada.dev"));
+        assert!(!allowlist.allows("owner2@example.org"));
+        assert!(!allowlist.allows("qa+staging@minwada.dev.evil.com"));
+    }
+}
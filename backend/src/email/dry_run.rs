@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use std::error::Error;
+
+use super::{EmailMessage, EmailSender};
+
+/// 組み立てたメールを送信せずにログへ出力する送信者（EMAIL_MODE=dry_run）
+#[derive(Default)]
+pub struct DryRunSender;
+
+impl DryRunSender {
+    pub fn new() -> Self {
+        DryRunSender
+    }
+}
+
+#[async_trait]
+impl EmailSender for DryRunSender {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        tracing::info!(
+            to = %message.to,
+            subject = %message.subject,
+            html_body = %message.html_body,
+            text_body = %message.text_body.as_deref().unwrap_or_default(),
+            "Email not sent (dry run)"
+        );
+        Ok(())
+    }
+}
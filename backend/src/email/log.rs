@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use std::error::Error;
+
+use super::{EmailMessage, EmailSender};
+
+/// メールを送信せずにログへ出力するだけの送信者（開発用）
+#[derive(Default)]
+pub struct LogSender;
+
+impl LogSender {
+    pub fn new() -> Self {
+        LogSender
+    }
+}
+
+#[async_trait]
+impl EmailSender for LogSender {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        tracing::info!(
+            to = %message.to,
+            subject = %message.subject,
+            body = %message.text_body.as_deref().unwrap_or(&message.html_body),
+            "Email not sent (EMAIL_PROVIDER=log)"
+        );
+        Ok(())
+    }
+}
@@ -1,17 +1,29 @@
 use async_trait::async_trait;
-use reqwest::Client;
 use std::error::Error;
 
 use super::{EmailMessage, EmailSender};
+use crate::http_client::HttpClient;
+
+/// Mailgun の API のベースURL
+const MAILGUN_API_BASE: &str = "https://api.mailgun.net/v3";
 
 pub struct MailgunSender {
-    client: Client,
+    http: HttpClient,
+    api_base: String,
+    api_key: String,
+    domain: String,
+    /// 送信元（`名前 <アドレス>`）
+    from: String,
 }
 
 impl MailgunSender {
-    pub fn new() -> Self {
+    pub fn new(http: HttpClient, api_key: String, domain: String, from: String) -> Self {
         MailgunSender {
-            client: Client::new(),
+            http,
+            api_base: MAILGUN_API_BASE.to_string(),
+            api_key,
+            domain,
+            from,
         }
     }
 }
@@ -19,18 +31,10 @@ impl MailgunSender {
 #[async_trait]
 impl EmailSender for MailgunSender {
     async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let api_key = std::env::var("MAILGUN_API_KEY").map_err(|_| "MAILGUN_API_KEY is not set")?;
-        let domain = std::env::var("MAILGUN_DOMAIN").map_err(|_| "MAILGUN_DOMAIN is not set")?;
-        let from_email =
-            std::env::var("EMAIL_FROM").unwrap_or_else(|_| "noreply@example.com".to_string());
-        let from_name =
-            std::env::var("EMAIL_FROM_NAME").unwrap_or_else(|_| "みんなの話題".to_string());
-
-        let from = format!("{} <{}>", from_name, from_email);
-        let url = format!("https://api.mailgun.net/v3/{}/messages", domain);
+        let url = format!("{}/{}/messages", self.api_base, self.domain);
 
         let form = reqwest::multipart::Form::new()
-            .text("from", from)
+            .text("from", self.from.clone())
             .text("to", message.to)
             .text("subject", message.subject)
             .text("html", message.html_body);
@@ -41,9 +45,9 @@ impl EmailSender for MailgunSender {
             form
         };
 
-        self.client
+        self.http
             .post(&url)
-            .basic_auth("api", Some(api_key))
+            .basic_auth("api", Some(&self.api_key))
             .multipart(form)
             .send()
             .await?
@@ -52,3 +56,74 @@ impl EmailSender for MailgunSender {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http_client::USER_AGENT, test_utils::test_config};
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// 受け取ったリクエストのドメイン・Authorization・User-Agent
+    type Received = Arc<Mutex<Vec<(String, String, String)>>>;
+
+    #[tokio::test]
+    async fn test_共有のクライアントでmailgunのapiに送信する() {
+        // 共有のクライアントでMailgunのAPIに送信することを確認
+        // 受け取ったリクエストのドメイン・認証・User-Agent を記録するサーバー
+        let received: Received = Arc::default();
+        let app = Router::new()
+            .route(
+                "/v3/{domain}/messages",
+                post(
+                    |State(received): State<Received>,
+                     Path(domain): Path<String>,
+                     headers: HeaderMap| async move {
+                        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+                        received.lock().unwrap().push((
+                            domain,
+                            header("authorization"),
+                            header("user-agent"),
+                        ));
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let sender = MailgunSender {
+            api_base: format!("http://{}/v3", addr),
+            ..MailgunSender::new(
+                HttpClient::new(&test_config()).unwrap(),
+                "key-123".to_string(),
+                "mg.example.com".to_string(),
+                test_config().email_from_mailbox(),
+            )
+        };
+        sender
+            .send_email(EmailMessage {
+                to: "user@example.com".to_string(),
+                subject: "Subject".to_string(),
+                html_body: "<p>Body</p>".to_string(),
+                text_body: Some("Body".to_string()),
+            })
+            .await
+            .unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (domain, authorization, user_agent) = &received[0];
+        assert_eq!(domain, "mg.example.com");
+        // api:key-123 の Basic 認証
+        assert_eq!(authorization, "Basic YXBpOmtleS0xMjM=");
+        assert_eq!(user_agent, USER_AGENT);
+    }
+}
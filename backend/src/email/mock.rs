@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+use super::{EmailMessage, EmailSender};
+
+/// 送信したメールを記録するテスト用の送信者
+#[derive(Default)]
+pub struct MockSender {
+    sent: Mutex<Vec<EmailMessage>>,
+    failing: bool,
+}
+
+impl MockSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 常に送信に失敗する送信者（再試行のテスト用）
+    pub fn failing() -> Self {
+        Self {
+            failing: true,
+            ..Self::default()
+        }
+    }
+
+    /// これまでに送信されたメール
+    pub fn sent(&self) -> Vec<EmailMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl EmailSender for MockSender {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.failing {
+            return Err("mock sender failure".into());
+        }
+        self.sent.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+/// 送信者として渡した後も送信したメールを確認できるようにする
+#[async_trait]
+impl EmailSender for Arc<MockSender> {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.as_ref().send_email(message).await
+    }
+}
@@ -2,8 +2,20 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
+use crate::{
+    config::{Config, EmailMode, EmailProvider},
+    http_client::HttpClient,
+};
+
+pub mod allowlist;
+pub mod dry_run;
+pub mod log;
 pub mod mailgun;
 pub mod mailhog;
+#[cfg(test)]
+pub mod mock;
+pub mod smtp;
+pub mod templates;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailMessage {
@@ -18,11 +30,127 @@ pub trait EmailSender: Send + Sync {
     async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
 
-pub fn get_email_sender() -> Box<dyn EmailSender> {
-    let app_env = std::env::var("APP_ENV").unwrap_or_else(|_| "development".to_string());
+/// EMAIL_MODE と EMAIL_PROVIDER に応じた送信者を返す
+///
+/// API で送信するプロバイダー（Mailgun）は共有の `http` クライアントを使います。
+pub fn get_email_sender(config: &Config, http: &HttpClient) -> Box<dyn EmailSender> {
+    match config.email_mode {
+        // dry_run では送信先のサービスの設定を必要としない
+        EmailMode::DryRun => Box::new(dry_run::DryRunSender::new()),
+        mode => with_mode(mode, &config.email_allowlist, provider_sender(config, http)),
+    }
+}
+
+/// 送信モードに応じて、送信者を実際に送信するかどうかで包む
+fn with_mode(
+    mode: EmailMode,
+    allowlist: &[String],
+    provider: Box<dyn EmailSender>,
+) -> Box<dyn EmailSender> {
+    match mode {
+        EmailMode::Live => provider,
+        EmailMode::DryRun => Box::new(dry_run::DryRunSender::new()),
+        EmailMode::Allowlist => Box::new(allowlist::AllowlistSender::new(
+            allowlist::EmailAllowlist::new(allowlist),
+            provider,
+        )),
+    }
+}
+
+/// EMAIL_PROVIDER（mailgun / mailhog / smtp / log）に応じた送信者を返す
+///
+/// 未設定の場合の選び方は `Config` を参照してください。
+/// 実際に送信するかどうかは APP_ENV ではなく EMAIL_MODE で決まります。
+fn provider_sender(config: &Config, http: &HttpClient) -> Box<dyn EmailSender> {
+    match config.email_provider {
+        // キーとドメインは Config::validate で確認済み
+        EmailProvider::Mailgun => Box::new(mailgun::MailgunSender::new(
+            http.clone(),
+            config
+                .mailgun_api_key
+                .as_ref()
+                .map(|key| key.expose().clone())
+                .unwrap_or_default(),
+            config.mailgun_domain.clone().unwrap_or_default(),
+            config.email_from_mailbox(),
+        )),
+        EmailProvider::Mailhog => Box::new(mailhog::MailhogSender::new(
+            config.email_from_mailbox(),
+            config.mailhog_host.clone(),
+            config.mailhog_port,
+        )),
+        // 接続先と送信元は Config::validate で確認済み
+        EmailProvider::Smtp => {
+            Box::new(smtp::SmtpSender::new(smtp::SmtpConfig::from_config(config)))
+        }
+        EmailProvider::Log => Box::new(log::LogSender::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::MockSender;
+    use std::sync::Arc;
+
+    fn message(to: &str) -> EmailMessage {
+        EmailMessage {
+            to: to.to_string(),
+            subject: "Subject".to_string(),
+            html_body: "<p>Body</p>".to_string(),
+            text_body: None,
+        }
+    }
+
+    /// 送信モードで包んだ送信者で、指定した宛先に送信したときに実際に送信されたメール
+    async fn route(mode: EmailMode, allowlist: &[&str], recipients: &[&str]) -> Vec<String> {
+        let provider = Arc::new(MockSender::new());
+        let allowlist: Vec<String> = allowlist.iter().map(|p| p.to_string()).collect();
+        let sender = with_mode(mode, &allowlist, Box::new(provider.clone()));
+
+        for to in recipients {
+            sender.send_email(message(to)).await.unwrap();
+        }
+
+        provider.sent().into_iter().map(|m| m.to).collect()
+    }
+
+    #[tokio::test]
+    async fn test_liveモードはすべての宛先に送信する() {
+        // liveモードはすべての宛先に送信することを確認
+        let sent = route(
+            EmailMode::Live,
+            &[],
+            &["user@example.com", "other@gmail.com"],
+        )
+        .await;
+
+        assert_eq!(sent, vec!["user@example.com", "other@gmail.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_dry_runモードは送信しない() {
+        // dry_runモードは送信しないことを確認
+        let sent = route(
+            EmailMode::DryRun,
+            &["example.com"],
+            &["user@example.com", "other@gmail.com"],
+        )
+        .await;
+
+        assert!(sent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_allowlistモードは許可された宛先にのみ送信する() {
+        // allowlistモードは許可された宛先にのみ送信することを確認
+        let sent = route(
+            EmailMode::Allowlist,
+            &["example.com", "qa+*@gmail.com"],
+            &["user@example.com", "other@gmail.com", "qa+1@gmail.com"],
+        )
+        .await;
 
-    match app_env.as_str() {
-        "production" | "staging" => Box::new(mailgun::MailgunSender::new()),
-        _ => Box::new(mailhog::MailhogSender::new()),
+        assert_eq!(sent, vec!["user@example.com", "qa+1@gmail.com"]);
     }
 }
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::error::Error;
+
+use super::{EmailMessage, EmailSender};
+use crate::config::{Config, Secret, SmtpTls};
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<Secret<String>>,
+    /// 送信元（`名前 <アドレス>` またはアドレスのみ）
+    pub from: String,
+    pub tls: SmtpTls,
+}
+
+impl SmtpConfig {
+    /// SMTP_* の設定から作成する（値は Config::validate で確認済み）
+    pub fn from_config(config: &Config) -> Self {
+        SmtpConfig {
+            host: config.smtp_host.clone().unwrap_or_default(),
+            port: config.smtp_port,
+            username: config.smtp_username.clone(),
+            password: config.smtp_password.clone(),
+            from: config.smtp_from_mailbox(),
+            tls: config.smtp_tls,
+        }
+    }
+}
+
+/// SMTPの認証情報でメールを送信する
+pub struct SmtpSender {
+    config: SmtpConfig,
+}
+
+impl SmtpSender {
+    pub fn new(config: SmtpConfig) -> Self {
+        SmtpSender { config }
+    }
+
+    fn transport(
+        &self,
+    ) -> Result<AsyncSmtpTransport<Tokio1Executor>, Box<dyn Error + Send + Sync>> {
+        let host = self.config.host.as_str();
+        let builder = match self.config.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+        let builder = builder.port(self.config.port);
+
+        let builder = match (&self.config.username, &self.config.password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose().clone(),
+            )),
+            _ => builder,
+        };
+
+        Ok(builder.build())
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpSender {
+    async fn send_email(&self, message: EmailMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let from = self.config.from.parse::<Mailbox>()?;
+        let email = build_message(from, message)?;
+
+        tracing::info!(
+            "Sending email via SMTP server at {}:{}",
+            self.config.host,
+            self.config.port
+        );
+        self.transport()?.send(email).await?;
+
+        Ok(())
+    }
+}
+
+/// `EmailMessage` からテキストとHTMLの両方を含むメールを組み立てる
+pub(super) fn build_message(
+    from: Mailbox,
+    message: EmailMessage,
+) -> Result<Message, Box<dyn Error + Send + Sync>> {
+    let to = message.to.parse::<Mailbox>()?;
+
+    let email = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(message.subject)
+        .multipart(
+            MultiPart::alternative()
+                .singlepart(
+                    SinglePart::builder().header(ContentType::TEXT_PLAIN).body(
+                        message
+                            .text_body
+                            .unwrap_or_else(|| message.html_body.clone()),
+                    ),
+                )
+                .singlepart(
+                    SinglePart::builder()
+                        .header(ContentType::TEXT_HTML)
+                        .body(message.html_body),
+                ),
+        )?;
+
+    Ok(email)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_message() -> EmailMessage {
+        EmailMessage {
+            to: "user@example.com".to_string(),
+            subject: "Verify your email".to_string(),
+            html_body: "<p>Click the link</p>".to_string(),
+            text_body: Some("Click the link".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_メッセージのヘッダーと本文を組み立てる() {
+        // 送信元・宛先・件名のヘッダーと、テキスト・HTMLの両方のパートが含まれることを確認
+        let from = "Minwada <noreply@example.com>".parse().unwrap();
+        let email = build_message(from, test_message()).unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+
+        assert!(formatted.contains("From: Minwada <noreply@example.com>"));
+        assert!(formatted.contains("To: user@example.com"));
+        assert!(formatted.contains("Subject: Verify your email"));
+        assert!(formatted.contains("Content-Type: multipart/alternative"));
+        assert!(formatted.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(formatted.contains("Content-Type: text/html; charset=utf-8"));
+        assert!(formatted.contains("Click the link"));
+        assert!(formatted.contains("<p>Click the link</p>"));
+    }
+
+    #[test]
+    fn test_不正な宛先はエラーになる() {
+        // メールアドレスとして解釈できない宛先は送信前にエラーになることを確認
+        let from = "noreply@example.com".parse().unwrap();
+        let message = EmailMessage {
+            to: "not an address".to_string(),
+            ..test_message()
+        };
+
+        assert!(build_message(from, message).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires MailHog listening on localhost:1025"]
+    async fn test_ローカルのmailhogにsmtpで送信できる() {
+        // ローカルのMailHogにSMTPで送信できることを確認
+        // docker compose でMailHogを起動してから `cargo test -- --ignored` で実行する
+        let sender = SmtpSender::new(SmtpConfig {
+            host: "localhost".to_string(),
+            port: 1025,
+            username: None,
+            password: None,
+            from: "Minwada <noreply@example.com>".to_string(),
+            tls: SmtpTls::None,
+        });
+
+        sender.send_email(test_message()).await.unwrap();
+    }
+}
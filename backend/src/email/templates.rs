@@ -0,0 +1,465 @@
+// templates/email/ 以下のテンプレートからメールの本文を組み立てる
+//
+// 各メールはHTML（`.html`、値はエスケープされる）とテキスト（`.txt`）の2つのテンプレートを持つ。
+use std::fmt;
+
+use askama::Template;
+
+use super::EmailMessage;
+use crate::error::AppError;
+
+/// メールの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    Verification,
+    PasswordReset,
+    MagicLink,
+    /// メールアドレス変更の確認（新しいアドレス宛て）
+    EmailChangeConfirm,
+    /// メールアドレス変更のお知らせと取り消しリンク（旧アドレス宛て）
+    EmailChangeNotice,
+}
+
+impl EmailTemplate {
+    pub fn subject(&self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => "メールアドレスの確認",
+            EmailTemplate::PasswordReset => "パスワードリセットのリクエスト",
+            EmailTemplate::MagicLink => "ログイン用リンク",
+            EmailTemplate::EmailChangeConfirm => "メールアドレス変更の確認",
+            EmailTemplate::EmailChangeNotice => "メールアドレス変更のお知らせ",
+        }
+    }
+}
+
+/// リンクの有効期間
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    Hours(i64),
+    Minutes(i64),
+}
+
+impl fmt::Display for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expiry::Hours(hours) => write!(f, "{}時間", hours),
+            Expiry::Minutes(minutes) => write!(f, "{}分", minutes),
+        }
+    }
+}
+
+/// テンプレートに渡す値
+#[derive(Debug, Clone)]
+pub struct EmailContext<'a> {
+    /// 宛先のメールアドレス
+    pub to: &'a str,
+    pub username: &'a str,
+    /// メール内のリンク先（確認・リセット・ログイン用のURL）
+    pub action_url: &'a str,
+    pub expires_in: Expiry,
+}
+
+#[derive(Template)]
+#[template(path = "email/verification.html")]
+struct VerificationHtml<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/verification.txt")]
+struct VerificationText<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/password_reset.html")]
+struct PasswordResetHtml<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/password_reset.txt")]
+struct PasswordResetText<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/magic_link.html")]
+struct MagicLinkHtml<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/magic_link.txt")]
+struct MagicLinkText<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_change_confirm.html")]
+struct EmailChangeConfirmHtml<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_change_confirm.txt")]
+struct EmailChangeConfirmText<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_change_notice.html")]
+struct EmailChangeNoticeHtml<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_change_notice.txt")]
+struct EmailChangeNoticeText<'a> {
+    ctx: &'a EmailContext<'a>,
+}
+
+/// 保存した検索条件に一致したスレッド
+#[derive(Debug, Clone)]
+pub struct AlertThread {
+    pub title: String,
+    pub url: String,
+}
+
+/// 保存した検索条件の通知メールに渡す値
+#[derive(Debug, Clone)]
+pub struct SavedSearchAlertContext<'a> {
+    pub to: &'a str,
+    pub username: &'a str,
+    pub query: &'a str,
+    pub threads: &'a [AlertThread],
+}
+
+#[derive(Template)]
+#[template(path = "email/saved_search_alert.html")]
+struct SavedSearchAlertHtml<'a> {
+    ctx: &'a SavedSearchAlertContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/saved_search_alert.txt")]
+struct SavedSearchAlertText<'a> {
+    ctx: &'a SavedSearchAlertContext<'a>,
+}
+
+/// 保存した検索条件に一致したスレッドの通知メールを組み立てる
+pub fn render_saved_search_alert(
+    context: &SavedSearchAlertContext<'_>,
+) -> Result<EmailMessage, AppError> {
+    let ctx = context;
+    let render_error =
+        |err: askama::Error| AppError::Internal(format!("Failed to render alert email: {}", err));
+
+    Ok(EmailMessage {
+        to: context.to.to_string(),
+        subject: format!("「{}」に一致する新しいスレッド", context.query),
+        html_body: SavedSearchAlertHtml { ctx }
+            .render()
+            .map_err(render_error)?,
+        text_body: Some(
+            SavedSearchAlertText { ctx }
+                .render()
+                .map_err(render_error)?,
+        ),
+    })
+}
+
+/// ログインした端末の情報（新しい端末からのログインの通知・確認メールに渡す値）
+#[derive(Debug, Clone)]
+pub struct NewSignInContext<'a> {
+    pub to: &'a str,
+    pub username: &'a str,
+    /// ログインした日時（表示用に整形したもの）
+    pub signed_in_at: &'a str,
+    pub user_agent: &'a str,
+    pub ip_address: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/new_sign_in.html")]
+struct NewSignInHtml<'a> {
+    ctx: &'a NewSignInContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/new_sign_in.txt")]
+struct NewSignInText<'a> {
+    ctx: &'a NewSignInContext<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "email/device_confirmation.html")]
+struct DeviceConfirmationHtml<'a> {
+    ctx: &'a NewSignInContext<'a>,
+    action_url: &'a str,
+    expires_in: Expiry,
+}
+
+#[derive(Template)]
+#[template(path = "email/device_confirmation.txt")]
+struct DeviceConfirmationText<'a> {
+    ctx: &'a NewSignInContext<'a>,
+    action_url: &'a str,
+    expires_in: Expiry,
+}
+
+/// 新しい端末からのログインを知らせるメールを組み立てる
+pub fn render_new_sign_in(context: &NewSignInContext<'_>) -> Result<EmailMessage, AppError> {
+    let ctx = context;
+    let render_error = |err: askama::Error| {
+        AppError::Internal(format!("Failed to render new sign-in email: {}", err))
+    };
+
+    Ok(EmailMessage {
+        to: context.to.to_string(),
+        subject: "新しい端末からのログイン".to_string(),
+        html_body: NewSignInHtml { ctx }.render().map_err(render_error)?,
+        text_body: Some(NewSignInText { ctx }.render().map_err(render_error)?),
+    })
+}
+
+/// 新しい端末からのログインを完了するリンク付きの確認メールを組み立てる（NEW_DEVICE_CONFIRMATION）
+pub fn render_device_confirmation(
+    context: &NewSignInContext<'_>,
+    action_url: &str,
+    expires_in: Expiry,
+) -> Result<EmailMessage, AppError> {
+    let ctx = context;
+    let render_error = |err: askama::Error| {
+        AppError::Internal(format!(
+            "Failed to render device confirmation email: {}",
+            err
+        ))
+    };
+
+    Ok(EmailMessage {
+        to: context.to.to_string(),
+        subject: "新しい端末からのログインの確認".to_string(),
+        html_body: DeviceConfirmationHtml {
+            ctx,
+            action_url,
+            expires_in,
+        }
+        .render()
+        .map_err(render_error)?,
+        text_body: Some(
+            DeviceConfirmationText {
+                ctx,
+                action_url,
+                expires_in,
+            }
+            .render()
+            .map_err(render_error)?,
+        ),
+    })
+}
+
+/// テンプレートからHTMLとテキストの両方の本文を持つメールを組み立てる
+pub fn render_email(
+    template: EmailTemplate,
+    context: &EmailContext<'_>,
+) -> Result<EmailMessage, AppError> {
+    let ctx = context;
+    let rendered = match template {
+        EmailTemplate::Verification => (
+            VerificationHtml { ctx }.render(),
+            VerificationText { ctx }.render(),
+        ),
+        EmailTemplate::PasswordReset => (
+            PasswordResetHtml { ctx }.render(),
+            PasswordResetText { ctx }.render(),
+        ),
+        EmailTemplate::MagicLink => (
+            MagicLinkHtml { ctx }.render(),
+            MagicLinkText { ctx }.render(),
+        ),
+        EmailTemplate::EmailChangeConfirm => (
+            EmailChangeConfirmHtml { ctx }.render(),
+            EmailChangeConfirmText { ctx }.render(),
+        ),
+        EmailTemplate::EmailChangeNotice => (
+            EmailChangeNoticeHtml { ctx }.render(),
+            EmailChangeNoticeText { ctx }.render(),
+        ),
+    };
+
+    let (html_body, text_body) = match rendered {
+        (Ok(html), Ok(text)) => (html, text),
+        (Err(err), _) | (_, Err(err)) => {
+            return Err(AppError::Internal(format!(
+                "Failed to render {:?} email: {}",
+                template, err
+            )))
+        }
+    };
+
+    Ok(EmailMessage {
+        to: context.to.to_string(),
+        subject: template.subject().to_string(),
+        html_body,
+        text_body: Some(text_body),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(expires_in: Expiry) -> EmailContext<'static> {
+        EmailContext {
+            to: "user@example.com",
+            username: "taro",
+            action_url: "http://localhost:3000/action/token-123",
+            expires_in,
+        }
+    }
+
+    #[test]
+    fn test_すべてのテンプレートでurlとユーザー名が両方の本文に含まれる() {
+        // すべてのテンプレートでURLとユーザー名が両方の本文に含まれることを確認
+        for template in [
+            EmailTemplate::Verification,
+            EmailTemplate::PasswordReset,
+            EmailTemplate::MagicLink,
+            EmailTemplate::EmailChangeConfirm,
+            EmailTemplate::EmailChangeNotice,
+        ] {
+            let message = render_email(template, &context(Expiry::Hours(24))).unwrap();
+            let text_body = message.text_body.as_deref().unwrap();
+
+            assert_eq!(message.to, "user@example.com");
+            assert_eq!(message.subject, template.subject());
+            assert!(message
+                .html_body
+                .contains("href=\"http://localhost:3000/action/token-123\""));
+            assert!(message.html_body.contains("こんにちは、taroさん"));
+            assert!(text_body.contains("http://localhost:3000/action/token-123"));
+            assert!(text_body.contains("こんにちは、taroさん"));
+            assert!(message.html_body.contains("24時間後に期限切れ"));
+            assert!(text_body.contains("24時間後に期限切れ"));
+        }
+    }
+
+    #[test]
+    fn test_検証メールのテキスト本文() {
+        // 検証メールのテキスト本文を確認
+        let message =
+            render_email(EmailTemplate::Verification, &context(Expiry::Hours(48))).unwrap();
+
+        assert_eq!(
+            message.text_body.unwrap().trim_end(),
+            "メールアドレスの確認\n\
+             \n\
+             こんにちは、taroさん\n\
+             \n\
+             以下のリンクをクリックしてメールアドレスを確認してください：\n\
+             \n\
+             http://localhost:3000/action/token-123\n\
+             \n\
+             このリンクは48時間後に期限切れになります。"
+        );
+    }
+
+    #[test]
+    fn test_有効期間は分でも表示できる() {
+        // 1時間未満の有効期間は分で表示されることを確認
+        let message =
+            render_email(EmailTemplate::MagicLink, &context(Expiry::Minutes(15))).unwrap();
+
+        assert!(message.html_body.contains("15分後に期限切れ"));
+        assert!(message.text_body.unwrap().contains("15分後に期限切れ"));
+    }
+
+    #[test]
+    fn test_検索条件の通知メールに一致したスレッドが含まれる() {
+        // 検索条件の通知メールに一致したスレッドが含まれることを確認
+        let threads = vec![
+            AlertThread {
+                title: "Rust と axum の質問".to_string(),
+                url: "http://localhost:3000/threads/1".to_string(),
+            },
+            AlertThread {
+                title: "<b>axum</b> 0.8".to_string(),
+                url: "http://localhost:3000/threads/2".to_string(),
+            },
+        ];
+        let message = render_saved_search_alert(&SavedSearchAlertContext {
+            to: "user@example.com",
+            username: "taro",
+            query: "rust axum",
+            threads: &threads,
+        })
+        .unwrap();
+        let text_body = message.text_body.as_deref().unwrap();
+
+        assert_eq!(message.subject, "「rust axum」に一致する新しいスレッド");
+        assert!(message
+            .html_body
+            .contains("href=\"http://localhost:3000/threads/1\""));
+        assert!(message.html_body.contains("&lt;b&gt;axum&lt;/b&gt; 0.8"));
+        assert!(text_body.contains("こんにちは、taroさん"));
+        assert!(text_body.contains("- Rust と axum の質問\n  http://localhost:3000/threads/1"));
+        assert!(text_body.contains("- <b>axum</b> 0.8"));
+    }
+
+    #[test]
+    fn test_新しい端末のメールにログインした端末の情報が含まれる() {
+        // 新しい端末のメールにログインした端末の情報が含まれることを確認
+        let ctx = NewSignInContext {
+            to: "user@example.com",
+            username: "taro",
+            signed_in_at: "2025-06-25 09:30 (UTC)",
+            user_agent: "<Mozilla/5.0>",
+            ip_address: "203.0.113.1",
+        };
+
+        let alert = render_new_sign_in(&ctx).unwrap();
+        let confirmation = render_device_confirmation(
+            &ctx,
+            "http://localhost:3000/confirm-device/token-123",
+            Expiry::Minutes(15),
+        )
+        .unwrap();
+
+        for message in [&alert, &confirmation] {
+            let text_body = message.text_body.as_deref().unwrap();
+            assert_eq!(message.to, "user@example.com");
+            assert!(text_body.contains("こんにちは、taroさん"));
+            assert!(text_body.contains("日時：2025-06-25 09:30 (UTC)"));
+            assert!(text_body.contains("ブラウザ：<Mozilla/5.0>"));
+            assert!(text_body.contains("IPアドレス：203.0.113.1"));
+            assert!(message.html_body.contains("&lt;Mozilla/5.0&gt;"));
+        }
+        assert_eq!(alert.subject, "新しい端末からのログイン");
+        assert!(confirmation
+            .html_body
+            .contains("href=\"http://localhost:3000/confirm-device/token-123\""));
+        assert!(confirmation
+            .text_body
+            .unwrap()
+            .contains("このリンクは15分後に期限切れ"));
+    }
+
+    #[test]
+    fn test_htmlではユーザー名がエスケープされる() {
+        // HTMLではユーザー名がエスケープされることを確認
+        let ctx = EmailContext {
+            username: "<script>alert(1)</script>",
+            ..context(Expiry::Hours(1))
+        };
+        let message = render_email(EmailTemplate::PasswordReset, &ctx).unwrap();
+
+        assert!(!message.html_body.contains("<script>"));
+        assert!(message.html_body.contains("&lt;script&gt;"));
+        // テキストはエスケープしない
+        assert!(message
+            .text_body
+            .unwrap()
+            .contains("<script>alert(1)</script>"));
+    }
+}
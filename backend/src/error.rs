@@ -1,11 +1,14 @@
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde_json::json;
 use thiserror::Error;
 
+use crate::models::moderation::UserBanStatus;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -58,6 +61,51 @@ pub enum AppError {
 
     #[error("Email verification required")]
     EmailVerificationRequired,
+
+    #[error("Too many requests, retry after {0} seconds")]
+    TooManyRequests(u64),
+
+    #[error("Verification email was sent recently, retry after {0} seconds")]
+    VerificationResendCooldown(u64),
+
+    #[error("Email address is already verified")]
+    EmailAlreadyVerified,
+
+    #[error("User is muted until {0}")]
+    UserMuted(DateTime<Utc>),
+
+    #[error("User is banned")]
+    UserBanned(UserBanStatus),
+
+    #[error("Thread is locked")]
+    ThreadLocked,
+
+    #[error("Comment edit window has expired")]
+    CommentEditWindowExpired,
+
+    #[error("Email address is not verified")]
+    EmailNotVerified,
+
+    #[error("Sign-in from a new device must be confirmed by email")]
+    DeviceConfirmationRequired,
+
+    #[error("Password must be reset before signing in")]
+    PasswordResetRequired,
+
+    #[error("Unique constraint violated ({code}): {message}")]
+    UniqueViolation {
+        code: &'static str,
+        message: &'static str,
+    },
+
+    #[error("Referenced resource not found ({0})")]
+    ForeignKeyViolation(&'static str),
+
+    #[error("Content was permanently deleted and cannot be restored")]
+    ContentNotRestorable,
+
+    #[error("Comment tree too large: {total} comments exceeds {max}")]
+    CommentTreeTooLarge { total: u64, max: u64 },
 }
 
 // Manual implementation of From trait for argon2 errors
@@ -74,8 +122,51 @@ impl From<Box<dyn std::error::Error>> for AppError {
     }
 }
 
+impl AppError {
+    /// クライアントがエラーの種類で分岐するためのコード
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            AppError::UserMuted(_) => Some("USER_MUTED"),
+            AppError::UserBanned(_) => Some("USER_BANNED"),
+            AppError::ThreadLocked => Some("THREAD_LOCKED"),
+            AppError::CommentEditWindowExpired => Some("COMMENT_EDIT_WINDOW_EXPIRED"),
+            AppError::EmailNotVerified => Some("EMAIL_NOT_VERIFIED"),
+            AppError::DeviceConfirmationRequired => Some("DEVICE_CONFIRMATION_REQUIRED"),
+            AppError::PasswordResetRequired => Some("PASSWORD_RESET_REQUIRED"),
+            AppError::EmailVerificationRequired => Some("EMAIL_VERIFICATION_REQUIRED"),
+            AppError::EmailAlreadyVerified => Some("EMAIL_ALREADY_VERIFIED"),
+            AppError::VerificationResendCooldown(_) => Some("RESEND_COOLDOWN"),
+            AppError::UniqueViolation { code, .. } => Some(*code),
+            AppError::ForeignKeyViolation(code) => Some(*code),
+            AppError::CommentTreeTooLarge { .. } => Some("COMMENT_TREE_TOO_LARGE"),
+            AppError::ContentNotRestorable => Some("CONTENT_NOT_RESTORABLE"),
+            _ => None,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match self {
+            AppError::TooManyRequests(seconds) | AppError::VerificationResendCooldown(seconds) => {
+                Some(seconds)
+            }
+            _ => None,
+        };
+        let code = self.code();
+        let muted_until = match self {
+            AppError::UserMuted(expires_at) => Some(expires_at),
+            _ => None,
+        };
+        let ban = match &self {
+            AppError::UserBanned(ban) => Some(ban.clone()),
+            _ => None,
+        };
+        let max_comments = match self {
+            AppError::CommentTreeTooLarge { max, .. } => Some(max),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             AppError::Database(err) => {
                 tracing::error!("Database error: {:?}", err);
@@ -151,14 +242,100 @@ impl IntoResponse for AppError {
                 tracing::error!("UUID parse error: {:?}", err);
                 (StatusCode::BAD_REQUEST, "Invalid UUID format".to_string())
             }
+            AppError::UserMuted(_) => (
+                StatusCode::FORBIDDEN,
+                "一定期間、投稿が制限されています".to_string(),
+            ),
+            AppError::ThreadLocked => (StatusCode::FORBIDDEN, "Thread is locked".to_string()),
+            AppError::CommentEditWindowExpired => (
+                StatusCode::FORBIDDEN,
+                "Comment can no longer be edited".to_string(),
+            ),
+            AppError::UserBanned(_) => (
+                StatusCode::FORBIDDEN,
+                "アカウントの利用が停止されています".to_string(),
+            ),
+            AppError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                "メールアドレスの認証が完了していません。届いたメールのリンクから認証してください"
+                    .to_string(),
+            ),
+            AppError::DeviceConfirmationRequired => (
+                StatusCode::FORBIDDEN,
+                "新しい端末からのログインです。届いたメールのリンクからログインを完了してください"
+                    .to_string(),
+            ),
+            AppError::PasswordResetRequired => (
+                StatusCode::FORBIDDEN,
+                "パスワードの再設定が必要です。届いたメールのリンクからパスワードを再設定してください"
+                    .to_string(),
+            ),
+            AppError::UniqueViolation { message, .. } => {
+                (StatusCode::CONFLICT, message.to_string())
+            }
+            AppError::ForeignKeyViolation(_) => {
+                (StatusCode::NOT_FOUND, "Resource not found".to_string())
+            }
+            AppError::ContentNotRestorable => (
+                StatusCode::GONE,
+                "投稿のデータが残っていないため復元できません".to_string(),
+            ),
+            AppError::TooManyRequests(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "リクエストが多すぎます。しばらくしてから再度お試しください".to_string(),
+            ),
+            AppError::VerificationResendCooldown(seconds) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("検証メールは送信済みです。{}秒後に再送信できます", seconds),
+            ),
+            AppError::EmailAlreadyVerified => (
+                StatusCode::BAD_REQUEST,
+                "このメールアドレスは既に確認済みです。".to_string(),
+            ),
+            AppError::CommentTreeTooLarge { total, max } => (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "コメントが多すぎるため一括で取得できません（{}件、上限{}件）。pageとlimitを指定して取得してください",
+                    total, max
+                ),
+            ),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message,
             "status": status.as_u16()
-        }));
+        });
+        // クライアントが判別できるようコードを付与
+        if let Some(code) = code {
+            body["code"] = json!(code);
+        }
+        // 再試行までの秒数も本文に含める
+        if let Some(seconds) = retry_after {
+            body["retry_after"] = json!(seconds);
+        }
+        // ミュート中の場合は期限も付与
+        if let Some(expires_at) = muted_until {
+            body["expires_at"] = json!(expires_at);
+        }
+        // 利用停止中の場合は理由と期限を付与
+        if let Some(ban) = ban {
+            body["ban_reason"] = json!(ban.reason);
+            body["banned_until"] = json!(ban.banned_until);
+        }
+        // 一括取得できる上限を付与
+        if let Some(max) = max_comments {
+            body["max_comments"] = json!(max);
+        }
+        let body = Json(body);
+
+        let mut response = (status, body).into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
 
-        (status, body).into_response()
+        response
     }
 }
 
@@ -0,0 +1,197 @@
+// 分析用のドメインイベントを専用のtracingターゲットへJSON Linesで出力する
+//
+// サブスクライバー側で `domain_events` ターゲットをファイルやコレクターに振り分けて使います。
+// ペイロードには個人情報（メールアドレス・ユーザー名・本文など）を含めず、ID・日時・件数のみとします。
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// ドメインイベントを出力するtracingのターゲット
+pub const TARGET: &str = "domain_events";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// イベントの出力を有効・無効にする（DOMAIN_EVENTS_ENABLED）
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// ドメインイベントのペイロード
+pub trait DomainEvent: Serialize {
+    /// イベントの種類（JSONの `kind`）
+    const KIND: &'static str;
+}
+
+/// 1行分のイベント
+#[derive(Serialize)]
+struct EventLine<'a, P: Serialize> {
+    kind: &'static str,
+    occurred_at: DateTime<Utc>,
+    payload: &'a P,
+}
+
+/// イベントをJSON Linesの1行として出力する
+pub fn emit<E: DomainEvent>(payload: &E) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let line = EventLine {
+        kind: E::KIND,
+        occurred_at: Utc::now(),
+        payload,
+    };
+    match serde_json::to_string(&line) {
+        Ok(json) => tracing::info!(target: TARGET, "{}", json),
+        Err(e) => tracing::warn!("Failed to serialize domain event {}: {}", E::KIND, e),
+    }
+}
+
+/// ユーザー登録
+#[derive(Debug, Serialize)]
+pub struct UserRegistered {
+    pub user_id: Uuid,
+}
+
+impl DomainEvent for UserRegistered {
+    const KIND: &'static str = "user_registered";
+}
+
+/// スレッド作成
+#[derive(Debug, Serialize)]
+pub struct ThreadCreated {
+    pub thread_id: Uuid,
+    pub user_id: Uuid,
+}
+
+impl DomainEvent for ThreadCreated {
+    const KIND: &'static str = "thread_created";
+}
+
+/// コメント作成
+#[derive(Debug, Serialize)]
+pub struct CommentCreated {
+    pub comment_id: Uuid,
+    pub thread_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub user_id: Uuid,
+}
+
+impl DomainEvent for CommentCreated {
+    const KIND: &'static str = "comment_created";
+}
+
+/// モデレーターが非表示にしたコメントの復元
+#[derive(Debug, Serialize)]
+pub struct CommentRestored {
+    pub comment_id: Uuid,
+    pub thread_id: Uuid,
+    /// 復元した管理者
+    pub actor_id: Uuid,
+}
+
+impl DomainEvent for CommentRestored {
+    const KIND: &'static str = "comment_restored";
+}
+
+/// 投票の操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteAction {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// スレッドへの投票
+#[derive(Debug, Serialize)]
+pub struct ThreadVoted {
+    pub thread_id: Uuid,
+    pub user_id: Uuid,
+    /// "upvote" または "downvote"
+    pub vote_type: String,
+    pub action: VoteAction,
+}
+
+impl DomainEvent for ThreadVoted {
+    const KIND: &'static str = "thread_voted";
+}
+
+#[cfg(test)]
+pub mod capture {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::subscriber::DefaultGuard;
+
+    /// 出力されたイベントを記録するテスト用のサブスクライバー
+    #[derive(Clone, Default)]
+    pub struct CapturedEvents(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedEvents {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedEvents {
+        /// 現在のスレッドで `domain_events` ターゲットの出力を記録し始める
+        pub fn start() -> (Self, DefaultGuard) {
+            let captured = Self::default();
+            let writer = captured.clone();
+            let subscriber = tracing_subscriber::fmt()
+                .with_writer(move || writer.clone())
+                .with_ansi(false)
+                .with_level(false)
+                .with_target(false)
+                .without_time()
+                .with_env_filter(super::TARGET)
+                .finish();
+            let guard = tracing::subscriber::set_default(subscriber);
+            (captured, guard)
+        }
+
+        /// 記録したイベントをJSONとして返す
+        pub fn events(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line.trim()).unwrap())
+                .collect()
+        }
+
+        /// 記録した出力をそのまま返す
+        pub fn raw(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capture::CapturedEvents, *};
+
+    #[test]
+    fn test_イベントはkindと日時とペイロードを含む() {
+        // 1行のJSONとして種類・発生日時・ペイロードが出力されることを確認
+        let (captured, _guard) = CapturedEvents::start();
+        let user_id = Uuid::new_v4();
+
+        emit(&UserRegistered { user_id });
+
+        let events = captured.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["kind"], "user_registered");
+        assert_eq!(events[0]["payload"]["user_id"], user_id.to_string());
+        assert!(events[0]["occurred_at"].is_string());
+    }
+}
@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        audit_log::{AuditLogFormat, AuditLogListResponse, AuditLogQuery, AuditLogResponse},
+        common::{ErrorResponse, PaginatedResponse},
+    },
+    pagination::{AdminListings, Pagination},
+    utils::audit_log::{self, AuditLogFilter},
+};
+
+/// 管理者操作の監査ログを検索する
+///
+/// `format=csv` を指定すると、条件に一致する全件をCSVファイルとして逐次出力します。
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit-log",
+    params(Pagination<AdminListings>, AuditLogQuery),
+    responses(
+        (status = 200, description = "Audit log entries (text/csv when format=csv)", body = AuditLogListResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_audit_log(
+    State(pool): State<PgPool>,
+    pagination: Pagination<AdminListings>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Response, AppError> {
+    let filter = AuditLogFilter::from(&query);
+
+    if query.format == AuditLogFormat::Csv {
+        let headers = [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"audit-log.csv\"",
+            ),
+        ];
+        return Ok((headers, audit_log::csv_body(pool, filter)).into_response());
+    }
+
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = audit_log::count(&pool, &filter).await?;
+    let entries = audit_log::search(&pool, &filter, limit as i64, offset)
+        .await?
+        .into_iter()
+        .map(AuditLogResponse::from)
+        .collect();
+
+    Ok(Json(AuditLogListResponse {
+        entries: PaginatedResponse::new(entries, total as u64, page, limit),
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::admin::mute_user::mute_user,
+        models::moderation::MuteUserRequest,
+        test_utils::{create_test_user, test_config},
+    };
+    use axum::{
+        body::to_bytes,
+        extract::{Extension, Path},
+        http::StatusCode,
+    };
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<AdminListings> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
+
+    async fn mute(pool: &PgPool, admin: &crate::models::User, reason: &str) -> uuid::Uuid {
+        let user = create_test_user(pool, true).await;
+        let _ = mute_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin.clone()),
+            Json(MuteUserRequest {
+                duration_minutes: 60,
+                reason: Some(reason.to_string()),
+            }),
+        )
+        .await
+        .expect("mute should succeed");
+        user.id
+    }
+
+    #[sqlx::test]
+    async fn test_ミュート操作が監査ログに記録され検索できる(pool: PgPool) {
+        // ミュートの操作が記録され、対象ユーザーで絞り込めることを確認
+        let admin = create_test_user(&pool, true).await;
+        let target_id = mute(&pool, &admin, "spam").await;
+        mute(&pool, &admin, "ads").await;
+
+        let response = get_audit_log(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(AuditLogQuery {
+                target_id: Some(target_id),
+                ..Default::default()
+            }),
+        )
+        .await
+        .expect("search should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let data = json["entries"]["data"].as_array().unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0]["action"], audit_log::actions::USER_MUTE);
+        assert_eq!(data[0]["actor_id"], admin.id.to_string());
+        assert_eq!(data[0]["details"]["reason"], "spam");
+        assert_eq!(json["entries"]["total"], 1);
+    }
+
+    #[sqlx::test]
+    async fn test_csv形式で全件を出力できる(pool: PgPool) {
+        // CSV形式ではヘッダー行と、エスケープされた各行が出力されることを確認
+        let admin = create_test_user(&pool, true).await;
+        mute(&pool, &admin, "spam, \"ads\"").await;
+        mute(&pool, &admin, "=cmd").await;
+
+        let response = get_audit_log(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(AuditLogQuery {
+                format: AuditLogFormat::Csv,
+                ..Default::default()
+            }),
+        )
+        .await
+        .expect("export should succeed");
+
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+
+        assert_eq!(lines[0], audit_log::CSV_HEADER.trim_end());
+        assert_eq!(lines.len(), 3);
+        assert!(lines
+            .iter()
+            .skip(1)
+            .all(|line| line.contains(",user.mute,user,")));
+        // details のJSONに含まれるカンマ・引用符はエスケープされる
+        assert!(csv.contains(r#"""reason"": ""spam, \""ads\"""""#));
+    }
+
+    #[sqlx::test]
+    async fn test_上限を超えるlimitは設定の上限に切り詰められる(pool: PgPool) {
+        // limit の上限を超える指定はエラーにせず、設定された上限で返すことを確認
+        let response = get_audit_log(
+            State(pool.clone()),
+            page_of(None, Some(1000)),
+            Query(Default::default()),
+        )
+        .await
+        .expect("search should succeed");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["entries"]["limit"],
+            test_config().content_limits.admin_listings.max
+        );
+    }
+}
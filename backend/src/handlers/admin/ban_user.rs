@@ -0,0 +1,324 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        moderation::{BanUserRequest, UserBanResponse},
+        User,
+    },
+    utils::audit_log,
+};
+
+/// ユーザーの利用を停止する
+///
+/// 利用停止中のユーザーはログインと閲覧はできますが、書き込みはできません。
+/// `duration_minutes` を省略すると無期限となり、そのユーザーのリフレッシュトークンはすべて無効化されます。
+/// 利用停止中のユーザーに対して呼ぶと、期限と理由を上書きします。
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/ban",
+    params(
+        ("id" = Uuid, Path, description = "利用を停止するユーザーのID")
+    ),
+    request_body = BanUserRequest,
+    responses(
+        (status = 200, description = "User banned", body = UserBanResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn ban_user(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+    Json(payload): Json<BanUserRequest>,
+) -> Result<Json<UserBanResponse>, AppError> {
+    payload.validate()?;
+
+    if user_id == admin.id {
+        return Err(AppError::BadRequest("You cannot ban yourself".to_string()));
+    }
+
+    let now = Utc::now();
+    let banned_until = payload
+        .duration_minutes
+        .map(|minutes| now + Duration::minutes(minutes));
+
+    // 利用停止・トークンの無効化・監査ログの記録は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET banned_at = $2, banned_until = $3, ban_reason = $4
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(now)
+    .bind(banned_until)
+    .bind(&payload.reason)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    // 無期限の場合は既存のセッションも終了させる
+    if banned_until.is_none() {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked = true WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::USER_BAN,
+        "user",
+        Some(user_id),
+        &json!({
+            "banned_until": banned_until,
+            "reason": payload.reason,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        user_id = %user_id,
+        banned_until = ?banned_until,
+        reason = ?payload.reason,
+        "User banned by admin"
+    );
+
+    Ok(Json(UserBanResponse {
+        user_id,
+        ban: user.active_ban(now),
+    }))
+}
+
+/// ユーザーの利用停止を解除する
+///
+/// 利用停止中でないユーザーに対して呼んでもエラーにはなりません。
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/unban",
+    params(
+        ("id" = Uuid, Path, description = "利用停止を解除するユーザーのID")
+    ),
+    responses(
+        (status = 200, description = "User unbanned", body = UserBanResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unban_user(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<UserBanResponse>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query(
+        "UPDATE users SET banned_at = NULL, banned_until = NULL, ban_reason = NULL WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::USER_UNBAN,
+        "user",
+        Some(user_id),
+        &json!({}),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        user_id = %user_id,
+        "User unbanned by admin"
+    );
+
+    Ok(Json(UserBanResponse { user_id, ban: None }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+
+    fn ban_request(duration_minutes: Option<i64>) -> BanUserRequest {
+        BanUserRequest {
+            duration_minutes,
+            reason: Some("spam".to_string()),
+        }
+    }
+
+    async fn find_user(pool: &PgPool, id: Uuid) -> User {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn active_refresh_tokens(pool: &PgPool, user_id: Uuid) -> i64 {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn insert_refresh_token(pool: &PgPool, user_id: Uuid) {
+        sqlx::query(
+            "INSERT INTO refresh_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, NOW() + INTERVAL '7 days')",
+        )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_無期限の利用停止ではリフレッシュトークンを無効化する(
+        pool: PgPool,
+    ) {
+        // 無期限の利用停止ではリフレッシュトークンが無効になることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, user.id).await;
+
+        let Json(response) = ban_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin),
+            Json(ban_request(None)),
+        )
+        .await
+        .unwrap();
+
+        let ban = response.ban.unwrap();
+        assert!(ban.permanent);
+        assert_eq!(ban.banned_until, None);
+        assert_eq!(ban.reason.as_deref(), Some("spam"));
+        assert_eq!(active_refresh_tokens(&pool, user.id).await, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_期限付きの利用停止は期限が過ぎると無効になる(pool: PgPool) {
+        // 期限付きの場合はセッションを残し、期限後はジョブ無しで解除扱いになることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, user.id).await;
+
+        let Json(response) = ban_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin),
+            Json(ban_request(Some(60))),
+        )
+        .await
+        .unwrap();
+
+        let ban = response.ban.unwrap();
+        assert!(!ban.permanent);
+        let banned_until = ban.banned_until.unwrap();
+        assert_eq!(active_refresh_tokens(&pool, user.id).await, 1);
+
+        let user = find_user(&pool, user.id).await;
+        assert!(user.active_ban(Utc::now()).is_some());
+        assert!(user
+            .active_ban(banned_until + Duration::seconds(1))
+            .is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_利用停止を解除できる(pool: PgPool) {
+        // 利用停止を解除するとログインできるようになることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+
+        let _ = ban_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin.clone()),
+            Json(ban_request(None)),
+        )
+        .await
+        .unwrap();
+        let Json(response) = unban_user(State(pool.clone()), Path(user.id), Extension(admin))
+            .await
+            .unwrap();
+
+        assert!(response.ban.is_none());
+        let user = find_user(&pool, user.id).await;
+        assert!(user.active_ban(Utc::now()).is_none());
+        assert_eq!(user.ban_reason, None);
+    }
+
+    #[sqlx::test]
+    async fn test_自分自身や存在しないユーザーは利用停止できない(
+        pool: PgPool,
+    ) {
+        // 自分自身や存在しないユーザーは利用停止できないことを確認
+        let admin = create_test_user(&pool, true).await;
+
+        let result = ban_user(
+            State(pool.clone()),
+            Path(admin.id),
+            Extension(admin.clone()),
+            Json(ban_request(None)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let result = ban_user(
+            State(pool.clone()),
+            Path(Uuid::new_v4()),
+            Extension(admin),
+            Json(ban_request(None)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        email_outbox::{EmailOutboxListResponse, EmailOutboxQuery, EmailOutboxResponse},
+    },
+    pagination::{AdminListings, Pagination},
+    utils::email_outbox,
+};
+
+/// メール送信のアウトボックスを一覧する（デバッグ用）
+///
+/// 本文には認証用のトークンが含まれるため、宛先・件名・送信状態のみを返します。
+#[utoipa::path(
+    get,
+    path = "/api/admin/email-outbox",
+    params(Pagination<AdminListings>, EmailOutboxQuery),
+    responses(
+        (status = 200, description = "Outbox entries", body = EmailOutboxListResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_email_outbox(
+    State(pool): State<PgPool>,
+    pagination: Pagination<AdminListings>,
+    Query(query): Query<EmailOutboxQuery>,
+) -> Result<Json<EmailOutboxListResponse>, AppError> {
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = email_outbox::count(&pool, query.status).await?;
+    let entries = email_outbox::search(&pool, query.status, limit as i64, offset)
+        .await?
+        .into_iter()
+        .map(EmailOutboxResponse::from)
+        .collect();
+
+    Ok(Json(EmailOutboxListResponse {
+        entries: PaginatedResponse::new(entries, total as u64, page, limit),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::{mock::MockSender, EmailMessage},
+        models::email_outbox::EmailOutboxStatus,
+        test_utils::test_config,
+    };
+    use chrono::Utc;
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<AdminListings> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
+
+    fn message(to: &str) -> EmailMessage {
+        EmailMessage {
+            to: to.to_string(),
+            subject: "Subject".to_string(),
+            html_body: "<p>secret-token</p>".to_string(),
+            text_body: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_送信状態で絞り込める(pool: PgPool) {
+        // 送信状態を指定するとそのメールだけが返されることを確認
+        email_outbox::enqueue(&pool, &message("sent@example.com"))
+            .await
+            .unwrap();
+        email_outbox::deliver_due(&pool, &MockSender::new(), Utc::now())
+            .await
+            .unwrap();
+        email_outbox::enqueue(&pool, &message("pending@example.com"))
+            .await
+            .unwrap();
+
+        let Json(response) = get_email_outbox(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(EmailOutboxQuery {
+                status: Some(EmailOutboxStatus::Pending),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.entries.total, 1);
+        assert_eq!(response.entries.data[0].to_email, "pending@example.com");
+        assert_eq!(response.entries.data[0].status, EmailOutboxStatus::Pending);
+
+        // 本文はレスポンスに含めない
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("secret-token"));
+    }
+}
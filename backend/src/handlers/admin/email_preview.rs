@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, State},
+    response::Html,
+};
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    email::templates::{render_email, EmailContext, EmailTemplate, Expiry},
+    error::AppError,
+    models::common::ErrorResponse,
+};
+
+/// プレビューできるメールの種類（パスに使う名前）
+const TEMPLATES: [(&str, EmailTemplate); 5] = [
+    ("verification", EmailTemplate::Verification),
+    ("password-reset", EmailTemplate::PasswordReset),
+    ("magic-link", EmailTemplate::MagicLink),
+    ("email-change-confirm", EmailTemplate::EmailChangeConfirm),
+    ("email-change-notice", EmailTemplate::EmailChangeNotice),
+];
+
+/// メールのHTMLの本文をプレビューする
+///
+/// 見本の宛先・ユーザー名・リンクで描画したHTMLの本文を返します。送信はしません。
+/// テンプレートの名前は `verification`・`password-reset`・`magic-link`・`email-change-confirm`・`email-change-notice` です。
+#[utoipa::path(
+    get,
+    path = "/api/admin/email-preview/{template}",
+    params(
+        ("template" = String, Path, description = "メールの種類（例: password-reset）")
+    ),
+    responses(
+        (status = 200, description = "Rendered HTML body of the email", content_type = "text/html"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Unknown template", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn preview_email(
+    State(config): State<Arc<Config>>,
+    Path(name): Path<String>,
+) -> Result<Html<String>, AppError> {
+    let template = TEMPLATES
+        .iter()
+        .find(|(template_name, _)| *template_name == name)
+        .map(|(_, template)| *template)
+        .ok_or(AppError::NotFound)?;
+
+    let action_url = format!("{}/preview/{}", config.frontend_url, name);
+    let message = render_email(
+        template,
+        &EmailContext {
+            to: "preview@example.com",
+            username: "preview_user",
+            action_url: &action_url,
+            expires_in: Expiry::Hours(24),
+        },
+    )?;
+
+    Ok(Html(message.html_body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    #[tokio::test]
+    async fn test_すべてのテンプレートを見本の値で描画する() {
+        // すべてのテンプレートが見本の値で描画できることを確認
+        let config = Arc::new(test_config());
+        for (name, _) in TEMPLATES {
+            let Html(html) = preview_email(State(config.clone()), Path(name.to_string()))
+                .await
+                .unwrap();
+            assert!(html.contains("preview_user"), "{}", name);
+            assert!(
+                html.contains(&format!("http://localhost:3000/preview/{}", name)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_存在しないテンプレートは404() {
+        // 存在しないテンプレート名を指定すると404になることを確認
+        let result =
+            preview_email(State(Arc::new(test_config())), Path("unknown".to_string())).await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    email::EmailSender,
+    error::AppError,
+    models::{auth::MessageResponse, common::ErrorResponse, User},
+    utils::{
+        audit_log, email_sender::send_password_reset_email, password_reset,
+        token_generator::TokenGenerator,
+    },
+};
+
+/// ユーザーにパスワードの再設定を必須にする
+///
+/// そのユーザーのリフレッシュトークンをすべて無効化し、通常と同じパスワード再設定のメールを送ります。
+/// 再設定が完了するまで、正しいパスワードでログインしてもトークンは発行されず `PASSWORD_RESET_REQUIRED` を返します。
+/// アカウントの乗っ取りが疑われる場合に使います。
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/force-password-reset",
+    params(
+        ("id" = Uuid, Path, description = "パスワードの再設定を必須にするユーザーのID")
+    ),
+    responses(
+        (status = 200, description = "Password reset required and email sent", body = MessageResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn force_password_reset(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Arc<dyn EmailSender>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    Path(user_id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<MessageResponse>, AppError> {
+    // 再設定の必須化・トークンの無効化・監査ログの記録は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    let user = password_reset::require_reset(&mut tx, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // 既存のセッションはすべて終了させる
+    let revoked = sqlx::query(
+        "UPDATE refresh_tokens SET revoked = true WHERE user_id = $1 AND revoked = false",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let reset_token =
+        password_reset::create_reset_token(&config, tokens.as_ref(), user_id, &mut tx).await?;
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::USER_FORCE_PASSWORD_RESET,
+        "user",
+        Some(user_id),
+        &json!({ "revoked_sessions": revoked }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    send_password_reset_email(&config, mailer.as_ref(), &user, &reset_token).await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        user_id = %user_id,
+        revoked_sessions = revoked,
+        "Password reset forced by admin"
+    );
+
+    Ok(Json(MessageResponse {
+        message: "Password reset required. A reset email has been sent to the user.".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::mock::MockSender,
+        handlers::auth::{login::login, reset_password::reset_password},
+        models::auth::{LoginRequest, ResetPasswordRequest},
+        test_utils::{
+            create_test_user, set_test_user_password, test_config, test_jwt, test_tokens,
+        },
+        utils::token_generator::RandomTokenGenerator,
+    };
+    use axum::http::HeaderMap;
+
+    async fn force(pool: &PgPool, mailer: &Arc<MockSender>, admin: &User, user_id: Uuid) {
+        let _ = force_password_reset(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Path(user_id),
+            Extension(admin.clone()),
+        )
+        .await
+        .unwrap();
+    }
+
+    // 何度もログインするため、リフレッシュトークンは毎回異なる値で発行する
+    async fn try_login(pool: &PgPool, user: &User, password: &str) -> Result<(), AppError> {
+        login(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(Arc::new(RandomTokenGenerator) as Arc<dyn TokenGenerator>),
+            HeaderMap::new(),
+            Json(LoginRequest {
+                email: user.email.clone(),
+                password: password.to_string(),
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn active_refresh_tokens(pool: &PgPool, user_id: Uuid) -> i64 {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_再設定が完了するまでパスワードでログインできない(
+        pool: PgPool,
+    ) {
+        // 再設定が完了するまでパスワードでログインできないことを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        try_login(&pool, &user, "password123").await.unwrap();
+        assert_eq!(active_refresh_tokens(&pool, user.id).await, 1);
+
+        let mailer = Arc::new(MockSender::new());
+        force(&pool, &mailer, &admin, user.id).await;
+
+        // セッションは無効化し、通常と同じ再設定のメールを送る
+        assert_eq!(active_refresh_tokens(&pool, user.id).await, 0);
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+        let reset_token = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT password_reset_token FROM users WHERE id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(sent[0].html_body.contains(&reset_token));
+
+        let audit_logs = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM admin_audit_logs WHERE action = $1 AND target_id = $2",
+        )
+        .bind(audit_log::actions::USER_FORCE_PASSWORD_RESET)
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(audit_logs, 1);
+
+        // 正しいパスワードでもトークンは発行しない（誤ったパスワードは通常どおり401）
+        assert!(matches!(
+            try_login(&pool, &user, "password123").await,
+            Err(AppError::PasswordResetRequired)
+        ));
+        assert!(matches!(
+            try_login(&pool, &user, "wrong-password").await,
+            Err(AppError::Unauthorized(_))
+        ));
+        assert_eq!(active_refresh_tokens(&pool, user.id).await, 0);
+
+        // 再設定すると解除され、新しいパスワードでログインできる
+        reset_password(
+            State(pool.clone()),
+            Path(reset_token),
+            Json(ResetPasswordRequest {
+                new_password: "new-password456".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(!password_reset::is_reset_required(&pool, user.id)
+            .await
+            .unwrap());
+        try_login(&pool, &user, "new-password456").await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーは404(pool: PgPool) {
+        // 存在しないユーザーのパスワードの再設定は404になることを確認
+        let admin = create_test_user(&pool, true).await;
+        let mailer = Arc::new(MockSender::new());
+
+        let result = force_password_reset(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Path(Uuid::new_v4()),
+            Extension(admin),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+        assert!(mailer.sent().is_empty());
+    }
+}
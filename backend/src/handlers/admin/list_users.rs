@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        users::{AdminUserListResponse, AdminUserQuery, AdminUserResponse},
+    },
+    pagination::{AdminListings, Pagination},
+};
+
+/// ユーザーを新しい順に一覧する
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    params(Pagination<AdminListings>, AdminUserQuery),
+    responses(
+        (status = 200, description = "Users", body = AdminUserListResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_users(
+    State(pool): State<PgPool>,
+    pagination: Pagination<AdminListings>,
+    Query(query): Query<AdminUserQuery>,
+) -> Result<Json<AdminUserListResponse>, AppError> {
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM users WHERE $1::user_role IS NULL OR role = $1",
+    )
+    .bind(query.role)
+    .fetch_one(&pool)
+    .await?;
+
+    let users = sqlx::query_as::<_, AdminUserResponse>(
+        r#"
+        SELECT id, username, email, display_name, role, email_verified, created_at
+        FROM users
+        WHERE $1::user_role IS NULL OR role = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(query.role)
+    .bind(limit as i64)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(AdminUserListResponse {
+        users: PaginatedResponse::new(users, total as u64, page, limit),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ContentLimits, PageSize},
+        models::UserRole,
+        test_utils::{create_test_user, test_config},
+    };
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<AdminListings> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
+
+    #[sqlx::test]
+    async fn test_ユーザーをページごとに取得できる(pool: PgPool) {
+        // ユーザーの一覧をページごとに取得できることを確認
+        for _ in 0..3 {
+            create_test_user(&pool, true).await;
+        }
+
+        let Json(first) = list_users(
+            State(pool.clone()),
+            page_of(Some(1), Some(2)),
+            Query(Default::default()),
+        )
+        .await
+        .unwrap();
+        let Json(second) = list_users(
+            State(pool.clone()),
+            page_of(Some(2), Some(2)),
+            Query(Default::default()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(first.users.total, 3);
+        assert_eq!(first.users.total_pages, 2);
+        assert_eq!(first.users.data.len(), 2);
+        assert_eq!(second.users.data.len(), 1);
+        assert!(first
+            .users
+            .data
+            .iter()
+            .all(|user| user.id != second.users.data[0].id));
+    }
+
+    #[sqlx::test]
+    async fn test_権限で絞り込める(pool: PgPool) {
+        // 権限を指定するとそのユーザーだけに絞り込まれることを確認
+        let moderator = create_test_user(&pool, true).await;
+        create_test_user(&pool, true).await;
+        sqlx::query("UPDATE users SET role = 'moderator' WHERE id = $1")
+            .bind(moderator.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let Json(response) = list_users(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(AdminUserQuery {
+                role: Some(UserRole::Moderator),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.users.total, 1);
+        assert_eq!(response.users.data[0].id, moderator.id);
+        assert_eq!(response.users.data[0].role, UserRole::Moderator);
+    }
+
+    #[sqlx::test]
+    async fn test_上限を超えるlimitは設定の上限に切り詰められる(pool: PgPool) {
+        // 上限を超えるlimitはエラーにせず、設定された上限に切り詰められることを確認
+        let limits = ContentLimits {
+            admin_listings: PageSize {
+                default: 5,
+                max: 10,
+            },
+            ..ContentLimits::default()
+        };
+
+        let Json(clamped) = list_users(
+            State(pool.clone()),
+            Pagination::new(None, Some(101), None, &limits),
+            Query(Default::default()),
+        )
+        .await
+        .unwrap();
+        let Json(default) = list_users(
+            State(pool),
+            Pagination::new(None, None, None, &limits),
+            Query(Default::default()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(clamped.users.limit, 10);
+        assert_eq!(default.users.limit, 5);
+    }
+}
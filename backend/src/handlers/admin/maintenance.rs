@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        maintenance::{CredentialReport, PurgeTokensResponse},
+        User,
+    },
+    utils::{audit_log, credentials, token_purge},
+};
+
+/// 期限切れのトークンを削除する
+///
+/// 定期実行しているジョブと同じ処理をすぐに実行し、削除した件数を返します。
+#[utoipa::path(
+    post,
+    path = "/api/admin/maintenance/purge-tokens",
+    responses(
+        (status = 200, description = "Expired tokens purged", body = PurgeTokensResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn purge_tokens(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<PurgeTokensResponse>, AppError> {
+    let result = token_purge::purge_expired(&pool, Utc::now()).await?;
+
+    audit_log::record(
+        &pool,
+        admin.id,
+        audit_log::actions::TOKENS_PURGE,
+        "maintenance",
+        None,
+        &json!(result),
+    )
+    .await?;
+
+    Ok(Json(result))
+}
+
+/// パスワードハッシュの形式ごとのアカウント数を取得する
+///
+/// 旧形式のアカウントが残っているかを確認するために使います。
+/// 旧形式のアカウントは `migrate-credentials` コマンドで移行し、残りは次のログイン時にハッシュを作り直します。
+#[utoipa::path(
+    get,
+    path = "/api/admin/maintenance/credentials",
+    responses(
+        (status = 200, description = "Number of accounts per password hash version", body = CredentialReport),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_credential_report(
+    State(pool): State<PgPool>,
+) -> Result<Json<CredentialReport>, AppError> {
+    Ok(Json(credentials::report(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+
+    #[sqlx::test]
+    async fn test_削除した件数を返し監査ログに記録する(pool: PgPool) {
+        // 削除したトークンの件数を返し、監査ログに記録することを確認
+        let admin = create_test_user(&pool, true).await;
+        sqlx::query(
+            "INSERT INTO refresh_tokens (user_id, token_hash, expires_at) VALUES ($1, 'old', NOW() - INTERVAL '60 days')",
+        )
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let Json(result) = purge_tokens(State(pool.clone()), Extension(admin.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(result.refresh_tokens, 1);
+        let action: String =
+            sqlx::query_scalar("SELECT action FROM admin_audit_logs WHERE actor_id = $1")
+                .bind(admin.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(action, audit_log::actions::TOKENS_PURGE);
+    }
+
+    #[sqlx::test]
+    async fn test_形式ごとのアカウント数を返す(pool: PgPool) {
+        // パスワードハッシュの形式ごとのアカウント数が返されることを確認
+        create_test_user(&pool, true).await;
+        let legacy = create_test_user(&pool, true).await;
+        let pending = create_test_user(&pool, true).await;
+        sqlx::query("UPDATE user_credentials SET hash_version = 1 WHERE user_id = ANY($1)")
+            .bind(vec![legacy.id, pending.id])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE user_credentials SET needs_rehash = true WHERE user_id = $1")
+            .bind(pending.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let Json(report) = get_credential_report(State(pool.clone())).await.unwrap();
+
+        assert_eq!(
+            report,
+            CredentialReport {
+                hash_version: crate::auth::password::HASH_VERSION,
+                current: 1,
+                legacy: 1,
+                pending_rehash: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        tags::{Tag, TagMergeResponse, TagResponse},
+        User,
+    },
+    utils::{
+        audit_log,
+        tags::{record_redirect, redirect_reasons, thread_count},
+    },
+};
+
+/// タグを別のタグに統合する
+///
+/// 統合元のスレッドを統合先のタグに付け替えてから統合元を削除します。
+/// 統合元のスラッグ（とそれを指していた旧スラッグ）は統合先へ転送されます。
+#[utoipa::path(
+    post,
+    path = "/api/admin/tags/{slug}/merge-into/{target}",
+    params(
+        ("slug" = String, Path, description = "統合元のタグのスラッグ"),
+        ("target" = String, Path, description = "統合先のタグのスラッグ")
+    ),
+    responses(
+        (status = 200, description = "Tags merged", body = TagMergeResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn merge_tags(
+    State(pool): State<PgPool>,
+    Path((slug, target_slug)): Path<(String, String)>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<TagMergeResponse>, AppError> {
+    if slug == target_slug {
+        return Err(AppError::BadRequest(
+            "Cannot merge a tag into itself".to_string(),
+        ));
+    }
+
+    // 付け替え・転送先の記録・削除・監査ログは同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    // 同時に逆向きの統合が走ってもデッドロックしないよう、ID順にロックする
+    let tags =
+        sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE slug = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(vec![slug.clone(), target_slug.clone()])
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let source = tags
+        .iter()
+        .find(|tag| tag.slug == slug)
+        .cloned()
+        .ok_or(AppError::NotFound)?;
+    let target = tags
+        .into_iter()
+        .find(|tag| tag.slug == target_slug)
+        .ok_or(AppError::NotFound)?;
+
+    // 両方のタグが付いているスレッドは統合先の行をそのまま残す
+    let moved_threads = sqlx::query(
+        r#"
+        INSERT INTO thread_tags (thread_id, tag_id, created_at)
+        SELECT thread_id, $2, created_at
+        FROM thread_tags
+        WHERE tag_id = $1
+        ON CONFLICT (thread_id, tag_id) DO NOTHING
+        "#,
+    )
+    .bind(source.id)
+    .bind(target.id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // 統合元を指していた旧スラッグも統合先へ向け直し、転送が連鎖しないようにする
+    sqlx::query("UPDATE tag_slug_redirects SET tag_id = $2 WHERE tag_id = $1")
+        .bind(source.id)
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await?;
+
+    record_redirect(
+        &mut *tx,
+        &source.slug,
+        target.id,
+        redirect_reasons::MERGE,
+        admin.id,
+    )
+    .await?;
+
+    // 残っている統合元の thread_tags はカスケードで削除される
+    sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let target =
+        sqlx::query_as::<_, Tag>("UPDATE tags SET updated_at = NOW() WHERE id = $1 RETURNING *")
+            .bind(target.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::TAG_MERGE,
+        "tag",
+        Some(target.id),
+        &json!({
+            "source_id": source.id,
+            "source_slug": source.slug,
+            "source_name": source.name,
+            "target_slug": target.slug,
+            "moved_threads": moved_threads,
+        }),
+    )
+    .await?;
+
+    let count = thread_count(&mut *tx, target.id).await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        source_id = %source.id,
+        target_id = %target.id,
+        moved_threads,
+        "Tags merged by admin"
+    );
+
+    Ok(Json(TagMergeResponse {
+        tag: TagResponse::new(target, count),
+        moved_threads,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::{admin::rename_tag::rename_tag, tags::detail::get_tag},
+        models::tags::RenameTagRequest,
+        test_utils::{create_test_tag, create_test_thread, create_test_user, tag_test_thread},
+    };
+    use axum::http::{header, StatusCode};
+    use uuid::Uuid;
+
+    fn merge_path(slug: &str, target: &str) -> Path<(String, String)> {
+        Path((slug.to_string(), target.to_string()))
+    }
+
+    #[sqlx::test]
+    async fn test_両方のタグが付いたスレッドは重複せずに統合される(
+        pool: PgPool,
+    ) {
+        // 統合元だけ・統合先だけ・両方のスレッドがすべて統合先に1行ずつ残ることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        let source = create_test_tag(&pool, "Rustlang").await;
+        let target = create_test_tag(&pool, "Rust").await;
+
+        let both = create_test_thread(&pool, user.id, "Both", "Content").await;
+        let source_only = create_test_thread(&pool, user.id, "Source", "Content").await;
+        let target_only = create_test_thread(&pool, user.id, "Target", "Content").await;
+        tag_test_thread(&pool, both, source.id).await;
+        tag_test_thread(&pool, both, target.id).await;
+        tag_test_thread(&pool, source_only, source.id).await;
+        tag_test_thread(&pool, target_only, target.id).await;
+
+        let Json(merged) = merge_tags(
+            State(pool.clone()),
+            merge_path("rustlang", "rust"),
+            Extension(admin.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.moved_threads, 1);
+        assert_eq!(merged.tag.id, target.id);
+        assert_eq!(merged.tag.thread_count, 3);
+
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT thread_id, tag_id FROM thread_tags ORDER BY thread_id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|(_, tag_id)| *tag_id == target.id));
+
+        let source_exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM tags WHERE id = $1)")
+                .bind(source.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(!source_exists);
+
+        let response = get_tag(State(pool.clone()), Path("rustlang".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/api/tags/rust");
+
+        let action = sqlx::query_scalar::<_, String>(
+            "SELECT action FROM admin_audit_logs WHERE actor_id = $1",
+        )
+        .bind(admin.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(action, audit_log::actions::TAG_MERGE);
+    }
+
+    #[sqlx::test]
+    async fn test_名前変更前の旧スラッグも統合先へ転送される(pool: PgPool) {
+        // rust → rust-lang に名前変更した後で統合しても、rust は統合先を直接指すことを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+        create_test_tag(&pool, "Programming").await;
+
+        let _ = rename_tag(
+            State(pool.clone()),
+            Path("rust".to_string()),
+            Extension(admin.clone()),
+            Json(RenameTagRequest {
+                name: "Rust Lang".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        let _ = merge_tags(
+            State(pool.clone()),
+            merge_path("rust-lang", "programming"),
+            Extension(admin),
+        )
+        .await
+        .unwrap();
+
+        for old_slug in ["rust", "rust-lang"] {
+            let response = get_tag(State(pool.clone()), Path(old_slug.to_string()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+            assert_eq!(
+                response.headers()[header::LOCATION],
+                "/api/tags/programming"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_同じタグには統合できない(pool: PgPool) {
+        // 同じタグを統合先に指定するとバリデーションエラーになることを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+
+        let result = merge_tags(
+            State(pool.clone()),
+            merge_path("rust", "rust"),
+            Extension(admin),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないタグは統合できない(pool: PgPool) {
+        // 存在しないタグは統合できず404になることを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+
+        let result = merge_tags(
+            State(pool.clone()),
+            merge_path("rust", "missing"),
+            Extension(admin),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
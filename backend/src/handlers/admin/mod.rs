@@ -0,0 +1,15 @@
+pub mod audit_log;
+pub mod ban_user;
+pub mod email_outbox;
+pub mod email_preview;
+pub mod force_password_reset;
+pub mod list_users;
+pub mod maintenance;
+pub mod merge_tags;
+pub mod meta;
+pub mod mute_user;
+pub mod remove_comment;
+pub mod rename_tag;
+pub mod reports;
+pub mod restore;
+pub mod tag_rules;
@@ -0,0 +1,245 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        moderation::{MuteUserRequest, UserMute, UserMuteResponse},
+        User,
+    },
+    utils::{audit_log, db_error},
+};
+
+/// ユーザーを一定期間ミュートする
+///
+/// ミュート中のユーザーは閲覧や投票はできますが、スレッドやコメントを投稿できません。
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/mute",
+    params(
+        ("id" = Uuid, Path, description = "ミュートするユーザーのID")
+    ),
+    request_body = MuteUserRequest,
+    responses(
+        (status = 201, description = "User muted", body = UserMuteResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn mute_user(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+    Json(payload): Json<MuteUserRequest>,
+) -> Result<(StatusCode, Json<UserMuteResponse>), AppError> {
+    payload.validate()?;
+
+    let user_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
+
+    if !user_exists {
+        return Err(AppError::NotFound);
+    }
+
+    let expires_at = Utc::now() + Duration::minutes(payload.duration_minutes);
+
+    // ミュートと監査ログの記録は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    let mute = sqlx::query_as::<_, UserMute>(
+        r#"
+        INSERT INTO user_mutes (user_id, muted_by, reason, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(admin.id)
+    .bind(&payload.reason)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::USER_MUTE,
+        "user",
+        Some(user_id),
+        &json!({
+            "mute_id": mute.id,
+            "expires_at": mute.expires_at,
+            "reason": mute.reason,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        user_id = %user_id,
+        mute_id = %mute.id,
+        expires_at = %mute.expires_at,
+        reason = ?mute.reason,
+        "User muted by admin"
+    );
+
+    Ok((StatusCode::CREATED, Json(UserMuteResponse::from(mute))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::{comments::create::create_comment, threads::create::create_thread},
+        models::{comments::CreateCommentRequest, threads::CreateThreadRequest},
+        test_utils::{
+            create_test_thread, create_test_user, noop_notifier, test_state, test_tokens,
+        },
+    };
+
+    fn mute_request(duration_minutes: i64) -> MuteUserRequest {
+        MuteUserRequest {
+            duration_minutes,
+            reason: Some("spam".to_string()),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_ミュートしたユーザーは投稿できない(pool: PgPool) {
+        // ミュート後はスレッド・コメントの作成がUSER_MUTEDで拒否されることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, admin.id, "Thread", "Content").await;
+
+        let (status, Json(mute)) = mute_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin.clone()),
+            Json(mute_request(60)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(mute.user_id, user.id);
+        assert_eq!(mute.muted_by, Some(admin.id));
+
+        let state = test_state(&pool);
+        let thread_result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config.clone()),
+            Extension(user.clone()),
+            Json(CreateThreadRequest {
+                title: "Muted".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+        assert!(matches!(thread_result, Err(AppError::UserMuted(_))));
+
+        let comment_result = create_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(state.config),
+            State(test_tokens()),
+            Path(thread_id),
+            Extension(user.clone()),
+            Json(CreateCommentRequest {
+                content: "Muted".to_string(),
+                parent_id: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+        match comment_result {
+            Err(AppError::UserMuted(expires_at)) => assert_eq!(expires_at, mute.expires_at),
+            other => panic!("Expected UserMuted, got {:?}", other.err()),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れのミュートでは投稿できる(pool: PgPool) {
+        // 期限が過ぎたミュートはジョブ無しで自動的に無効になることを確認
+        let user = create_test_user(&pool, true).await;
+        sqlx::query(
+            "INSERT INTO user_mutes (user_id, expires_at) VALUES ($1, NOW() - INTERVAL '1 minute')",
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(CreateThreadRequest {
+                title: "After mute".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーはミュートできない(pool: PgPool) {
+        // 存在しないユーザーはミュートできないことを確認
+        let admin = create_test_user(&pool, true).await;
+
+        let result = mute_user(
+            State(pool.clone()),
+            Path(Uuid::new_v4()),
+            Extension(admin),
+            Json(mute_request(60)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[sqlx::test]
+    async fn test_不正な期間はバリデーションエラーになる(pool: PgPool) {
+        // 不正なミュート期間はバリデーションエラーになることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+
+        let result = mute_user(
+            State(pool.clone()),
+            Path(user.id),
+            Extension(admin),
+            Json(mute_request(0)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,407 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{comments::RemoveCommentQuery, common::ErrorResponse, User},
+    side_effects::{Notification, Notifier, SideEffects},
+    utils::audit_log,
+};
+
+/// 投稿ルールのページのURL（非表示にしたことを投稿者に知らせる通知に含める）
+fn rules_url(config: &Config) -> String {
+    format!("{}/rules", config.frontend_url)
+}
+
+/// モデレーターがコメントを非表示にする
+///
+/// 投稿者による削除とは異なり、コメントは `[removed]` として表示され、返信はそのまま残ります。
+/// 本文は確認のためデータベースに残し、投稿者には理由の分類と投稿ルールへのリンクを通知します。
+/// 通知は `notifications` に非表示と同じトランザクションで記録し、`Notifier` にも送ります。
+#[utoipa::path(
+    delete,
+    path = "/api/admin/comments/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Comment ID"),
+        RemoveCommentQuery
+    ),
+    responses(
+        (status = 204, description = "Comment removed. `X-Degraded: true` is set when sending the live notification event failed"),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Moderator privileges required", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+        (status = 409, description = "Comment is already removed", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn remove_comment(
+    State(pool): State<PgPool>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<Uuid>,
+    Extension(moderator): Extension<User>,
+    Query(query): Query<RemoveCommentQuery>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    // 非表示と監査ログ・投稿者への通知の記録は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    let removed = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"
+        UPDATE comments
+        SET removed_by_moderator = true, removal_reason = $2
+        WHERE id = $1 AND deleted_at IS NULL AND NOT removed_by_moderator
+        RETURNING user_id, thread_id
+        "#,
+    )
+    .bind(id)
+    .bind(query.reason.as_str())
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((author_id, thread_id)) = removed else {
+        // 投稿者が削除済みのコメントは存在しないものとして扱う
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        return Err(if exists {
+            AppError::Conflict("Comment is already removed".to_string())
+        } else {
+            AppError::NotFound
+        });
+    };
+
+    audit_log::record(
+        &mut *tx,
+        moderator.id,
+        audit_log::actions::COMMENT_REMOVE,
+        "comment",
+        Some(id),
+        &json!({
+            "thread_id": thread_id,
+            "author_id": author_id,
+            "reason": query.reason.as_str(),
+        }),
+    )
+    .await?;
+
+    let rules_url = rules_url(&config);
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, kind, thread_id, comment_id, removal_reason, rules_url)
+        VALUES ($1, 'comment_removed', $2, $3, $4, $5)
+        "#,
+    )
+    .bind(author_id)
+    .bind(thread_id)
+    .bind(id)
+    .bind(query.reason.as_str())
+    .bind(&rules_url)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        comment_id = %id,
+        moderator_id = %moderator.id,
+        reason = query.reason.as_str(),
+        "Comment removed by moderator"
+    );
+
+    // 通知先へのイベントに失敗しても非表示は取り消さない
+    let mut side_effects = SideEffects::new(notifier);
+    side_effects
+        .notify(Notification::CommentRemoved {
+            comment_id: id,
+            thread_id,
+            user_id: author_id,
+            reason: query.reason,
+            rules_url,
+        })
+        .await;
+
+    Ok((StatusCode::NO_CONTENT, side_effects.headers()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{comments::CommentRemovalReason, UserRole},
+        side_effects::DEGRADED_HEADER,
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user,
+            fakes::{FailingNotifier, RecordingNotifier},
+            test_config,
+        },
+    };
+
+    async fn remove(
+        pool: &PgPool,
+        notifier: Arc<dyn Notifier>,
+        id: Uuid,
+        moderator: &User,
+        reason: CommentRemovalReason,
+    ) -> Result<(StatusCode, HeaderMap), AppError> {
+        remove_comment(
+            State(pool.clone()),
+            State(notifier),
+            State(Arc::new(test_config())),
+            Path(id),
+            Extension(moderator.clone()),
+            Query(RemoveCommentQuery { reason }),
+        )
+        .await
+    }
+
+    /// 投稿者への通知の行（種類・コメント・理由・投稿ルールのURL）
+    async fn removal_notifications(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Vec<(String, Option<Uuid>, Option<String>, Option<String>)> {
+        sqlx::query_as(
+            r#"
+            SELECT kind, comment_id, removal_reason, rules_url
+            FROM notifications
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    fn moderator(user: User) -> User {
+        User {
+            role: UserRole::Moderator,
+            ..user
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_非表示にしたコメントは本文を残して監査ログに記録される(
+        pool: PgPool,
+    ) {
+        // 非表示にしたコメントは本文を残したまま監査ログに記録されることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = moderator(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "Spam", None).await;
+        let reply_id =
+            create_test_comment(&pool, moderator.id, thread_id, "Reply", Some(comment_id)).await;
+        let notifier = Arc::new(RecordingNotifier::default());
+
+        let (status, headers) = remove(
+            &pool,
+            notifier.clone(),
+            comment_id,
+            &moderator,
+            CommentRemovalReason::Spam,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(headers.get(DEGRADED_HEADER).is_none());
+
+        let (content, removed, reason): (String, bool, Option<String>) = sqlx::query_as(
+            "SELECT content, removed_by_moderator, removal_reason FROM comments WHERE id = $1",
+        )
+        .bind(comment_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(content, "Spam");
+        assert!(removed);
+        assert_eq!(reason.as_deref(), Some("spam"));
+
+        // 返信は残る
+        let replies: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE id = $1")
+            .bind(reply_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(replies, 1);
+
+        let log: (Uuid, String, Option<Uuid>, String, String) = sqlx::query_as(
+            r#"
+            SELECT actor_id, action, target_id, details->>'reason', details->>'author_id'
+            FROM admin_audit_logs
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            log,
+            (
+                moderator.id,
+                audit_log::actions::COMMENT_REMOVE.to_string(),
+                Some(comment_id),
+                "spam".to_string(),
+                author.id.to_string()
+            )
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_投稿者に理由とルールへのリンクが通知される(pool: PgPool) {
+        // 非表示にしたコメントの投稿者に理由とルールへのリンクが通知されることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = moderator(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "Off", None).await;
+        let notifier = Arc::new(RecordingNotifier::default());
+
+        remove(
+            &pool,
+            notifier.clone(),
+            comment_id,
+            &moderator,
+            CommentRemovalReason::OffTopic,
+        )
+        .await
+        .unwrap();
+
+        let notifications = notifier.notifications();
+        assert_eq!(notifications.len(), 1);
+        match &notifications[0] {
+            Notification::CommentRemoved {
+                comment_id: notified_id,
+                thread_id: notified_thread_id,
+                user_id,
+                reason,
+                rules_url,
+            } => {
+                assert_eq!(*notified_id, comment_id);
+                assert_eq!(*notified_thread_id, thread_id);
+                assert_eq!(*user_id, author.id);
+                assert_eq!(*reason, CommentRemovalReason::OffTopic);
+                assert_eq!(rules_url, "http://localhost:3000/rules");
+            }
+            other => panic!("Expected CommentRemoved, got {:?}", other),
+        }
+
+        assert_eq!(
+            removal_notifications(&pool, author.id).await,
+            vec![(
+                "comment_removed".to_string(),
+                Some(comment_id),
+                Some("off_topic".to_string()),
+                Some("http://localhost:3000/rules".to_string())
+            )]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_通知に失敗しても非表示にする(pool: PgPool) {
+        // 通知に失敗してもコメントは非表示になることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = moderator(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "Abuse", None).await;
+
+        let (status, headers) = remove(
+            &pool,
+            Arc::new(FailingNotifier::default()),
+            comment_id,
+            &moderator,
+            CommentRemovalReason::Abuse,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(headers[DEGRADED_HEADER], "true");
+        let removed: bool =
+            sqlx::query_scalar("SELECT removed_by_moderator FROM comments WHERE id = $1")
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(removed);
+
+        // 通知先へのイベントに失敗しても、アプリ内の通知は残る
+        assert_eq!(removal_notifications(&pool, author.id).await.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_非表示済み_削除済み_存在しないコメント(pool: PgPool) {
+        // 非表示済み・削除済み・存在しないコメントはそれぞれのエラーになることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = moderator(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "Spam", None).await;
+        let notifier = Arc::new(RecordingNotifier::default());
+
+        remove(
+            &pool,
+            notifier.clone(),
+            comment_id,
+            &moderator,
+            CommentRemovalReason::Spam,
+        )
+        .await
+        .unwrap();
+        let result = remove(
+            &pool,
+            notifier.clone(),
+            comment_id,
+            &moderator,
+            CommentRemovalReason::Other,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+
+        let deleted_id = create_test_comment(&pool, author.id, thread_id, "Gone", None).await;
+        sqlx::query("UPDATE comments SET deleted_at = NOW(), content = '' WHERE id = $1")
+            .bind(deleted_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let result = remove(
+            &pool,
+            notifier.clone(),
+            deleted_id,
+            &moderator,
+            CommentRemovalReason::Spam,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let result = remove(
+            &pool,
+            notifier.clone(),
+            Uuid::new_v4(),
+            &moderator,
+            CommentRemovalReason::Spam,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        // 失敗した操作は通知も監査ログも残さない
+        assert_eq!(notifier.notifications().len(), 1);
+        assert_eq!(removal_notifications(&pool, author.id).await.len(), 1);
+        let logs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admin_audit_logs")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(logs, 1);
+    }
+}
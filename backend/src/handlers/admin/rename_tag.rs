@@ -0,0 +1,261 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        tags::{RenameTagRequest, Tag, TagResponse},
+        User,
+    },
+    utils::{
+        audit_log, db_error,
+        tags::{record_redirect, redirect_reasons, slugify, thread_count},
+    },
+};
+
+/// タグの名前を変更する
+///
+/// スラッグは新しい名前から再生成され、旧スラッグは `GET /api/tags/{slug}` で新しいスラッグへ転送されます。
+#[utoipa::path(
+    put,
+    path = "/api/admin/tags/{slug}",
+    params(
+        ("slug" = String, Path, description = "名前を変更するタグのスラッグ")
+    ),
+    request_body = RenameTagRequest,
+    responses(
+        (status = 200, description = "Tag renamed", body = TagResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse),
+        (status = 409, description = "Another tag already uses the new slug", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn rename_tag(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Extension(admin): Extension<User>,
+    Json(payload): Json<RenameTagRequest>,
+) -> Result<Json<TagResponse>, AppError> {
+    payload.validate()?;
+
+    let name = payload.name.trim();
+    let new_slug = slugify(name);
+    if new_slug.is_empty() {
+        return Err(AppError::BadRequest(
+            "Tag name must contain at least one letter or digit".to_string(),
+        ));
+    }
+
+    // 名前変更・転送先の記録・監査ログは同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE slug = $1 FOR UPDATE")
+        .bind(&slug)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if new_slug != tag.slug {
+        // 以前の旧スラッグに戻す場合は、その転送を取り消して現在のスラッグとして使う
+        sqlx::query("DELETE FROM tag_slug_redirects WHERE old_slug = $1")
+            .bind(&new_slug)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let renamed = sqlx::query_as::<_, Tag>(
+        r#"
+        UPDATE tags
+        SET name = $1, slug = $2, updated_at = NOW()
+        WHERE id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(&new_slug)
+    .bind(tag.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    if renamed.slug != tag.slug {
+        record_redirect(
+            &mut *tx,
+            &tag.slug,
+            tag.id,
+            redirect_reasons::RENAME,
+            admin.id,
+        )
+        .await?;
+    }
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::TAG_RENAME,
+        "tag",
+        Some(tag.id),
+        &json!({
+            "from_name": tag.name,
+            "to_name": renamed.name,
+            "from_slug": tag.slug,
+            "to_slug": renamed.slug,
+        }),
+    )
+    .await?;
+
+    let count = thread_count(&mut *tx, renamed.id).await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        admin_id = %admin.id,
+        tag_id = %renamed.id,
+        from_slug = %tag.slug,
+        to_slug = %renamed.slug,
+        "Tag renamed by admin"
+    );
+
+    Ok(Json(TagResponse::new(renamed, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::tags::detail::get_tag,
+        test_utils::{create_test_tag, create_test_user},
+    };
+    use axum::http::{header, StatusCode};
+
+    fn rename_request(name: &str) -> Json<RenameTagRequest> {
+        Json(RenameTagRequest {
+            name: name.to_string(),
+        })
+    }
+
+    #[sqlx::test]
+    async fn test_名前変更後は旧スラッグが新しいスラッグへ転送される(
+        pool: PgPool,
+    ) {
+        // スラッグが再生成され、旧スラッグは308で新しいスラッグを指すことを確認
+        let admin = create_test_user(&pool, true).await;
+        let tag = create_test_tag(&pool, "Rust").await;
+
+        let Json(renamed) = rename_tag(
+            State(pool.clone()),
+            Path("rust".to_string()),
+            Extension(admin.clone()),
+            rename_request("Rust Lang"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(renamed.id, tag.id);
+        assert_eq!(renamed.name, "Rust Lang");
+        assert_eq!(renamed.slug, "rust-lang");
+
+        let response = get_tag(State(pool.clone()), Path("rust".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/api/tags/rust-lang");
+
+        let response = get_tag(State(pool.clone()), Path("rust-lang".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (action, target_id) = sqlx::query_as::<_, (String, Option<uuid::Uuid>)>(
+            "SELECT action, target_id FROM admin_audit_logs WHERE actor_id = $1",
+        )
+        .bind(admin.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(action, audit_log::actions::TAG_RENAME);
+        assert_eq!(target_id, Some(tag.id));
+    }
+
+    #[sqlx::test]
+    async fn test_元の名前に戻すと旧スラッグの転送は取り消される(
+        pool: PgPool,
+    ) {
+        // 元の名前に戻すと旧スラッグの転送が取り消されることを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+
+        for (slug, name) in [("rust", "Rust Lang"), ("rust-lang", "Rust")] {
+            let _ = rename_tag(
+                State(pool.clone()),
+                Path(slug.to_string()),
+                Extension(admin.clone()),
+                rename_request(name),
+            )
+            .await
+            .unwrap();
+        }
+
+        let response = get_tag(State(pool.clone()), Path("rust".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get_tag(State(pool.clone()), Path("rust-lang".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/api/tags/rust");
+    }
+
+    #[sqlx::test]
+    async fn test_他のタグと同じスラッグには変更できない(pool: PgPool) {
+        // 他のタグと同じスラッグになる名前には変更できないことを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+        create_test_tag(&pool, "Go").await;
+
+        let result = rename_tag(
+            State(pool.clone()),
+            Path("go".to_string()),
+            Extension(admin),
+            rename_request("RUST"),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(AppError::UniqueViolation {
+                code: "TAG_SLUG_TAKEN",
+                ..
+            })
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_記号だけの名前には変更できない(pool: PgPool) {
+        // スラッグを作れない記号だけの名前には変更できないことを確認
+        let admin = create_test_user(&pool, true).await;
+        create_test_tag(&pool, "Rust").await;
+
+        let result = rename_tag(
+            State(pool.clone()),
+            Path("rust".to_string()),
+            Extension(admin),
+            rename_request("!!!"),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,335 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        reports::{ReportListResponse, ReportQuery, ReportResponse, ResolveReportRequest},
+        User,
+    },
+    pagination::{AdminListings, Pagination},
+    utils::reports,
+};
+
+/// 通報を古い順に一覧する
+///
+/// モデレーター・管理者が使えます。
+#[utoipa::path(
+    get,
+    path = "/api/admin/reports",
+    params(Pagination<AdminListings>, ReportQuery),
+    responses(
+        (status = 200, description = "Reports", body = ReportListResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Moderator privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_reports(
+    State(pool): State<PgPool>,
+    pagination: Pagination<AdminListings>,
+    Query(query): Query<ReportQuery>,
+) -> Result<Json<ReportListResponse>, AppError> {
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = reports::count(&pool, query.status).await?;
+    let data = reports::search(&pool, query.status, limit as i64, offset)
+        .await?
+        .into_iter()
+        .map(ReportResponse::from)
+        .collect();
+
+    Ok(Json(ReportListResponse {
+        reports: PaginatedResponse::new(data, total as u64, page, limit),
+    }))
+}
+
+/// 通報を対応済みにする
+///
+/// モデレーター・管理者が使えます。対応済みの通報は再度対応済みにできません。
+/// 同じ対象を通報したユーザー全員に対応結果（`outcome`）を通知します（対応したモデレーターは通知に含めません）。
+#[utoipa::path(
+    post,
+    path = "/api/admin/reports/{id}/resolve",
+    params(
+        ("id" = Uuid, Path, description = "通報のID")
+    ),
+    request_body = ResolveReportRequest,
+    responses(
+        (status = 200, description = "Report resolved", body = ReportResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Moderator privileges required", body = ErrorResponse),
+        (status = 404, description = "Report not found", body = ErrorResponse),
+        (status = 409, description = "Report is already resolved", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn resolve_report(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(moderator): Extension<User>,
+    Json(payload): Json<ResolveReportRequest>,
+) -> Result<Json<ReportResponse>, AppError> {
+    let report = reports::resolve(&pool, id, moderator.id, payload.outcome).await?;
+
+    tracing::info!(
+        report_id = %report.id,
+        moderator_id = %moderator.id,
+        outcome = payload.outcome.as_str(),
+        "Report resolved"
+    );
+
+    Ok(Json(ReportResponse::from(report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::reports::{
+            CreateReportRequest, ReportOutcome, ReportReason, ReportStatus, ReportTargetType,
+        },
+        test_utils::{create_test_thread, create_test_user, test_config},
+    };
+
+    fn actioned() -> Json<ResolveReportRequest> {
+        Json(ResolveReportRequest {
+            outcome: ReportOutcome::Actioned,
+        })
+    }
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<AdminListings> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
+
+    async fn report_thread(pool: &PgPool, reporter_id: Uuid, thread_id: Uuid) -> Uuid {
+        let request = CreateReportRequest {
+            reason: ReportReason::Spam,
+            detail: None,
+        };
+        reports::create(
+            pool,
+            reporter_id,
+            ReportTargetType::Thread,
+            thread_id,
+            &request,
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    #[sqlx::test]
+    async fn test_通報を対応済みにすると未対応の一覧から外れる(pool: PgPool) {
+        // 対応済みにした通報は未対応の一覧から外れることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+        let mut report_ids = Vec::new();
+        for _ in 0..2 {
+            let reporter = create_test_user(&pool, true).await;
+            report_ids.push(report_thread(&pool, reporter.id, thread_id).await);
+        }
+
+        let Json(resolved) = resolve_report(
+            State(pool.clone()),
+            Path(report_ids[0]),
+            Extension(moderator.clone()),
+            actioned(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved.status, ReportStatus::Resolved);
+        assert_eq!(resolved.outcome, Some(ReportOutcome::Actioned));
+        assert_eq!(resolved.resolved_by, Some(moderator.id));
+        assert!(resolved.resolved_at.is_some());
+
+        let Json(open) = list_reports(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(ReportQuery {
+                status: Some(ReportStatus::Open),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(open.reports.total, 1);
+        assert_eq!(open.reports.data[0].id, report_ids[1]);
+
+        let Json(all) = list_reports(
+            State(pool.clone()),
+            page_of(None, None),
+            Query(ReportQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(all.reports.total, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_対応済みの通報は再度対応済みにできない(pool: PgPool) {
+        // 対応済みの通報をもう一度対応済みにすると409になることを確認
+        let author = create_test_user(&pool, true).await;
+        let reporter = create_test_user(&pool, true).await;
+        let moderator = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+        let report_id = report_thread(&pool, reporter.id, thread_id).await;
+
+        let _ = resolve_report(
+            State(pool.clone()),
+            Path(report_id),
+            Extension(moderator.clone()),
+            actioned(),
+        )
+        .await
+        .unwrap();
+
+        let again = resolve_report(
+            State(pool.clone()),
+            Path(report_id),
+            Extension(moderator.clone()),
+            actioned(),
+        )
+        .await;
+        assert!(matches!(again, Err(AppError::Conflict(_))));
+
+        let missing = resolve_report(
+            State(pool.clone()),
+            Path(Uuid::new_v4()),
+            Extension(moderator),
+            actioned(),
+        )
+        .await;
+        assert!(matches!(missing, Err(AppError::NotFound)));
+    }
+
+    // (通知先のユーザーID, 結果) を通知先のID順に返す
+    async fn outcome_notifications(pool: &PgPool, target_id: Uuid) -> Vec<(Uuid, String)> {
+        sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT user_id, report_outcome FROM notifications
+            WHERE kind = 'report_resolved' AND report_target_id = $1
+            ORDER BY user_id
+            "#,
+        )
+        .bind(target_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_対応すると同じ対象を通報したユーザー全員に結果を通知する(
+        pool: PgPool,
+    ) {
+        // 通報に対応すると、同じ対象を通報したユーザー全員に結果が通知されることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+        let other_thread_id = create_test_thread(&pool, author.id, "Other", "Content").await;
+        let mut reporters = Vec::new();
+        let mut report_ids = Vec::new();
+        for _ in 0..3 {
+            let reporter = create_test_user(&pool, true).await;
+            report_ids.push(report_thread(&pool, reporter.id, thread_id).await);
+            reporters.push(reporter.id);
+        }
+        // 別の対象の通報者には通知しない
+        let other_reporter = create_test_user(&pool, true).await;
+        report_thread(&pool, other_reporter.id, other_thread_id).await;
+
+        let _ = resolve_report(
+            State(pool.clone()),
+            Path(report_ids[0]),
+            Extension(moderator.clone()),
+            Json(ResolveReportRequest {
+                outcome: ReportOutcome::NoViolation,
+            }),
+        )
+        .await
+        .unwrap();
+
+        reporters.sort();
+        let expected: Vec<(Uuid, String)> = reporters
+            .iter()
+            .map(|id| (*id, "no_violation".to_string()))
+            .collect();
+        assert_eq!(outcome_notifications(&pool, thread_id).await, expected);
+        assert!(outcome_notifications(&pool, other_thread_id)
+            .await
+            .is_empty());
+
+        // モデレーターには通知しない
+        let moderator_notifications =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM notifications WHERE user_id = $1")
+                .bind(moderator.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(moderator_notifications, 0);
+
+        // 同じ対象の残りの通報を対応しても、同じユーザーに二度通知しない
+        let _ = resolve_report(
+            State(pool.clone()),
+            Path(report_ids[1]),
+            Extension(moderator),
+            actioned(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome_notifications(&pool, thread_id).await, expected);
+    }
+
+    #[sqlx::test]
+    async fn test_同じ対象の結果は同じユーザーに一度だけ通知する(
+        pool: PgPool,
+    ) {
+        // 同じ対象の通報の結果は同じユーザーに一度だけ通知されることを確認
+        let author = create_test_user(&pool, true).await;
+        let reporter = create_test_user(&pool, true).await;
+        let moderator = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+        let report_id = report_thread(&pool, reporter.id, thread_id).await;
+
+        let _ = resolve_report(
+            State(pool.clone()),
+            Path(report_id),
+            Extension(moderator),
+            actioned(),
+        )
+        .await
+        .unwrap();
+        // 通知をもう一度追加しようとしても重複しない
+        let again = reports::notify_reporters(
+            &pool,
+            ReportTargetType::Thread.as_str(),
+            thread_id,
+            ReportOutcome::Actioned,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(again, 0);
+        assert_eq!(
+            outcome_notifications(&pool, thread_id).await,
+            vec![(reporter.id, "actioned".to_string())]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+};
+use serde_json::json;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    events::{self, CommentRestored},
+    models::{common::ErrorResponse, User},
+    utils::{audit_log, moderation_log},
+};
+
+/// 削除したスレッドを復元する
+///
+/// スレッドの削除はデータを残さないため、モデレーターが削除したスレッドは 410（`CONTENT_NOT_RESTORABLE`）を返します。
+/// 削除されていないスレッドは 409、削除の記録も無いスレッドは 404 です。
+#[utoipa::path(
+    post,
+    path = "/api/admin/threads/{id}/restore",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse),
+        (status = 409, description = "Thread is not deleted", body = ErrorResponse),
+        (status = 410, description = "Thread was permanently deleted (CONTENT_NOT_RESTORABLE)", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn restore_thread(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+    if exists {
+        return Err(AppError::Conflict("Thread is not deleted".to_string()));
+    }
+
+    Err(deleted_error(&pool, moderation_log::actions::THREAD_DELETE, id).await?)
+}
+
+/// モデレーターが非表示にしたコメントを復元する
+///
+/// 本文を残している非表示（`DELETE /api/admin/comments/{id}`）のみ元に戻せます。
+/// 投稿者が削除したトムストーン（本文を消している）や、行ごと削除されたコメントは 410（`CONTENT_NOT_RESTORABLE`）を返します。
+#[utoipa::path(
+    post,
+    path = "/api/admin/comments/{id}/restore",
+    params(
+        ("id" = Uuid, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 204, description = "Comment restored"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+        (status = 409, description = "Comment is not removed", body = ErrorResponse),
+        (status = 410, description = "Comment was permanently deleted (CONTENT_NOT_RESTORABLE)", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn restore_comment(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    // 復元と監査ログの記録は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+
+    // 監査ログには非表示にした理由（更新前の値）を残す
+    let restored = sqlx::query_as::<_, (Uuid, Uuid, Option<String>)>(
+        r#"
+        UPDATE comments c
+        SET removed_by_moderator = false, removal_reason = NULL
+        FROM comments old
+        WHERE c.id = $1 AND old.id = c.id
+          AND c.removed_by_moderator AND c.deleted_at IS NULL
+        RETURNING c.user_id, c.thread_id, old.removal_reason
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((author_id, thread_id, reason)) = restored else {
+        let deleted = sqlx::query_scalar::<_, bool>(
+            "SELECT deleted_at IS NOT NULL FROM comments WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        return Err(match deleted {
+            // 投稿者が削除したトムストーンは本文を消しているため戻せない
+            Some(true) => AppError::ContentNotRestorable,
+            Some(false) => AppError::Conflict("Comment is not removed".to_string()),
+            None => deleted_error(&mut *tx, moderation_log::actions::COMMENT_DELETE, id).await?,
+        });
+    };
+
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::COMMENT_RESTORE,
+        "comment",
+        Some(id),
+        &json!({
+            "thread_id": thread_id,
+            "author_id": author_id,
+            "reason": reason,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(comment_id = %id, admin_id = %admin.id, "Comment restored by admin");
+    events::emit(&CommentRestored {
+        comment_id: id,
+        thread_id,
+        actor_id: admin.id,
+    });
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 行が残っていない投稿のエラー
+///
+/// モデレーションログに削除の記録があれば 410、無ければ存在しなかったものとして 404 を返します。
+async fn deleted_error<'e>(
+    executor: impl PgExecutor<'e>,
+    action: &str,
+    id: Uuid,
+) -> Result<AppError, AppError> {
+    let deleted = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM moderation_log WHERE action = $1 AND target_id = $2)",
+    )
+    .bind(action)
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(if deleted {
+        AppError::ContentNotRestorable
+    } else {
+        AppError::NotFound
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::capture::CapturedEvents,
+        handlers::{admin::remove_comment::remove_comment, comments::delete::delete_comment},
+        models::{
+            comments::{CommentRemovalReason, RemoveCommentQuery},
+            moderation::ModerationDeleteQuery,
+            UserRole,
+        },
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user, noop_notifier, test_config,
+        },
+    };
+    use axum::extract::Query;
+    use std::sync::Arc;
+
+    fn admin(user: User) -> User {
+        User {
+            role: UserRole::Admin,
+            ..user
+        }
+    }
+
+    async fn restore(pool: &PgPool, id: Uuid, admin: &User) -> Result<StatusCode, AppError> {
+        restore_comment(State(pool.clone()), Path(id), Extension(admin.clone())).await
+    }
+
+    #[sqlx::test]
+    async fn test_非表示にしたコメントを復元し監査ログとイベントを残す(
+        pool: PgPool,
+    ) {
+        // 非表示にしたコメントを復元し、監査ログとイベントを残すことを確認
+        let author = create_test_user(&pool, true).await;
+        let admin = admin(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "Oops", None).await;
+        remove_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            Path(comment_id),
+            Extension(admin.clone()),
+            Query(RemoveCommentQuery {
+                reason: CommentRemovalReason::Spam,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let (captured, _guard) = CapturedEvents::start();
+        let status = restore(&pool, comment_id, &admin).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (content, removed, reason): (String, bool, Option<String>) = sqlx::query_as(
+            "SELECT content, removed_by_moderator, removal_reason FROM comments WHERE id = $1",
+        )
+        .bind(comment_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((content.as_str(), removed, reason), ("Oops", false, None));
+
+        let log: (String, Option<Uuid>, String) = sqlx::query_as(
+            r#"
+            SELECT action, target_id, details->>'reason'
+            FROM admin_audit_logs
+            WHERE action = $1
+            "#,
+        )
+        .bind(audit_log::actions::COMMENT_RESTORE)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            log,
+            (
+                audit_log::actions::COMMENT_RESTORE.to_string(),
+                Some(comment_id),
+                "spam".to_string()
+            )
+        );
+
+        let events = captured.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["kind"], "comment_restored");
+        assert_eq!(events[0]["payload"]["comment_id"], comment_id.to_string());
+
+        // 非表示になっていないコメントは復元できない
+        assert!(matches!(
+            restore(&pool, comment_id, &admin).await,
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_データが残っていない投稿は410になる(pool: PgPool) {
+        // データが残っていない投稿の復元は410になることを確認
+        let author = create_test_user(&pool, true).await;
+        let admin = admin(create_test_user(&pool, true).await);
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+
+        // 投稿者が削除したトムストーン（本文は消えている）
+        let tombstone_id = create_test_comment(&pool, author.id, thread_id, "Parent", None).await;
+        create_test_comment(&pool, admin.id, thread_id, "Reply", Some(tombstone_id)).await;
+        delete_comment(
+            State(pool.clone()),
+            Path(tombstone_id),
+            Extension(author.clone()),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+        let result = restore(&pool, tombstone_id, &admin).await;
+        assert!(matches!(result, Err(AppError::ContentNotRestorable)));
+        assert_eq!(result.unwrap_err().code(), Some("CONTENT_NOT_RESTORABLE"));
+
+        // モデレーターが行ごと削除したコメント
+        let deleted_id = create_test_comment(&pool, author.id, thread_id, "Gone", None).await;
+        delete_comment(
+            State(pool.clone()),
+            Path(deleted_id),
+            Extension(admin.clone()),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            restore(&pool, deleted_id, &admin).await,
+            Err(AppError::ContentNotRestorable)
+        ));
+
+        // モデレーターが削除したスレッド
+        sqlx::query("DELETE FROM threads WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        moderation_log::record(
+            &pool,
+            admin.id,
+            moderation_log::actions::THREAD_DELETE,
+            "thread",
+            thread_id,
+            author.id,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            restore_thread(State(pool.clone()), Path(thread_id)).await,
+            Err(AppError::ContentNotRestorable)
+        ));
+
+        // 削除の記録が無いものは存在しない扱い
+        assert!(matches!(
+            restore(&pool, Uuid::new_v4(), &admin).await,
+            Err(AppError::NotFound)
+        ));
+        assert!(matches!(
+            restore_thread(State(pool.clone()), Path(Uuid::new_v4())).await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
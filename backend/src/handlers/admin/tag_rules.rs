@@ -0,0 +1,514 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        auth::MessageResponse,
+        common::ErrorResponse,
+        tag_rules::{
+            CreateTagRuleRequest, TagRuleListResponse, TagRuleResponse, UpdateTagRuleRequest,
+        },
+        User,
+    },
+    utils::{
+        audit_log, db_error,
+        tag_rules::{self, TagRuleCache},
+    },
+};
+
+// 前後の空白を除いたパターン（空白だけのパターンはすべてのスレッドに一致してしまうため拒否する）
+fn normalize_pattern(pattern: &str) -> Result<&str, AppError> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err(AppError::BadRequest(
+            "Pattern must not be blank".to_string(),
+        ));
+    }
+    Ok(pattern)
+}
+
+/// 自動タグ付けルールの一覧
+///
+/// 無効なルールも含め、作成順（タグの上限に達した場合に優先される順）に返します。
+#[utoipa::path(
+    get,
+    path = "/api/admin/tag-rules",
+    responses(
+        (status = 200, description = "Tag rules", body = TagRuleListResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_tag_rules(
+    State(pool): State<PgPool>,
+) -> Result<Json<TagRuleListResponse>, AppError> {
+    let rules = tag_rules::list(&pool, false).await?;
+
+    Ok(Json(TagRuleListResponse {
+        rules: rules.into_iter().map(TagRuleResponse::from).collect(),
+    }))
+}
+
+/// 自動タグ付けルールを作成する
+///
+/// 以降に作成されるスレッドのうち、指定した項目にパターンを含むもの（大文字・小文字は区別しない）にタグが付きます。
+#[utoipa::path(
+    post,
+    path = "/api/admin/tag-rules",
+    request_body = CreateTagRuleRequest,
+    responses(
+        (status = 201, description = "Tag rule created", body = TagRuleResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_tag_rule(
+    State(pool): State<PgPool>,
+    State(cache): State<Arc<TagRuleCache>>,
+    Extension(admin): Extension<User>,
+    Json(payload): Json<CreateTagRuleRequest>,
+) -> Result<(StatusCode, Json<TagRuleResponse>), AppError> {
+    payload.validate()?;
+    let pattern = normalize_pattern(&payload.pattern)?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO tag_rules (pattern, match_field, tag_id, enabled, created_by)
+        VALUES ($1, $2, $3, COALESCE($4, true), $5)
+        RETURNING id
+        "#,
+    )
+    .bind(pattern)
+    .bind(payload.match_field.as_str())
+    .bind(payload.tag_id)
+    .bind(payload.enabled)
+    .bind(admin.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    let rule = tag_rules::find(&mut *tx, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // 監査ログ
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::TAG_RULE_CREATE,
+        "tag_rule",
+        Some(rule.id),
+        &json!({
+            "pattern": rule.pattern,
+            "match_field": rule.match_field,
+            "tag_id": rule.tag_id,
+            "enabled": rule.enabled,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    cache.invalidate();
+
+    Ok((StatusCode::CREATED, Json(TagRuleResponse::from(rule))))
+}
+
+/// 自動タグ付けルールを更新する
+///
+/// 指定された項目のみ更新します。既に作成されたスレッドのタグは変わりません。
+#[utoipa::path(
+    put,
+    path = "/api/admin/tag-rules/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ルールのID")
+    ),
+    request_body = UpdateTagRuleRequest,
+    responses(
+        (status = 200, description = "Tag rule updated", body = TagRuleResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Tag rule or tag not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_tag_rule(
+    State(pool): State<PgPool>,
+    State(cache): State<Arc<TagRuleCache>>,
+    Path(id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+    Json(payload): Json<UpdateTagRuleRequest>,
+) -> Result<Json<TagRuleResponse>, AppError> {
+    payload.validate()?;
+    let pattern = payload
+        .pattern
+        .as_deref()
+        .map(normalize_pattern)
+        .transpose()?;
+
+    let mut tx = pool.begin().await?;
+
+    let before = sqlx::query_as::<_, (String, String, Uuid, bool)>(
+        "SELECT pattern, match_field, tag_id, enabled FROM tag_rules WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    sqlx::query(
+        r#"
+        UPDATE tag_rules
+        SET
+            pattern = COALESCE($2, pattern),
+            match_field = COALESCE($3, match_field),
+            tag_id = COALESCE($4, tag_id),
+            enabled = COALESCE($5, enabled),
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(pattern)
+    .bind(payload.match_field.map(|field| field.as_str()))
+    .bind(payload.tag_id)
+    .bind(payload.enabled)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    let rule = tag_rules::find(&mut *tx, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // 監査ログ
+    let (from_pattern, from_match_field, from_tag_id, from_enabled) = before;
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::TAG_RULE_UPDATE,
+        "tag_rule",
+        Some(rule.id),
+        &json!({
+            "from": {
+                "pattern": from_pattern,
+                "match_field": from_match_field,
+                "tag_id": from_tag_id,
+                "enabled": from_enabled,
+            },
+            "to": {
+                "pattern": rule.pattern,
+                "match_field": rule.match_field,
+                "tag_id": rule.tag_id,
+                "enabled": rule.enabled,
+            },
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    cache.invalidate();
+
+    Ok(Json(TagRuleResponse::from(rule)))
+}
+
+/// 自動タグ付けルールを削除する
+///
+/// 既に作成されたスレッドのタグは変わりません。
+#[utoipa::path(
+    delete,
+    path = "/api/admin/tag-rules/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ルールのID")
+    ),
+    responses(
+        (status = 200, description = "Tag rule deleted", body = MessageResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Admin privileges required", body = ErrorResponse),
+        (status = 404, description = "Tag rule not found", body = ErrorResponse)
+    ),
+    tag = "admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_tag_rule(
+    State(pool): State<PgPool>,
+    State(cache): State<Arc<TagRuleCache>>,
+    Path(id): Path<Uuid>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<MessageResponse>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query_as::<_, (String, String, Uuid)>(
+        "DELETE FROM tag_rules WHERE id = $1 RETURNING pattern, match_field, tag_id",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    // 監査ログ
+    let (pattern, match_field, tag_id) = deleted;
+    audit_log::record(
+        &mut *tx,
+        admin.id,
+        audit_log::actions::TAG_RULE_DELETE,
+        "tag_rule",
+        Some(id),
+        &json!({
+            "pattern": pattern,
+            "match_field": match_field,
+            "tag_id": tag_id,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    cache.invalidate();
+
+    Ok(Json(MessageResponse {
+        message: "Tag rule deleted successfully".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::threads::create::create_thread,
+        models::{tag_rules::TagRuleField, threads::CreateThreadRequest},
+        state::AppState,
+        test_utils::{create_test_tag, create_test_user, test_state},
+    };
+
+    fn create_request(
+        pattern: &str,
+        field: TagRuleField,
+        tag_id: Uuid,
+    ) -> Json<CreateTagRuleRequest> {
+        Json(CreateTagRuleRequest {
+            pattern: pattern.to_string(),
+            match_field: field,
+            tag_id,
+            enabled: None,
+        })
+    }
+
+    fn update_request() -> UpdateTagRuleRequest {
+        UpdateTagRuleRequest {
+            pattern: None,
+            match_field: None,
+            tag_id: None,
+            enabled: None,
+        }
+    }
+
+    // スレッドを作成して付いたタグIDを返す
+    async fn create_thread_tags(state: &AppState, user: &User, title: &str) -> Vec<Uuid> {
+        let (_, _, Json(thread)) = create_thread(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            State(state.tag_rules.clone()),
+            State(state.notifier.clone()),
+            State(state.config.clone()),
+            Extension(user.clone()),
+            Json(CreateThreadRequest {
+                title: title.to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        sqlx::query_scalar::<_, Uuid>("SELECT tag_id FROM thread_tags WHERE thread_id = $1")
+            .bind(thread.id)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_ルールの変更は次のスレッド作成から反映される(pool: PgPool) {
+        // 作成・更新・削除のたびにキャッシュが読み込み直され、次のスレッド作成から反映されることを確認
+        let admin = create_test_user(&pool, true).await;
+        let user = create_test_user(&pool, true).await;
+        let rust = create_test_tag(&pool, "Rust").await;
+        let go = create_test_tag(&pool, "Go").await;
+        let state = test_state(&pool);
+
+        // ルールが無い状態でキャッシュを読み込んでおく
+        assert!(create_thread_tags(&state, &user, "Rust question")
+            .await
+            .is_empty());
+
+        let (status, Json(rule)) = create_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules.clone()),
+            Extension(admin.clone()),
+            create_request("rust", TagRuleField::Title, rust.id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(rule.tag_slug, "rust");
+        assert!(rule.enabled);
+        assert_eq!(
+            create_thread_tags(&state, &user, "Rust question").await,
+            vec![rust.id]
+        );
+
+        // 付けるタグを変更する
+        let _ = update_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules.clone()),
+            Path(rule.id),
+            Extension(admin.clone()),
+            Json(UpdateTagRuleRequest {
+                tag_id: Some(go.id),
+                ..update_request()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            create_thread_tags(&state, &user, "Rust question").await,
+            vec![go.id]
+        );
+
+        // 無効にする
+        let Json(disabled) = update_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules.clone()),
+            Path(rule.id),
+            Extension(admin.clone()),
+            Json(UpdateTagRuleRequest {
+                enabled: Some(false),
+                ..update_request()
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(!disabled.enabled);
+        assert_eq!(disabled.pattern, "rust");
+        assert!(create_thread_tags(&state, &user, "Rust question")
+            .await
+            .is_empty());
+
+        let _ = delete_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules.clone()),
+            Path(rule.id),
+            Extension(admin.clone()),
+        )
+        .await
+        .unwrap();
+        let Json(list) = list_tag_rules(State(pool.clone())).await.unwrap();
+        assert!(list.rules.is_empty());
+
+        let actions = sqlx::query_scalar::<_, String>(
+            "SELECT action FROM admin_audit_logs WHERE actor_id = $1 ORDER BY created_at",
+        )
+        .bind(admin.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                audit_log::actions::TAG_RULE_CREATE,
+                audit_log::actions::TAG_RULE_UPDATE,
+                audit_log::actions::TAG_RULE_UPDATE,
+                audit_log::actions::TAG_RULE_DELETE,
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないタグのルールは作成できない(pool: PgPool) {
+        // 存在しないタグを付けるルールは作成できないことを確認
+        let admin = create_test_user(&pool, true).await;
+        let state = test_state(&pool);
+
+        let result = create_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules),
+            Extension(admin),
+            create_request("rust", TagRuleField::Title, Uuid::new_v4()),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(AppError::ForeignKeyViolation("TAG_NOT_FOUND"))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_空白だけのパターンは拒否する(pool: PgPool) {
+        // 空白だけのパターンはバリデーションエラーになることを確認
+        let admin = create_test_user(&pool, true).await;
+        let tag = create_test_tag(&pool, "Rust").await;
+        let state = test_state(&pool);
+
+        let result = create_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules),
+            Extension(admin),
+            create_request("   ", TagRuleField::Content, tag.id),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないルールは更新削除できない(pool: PgPool) {
+        // 存在しないルールの更新・削除は404になることを確認
+        let admin = create_test_user(&pool, true).await;
+        let state = test_state(&pool);
+
+        let updated = update_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules.clone()),
+            Path(Uuid::new_v4()),
+            Extension(admin.clone()),
+            Json(update_request()),
+        )
+        .await;
+        assert!(matches!(updated, Err(AppError::NotFound)));
+
+        let deleted = delete_tag_rule(
+            State(pool.clone()),
+            State(state.tag_rules),
+            Path(Uuid::new_v4()),
+            Extension(admin),
+        )
+        .await;
+        assert!(matches!(deleted, Err(AppError::NotFound)));
+    }
+}
@@ -3,18 +3,24 @@ use axum::{
     Json,
 };
 use sqlx::PgPool;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     auth::password::{hash_password, verify_password},
     error::AppError,
     models::{
-        auth::{ChangePasswordRequest, MessageResponse},
+        auth::{ChangePasswordRequest, Claims, MessageResponse},
         common::ErrorResponse,
         User, UserCredentials,
     },
+    utils::credentials,
 };
 
+/// パスワードを変更する
+///
+/// 乗っ取られたセッションが残らないよう、変更と同時にリフレッシュトークンをすべて失効させます。
+/// `keep_current_session` を指定した場合は、変更を行ったセッションのみ残します。
 #[utoipa::path(
     post,
     path = "/api/auth/change-password",
@@ -33,6 +39,7 @@ use crate::{
 pub async fn change_password(
     State(pool): State<PgPool>,
     Extension(current_user): Extension<User>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<ChangePasswordRequest>,
 ) -> Result<Json<MessageResponse>, AppError> {
     // バリデーション実行
@@ -51,22 +58,34 @@ pub async fn change_password(
     }
 
     // 新しいパスワードをハッシュ化
-    let (password_hash, salt) = hash_password(&payload.new_password)?;
-
-    // パスワードを更新
+    let password_hash = hash_password(&payload.new_password)?;
+
+    // パスワードの更新とセッションの失効は同じトランザクションで行う
+    let mut tx = pool.begin().await?;
+    credentials::save_password_hash(&mut *tx, current_user.id, &password_hash).await?;
+
+    let current_session = payload
+        .keep_current_session
+        .then(|| {
+            claims
+                .sid
+                .as_deref()
+                .and_then(|sid| Uuid::parse_str(sid).ok())
+        })
+        .flatten();
     sqlx::query(
         r#"
-        UPDATE user_credentials 
-        SET password_hash = $1, salt = $2, updated_at = NOW() 
-        WHERE user_id = $3
+        UPDATE refresh_tokens SET revoked = true
+        WHERE user_id = $1 AND revoked = false AND ($2::uuid IS NULL OR id <> $2)
         "#,
     )
-    .bind(&password_hash)
-    .bind(&salt)
     .bind(current_user.id)
-    .execute(&pool)
+    .bind(current_session)
+    .execute(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     Ok(Json(MessageResponse {
         message: "パスワードが正常に変更されました".to_string(),
     }))
@@ -76,7 +95,112 @@ pub async fn change_password(
 mod tests {
 
     use super::*;
-    use crate::{auth::password::hash_password, test_utils::seed_test_user};
+    use crate::{
+        handlers::auth::{login::login, refresh_token::refresh_token},
+        models::auth::{AuthResponse, LoginRequest, RefreshTokenRequest},
+        test_utils::{
+            create_test_user, seed_test_user, set_test_user_password, test_config, test_jwt,
+            test_tokens,
+        },
+        utils::token_generator::{RandomTokenGenerator, TokenGenerator},
+    };
+    use axum::http::HeaderMap;
+    use std::sync::Arc;
+
+    // セッションを確認しないテスト用のクレーム（存在しないセッションを指す）
+    fn claims_for(user: &User) -> Claims {
+        test_jwt().claims(
+            &user.id.to_string(),
+            &user.username,
+            &user.email,
+            user.role,
+            &Uuid::new_v4().to_string(),
+            15,
+        )
+    }
+
+    // 同じテストで何度もログインするため、リフレッシュトークンは毎回異なる値で発行する
+    async fn login_as(pool: &PgPool, user: &User) -> AuthResponse {
+        let tokens: Arc<dyn TokenGenerator> = Arc::new(RandomTokenGenerator);
+        login(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens),
+            HeaderMap::new(),
+            Json(LoginRequest {
+                email: user.email.clone(),
+                password: "password123".to_string(),
+            }),
+        )
+        .await
+        .expect("Login should succeed")
+        .1
+         .0
+    }
+
+    async fn can_refresh(pool: &PgPool, session: &AuthResponse) -> bool {
+        refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: session.refresh_token.clone(),
+            })),
+        )
+        .await
+        .is_ok()
+    }
+
+    async fn change(pool: &PgPool, user: &User, session: &AuthResponse, keep_current: bool) {
+        let claims = test_jwt().decode(&session.access_token).unwrap();
+        let _ = change_password(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Extension(claims),
+            Json(ChangePasswordRequest {
+                current_password: "password123".to_string(),
+                new_password: "newpassword456".to_string(),
+                keep_current_session: keep_current,
+            }),
+        )
+        .await
+        .expect("Password change should succeed");
+    }
+
+    #[sqlx::test]
+    async fn test_パスワードを変更するとすべてのセッションが失効する(
+        pool: PgPool,
+    ) {
+        // 変更前のリフレッシュトークンではトークンを更新できないことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let current = login_as(&pool, &user).await;
+        let other = login_as(&pool, &user).await;
+
+        change(&pool, &user, &current, false).await;
+
+        assert!(!can_refresh(&pool, &current).await);
+        assert!(!can_refresh(&pool, &other).await);
+    }
+
+    #[sqlx::test]
+    async fn test_現在のセッションを残して他のセッションを失効させる(
+        pool: PgPool,
+    ) {
+        // keep_current_session を指定すると、変更を行ったセッションだけ残ることを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let current = login_as(&pool, &user).await;
+        let other = login_as(&pool, &user).await;
+
+        change(&pool, &user, &current, true).await;
+
+        assert!(!can_refresh(&pool, &other).await);
+        assert!(can_refresh(&pool, &current).await);
+    }
 
     #[sqlx::test]
     async fn test_change_password_success(pool: PgPool) {
@@ -92,25 +216,23 @@ mod tests {
 
         // 既存のパスワードをセットアップ
         let current_password = "password123";
-        let (password_hash, salt) = hash_password(current_password).unwrap();
-
-        // ユーザー認証情報を更新
-        sqlx::query("UPDATE user_credentials SET password_hash = $1, salt = $2 WHERE user_id = $3")
-            .bind(&password_hash)
-            .bind(&salt)
-            .bind(user_id)
-            .execute(&pool)
-            .await
-            .expect("Failed to update user credentials");
+        set_test_user_password(&pool, user_id, current_password).await;
 
         // リクエストを作成
         let request = ChangePasswordRequest {
             current_password: current_password.to_string(),
             new_password: "newpassword456".to_string(),
+            keep_current_session: false,
         };
 
         // ハンドラを呼び出し
-        let result = change_password(State(pool.clone()), Extension(user), Json(request)).await;
+        let result = change_password(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Extension(claims_for(&user)),
+            Json(request),
+        )
+        .await;
 
         // 結果を検証
         assert!(result.is_ok(), "Password change should succeed");
@@ -143,25 +265,23 @@ mod tests {
 
         // 既存のパスワードをセットアップ
         let current_password = "password123";
-        let (password_hash, salt) = hash_password(current_password).unwrap();
-
-        // ユーザー認証情報を更新
-        sqlx::query("UPDATE user_credentials SET password_hash = $1, salt = $2 WHERE user_id = $3")
-            .bind(&password_hash)
-            .bind(&salt)
-            .bind(user_id)
-            .execute(&pool)
-            .await
-            .expect("Failed to update user credentials");
+        set_test_user_password(&pool, user_id, current_password).await;
 
         // 間違ったパスワードでリクエストを作成
         let request = ChangePasswordRequest {
             current_password: "wrongpassword".to_string(),
             new_password: "newpassword456".to_string(),
+            keep_current_session: false,
         };
 
         // ハンドラを呼び出し
-        let result = change_password(State(pool.clone()), Extension(user), Json(request)).await;
+        let result = change_password(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Extension(claims_for(&user)),
+            Json(request),
+        )
+        .await;
 
         // 結果を検証 - エラーが発生するはず
         assert!(
@@ -190,25 +310,23 @@ mod tests {
 
         // 既存のパスワードをセットアップ
         let current_password = "password123";
-        let (password_hash, salt) = hash_password(current_password).unwrap();
-
-        // ユーザー認証情報を更新
-        sqlx::query("UPDATE user_credentials SET password_hash = $1, salt = $2 WHERE user_id = $3")
-            .bind(&password_hash)
-            .bind(&salt)
-            .bind(user_id)
-            .execute(&pool)
-            .await
-            .expect("Failed to update user credentials");
+        set_test_user_password(&pool, user_id, current_password).await;
 
         // 短すぎる新しいパスワードでリクエストを作成
         let request = ChangePasswordRequest {
             current_password: current_password.to_string(),
             new_password: "short".to_string(), // 8文字未満
+            keep_current_session: false,
         };
 
         // ハンドラを呼び出し
-        let result = change_password(State(pool.clone()), Extension(user), Json(request)).await;
+        let result = change_password(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Extension(claims_for(&user)),
+            Json(request),
+        )
+        .await;
 
         // 結果を検証 - バリデーションエラーが発生するはず
         assert!(
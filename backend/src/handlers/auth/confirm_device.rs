@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    auth::jwt::JwtService,
+    config::Config,
+    error::AppError,
+    models::{auth::AuthResponse, common::ErrorResponse, User},
+    utils::{auth_session, login_alerts, token_generator::TokenGenerator},
+};
+
+/// Confirm a new device
+///
+/// Consume the device confirmation token sent by email when NEW_DEVICE_CONFIRMATION is enabled,
+/// record the sign-in as an in-app notification and issue access and refresh tokens.
+#[utoipa::path(
+    post,
+    path = "/api/auth/confirm-device/{token}",
+    params(
+        ("token" = String, Path, description = "Device confirmation token")
+    ),
+    responses(
+        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 401, description = "Invalid or expired device confirmation", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn confirm_device(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let (user_id, device) = login_alerts::consume_device_confirmation(&pool, &token).await?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await?;
+
+    // 確認メールで端末の情報は知らせているため、ここではアプリ内の通知のみ追加する
+    login_alerts::record_sign_in_notification(&pool, user.id, &device).await?;
+
+    let (cookie_headers, response) =
+        auth_session::issue_session(&pool, &config, &jwt, tokens.as_ref(), user, &headers).await?;
+
+    Ok((cookie_headers, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_test_user, test_config, test_jwt, test_tokens},
+        utils::login_alerts::SignInDevice,
+    };
+    use axum::http::{header::USER_AGENT, HeaderValue};
+
+    fn chrome() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("Chrome"));
+        headers
+    }
+
+    async fn confirm(pool: &PgPool, token: String) -> Result<AuthResponse, AppError> {
+        let config = Config {
+            new_device_confirmation: true,
+            ..test_config()
+        };
+        confirm_device(
+            State(pool.clone()),
+            State(Arc::new(config)),
+            State(test_jwt()),
+            State(test_tokens()),
+            chrome(),
+            Path(token),
+        )
+        .await
+        .map(|(_, Json(response))| response)
+    }
+
+    #[sqlx::test]
+    async fn test_端末を確認するとログインし通知が残る(pool: PgPool) {
+        // 端末を確認するとログインでき、通知が残ることを確認
+        let user = create_test_user(&pool, true).await;
+        let device = SignInDevice::from_headers(&chrome());
+        let token = login_alerts::request_device_confirmation(
+            &pool,
+            &test_config(),
+            test_tokens().as_ref(),
+            &user,
+            &device,
+        )
+        .await
+        .unwrap();
+
+        let response = confirm(&pool, token.clone()).await.unwrap();
+        assert_eq!(response.user.id, user.id);
+        assert!(!response.access_token.is_empty());
+
+        let notified: Vec<Option<String>> = sqlx::query_scalar(
+            "SELECT user_agent FROM notifications WHERE user_id = $1 AND kind = 'new_sign_in'",
+        )
+        .bind(user.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(notified, vec![Some("Chrome".to_string())]);
+
+        // 確認した端末は以降のログインで新しい端末として扱わない
+        assert!(!login_alerts::is_new_device(&pool, user.id, &device)
+            .await
+            .unwrap());
+
+        // 同じトークンは一度しか使用できない
+        assert!(matches!(
+            confirm(&pool, token).await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れの確認トークンは使用できない(pool: PgPool) {
+        // 期限切れの確認トークンでは端末を確認できないことを確認
+        let user = create_test_user(&pool, true).await;
+        let token = login_alerts::request_device_confirmation(
+            &pool,
+            &test_config(),
+            test_tokens().as_ref(),
+            &user,
+            &SignInDevice::from_headers(&chrome()),
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "UPDATE device_confirmation_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            confirm(&pool, token).await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+}
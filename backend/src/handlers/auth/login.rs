@@ -1,17 +1,23 @@
-use axum::{extract::State, Json};
+use axum::{extract::State, http::HeaderMap, Json};
 use sqlx::PgPool;
+use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
-    auth::{jwt::create_jwt_token, password::verify_password},
+    auth::{jwt::JwtService, password::verify_password},
     config::Config,
     error::AppError,
     models::{
-        auth::{AuthResponse, LoginRequest, UserInfo},
+        auth::{AuthResponse, LoginRequest},
         common::ErrorResponse,
         User, UserCredentials,
     },
-    utils::{self, token_hash::hash_refresh_token},
+    utils::{
+        auth_session, credentials,
+        login_alerts::{self, SignInDevice},
+        password_reset,
+        token_generator::TokenGenerator,
+    },
 };
 
 #[utoipa::path(
@@ -21,21 +27,41 @@ use crate::{
     responses(
         (status = 200, description = "Login successful", body = AuthResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
-        (status = 401, description = "Invalid credentials", body = ErrorResponse)
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Email not verified (REQUIRE_VERIFIED_LOGIN), sign-in from a new device must be confirmed by email (NEW_DEVICE_CONFIRMATION), or an administrator required a password reset (PASSWORD_RESET_REQUIRED)", body = ErrorResponse),
+        (status = 429, description = "Too many login attempts", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn login(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, AppError> {
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let (cookie_headers, response) =
+        authenticate(&pool, &config, &jwt, tokens.as_ref(), &headers, payload).await?;
+
+    Ok((cookie_headers, Json(response)))
+}
+
+async fn authenticate(
+    pool: &PgPool,
+    config: &Config,
+    jwt: &JwtService,
+    tokens: &dyn TokenGenerator,
+    headers: &HeaderMap,
+    payload: LoginRequest,
+) -> Result<(HeaderMap, AuthResponse), AppError> {
     // Validate input
     payload.validate()?;
 
     // Find user by email
-    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(email) = LOWER($1)")
         .bind(&payload.email)
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await?
         .ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
 
@@ -43,7 +69,7 @@ pub async fn login(
     let credentials =
         sqlx::query_as::<_, UserCredentials>("SELECT * FROM user_credentials WHERE user_id = $1")
             .bind(user.id)
-            .fetch_optional(&pool)
+            .fetch_optional(pool)
             .await?
             .ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
 
@@ -52,46 +78,376 @@ pub async fn login(
         return Err(AppError::Unauthorized("Invalid credentials".to_string()));
     }
 
-    // Generate tokens
-    let config = Config::from_env()?;
-    let access_token = create_jwt_token(
-        &user.id.to_string(),
-        &user.username,
-        &user.email,
-        &config.jwt_secret,
-        15, // 15 minutes
-    )?;
-
-    let refresh_token = utils::generate_secure_token();
-    let refresh_token_hash = hash_refresh_token(&refresh_token);
-
-    // Store refresh token
-    sqlx::query(
-        r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2, NOW() + INTERVAL '7 days')
-        "#,
-    )
-    .bind(user.id)
-    .bind(&refresh_token_hash)
-    .execute(&pool)
-    .await?;
-
-    let response = AuthResponse {
-        access_token,
-        refresh_token,
-        token_type: "Bearer".to_string(),
-        expires_in: 900, // 15 minutes in seconds
-        user: UserInfo {
-            id: user.id,
-            username: user.username,
-            email: user.email,
-            display_name: user.display_name,
-            avatar_url: user.avatar_url,
-            email_verified: user.email_verified,
-            created_at: user.created_at,
-        },
+    // 旧形式のハッシュは平文のパスワードが分かるこの時点で作り直す（失敗してもログインは続ける）
+    if let Err(err) = credentials::rehash_if_needed(pool, &credentials, &payload.password).await {
+        tracing::warn!(user_id = %user.id, "Failed to rehash legacy password: {}", err);
+    }
+
+    // 管理者がパスワードの再設定を必須にした場合は、再設定が完了するまでトークンを発行しない
+    if password_reset::is_reset_required(pool, user.id).await? {
+        return Err(AppError::PasswordResetRequired);
+    }
+
+    // メール認証が必須の場合、未認証ユーザーにはトークンを発行しない
+    if config.require_verified_login && !user.email_verified {
+        return Err(AppError::EmailNotVerified);
+    }
+
+    // 過去のセッションに無い端末からのログインは本人に知らせる
+    let device = SignInDevice::from_headers(headers);
+    if login_alerts::is_new_device(pool, user.id, &device).await? {
+        // 厳格モードでは、メールのリンクで端末を確認するまでトークンを発行しない
+        if config.new_device_confirmation {
+            login_alerts::request_device_confirmation(pool, config, tokens, &user, &device).await?;
+            return Err(AppError::DeviceConfirmationRequired);
+        }
+        // 通知に失敗してもログインは続ける
+        if let Err(err) = login_alerts::notify_new_sign_in(pool, &user, &device).await {
+            tracing::warn!(user_id = %user.id, "Failed to notify new sign-in: {}", err);
+        }
+    }
+
+    auth_session::issue_session(pool, config, jwt, tokens, user, headers).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        create_test_user, set_test_user_password, test_config, test_config_with_verified_login,
+        test_jwt, test_tokens,
     };
+    use axum::http::{header::USER_AGENT, HeaderValue};
+
+    fn login_request(user: &User) -> LoginRequest {
+        LoginRequest {
+            email: user.email.clone(),
+            password: "password123".to_string(),
+        }
+    }
+
+    /// 同じIPアドレスから、指定したブラウザでアクセスした場合のヘッダー
+    fn device_headers(user_agent: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(user_agent));
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("203.0.113.1"));
+        headers
+    }
+
+    /// 指定したブラウザからログインする（同じテスト内では同じ `tokens` を渡し、トークンの重複を避ける）
+    async fn login_from(
+        pool: &PgPool,
+        config: &Config,
+        tokens: &dyn TokenGenerator,
+        user: &User,
+        user_agent: &'static str,
+    ) -> Result<(HeaderMap, AuthResponse), AppError> {
+        authenticate(
+            pool,
+            config,
+            &test_jwt(),
+            tokens,
+            &device_headers(user_agent),
+            login_request(user),
+        )
+        .await
+    }
+
+    /// 新しい端末からのログインの通知（User-Agent）
+    async fn sign_in_notifications(pool: &PgPool, user_id: uuid::Uuid) -> Vec<Option<String>> {
+        sqlx::query_scalar(
+            "SELECT user_agent FROM notifications WHERE user_id = $1 AND kind = 'new_sign_in'",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn outbox_subjects(pool: &PgPool, user: &User) -> Vec<String> {
+        sqlx::query_scalar("SELECT subject FROM email_outbox WHERE to_email = $1")
+            .bind(&user.email)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_未認証ユーザーはログインできない(
+        pool: PgPool,
+    ) {
+        // REQUIRE_VERIFIED_LOGINが有効な場合、未認証ユーザーにはトークンを発行しないことを確認
+        let user = create_test_user(&pool, false).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let result = authenticate(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            login_request(&user),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::EmailNotVerified)));
+
+        let token_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM refresh_tokens WHERE user_id = $1)",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!token_exists, "Refresh token should not be issued");
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_認証済みユーザーはログインできる(
+        pool: PgPool,
+    ) {
+        // REQUIRE_VERIFIED_LOGINが有効でも、認証済みユーザーはログインできることを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let (_, response) = authenticate(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            login_request(&user),
+        )
+        .await
+        .expect("verified user should be able to log in");
+
+        assert_eq!(response.user.id, user.id);
+        assert!(response.user.email_verified);
+    }
 
-    Ok(Json(response))
+    #[sqlx::test]
+    async fn test_メール認証任意_未認証ユーザーもログインできる(
+        pool: PgPool,
+    ) {
+        // REQUIRE_VERIFIED_LOGINが無効な場合は従来通り未認証ユーザーもログインできることを確認
+        let user = create_test_user(&pool, false).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let (_, response) = authenticate(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            login_request(&user),
+        )
+        .await
+        .expect("unverified user should be able to log in");
+
+        assert_eq!(response.user.id, user.id);
+        assert!(!response.user.email_verified);
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_パスワード誤りは認証エラーを優先する(
+        pool: PgPool,
+    ) {
+        // パスワードが誤っている場合はメール認証状態を明かさずに401を返すことを確認
+        let user = create_test_user(&pool, false).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let request = LoginRequest {
+            email: user.email.clone(),
+            password: "wrongpassword".to_string(),
+        };
+        let result = authenticate(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            request,
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_旧形式のハッシュはログイン時に作り直す(pool: PgPool) {
+        // 旧形式のパスワードハッシュはログイン時に現在の形式で作り直されることを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        sqlx::query(
+            "UPDATE user_credentials SET salt = 'legacy_salt', hash_version = 1 WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        authenticate(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            login_request(&user),
+        )
+        .await
+        .expect("legacy user should be able to log in");
+
+        let credentials = sqlx::query_as::<_, UserCredentials>(
+            "SELECT * FROM user_credentials WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            credentials.hash_version,
+            crate::auth::password::HASH_VERSION
+        );
+        assert_eq!(credentials.salt, None);
+        assert!(verify_password("password123", &credentials.password_hash).unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_初めてのログインは通知しない(pool: PgPool) {
+        // 比較する過去のセッションが無いため、初めてのログインは新しい端末として通知しないことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let tokens = test_tokens();
+
+        login_from(&pool, &test_config(), tokens.as_ref(), &user, "Firefox")
+            .await
+            .expect("first login should succeed");
+
+        assert!(sign_in_notifications(&pool, user.id).await.is_empty());
+        assert!(outbox_subjects(&pool, &user).await.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_同じ端末からのログインは通知しない(pool: PgPool) {
+        // 同じ端末からのログインは通知しないことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let config = test_config();
+        let tokens = test_tokens();
+
+        for _ in 0..2 {
+            login_from(&pool, &config, tokens.as_ref(), &user, "Firefox")
+                .await
+                .unwrap();
+        }
+
+        assert!(sign_in_notifications(&pool, user.id).await.is_empty());
+        assert!(outbox_subjects(&pool, &user).await.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_新しい端末からのログインはメールとアプリ内で通知する(
+        pool: PgPool,
+    ) {
+        // 新しい端末からのログインはメールとアプリ内の通知で知らせることを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let config = test_config();
+        let tokens = test_tokens();
+        login_from(&pool, &config, tokens.as_ref(), &user, "Firefox")
+            .await
+            .unwrap();
+
+        // 通知してもログインは成功する
+        let (_, response) = login_from(&pool, &config, tokens.as_ref(), &user, "Chrome")
+            .await
+            .unwrap();
+        assert_eq!(response.user.id, user.id);
+
+        assert_eq!(
+            sign_in_notifications(&pool, user.id).await,
+            vec![Some("Chrome".to_string())]
+        );
+        assert_eq!(
+            outbox_subjects(&pool, &user).await,
+            vec!["新しい端末からのログイン".to_string()]
+        );
+
+        // 一度ログインした端末は次から通知しない
+        login_from(&pool, &config, tokens.as_ref(), &user, "Chrome")
+            .await
+            .unwrap();
+        assert_eq!(sign_in_notifications(&pool, user.id).await.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_厳格モードでは新しい端末を確認するまでトークンを発行しない(
+        pool: PgPool,
+    ) {
+        // 厳格モードでは新しい端末を確認するまでトークンを発行しないことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let config = Config {
+            new_device_confirmation: true,
+            ..test_config()
+        };
+        let tokens = test_tokens();
+        login_from(&pool, &config, tokens.as_ref(), &user, "Firefox")
+            .await
+            .unwrap();
+
+        let result = login_from(&pool, &config, tokens.as_ref(), &user, "Chrome").await;
+        assert!(matches!(result, Err(AppError::DeviceConfirmationRequired)));
+
+        let sessions: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1")
+                .bind(user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(sessions, 1, "Refresh token should not be issued");
+        let pending: Option<String> = sqlx::query_scalar(
+            "SELECT user_agent FROM device_confirmation_tokens WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending.as_deref(), Some("Chrome"));
+        assert_eq!(
+            outbox_subjects(&pool, &user).await,
+            vec!["新しい端末からのログインの確認".to_string()]
+        );
+        // 確認が済むまではログインしていないため、アプリ内の通知は作成しない
+        assert!(sign_in_notifications(&pool, user.id).await.is_empty());
+
+        // ログインしたことのある端末は厳格モードでもそのままログインできる
+        login_from(&pool, &config, tokens.as_ref(), &user, "Firefox")
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_メールアドレスの大文字・小文字を区別せずにログインできる(
+        pool: PgPool,
+    ) {
+        // メールアドレスの大文字・小文字を区別せずにログインできることを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let result = authenticate(
+            &pool,
+            &test_config(),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            LoginRequest {
+                email: user.email.to_uppercase(),
+                password: "password123".to_string(),
+            },
+        )
+        .await;
+
+        assert!(result.is_ok(), "login should succeed: {:?}", result.err());
+    }
 }
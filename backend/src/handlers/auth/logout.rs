@@ -1,10 +1,20 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Extension, State},
+    http::HeaderMap,
+    Json,
+};
 use sqlx::PgPool;
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     error::AppError,
-    models::auth::{LogoutResponse, RefreshTokenRequest},
-    utils::token_hash::hash_refresh_token,
+    models::{
+        auth::{LogoutResponse, RefreshTokenRequest},
+        common::ErrorResponse,
+        User,
+    },
+    utils::{refresh_token_cookie, token_hash::hash_refresh_token},
 };
 
 #[utoipa::path(
@@ -20,18 +30,140 @@ use crate::{
 )]
 pub async fn logout(
     State(pool): State<PgPool>,
-    Json(payload): Json<RefreshTokenRequest>,
-) -> Result<Json<LogoutResponse>, AppError> {
-    // Hash the provided refresh token to find it in database
-    let token_hash = hash_refresh_token(&payload.refresh_token);
+    State(config): State<Arc<Config>>,
+    headers: HeaderMap,
+    payload: Option<Json<RefreshTokenRequest>>,
+) -> Result<(HeaderMap, Json<LogoutResponse>), AppError> {
+    // ボディに無い場合はCookieから読み取る
+    let presented_token = payload
+        .and_then(|Json(payload)| payload.refresh_token)
+        .or_else(|| refresh_token_cookie::read_refresh_token_cookie(&headers));
+
+    if let Some(presented_token) = presented_token {
+        // Hash the provided refresh token to find it in database
+        let token_hash = hash_refresh_token(&presented_token);
+
+        // Revoke refresh token
+        sqlx::query("UPDATE refresh_tokens SET revoked = true WHERE token_hash = $1")
+            .bind(&token_hash)
+            .execute(&pool)
+            .await?;
+    }
 
-    // Revoke refresh token
-    sqlx::query("UPDATE refresh_tokens SET revoked = true WHERE token_hash = $1")
-        .bind(&token_hash)
+    Ok((
+        refresh_token_cookie::clear_refresh_token(&config),
+        Json(LogoutResponse {
+            message: "Logged out successfully".to_string(),
+        }),
+    ))
+}
+
+/// すべてのセッションからログアウト
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout_all",
+    responses(
+        (status = 200, description = "All sessions revoked", body = LogoutResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "auth",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn logout_all(
+    State(pool): State<PgPool>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<LogoutResponse>, AppError> {
+    // ユーザーの全リフレッシュトークンを失効させる
+    sqlx::query("UPDATE refresh_tokens SET revoked = true WHERE user_id = $1 AND revoked = false")
+        .bind(current_user.id)
         .execute(&pool)
         .await?;
 
     Ok(Json(LogoutResponse {
-        message: "Logged out successfully".to_string(),
+        message: "Logged out from all sessions".to_string(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_user, test_config};
+
+    // テスト用のリフレッシュトークンを作成する
+    async fn insert_refresh_token(pool: &PgPool, user: &User, token: &str) {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, NOW() + INTERVAL '7 days')
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_refresh_token(token))
+        .execute(pool)
+        .await
+        .expect("Failed to create refresh token");
+    }
+
+    #[sqlx::test]
+    async fn test_すべてのセッションからログアウトできる(pool: PgPool) {
+        // 自分のリフレッシュトークンはすべて失効し、他人のものは残ることを確認
+        let user = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, &user, "token_a").await;
+        insert_refresh_token(&pool, &user, "token_b").await;
+        insert_refresh_token(&pool, &other, "token_c").await;
+
+        let result = logout_all(State(pool.clone()), Extension(user.clone())).await;
+        assert!(result.is_ok());
+
+        let active: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(active, 0);
+
+        let other_active: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(other.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(other_active, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_cookieのトークンでログアウトできる(pool: PgPool) {
+        // ボディが無い場合はCookieのリフレッシュトークンを失効させることを確認
+        let user = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, &user, "cookie_token").await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            axum::http::HeaderValue::from_static("refresh_token=cookie_token"),
+        );
+
+        let result = logout(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            headers,
+            None,
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let revoked: bool =
+            sqlx::query_scalar("SELECT revoked FROM refresh_tokens WHERE token_hash = $1")
+                .bind(hash_refresh_token("cookie_token"))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(revoked);
+    }
+}
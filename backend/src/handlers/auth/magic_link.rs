@@ -0,0 +1,269 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::{
+    auth::jwt::JwtService,
+    config::Config,
+    email::EmailSender,
+    error::AppError,
+    models::{
+        auth::{AuthResponse, MagicLinkRequest, MessageResponse},
+        common::ErrorResponse,
+        User,
+    },
+    utils::{
+        auth_session, email_sender::send_magic_link_email, magic_link,
+        token_generator::TokenGenerator,
+    },
+};
+
+const MAGIC_LINK_SENT_MESSAGE: &str =
+    "ログイン用のメールを送信しました。メールに記載されたリンクからログインしてください。";
+
+/// Request a magic link
+///
+/// Send an email with a single-use login link. Always succeeds to prevent account enumeration.
+#[utoipa::path(
+    post,
+    path = "/api/auth/magic-link",
+    request_body = MagicLinkRequest,
+    responses(
+        (status = 200, description = "Magic link email sent if the account exists", body = MessageResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 429, description = "Too many magic link requests", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn request_magic_link(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Arc<dyn EmailSender>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    Json(payload): Json<MagicLinkRequest>,
+) -> Result<Json<MessageResponse>, AppError> {
+    payload.validate()?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(email) = LOWER($1)")
+        .bind(&payload.email)
+        .fetch_optional(&pool)
+        .await?;
+
+    // セキュリティ上の理由から、ユーザーが存在しない場合でも同じレスポンスを返す
+    if let Some(user) = user {
+        let token = magic_link::create_magic_link_token(&pool, tokens.as_ref(), user.id).await?;
+        send_magic_link_email(&config, mailer.as_ref(), &user, &token).await?;
+    }
+
+    Ok(Json(MessageResponse {
+        message: MAGIC_LINK_SENT_MESSAGE.to_string(),
+    }))
+}
+
+/// Log in with a magic link
+///
+/// Consume a magic link token and issue access and refresh tokens.
+/// The email address is marked as verified since the user proved control of the mailbox.
+#[utoipa::path(
+    post,
+    path = "/api/auth/magic-link/{token}",
+    params(
+        ("token" = String, Path, description = "Magic link token")
+    ),
+    responses(
+        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 401, description = "Invalid or expired magic link", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn login_with_magic_link(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let user_id = magic_link::consume_magic_link_token(&pool, &token).await?;
+
+    // メールボックスの所有が確認できたため、メールアドレスを認証済みにする
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET email_verified = true,
+            email_verified_at = COALESCE(email_verified_at, NOW()),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let (cookie_headers, response) =
+        auth_session::issue_session(&pool, &config, &jwt, tokens.as_ref(), user, &headers).await?;
+
+    Ok((cookie_headers, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::mock::MockSender,
+        test_utils::{create_test_user, test_config, test_jwt, test_tokens},
+    };
+
+    #[sqlx::test]
+    async fn test_マジックリンクでログインしメール認証済みになる(
+        pool: PgPool,
+    ) {
+        // 有効なトークンでトークンが発行され、メールアドレスが認証済みになることを確認
+        let user = create_test_user(&pool, false).await;
+        let token = magic_link::create_magic_link_token(&pool, test_tokens().as_ref(), user.id)
+            .await
+            .unwrap();
+
+        let (_, Json(response)) = login_with_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Path(token),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.user.id, user.id);
+        assert!(response.user.email_verified);
+        assert!(!response.access_token.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_マジックリンクは一度しか使用できない(pool: PgPool) {
+        // 同じトークンでの2回目のログインは拒否されることを確認
+        let user = create_test_user(&pool, true).await;
+        let token = magic_link::create_magic_link_token(&pool, test_tokens().as_ref(), user.id)
+            .await
+            .unwrap();
+
+        let first = login_with_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Path(token.clone()),
+        )
+        .await;
+        assert!(first.is_ok());
+
+        let second = login_with_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Path(token),
+        )
+        .await;
+        assert!(matches!(second, Err(AppError::Unauthorized(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れのマジックリンクは使用できない(pool: PgPool) {
+        // 有効期限を過ぎたトークンは拒否されることを確認
+        let user = create_test_user(&pool, true).await;
+        let token = magic_link::create_magic_link_token(&pool, test_tokens().as_ref(), user.id)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE magic_link_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = login_with_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Path(token),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_トークンはハッシュ化して保存される(pool: PgPool) {
+        // マジックリンクのトークンは平文ではなくハッシュで保存されることを確認
+        let user = create_test_user(&pool, true).await;
+        let token = magic_link::create_magic_link_token(&pool, test_tokens().as_ref(), user.id)
+            .await
+            .unwrap();
+
+        let stored: String =
+            sqlx::query_scalar("SELECT token_hash FROM magic_link_tokens WHERE user_id = $1")
+                .bind(user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_ne!(stored, token);
+    }
+
+    #[sqlx::test]
+    async fn test_登録済みのメールアドレスにはリンクを送信する(pool: PgPool) {
+        // ユーザー本人のメールアドレス宛てにログイン用のメールが1通送信されることを確認
+        let user = create_test_user(&pool, true).await;
+        let mailer = Arc::new(MockSender::new());
+
+        let _ = request_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Json(MagicLinkRequest {
+                email: user.email.clone(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+    }
+
+    #[sqlx::test]
+    async fn test_未登録のメールアドレスでも成功を返す(pool: PgPool) {
+        // アカウントの存在が推測できないよう、同じメッセージを返しトークンもメールも作成しないことを確認
+        let mailer = Arc::new(MockSender::new());
+        let result = request_magic_link(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Json(MagicLinkRequest {
+                email: "unknown@example.com".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.0.message, MAGIC_LINK_SENT_MESSAGE);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM magic_link_tokens")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(mailer.sent().is_empty());
+    }
+}
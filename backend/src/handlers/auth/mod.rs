@@ -1,21 +1,13 @@
 pub mod change_password;
+pub mod confirm_device;
 pub mod google_auth;
 pub mod google_callback;
 pub mod login;
 pub mod logout;
+pub mod magic_link;
 pub mod refresh_token;
 pub mod register;
 pub mod request_password_reset;
 pub mod reset_password;
+pub mod sessions;
 pub mod verify_email;
-
-pub use change_password::change_password;
-pub use google_auth::google_auth;
-pub use google_callback::google_callback;
-pub use login::login;
-pub use logout::logout;
-pub use refresh_token::refresh_token;
-pub use register::register;
-pub use request_password_reset::request_password_reset;
-pub use reset_password::reset_password;
-pub use verify_email::{resend_verification, verify_email};
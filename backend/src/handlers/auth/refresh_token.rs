@@ -1,8 +1,10 @@
-use axum::{extract::State, Json};
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::Utc;
 use sqlx::PgPool;
+use std::sync::Arc;
 
 use crate::{
-    auth::jwt::create_jwt_token,
+    auth::jwt::{JwtService, ACCESS_TOKEN_EXPIRES_IN_MINUTES},
     config::Config,
     error::AppError,
     models::{
@@ -10,7 +12,10 @@ use crate::{
         common::ErrorResponse,
         RefreshToken, User,
     },
-    utils::{self, token_hash::hash_refresh_token},
+    utils::{
+        refresh_token_cookie, request_info, token_generator::TokenGenerator,
+        token_hash::hash_refresh_token,
+    },
 };
 
 #[utoipa::path(
@@ -25,9 +30,18 @@ use crate::{
 )]
 pub async fn refresh_token(
     State(pool): State<PgPool>,
-    Json(payload): Json<RefreshTokenRequest>,
-) -> Result<Json<AuthResponse>, AppError> {
-    let token_hash = hash_refresh_token(&payload.refresh_token);
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
+    payload: Option<Json<RefreshTokenRequest>>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    // ボディに無い場合はCookieから読み取る
+    let presented_token = payload
+        .and_then(|Json(payload)| payload.refresh_token)
+        .or_else(|| refresh_token_cookie::read_refresh_token_cookie(&headers))
+        .ok_or_else(|| AppError::Unauthorized("Missing refresh token".to_string()))?;
+    let token_hash = hash_refresh_token(&presented_token);
 
     // Find and validate refresh token
     let refresh_token = sqlx::query_as::<_, RefreshToken>(
@@ -47,18 +61,8 @@ pub async fn refresh_token(
         .fetch_one(&pool)
         .await?;
 
-    // Generate new access token
-    let config = Config::from_env()?;
-    let access_token = create_jwt_token(
-        &user.id.to_string(),
-        &user.username,
-        &user.email,
-        &config.jwt_secret,
-        15, // 15 minutes
-    )?;
-
     // Generate new refresh token
-    let new_refresh_token = utils::generate_secure_token();
+    let new_refresh_token = tokens.secure_token();
     let new_refresh_token_hash = hash_refresh_token(&new_refresh_token);
 
     // Revoke old refresh token and create new one
@@ -69,19 +73,37 @@ pub async fn refresh_token(
         .execute(&mut *tx)
         .await?;
 
-    sqlx::query(
+    // ヘッダーが無い場合は元のセッションの情報を引き継ぐ
+    let session_id = sqlx::query_scalar::<_, uuid::Uuid>(
         r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2, NOW() + INTERVAL '7 days')
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at, last_used_at, user_agent, ip_address)
+        VALUES ($1, $2, NOW() + INTERVAL '7 days', NOW(), $3, $4)
+        RETURNING id
         "#,
     )
     .bind(user.id)
     .bind(&new_refresh_token_hash)
-    .execute(&mut *tx)
+    .bind(request_info::user_agent(&headers).or(refresh_token.user_agent))
+    .bind(request_info::client_ip(&headers).or(refresh_token.ip_address))
+    .fetch_one(&mut *tx)
     .await?;
 
     tx.commit().await?;
 
+    // Generate new access token
+    let access_token = jwt.encode(&jwt.claims(
+        &user.id.to_string(),
+        &user.username,
+        &user.email,
+        user.role,
+        &session_id.to_string(),
+        ACCESS_TOKEN_EXPIRES_IN_MINUTES,
+    ))?;
+
+    let (cookie_headers, new_refresh_token) =
+        refresh_token_cookie::deliver_refresh_token(&config, new_refresh_token);
+
+    let ban = user.active_ban(Utc::now());
     let response = AuthResponse {
         access_token,
         refresh_token: new_refresh_token,
@@ -96,7 +118,103 @@ pub async fn refresh_token(
             email_verified: user.email_verified,
             created_at: user.created_at,
         },
+        ban,
     };
 
-    Ok(Json(response))
+    Ok((cookie_headers, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_user, test_config, test_jwt, test_tokens};
+    use axum::http::{header::COOKIE, HeaderValue};
+
+    // テスト用のリフレッシュトークンを作成する
+    async fn insert_refresh_token(pool: &PgPool, user: &User, token: &str) {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, NOW() + INTERVAL '7 days')
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_refresh_token(token))
+        .execute(pool)
+        .await
+        .expect("Failed to create refresh token");
+    }
+
+    #[sqlx::test]
+    async fn test_ボディのリフレッシュトークンで更新できる(pool: PgPool) {
+        // JSONボディでトークンを渡すと新しいトークンが発行され、古いトークンは失効することを確認
+        let user = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, &user, "body_token").await;
+
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: Some("body_token".to_string()),
+            })),
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let reused = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: Some("body_token".to_string()),
+            })),
+        )
+        .await;
+        assert!(matches!(reused, Err(AppError::Unauthorized(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_cookieのリフレッシュトークンで更新できる(pool: PgPool) {
+        // ボディが無い場合はCookieからトークンを読み取ることを確認
+        let user = create_test_user(&pool, true).await;
+        insert_refresh_token(&pool, &user, "cookie_token").await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("refresh_token=cookie_token"),
+        );
+
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            headers,
+            None,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().1 .0.user.id, user.id);
+    }
+
+    #[sqlx::test]
+    async fn test_トークンが無い場合はエラーになる(pool: PgPool) {
+        // ボディにもCookieにもトークンが無い場合はUnauthorizedになることを確認
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
 }
@@ -1,51 +1,124 @@
-use axum::{extract::State, http::StatusCode, Json};
-use sqlx::PgPool;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use sqlx::{Acquire, PgPool};
+use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
-    auth::{jwt::create_jwt_token, password::hash_password},
+    auth::{
+        jwt::JwtService,
+        password::{hash_password, HASH_VERSION},
+    },
     config::Config,
     error::AppError,
+    events,
     models::{
-        auth::{AuthResponse, RegisterRequest, UserInfo},
+        auth::{AuthResponse, MessageResponse, RegisterRequest},
         common::ErrorResponse,
         User,
     },
-    utils::{self, email_sender, token_hash::hash_refresh_token},
+    side_effects::{Notification, Notifier, SideEffects},
+    utils::{
+        auth_session, db_error, email_sender, token_generator::TokenGenerator, username_history,
+    },
 };
 
+/// ユーザー登録のレスポンス
+#[derive(Debug)]
+pub enum RegisterResponse {
+    /// 登録と同時にログインする場合
+    Authenticated(HeaderMap, Box<AuthResponse>),
+    /// メール認証が完了するまでトークンを発行しない場合（REQUIRE_VERIFIED_LOGIN）
+    VerificationPending(MessageResponse),
+}
+
+impl IntoResponse for RegisterResponse {
+    fn into_response(self) -> Response {
+        match self {
+            RegisterResponse::Authenticated(headers, body) => {
+                (StatusCode::CREATED, headers, Json(*body)).into_response()
+            }
+            RegisterResponse::VerificationPending(body) => {
+                (StatusCode::CREATED, Json(body)).into_response()
+            }
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/register",
     request_body = RegisterRequest,
     responses(
-        (status = 201, description = "User registered successfully", body = AuthResponse),
+        (status = 201, description = "User registered successfully. When REQUIRE_VERIFIED_LOGIN is enabled, a MessageResponse is returned without tokens. `X-Degraded: true` is set when a side effect such as the verification email failed", body = AuthResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
-        (status = 409, description = "User already exists", body = ErrorResponse)
+        (status = 409, description = "User already exists or the username was recently used by another user", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn register(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
-) -> Result<(StatusCode, Json<AuthResponse>), AppError> {
+) -> Result<(HeaderMap, RegisterResponse), AppError> {
+    let mut side_effects = SideEffects::new(notifier);
+    let response = register_user(
+        &pool,
+        &config,
+        &jwt,
+        tokens.as_ref(),
+        &headers,
+        &mut side_effects,
+        payload,
+    )
+    .await?;
+
+    Ok((side_effects.headers(), response))
+}
+
+async fn register_user(
+    pool: &PgPool,
+    config: &Config,
+    jwt: &JwtService,
+    tokens: &dyn TokenGenerator,
+    headers: &HeaderMap,
+    side_effects: &mut SideEffects,
+    payload: RegisterRequest,
+) -> Result<RegisterResponse, AppError> {
     // Validate input
     payload.validate()?;
 
     // Check if user already exists
-    let existing_user =
-        sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1 OR username = $2")
-            .bind(&payload.email)
-            .bind(&payload.username)
-            .fetch_optional(&pool)
-            .await?;
+    let existing_user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE LOWER(email) = LOWER($1) OR LOWER(username) = LOWER($2)",
+    )
+    .bind(&payload.email)
+    .bind(&payload.username)
+    .fetch_optional(pool)
+    .await?;
 
     if existing_user.is_some() {
         return Err(AppError::Conflict("User already exists".to_string()));
     }
 
+    // 他のユーザーが最近まで使っていたユーザー名は、なりすましを防ぐため使えない
+    if username_history::is_reserved(pool, &payload.username, None, Utc::now()).await? {
+        return Err(AppError::Conflict(
+            "Username was recently used by another user".to_string(),
+        ));
+    }
+
     // Hash password
-    let (password_hash, salt) = hash_password(&payload.password)?;
+    let password_hash = hash_password(&payload.password)?;
 
     // Start transaction
     let mut tx = pool.begin().await?;
@@ -62,85 +135,82 @@ pub async fn register(
     .bind(&payload.email)
     .bind(&payload.display_name)
     .fetch_one(&mut *tx)
-    .await?;
+    .await
+    .map_err(db_error::map_db_error)?;
 
     // Create user credentials
     sqlx::query(
         r#"
-        INSERT INTO user_credentials (user_id, password_hash, salt)
+        INSERT INTO user_credentials (user_id, password_hash, hash_version)
         VALUES ($1, $2, $3)
         "#,
     )
     .bind(user.id)
     .bind(&password_hash)
-    .bind(&salt)
+    .bind(HASH_VERSION)
     .execute(&mut *tx)
     .await?;
 
-    // Commit transaction
-    tx.commit().await?;
+    // 確認メールはユーザーと同じトランザクションでアウトボックスに追加する
+    // 追加に失敗した場合もセーブポイントまで戻して登録は続行する（確認メールは再送できる）
+    side_effects
+        .run("verification_email", async {
+            let mut savepoint = Acquire::begin(&mut tx).await?;
+            let verification_token =
+                email_sender::start_verification_flow(config, tokens, &user, &mut savepoint)
+                    .await?;
+            email_sender::queue_verification_email(
+                config,
+                &user,
+                &verification_token,
+                &mut savepoint,
+            )
+            .await?;
+            savepoint.commit().await?;
+            Ok::<(), AppError>(())
+        })
+        .await;
 
-    // Generate verification token and send verification email
-    let mut tx = pool.begin().await?;
-    let verification_token = email_sender::start_verification_flow(&user, &mut tx).await?;
+    // Commit transaction
     tx.commit().await?;
 
-    // ユーザー情報をクローンして非同期処理に渡す
-    let user_clone = user.clone();
-
-    // 非同期でメール送信
-    tokio::spawn(async move {
-        let _ = email_sender::send_verification_email(&user_clone, &verification_token).await;
-    });
-
-    // Generate tokens
-    let config = Config::from_env()?;
-    let access_token = create_jwt_token(
-        &user.id.to_string(),
-        &user.username,
-        &user.email,
-        &config.jwt_secret,
-        15, // 15 minutes
-    )?;
-
-    let refresh_token = utils::generate_secure_token();
-    let refresh_token_hash = hash_refresh_token(&refresh_token);
-
-    // Store refresh token
-    sqlx::query(
-        r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2, NOW() + INTERVAL '7 days')
-        "#,
-    )
-    .bind(user.id)
-    .bind(&refresh_token_hash)
-    .execute(&pool)
-    .await?;
+    events::emit(&events::UserRegistered { user_id: user.id });
+    side_effects
+        .notify(Notification::UserRegistered { user_id: user.id })
+        .await;
+
+    // メール認証が必須の場合はトークンを発行しない
+    if config.require_verified_login {
+        return Ok(RegisterResponse::VerificationPending(MessageResponse {
+            message:
+                "確認メールを送信しました。メールに記載されたリンクから認証を完了してください。"
+                    .to_string(),
+        }));
+    }
 
-    let response = AuthResponse {
-        access_token,
-        refresh_token,
-        token_type: "Bearer".to_string(),
-        expires_in: 900, // 15 minutes in seconds
-        user: UserInfo {
-            id: user.id,
-            username: user.username,
-            email: user.email,
-            display_name: user.display_name,
-            avatar_url: user.avatar_url,
-            email_verified: user.email_verified,
-            created_at: user.created_at,
-        },
-    };
+    let (cookie_headers, response) =
+        auth_session::issue_session(pool, config, jwt, tokens, user, headers).await?;
 
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok(RegisterResponse::Authenticated(
+        cookie_headers,
+        Box::new(response),
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{models::User, test_utils::seed_test_user};
+    use crate::{
+        models::User,
+        side_effects::{self, DEGRADED_HEADER},
+        test_utils::fakes::{FailingNotifier, SeededTokenGenerator},
+        test_utils::{
+            noop_notifier, reserve_test_username, seed_test_user, test_config,
+            test_config_with_verified_login, test_jwt, test_tokens, TEST_RESERVED_USERNAME,
+            TEST_TOKEN_SEED,
+        },
+        utils::{email_verification, token_hash::hash_refresh_token},
+    };
     use axum::http::StatusCode;
 
     #[sqlx::test]
@@ -154,19 +224,33 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // レスポンスを検証
         assert!(result.is_ok(), "register should return Ok");
-        let response = result.unwrap();
+        let (_, response) = result.unwrap();
 
-        // ステータスコードを確認
-        assert_eq!(response.0, StatusCode::CREATED);
+        // トークンが発行されていることを確認
+        let auth_response = match response {
+            RegisterResponse::Authenticated(_, auth_response) => auth_response,
+            other => panic!("Expected Authenticated response, got {:?}", other),
+        };
 
         // レスポンスボディを確認
-        let auth_response = response.1 .0;
         assert!(!auth_response.access_token.is_empty());
-        assert!(!auth_response.refresh_token.is_empty());
+        assert!(auth_response
+            .refresh_token
+            .as_ref()
+            .is_some_and(|t| !t.is_empty()));
         assert_eq!(auth_response.token_type, "Bearer");
         assert_eq!(auth_response.expires_in, 900);
         assert_eq!(auth_response.user.username, "newuser123");
@@ -206,6 +290,90 @@ mod tests {
         assert!(token_exists, "Refresh token should exist");
     }
 
+    #[sqlx::test]
+    async fn test_登録時に確認メールがアウトボックスに追加される(
+        pool: PgPool,
+    ) {
+        // 登録と同じトランザクションで、新しいメールアドレス宛ての確認メールが送信待ちになる
+        let register_request = RegisterRequest {
+            username: "outbox_user".to_string(),
+            email: "outbox@example.com".to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+
+        register_user(
+            &pool,
+            &test_config(),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            &mut SideEffects::new(noop_notifier()),
+            register_request,
+        )
+        .await
+        .expect("register should succeed");
+
+        let (subject, status, attempts, text_body) =
+            sqlx::query_as::<_, (String, String, i32, Option<String>)>(
+                "SELECT subject, status, attempts, text_body FROM email_outbox WHERE to_email = $1",
+            )
+            .bind("outbox@example.com")
+            .fetch_one(&pool)
+            .await
+            .expect("Verification email should be queued");
+        assert_eq!(subject, "メールアドレスの確認");
+        assert_eq!(status, "pending");
+        assert_eq!(attempts, 0);
+
+        // 登録で最初に生成されるのは検証トークンなので、同じシードで生成した最初のトークンと一致する
+        let expected_token = SeededTokenGenerator::new(TEST_TOKEN_SEED)
+            .alphanumeric_token(email_verification::TOKEN_LENGTH);
+        assert!(text_body
+            .unwrap()
+            .contains(&format!("/verify-email/{}", expected_token)));
+        let stored_hash = sqlx::query_scalar::<_, String>(
+            "SELECT t.token_hash FROM email_verification_tokens t JOIN users u ON u.id = t.user_id WHERE u.email = $1",
+        )
+        .bind("outbox@example.com")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stored_hash, hash_refresh_token(&expected_token));
+    }
+
+    #[sqlx::test]
+    async fn test_登録に失敗した場合はアウトボックスに追加されない(
+        pool: PgPool,
+    ) {
+        // 登録に失敗した場合は確認メールがアウトボックスに追加されないことを確認
+        seed_test_user(&pool, "outbox_conflict").await;
+        let register_request = RegisterRequest {
+            username: "testuser_outbox_conflict".to_string(),
+            email: "outbox_conflict@example.com".to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+
+        let result = register_user(
+            &pool,
+            &test_config(),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            &mut SideEffects::new(noop_notifier()),
+            register_request,
+        )
+        .await;
+        assert!(result.is_err());
+
+        let queued = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM email_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(queued, 0);
+    }
+
     #[sqlx::test]
     async fn test_register_existing_username(pool: PgPool) {
         // 既存のテストユーザーを作成
@@ -220,7 +388,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for existing username");
@@ -246,7 +423,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for existing email");
@@ -269,7 +455,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for invalid password");
@@ -292,7 +487,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for invalid email");
@@ -315,7 +519,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(
@@ -341,7 +554,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for reserved username");
@@ -353,6 +575,40 @@ mod tests {
         }
     }
 
+    #[sqlx::test]
+    async fn test_register_route_and_configured_reserved_usernames(pool: PgPool) {
+        // ルートと衝突する語・大文字を含む語・環境変数で追加した語は、いずれも予約語として拒否することを確認
+        reserve_test_username();
+
+        for username in ["me", "Settings", TEST_RESERVED_USERNAME] {
+            let result = register(
+                State(pool.clone()),
+                State(Arc::new(test_config())),
+                State(test_jwt()),
+                State(noop_notifier()),
+                State(test_tokens()),
+                HeaderMap::new(),
+                Json(RegisterRequest {
+                    username: username.to_string(),
+                    email: format!("{}@example.com", username.to_lowercase()),
+                    password: "password123".to_string(),
+                    display_name: None,
+                }),
+            )
+            .await;
+
+            let Err(AppError::Validation(errors)) = result else {
+                panic!("Expected Validation error for {}", username);
+            };
+            assert_eq!(
+                errors.field_errors()["username"][0].code,
+                "username_reserved",
+                "{} should be rejected as reserved",
+                username
+            );
+        }
+    }
+
     #[sqlx::test]
     async fn test_register_invalid_username_characters(pool: PgPool) {
         // 無効な文字を含むユーザー名で登録リクエストを作成
@@ -364,7 +620,16 @@ mod tests {
         };
 
         // ハンドラを直接呼び出し
-        let result = register(State(pool.clone()), Json(register_request)).await;
+        let result = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(noop_notifier()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(register_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(
@@ -378,4 +643,284 @@ mod tests {
             _ => panic!("Expected Validation error"),
         }
     }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_登録時にトークンを発行しない(pool: PgPool) {
+        // REQUIRE_VERIFIED_LOGINが有効な場合、登録時はメッセージのみを返しトークンを発行しないことを確認
+        let register_request = RegisterRequest {
+            username: "pending_user".to_string(),
+            email: "pending@example.com".to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+
+        let response = register_user(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            &mut SideEffects::new(noop_notifier()),
+            register_request,
+        )
+        .await
+        .expect("register should succeed");
+
+        match response {
+            RegisterResponse::VerificationPending(body) => assert!(!body.message.is_empty()),
+            other => panic!("Expected VerificationPending response, got {:?}", other),
+        }
+
+        let user_id =
+            sqlx::query_scalar::<_, uuid::Uuid>("SELECT id FROM users WHERE username = $1")
+                .bind("pending_user")
+                .fetch_one(&pool)
+                .await
+                .expect("User should be created");
+
+        // リフレッシュトークンが作成されていないことを確認
+        let token_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM refresh_tokens WHERE user_id = $1)",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to check refresh token");
+        assert!(!token_exists, "Refresh token should not be issued");
+
+        // メール認証用のトークンは発行されていることを確認
+        let verification_token_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM email_verification_tokens WHERE user_id = $1)",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to check verification token");
+        assert!(verification_token_exists);
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_ステータスコードは201(pool: PgPool) {
+        // トークンを発行しない場合もステータスコードは201であることを確認
+        let register_request = RegisterRequest {
+            username: "pending_status".to_string(),
+            email: "pending_status@example.com".to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+
+        let response = register_user(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            &mut SideEffects::new(noop_notifier()),
+            register_request,
+        )
+        .await
+        .expect("register should succeed")
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[sqlx::test]
+    async fn test_同時登録_同じユーザー名は409になる(pool: PgPool) {
+        // 同じユーザー名での同時登録は片方だけが成功し、もう片方は500ではなく409になることを確認
+        let request = |email: &str| RegisterRequest {
+            username: "race_register".to_string(),
+            email: email.to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+        let config = test_config_with_verified_login(true);
+        let jwt = test_jwt();
+        let tokens = test_tokens();
+        let headers = HeaderMap::new();
+        let mut first_side_effects = SideEffects::new(noop_notifier());
+        let mut second_side_effects = SideEffects::new(noop_notifier());
+
+        let (first, second) = tokio::join!(
+            register_user(
+                &pool,
+                &config,
+                &jwt,
+                tokens.as_ref(),
+                &headers,
+                &mut first_side_effects,
+                request("race1@example.com")
+            ),
+            register_user(
+                &pool,
+                &config,
+                &jwt,
+                tokens.as_ref(),
+                &headers,
+                &mut second_side_effects,
+                request("race2@example.com")
+            ),
+        );
+
+        let err = match (first, second) {
+            (Ok(_), Err(err)) | (Err(err), Ok(_)) => err,
+            other => panic!("Expected exactly one failure, got {:?}", other),
+        };
+        assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE username = $1")
+            .bind("race_register")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_通知に失敗しても登録は成功する(pool: PgPool) {
+        // 通知先が停止していても201を返し、ユーザーと確認メールは保存される
+        let notifier = Arc::new(FailingNotifier::default());
+        let failures_before = side_effects::failure_count();
+
+        let (headers, response) = register(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(notifier.clone()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Json(RegisterRequest {
+                username: "degraded_user".to_string(),
+                email: "degraded@example.com".to_string(),
+                password: "password123".to_string(),
+                display_name: None,
+            }),
+        )
+        .await
+        .expect("register should succeed");
+
+        assert_eq!(response.into_response().status(), StatusCode::CREATED);
+        assert_eq!(headers.get(DEGRADED_HEADER).unwrap(), "true");
+        assert_eq!(notifier.attempts(), 1);
+        assert!(side_effects::failure_count() > failures_before);
+
+        let queued =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM email_outbox WHERE to_email = $1")
+                .bind("degraded@example.com")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(queued, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_確認メールの追加に失敗しても登録は成功する(pool: PgPool) {
+        // アウトボックスへの追加が失敗した場合はその分だけ取り消し、ユーザーの作成は確定することを確認
+        sqlx::query("ALTER TABLE email_outbox RENAME TO email_outbox_unavailable")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut side_effects = SideEffects::new(noop_notifier());
+
+        let response = register_user(
+            &pool,
+            &test_config(),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &HeaderMap::new(),
+            &mut side_effects,
+            RegisterRequest {
+                username: "no_outbox_user".to_string(),
+                email: "no_outbox@example.com".to_string(),
+                password: "password123".to_string(),
+                display_name: None,
+            },
+        )
+        .await
+        .expect("register should succeed");
+
+        assert!(matches!(response, RegisterResponse::Authenticated(..)));
+        assert_eq!(side_effects.headers().get(DEGRADED_HEADER).unwrap(), "true");
+
+        let created =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)")
+                .bind("no_outbox_user")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(created);
+    }
+
+    #[sqlx::test]
+    async fn test_大文字・小文字だけが異なるユーザー名とメールアドレスは409になる(
+        pool: PgPool,
+    ) {
+        // 大文字・小文字だけが異なるユーザー名とメールアドレスでは登録できず409になることを確認
+        let request = |username: &str, email: &str| RegisterRequest {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+        let config = test_config_with_verified_login(true);
+        let tokens = test_tokens();
+        let try_register = |payload| {
+            let pool = pool.clone();
+            let config = config.clone();
+            let tokens = tokens.clone();
+            async move {
+                register_user(
+                    &pool,
+                    &config,
+                    &test_jwt(),
+                    tokens.as_ref(),
+                    &HeaderMap::new(),
+                    &mut SideEffects::new(noop_notifier()),
+                    payload,
+                )
+                .await
+            }
+        };
+
+        try_register(request("Alice_Case", "Alice.Case@example.com"))
+            .await
+            .expect("register should succeed");
+
+        for payload in [
+            request("alice_case", "other@example.com"),
+            request("other_user", "alice.case@EXAMPLE.com"),
+        ] {
+            let result = try_register(payload).await;
+            assert!(
+                matches!(result, Err(AppError::Conflict(_))),
+                "Expected Conflict, got {:?}",
+                result
+            );
+        }
+
+        // 表示用の大文字・小文字は登録したまま
+        let username = sqlx::query_scalar::<_, String>(
+            "SELECT username FROM users WHERE LOWER(username) = 'alice_case'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(username, "Alice_Case");
+
+        // 事前チェックをすり抜けた場合も一意インデックスで409になる
+        let err = sqlx::query(
+            "INSERT INTO users (username, email) VALUES ('ALICE_CASE', 'x@example.com')",
+        )
+        .execute(&pool)
+        .await
+        .map_err(db_error::map_db_error)
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::UniqueViolation {
+                code: "USERNAME_TAKEN",
+                ..
+            }
+        ));
+    }
 }
@@ -1,15 +1,21 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde_json::json;
 use sqlx::PgPool;
+use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
+    config::Config,
+    email::EmailSender,
     error::AppError,
     models::{
         auth::{MessageResponse, RequestPasswordResetRequest},
         common::ErrorResponse,
+        User,
+    },
+    utils::{
+        email_sender::send_password_reset_email, password_reset, token_generator::TokenGenerator,
     },
-    utils::{email_sender::send_password_reset_email, password_reset},
 };
 
 /// Request a password reset
@@ -23,65 +29,43 @@ use crate::{
         (status = 200, description = "Password reset email sent successfully", body = MessageResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 429, description = "Too many password reset requests", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse),
     ),
     tag = "Auth"
 )]
 pub async fn request_password_reset(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Arc<dyn EmailSender>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
     Json(request): Json<RequestPasswordResetRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     // バリデーション
     request.validate()?;
 
     // メールアドレスからユーザーを検索
-    let user = sqlx::query!(
-        r#"
-        SELECT 
-            id, username, email, display_name, avatar_url, 
-            email_verified::bool as "email_verified!",
-            email_verified_at,
-            verification_token,
-            verification_token_expires_at,
-            created_at as "created_at!", updated_at as "updated_at!"
-        FROM users
-        WHERE email = $1
-        "#,
-        request.email
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| AppError::Database(e))?;
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+        .bind(&request.email)
+        .fetch_optional(&pool)
+        .await
+        .map_err(AppError::Database)?;
 
     match user {
         Some(user) => {
             // ユーザーが存在する場合はパスワードリセットトークンを生成し、メールを送信
             let mut tx = pool.begin().await.map_err(|e| AppError::Database(e))?;
 
-            let user_model = crate::models::User {
-                id: user.id,
-                username: user.username,
-                email: user.email,
-                display_name: user.display_name,
-                avatar_url: user.avatar_url,
-                email_verified: user.email_verified,
-                email_verified_at: user.email_verified_at,
-                verification_token: user.verification_token,
-                verification_token_expires_at: user.verification_token_expires_at,
-                password_reset_token: None,
-                password_reset_token_expires_at: None,
-                created_at: user.created_at,
-                updated_at: user.updated_at,
-            };
-
             // パスワードリセットトークンの生成
-            let reset_token = password_reset::create_reset_token(user_model.id, &mut tx).await?;
+            let reset_token =
+                password_reset::create_reset_token(&config, tokens.as_ref(), user.id, &mut tx)
+                    .await?;
 
             // トランザクションのコミット
             tx.commit().await.map_err(|e| AppError::Database(e))?;
 
             // メール送信
-            send_password_reset_email(&user_model, &reset_token).await?;
+            send_password_reset_email(&config, mailer.as_ref(), &user, &reset_token).await?;
 
             // 成功レスポンス（セキュリティのため、ユーザーが見つからない場合と同じメッセージを返す）
             Ok((
@@ -102,3 +86,72 @@ pub async fn request_password_reset(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::mock::MockSender,
+        test_utils::{create_test_user, test_config, test_tokens},
+    };
+
+    async fn request(pool: &PgPool, mailer: &Arc<MockSender>, email: &str) -> StatusCode {
+        request_password_reset(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Json(RequestPasswordResetRequest {
+                email: email.to_string(),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response()
+        .status()
+    }
+
+    #[sqlx::test]
+    async fn test_登録済みのメールアドレスにリセット用のリンクを送る(
+        pool: PgPool,
+    ) {
+        // 登録済みのメールアドレスにリセット用のリンクが送られることを確認
+        let user = create_test_user(&pool, true).await;
+        let mailer = Arc::new(MockSender::new());
+
+        assert_eq!(request(&pool, &mailer, &user.email).await, StatusCode::OK);
+
+        let reset_token = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT password_reset_token FROM users WHERE id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .unwrap();
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+        assert!(sent[0].html_body.contains(&reset_token));
+        assert_eq!(
+            password_reset::verify_reset_token(&reset_token, &pool)
+                .await
+                .unwrap(),
+            user.id
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_未登録のメールアドレスでも同じレスポンスを返し_メールは送らない(
+        pool: PgPool,
+    ) {
+        // 未登録のメールアドレスでも同じレスポンスを返し、メールは送らないことを確認
+        let mailer = Arc::new(MockSender::new());
+
+        assert_eq!(
+            request(&pool, &mailer, "nobody@example.com").await,
+            StatusCode::OK
+        );
+        assert!(mailer.sent().is_empty());
+    }
+}
@@ -10,12 +10,12 @@ use crate::{
         auth::{MessageResponse, ResetPasswordRequest},
         common::ErrorResponse,
     },
-    utils::password_reset,
+    utils::{credentials, password_reset},
 };
 
 /// Reset password
 ///
-/// Reset user's password using a valid reset token
+/// Reset user's password using a valid reset token. All existing sessions are signed out.
 #[utoipa::path(
     post,
     path = "/api/auth/password-reset/{token}",
@@ -43,39 +43,37 @@ pub async fn reset_password(
     let user_id = password_reset::verify_reset_token(&token, &pool).await?;
 
     // パスワードハッシュの生成
-    let (password_hash, salt) = password::hash_password(&request.new_password)?;
+    let password_hash = password::hash_password(&request.new_password)?;
 
     // トランザクション開始
     let mut tx = pool.begin().await.map_err(|e| AppError::Database(e))?;
 
     // ユーザー認証情報の更新
-    sqlx::query!(
-        r#"
-        UPDATE user_credentials
-        SET password_hash = $1, salt = $2, updated_at = NOW()
-        WHERE user_id = $3
-        "#,
-        password_hash,
-        salt,
-        user_id
-    )
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| AppError::Database(e))?;
+    credentials::save_password_hash(&mut *tx, user_id, &password_hash).await?;
+
+    // 再設定前のセッションはすべて終了させる
+    sqlx::query("UPDATE refresh_tokens SET revoked = true WHERE user_id = $1 AND revoked = false")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
 
     // リセットトークンの消去
-    sqlx::query!(
+    sqlx::query(
         r#"
         UPDATE users
         SET password_reset_token = NULL, password_reset_token_expires_at = NULL
         WHERE id = $1
         "#,
-        user_id
     )
+    .bind(user_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| AppError::Database(e))?;
 
+    // 管理者が再設定を必須にしていた場合は解除する
+    password_reset::clear_required_reset(&mut tx, user_id).await?;
+
     // トランザクションのコミット
     tx.commit().await.map_err(|e| AppError::Database(e))?;
 
@@ -87,3 +85,85 @@ pub async fn reset_password(
         })),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::auth::{login::login, refresh_token::refresh_token},
+        models::auth::{LoginRequest, RefreshTokenRequest},
+        test_utils::{
+            create_test_user, set_test_user_password, test_config, test_jwt, test_tokens,
+        },
+    };
+    use axum::{extract::Path, http::HeaderMap};
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn test_パスワードを再設定すると既存のセッションが失効する(
+        pool: PgPool,
+    ) {
+        // 再設定前に発行したリフレッシュトークンではトークンを更新できないことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+        let tokens = test_tokens();
+        let (_, Json(session)) = login(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens.clone()),
+            HeaderMap::new(),
+            Json(LoginRequest {
+                email: user.email.clone(),
+                password: "password123".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        let reset_token =
+            password_reset::create_reset_token(&test_config(), tokens.as_ref(), user.id, &mut tx)
+                .await
+                .unwrap();
+        tx.commit().await.unwrap();
+
+        reset_password(
+            State(pool.clone()),
+            Path(reset_token),
+            Json(ResetPasswordRequest {
+                new_password: "new-password456".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: session.refresh_token,
+            })),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_無効なトークンでは再設定できない(pool: PgPool) {
+        // 無効なトークンではパスワードを再設定できないことを確認
+        let result = reset_password(
+            State(pool.clone()),
+            Path("invalid-token".to_string()),
+            Json(ResetPasswordRequest {
+                new_password: "new-password456".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
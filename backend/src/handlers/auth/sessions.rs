@@ -0,0 +1,243 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        auth::{Claims, SessionListResponse, SessionResponse},
+        common::ErrorResponse,
+        RefreshToken, User,
+    },
+};
+
+/// ログイン中のセッション一覧を取得
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    responses(
+        (status = 200, description = "Active sessions", body = SessionListResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "auth",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_sessions(
+    State(pool): State<PgPool>,
+    Extension(current_user): Extension<User>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<SessionListResponse>, AppError> {
+    // 失効しておらず、有効期限内のリフレッシュトークンのみを取得
+    let tokens = sqlx::query_as::<_, RefreshToken>(
+        r#"
+        SELECT * FROM refresh_tokens
+        WHERE user_id = $1 AND revoked = false AND expires_at > NOW()
+        ORDER BY COALESCE(last_used_at, created_at) DESC
+        "#,
+    )
+    .bind(current_user.id)
+    .fetch_all(&pool)
+    .await?;
+
+    let sessions = tokens
+        .into_iter()
+        .map(|token| SessionResponse {
+            is_current: claims.sid.as_deref() == Some(token.id.to_string().as_str()),
+            id: token.id,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            user_agent: token.user_agent,
+            ip_address: token.ip_address,
+        })
+        .collect();
+
+    Ok(Json(SessionListResponse { sessions }))
+}
+
+/// 指定したセッションを失効させる
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 204, description = "Session revoked successfully"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse)
+    ),
+    tag = "auth",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn revoke_session(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    // 他人のセッションは存在しないものとして扱う
+    let revoked_rows = sqlx::query(
+        "UPDATE refresh_tokens SET revoked = true WHERE id = $1 AND user_id = $2 AND revoked = false",
+    )
+    .bind(id)
+    .bind(current_user.id)
+    .execute(&pool)
+    .await?;
+
+    if revoked_rows.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::auth::{login::login, refresh_token::refresh_token},
+        models::auth::{AuthResponse, LoginRequest, RefreshTokenRequest},
+        test_utils::{
+            create_test_user, set_test_user_password, test_config, test_jwt, test_tokens,
+        },
+        utils::token_generator::{RandomTokenGenerator, TokenGenerator},
+    };
+    use axum::http::{header::USER_AGENT, HeaderMap, HeaderValue};
+    use std::sync::Arc;
+
+    // ログインしてレスポンスとアクセストークンのクレームを返す
+    // 同じテストで何度もログインするため、リフレッシュトークンは毎回異なる値で発行する
+    async fn login_as(pool: &PgPool, user: &User, agent: &'static str) -> (AuthResponse, Claims) {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(agent));
+        let tokens: Arc<dyn TokenGenerator> = Arc::new(RandomTokenGenerator);
+
+        let response = login(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens),
+            headers,
+            Json(LoginRequest {
+                email: user.email.clone(),
+                password: "password123".to_string(),
+            }),
+        )
+        .await
+        .expect("Login should succeed")
+        .1
+         .0;
+
+        let claims = test_jwt().decode(&response.access_token).unwrap();
+
+        (response, claims)
+    }
+
+    #[sqlx::test]
+    async fn test_セッション一覧に現在のセッションが含まれる(pool: PgPool) {
+        // 2回ログインすると2つのセッションが返り、現在のセッションにフラグが立つことを確認
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        login_as(&pool, &user, "first-agent").await;
+        let (_, claims) = login_as(&pool, &user, "second-agent").await;
+
+        let result = list_sessions(
+            State(pool.clone()),
+            Extension(user),
+            Extension(claims.clone()),
+        )
+        .await
+        .unwrap();
+
+        let sessions = result.0.sessions;
+        assert_eq!(sessions.len(), 2);
+
+        let current: Vec<_> = sessions.iter().filter(|s| s.is_current).collect();
+        assert_eq!(current.len(), 1);
+        assert_eq!(Some(current[0].id.to_string()), claims.sid);
+        assert_eq!(current[0].user_agent.as_deref(), Some("second-agent"));
+    }
+
+    #[sqlx::test]
+    async fn test_セッションを失効させるとリフレッシュできなくなる(
+        pool: PgPool,
+    ) {
+        // 失効させたセッションのリフレッシュトークンでは更新できず、他のセッションは使えることを確認
+        // 2回ログインし、1つ目のセッションを失効させる
+        let user = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, user.id, "password123").await;
+
+        let (first, first_claims) = login_as(&pool, &user, "first-agent").await;
+        let (second, second_claims) = login_as(&pool, &user, "second-agent").await;
+
+        let first_id = Uuid::parse_str(first_claims.sid.as_deref().unwrap()).unwrap();
+        let status = revoke_session(State(pool.clone()), Path(first_id), Extension(user.clone()))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        // 失効したセッションはリフレッシュできない
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: first.refresh_token,
+            })),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+
+        // 残ったセッションは一覧に1つだけ
+        let sessions = list_sessions(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Extension(second_claims),
+        )
+        .await
+        .unwrap()
+        .0
+        .sessions;
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].is_current);
+
+        // もう一方のセッションは引き続きリフレッシュできる
+        let result = refresh_token(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(test_tokens()),
+            HeaderMap::new(),
+            Some(Json(RefreshTokenRequest {
+                refresh_token: second.refresh_token,
+            })),
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn test_他人のセッションは失効できない(pool: PgPool) {
+        // 他のユーザーのセッションIDを指定するとNotFoundになることを確認
+        let owner = create_test_user(&pool, true).await;
+        set_test_user_password(&pool, owner.id, "password123").await;
+        let other = create_test_user(&pool, true).await;
+
+        let (_, claims) = login_as(&pool, &owner, "owner-agent").await;
+        let session_id = Uuid::parse_str(claims.sid.as_deref().unwrap()).unwrap();
+
+        let result = revoke_session(State(pool.clone()), Path(session_id), Extension(other)).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -1,17 +1,21 @@
 use axum::{
     extract::{Extension, Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::sync::Arc;
 use utoipa::ToSchema;
 
 use crate::{
+    auth::jwt::JwtService,
+    config::Config,
+    email::EmailSender,
     error::AppError,
-    models::{common::ErrorResponse, User},
-    utils::email_verification,
+    models::{auth::AuthResponse, common::ErrorResponse, User},
+    utils::{auth_session, email_verification, token_generator::TokenGenerator},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -20,13 +24,35 @@ pub struct VerifyEmailResponse {
     verified: bool,
 }
 
+/// メールアドレス検証の結果
+#[derive(Debug)]
+pub enum VerifyEmailResult {
+    /// 検証のみ完了した場合
+    Verified(VerifyEmailResponse),
+    /// 検証と同時にログインした場合（REQUIRE_VERIFIED_LOGIN）
+    Authenticated(HeaderMap, Box<AuthResponse>),
+}
+
+impl IntoResponse for VerifyEmailResult {
+    fn into_response(self) -> Response {
+        match self {
+            VerifyEmailResult::Verified(body) => (StatusCode::OK, Json(body)).into_response(),
+            VerifyEmailResult::Authenticated(headers, body) => {
+                (StatusCode::OK, headers, Json(*body)).into_response()
+            }
+        }
+    }
+}
+
 /// メールアドレス検証
+///
+/// REQUIRE_VERIFIED_LOGINが有効な場合は、登録時に発行しなかったトークンをここで発行します。
 #[utoipa::path(
     post,
     path = "/api/auth/verify-email/{token}",
     tag = "認証",
     responses(
-        (status = 200, description = "メールアドレス検証が成功", body = VerifyEmailResponse),
+        (status = 200, description = "メールアドレス検証が成功（REQUIRE_VERIFIED_LOGINが有効な場合はAuthResponse）", body = VerifyEmailResponse),
         (status = 400, description = "無効なトークン", body = ErrorResponse),
         (status = 500, description = "サーバーエラー", body = ErrorResponse),
     ),
@@ -36,17 +62,43 @@ pub struct VerifyEmailResponse {
 )]
 pub async fn verify_email(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
     Path(token): Path<String>,
-) -> Result<impl IntoResponse, AppError> {
-    email_verification::verify_email(&token, &pool).await?;
+    headers: HeaderMap,
+) -> Result<VerifyEmailResult, AppError> {
+    verify_email_token(&pool, &config, &jwt, tokens.as_ref(), &token, &headers).await
+}
 
-    Ok((
-        StatusCode::OK,
-        Json(VerifyEmailResponse {
-            message: "メールアドレスが正常に検証されました。".to_string(),
-            verified: true,
-        }),
-    ))
+async fn verify_email_token(
+    pool: &PgPool,
+    config: &Config,
+    jwt: &JwtService,
+    tokens: &dyn TokenGenerator,
+    token: &str,
+    headers: &HeaderMap,
+) -> Result<VerifyEmailResult, AppError> {
+    let user_id = email_verification::verify_email(token, pool).await?;
+
+    if config.require_verified_login {
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        let (cookie_headers, response) =
+            auth_session::issue_session(pool, config, jwt, tokens, user, headers).await?;
+
+        return Ok(VerifyEmailResult::Authenticated(
+            cookie_headers,
+            Box::new(response),
+        ));
+    }
+
+    Ok(VerifyEmailResult::Verified(VerifyEmailResponse {
+        message: "メールアドレスが正常に検証されました。".to_string(),
+        verified: true,
+    }))
 }
 
 /// 検証メールの再送信リクエスト
@@ -56,25 +108,38 @@ pub struct ResendVerificationResponse {
 }
 
 /// 検証メール再送信
+///
+/// 再送信は5分に1回、1日5回までです。以前に送信したリンクも有効期限までは使えます。
 #[utoipa::path(
     post,
     path = "/api/auth/resend-verification",
     tag = "認証",
     responses(
         (status = 200, description = "検証メールを再送信しました", body = ResendVerificationResponse),
-        (status = 400, description = "不正なリクエスト", body = ErrorResponse),
+        (status = 400, description = "既に確認済み（code: EMAIL_ALREADY_VERIFIED）", body = ErrorResponse),
         (status = 404, description = "ユーザーが見つかりません", body = ErrorResponse),
+        (status = 429, description = "再送信の間隔が短すぎる、または1日の上限に達した（code: RESEND_COOLDOWN、Retry-Afterヘッダーに残り秒数）", body = ErrorResponse),
         (status = 500, description = "サーバーエラー", body = ErrorResponse),
     ),
     security(
-        ("jwt_auth" = [])
+        ("bearer_auth" = [])
     )
 )]
 pub async fn resend_verification(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Arc<dyn EmailSender>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
     Extension(current_user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
-    crate::utils::email_sender::resend_verification_email(current_user.id, &pool).await?;
+    crate::utils::email_sender::resend_verification_email(
+        &config,
+        mailer.as_ref(),
+        tokens.as_ref(),
+        current_user.id,
+        &pool,
+    )
+    .await?;
 
     Ok((
         StatusCode::OK,
@@ -83,3 +148,322 @@ pub async fn resend_verification(
         }),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::mock::MockSender,
+        test_utils::{
+            create_test_user, test_config, test_config_with_verified_login, test_jwt, test_tokens,
+        },
+        utils::{
+            email_sender, email_verification::create_verification_token,
+            token_generator::RandomTokenGenerator,
+        },
+    };
+    use chrono::{Duration, Utc};
+
+    // 以前に送信したリンクのトークンを発行する（再送信とは別の乱数で生成する）
+    async fn issue_token(pool: &PgPool, user: &User) -> String {
+        let mut tx = pool.begin().await.unwrap();
+        let token = create_verification_token(
+            &test_config(),
+            &RandomTokenGenerator,
+            user.id,
+            &user.email,
+            &mut tx,
+        )
+        .await
+        .expect("Failed to create verification token");
+        tx.commit().await.unwrap();
+        token
+    }
+
+    #[sqlx::test]
+    async fn test_メール検証_トークンを発行しない(pool: PgPool) {
+        // REQUIRE_VERIFIED_LOGINが無効な場合は検証結果のみを返すことを確認
+        let user = create_test_user(&pool, false).await;
+        let token = issue_token(&pool, &user).await;
+
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &token,
+            &HeaderMap::new(),
+        )
+        .await
+        .expect("verification should succeed");
+
+        match result {
+            VerifyEmailResult::Verified(body) => assert!(body.verified),
+            other => panic!("Expected Verified result, got {:?}", other),
+        }
+
+        let token_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM refresh_tokens WHERE user_id = $1)",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!token_exists);
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_検証時にトークンを発行する(pool: PgPool) {
+        // REQUIRE_VERIFIED_LOGINが有効な場合は検証と同時にログインできることを確認
+        let user = create_test_user(&pool, false).await;
+        let token = issue_token(&pool, &user).await;
+
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &token,
+            &HeaderMap::new(),
+        )
+        .await
+        .expect("verification should succeed");
+
+        let auth_response = match result {
+            VerifyEmailResult::Authenticated(_, auth_response) => auth_response,
+            other => panic!("Expected Authenticated result, got {:?}", other),
+        };
+        assert!(!auth_response.access_token.is_empty());
+        assert_eq!(auth_response.user.id, user.id);
+        assert!(auth_response.user.email_verified);
+
+        let token_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM refresh_tokens WHERE user_id = $1)",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(token_exists);
+    }
+
+    #[sqlx::test]
+    async fn test_メール認証必須_無効なトークンはエラー(pool: PgPool) {
+        // 無効なトークンではトークンを発行せずエラーになることを確認
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(true),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            "unknown-token",
+            &HeaderMap::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_再送信後も古いリンクで検証できる(pool: PgPool) {
+        // 再送信しても以前に送ったリンクは有効期限まで使えることを確認
+        let user = create_test_user(&pool, false).await;
+        let old_token = issue_token(&pool, &user).await;
+        let (_, new_token) = email_sender::issue_resend_token(
+            &test_config(),
+            test_tokens().as_ref(),
+            user.id,
+            &pool,
+            Utc::now() + Duration::minutes(10),
+        )
+        .await
+        .expect("resend should succeed");
+        assert_ne!(old_token, new_token);
+
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &old_token,
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(result.is_ok(), "old link should still verify");
+
+        // 検証済みになった後は新しいリンクも使えない
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &new_token,
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_使用済みのリンクは再利用できない(pool: PgPool) {
+        // トークンは使用時に消費済みになることを確認
+        let user = create_test_user(&pool, false).await;
+        let token = issue_token(&pool, &user).await;
+
+        verify_email_token(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &token,
+            &HeaderMap::new(),
+        )
+        .await
+        .expect("verification should succeed");
+
+        let consumed = sqlx::query_scalar::<_, bool>(
+            "SELECT consumed_at IS NOT NULL FROM email_verification_tokens WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(consumed);
+    }
+
+    #[sqlx::test]
+    async fn test_メールアドレス変更前のリンクでは検証できない(pool: PgPool) {
+        // 古いメールアドレス宛てのリンクで新しいメールアドレスが確認済みにならないことを確認
+        let user = create_test_user(&pool, false).await;
+        let token = issue_token(&pool, &user).await;
+        sqlx::query("UPDATE users SET email = 'changed@example.com' WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = verify_email_token(
+            &pool,
+            &test_config_with_verified_login(false),
+            &test_jwt(),
+            test_tokens().as_ref(),
+            &token,
+            &HeaderMap::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_再送信で検証メールが送信される(pool: PgPool) {
+        // 注入した送信者を通じてユーザー宛てに検証メールが1通送信されることを確認
+        let user = create_test_user(&pool, false).await;
+        let mailer = Arc::new(MockSender::new());
+
+        resend_verification(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(mailer.clone()),
+            State(test_tokens()),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+        assert_eq!(sent[0].subject, "メールアドレスの確認");
+    }
+
+    #[sqlx::test]
+    async fn test_再送信はクールダウン中は429になる(pool: PgPool) {
+        // 前回の送信から5分以内の再送信は残り秒数付きで拒否されることを確認
+        let user = create_test_user(&pool, false).await;
+        issue_token(&pool, &user).await;
+
+        let result = email_sender::issue_resend_token(
+            &test_config(),
+            test_tokens().as_ref(),
+            user.id,
+            &pool,
+            Utc::now(),
+        )
+        .await;
+
+        let err = result.expect_err("resend should be rejected during cooldown");
+        match &err {
+            AppError::VerificationResendCooldown(seconds) => {
+                assert!(*seconds > 0 && *seconds <= 300, "remaining: {}", seconds)
+            }
+            other => panic!("Expected VerificationResendCooldown, got {:?}", other),
+        }
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+    }
+
+    #[sqlx::test]
+    async fn test_再送信は1日5回まで(pool: PgPool) {
+        // 直近24時間に5回送信している場合はクールダウン後でも拒否されることを確認
+        let user = create_test_user(&pool, false).await;
+        for hours_ago in [20, 16, 12, 8, 4] {
+            sqlx::query(
+                r#"
+                INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at, created_at)
+                VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour', NOW() - make_interval(hours => $4))
+                "#,
+            )
+            .bind(user.id)
+            .bind(&user.email)
+            .bind(format!("hash-{}", hours_ago))
+            .bind(hours_ago)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let result = email_sender::issue_resend_token(
+            &test_config(),
+            test_tokens().as_ref(),
+            user.id,
+            &pool,
+            Utc::now(),
+        )
+        .await;
+
+        match result {
+            // 最も古い送信（20時間前）が枠から外れるまでの約4時間
+            Err(AppError::VerificationResendCooldown(seconds)) => {
+                assert!(
+                    seconds > 3 * 3600 && seconds <= 4 * 3600,
+                    "remaining: {}",
+                    seconds
+                )
+            }
+            other => panic!("Expected VerificationResendCooldown, got {:?}", other),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_確認済みユーザーの再送信はクールダウンと区別される(
+        pool: PgPool,
+    ) {
+        // 確認済みの場合は429ではなく専用のエラーになることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = email_sender::issue_resend_token(
+            &test_config(),
+            test_tokens().as_ref(),
+            user.id,
+            &pool,
+            Utc::now(),
+        )
+        .await;
+
+        let err = result.expect_err("verified user should not receive a new link");
+        assert!(matches!(err, AppError::EmailAlreadyVerified));
+        assert_eq!(err.code(), Some("EMAIL_ALREADY_VERIFIED"));
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,19 +1,26 @@
 use axum::{
     extract::{Extension, Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
+use chrono::Utc;
 use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
+    events,
     models::{
-        comments::{CommentResponse, CommentWithUser, CreateCommentRequest},
+        comments::{CommentResponse, CommentWithUser, CreateCommentRequest, MAX_DEPTH},
         common::ErrorResponse,
+        moderation::UserMutedErrorResponse,
         User,
     },
+    side_effects::{Notification, Notifier, SideEffects},
+    utils::{db_error, short_id, soft_limits, token_generator::TokenGenerator, user_mute},
 };
 
 #[utoipa::path(
@@ -24,9 +31,10 @@ use crate::{
     ),
     request_body = CreateCommentRequest,
     responses(
-        (status = 201, description = "Comment created successfully", body = CommentResponse),
+        (status = 201, description = "Comment created successfully. `X-Degraded: true` is set when a side effect such as a notification failed. `warnings` lists content close to its limit and replies that reached the maximum depth", body = CommentResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "User is muted or thread is locked", body = UserMutedErrorResponse),
         (status = 404, description = "Thread not found", body = ErrorResponse)
     ),
     tag = "comments",
@@ -36,10 +44,90 @@ use crate::{
 )]
 pub async fn create_comment(
     State(pool): State<PgPool>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    State(config): State<Arc<Config>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
     Path(thread_id): Path<Uuid>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<CreateCommentRequest>,
-) -> Result<(StatusCode, Json<CommentResponse>), AppError> {
+) -> Result<(StatusCode, HeaderMap, Json<CommentResponse>), AppError> {
+    let depth = prepare_comment(&pool, thread_id, &current_user, &payload).await?;
+
+    // Create comment（短いIDが衝突した場合は再生成して再試行）
+    // スレッドの最終アクティビティ日時も同じ文で進める
+    let generate_short_id = || tokens.short_id();
+    let comment = short_id::insert_with_short_id(generate_short_id, |short_id| {
+        sqlx::query_as::<_, CommentWithUser>(
+            r#"
+            WITH inserted AS (
+                INSERT INTO comments (thread_id, user_id, content, parent_id, short_id)
+                VALUES ($1, $2, $3, $4, $8)
+                RETURNING id, short_id, content, parent_id, created_at, updated_at
+            ),
+            touched AS (
+                UPDATE threads
+                SET last_activity_at = GREATEST(threads.last_activity_at, inserted.created_at)
+                FROM inserted
+                WHERE threads.id = $1
+            )
+            SELECT
+                id, short_id, $1 as thread_id, content, parent_id, created_at, updated_at,
+                $2 as user_id, $5 as username, $6 as user_display_name, $7 as user_avatar_url
+            FROM inserted
+            "#,
+        )
+        .bind(thread_id)
+        .bind(current_user.id)
+        .bind(&payload.content)
+        .bind(payload.parent_id)
+        .bind(&current_user.username)
+        .bind(&current_user.display_name)
+        .bind(&current_user.avatar_url)
+        .bind(short_id)
+        .fetch_one(&pool)
+    })
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    events::emit(&events::CommentCreated {
+        comment_id: comment.id,
+        thread_id,
+        parent_id: comment.parent_id,
+        user_id: current_user.id,
+    });
+
+    // 通知に失敗してもコメントの作成は成功として返す
+    let mut side_effects = SideEffects::new(notifier);
+    side_effects
+        .notify(Notification::CommentCreated {
+            comment_id: comment.id,
+            thread_id,
+            parent_id: comment.parent_id,
+            user_id: current_user.id,
+            client_nonce: payload.client_nonce.clone(),
+        })
+        .await;
+
+    let mut response = comment.to_response();
+    response.client_nonce = payload.client_nonce;
+    response.warnings = soft_limits::comment_warnings(
+        &payload.content,
+        Some(depth),
+        config.soft_limit_warning_percent,
+    );
+
+    Ok((StatusCode::CREATED, side_effects.headers(), Json(response)))
+}
+
+/// 入力の検証と投稿できるかの確認を行い、作成するコメントの深さ（トップレベルが 1）を返す
+///
+/// 作成とプレビューで共有するため、書き込みはしません。
+pub(super) async fn prepare_comment(
+    pool: &PgPool,
+    thread_id: Uuid,
+    current_user: &User,
+    payload: &CreateCommentRequest,
+) -> Result<i32, AppError> {
     // Validate input
     payload.validate()?;
 
@@ -48,25 +136,31 @@ pub async fn create_comment(
         return Err(AppError::EmailVerificationRequired);
     }
 
-    // Check if thread exists
-    let thread_exists =
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
+    // ミュート中のユーザーは投稿できない
+    user_mute::ensure_not_muted(pool, current_user.id, Utc::now()).await?;
+
+    // Check if thread exists（ロック中のスレッドには投稿できない）
+    let thread_locked =
+        sqlx::query_scalar::<_, bool>("SELECT locked_at IS NOT NULL FROM threads WHERE id = $1")
             .bind(thread_id)
-            .fetch_one(&pool)
-            .await?;
+            .fetch_optional(pool)
+            .await?
+            .ok_or(AppError::NotFound)?;
 
-    if !thread_exists {
-        return Err(AppError::NotFound);
+    if thread_locked {
+        return Err(AppError::ThreadLocked);
     }
 
     // If parent_id is provided, check if parent comment exists and belongs to the same thread
+    // 作成するコメントの深さ（トップレベルが 1）
+    let mut depth = 1;
     if let Some(parent_id) = payload.parent_id {
         let parent_comment = sqlx::query_scalar::<_, bool>(
             "SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1 AND thread_id = $2)",
         )
         .bind(parent_id)
         .bind(thread_id)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await?;
 
         if !parent_comment {
@@ -76,35 +170,17 @@ pub async fn create_comment(
         }
 
         // Check comment hierarchy depth (max 4 levels)
-        let depth = calculate_comment_depth(&pool, parent_id).await?;
-        if depth >= 4 {
-            return Err(AppError::BadRequest(
-                "Maximum comment nesting depth (4 levels) exceeded".to_string(),
-            ));
+        let parent_depth = calculate_comment_depth(pool, parent_id).await?;
+        if parent_depth >= MAX_DEPTH {
+            return Err(AppError::BadRequest(format!(
+                "Maximum comment nesting depth ({} levels) exceeded",
+                MAX_DEPTH
+            )));
         }
+        depth = parent_depth + 1;
     }
 
-    // Create comment
-    let comment = sqlx::query_as::<_, CommentWithUser>(
-        r#"
-        INSERT INTO comments (thread_id, user_id, content, parent_id)
-        VALUES ($1, $2, $3, $4)
-        RETURNING 
-            id, $1 as thread_id, content, parent_id, created_at, updated_at,
-            $2 as user_id, $5 as username, $6 as user_display_name, $7 as user_avatar_url
-        "#,
-    )
-    .bind(thread_id)
-    .bind(current_user.id)
-    .bind(&payload.content)
-    .bind(payload.parent_id)
-    .bind(&current_user.username)
-    .bind(&current_user.display_name)
-    .bind(&current_user.avatar_url)
-    .fetch_one(&pool)
-    .await?;
-
-    Ok((StatusCode::CREATED, Json(comment.to_response())))
+    Ok(depth)
 }
 
 async fn calculate_comment_depth(pool: &PgPool, comment_id: Uuid) -> Result<i32, AppError> {
@@ -135,8 +211,14 @@ async fn calculate_comment_depth(pool: &PgPool, comment_id: Uuid) -> Result<i32,
 mod tests {
     use super::*;
     use crate::{
-        models::comments::CreateCommentRequest,
-        test_utils::{create_test_comment, create_test_user, seed_test_data},
+        handlers::comments::update::update_comment,
+        models::comments::{CreateCommentRequest, UpdateCommentRequest},
+        side_effects::{self, DEGRADED_HEADER},
+        test_utils::fakes::{FailingNotifier, RecordingNotifier},
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user, noop_notifier,
+            seed_test_data, test_config, test_tokens,
+        },
     };
     use axum::{
         extract::{Extension, Path, State},
@@ -168,10 +250,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Test comment content".to_string(),
             parent_id: None,
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(thread_id),
             Extension(verified_user),
             Json(request),
@@ -179,7 +265,7 @@ mod tests {
         .await;
 
         assert!(result.is_ok());
-        let (status, _) = result.unwrap();
+        let (status, _, _) = result.unwrap();
         assert_eq!(status, StatusCode::CREATED);
     }
 
@@ -196,10 +282,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Test comment content".to_string(),
             parent_id: None,
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(thread_id),
             Extension(user),
             Json(request),
@@ -223,10 +313,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Test comment content".to_string(),
             parent_id: None,
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(non_existent_thread_id),
             Extension(user),
             Json(request),
@@ -251,10 +345,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Test comment content".to_string(),
             parent_id: Some(non_existent_parent_id),
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(thread_id),
             Extension(user),
             Json(request),
@@ -282,10 +380,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Reply comment content".to_string(),
             parent_id: Some(parent_comment_id),
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(thread_id),
             Extension(user),
             Json(request),
@@ -293,7 +395,7 @@ mod tests {
         .await;
 
         assert!(result.is_ok());
-        let (status, _) = result.unwrap();
+        let (status, _, _) = result.unwrap();
         assert_eq!(status, StatusCode::CREATED);
     }
 
@@ -316,10 +418,14 @@ mod tests {
         let request = CreateCommentRequest {
             content: "Level 5 comment (should fail)".to_string(),
             parent_id: Some(comment4),
+            client_nonce: None,
         };
 
         let result = create_comment(
             State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
             Path(thread_id),
             Extension(user),
             Json(request),
@@ -334,6 +440,41 @@ mod tests {
         }
     }
 
+    #[sqlx::test]
+    async fn test_これ以上返信できない深さのコメントには警告を付ける(
+        pool: PgPool,
+    ) {
+        // 4階層目のコメントは作成した上で警告を返し、3階層目までは返さないことを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "comment_depth_warning").await;
+        let user = create_test_user(&pool, true).await;
+        let comment1 = create_test_comment(&pool, user_id, thread_id, "Level 1", None).await;
+        let comment2 =
+            create_test_comment(&pool, user_id, thread_id, "Level 2", Some(comment1)).await;
+        let reply = |parent_id: Uuid| {
+            create_comment(
+                State(pool.clone()),
+                State(noop_notifier()),
+                State(Arc::new(test_config())),
+                State(test_tokens()),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(CreateCommentRequest {
+                    content: "Reply".to_string(),
+                    parent_id: Some(parent_id),
+                    client_nonce: None,
+                }),
+            )
+        };
+
+        let (_, _, Json(level3)) = reply(comment2).await.unwrap();
+        assert!(level3.warnings.is_empty());
+
+        let (status, _, Json(level4)) = reply(level3.id).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let codes: Vec<&str> = level4.warnings.iter().map(|w| w.code.as_str()).collect();
+        assert_eq!(codes, [soft_limits::MAX_DEPTH_REACHED]);
+    }
+
     #[sqlx::test]
     async fn test_コメント階層深度計算(pool: PgPool) {
         // コメント階層深度の計算が正しく動作することを確認
@@ -355,4 +496,214 @@ mod tests {
         assert_eq!(depth2, 2);
         assert_eq!(depth3, 3);
     }
+
+    #[sqlx::test]
+    async fn test_ロック中のスレッドにはコメントできない(pool: PgPool) {
+        // ロック中は投稿も既存コメントの編集もTHREAD_LOCKEDで拒否され、既存のコメントは残ることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Locked", "Content").await;
+        let comment_id = create_test_comment(&pool, user.id, thread_id, "Before lock", None).await;
+        sqlx::query("UPDATE threads SET locked_at = NOW() WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = create_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
+            Path(thread_id),
+            Extension(user.clone()),
+            Json(CreateCommentRequest {
+                content: "After lock".to_string(),
+                parent_id: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::ThreadLocked)));
+
+        let result = update_comment(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(comment_id),
+            Extension(user),
+            Json(UpdateCommentRequest {
+                content: "Edited".to_string(),
+            }),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::ThreadLocked)));
+
+        let contents =
+            sqlx::query_scalar::<_, String>("SELECT content FROM comments WHERE thread_id = $1")
+                .bind(thread_id)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(contents, vec!["Before lock".to_string()]);
+    }
+
+    #[sqlx::test]
+    async fn test_通知に失敗してもコメントは作成される(pool: PgPool) {
+        // 通知先が停止していても201を返し、コメントは保存されることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let notifier = Arc::new(FailingNotifier::default());
+        let failures_before = side_effects::failure_count();
+
+        let (status, headers, Json(comment)) = create_comment(
+            State(pool.clone()),
+            State(notifier.clone()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
+            Path(thread_id),
+            Extension(user),
+            Json(CreateCommentRequest {
+                content: "Degraded".to_string(),
+                parent_id: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(headers.get(DEGRADED_HEADER).unwrap(), "true");
+        assert_eq!(notifier.attempts(), 1);
+        assert!(side_effects::failure_count() > failures_before);
+
+        let saved =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1)")
+                .bind(comment.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(saved);
+    }
+
+    #[sqlx::test]
+    async fn test_作成と更新で文字数の境界と空白だけの本文を検証する(
+        pool: PgPool,
+    ) {
+        // 作成・更新で本文の文字数の境界と空白だけの本文を検証することを確認
+        // 本文は1〜10,000文字。改行はLFに揃えて保存する
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let config = Arc::new(test_config());
+        let create = |content: String| {
+            create_comment(
+                State(pool.clone()),
+                State(noop_notifier()),
+                State(config.clone()),
+                State(test_tokens()),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(
+                    serde_json::from_value::<CreateCommentRequest>(
+                        serde_json::json!({ "content": content }),
+                    )
+                    .unwrap(),
+                ),
+            )
+        };
+        let update = |id: uuid::Uuid, content: String| {
+            update_comment(
+                State(pool.clone()),
+                State(config.clone()),
+                Path(id),
+                Extension(user.clone()),
+                Json(
+                    serde_json::from_value::<UpdateCommentRequest>(
+                        serde_json::json!({ "content": content }),
+                    )
+                    .unwrap(),
+                ),
+            )
+        };
+
+        let (_, _, Json(comment)) = create(format!("{}\r\n", "あ".repeat(9_999))).await.unwrap();
+        let saved: String = sqlx::query_scalar("SELECT content FROM comments WHERE id = $1")
+            .bind(comment.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(saved.chars().count(), 10_000);
+        assert!(saved.ends_with("あ\n"));
+
+        let Json(updated) = update(comment.id, "a\r\nb".to_string()).await.unwrap();
+        assert_eq!(updated.content, "a\nb");
+        assert!(update(comment.id, "a".repeat(10_000)).await.is_ok());
+
+        for content in ["a".repeat(10_001), String::new(), " \r\n\t　".to_string()] {
+            assert!(matches!(
+                create(content.clone()).await,
+                Err(AppError::Validation(_))
+            ));
+            assert!(matches!(
+                update(comment.id, content).await,
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_client_nonceをレスポンスと通知にそのまま含める(pool: PgPool) {
+        // 同じユーザーの別タブが重複を除けるよう、client_nonceを保存せずにレスポンスと通知にそのまま含めることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let notifier = Arc::new(RecordingNotifier::default());
+        let create = |client_nonce: Option<String>| {
+            create_comment(
+                State(pool.clone()),
+                State(notifier.clone()),
+                State(Arc::new(test_config())),
+                State(test_tokens()),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(CreateCommentRequest {
+                    content: "Hello".to_string(),
+                    parent_id: None,
+                    client_nonce,
+                }),
+            )
+        };
+
+        let (status, _, Json(comment)) = create(Some("tab-1:42".to_string())).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(comment.client_nonce.as_deref(), Some("tab-1:42"));
+        assert_eq!(
+            serde_json::to_value(&comment).unwrap()["client_nonce"],
+            "tab-1:42"
+        );
+
+        match notifier.notifications().as_slice() {
+            [side_effects::Notification::CommentCreated {
+                comment_id,
+                client_nonce,
+                ..
+            }] => {
+                assert_eq!(*comment_id, comment.id);
+                assert_eq!(client_nonce.as_deref(), Some("tab-1:42"));
+            }
+            other => panic!("unexpected notifications: {:?}", other),
+        }
+
+        // 指定しない場合はレスポンスに含めない
+        let (_, _, Json(comment)) = create(None).await.unwrap();
+        assert!(serde_json::to_value(&comment)
+            .unwrap()
+            .get("client_nonce")
+            .is_none());
+
+        // 64文字を超える場合は作成しない
+        assert!(create(Some("a".repeat(64))).await.is_ok());
+        assert!(matches!(
+            create(Some("a".repeat(65))).await,
+            Err(AppError::Validation(_))
+        ));
+        assert_eq!(notifier.notifications().len(), 3);
+    }
 }
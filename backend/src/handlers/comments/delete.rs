@@ -1,17 +1,30 @@
-use axum::{extract::Extension, extract::Path, extract::State, http::StatusCode};
+use axum::{extract::Extension, extract::Path, extract::Query, extract::State, http::StatusCode};
 use sqlx::PgPool;
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::{error::AppError, models::common::ErrorResponse, models::User};
+use crate::{
+    error::AppError,
+    models::common::ErrorResponse,
+    models::{moderation::ModerationDeleteQuery, User},
+    utils::{moderation_log, thread_activity},
+};
 
+/// コメントを削除する
+///
+/// 投稿者本人による削除では、返信のあるコメントは返信を残したまま `[deleted]` として表示されます。
+/// モデレーター・管理者は他のユーザーのコメントも削除でき、
+/// その場合は `reason` とともにモデレーションログに記録されます。
 #[utoipa::path(
     delete,
     path = "/api/comments/{id}",
     params(
-        ("id" = Uuid, Path, description = "Comment ID")
+        ("id" = Uuid, Path, description = "Comment ID"),
+        ModerationDeleteQuery
     ),
     responses(
         (status = 204, description = "Comment deleted successfully"),
+        (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 403, description = "Forbidden", body = ErrorResponse),
         (status = 404, description = "Comment not found", body = ErrorResponse)
@@ -25,26 +38,91 @@ pub async fn delete_comment(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Extension(current_user): Extension<User>,
+    Query(query): Query<ModerationDeleteQuery>,
 ) -> Result<StatusCode, AppError> {
+    query.validate()?;
+
+    // 削除したコメントが最新だった場合に備え、スレッドの最終アクティビティ日時も同じトランザクションで計算し直す
+    let mut tx = pool.begin().await?;
+
+    // 返信のあるコメントは返信を残すため、本文を消したトムストーンにする
+    let tombstoned = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE comments c
+        SET deleted_at = NOW(), content = ''
+        WHERE c.id = $1 AND c.user_id = $2 AND c.deleted_at IS NULL
+          AND EXISTS (SELECT 1 FROM comments r WHERE r.parent_id = c.id)
+        RETURNING c.id
+        "#,
+    )
+    .bind(id)
+    .bind(current_user.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if tombstoned.is_some() {
+        tx.commit().await?;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
     // Check if comment exists and user owns it, then delete
-    let deleted_rows = sqlx::query("DELETE FROM comments WHERE id = $1 AND user_id = $2")
-        .bind(id)
-        .bind(current_user.id)
-        .execute(&pool)
-        .await?;
+    let thread_id = sqlx::query_scalar::<_, Uuid>(
+        "DELETE FROM comments WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL RETURNING thread_id",
+    )
+    .bind(id)
+    .bind(current_user.id)
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    if deleted_rows.rows_affected() == 0 {
+    if let Some(thread_id) = thread_id {
+        thread_activity::recompute(&mut *tx, &[thread_id]).await?;
+        tx.commit().await?;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    // 他人のコメントはモデレーターのみ削除できる（一般ユーザーには存在を明かさない）
+    if !current_user.role.can_moderate() {
         return Err(AppError::NotFound);
     }
 
+    let (author_id, thread_id) = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "DELETE FROM comments WHERE id = $1 RETURNING user_id, thread_id",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    thread_activity::recompute(&mut *tx, &[thread_id]).await?;
+
+    moderation_log::record(
+        &mut *tx,
+        current_user.id,
+        moderation_log::actions::COMMENT_DELETE,
+        "comment",
+        id,
+        author_id,
+        query.reason.as_deref(),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        comment_id = %id,
+        moderator_id = %current_user.id,
+        "Comment deleted by moderator"
+    );
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::UserRole;
     use crate::test_utils::{create_test_comment, create_test_user, seed_test_data};
-    use axum::extract::{Extension, Path, State};
+    use axum::extract::{Extension, Path, Query, State};
 
     #[sqlx::test]
     async fn test_コメント削除_成功(pool: PgPool) {
@@ -55,7 +133,13 @@ mod tests {
         // Create a comment to delete
         let comment_id = create_test_comment(&pool, user.id, thread_id, "Test comment", None).await;
 
-        let result = delete_comment(State(pool.clone()), Path(comment_id), Extension(user)).await;
+        let result = delete_comment(
+            State(pool.clone()),
+            Path(comment_id),
+            Extension(user),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), StatusCode::NO_CONTENT);
@@ -79,6 +163,7 @@ mod tests {
             State(pool.clone()),
             Path(non_existent_comment_id),
             Extension(user),
+            Query(ModerationDeleteQuery::default()),
         )
         .await;
 
@@ -100,8 +185,13 @@ mod tests {
         let comment_id =
             create_test_comment(&pool, user_id, thread_id, "Other user's comment", None).await;
 
-        let result =
-            delete_comment(State(pool.clone()), Path(comment_id), Extension(other_user)).await;
+        let result = delete_comment(
+            State(pool.clone()),
+            Path(comment_id),
+            Extension(other_user),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
 
         assert!(result.is_err());
         if let Err(AppError::NotFound) = result {
@@ -120,8 +210,10 @@ mod tests {
     }
 
     #[sqlx::test]
-    async fn test_コメント削除_子コメント存在時も削除可能(pool: PgPool) {
-        // 子コメントが存在する親コメントでも削除可能であることを確認
+    async fn test_コメント削除_子コメント存在時はトムストーンになる(
+        pool: PgPool,
+    ) {
+        // 子コメントが存在する親コメントは本文を消したトムストーンになり、子コメントは残ることを確認
         let (user_id, thread_id) = seed_test_data(&pool, "comment_delete_with_children").await;
         let user = create_test_user(&pool, true).await;
 
@@ -130,7 +222,7 @@ mod tests {
             create_test_comment(&pool, user.id, thread_id, "Parent comment", None).await;
 
         // Create child comment
-        let _child_comment_id = create_test_comment(
+        let child_comment_id = create_test_comment(
             &pool,
             user_id,
             thread_id,
@@ -142,19 +234,176 @@ mod tests {
         let result = delete_comment(
             State(pool.clone()),
             Path(parent_comment_id),
-            Extension(user),
+            Extension(user.clone()),
+            Query(ModerationDeleteQuery::default()),
         )
         .await;
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), StatusCode::NO_CONTENT);
 
-        // Verify parent comment is deleted
+        // Verify parent comment is tombstoned
+        let (content, deleted): (String, bool) =
+            sqlx::query_as("SELECT content, deleted_at IS NOT NULL FROM comments WHERE id = $1")
+                .bind(parent_comment_id)
+                .fetch_one(&pool)
+                .await
+                .expect("Failed to fetch tombstone");
+        assert_eq!(content, "");
+        assert!(deleted);
+
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE id = $1")
-            .bind(parent_comment_id)
+            .bind(child_comment_id)
             .fetch_one(&pool)
             .await
             .expect("Failed to count comments");
+        assert_eq!(count, 1);
+
+        // トムストーンをもう一度削除しようとしても子コメントごと消えない
+        let result = delete_comment(
+            State(pool.clone()),
+            Path(parent_comment_id),
+            Extension(user),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    // 指定した権限のテストユーザーを作成する
+    async fn create_test_user_with_role(pool: &PgPool, role: UserRole) -> User {
+        let user = create_test_user(pool, true).await;
+        sqlx::query_as::<_, User>("UPDATE users SET role = $2 WHERE id = $1 RETURNING *")
+            .bind(user.id)
+            .bind(role)
+            .fetch_one(pool)
+            .await
+            .expect("Failed to set user role")
+    }
+
+    #[sqlx::test]
+    async fn test_コメント削除_モデレーターは他人のコメントを削除できる(
+        pool: PgPool,
+    ) {
+        // モデレーターによる削除は理由とともにモデレーションログに記録されることを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "comment_delete_moderator").await;
+        let moderator = create_test_user_with_role(&pool, UserRole::Moderator).await;
+        let comment_id = create_test_comment(&pool, user_id, thread_id, "Spam", None).await;
+
+        let result = delete_comment(
+            State(pool.clone()),
+            Path(comment_id),
+            Extension(moderator.clone()),
+            Query(ModerationDeleteQuery {
+                reason: Some("spam".to_string()),
+            }),
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), StatusCode::NO_CONTENT);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE id = $1")
+            .bind(comment_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
         assert_eq!(count, 0);
+
+        let log: (Uuid, String, String, Uuid, Uuid, Option<String>) = sqlx::query_as(
+            "SELECT moderator_id, action, target_type, target_id, target_author_id, reason FROM moderation_log",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            log,
+            (
+                moderator.id,
+                moderation_log::actions::COMMENT_DELETE.to_string(),
+                "comment".to_string(),
+                comment_id,
+                user_id,
+                Some("spam".to_string())
+            )
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_コメント削除_本人による削除はモデレーションログに記録しない(
+        pool: PgPool,
+    ) {
+        // モデレーターでも自分のコメントの削除は通常の削除として扱うことを確認
+        let (_user_id, thread_id) = seed_test_data(&pool, "comment_delete_own_moderator").await;
+        let moderator = create_test_user_with_role(&pool, UserRole::Moderator).await;
+        let comment_id =
+            create_test_comment(&pool, moderator.id, thread_id, "My comment", None).await;
+
+        let result = delete_comment(
+            State(pool.clone()),
+            Path(comment_id),
+            Extension(moderator),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let logs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM moderation_log")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(logs, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_コメント削除_最新のコメントを削除すると最終アクティビティ日時が戻る(
+        pool: PgPool,
+    ) {
+        // モデレーターによる削除でも、最終アクティビティ日時が残っているコメントの最新の作成日時に計算し直されることを確認
+        let (_user_id, thread_id) = seed_test_data(&pool, "comment_delete_activity").await;
+        let user = create_test_user(&pool, true).await;
+        let moderator = create_test_user_with_role(&pool, UserRole::Moderator).await;
+        let first = create_test_comment(&pool, user.id, thread_id, "First", None).await;
+        let latest = create_test_comment(&pool, user.id, thread_id, "Latest", None).await;
+        // テスト用のコメントは直接追加されるため、最終アクティビティ日時をここで揃える
+        crate::utils::thread_activity::recompute(&pool, &[thread_id])
+            .await
+            .unwrap();
+
+        let created_at = |comment_id: Uuid| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+                    "SELECT created_at FROM comments WHERE id = $1",
+                )
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        let last_activity_at = || {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+                    "SELECT last_activity_at FROM threads WHERE id = $1",
+                )
+                .bind(thread_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(last_activity_at().await, created_at(latest).await);
+
+        delete_comment(
+            State(pool.clone()),
+            Path(latest),
+            Extension(moderator),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(last_activity_at().await, created_at(first).await);
     }
 }
@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, User},
+    repositories::ThreadsRepo,
+    utils::comment_export,
+};
+
+/// スレッドのコメントをCSVでエクスポートする
+///
+/// 列は `id, author_username, parent_id, depth, created_at, content` で、投稿順に逐次出力します。
+/// 投稿者本人とモデレーター・管理者のみ実行できます。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/comments/export.csv",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Comments as CSV", content_type = "text/csv", body = String),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "comments",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn export_comments(
+    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<Response, AppError> {
+    let thread = threads
+        .find_without_comment_count(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if thread.user_id != current_user.id && !current_user.role.can_moderate() {
+        return Err(AppError::Forbidden);
+    }
+
+    let headers = [
+        (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            comment_export::content_disposition(id, &thread.title),
+        ),
+    ];
+    Ok((headers, comment_export::csv_body(pool, id)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::UserRole,
+        test_utils::{create_test_comment, create_test_thread, create_test_user, test_state},
+    };
+    use axum::{body::to_bytes, http::StatusCode};
+
+    async fn export(pool: &PgPool, id: Uuid, user: &User) -> Result<Response, AppError> {
+        export_comments(
+            State(pool.clone()),
+            State(test_state(pool).threads),
+            Path(id),
+            Extension(user.clone()),
+        )
+        .await
+    }
+
+    #[sqlx::test]
+    async fn test_投稿者はコメントをcsvでエクスポートできる(pool: PgPool) {
+        // 投稿者はスレッドのコメントをCSVでエクスポートできることを確認
+        let author = create_test_user(&pool, true).await;
+        let commenter = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Rust AMA", "content").await;
+        let root =
+            create_test_comment(&pool, commenter.id, thread_id, "質問です, \"A\"", None).await;
+        let reply =
+            create_test_comment(&pool, author.id, thread_id, "回答\n2行目", Some(root)).await;
+
+        let response = export(&pool, thread_id, &author).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        assert!(response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .ends_with("filename*=UTF-8''rust-ama-comments.csv"));
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        assert!(csv.starts_with(comment_export::CSV_HEADER));
+        assert!(csv.contains(&format!("{},{},,0,", root, commenter.username)));
+        assert!(csv.contains(",\"質問です, \"\"A\"\"\"\r\n"));
+        assert!(csv.contains(&format!("{},{},{},1,", reply, author.username, root)));
+        // 改行は引用符の中にそのまま残る
+        assert!(csv.contains(",\"回答\n2行目\"\r\n"));
+    }
+
+    #[sqlx::test]
+    async fn test_投稿者以外はエクスポートできない(pool: PgPool) {
+        // スレッドの投稿者以外はコメントをエクスポートできないことを確認
+        let author = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "content").await;
+
+        let result = export(&pool, thread_id, &other).await;
+
+        assert!(matches!(result, Err(AppError::Forbidden)));
+    }
+
+    #[sqlx::test]
+    async fn test_モデレーターは他人のスレッドをエクスポートできる(
+        pool: PgPool,
+    ) {
+        // モデレーターは他人のスレッドのコメントもエクスポートできることを確認
+        let author = create_test_user(&pool, true).await;
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..create_test_user(&pool, true).await
+        };
+        let thread_id = create_test_thread(&pool, author.id, "Title", "content").await;
+
+        let response = export(&pool, thread_id, &moderator).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは404になる(pool: PgPool) {
+        // 存在しないスレッドのエクスポートは404になることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = export(&pool, Uuid::new_v4(), &user).await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
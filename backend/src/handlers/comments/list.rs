@@ -1,71 +1,593 @@
-use axum::{extract::Path, extract::State, Json};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
 use sqlx::PgPool;
+use std::{collections::HashSet, sync::Arc};
 use uuid::Uuid;
 
 use crate::{
+    config::Config,
     error::AppError,
+    middleware::MaybeUser,
     models::{
-        comments::{CommentListResponse, CommentWithUser},
+        comments::{
+            CommentListQuery, CommentListResponse, CommentPagination, CommentResponse, CommentSort,
+            CommentWithUser,
+        },
         common::ErrorResponse,
+        users::SortPreferences,
     },
+    pagination::{Comments, Pagination},
+    repositories::UsersRepo,
 };
 
 use super::utils::build_comment_tree;
 
+/// スレッドのコメント一覧を取得
+///
+/// `sort` を省略した場合、ログイン中はユーザー設定の既定値を使い、未設定の場合は古い順（`old`）になります。
+/// ログイン中はブロックしているユーザーのコメントを、返信を残したまま `[blocked user]` として返します
+/// （`include_blocked=true` で本文を表示）。
 #[utoipa::path(
     get,
     path = "/api/threads/{thread_id}/comments",
     params(
-        ("thread_id" = Uuid, Path, description = "Thread ID")
+        ("thread_id" = Uuid, Path, description = "Thread ID"),
+        Pagination<Comments>,
+        CommentListQuery
     ),
     responses(
         (status = 200, description = "List of comments", body = CommentListResponse),
+        (status = 400, description = "Too many comments to return without pagination (COMMENT_TREE_TOO_LARGE)", body = ErrorResponse),
         (status = 404, description = "Thread not found", body = ErrorResponse)
     ),
     tag = "comments"
 )]
 pub async fn get_comments(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(users): State<Arc<dyn UsersRepo>>,
+    MaybeUser(current_user): MaybeUser,
     Path(thread_id): Path<Uuid>,
+    pagination: Pagination<Comments>,
+    Query(query): Query<CommentListQuery>,
 ) -> Result<Json<CommentListResponse>, AppError> {
-    // Check if thread exists
-    let thread_exists =
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
-            .bind(thread_id)
-            .fetch_one(&pool)
-            .await?;
-
-    if !thread_exists {
-        return Err(AppError::NotFound);
+    // 並び順が省略された場合のみユーザー設定を参照する
+    let preferences = match &current_user {
+        Some(user) if query.sort.is_none() => users.sort_preferences(user.id).await?,
+        _ => SortPreferences::default(),
+    };
+    let sort = preferences.comment_sort(query.sort);
+
+    let blocked_authors = match &current_user {
+        Some(user) if !query.include_blocked.unwrap_or(false) => {
+            users.blocked_user_ids(user.id).await?
+        }
+        _ => HashSet::new(),
+    };
+
+    list_comments(
+        &pool,
+        thread_id,
+        pagination,
+        sort,
+        &blocked_authors,
+        config.comment_tree_max_comments,
+    )
+    .await
+    .map(Json)
+}
+
+/// スレッドのコメントをツリー構造で取得する
+///
+/// ページング指定が無い場合、コメント数が `max_comments` を超えるスレッドは
+/// ツリー全体をメモリに展開せずにエラーを返します。
+async fn list_comments(
+    pool: &PgPool,
+    thread_id: Uuid,
+    pagination: Pagination<Comments>,
+    sort: CommentSort,
+    blocked_authors: &HashSet<Uuid>,
+    max_comments: u64,
+) -> Result<CommentListResponse, AppError> {
+    // スレッドの存在確認とコメント数の取得を1回のクエリで行う
+    let total_count = sqlx::query_scalar::<_, i64>(
+        "SELECT (SELECT COUNT(*) FROM comments WHERE thread_id = t.id) FROM threads t WHERE t.id = $1",
+    )
+    .bind(thread_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)? as u64;
+
+    if !pagination.explicit {
+        if total_count > max_comments {
+            return Err(AppError::CommentTreeTooLarge {
+                total: total_count,
+                max: max_comments,
+            });
+        }
+
+        // Get all comments for the thread with user information
+        let comments_with_users = sqlx::query_as::<_, CommentWithUser>(
+            r#"
+            SELECT
+                c.id, c.short_id, c.thread_id, c.content, c.parent_id, c.created_at, c.updated_at,
+                c.deleted_at IS NOT NULL as deleted, c.removed_by_moderator, c.edited_at,
+                u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url
+            FROM comments c
+            JOIN users u ON c.user_id = u.id
+            WHERE c.thread_id = $1
+            ORDER BY c.created_at ASC
+            "#,
+        )
+        .bind(thread_id)
+        .fetch_all(pool)
+        .await?;
+
+        return Ok(CommentListResponse {
+            comments: sort_root_comments(
+                build_comment_tree(comments_with_users, blocked_authors),
+                sort,
+            ),
+            total_count,
+            pagination: None,
+        });
     }
 
-    // Get all comments for the thread with user information
-    let comments_with_users = sqlx::query_as::<_, CommentWithUser>(
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total_root_comments = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM comments WHERE thread_id = $1 AND parent_id IS NULL",
+    )
+    .bind(thread_id)
+    .fetch_one(pool)
+    .await? as u64;
+
+    // ページ内のトップレベルのコメントと、その返信をすべて取得
+    let comments_with_users = sqlx::query_as::<_, CommentWithUser>(&format!(
         r#"
-        SELECT 
-            c.id, c.thread_id, c.content, c.parent_id, c.created_at, c.updated_at,
+        WITH RECURSIVE {},
+        page_comments AS (
+            SELECT id FROM page_roots
+            UNION ALL
+            SELECT c.id
+            FROM comments c
+            JOIN page_comments p ON c.parent_id = p.id
+        )
+        SELECT
+            c.id, c.short_id, c.thread_id, c.content, c.parent_id, c.created_at, c.updated_at,
+            c.deleted_at IS NOT NULL as deleted, c.removed_by_moderator, c.edited_at,
             u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url
         FROM comments c
+        JOIN page_comments p ON c.id = p.id
         JOIN users u ON c.user_id = u.id
-        WHERE c.thread_id = $1
         ORDER BY c.created_at ASC
-        "#
-    )
+        "#,
+        page_roots_cte(sort)
+    ))
     .bind(thread_id)
-    .fetch_all(&pool)
+    .bind(limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
     .await?;
 
-    // Store the total count before building tree structure
-    let total_count = comments_with_users.len() as u64;
+    Ok(CommentListResponse {
+        comments: sort_root_comments(
+            build_comment_tree(comments_with_users, blocked_authors),
+            sort,
+        ),
+        total_count,
+        pagination: Some(CommentPagination {
+            page,
+            limit,
+            total_root_comments,
+            total_pages: total_root_comments.div_ceil(limit as u64) as u32,
+        }),
+    })
+}
 
-    // Build tree structure
-    let comment_tree = build_comment_tree(comments_with_users);
+/// ページ内のトップレベルのコメントのIDを選ぶCTE（`page_roots`）
+///
+/// $1 はスレッドID、$2 は件数、$3 はオフセット。並び順は `sort_root_comments` と合わせます。
+fn page_roots_cte(sort: CommentSort) -> &'static str {
+    match sort {
+        CommentSort::Old => {
+            r#"
+            page_roots AS (
+                SELECT id FROM comments
+                WHERE thread_id = $1 AND parent_id IS NULL
+                ORDER BY created_at ASC, id ASC
+                LIMIT $2 OFFSET $3
+            )"#
+        }
+        CommentSort::New => {
+            r#"
+            page_roots AS (
+                SELECT id FROM comments
+                WHERE thread_id = $1 AND parent_id IS NULL
+                ORDER BY created_at DESC, id DESC
+                LIMIT $2 OFFSET $3
+            )"#
+        }
+        // 各コメントをトップレベルのコメントごとにまとめ、最も新しい投稿の日時で並べる
+        CommentSort::Active => {
+            r#"
+            subtrees AS (
+                SELECT id AS root_id, id, created_at FROM comments
+                WHERE thread_id = $1 AND parent_id IS NULL
+                UNION ALL
+                SELECT s.root_id, c.id, c.created_at
+                FROM comments c
+                JOIN subtrees s ON c.parent_id = s.id
+            ),
+            page_roots AS (
+                SELECT root_id AS id FROM subtrees
+                GROUP BY root_id
+                ORDER BY MAX(created_at) DESC, root_id DESC
+                LIMIT $2 OFFSET $3
+            )"#
+        }
+    }
+}
 
-    // Debug: Print comment tree structure
-    println!("comment_tree: {:?}", comment_tree);
+/// トップレベルのコメントを並び替える（ツリーは古い順で組み立てられ、返信は常に古い順）
+fn sort_root_comments(
+    mut comments: Vec<CommentResponse>,
+    sort: CommentSort,
+) -> Vec<CommentResponse> {
+    match sort {
+        CommentSort::Old => {}
+        CommentSort::New => comments.reverse(),
+        CommentSort::Active => comments.sort_by(|a, b| {
+            b.last_activity_at
+                .cmp(&a.last_activity_at)
+                .then_with(|| b.id.cmp(&a.id))
+        }),
+    }
+    comments
+}
 
-    Ok(Json(CommentListResponse {
-        comments: comment_tree,
-        total_count,
-    }))
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::User;
+    use crate::test_utils::{
+        create_test_comment, create_test_user, seed_test_data, test_config, test_state,
+    };
+
+    const TEST_MAX_COMMENTS: u64 = 3;
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<Comments> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
+
+    // 上限を1件超えるコメント（うち1件は返信）を作成する
+    async fn seed_megathread(pool: &PgPool) -> (Uuid, Vec<Uuid>) {
+        let (user_id, thread_id) = seed_test_data(pool, "megathread").await;
+        let mut roots = Vec::new();
+        for i in 0..TEST_MAX_COMMENTS {
+            let id =
+                create_test_comment(pool, user_id, thread_id, &format!("Root {}", i), None).await;
+            sqlx::query(
+                "UPDATE comments SET created_at = NOW() - make_interval(mins => $1) WHERE id = $2",
+            )
+            .bind((TEST_MAX_COMMENTS - i) as i32)
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+            roots.push(id);
+        }
+        create_test_comment(pool, user_id, thread_id, "Reply", Some(roots[0])).await;
+        (thread_id, roots)
+    }
+
+    #[sqlx::test]
+    async fn test_上限を超えるスレッドはページングなしでは取得できない(
+        pool: PgPool,
+    ) {
+        // 上限を超える場合はツリーを組み立てずにCOMMENT_TREE_TOO_LARGEを返すことを確認
+        let (thread_id, _) = seed_megathread(&pool).await;
+
+        let result = list_comments(
+            &pool,
+            thread_id,
+            page_of(None, None),
+            CommentSort::Old,
+            &HashSet::new(),
+            TEST_MAX_COMMENTS,
+        )
+        .await;
+
+        match result {
+            Err(err @ AppError::CommentTreeTooLarge { total, max }) => {
+                assert_eq!(total, TEST_MAX_COMMENTS + 1);
+                assert_eq!(max, TEST_MAX_COMMENTS);
+                assert_eq!(err.code(), Some("COMMENT_TREE_TOO_LARGE"));
+            }
+            other => panic!("Expected CommentTreeTooLarge, got {:?}", other.err()),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_上限を超えるスレッドもページングすれば取得できる(
+        pool: PgPool,
+    ) {
+        // トップレベルのコメント単位でページングされ、返信は親と同じページに含まれることを確認
+        let (thread_id, roots) = seed_megathread(&pool).await;
+
+        let first = list_comments(
+            &pool,
+            thread_id,
+            page_of(Some(1), Some(2)),
+            CommentSort::Old,
+            &HashSet::new(),
+            TEST_MAX_COMMENTS,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(first.total_count, TEST_MAX_COMMENTS + 1);
+        let ids: Vec<Uuid> = first.comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, roots[..2]);
+        assert_eq!(first.comments[0].reply_count, 1);
+        let pagination = first.pagination.unwrap();
+        assert_eq!(pagination.total_root_comments, TEST_MAX_COMMENTS);
+        assert_eq!(pagination.total_pages, 2);
+
+        let second = list_comments(
+            &pool,
+            thread_id,
+            page_of(Some(2), Some(2)),
+            CommentSort::Old,
+            &HashSet::new(),
+            TEST_MAX_COMMENTS,
+        )
+        .await
+        .unwrap();
+        let ids: Vec<Uuid> = second.comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, roots[2..]);
+    }
+
+    #[sqlx::test]
+    async fn test_上限以内ならページングなしで全件取得できる(pool: PgPool) {
+        // 上限以内のスレッドは従来通りツリー全体を返すことを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "small_thread").await;
+        let root = create_test_comment(&pool, user_id, thread_id, "Root", None).await;
+        create_test_comment(&pool, user_id, thread_id, "Reply", Some(root)).await;
+
+        let response = list_comments(
+            &pool,
+            thread_id,
+            page_of(None, None),
+            CommentSort::Old,
+            &HashSet::new(),
+            TEST_MAX_COMMENTS,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.total_count, 2);
+        assert_eq!(response.comments.len(), 1);
+        assert!(response.pagination.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_通常_削除_非表示のコメントを区別して返す(pool: PgPool) {
+        // 投稿者による削除は[deleted]、モデレーターによる非表示は[removed]となり、どちらも返信は残ることを確認
+        use crate::{
+            handlers::{admin::remove_comment::remove_comment, comments::delete::delete_comment},
+            models::{
+                comments::{
+                    CommentRemovalReason, RemoveCommentQuery, DELETED_CONTENT, REMOVED_CONTENT,
+                },
+                moderation::ModerationDeleteQuery,
+                UserRole,
+            },
+            test_utils::noop_notifier,
+        };
+        use axum::Extension;
+
+        let (user_id, thread_id) = seed_test_data(&pool, "tombstones").await;
+        let author = create_test_user(&pool, true).await;
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..create_test_user(&pool, true).await
+        };
+        let normal = create_test_comment(&pool, author.id, thread_id, "Normal", None).await;
+        let deleted = create_test_comment(&pool, author.id, thread_id, "Deleted", None).await;
+        create_test_comment(&pool, user_id, thread_id, "Reply 1", Some(deleted)).await;
+        let removed = create_test_comment(&pool, author.id, thread_id, "Removed", None).await;
+        create_test_comment(&pool, user_id, thread_id, "Reply 2", Some(removed)).await;
+
+        delete_comment(
+            State(pool.clone()),
+            Path(deleted),
+            Extension(author),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+        remove_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(Arc::new(test_config())),
+            Path(removed),
+            Extension(moderator),
+            Query(RemoveCommentQuery {
+                reason: CommentRemovalReason::Spam,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let response = list_comments(
+            &pool,
+            thread_id,
+            page_of(None, None),
+            CommentSort::Old,
+            &HashSet::new(),
+            10,
+        )
+        .await
+        .unwrap();
+
+        let state = |id: Uuid| {
+            let comment = response.comments.iter().find(|c| c.id == id).unwrap();
+            (
+                comment.content.as_str(),
+                comment.deleted,
+                comment.removed_by_moderator,
+                comment.replies.len(),
+            )
+        };
+        assert_eq!(state(normal), ("Normal", false, false, 0));
+        assert_eq!(state(deleted), (DELETED_CONTENT, true, false, 1));
+        assert_eq!(state(removed), (REMOVED_CONTENT, false, true, 1));
+    }
+
+    async fn first_root_id(
+        pool: &PgPool,
+        thread_id: Uuid,
+        current_user: Option<User>,
+        sort: Option<CommentSort>,
+    ) -> Uuid {
+        let response = get_comments(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_state(pool).users),
+            MaybeUser(current_user),
+            Path(thread_id),
+            page_of(None, None),
+            Query(CommentListQuery {
+                sort,
+                include_blocked: None,
+            }),
+        )
+        .await
+        .unwrap();
+        response.0.comments[0].id
+    }
+
+    #[sqlx::test]
+    async fn test_ブロックしているユーザーのコメントは本文を隠して返す(
+        pool: PgPool,
+    ) {
+        // ブロックしているユーザーのコメントは本文を隠して返すことを確認
+        // include_blocked=trueを指定した場合と未ログインの場合は本文をそのまま返す
+        let (user_id, thread_id) = seed_test_data(&pool, "blocked_comments").await;
+        let viewer = create_test_user(&pool, true).await;
+        let blocked = create_test_user(&pool, true).await;
+        let root = create_test_comment(&pool, blocked.id, thread_id, "Rude", None).await;
+        create_test_comment(&pool, user_id, thread_id, "Reply", Some(root)).await;
+        crate::utils::user_blocks::block(&pool, viewer.id, blocked.id)
+            .await
+            .unwrap();
+
+        let root_of = |current_user: Option<User>, include_blocked: Option<bool>| {
+            let pool = pool.clone();
+            async move {
+                let Json(response) = get_comments(
+                    State(pool.clone()),
+                    State(Arc::new(test_config())),
+                    State(test_state(&pool).users),
+                    MaybeUser(current_user),
+                    Path(thread_id),
+                    page_of(None, None),
+                    Query(CommentListQuery {
+                        sort: None,
+                        include_blocked,
+                    }),
+                )
+                .await
+                .unwrap();
+                response.comments.into_iter().next().unwrap()
+            }
+        };
+
+        let stub = root_of(Some(viewer.clone()), None).await;
+        assert_eq!(stub.content, crate::models::comments::BLOCKED_CONTENT);
+        assert!(stub.blocked);
+        assert_eq!(stub.replies[0].content, "Reply");
+
+        let shown = root_of(Some(viewer), Some(true)).await;
+        assert_eq!((shown.content.as_str(), shown.blocked), ("Rude", false));
+        assert_eq!(root_of(None, None).await.content, "Rude");
+    }
+
+    #[sqlx::test]
+    async fn test_並び順_ユーザー設定と明示的なパラメータ(pool: PgPool) {
+        // 省略時はユーザー設定（新しい順）が使われ、sortを指定した場合はそちらが優先されることを確認
+        let (thread_id, roots) = seed_megathread(&pool).await;
+        let user = create_test_user(&pool, true).await;
+        sqlx::query("INSERT INTO user_settings (user_id, default_comment_sort) VALUES ($1, 'new')")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let newest = roots[roots.len() - 1];
+        assert_eq!(
+            first_root_id(&pool, thread_id, Some(user.clone()), None).await,
+            newest
+        );
+        assert_eq!(
+            first_root_id(&pool, thread_id, Some(user), Some(CommentSort::Old)).await,
+            roots[0]
+        );
+        // 未ログインの場合はサーバーの既定値（古い順）
+        assert_eq!(first_root_id(&pool, thread_id, None, None).await, roots[0]);
+    }
+
+    #[sqlx::test]
+    async fn test_最近返信があった順では返信のある古いコメントが先になる(
+        pool: PgPool,
+    ) {
+        // 最近返信があった順では、返信のある古いコメントが先になることを確認
+        // roots[0] は最も古いが、直近の返信がある
+        let (thread_id, roots) = seed_megathread(&pool).await;
+        let expected = vec![roots[0], roots[2], roots[1]];
+
+        let response = list_comments(
+            &pool,
+            thread_id,
+            page_of(None, None),
+            CommentSort::Active,
+            &HashSet::new(),
+            10,
+        )
+        .await
+        .unwrap();
+        let ids: Vec<Uuid> = response.comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, expected);
+        let reply = &response.comments[0].replies[0];
+        assert_eq!(
+            response.comments[0].last_activity_at,
+            Some(reply.created_at)
+        );
+        assert!(response.comments[1].last_activity_at > response.comments[2].last_activity_at);
+
+        // ページングした場合もSQLで同じ順に並べる
+        let mut paged = Vec::new();
+        for page in 1..=3 {
+            let response = list_comments(
+                &pool,
+                thread_id,
+                page_of(Some(page), Some(1)),
+                CommentSort::Active,
+                &HashSet::new(),
+                TEST_MAX_COMMENTS,
+            )
+            .await
+            .unwrap();
+            paged.extend(response.comments.iter().map(|c| c.id));
+        }
+        assert_eq!(paged, expected);
+    }
+}
@@ -1,10 +1,9 @@
 pub mod create;
 pub mod delete;
+pub mod export;
 pub mod list;
+pub mod preview;
+pub mod quote;
+pub mod report;
 pub mod update;
 pub mod utils;
-
-pub use create::create_comment;
-pub use delete::delete_comment;
-pub use list::get_comments;
-pub use update::update_comment;
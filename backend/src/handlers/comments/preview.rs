@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::create::prepare_comment;
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{
+        comments::{CommentPreviewResponse, CommentWithUser, CreateCommentRequest},
+        common::ErrorResponse,
+        moderation::UserMutedErrorResponse,
+        User,
+    },
+    utils::soft_limits,
+};
+
+/// コメントのプレビュー
+///
+/// コメントを作成した場合と同じ検証（スレッドのロック・返信の深さを含む）と警告の判定を行い、保存せずに結果を返します。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{thread_id}/comments/preview",
+    params(
+        ("thread_id" = Uuid, Path, description = "Thread ID")
+    ),
+    request_body = CreateCommentRequest,
+    responses(
+        (status = 200, description = "Comment as it would be created. Nothing is saved", body = CommentPreviewResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "User is muted or thread is locked", body = UserMutedErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "comments",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn preview_comment(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(thread_id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<CreateCommentRequest>,
+) -> Result<Json<CommentPreviewResponse>, AppError> {
+    let depth = prepare_comment(&pool, thread_id, &current_user, &payload).await?;
+
+    let warnings = soft_limits::comment_warnings(
+        &payload.content,
+        Some(depth),
+        config.soft_limit_warning_percent,
+    );
+
+    // 作成直後のコメントと同じ内容（IDは未定のため nil、短いIDは空）
+    let now = Utc::now();
+    let mut comment = CommentWithUser {
+        id: Uuid::nil(),
+        short_id: String::new(),
+        content: payload.content,
+        parent_id: payload.parent_id,
+        created_at: now,
+        updated_at: now,
+        user_id: current_user.id,
+        username: current_user.username,
+        user_display_name: current_user.display_name,
+        user_avatar_url: current_user.avatar_url,
+        deleted: false,
+        removed_by_moderator: false,
+        edited_at: None,
+    }
+    .to_response();
+    comment.warnings = warnings;
+
+    Ok(Json(CommentPreviewResponse {
+        preview: true,
+        comment,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::comments::create::create_comment,
+        models::comments::MAX_DEPTH,
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user, noop_notifier, test_config,
+            test_tokens,
+        },
+        utils::soft_limits::MAX_DEPTH_REACHED,
+    };
+
+    async fn comment_count(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM comments")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_プレビューは作成した結果と一致し何も保存しない(
+        pool: PgPool,
+    ) {
+        // コメントのプレビューは作成した結果と一致し、何も保存しないことを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        // 返信できない深さの親コメント
+        let mut parent_id = None;
+        for _ in 0..MAX_DEPTH - 1 {
+            parent_id =
+                Some(create_test_comment(&pool, user.id, thread_id, "Reply", parent_id).await);
+        }
+        let before = comment_count(&pool).await;
+        let request = || CreateCommentRequest {
+            content: "a".repeat(9_500),
+            parent_id,
+            client_nonce: None,
+        };
+        let config = Arc::new(test_config());
+
+        let Json(preview) = preview_comment(
+            State(pool.clone()),
+            State(config.clone()),
+            Path(thread_id),
+            Extension(user.clone()),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(comment_count(&pool).await, before);
+
+        let (_, _, Json(created)) = create_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(config),
+            State(test_tokens()),
+            Path(thread_id),
+            Extension(user),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+
+        assert!(preview.preview);
+        let comment = &preview.comment;
+        assert_eq!(comment.id, Uuid::nil());
+        assert_eq!(comment.content, created.content);
+        assert_eq!(comment.parent_id, created.parent_id);
+        assert_eq!(comment.user.id, created.user.id);
+        assert_eq!(comment.user.username, created.user.username);
+        assert_eq!(
+            comment.warnings.iter().map(|w| &w.code).collect::<Vec<_>>(),
+            created.warnings.iter().map(|w| &w.code).collect::<Vec<_>>()
+        );
+        assert!(comment.warnings.iter().any(|w| w.code == MAX_DEPTH_REACHED));
+    }
+
+    #[sqlx::test]
+    async fn test_ロック中のスレッドは作成と同じくエラーになる(pool: PgPool) {
+        // ロック中のスレッドへのプレビューは作成と同じくエラーになることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        sqlx::query("UPDATE threads SET locked_at = NOW() WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = preview_comment(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(thread_id),
+            Extension(user),
+            Json(CreateCommentRequest {
+                content: "Comment".to_string(),
+                parent_id: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::ThreadLocked)));
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{comments::CommentQuoteResponse, common::ErrorResponse, users},
+    utils::short_id,
+};
+
+use super::utils::format_quote_markdown;
+
+#[derive(Debug, sqlx::FromRow)]
+struct QuotedComment {
+    thread_id: Uuid,
+    content: String,
+    username: String,
+}
+
+/// コメントの引用用Markdownを取得
+///
+/// クライアント間で引用の形式を統一するため、整形済みの引用文を返します。
+#[utoipa::path(
+    get,
+    path = "/api/comments/{id}/quote",
+    params(
+        ("id" = String, Path, description = "Comment ID (UUID or short ID)")
+    ),
+    responses(
+        (status = 200, description = "Quoted comment", body = CommentQuoteResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse)
+    ),
+    tag = "comments"
+)]
+pub async fn get_comment_quote(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(id_or_short_id): Path<String>,
+) -> Result<Json<CommentQuoteResponse>, AppError> {
+    let id = short_id::resolve_comment_id(&pool, &id_or_short_id).await?;
+
+    let comment = sqlx::query_as::<_, QuotedComment>(
+        r#"
+        SELECT c.thread_id, c.content, u.username
+        FROM comments c
+        JOIN users u ON c.user_id = u.id
+        WHERE c.id = $1 AND c.deleted_at IS NULL AND NOT c.removed_by_moderator
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(CommentQuoteResponse {
+        markdown: format_quote_markdown(&comment.content, config.comment_quote_max_length),
+        author_username: users::display_username(comment.username),
+        comment_url: format!(
+            "{}/threads/{}#comment-{}",
+            config.frontend_url, comment.thread_id, id
+        ),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_comment, seed_test_data, test_config};
+
+    #[sqlx::test]
+    async fn test_コメントの引用を取得できる(pool: PgPool) {
+        // 引用Markdownと投稿者名、コメントURLが返されることを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "comment_quote").await;
+        let comment_id =
+            create_test_comment(&pool, user_id, thread_id, "first line\nsecond line", None).await;
+
+        let response = get_comment_quote(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(comment_id.to_string()),
+        )
+        .await
+        .unwrap()
+        .0;
+
+        assert_eq!(response.markdown, "> first line\n> second line\n\n");
+        assert_eq!(response.author_username, "testuser_comment_quote");
+        assert!(response
+            .comment_url
+            .ends_with(&format!("/threads/{}#comment-{}", thread_id, comment_id)));
+
+        // 機密情報が含まれていないことを確認
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("email"));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないコメントの引用は404になる(pool: PgPool) {
+        // 存在しないコメントIDを指定するとNotFoundになることを確認
+        let result = get_comment_quote(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(Uuid::new_v4().to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        reports::{CreateReportRequest, ReportResponse, ReportTargetType},
+        User,
+    },
+    utils::reports,
+};
+
+/// コメントを通報する
+///
+/// 同じコメントを通報できるのは1回のみです。
+#[utoipa::path(
+    post,
+    path = "/api/comments/{id}/report",
+    params(
+        ("id" = Uuid, Path, description = "Comment ID")
+    ),
+    request_body = CreateReportRequest,
+    responses(
+        (status = 201, description = "Comment reported", body = ReportResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+        (status = 409, description = "Already reported", body = ErrorResponse)
+    ),
+    tag = "comments",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn report_comment(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<CreateReportRequest>,
+) -> Result<(StatusCode, Json<ReportResponse>), AppError> {
+    payload.validate()?;
+
+    let report = reports::create(
+        &pool,
+        current_user.id,
+        ReportTargetType::Comment,
+        id,
+        &payload,
+    )
+    .await?;
+
+    tracing::info!(
+        report_id = %report.id,
+        comment_id = %id,
+        reporter_id = %current_user.id,
+        "Comment reported"
+    );
+
+    Ok((StatusCode::CREATED, Json(ReportResponse::from(report))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::reports::ReportReason,
+        test_utils::{create_test_comment, create_test_thread, create_test_user},
+    };
+
+    #[sqlx::test]
+    async fn test_コメントを通報できる(pool: PgPool) {
+        // コメントを通報でき、通報の一覧に載ることを確認
+        let author = create_test_user(&pool, true).await;
+        let reporter = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+        let comment_id = create_test_comment(&pool, author.id, thread_id, "spam", None).await;
+
+        let (status, Json(report)) = report_comment(
+            State(pool.clone()),
+            Path(comment_id),
+            Extension(reporter.clone()),
+            Json(CreateReportRequest {
+                reason: ReportReason::Spam,
+                detail: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(report.target_type, ReportTargetType::Comment);
+        assert_eq!(report.target_id, comment_id);
+        assert_eq!(report.reporter_id, reporter.id);
+
+        // スレッドのIDではコメントとして通報できない
+        let result = report_comment(
+            State(pool.clone()),
+            Path(thread_id),
+            Extension(reporter),
+            Json(CreateReportRequest {
+                reason: ReportReason::Spam,
+                detail: None,
+            }),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -1,15 +1,19 @@
 use axum::{extract::Extension, extract::Path, extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
     models::{
         comments::{CommentResponse, CommentWithUser, UpdateCommentRequest},
         common::ErrorResponse,
         User,
     },
+    utils::soft_limits,
 };
 
 #[utoipa::path(
@@ -20,10 +24,10 @@ use crate::{
     ),
     request_body = UpdateCommentRequest,
     responses(
-        (status = 200, description = "Comment updated successfully", body = CommentResponse),
+        (status = 200, description = "Comment updated successfully. `warnings` lists content close to its limit", body = CommentResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
-        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 403, description = "Thread is locked (THREAD_LOCKED) or the edit window has passed (COMMENT_EDIT_WINDOW_EXPIRED)", body = ErrorResponse),
         (status = 404, description = "Comment not found", body = ErrorResponse)
     ),
     tag = "comments",
@@ -33,6 +37,7 @@ use crate::{
 )]
 pub async fn update_comment(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
     Path(id): Path<Uuid>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<UpdateCommentRequest>,
@@ -40,27 +45,43 @@ pub async fn update_comment(
     // Validate input
     payload.validate()?;
 
-    // Check if comment exists and user owns it
-    let existing_comment = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1 AND user_id = $2)",
+    // Check if comment exists and user owns it（ロック中のスレッドのコメントや、削除・非表示になったコメントは編集できない）
+    let (thread_locked, created_at) = sqlx::query_as::<_, (bool, DateTime<Utc>)>(
+        r#"
+        SELECT t.locked_at IS NOT NULL, c.created_at
+        FROM comments c
+        JOIN threads t ON t.id = c.thread_id
+        WHERE c.id = $1 AND c.user_id = $2
+          AND c.deleted_at IS NULL AND NOT c.removed_by_moderator
+        "#,
     )
     .bind(id)
     .bind(current_user.id)
-    .fetch_one(&pool)
-    .await?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
 
-    if !existing_comment {
-        return Err(AppError::NotFound);
+    if thread_locked {
+        return Err(AppError::ThreadLocked);
+    }
+
+    // 返信が付いた後に内容を書き換えられないよう、投稿から一定時間を過ぎたら編集できない（モデレーターは除く）
+    let window = config.comment_edit_window_minutes;
+    if window > 0
+        && !current_user.role.can_moderate()
+        && Utc::now() - created_at > Duration::minutes(i64::from(window))
+    {
+        return Err(AppError::CommentEditWindowExpired);
     }
 
     // Update comment
     let updated_comment = sqlx::query_as::<_, CommentWithUser>(
         r#"
         UPDATE comments 
-        SET content = $2, updated_at = NOW()
+        SET content = $2, updated_at = NOW(), edited_at = NOW()
         WHERE id = $1
         RETURNING 
-            id, thread_id, content, parent_id, created_at, updated_at,
+            id, short_id, thread_id, content, parent_id, created_at, updated_at, edited_at,
             user_id, $3 as username, $4 as user_display_name, $5 as user_avatar_url
         "#,
     )
@@ -72,5 +93,153 @@ pub async fn update_comment(
     .fetch_one(&pool)
     .await?;
 
-    Ok(Json(updated_comment.to_response()))
-}
\ No newline at end of file
+    let mut response = updated_comment.to_response();
+    // 深さは変わらないため本文のみ確認する
+    response.warnings =
+        soft_limits::comment_warnings(&payload.content, None, config.soft_limit_warning_percent);
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::comments::list::get_comments,
+        middleware::MaybeUser,
+        models::{comments::CommentListQuery, UserRole},
+        pagination::Pagination,
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user, test_config, test_state,
+        },
+    };
+    use axum::extract::Query;
+
+    async fn update(
+        pool: &PgPool,
+        config: Config,
+        id: Uuid,
+        user: &User,
+    ) -> Result<Json<CommentResponse>, AppError> {
+        update_comment(
+            State(pool.clone()),
+            State(Arc::new(config)),
+            Path(id),
+            Extension(user.clone()),
+            Json(UpdateCommentRequest {
+                content: "Edited".to_string(),
+            }),
+        )
+        .await
+    }
+
+    /// スレッドのコメント一覧（ツリー）
+    async fn comments(pool: &PgPool, thread_id: Uuid) -> Vec<CommentResponse> {
+        let state = test_state(pool);
+        let Json(list) = get_comments(
+            State(pool.clone()),
+            State(state.config),
+            State(state.users),
+            MaybeUser(None),
+            Path(thread_id),
+            Pagination::new(None, None, None, &test_config().content_limits),
+            Query(CommentListQuery::default()),
+        )
+        .await
+        .unwrap();
+        list.comments
+    }
+
+    /// 投稿日時を `minutes` 分前にする
+    async fn age_comment(pool: &PgPool, id: Uuid, minutes: i64) {
+        sqlx::query(
+            "UPDATE comments SET created_at = NOW() - make_interval(mins => $2) WHERE id = $1",
+        )
+        .bind(id)
+        .bind(minutes as i32)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_編集できる時間内の編集で編集日時を記録する(pool: PgPool) {
+        // 編集できる時間内に編集すると編集日時が記録されることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, user.id, thread_id, "Original", None).await;
+        age_comment(&pool, comment_id, 59).await;
+
+        let Json(response) = update(&pool, test_config(), comment_id, &user)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Edited");
+        assert!(response.edited);
+        let edited_at = response.edited_at.unwrap();
+
+        // コメント一覧でも編集されたことが分かる
+        let list = comments(&pool, thread_id).await;
+        let comment = &list[0];
+        assert!(comment.edited);
+        assert_eq!(comment.edited_at, Some(edited_at));
+        let json = serde_json::to_value(comment).unwrap();
+        assert_eq!(json["edited"], true);
+
+        // 編集していないコメントは edited: false
+        create_test_comment(&pool, user.id, thread_id, "Untouched", None).await;
+        let list = comments(&pool, thread_id).await;
+        let untouched = list.iter().find(|c| c.content == "Untouched").unwrap();
+        assert!(!untouched.edited && untouched.edited_at.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_編集できる時間を過ぎたコメントは編集できない(pool: PgPool) {
+        // 編集できる時間を過ぎたコメントはCOMMENT_EDIT_WINDOW_EXPIREDで編集できないことを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, user.id, thread_id, "Original", None).await;
+        age_comment(&pool, comment_id, 61).await;
+
+        let result = update(&pool, test_config(), comment_id, &user).await;
+        assert!(matches!(result, Err(AppError::CommentEditWindowExpired)));
+        assert_eq!(
+            result.unwrap_err().code(),
+            Some("COMMENT_EDIT_WINDOW_EXPIRED")
+        );
+        let (content, edited_at): (String, Option<DateTime<Utc>>) =
+            sqlx::query_as("SELECT content, edited_at FROM comments WHERE id = $1")
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((content.as_str(), edited_at), ("Original", None));
+
+        // 0 の場合は制限しない
+        let unlimited = Config {
+            comment_edit_window_minutes: 0,
+            ..test_config()
+        };
+        assert!(update(&pool, unlimited, comment_id, &user).await.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn test_モデレーターは編集できる時間を過ぎても編集できる(
+        pool: PgPool,
+    ) {
+        // モデレーターは編集できる時間を過ぎたコメントも編集できることを確認
+        let user = create_test_user(&pool, true).await;
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..user
+        };
+        let thread_id = create_test_thread(&pool, moderator.id, "Thread", "Content").await;
+        let comment_id =
+            create_test_comment(&pool, moderator.id, thread_id, "Original", None).await;
+        age_comment(&pool, comment_id, 24 * 60).await;
+
+        let Json(response) = update(&pool, test_config(), comment_id, &moderator)
+            .await
+            .unwrap();
+        assert!(response.edited);
+    }
+}
@@ -1,15 +1,45 @@
-use crate::models::comments::{CommentResponse, CommentWithUser};
-use std::collections::HashMap;
+use crate::models::comments::{
+    CommentResponse, CommentWithUser, BLOCKED_CONTENT, DELETED_CONTENT, REMOVED_CONTENT,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-pub fn build_comment_tree(comments: Vec<CommentWithUser>) -> Vec<CommentResponse> {
+/// コメントを表示用に変換する（トムストーンとブロックしているユーザーのコメントは本文を隠す）
+fn render_comment(
+    mut comment: CommentWithUser,
+    blocked_authors: &HashSet<Uuid>,
+) -> CommentResponse {
+    let blocked = blocked_authors.contains(&comment.user_id);
+    if comment.removed_by_moderator {
+        comment.content = REMOVED_CONTENT.to_string();
+    } else if comment.deleted {
+        comment.content = DELETED_CONTENT.to_string();
+    } else if blocked {
+        comment.content = BLOCKED_CONTENT.to_string();
+    }
+    CommentResponse {
+        blocked,
+        ..comment.to_response()
+    }
+}
+
+/// コメントをツリー構造に組み立てる
+///
+/// `blocked_authors` のユーザーのコメントは返信を残したまま `[blocked user]` として表示します。
+pub fn build_comment_tree(
+    comments: Vec<CommentWithUser>,
+    blocked_authors: &HashSet<Uuid>,
+) -> Vec<CommentResponse> {
     if comments.is_empty() {
         return Vec::new();
     }
 
     // 1. すべてのコメントをCommentResponseに変換し、created_at順でソート
-    let mut all_comments: Vec<CommentResponse> =
-        comments.into_iter().map(|c| c.to_response()).collect();
+    let mut all_comments: Vec<CommentResponse> = comments
+        .into_iter()
+        .map(|comment| render_comment(comment, blocked_authors))
+        .collect();
     all_comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
     // 2. 親IDごとに子コメントをグループ化するHashMapを構築
@@ -27,32 +57,84 @@ pub fn build_comment_tree(comments: Vec<CommentWithUser>) -> Vec<CommentResponse
         }
     }
 
-    // 3. 各コメントに子コメントを再帰的に追加
+    // 3. 各コメントに子コメントを再帰的に追加し、サブツリー内で最も新しい投稿の日時を返す
     fn build_replies(
         comment: &mut CommentResponse,
         children_map: &HashMap<Uuid, Vec<CommentResponse>>,
-    ) {
+    ) -> DateTime<Utc> {
+        let mut last_activity_at = comment.created_at;
         if let Some(children) = children_map.get(&comment.id) {
             for mut child in children.clone() {
-                build_replies(&mut child, children_map);
+                last_activity_at = last_activity_at.max(build_replies(&mut child, children_map));
                 comment.reply_count += 1;
                 comment.replies.push(child);
             }
         }
+        last_activity_at
     }
 
     // 4. 各ルートコメントに子コメントを追加
     for root_comment in &mut root_comments {
-        build_replies(root_comment, &children_map);
+        root_comment.last_activity_at = Some(build_replies(root_comment, &children_map));
     }
 
     root_comments
 }
 
+/// 引用時に省略したことを示すマーカー
+pub const QUOTE_ELLIPSIS: &str = "…";
+
+/// コメント本文を引用用のMarkdownに整形する
+///
+/// - 元の本文に含まれる引用行（`>`で始まる行）は取り除き、引用が入れ子にならないようにする
+/// - `max_length`文字を超える場合は切り詰めて末尾に省略記号を付ける
+/// - 各行を`> `で引用し、末尾に空行を付けて返す
+pub fn format_quote_markdown(content: &str, max_length: usize) -> String {
+    // 既存の引用行を除去し、連続する空行を1つにまとめる
+    let mut lines: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with('>') {
+            continue;
+        }
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    // 文字数で切り詰める（マルチバイト文字を壊さないようcharsで数える）
+    let body = lines.join("\n");
+    let body = if body.chars().count() > max_length {
+        let truncated: String = body.chars().take(max_length).collect();
+        format!("{}{}", truncated.trim_end(), QUOTE_ELLIPSIS)
+    } else {
+        body
+    };
+
+    let mut markdown = String::new();
+    for line in body.lines() {
+        if line.is_empty() {
+            markdown.push_str(">\n");
+        } else {
+            markdown.push_str("> ");
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+    }
+    markdown.push('\n');
+
+    markdown
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::models::comments::CommentWithUser;
+    use crate::utils::token_generator::{RandomTokenGenerator, TokenGenerator};
     use chrono::{DateTime, Utc};
     use uuid::Uuid;
 
@@ -64,6 +146,7 @@ mod tests {
     ) -> CommentWithUser {
         CommentWithUser {
             id,
+            short_id: RandomTokenGenerator.short_id(),
             // thread_id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
             parent_id,
@@ -73,13 +156,16 @@ mod tests {
             username: "testuser".to_string(),
             user_display_name: Some("Test User".to_string()),
             user_avatar_url: None,
+            deleted: false,
+            removed_by_moderator: false,
+            edited_at: None,
         }
     }
 
     #[test]
     fn test_空のコメントリストで空の結果を返す() {
         let comments = Vec::new();
-        let result = build_comment_tree(comments);
+        let result = build_comment_tree(comments, &HashSet::new());
         assert!(result.is_empty());
     }
 
@@ -95,7 +181,7 @@ mod tests {
             base_time,
         )];
 
-        let result = build_comment_tree(comments);
+        let result = build_comment_tree(comments, &HashSet::new());
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].id, comment1_id);
@@ -134,7 +220,7 @@ mod tests {
             ),
         ];
 
-        let result = build_comment_tree(comments);
+        let result = build_comment_tree(comments, &HashSet::new());
 
         assert_eq!(result.len(), 1);
         let root = &result[0];
@@ -176,7 +262,7 @@ mod tests {
             create_test_comment(root1_id, None, "Root 1", base_time),
         ];
 
-        let result = build_comment_tree(comments);
+        let result = build_comment_tree(comments, &HashSet::new());
 
         assert_eq!(result.len(), 2);
         // created_at順でソートされていることを確認
@@ -191,6 +277,54 @@ mod tests {
         assert_eq!(result[1].replies.len(), 0);
     }
 
+    #[test]
+    fn test_ルートコメントに返信を含めた最新の投稿日時を付ける() {
+        // ルートコメントに返信を含めた最新の投稿日時が付くことを確認
+        let base_time = Utc::now();
+        let (old_root, new_root, child, grandchild) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+
+        let comments = vec![
+            create_test_comment(old_root, None, "Old root", base_time),
+            create_test_comment(
+                child,
+                Some(old_root),
+                "Child",
+                base_time + chrono::Duration::minutes(1),
+            ),
+            create_test_comment(
+                new_root,
+                None,
+                "New root",
+                base_time + chrono::Duration::minutes(2),
+            ),
+            create_test_comment(
+                grandchild,
+                Some(child),
+                "Grandchild",
+                base_time + chrono::Duration::minutes(3),
+            ),
+        ];
+
+        let result = build_comment_tree(comments, &HashSet::new());
+
+        // 孫の返信までたどり、返信が無いコメントは自身の投稿日時になる
+        assert_eq!(
+            result[0].last_activity_at,
+            Some(base_time + chrono::Duration::minutes(3))
+        );
+        assert_eq!(
+            result[1].last_activity_at,
+            Some(base_time + chrono::Duration::minutes(2))
+        );
+        // 返信には付けない
+        assert_eq!(result[0].replies[0].last_activity_at, None);
+    }
+
     #[test]
     fn test_深い階層のコメントツリー() {
         let base_time = Utc::now();
@@ -224,7 +358,7 @@ mod tests {
             ),
         ];
 
-        let result = build_comment_tree(comments);
+        let result = build_comment_tree(comments, &HashSet::new());
 
         assert_eq!(result.len(), 1);
 
@@ -242,4 +376,127 @@ mod tests {
         assert_eq!(current.replies.len(), 0);
         assert_eq!(current.reply_count, 0);
     }
+
+    #[test]
+    fn test_削除と非表示のコメントは本文を隠して返信を残す() {
+        // 削除・非表示のコメントは本文を隠し、返信はツリーに残すことを確認
+        let base_time = Utc::now();
+        let normal_id = Uuid::new_v4();
+        let deleted_id = Uuid::new_v4();
+        let removed_id = Uuid::new_v4();
+
+        let comments = vec![
+            create_test_comment(normal_id, None, "Normal", base_time),
+            CommentWithUser {
+                deleted: true,
+                ..create_test_comment(
+                    deleted_id,
+                    None,
+                    "",
+                    base_time + chrono::Duration::seconds(1),
+                )
+            },
+            CommentWithUser {
+                removed_by_moderator: true,
+                ..create_test_comment(
+                    removed_id,
+                    Some(deleted_id),
+                    "Spam link",
+                    base_time + chrono::Duration::seconds(2),
+                )
+            },
+            create_test_comment(
+                Uuid::new_v4(),
+                Some(removed_id),
+                "Reply to spam",
+                base_time + chrono::Duration::seconds(3),
+            ),
+        ];
+
+        let result = build_comment_tree(comments, &HashSet::new());
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "Normal");
+        assert!(!result[0].deleted && !result[0].removed_by_moderator);
+
+        let deleted = &result[1];
+        assert_eq!(deleted.content, DELETED_CONTENT);
+        assert!(deleted.deleted && !deleted.removed_by_moderator);
+
+        let removed = &deleted.replies[0];
+        assert_eq!(removed.content, REMOVED_CONTENT);
+        assert!(!removed.deleted && removed.removed_by_moderator);
+        // 非表示にした本文は HTML にも残らない
+        assert!(!removed.content_html.contains("Spam"));
+        assert_eq!(removed.replies[0].content, "Reply to spam");
+    }
+
+    #[test]
+    fn test_ブロックしているユーザーのコメントは本文を隠して返信を残す() {
+        // ブロックしているユーザーのコメントは本文を隠し、返信はツリーに残すことを確認
+        let base_time = Utc::now();
+        let blocked = create_test_comment(Uuid::new_v4(), None, "Rude", base_time);
+        let blocked_author = blocked.user_id;
+        let comments = vec![
+            create_test_comment(
+                Uuid::new_v4(),
+                Some(blocked.id),
+                "Reply",
+                base_time + chrono::Duration::seconds(1),
+            ),
+            blocked,
+        ];
+
+        let result = build_comment_tree(comments, &HashSet::from([blocked_author]));
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content, BLOCKED_CONTENT);
+        assert!(result[0].blocked && !result[0].deleted);
+        assert!(!result[0].content_html.contains("Rude"));
+        assert_eq!(result[0].reply_count, 1);
+        assert_eq!(result[0].replies[0].content, "Reply");
+        assert!(!result[0].replies[0].blocked);
+    }
+
+    #[test]
+    fn test_複数行のコメントを行ごとに引用する() {
+        // 複数行のコメントは行ごとに引用されることを確認
+        let result = format_quote_markdown("original text\nsecond line", 200);
+        assert_eq!(result, "> original text\n> second line\n\n");
+    }
+
+    #[test]
+    fn test_空行を含むコメントは空の引用行になる() {
+        // 空行は空の引用行になることを確認
+        let result = format_quote_markdown("first\n\n\n\nsecond\n\n", 200);
+        assert_eq!(result, "> first\n>\n> second\n\n");
+    }
+
+    #[test]
+    fn test_長いコメントは省略記号付きで切り詰められる() {
+        // 上限を超えるコメントは省略記号を付けて切り詰められることを確認
+        let content = "あいうえおかきくけこ";
+        let result = format_quote_markdown(content, 5);
+        assert_eq!(result, format!("> あいうえお{}\n\n", QUOTE_ELLIPSIS));
+
+        // ちょうど上限の長さなら省略しない
+        let result = format_quote_markdown("abcde", 5);
+        assert_eq!(result, "> abcde\n\n");
+    }
+
+    #[test]
+    fn test_入れ子の引用は取り除かれる() {
+        // 引用元のコメントに含まれる引用は取り除かれることを確認
+        let content = "> earlier quote\n>> deeper quote\nmy reply\n  > indented quote\nend";
+        let result = format_quote_markdown(content, 200);
+        assert_eq!(result, "> my reply\n> end\n\n");
+        assert!(!result.contains(">>"));
+    }
+
+    #[test]
+    fn test_引用のみのコメントは空の引用になる() {
+        // 引用だけのコメントは空の引用になることを確認
+        let result = format_quote_markdown("> only a quote", 200);
+        assert_eq!(result, "\n");
+    }
 }
@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{common::ErrorResponse, User},
+    pagination::{Pagination, Threads},
+    utils::atom::{feed_response, strip_feed_extension, Feed, FeedFilter},
+};
+
+/// ユーザーが投稿したスレッドの Atom フィード
+///
+/// ユーザーのスレッドを新しい順に返します。ページ送りと条件付き取得はタグのフィードと同じです。
+#[utoipa::path(
+    get,
+    path = "/api/feeds/users/{file}",
+    params(
+        ("file" = String, Path, description = "ユーザー名に .atom を付けたファイル名（例: alice.atom）"),
+        Pagination<Threads>
+    ),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml"),
+        (status = 304, description = "Not modified since If-Modified-Since"),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users"
+)]
+pub async fn get_author_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(file): Path<String>,
+    pagination: Pagination<Threads>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let username = strip_feed_extension(&file)?;
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(username)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let feed = Feed::new(
+        &config,
+        format!(
+            "{} - minwada",
+            user.display_name.as_deref().unwrap_or(&user.username)
+        ),
+        &format!("/api/feeds/users/{}.atom", user.username),
+        format!("{}/users/{}", config.frontend_url, user.username),
+    );
+    feed_response(
+        &pool,
+        &headers,
+        &feed,
+        FeedFilter::Author(user.id),
+        &pagination,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_thread, create_test_user, test_config};
+    use axum::{body::to_bytes, http::StatusCode};
+
+    #[sqlx::test]
+    async fn test_ユーザーのスレッドだけを載せる(pool: PgPool) {
+        // 作者のフィードにはそのユーザーのスレッドだけが載ることを確認
+        let author = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        create_test_thread(&pool, author.id, "Mine", "Content").await;
+        create_test_thread(&pool, other.id, "Theirs", "Content").await;
+        let config = test_config();
+
+        let response = get_author_feed(
+            State(pool.clone()),
+            State(Arc::new(config.clone())),
+            Path(format!("{}.atom", author.username)),
+            Pagination::new(None, None, None, &config.content_limits),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("<title>Mine</title>"));
+        assert!(!body.contains("Theirs"));
+        assert!(body.contains(&format!(
+            r#"<link rel="alternate" type="text/html" href="http://localhost:3000/users/{}"/>"#,
+            author.username
+        )));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod author;
+pub mod tag;
+pub mod thread_comments;
+pub mod user_comments;
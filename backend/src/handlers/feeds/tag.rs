@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{common::ErrorResponse, tags::Tag},
+    pagination::{Pagination, Threads},
+    utils::{
+        atom::{feed_response, strip_feed_extension, Feed, FeedFilter},
+        tags::encode_slug,
+    },
+};
+
+/// タグのスレッドの Atom フィード
+///
+/// タグが付いたスレッドを新しい順に返します。続きのページは `rel="next"` のリンクで辿れます。
+/// If-Modified-Since 以降に新しいエントリーや更新が無ければ 304 を返します。
+#[utoipa::path(
+    get,
+    path = "/api/feeds/tags/{file}",
+    params(
+        ("file" = String, Path, description = "タグのスラッグに .atom を付けたファイル名（例: rust.atom）"),
+        Pagination<Threads>
+    ),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml"),
+        (status = 304, description = "Not modified since If-Modified-Since"),
+        (status = 404, description = "Tag not found", body = ErrorResponse)
+    ),
+    tag = "tags"
+)]
+pub async fn get_tag_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(file): Path<String>,
+    pagination: Pagination<Threads>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let slug = strip_feed_extension(&file)?;
+    let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE slug = $1")
+        .bind(slug)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let feed = Feed::new(
+        &config,
+        format!("{} - minwada", tag.name),
+        &format!("/api/feeds/tags/{}.atom", encode_slug(&tag.slug)),
+        config.frontend_url.clone(),
+    );
+    feed_response(&pool, &headers, &feed, FeedFilter::Tag(tag.id), &pagination).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        create_test_tag, create_test_thread, create_test_user, tag_test_thread, test_config,
+    };
+    use axum::{
+        body::to_bytes,
+        http::{header, HeaderValue, StatusCode},
+    };
+
+    async fn feed(
+        pool: &PgPool,
+        file: &str,
+        limit: Option<u32>,
+        headers: HeaderMap,
+    ) -> Result<Response, AppError> {
+        let config = test_config();
+        let pagination = Pagination::new(None, limit, None, &config.content_limits);
+        get_tag_feed(
+            State(pool.clone()),
+            State(Arc::new(config)),
+            Path(file.to_string()),
+            pagination,
+            headers,
+        )
+        .await
+    }
+
+    async fn body_of(response: Response) -> String {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_タグのスレッドだけを載せ続きがあればnextリンクを付ける(
+        pool: PgPool,
+    ) {
+        // タグのスレッドだけがフィードに載り、続きがあればnextリンクが付くことを確認
+        let user = create_test_user(&pool, true).await;
+        let tag = create_test_tag(&pool, "Rust").await;
+        for title in ["First", "Second"] {
+            let thread_id = create_test_thread(&pool, user.id, title, "<b>Content</b>").await;
+            tag_test_thread(&pool, thread_id, tag.id).await;
+        }
+        create_test_thread(&pool, user.id, "Untagged", "Content").await;
+
+        let response = feed(&pool, "rust.atom", Some(1), HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("application/atom+xml"));
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        let body = body_of(response).await;
+        assert_eq!(body.matches("<entry>").count(), 1);
+        assert!(body.contains(r#"rel="next""#));
+        assert!(body.contains("/api/feeds/tags/rust.atom?page=2&amp;limit=1"));
+        assert!(body.contains("&lt;b&gt;Content&lt;/b&gt;"));
+        assert!(!body.contains("Untagged"));
+
+        // 全件が1ページに収まる場合は next を付けない
+        let body = body_of(
+            feed(&pool, "rust.atom", None, HeaderMap::new())
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(body.matches("<entry>").count(), 2);
+        assert!(!body.contains(r#"rel="next""#));
+    }
+
+    #[sqlx::test]
+    async fn test_変更が無ければ304を返す(pool: PgPool) {
+        // 変更が無ければ304を返し、スレッドが更新されると200を返すことを確認
+        let user = create_test_user(&pool, true).await;
+        let tag = create_test_tag(&pool, "Rust").await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        tag_test_thread(&pool, thread_id, tag.id).await;
+
+        let response = feed(&pool, "rust.atom", None, HeaderMap::new())
+            .await
+            .unwrap();
+        let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        let response = feed(&pool, "rust.atom", None, headers.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::LAST_MODIFIED], last_modified);
+        assert!(body_of(response).await.is_empty());
+
+        // スレッドが更新されると本文を返す
+        // （updated_at はトリガーで現在時刻に上書きされるため、トリガーを止めて1分後の更新にする）
+        sqlx::query("ALTER TABLE threads DISABLE TRIGGER update_threads_updated_at")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE threads SET updated_at = NOW() + INTERVAL '1 minute' WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let response = feed(&pool, "rust.atom", None, headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 古い日時の指定は無視して本文を返す
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Mon, 01 Jan 2001 00:00:00 GMT"),
+        );
+        let response = feed(&pool, "rust.atom", None, headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないタグや拡張子の違いは404になる(pool: PgPool) {
+        // 存在しないタグや対応していない拡張子は404になることを確認
+        create_test_tag(&pool, "Rust").await;
+        for file in ["missing.atom", "rust.rss", "rust"] {
+            assert!(matches!(
+                feed(&pool, file, None, HeaderMap::new()).await,
+                Err(AppError::NotFound)
+            ));
+        }
+    }
+}
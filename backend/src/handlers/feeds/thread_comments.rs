@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::common::ErrorResponse,
+    utils::{
+        atom::{comment_feed_response, CommentFeedFilter, Feed},
+        short_id,
+    },
+};
+
+/// スレッドのコメントの Atom フィード
+///
+/// 最新の50件のコメントを、返信の階層を無視して新しい順に返します。
+/// 削除されたコメントとモデレーターが非表示にしたコメントは載せません。条件付き取得はスレッドのフィードと同じです。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/comments.atom",
+    params(
+        ("id" = String, Path, description = "Thread ID (UUID or short ID)")
+    ),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml"),
+        (status = 304, description = "Not modified since If-Modified-Since"),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "comments"
+)]
+pub async fn get_thread_comments_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(id_or_short_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let id = short_id::resolve_thread_id(&pool, &id_or_short_id).await?;
+    let (id, title) =
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, title FROM threads WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+    let feed = Feed::new(
+        &config,
+        format!("{} のコメント - minwada", title),
+        &format!("/api/threads/{}/comments.atom", id),
+        format!("{}/threads/{}", config.frontend_url, id),
+    );
+    comment_feed_response(&pool, &headers, &feed, CommentFeedFilter::Thread(id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{
+            assert_well_formed_xml, create_test_comment, create_test_thread, create_test_user,
+            test_config,
+        },
+        utils::atom::{COMMENT_FEED_LIMIT, FEED_MAX_AGE_SECONDS},
+    };
+    use axum::{
+        body::to_bytes,
+        http::{header, StatusCode},
+    };
+
+    async fn feed(pool: &PgPool, id: String) -> Result<Response, AppError> {
+        get_thread_comments_feed(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(id),
+            HeaderMap::new(),
+        )
+        .await
+    }
+
+    async fn body_of(response: Response) -> String {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_スレッドのコメントを階層を無視して載せる(pool: PgPool) {
+        // スレッドのコメントが階層を無視してフィードに載ることを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Thread & <Title>", "Content").await;
+        let other_thread = create_test_thread(&pool, author.id, "Other", "Content").await;
+        let parent = create_test_comment(&pool, author.id, thread_id, "Parent", None).await;
+        let reply = create_test_comment(
+            &pool,
+            author.id,
+            thread_id,
+            "<script>alert('x')</script> & more",
+            Some(parent),
+        )
+        .await;
+        create_test_comment(&pool, author.id, other_thread, "Elsewhere", None).await;
+
+        let response = feed(&pool, thread_id.to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("application/atom+xml"));
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            format!("public, max-age={}", FEED_MAX_AGE_SECONDS)
+        );
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+
+        let body = body_of(response).await;
+        assert_well_formed_xml(&body);
+        assert_eq!(body.matches("<entry>").count(), 2);
+        assert!(body.contains("<title>Re: Thread &amp; &lt;Title&gt;</title>"));
+        assert!(body.contains("&lt;script&gt;"));
+        assert!(!body.contains("<script>"));
+        assert!(!body.contains("Elsewhere"));
+        assert!(body.contains(&format!(
+            r#"href="http://localhost:3000/threads/{}#comment-{}""#,
+            thread_id, reply
+        )));
+        assert!(body.contains(&format!("<name>{}</name>", author.username)));
+    }
+
+    #[sqlx::test]
+    async fn test_削除や非表示のコメントは載せず最新の50件に限る(
+        pool: PgPool,
+    ) {
+        // 削除・非表示のコメントはフィードに載せず、最新の50件に限ることを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let deleted = create_test_comment(&pool, author.id, thread_id, "Deleted", None).await;
+        let removed = create_test_comment(&pool, author.id, thread_id, "Removed", None).await;
+        sqlx::query("UPDATE comments SET deleted_at = NOW(), content = '' WHERE id = $1")
+            .bind(deleted)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE comments SET removed_by_moderator = true, removal_reason = 'spam' WHERE id = $1",
+        )
+        .bind(removed)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let body = body_of(feed(&pool, thread_id.to_string()).await.unwrap()).await;
+        assert_well_formed_xml(&body);
+        assert_eq!(body.matches("<entry>").count(), 0);
+        assert!(!body.contains("Removed"));
+
+        sqlx::query(
+            r#"
+            INSERT INTO comments (thread_id, user_id, content, created_at)
+            SELECT $1, $2, 'Comment ' || n, NOW() - n * INTERVAL '1 second'
+            FROM generate_series(1, $3) AS n
+            "#,
+        )
+        .bind(thread_id)
+        .bind(author.id)
+        .bind(COMMENT_FEED_LIMIT as i32 + 5)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let body = body_of(feed(&pool, thread_id.to_string()).await.unwrap()).await;
+        assert_well_formed_xml(&body);
+        assert_eq!(body.matches("<entry>").count() as i64, COMMENT_FEED_LIMIT);
+        assert!(body.contains("<content type=\"text\">Comment 1</content>"));
+        assert!(!body.contains(&format!("Comment {}<", COMMENT_FEED_LIMIT + 1)));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは404になる(pool: PgPool) {
+        // 存在しないスレッドのコメントフィードは404になることを確認
+        for id in [Uuid::new_v4().to_string(), "unknown-id".to_string()] {
+            assert!(matches!(feed(&pool, id).await, Err(AppError::NotFound)));
+        }
+    }
+}
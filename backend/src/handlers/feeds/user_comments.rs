@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{common::ErrorResponse, User},
+    utils::atom::{comment_feed_response, CommentFeedFilter, Feed},
+};
+
+/// ユーザーが投稿したコメントの Atom フィード
+///
+/// ユーザーの最新の50件のコメントを、スレッドをまたいで新しい順に返します。
+/// 削除されたコメントとモデレーターが非表示にしたコメントは載せません。
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/comments.atom",
+    params(
+        ("username" = String, Path, description = "Username")
+    ),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml"),
+        (status = 304, description = "Not modified since If-Modified-Since"),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users"
+)]
+pub async fn get_user_comments_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(&username)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let feed = Feed::new(
+        &config,
+        format!(
+            "{} のコメント - minwada",
+            user.display_name.as_deref().unwrap_or(&user.username)
+        ),
+        &format!("/api/users/{}/comments.atom", user.username),
+        format!("{}/users/{}", config.frontend_url, user.username),
+    );
+    comment_feed_response(&pool, &headers, &feed, CommentFeedFilter::Author(user.id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        assert_well_formed_xml, create_test_comment, create_test_thread, create_test_user,
+        test_config,
+    };
+    use axum::{
+        body::to_bytes,
+        http::{header, StatusCode},
+    };
+
+    async fn feed(pool: &PgPool, username: &str, headers: HeaderMap) -> Result<Response, AppError> {
+        get_user_comments_feed(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(username.to_string()),
+            headers,
+        )
+        .await
+    }
+
+    #[sqlx::test]
+    async fn test_ユーザーのコメントだけをスレッドをまたいで載せる(
+        pool: PgPool,
+    ) {
+        // ユーザーのコメントだけがスレッドをまたいでフィードに載ることを確認
+        let author = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let first = create_test_thread(&pool, other.id, "First", "Content").await;
+        let second = create_test_thread(&pool, other.id, "Second", "Content").await;
+        create_test_comment(&pool, author.id, first, "Mine in first", None).await;
+        create_test_comment(&pool, author.id, second, "Mine in second", None).await;
+        create_test_comment(&pool, other.id, first, "Theirs", None).await;
+
+        let response = feed(&pool, &author.username.to_uppercase(), HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert_well_formed_xml(&body);
+        assert_eq!(body.matches("<entry>").count(), 2);
+        assert!(body.contains("<title>Re: First</title>"));
+        assert!(body.contains("<title>Re: Second</title>"));
+        assert!(!body.contains("Theirs"));
+        assert!(body.contains(&format!("/api/users/{}/comments.atom", author.username)));
+
+        // 新しいコメントが無ければ304を返す
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MODIFIED_SINCE, last_modified);
+        let response = feed(&pool, &author.username, headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーは404になる(pool: PgPool) {
+        // 存在しないユーザーのコメントフィードは404になることを確認
+        assert!(matches!(
+            feed(&pool, "no_such_user", HeaderMap::new()).await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
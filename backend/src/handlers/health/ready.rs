@@ -0,0 +1,153 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{sync::Arc, time::Duration};
+use utoipa::ToSchema;
+
+use crate::{
+    config::{Config, DbConnectMode},
+    panic, side_effects,
+};
+
+/// データベースの確認を待つ時間の上限
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// データベースの接続状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseStatus {
+    /// クエリを実行できた
+    Connected,
+    /// lazy モードでまだ一度も接続していない（最初のクエリで接続する）
+    NotConnected,
+    /// 接続できない、またはクエリが失敗した
+    Unavailable,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    /// `ready` または `not_ready`
+    pub status: String,
+    pub database: DatabaseStatus,
+    /// 起動してから失敗した副作用（通知など）の件数。準備状態には影響しない
+    pub side_effect_failures: u64,
+    /// 起動してからハンドラーでパニックした件数（`panics_total`）。準備状態には影響しない
+    pub panics_total: u64,
+}
+
+/// リクエストを受け付けられるかを返す
+///
+/// lazy モードで接続前の場合は、データベースに接続せずに `not_connected` として 200 を返します。
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "Ready to serve requests", body = ReadinessResponse),
+        (status = 503, description = "Database is unavailable", body = ReadinessResponse)
+    ),
+    tag = "health"
+)]
+pub async fn readyz(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let database = database_status(&pool, config.db_connect_mode).await;
+
+    let (status_code, status) = match database {
+        DatabaseStatus::Connected | DatabaseStatus::NotConnected => (StatusCode::OK, "ready"),
+        DatabaseStatus::Unavailable => (StatusCode::SERVICE_UNAVAILABLE, "not_ready"),
+    };
+
+    (
+        status_code,
+        Json(ReadinessResponse {
+            status: status.to_string(),
+            database,
+            side_effect_failures: side_effects::failure_count(),
+            panics_total: panic::panic_count(),
+        }),
+    )
+}
+
+async fn database_status(pool: &PgPool, mode: DbConnectMode) -> DatabaseStatus {
+    // lazy モードでは確認のためだけに接続を開かない
+    if mode == DbConnectMode::Lazy && pool.size() == 0 {
+        return DatabaseStatus::NotConnected;
+    }
+
+    let check = sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(pool);
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(_)) => DatabaseStatus::Connected,
+        Ok(Err(err)) => {
+            tracing::warn!("Readiness check failed: {}", err);
+            DatabaseStatus::Unavailable
+        }
+        Err(_) => {
+            tracing::warn!("Readiness check timed out");
+            DatabaseStatus::Unavailable
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    fn config_with_mode(db_connect_mode: DbConnectMode) -> Arc<Config> {
+        Arc::new(Config {
+            db_connect_mode,
+            ..test_config()
+        })
+    }
+
+    /// 接続できないアドレスを指す、まだ接続していないプール
+    fn unreachable_lazy_pool() -> PgPool {
+        PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(500))
+            .connect_lazy_with(PgConnectOptions::new().host("127.0.0.1").port(1))
+    }
+
+    #[sqlx::test]
+    async fn test_接続済みならconnectedを返す(pool: PgPool) {
+        // 接続済みのプールではconnectedを返すことを確認
+        let (status, Json(body)) =
+            readyz(State(pool), State(config_with_mode(DbConnectMode::Eager))).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.status, "ready");
+        assert_eq!(body.database, DatabaseStatus::Connected);
+    }
+
+    #[tokio::test]
+    async fn test_lazyモードで未接続ならnot_connectedを返す() {
+        // lazyモードで未接続の場合は503ではなくnot_connectedを返すことを確認
+        let pool = unreachable_lazy_pool();
+
+        let (status, Json(body)) = readyz(
+            State(pool.clone()),
+            State(config_with_mode(DbConnectMode::Lazy)),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.database, DatabaseStatus::NotConnected);
+        // 確認のために接続を開いていない
+        assert_eq!(pool.size(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_接続できなければ503を返す() {
+        // データベースに接続できない場合は503を返すことを確認
+        let (status, Json(body)) = readyz(
+            State(unreachable_lazy_pool()),
+            State(config_with_mode(DbConnectMode::Eager)),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.status, "not_ready");
+        assert_eq!(body.database, DatabaseStatus::Unavailable);
+    }
+}
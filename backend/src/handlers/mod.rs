@@ -1,4 +1,112 @@
+// ハンドラー
+//
+// 使われなくなったハンドラーや関数が残らないよう、未使用のコードはコンパイルエラーにする。
+#![deny(dead_code)]
+
+pub mod admin;
 pub mod auth;
 pub mod comments;
+pub mod feeds;
+pub mod health;
+pub mod tags;
+#[cfg(feature = "test-endpoints")]
+pub mod testing;
 pub mod threads;
 pub mod users;
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    // ディレクトリ以下の .rs ファイル
+    fn rust_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(rust_files(&path));
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    // `mod` で宣言されている場合に、宣言しているはずのファイルの候補とモジュール名を返す
+    fn declaring_files(src: &Path, file: &Path) -> (Vec<PathBuf>, String) {
+        let (dir, name) = if file.file_stem().unwrap() == "mod" {
+            let dir = file.parent().unwrap();
+            (dir.parent().unwrap(), dir.file_name().unwrap())
+        } else {
+            (file.parent().unwrap(), file.file_stem().unwrap())
+        };
+        let candidates = if dir == src {
+            vec![src.join("main.rs")]
+        } else {
+            vec![dir.join("mod.rs"), dir.with_extension("rs")]
+        };
+        (candidates, name.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn test_すべてのソースファイルがモジュールとして宣言されている() {
+        // handlers 以下のすべてのソースファイルがモジュールとして宣言されていることを確認
+        // 宣言されていないファイルはコンパイルされないため、古いコードが残っていても気付けない
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+
+        let mut orphans = Vec::new();
+        for file in rust_files(&src) {
+            if file == src.join("main.rs") {
+                continue;
+            }
+            let (candidates, name) = declaring_files(&src, &file);
+            let declared = candidates.iter().any(|candidate| {
+                std::fs::read_to_string(candidate).is_ok_and(|source| {
+                    source.lines().any(|line| {
+                        let line = line.trim();
+                        let line = line
+                            .strip_prefix("pub(crate) ")
+                            .or_else(|| line.strip_prefix("pub "))
+                            .unwrap_or(line);
+                        line == format!("mod {};", name) || line == format!("mod {} {{", name)
+                    })
+                })
+            });
+            if !declared {
+                orphans.push(file.strip_prefix(&src).unwrap().display().to_string());
+            }
+        }
+
+        assert!(
+            orphans.is_empty(),
+            "どのモジュールからも宣言されていないファイルがあります。不要なら削除してください: {:?}",
+            orphans
+        );
+    }
+
+    #[test]
+    fn test_コンパイル時にデータベースが必要なマクロを使っていない() {
+        // コンパイル時にデータベースが必要なマクロを使っていないことを確認
+        // query! などはビルド時にデータベース（またはオフライン用のデータ）が必要になるため、
+        // query_as::<_, T> などの関数で書く
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let macros = ["query", "query_as", "query_scalar"].map(|name| format!("sqlx::{}!(", name));
+
+        let mut usages = Vec::new();
+        for file in rust_files(&src) {
+            let source = std::fs::read_to_string(&file).unwrap();
+            for (number, line) in source.lines().enumerate() {
+                if macros.iter().any(|m| line.contains(m.as_str())) {
+                    let path = file.strip_prefix(&src).unwrap().display();
+                    usages.push(format!("{}:{}", path, number + 1));
+                }
+            }
+        }
+
+        assert!(
+            usages.is_empty(),
+            "sqlx のマクロを使っている箇所があります: {:?}",
+            usages
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::PgPool;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        tags::{Tag, TagResponse},
+    },
+    utils::tags::{encode_slug, thread_count},
+};
+
+/// スラッグでタグを取得する
+///
+/// 名前変更や統合で使われなくなった旧スラッグは、現在のスラッグへ308で転送します。
+#[utoipa::path(
+    get,
+    path = "/api/tags/{slug}",
+    params(
+        ("slug" = String, Path, description = "タグのスラッグ")
+    ),
+    responses(
+        (status = 200, description = "Tag found", body = TagResponse),
+        (status = 308, description = "Old slug; Location points to the current tag"),
+        (status = 404, description = "Tag not found", body = ErrorResponse)
+    ),
+    tag = "tags"
+)]
+pub async fn get_tag(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE slug = $1")
+        .bind(&slug)
+        .fetch_optional(&pool)
+        .await?;
+
+    if let Some(tag) = tag {
+        let count = thread_count(&pool, tag.id).await?;
+        return Ok(Json(TagResponse::new(tag, count)).into_response());
+    }
+
+    // 転送先は常に現在のタグを指しているので、1回のリダイレクトで済む
+    let current_slug = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT t.slug
+        FROM tag_slug_redirects r
+        JOIN tags t ON t.id = r.tag_id
+        WHERE r.old_slug = $1
+        "#,
+    )
+    .bind(&slug)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let location = format!("/api/tags/{}", encode_slug(&current_slug));
+    Ok((
+        StatusCode::PERMANENT_REDIRECT,
+        [(header::LOCATION, location)],
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        create_test_tag, create_test_thread, create_test_user, tag_test_thread,
+    };
+    use axum::body::to_bytes;
+
+    #[sqlx::test]
+    async fn test_スラッグでタグとスレッド数を取得できる(pool: PgPool) {
+        // スラッグでタグとスレッド数を取得できることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let tag = create_test_tag(&pool, "Rust").await;
+        tag_test_thread(&pool, thread_id, tag.id).await;
+
+        let response = get_tag(State(pool.clone()), Path("rust".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["id"], tag.id.to_string());
+        assert_eq!(json["name"], "Rust");
+        assert_eq!(json["thread_count"], 1);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスラッグは404になる(pool: PgPool) {
+        // 存在しないスラッグは404になることを確認
+        let result = get_tag(State(pool.clone()), Path("missing".to_string())).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -0,0 +1,126 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::{jwt::JwtService, password::hash_password},
+    config::Config,
+    error::AppError,
+    models::{User, UserRole},
+    utils::{auth_session, credentials, token_generator::TokenGenerator},
+};
+
+/// パスワードを指定しない場合のパスワード
+pub const DEFAULT_PASSWORD: &str = "password123";
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    /// 省略した場合は `e2e_` から始まるユーザー名を生成する
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+/// メール認証済みのユーザーを作成し、ログインした状態のトークンを返す
+///
+/// メールアドレスは `{ユーザー名}@example.com` です。
+pub async fn create_user(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(jwt): State<Arc<JwtService>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let username = payload
+        .username
+        .unwrap_or_else(|| format!("e2e_{}", Uuid::new_v4().simple().to_string().split_at(8).0));
+    let password_hash = hash_password(payload.password.as_deref().unwrap_or(DEFAULT_PASSWORD))?;
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await?;
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (username, email, role, email_verified, email_verified_at, created_at, updated_at)
+        VALUES ($1, $2, $3, true, $4, $4, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(&username)
+    .bind(format!("{}@example.com", username))
+    .bind(payload.role.unwrap_or(UserRole::User))
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+    credentials::save_password_hash(&mut *tx, user.id, &password_hash).await?;
+    tx.commit().await?;
+
+    let (cookie_headers, response) =
+        auth_session::issue_session(&pool, &config, &jwt, tokens.as_ref(), user, &headers).await?;
+
+    Ok((StatusCode::CREATED, cookie_headers, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::auth::LoginRequest,
+        test_utils::{test_config, test_jwt, test_tokens},
+    };
+
+    #[sqlx::test]
+    async fn test_作成したユーザーは認証済みでパスワードでもログインできる(
+        pool: PgPool,
+    ) {
+        // 作成したユーザーはメール認証済みで、パスワードでもログインできることを確認
+        // 作成時とログイン時で同じリフレッシュトークンを発行しないよう、生成器を共有する
+        let tokens = test_tokens();
+        let response = create_user(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens.clone()),
+            HeaderMap::new(),
+            Json(CreateUserRequest {
+                username: Some("e2e_moderator".to_string()),
+                password: None,
+                role: Some(UserRole::Moderator),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
+            .bind("e2e_moderator")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(user.email_verified);
+        assert_eq!(user.role, UserRole::Moderator);
+
+        crate::handlers::auth::login::login(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_jwt()),
+            State(tokens),
+            HeaderMap::new(),
+            Json(LoginRequest {
+                email: "e2e_moderator@example.com".to_string(),
+                password: DEFAULT_PASSWORD.to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// 期限切れにするトークンの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    EmailVerification,
+    PasswordReset,
+    Refresh,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExpireTokensRequest {
+    pub kind: TokenKind,
+}
+
+/// ユーザーの未使用のトークンを期限切れにする
+///
+/// 「認証メールのリンクの期限が切れた」などの状態を、時間を待たずに作るために使います。
+pub async fn expire_tokens(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<ExpireTokensRequest>,
+) -> Result<StatusCode, AppError> {
+    let user_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
+    if !user_exists {
+        return Err(AppError::NotFound);
+    }
+
+    let sql = match payload.kind {
+        TokenKind::EmailVerification => {
+            "UPDATE email_verification_tokens SET expires_at = NOW() - INTERVAL '1 second' WHERE user_id = $1 AND consumed_at IS NULL"
+        }
+        TokenKind::PasswordReset => {
+            "UPDATE users SET password_reset_token_expires_at = NOW() - INTERVAL '1 second' WHERE id = $1 AND password_reset_token IS NOT NULL"
+        }
+        TokenKind::Refresh => {
+            "UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 second' WHERE user_id = $1 AND revoked = false"
+        }
+    };
+    sqlx::query(sql).bind(user_id).execute(&pool).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_test_user, test_tokens},
+        utils::email_verification,
+    };
+
+    #[sqlx::test]
+    async fn test_期限切れにした認証トークンでは認証できない(pool: PgPool) {
+        // 期限切れにした認証トークンでは認証できないことを確認
+        let user = create_test_user(&pool, false).await;
+        let mut tx = pool.begin().await.unwrap();
+        let token = email_verification::create_verification_token(
+            test_tokens().as_ref(),
+            user.id,
+            &user.email,
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        let status = expire_tokens(
+            State(pool.clone()),
+            Path(user.id),
+            Json(ExpireTokensRequest {
+                kind: TokenKind::EmailVerification,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(email_verification::verify_email(&token, &pool)
+            .await
+            .is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーは404(pool: PgPool) {
+        // 存在しないユーザーのトークンは期限切れにできず404になることを確認
+        let result = expire_tokens(
+            State(pool.clone()),
+            Path(Uuid::new_v4()),
+            Json(ExpireTokensRequest {
+                kind: TokenKind::Refresh,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -0,0 +1,23 @@
+// E2Eテスト用のルート（/api/_test/*）
+//
+// フロントエンドのE2Eテストで、データベースを直接操作せずに「トークンの期限切れ」などの状態を作るためのルートです。
+// `test-endpoints` フィーチャーを有効にしたビルドにのみ含まれ、さらに設定の
+// `enable_test_endpoints`（ENABLE_TEST_ENDPOINTS=true）のときだけルーターに登録します。
+// OpenAPIドキュメントには載せません。
+pub mod create_user;
+pub mod expire_tokens;
+pub mod seed_thread;
+
+use axum::{routing::post, Router};
+
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/_test/users", post(create_user::create_user))
+        .route(
+            "/api/_test/users/{id}/expire-tokens",
+            post(expire_tokens::expire_tokens),
+        )
+        .route("/api/_test/threads", post(seed_thread::seed_thread))
+}
@@ -0,0 +1,134 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// 一度に作成できるコメントの最大数
+pub const MAX_SEED_COMMENTS: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct SeedThreadRequest {
+    /// スレッドとコメントの投稿者
+    pub user_id: Uuid,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub comment_count: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedThreadResponse {
+    pub thread_id: Uuid,
+    pub short_id: String,
+    /// 投稿日時の古い順
+    pub comment_ids: Vec<Uuid>,
+}
+
+/// 指定した数のコメントが付いたスレッドを作成する
+///
+/// ページングなどの確認用に、コメントは1ミリ秒ずつ投稿日時をずらして作成します。
+pub async fn seed_thread(
+    State(pool): State<PgPool>,
+    Json(payload): Json<SeedThreadRequest>,
+) -> Result<(StatusCode, Json<SeedThreadResponse>), AppError> {
+    if payload.comment_count > MAX_SEED_COMMENTS {
+        return Err(AppError::BadRequest(format!(
+            "comment_count must be at most {}",
+            MAX_SEED_COMMENTS
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let (thread_id, short_id) = sqlx::query_as::<_, (Uuid, String)>(
+        r#"
+        INSERT INTO threads (title, content, user_id)
+        VALUES ($1, $2, $3)
+        RETURNING id, short_id
+        "#,
+    )
+    .bind(payload.title.as_deref().unwrap_or("E2E thread"))
+    .bind("Seeded for end-to-end tests")
+    .bind(payload.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let comment_ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO comments (thread_id, user_id, content, created_at, updated_at)
+        SELECT $1, $2, 'Comment ' || n, NOW() + n * INTERVAL '1 millisecond', NOW() + n * INTERVAL '1 millisecond'
+        FROM generate_series(1, $3) AS n
+        ORDER BY n
+        RETURNING id
+        "#,
+    )
+    .bind(thread_id)
+    .bind(payload.user_id)
+    .bind(payload.comment_count as i32)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SeedThreadResponse {
+            thread_id,
+            short_id,
+            comment_ids,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+
+    #[sqlx::test]
+    async fn test_指定した数のコメントが付いたスレッドを作成する(
+        pool: PgPool,
+    ) {
+        // 指定した数のコメントが付いたスレッドが作成されることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let (status, Json(response)) = seed_thread(
+            State(pool.clone()),
+            Json(SeedThreadRequest {
+                user_id: user.id,
+                title: None,
+                comment_count: 3,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.comment_ids.len(), 3);
+        let ordered = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM comments WHERE thread_id = $1 ORDER BY created_at",
+        )
+        .bind(response.thread_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(ordered, response.comment_ids);
+    }
+
+    #[sqlx::test]
+    async fn test_コメントが多すぎる場合は400(pool: PgPool) {
+        // コメント数が多すぎる場合は400になることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = seed_thread(
+            State(pool.clone()),
+            Json(SeedThreadRequest {
+                user_id: user.id,
+                title: None,
+                comment_count: MAX_SEED_COMMENTS + 1,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, User},
+    repositories::ThreadsRepo,
+};
+
+/// スレッドをブックマーク
+///
+/// 既にブックマークしている場合も 200 を返します。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{id}/bookmark",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Thread bookmarked (or already bookmarked)"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(("bearer_auth" = []))
+)]
+pub async fn bookmark_thread(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    threads.add_bookmark(current_user.id, id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// スレッドのブックマークを外す
+///
+/// ブックマークしていない場合も 204 を返します。
+#[utoipa::path(
+    delete,
+    path = "/api/threads/{id}/bookmark",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 204, description = "Bookmark removed (or was not bookmarked)"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(("bearer_auth" = []))
+)]
+pub async fn unbookmark_thread(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    threads.remove_bookmark(current_user.id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::{
+            threads::{list::get_threads, models::ThreadQuery},
+            users::bookmarks::list_bookmarks,
+        },
+        middleware::MaybeUser,
+        pagination::Pagination,
+        test_utils::{
+            create_test_thread, create_test_user,
+            fakes::{fake_user, FakeRepos},
+            test_config, test_state,
+        },
+    };
+    use axum::{extract::Query, Json};
+    use sqlx::PgPool;
+
+    async fn bookmark(repos: &FakeRepos, id: Uuid, user: &User) -> Result<StatusCode, AppError> {
+        bookmark_thread(
+            State(repos.threads_repo()),
+            Path(id),
+            Extension(user.clone()),
+        )
+        .await
+    }
+
+    async fn unbookmark(repos: &FakeRepos, id: Uuid, user: &User) -> StatusCode {
+        unbookmark_thread(
+            State(repos.threads_repo()),
+            Path(id),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn is_bookmarked(repos: &FakeRepos, id: Uuid, user: &User) -> bool {
+        repos
+            .threads
+            .bookmarked_ids(user.id, &[id])
+            .await
+            .unwrap()
+            .contains(&id)
+    }
+
+    #[tokio::test]
+    async fn test_ブックマークの追加と削除は何度行っても同じ結果になる() {
+        // ブックマークの追加と削除を何度行っても同じ結果になることを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "Thread");
+
+        for _ in 0..2 {
+            assert_eq!(
+                bookmark(&repos, thread_id, &user).await.unwrap(),
+                StatusCode::OK
+            );
+            assert!(is_bookmarked(&repos, thread_id, &user).await);
+        }
+        assert_eq!(repos.threads.count_bookmarks(user.id).await.unwrap(), 1);
+
+        for _ in 0..2 {
+            assert_eq!(
+                unbookmark(&repos, thread_id, &user).await,
+                StatusCode::NO_CONTENT
+            );
+            assert!(!is_bookmarked(&repos, thread_id, &user).await);
+        }
+        assert_eq!(repos.threads.count_bookmarks(user.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_存在しないスレッドはブックマークできない() {
+        // 存在しないスレッドはブックマークできないことを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+
+        let result = bookmark(&repos, Uuid::new_v4(), &user).await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+        assert_eq!(repos.threads.count_bookmarks(user.id).await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_ログイン中のスレッド一覧にブックマークしているかを付ける(
+        pool: PgPool,
+    ) {
+        // ログイン中のスレッド一覧にブックマークしているかが付くことを確認
+        let state = test_state(&pool);
+        let user = create_test_user(&pool, true).await;
+        let bookmarked = create_test_thread(&pool, user.id, "Bookmarked", "Content").await;
+        let other = create_test_thread(&pool, user.id, "Other", "Content").await;
+
+        // 2回目は何もしない
+        for _ in 0..2 {
+            bookmark_thread(
+                State(state.threads.clone()),
+                Path(bookmarked),
+                Extension(user.clone()),
+            )
+            .await
+            .unwrap();
+        }
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bookmarks WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+
+        let list = |current_user: Option<User>| {
+            get_threads(
+                State(state.threads.clone()),
+                State(state.users.clone()),
+                MaybeUser(current_user),
+                Pagination::new(None, None, None, &test_config().content_limits),
+                Query(ThreadQuery {
+                    sort: None,
+                    window: None,
+                    include_blocked: None,
+                    filter: None,
+                }),
+            )
+        };
+        let Json(response) = list(Some(user.clone())).await.unwrap();
+        let flag = |id: Uuid| {
+            response
+                .threads
+                .data
+                .iter()
+                .find(|t| t.id == id)
+                .unwrap()
+                .bookmarked
+        };
+        assert_eq!(flag(bookmarked), Some(true));
+        assert_eq!(flag(other), Some(false));
+
+        // 未ログインの場合は付けない
+        let Json(response) = list(None).await.unwrap();
+        assert!(response.threads.data.iter().all(|t| t.bookmarked.is_none()));
+        let json = serde_json::to_value(&response.threads.data[0]).unwrap();
+        assert!(json.get("bookmarked").is_none());
+
+        let Json(page) = list_bookmarks(
+            State(state.threads.clone()),
+            Extension(user.clone()),
+            Pagination::new(None, None, None, &test_config().content_limits),
+        )
+        .await
+        .unwrap();
+        let ids: Vec<Uuid> = page.data.iter().map(|t| t.id).collect();
+        assert_eq!((ids, page.total), (vec![bookmarked], 1));
+    }
+}
@@ -0,0 +1,247 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        comments::CommentUser,
+        common::ErrorResponse,
+        threads::{ThreadContributor, ThreadContributorsResponse},
+        users,
+    },
+    utils::short_id,
+};
+
+/// 返すユーザーの上限
+const MAX_CONTRIBUTORS: i64 = 10;
+
+/// 集計結果をキャッシュしてよい時間（コメントのたびに変わるため短めにする）
+const CACHE_CONTROL: &str = "public, max-age=60";
+
+#[derive(Debug, sqlx::FromRow)]
+struct ContributorRow {
+    id: Uuid,
+    username: String,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    comment_count: i64,
+    first_commented_at: DateTime<Utc>,
+}
+
+/// スレッドでよくコメントしているユーザーを取得
+///
+/// コメント数の多い順に最大10人を返します（同数の場合は先にコメントしたユーザーが先）。
+/// 投稿者が削除したコメントとモデレーターが非表示にしたコメントは数えません。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/contributors",
+    params(
+        ("id" = String, Path, description = "Thread ID (UUID or short ID)")
+    ),
+    responses(
+        (status = 200, description = "Top contributors of the thread", body = ThreadContributorsResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads"
+)]
+pub async fn get_thread_contributors(
+    State(pool): State<PgPool>,
+    Path(id_or_short_id): Path<String>,
+) -> Result<(HeaderMap, Json<ThreadContributorsResponse>), AppError> {
+    let id = short_id::resolve_thread_id(&pool, &id_or_short_id).await?;
+
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    let rows = sqlx::query_as::<_, ContributorRow>(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.avatar_url,
+               COUNT(*) AS comment_count,
+               MIN(c.created_at) AS first_commented_at
+        FROM comments c
+        JOIN users u ON c.user_id = u.id
+        WHERE c.thread_id = $1 AND c.deleted_at IS NULL AND NOT c.removed_by_moderator
+        GROUP BY u.id
+        ORDER BY comment_count DESC, first_commented_at ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(id)
+    .bind(MAX_CONTRIBUTORS)
+    .fetch_all(&pool)
+    .await?;
+
+    let contributors = rows
+        .into_iter()
+        .map(|row| ThreadContributor {
+            user: CommentUser {
+                id: row.id,
+                username: users::display_username(row.username),
+                display_name: row.display_name,
+                avatar_url: row.avatar_url,
+            },
+            comment_count: row.comment_count,
+            first_commented_at: row.first_commented_at,
+        })
+        .collect();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(CACHE_CONTROL),
+    );
+
+    Ok((headers, Json(ThreadContributorsResponse { contributors })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_comment, create_test_thread, create_test_user};
+    use chrono::Duration;
+
+    /// 指定した時刻にコメントを作成する
+    async fn comment_at(
+        pool: &PgPool,
+        user_id: Uuid,
+        thread_id: Uuid,
+        created_at: DateTime<Utc>,
+    ) -> Uuid {
+        let id = create_test_comment(pool, user_id, thread_id, "Comment", None).await;
+        sqlx::query("UPDATE comments SET created_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(created_at)
+            .execute(pool)
+            .await
+            .unwrap();
+        id
+    }
+
+    #[sqlx::test]
+    async fn test_コメント数の多い順に並び同数は先にコメントしたユーザーが先(
+        pool: PgPool,
+    ) {
+        // コメント数の多い順に並び、同数の場合は先にコメントしたユーザーが先になることを確認
+        let base = Utc::now() - Duration::hours(1);
+        let owner = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, owner.id, "Thread", "Content").await;
+        let frequent = create_test_user(&pool, true).await;
+        let early = create_test_user(&pool, true).await;
+        let late = create_test_user(&pool, true).await;
+        let moderated = create_test_user(&pool, true).await;
+
+        for minutes in [10, 11, 12] {
+            comment_at(
+                &pool,
+                frequent.id,
+                thread_id,
+                base + Duration::minutes(minutes),
+            )
+            .await;
+        }
+        // early と late はどちらも2件だが、early の方が先にコメントしている
+        for minutes in [1, 30] {
+            comment_at(
+                &pool,
+                early.id,
+                thread_id,
+                base + Duration::minutes(minutes),
+            )
+            .await;
+        }
+        for minutes in [5, 6] {
+            comment_at(&pool, late.id, thread_id, base + Duration::minutes(minutes)).await;
+        }
+        // 削除・非表示にされたコメントは数えない
+        comment_at(&pool, moderated.id, thread_id, base).await;
+        let deleted = comment_at(&pool, moderated.id, thread_id, base).await;
+        let removed = comment_at(&pool, moderated.id, thread_id, base).await;
+        let removed_only = comment_at(&pool, owner.id, thread_id, base).await;
+        sqlx::query("UPDATE comments SET deleted_at = NOW(), content = '' WHERE id = $1")
+            .bind(deleted)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE comments SET removed_by_moderator = true, removal_reason = 'spam' WHERE id = ANY($1)",
+        )
+        .bind(vec![removed, removed_only])
+        .execute(&pool)
+        .await
+        .unwrap();
+        // 他のスレッドのコメントは数えない
+        let other_thread = create_test_thread(&pool, owner.id, "Other", "Content").await;
+        for _ in 0..5 {
+            comment_at(&pool, late.id, other_thread, base).await;
+        }
+
+        let (headers, Json(response)) =
+            get_thread_contributors(State(pool.clone()), Path(thread_id.to_string()))
+                .await
+                .unwrap();
+
+        let ranking: Vec<(Uuid, i64)> = response
+            .contributors
+            .iter()
+            .map(|contributor| (contributor.user.id, contributor.comment_count))
+            .collect();
+        assert_eq!(
+            ranking,
+            vec![
+                (frequent.id, 3),
+                (early.id, 2),
+                (late.id, 2),
+                (moderated.id, 1)
+            ]
+        );
+        assert_eq!(
+            response.contributors[1].first_commented_at.timestamp(),
+            (base + Duration::minutes(1)).timestamp()
+        );
+        assert_eq!(response.contributors[0].user.username, frequent.username);
+        assert_eq!(headers[header::CACHE_CONTROL], CACHE_CONTROL);
+
+        // 機密情報が含まれていないことを確認
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("email"));
+    }
+
+    #[sqlx::test]
+    async fn test_上位10人までを返す(pool: PgPool) {
+        // コメントしたユーザーは上位10人までが返されることを確認
+        let owner = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, owner.id, "Thread", "Content").await;
+        for _ in 0..12 {
+            let user = create_test_user(&pool, true).await;
+            create_test_comment(&pool, user.id, thread_id, "Comment", None).await;
+        }
+
+        let (_, Json(response)) =
+            get_thread_contributors(State(pool.clone()), Path(thread_id.to_string()))
+                .await
+                .unwrap();
+
+        assert_eq!(response.contributors.len(), MAX_CONTRIBUTORS as usize);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは404になる(pool: PgPool) {
+        // 存在しないスレッドの参加者は404になることを確認
+        let result =
+            get_thread_contributors(State(pool.clone()), Path(Uuid::new_v4().to_string())).await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
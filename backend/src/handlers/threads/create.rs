@@ -1,18 +1,30 @@
 use axum::{
     extract::{Extension, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
-use sqlx::PgPool;
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
+    events,
     models::{
-        common::ErrorResponse,
-        threads::{CreateThreadRequest, ThreadResponse, ThreadWithUser},
+        common::{ApiWarning, ErrorResponse},
+        moderation::UserMutedErrorResponse,
+        threads::{CreateThreadRequest, ThreadPreviewTag, ThreadResponse},
         User,
     },
+    repositories::{ThreadsRepo, UsersRepo},
+    side_effects::{Notification, Notifier, SideEffects},
+    utils::{
+        soft_limits,
+        tag_rules::{matching_rules, TagRuleCache},
+        tags::MAX_TAGS_PER_THREAD,
+    },
 };
 
 #[utoipa::path(
@@ -20,9 +32,10 @@ use crate::{
     path = "/api/threads",
     request_body = CreateThreadRequest,
     responses(
-        (status = 201, description = "Thread created successfully", body = ThreadResponse),
+        (status = 201, description = "Thread created successfully. `X-Degraded: true` is set when a side effect such as a notification failed. `warnings` lists fields close to their limits", body = ThreadResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
-        (status = 401, description = "Unauthorized", body = ErrorResponse)
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "User is muted", body = UserMutedErrorResponse)
     ),
     tag = "threads",
     security(
@@ -30,10 +43,63 @@ use crate::{
     )
 )]
 pub async fn create_thread(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(users): State<Arc<dyn UsersRepo>>,
+    State(tag_rules): State<Arc<TagRuleCache>>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    State(config): State<Arc<Config>>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<CreateThreadRequest>,
-) -> Result<(StatusCode, Json<ThreadResponse>), AppError> {
+) -> Result<(StatusCode, HeaderMap, Json<ThreadResponse>), AppError> {
+    let prepared =
+        prepare_thread(users.as_ref(), &tag_rules, &config, &current_user, &payload).await?;
+    let tag_ids: Vec<Uuid> = prepared.tags.iter().map(|tag| tag.id).collect();
+
+    let thread = threads
+        .create(
+            &current_user,
+            &payload.title,
+            payload.content.as_deref(),
+            &tag_ids,
+        )
+        .await?;
+
+    events::emit(&events::ThreadCreated {
+        thread_id: thread.id,
+        user_id: current_user.id,
+    });
+
+    // 通知に失敗してもスレッドの作成は成功として返す
+    let mut side_effects = SideEffects::new(notifier);
+    side_effects
+        .notify(Notification::ThreadCreated {
+            thread_id: thread.id,
+            user_id: current_user.id,
+        })
+        .await;
+
+    let mut response = ThreadResponse::from(thread);
+    response.warnings = prepared.warnings;
+    response.client_nonce = payload.client_nonce;
+
+    Ok((StatusCode::CREATED, side_effects.headers(), Json(response)))
+}
+
+/// 作成時に決まる内容（作成とプレビューで共有する）
+pub(super) struct PreparedThread {
+    /// 自動タグ付けルールに一致したタグ
+    pub tags: Vec<ThreadPreviewTag>,
+    pub warnings: Vec<ApiWarning>,
+}
+
+/// 入力の検証・投稿できるかの確認・自動タグ付け・警告の判定を行う（書き込みはしない）
+pub(super) async fn prepare_thread(
+    users: &dyn UsersRepo,
+    tag_rules: &TagRuleCache,
+    config: &Config,
+    current_user: &User,
+    payload: &CreateThreadRequest,
+) -> Result<PreparedThread, AppError> {
     // Validate input
     payload.validate()?;
 
@@ -42,34 +108,44 @@ pub async fn create_thread(
         return Err(AppError::EmailVerificationRequired);
     }
 
-    // Create thread
-    let thread = sqlx::query_as::<_, ThreadWithUser>(
-        r#"
-        INSERT INTO threads (user_id, title, content)
-        VALUES ($1, $2, $3)
-        RETURNING
-            id, title, content, created_at, updated_at,
-            0 as upvote_count, 0 as downvote_count,
-            $1 as user_id, $4 as username, $5 as user_display_name, $6 as user_avatar_url,
-            0::bigint as comment_count
-        "#,
+    // ミュート中のユーザーは投稿できない
+    users.ensure_not_muted(current_user.id, Utc::now()).await?;
+
+    // 自動タグ付けルールに一致したタグをスレッドと一緒に付ける
+    let rules = tag_rules.rules().await?;
+    let tags = matching_rules(
+        &rules,
+        &payload.title,
+        payload.content.as_deref(),
+        MAX_TAGS_PER_THREAD,
     )
-    .bind(current_user.id)
-    .bind(&payload.title)
-    .bind(&payload.content)
-    .bind(&current_user.username)
-    .bind(&current_user.display_name)
-    .bind(&current_user.avatar_url)
-    .fetch_one(&pool)
-    .await?;
-
-    Ok((StatusCode::CREATED, Json(ThreadResponse::from(thread))))
+    .into_iter()
+    .map(|rule| ThreadPreviewTag {
+        id: rule.tag_id,
+        slug: rule.tag_slug.clone(),
+        name: rule.tag_name.clone(),
+    })
+    .collect();
+
+    let warnings = soft_limits::thread_warnings(
+        Some(&payload.title),
+        payload.content.as_deref(),
+        config.soft_limit_warning_percent,
+    );
+
+    Ok(PreparedThread { tags, warnings })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils;
+    use crate::events::capture::CapturedEvents;
+    use crate::side_effects::{self, DEGRADED_HEADER};
+    use crate::test_utils::{
+        self,
+        fakes::{fake_user, FailingNotifier, FakeRepos},
+        test_config, test_state,
+    };
     use axum::http::StatusCode;
     use sqlx::PgPool;
 
@@ -80,12 +156,23 @@ mod tests {
         let request = CreateThreadRequest {
             title: "Test Thread".to_string(),
             content: Some("This is a test thread content".to_string()),
+            client_nonce: None,
         };
 
-        let result = create_thread(State(pool), Extension(user), Json(request)).await;
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         assert!(result.is_ok());
-        let (status, _) = result.unwrap();
+        let (status, _, _) = result.unwrap();
         assert_eq!(status, StatusCode::CREATED);
     }
 
@@ -96,9 +183,20 @@ mod tests {
         let request = CreateThreadRequest {
             title: "Test Thread".to_string(),
             content: Some("This is a test thread content".to_string()),
+            client_nonce: None,
         };
 
-        let result = create_thread(State(pool), Extension(user), Json(request)).await;
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -114,9 +212,20 @@ mod tests {
         let request = CreateThreadRequest {
             title: "".to_string(), // タイトルが空
             content: Some("This is a test thread content".to_string()),
+            client_nonce: None,
         };
 
-        let result = create_thread(State(pool), Extension(user), Json(request)).await;
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -129,14 +238,25 @@ mod tests {
     async fn test_too_long_thread_title(pool: PgPool) {
         // テスト：タイトルが長すぎる場合のエラー
         let user = test_utils::create_test_user(&pool, true).await;
-        let long_title = "a".repeat(51); // タイトルが256文字を超える
+        let long_title = "a".repeat(301); // タイトルが300文字を超える
 
         let request = CreateThreadRequest {
             title: long_title,
             content: Some("This is a test thread content".to_string()),
+            client_nonce: None,
         };
 
-        let result = create_thread(State(pool), Extension(user), Json(request)).await;
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -149,14 +269,25 @@ mod tests {
     async fn test_create_too_long_thread_content(pool: PgPool) {
         // テスト：コンテンツが長すぎる場合のエラー
         let user = test_utils::create_test_user(&pool, true).await;
-        let long_content = "a".repeat(1001); // コンテンツが1000文字を超える
+        let long_content = "a".repeat(40_001); // コンテンツが40,000文字を超える
 
         let request = CreateThreadRequest {
             title: "Test Thread".to_string(),
             content: Some(long_content),
+            client_nonce: None,
         };
 
-        let result = create_thread(State(pool), Extension(user), Json(request)).await;
+        let state = test_state(&pool);
+        let result = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -164,4 +295,359 @@ mod tests {
             err => panic!("Expected ValidationError, got {:?}", err),
         }
     }
+
+    async fn thread_tag_ids(pool: &PgPool, thread_id: uuid::Uuid) -> Vec<uuid::Uuid> {
+        sqlx::query_scalar::<_, uuid::Uuid>(
+            "SELECT tag_id FROM thread_tags WHERE thread_id = $1 ORDER BY created_at, tag_id",
+        )
+        .bind(thread_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_ルールに一致したタグが付く(pool: PgPool) {
+        // タイトル・本文それぞれのルールが大文字・小文字を区別せずに適用されることを確認
+        let user = test_utils::create_test_user(&pool, true).await;
+        let rust = test_utils::create_test_tag(&pool, "Rust").await;
+        let go = test_utils::create_test_tag(&pool, "Go").await;
+        let python = test_utils::create_test_tag(&pool, "Python").await;
+        test_utils::create_test_tag_rule(&pool, "rust", "title", rust.id).await;
+        test_utils::create_test_tag_rule(&pool, "goroutine", "content", go.id).await;
+        test_utils::create_test_tag_rule(&pool, "python", "content", python.id).await;
+
+        let state = test_state(&pool);
+        let (_, _, Json(response)) = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(CreateThreadRequest {
+                title: "RUST vs Python".to_string(),
+                content: Some("Goroutine and async".to_string()),
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let mut tag_ids = thread_tag_ids(&pool, response.id).await;
+        tag_ids.sort();
+        let mut expected = vec![rust.id, go.id];
+        expected.sort();
+        assert_eq!(tag_ids, expected);
+    }
+
+    #[sqlx::test]
+    async fn test_自動タグはスレッドのタグ上限までしか付かない(pool: PgPool) {
+        // ルールで付くタグがスレッドのタグ上限を超えないことを確認
+        let user = test_utils::create_test_user(&pool, true).await;
+        let mut tags = Vec::new();
+        for i in 0..MAX_TAGS_PER_THREAD + 2 {
+            let tag = test_utils::create_test_tag(&pool, &format!("Tag {}", i)).await;
+            test_utils::create_test_tag_rule(&pool, "tag", "title", tag.id).await;
+            tags.push(tag.id);
+        }
+
+        let state = test_state(&pool);
+        let (_, _, Json(response)) = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(CreateThreadRequest {
+                title: "Many tags".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let tag_ids = thread_tag_ids(&pool, response.id).await;
+        assert_eq!(tag_ids.len(), MAX_TAGS_PER_THREAD);
+        assert!(tag_ids.iter().all(|id| tags.contains(id)));
+    }
+
+    #[tokio::test]
+    async fn test_インメモリ実装でスレッドを作成できる() {
+        // データベース無しでレスポンスの組み立てを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+
+        let (status, _, Json(response)) = create_thread(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            State(repos.tag_rules.clone()),
+            State(repos.notifier()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(CreateThreadRequest {
+                title: "Fake Thread".to_string(),
+                content: Some("content".to_string()),
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.title, "Fake Thread");
+        assert_eq!(response.user.id, user.id);
+        assert_eq!(response.comment_count, 0);
+        // 一致するルールが無いためタグは付かない
+        assert!(repos.threads.tags_of(response.id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_文字数の境界と空白だけの入力() {
+        // スレッドのタイトル・本文の文字数の境界と空白だけの入力を検証することを確認
+        // タイトルは前後の空白を除いて300文字まで、本文は40,000文字まで。改行はLFに揃えて保存する
+        let repos = FakeRepos::default();
+        let create = |body: serde_json::Value| {
+            create_thread(
+                State(repos.threads_repo()),
+                State(repos.users_repo()),
+                State(repos.tag_rules.clone()),
+                State(repos.notifier()),
+                State(Arc::new(test_config())),
+                Extension(fake_user(true)),
+                Json(serde_json::from_value::<CreateThreadRequest>(body).unwrap()),
+            )
+        };
+
+        let title = "あ".repeat(300);
+        let (_, _, Json(response)) = create(serde_json::json!({
+            "title": format!("  {}\n", title),
+            "content": format!("{}\r\n", "a".repeat(39_999)),
+        }))
+        .await
+        .unwrap();
+        assert_eq!(response.title, title);
+        let content = response.content.unwrap();
+        assert_eq!(content.chars().count(), 40_000);
+        assert!(content.ends_with("a\n"));
+
+        // 本文は空文字列なら省略と同じ扱い
+        assert!(
+            create(serde_json::json!({ "title": "Title", "content": "" }))
+                .await
+                .is_ok()
+        );
+
+        for body in [
+            serde_json::json!({ "title": format!("{}あ", title) }),
+            serde_json::json!({ "title": " \t　" }),
+            serde_json::json!({ "title": "Title", "content": "a".repeat(40_001) }),
+            serde_json::json!({ "title": "Title", "content": "\r\n  \n" }),
+        ] {
+            assert!(matches!(
+                create(body.clone()).await,
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_上限に近いタイトルには警告を付けて作成する() {
+        // 上限の90%（270文字）のタイトルは作成した上で警告を返し、その手前では返さないことを確認
+        let repos = FakeRepos::default();
+        let create = |title: String| {
+            create_thread(
+                State(repos.threads_repo()),
+                State(repos.users_repo()),
+                State(repos.tag_rules.clone()),
+                State(repos.notifier()),
+                State(Arc::new(test_config())),
+                Extension(fake_user(true)),
+                Json(CreateThreadRequest {
+                    title,
+                    content: None,
+                    client_nonce: None,
+                }),
+            )
+        };
+
+        let (status, _, Json(response)) = create("a".repeat(270)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].code, soft_limits::TITLE_NEAR_LIMIT);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["warnings"][0]["code"], "TITLE_NEAR_LIMIT");
+
+        let (_, _, Json(response)) = create("a".repeat(269)).await.unwrap();
+        assert!(response.warnings.is_empty());
+        // 警告が無い場合は warnings を省略する
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("warnings").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_インメモリ実装でミュート中のユーザーは作成できない() {
+        // ミュート中はUSER_MUTEDとなり、スレッドは保存されないことを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        repos.users.mute(user.id, expires_at);
+
+        let result = create_thread(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            State(repos.tag_rules.clone()),
+            State(repos.notifier()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(CreateThreadRequest {
+                title: "Muted".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::UserMuted(at)) if at == expires_at));
+        assert_eq!(repos.threads.count(None, &[], None).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_スレッド作成時に個人情報を含まないイベントを出力する() {
+        // thread_createdイベントにはIDのみが含まれ、メールアドレス・ユーザー名・本文は含まれないことを確認
+        let (captured, _guard) = CapturedEvents::start();
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+
+        let (_, _, Json(response)) = create_thread(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            State(repos.tag_rules.clone()),
+            State(repos.notifier()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(CreateThreadRequest {
+                title: "Secret Title".to_string(),
+                content: Some("secret content".to_string()),
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let events = captured.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["kind"], "thread_created");
+        assert_eq!(events[0]["payload"]["thread_id"], response.id.to_string());
+        assert_eq!(events[0]["payload"]["user_id"], user.id.to_string());
+
+        let raw = captured.raw();
+        for pii in [
+            user.email.as_str(),
+            user.username.as_str(),
+            "Secret Title",
+            "secret content",
+        ] {
+            assert!(!raw.contains(pii), "event should not contain {}", pii);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_通知に失敗してもスレッドは作成される() {
+        // 通知先が停止していても201を返し、X-Degradedヘッダーと失敗件数で記録することを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let notifier = Arc::new(FailingNotifier::default());
+        let failures_before = side_effects::failure_count();
+
+        let (status, headers, Json(response)) = create_thread(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            State(repos.tag_rules.clone()),
+            State(notifier.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(CreateThreadRequest {
+                title: "Degraded".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(headers.get(DEGRADED_HEADER).unwrap(), "true");
+        assert_eq!(notifier.attempts(), 1);
+        assert!(side_effects::failure_count() > failures_before);
+        assert_eq!(repos.threads.count(None, &[], None).await.unwrap(), 1);
+        assert_eq!(response.title, "Degraded");
+    }
+
+    #[tokio::test]
+    async fn test_通知に成功した場合はdegradedヘッダーを付けない() {
+        // 通知に成功した場合はX-Degradedヘッダーが付かないことを確認
+        let repos = FakeRepos::default();
+
+        let (_, headers, _) = create_thread(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            State(repos.tag_rules.clone()),
+            State(repos.notifier()),
+            State(Arc::new(test_config())),
+            Extension(fake_user(true)),
+            Json(CreateThreadRequest {
+                title: "Healthy".to_string(),
+                content: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert!(headers.get(DEGRADED_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_client_nonceをレスポンスにそのまま含める() {
+        // client_nonceは保存せず、作成時のレスポンスにだけ含めることを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let create = |client_nonce: Option<String>| {
+            create_thread(
+                State(repos.threads_repo()),
+                State(repos.users_repo()),
+                State(repos.tag_rules.clone()),
+                State(repos.notifier()),
+                State(Arc::new(test_config())),
+                Extension(user.clone()),
+                Json(CreateThreadRequest {
+                    title: "Nonce".to_string(),
+                    content: None,
+                    client_nonce,
+                }),
+            )
+        };
+
+        let (status, _, Json(response)) = create(Some("tab-1:7".to_string())).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            serde_json::to_value(&response).unwrap()["client_nonce"],
+            "tab-1:7"
+        );
+
+        let (_, _, Json(response)) = create(None).await.unwrap();
+        assert!(serde_json::to_value(&response)
+            .unwrap()
+            .get("client_nonce")
+            .is_none());
+
+        assert!(matches!(
+            create(Some("a".repeat(65))).await,
+            Err(AppError::Validation(_))
+        ));
+        assert_eq!(repos.threads.count(None, &[], None).await.unwrap(), 2);
+    }
 }
@@ -1,23 +1,31 @@
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
 };
-use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
     error::AppError,
-    models::{common::ErrorResponse, User},
+    models::{common::ErrorResponse, moderation::ModerationDeleteQuery, User},
+    repositories::ThreadsRepo,
 };
 
+/// スレッドを削除する
+///
+/// 投稿者本人に加えて、モデレーター・管理者は他のユーザーのスレッドも削除でき、
+/// その場合は `reason` とともにモデレーションログに記録されます。
 #[utoipa::path(
     delete,
     path = "/api/threads/{id}",
     params(
-        ("id" = Uuid, Path, description = "Thread ID")
+        ("id" = Uuid, Path, description = "Thread ID"),
+        ModerationDeleteQuery
     ),
     responses(
         (status = 204, description = "Thread deleted successfully"),
+        (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 403, description = "Forbidden", body = ErrorResponse),
         (status = 404, description = "Thread not found", body = ErrorResponse)
@@ -28,20 +36,108 @@ use crate::{
     )
 )]
 pub async fn delete_thread(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
     Path(id): Path<Uuid>,
     Extension(current_user): Extension<User>,
+    Query(query): Query<ModerationDeleteQuery>,
 ) -> Result<StatusCode, AppError> {
+    query.validate()?;
+
     // Check if thread exists and user owns it, then delete
-    let deleted_rows = sqlx::query("DELETE FROM threads WHERE id = $1 AND user_id = $2")
-        .bind(id)
-        .bind(current_user.id)
-        .execute(&pool)
-        .await?;
-
-    if deleted_rows.rows_affected() == 0 {
-        return Err(AppError::NotFound);
+    if threads.delete_owned(id, current_user.id).await? {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    // 他人のスレッドはモデレーターのみ削除できる（一般ユーザーには存在を明かさない）
+    if current_user.role.can_moderate()
+        && threads
+            .delete_as_moderator(id, current_user.id, query.reason.as_deref())
+            .await?
+    {
+        tracing::info!(
+            thread_id = %id,
+            moderator_id = %current_user.id,
+            "Thread deleted by moderator"
+        );
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    Err(AppError::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserRole;
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+
+    #[tokio::test]
+    async fn test_他人のスレッドは削除できない() {
+        // 投稿者以外の削除はNotFoundとなり、投稿者本人は削除できることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Title");
+
+        let result = delete_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(fake_user(true)),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let status = delete_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(author),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!repos.threads.exists(thread_id).await.unwrap());
+        // 本人による削除は記録しない
+        assert!(repos.threads.moderation_log().is_empty());
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    #[tokio::test]
+    async fn test_モデレーターは他人のスレッドを削除でき記録が残る() {
+        // モデレーターは他人のスレッドを削除でき、モデレーションログに記録が残ることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Spam");
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..fake_user(true)
+        };
+
+        let status = delete_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(moderator.clone()),
+            Query(ModerationDeleteQuery {
+                reason: Some("spam".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!repos.threads.exists(thread_id).await.unwrap());
+        assert_eq!(
+            repos.threads.moderation_log(),
+            vec![(thread_id, moderator.id, Some("spam".to_string()))]
+        );
+
+        // 存在しないスレッドはモデレーターでもNotFound
+        let result = delete_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(moderator),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
 }
@@ -1,60 +1,131 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     Json,
 };
-use sqlx::PgPool;
-use uuid::Uuid;
+use std::sync::Arc;
 
 use crate::{
     error::AppError,
-    models::{
-        common::ErrorResponse,
-        threads::{ThreadResponse, ThreadWithUser},
-    },
+    middleware::MaybeUser,
+    models::{common::ErrorResponse, threads::ThreadResponse},
+    repositories::{CommentsRepo, ThreadsRepo},
+    utils::http_cache::{self, Conditional},
 };
 
+/// スレッドの詳細を取得
+///
+/// 弱いETagを返し、If-None-Match が一致する場合は本文を省いて 304 を返します。
 #[utoipa::path(
     get,
     path = "/api/threads/{id}",
     params(
-        ("id" = Uuid, Path, description = "Thread ID")
+        ("id" = String, Path, description = "Thread ID (UUID or short ID)")
     ),
     responses(
-        (status = 200, description = "Thread details", body = ThreadResponse),
+        (status = 200, description = "Thread details", body = ThreadResponse,
+            headers(("ETag" = String, description = "Weak entity tag of the thread"))),
+        (status = 304, description = "Not modified since the given If-None-Match"),
         (status = 404, description = "Thread not found", body = ErrorResponse)
     ),
     tag = "threads"
 )]
 pub async fn get_thread(
-    State(pool): State<PgPool>,
-    Path(id): Path<Uuid>,
-) -> Result<Json<ThreadResponse>, AppError> {
-    let thread = sqlx::query_as::<_, ThreadWithUser>(
-        r#"
-        SELECT 
-            t.id, t.title, t.content, t.created_at, t.updated_at,
-            t.upvote_count, t.downvote_count,
-            u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
-            COUNT(c.id)::bigint as comment_count
-        FROM threads t
-        JOIN users u ON t.user_id = u.id
-        LEFT JOIN comments c ON t.id = c.thread_id
-        WHERE t.id = $1
-        GROUP BY t.id, t.upvote_count, t.downvote_count, u.id, u.username, u.display_name, u.avatar_url
-        "#
-    )
-    .bind(id)
-    .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound)?;
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(comments): State<Arc<dyn CommentsRepo>>,
+    Path(id_or_short_id): Path<String>,
+    headers: HeaderMap,
+    MaybeUser(current_user): MaybeUser,
+) -> Result<Conditional<Json<ThreadResponse>>, AppError> {
+    let id = threads.resolve_id(&id_or_short_id).await?;
+
+    let thread = threads.find(id).await?.ok_or_else(|| AppError::NotFound)?;
+
+    let mut response = ThreadResponse::from(thread);
+
+    // 認証済みの場合は既読位置と未読数、ブックマークしているか、投票を付与
+    if let Some(user) = current_user {
+        let position = threads.read_position(user.id, id).await?;
+        response.unread_comment_count = Some(comments.count_unread(id, position.as_ref()).await?);
+        response.last_read_comment_id = position.and_then(|p| p.last_read_comment_id);
+        response.bookmarked = Some(threads.bookmarked_ids(user.id, &[id]).await?.contains(&id));
+        response.user_vote = threads.find_vote(user.id, id).await?;
+    }
 
-    Ok(Json(ThreadResponse::from(thread)))
+    let etag = http_cache::weak_etag(&etag_tag(&response));
+    Conditional::new(&headers, etag, || Ok(Json(response)))
+}
+
+/// ETag の元になる値
+///
+/// 編集（updated_at）とコメント数に加え、更新日時を変えずに変わる投票数・ロック状態と、
+/// 認証済みユーザーごとの既読情報・ブックマーク・投票も含めます。
+fn etag_tag(thread: &ThreadResponse) -> String {
+    format!(
+        "{}:{}:{}:{}:{}:{:?}:{:?}:{:?}:{:?}",
+        thread.updated_at.timestamp_micros(),
+        thread.comment_count,
+        thread.upvote_count,
+        thread.downvote_count,
+        thread.locked,
+        thread.last_read_comment_id,
+        thread.unread_comment_count,
+        thread.bookmarked,
+        thread.user_vote,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::handlers::threads::test_utils::seed_test_data;
+    use crate::models::User;
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+    use crate::test_utils::test_state;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+    };
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    // 実データベースを使って取得する
+    async fn get_thread_from_db(
+        pool: &PgPool,
+        id_or_short_id: String,
+        current_user: Option<User>,
+    ) -> Result<Json<ThreadResponse>, AppError> {
+        let state = test_state(pool);
+        let response = get_thread(
+            State(state.threads),
+            State(state.comments),
+            Path(id_or_short_id),
+            HeaderMap::new(),
+            MaybeUser(current_user),
+        )
+        .await?;
+        match response {
+            Conditional::Modified { body, .. } => Ok(body),
+            Conditional::NotModified { .. } => panic!("If-None-Match なしで 304 が返された"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_get_thread_by_short_id(pool: PgPool) {
+        // 短いIDでもUUIDと同じスレッドが取得できることを確認
+        let (_user_id, thread_id) = seed_test_data(&pool, "detail_short_id").await;
+
+        let by_id = get_thread_from_db(&pool, thread_id.to_string(), None)
+            .await
+            .unwrap();
+        let short_id = by_id.0.short_id.clone();
+
+        let by_short_id = get_thread_from_db(&pool, short_id.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(by_short_id.0.id, thread_id);
+        assert_eq!(by_short_id.0.short_id, short_id);
+    }
 
     #[sqlx::test]
     async fn test_get_thread_success(pool: PgPool) {
@@ -62,7 +133,7 @@ mod tests {
         let (user_id, thread_id) = seed_test_data(&pool, "detail_test").await;
 
         // テスト実行: 特定のスレッドを取得
-        let result = get_thread(State(pool.clone()), Path(thread_id)).await;
+        let result = get_thread_from_db(&pool, thread_id.to_string(), None).await;
 
         // アサーション
         assert!(result.is_ok(), "get_thread should return Ok");
@@ -76,6 +147,46 @@ mod tests {
         );
         assert!(thread.content.is_some(), "Thread content should exist");
         assert_eq!(thread.user.id, user_id, "User ID should match");
+        assert!(
+            thread.unread_comment_count.is_none(),
+            "Unread count should not be present for anonymous users"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_get_thread_with_read_position(pool: PgPool) {
+        // 認証済みユーザーには既読位置と未読コメント数が返されることを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "detail_read_position").await;
+        let reader = crate::test_utils::create_test_user(&pool, true).await;
+
+        let first =
+            crate::test_utils::create_test_comment(&pool, user_id, thread_id, "First", None).await;
+        sqlx::query("UPDATE comments SET created_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(first)
+            .execute(&pool)
+            .await
+            .unwrap();
+        crate::test_utils::create_test_comment(&pool, user_id, thread_id, "Second", None).await;
+
+        sqlx::query(
+            r#"
+            INSERT INTO thread_read_positions (user_id, thread_id, last_read_comment_id, last_read_at)
+            SELECT $1, $2, id, created_at FROM comments WHERE id = $3
+            "#,
+        )
+        .bind(reader.id)
+        .bind(thread_id)
+        .bind(first)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let response = get_thread_from_db(&pool, thread_id.to_string(), Some(reader))
+            .await
+            .unwrap();
+
+        assert_eq!(response.0.last_read_comment_id, Some(first));
+        assert_eq!(response.0.unread_comment_count, Some(1));
     }
 
     #[sqlx::test]
@@ -84,7 +195,7 @@ mod tests {
         let non_existent_id = Uuid::new_v4();
 
         // テスト実行: 存在しないスレッドを取得
-        let result = get_thread(State(pool.clone()), Path(non_existent_id)).await;
+        let result = get_thread_from_db(&pool, non_existent_id.to_string(), None).await;
 
         // アサーション
         assert!(
@@ -98,4 +209,134 @@ mod tests {
             _ => panic!("Expected NotFound error"),
         }
     }
+
+    #[tokio::test]
+    async fn test_インメモリ実装で未読コメント数が付与される() {
+        // 既読位置より後のコメントのみ未読として数えられることを確認
+        let repos = FakeRepos::default();
+        let reader = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "Thread");
+        let base = chrono::Utc::now() - chrono::Duration::minutes(10);
+        let first = repos.comments.insert(thread_id, base);
+        repos
+            .comments
+            .insert(thread_id, base + chrono::Duration::minutes(1));
+        repos
+            .threads
+            .save_read_position(reader.id, thread_id, Some(first), base)
+            .await
+            .unwrap();
+
+        let Conditional::Modified {
+            body: Json(response),
+            ..
+        } = get_thread(
+            State(repos.threads_repo()),
+            State(repos.comments_repo()),
+            Path(thread_id.to_string()),
+            HeaderMap::new(),
+            MaybeUser(Some(reader)),
+        )
+        .await
+        .unwrap()
+        else {
+            panic!("If-None-Match なしで 304 が返された");
+        };
+
+        assert_eq!(response.last_read_comment_id, Some(first));
+        assert_eq!(response.unread_comment_count, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_認証済みの場合は自分の投票とブックマークが付与される() {
+        // 認証済みの場合は自分の投票とブックマークが付くことを確認
+        let repos = FakeRepos::default();
+        let reader = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "Thread");
+        repos
+            .threads
+            .set_vote(reader.id, thread_id, "upvote")
+            .await
+            .unwrap();
+        repos
+            .threads
+            .add_bookmark(reader.id, thread_id)
+            .await
+            .unwrap();
+        let fetch = |user: Option<User>| {
+            get_thread(
+                State(repos.threads_repo()),
+                State(repos.comments_repo()),
+                Path(thread_id.to_string()),
+                HeaderMap::new(),
+                MaybeUser(user),
+            )
+        };
+
+        let Conditional::Modified {
+            body: Json(response),
+            ..
+        } = fetch(Some(reader)).await.unwrap()
+        else {
+            panic!("If-None-Match なしで 304 が返された");
+        };
+        assert_eq!(response.user_vote.as_deref(), Some("upvote"));
+        assert_eq!(response.bookmarked, Some(true));
+
+        // 他のユーザー・未ログインには付かない
+        let Conditional::Modified {
+            body: Json(response),
+            ..
+        } = fetch(Some(fake_user(true))).await.unwrap()
+        else {
+            panic!("If-None-Match なしで 304 が返された");
+        };
+        assert_eq!(response.user_vote, None);
+        assert_eq!(response.bookmarked, Some(false));
+        let Conditional::Modified {
+            body: Json(response),
+            ..
+        } = fetch(None).await.unwrap()
+        else {
+            panic!("If-None-Match なしで 304 が返された");
+        };
+        assert_eq!((response.user_vote, response.bookmarked), (None, None));
+    }
+
+    #[sqlx::test]
+    async fn test_etagが一致すれば304を返し変更後は200を返す(pool: PgPool) {
+        // ETagが一致すれば304を返し、スレッドを変更した後は200を返すことを確認
+        let (user_id, thread_id) = seed_test_data(&pool, "detail_etag").await;
+        let state = test_state(&pool);
+        let fetch = |headers: HeaderMap| {
+            get_thread(
+                State(state.threads.clone()),
+                State(state.comments.clone()),
+                Path(thread_id.to_string()),
+                headers,
+                MaybeUser(None),
+            )
+        };
+
+        let first = fetch(HeaderMap::new()).await.unwrap().into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[header::ETAG].clone();
+        assert!(etag.to_str().unwrap().starts_with("W/"));
+
+        let mut conditional = HeaderMap::new();
+        conditional.insert(header::IF_NONE_MATCH, etag.clone());
+        let second = fetch(conditional.clone()).await.unwrap().into_response();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag);
+        let body = axum::body::to_bytes(second.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        // コメントが増えると ETag が変わり、本文を返す
+        crate::test_utils::create_test_comment(&pool, user_id, thread_id, "New", None).await;
+        let third = fetch(conditional).await.unwrap().into_response();
+        assert_eq!(third.status(), StatusCode::OK);
+        assert_ne!(third.headers()[header::ETAG], etag);
+    }
 }
@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream};
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError, models::common::ErrorResponse, repositories::ThreadsRepo,
+    utils::thread_events::ThreadEvents,
+};
+
+/// スレッドのライブイベントを購読する（Server-Sent Events）
+///
+/// 閲覧者が変わると `presence_changed`（`thread_id`・`viewer_count`・`usernames`）を配信します。
+/// 購読する前のイベントは届きません。接続を保つため、イベントが無い間も定期的にコメント行を送ります。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/events",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Event stream of the thread (`presence_changed`)", content_type = "text/event-stream"),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads"
+)]
+pub async fn get_thread_events(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(events): State<Arc<ThreadEvents>>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let receiver = events.subscribe(id);
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Ok(event.to_sse()), receiver)),
+                // 受信が追いつかずにあふれた分は飛ばし、新しいイベントから続ける
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!(skipped, "Thread event subscriber lagged");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use futures_util::StreamExt;
+    use sqlx::PgPool;
+    use std::time::Duration;
+
+    use crate::test_utils::{
+        app::{spawn_app, Persona},
+        create_test_thread, create_test_user,
+    };
+
+    #[sqlx::test]
+    async fn test_ハートビートで閲覧者の変化が購読者に届く(pool: PgPool) {
+        // ハートビートで閲覧者の変化が購読者に届くことを確認
+        let app = spawn_app(&pool);
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+        let token = app.token_for(Persona::Verified).await.unwrap();
+
+        let response = app
+            .open(
+                Request::get(format!("/api/threads/{}/events", thread_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let mut body = response.into_body().into_data_stream();
+
+        let heartbeat = app
+            .request(
+                Method::POST,
+                &format!("/api/threads/{}/presence", thread_id),
+                Some(&token),
+                Some(r#"{"anonymous": true}"#),
+            )
+            .await;
+        assert_eq!(heartbeat.status, StatusCode::OK, "{}", heartbeat.body);
+
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .expect("presence_changed was not delivered")
+            .unwrap()
+            .unwrap();
+        let message = String::from_utf8(chunk.to_vec()).unwrap();
+        let mut lines = message.lines();
+        assert_eq!(lines.next(), Some("event: presence_changed"));
+        let data: serde_json::Value = serde_json::from_str(
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix("data: "))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(data["thread_id"], thread_id.to_string());
+        assert_eq!(data["viewer_count"], 1);
+        // 匿名の閲覧者はユーザー名を含めない
+        assert_eq!(data["usernames"], serde_json::json!([]));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは購読できない(pool: PgPool) {
+        // 存在しないスレッドのイベントは購読できないことを確認
+        let app = spawn_app(&pool);
+
+        let response = app
+            .request(
+                Method::GET,
+                &format!("/api/threads/{}/events", uuid::Uuid::new_v4()),
+                None,
+                None,
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Path, State},
+    response::Response,
+};
+use std::sync::Arc;
+
+use crate::{
+    error::AppError, models::common::Existence, repositories::ThreadsRepo, utils::http_cache,
+};
+
+/// スレッドが存在するかを確認
+///
+/// 詳細ページと404ページのどちらを表示するかの判定用です。本文は返さず、ステータスのみで答えます。
+/// モデレーターに削除されたスレッドは 410 を返します。結果は短時間キャッシュできます。
+#[utoipa::path(
+    head,
+    path = "/api/threads/{id}",
+    params(
+        ("id" = String, Path, description = "Thread ID (UUID or short ID)")
+    ),
+    responses(
+        (status = 200, description = "Thread exists"),
+        (status = 404, description = "Thread not found"),
+        (status = 410, description = "Thread was deleted by a moderator")
+    ),
+    tag = "threads"
+)]
+pub async fn thread_exists(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id_or_short_id): Path<String>,
+) -> Result<Response, AppError> {
+    let existence = match threads.resolve_id(&id_or_short_id).await {
+        Ok(id) => threads.existence(id).await?,
+        Err(AppError::NotFound) => Existence::Missing,
+        Err(err) => return Err(err),
+    };
+
+    Ok(http_cache::existence_response(existence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+    use axum::http::{header, StatusCode};
+
+    async fn head(repos: &FakeRepos, id: String) -> Response {
+        thread_exists(State(repos.threads_repo()), Path(id))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_存在確認はステータスのみを返し_詳細を読み込まない() {
+        // 存在確認はステータスのみを返し、スレッドの詳細を読み込まないことを確認
+        let repos = FakeRepos::default();
+        let moderator = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "Thread");
+        let removed_id = repos.threads.insert(&fake_user(true), "Removed");
+        repos
+            .threads
+            .delete_as_moderator(removed_id, moderator.id, None)
+            .await
+            .unwrap();
+
+        for (id, expected) in [
+            (thread_id.to_string(), StatusCode::OK),
+            (removed_id.to_string(), StatusCode::GONE),
+            (uuid::Uuid::new_v4().to_string(), StatusCode::NOT_FOUND),
+            ("unknown-short-id".to_string(), StatusCode::NOT_FOUND),
+        ] {
+            let response = head(&repos, id.clone()).await;
+            assert_eq!(response.status(), expected, "{}", id);
+            assert_eq!(
+                response.headers()[header::CACHE_CONTROL],
+                format!("public, max-age={}", http_cache::EXISTENCE_MAX_AGE_SECONDS)
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty());
+        }
+
+        assert_eq!(repos.threads.find_calls(), 0);
+    }
+}
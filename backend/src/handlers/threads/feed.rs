@@ -0,0 +1,130 @@
+use axum::{extract::State, Extension, Json};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        threads::ThreadResponse,
+        User,
+    },
+    pagination::{Pagination, Threads},
+    repositories::ThreadsRepo,
+    utils::follows,
+};
+
+/// フォロー中のユーザーのスレッド一覧
+///
+/// フォロー中のユーザーが投稿したスレッドを新しい順に返します。
+#[utoipa::path(
+    get,
+    path = "/api/feed",
+    params(Pagination<Threads>),
+    responses(
+        (status = 200, description = "Threads by followed users, newest first", body = PaginatedResponse<ThreadResponse>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(("bearer_auth" = []))
+)]
+pub async fn get_feed(
+    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Extension(current_user): Extension<User>,
+    pagination: Pagination<Threads>,
+) -> Result<Json<PaginatedResponse<ThreadResponse>>, AppError> {
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = follows::count_feed(&pool, current_user.id).await?;
+    let mut feed: Vec<ThreadResponse> =
+        follows::list_feed(&pool, current_user.id, limit as i64, offset)
+            .await?
+            .into_iter()
+            .map(ThreadResponse::from)
+            .collect();
+
+    // スレッド一覧と同じくブックマークしているかを付与
+    let ids: Vec<Uuid> = feed.iter().map(|t| t.id).collect();
+    let bookmarked = threads.bookmarked_ids(current_user.id, &ids).await?;
+    for thread in &mut feed {
+        thread.bookmarked = Some(bookmarked.contains(&thread.id));
+    }
+
+    Ok(Json(PaginatedResponse::new(
+        feed,
+        total as u64,
+        page,
+        limit,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::users::follows::{follow_user, unfollow_user},
+        test_utils::{create_test_thread, create_test_user, test_config, test_state},
+    };
+    use axum::extract::Path;
+
+    async fn feed_titles(pool: &PgPool, user: &User) -> (Vec<String>, u64) {
+        let state = test_state(pool);
+        let Json(response) = get_feed(
+            State(pool.clone()),
+            State(state.threads.clone()),
+            Extension(user.clone()),
+            Pagination::new(Some(1), Some(20), None, &test_config().content_limits),
+        )
+        .await
+        .unwrap();
+        (
+            response.data.into_iter().map(|t| t.title).collect(),
+            response.total,
+        )
+    }
+
+    #[sqlx::test]
+    async fn test_フォロー中のユーザーのスレッドだけが新しい順に返る(
+        pool: PgPool,
+    ) {
+        // フォロー中のユーザーのスレッドだけが新しい順に返されることを確認
+        let reader = create_test_user(&pool, true).await;
+        let followee = create_test_user(&pool, true).await;
+        let stranger = create_test_user(&pool, true).await;
+        create_test_thread(&pool, followee.id, "First", "Content").await;
+        create_test_thread(&pool, stranger.id, "Not followed", "Content").await;
+        create_test_thread(&pool, followee.id, "Second", "Content").await;
+
+        // フォローするまでは空
+        assert_eq!(feed_titles(&pool, &reader).await, (vec![], 0));
+
+        follow_user(
+            State(pool.clone()),
+            Path(followee.username.clone()),
+            Extension(reader.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            feed_titles(&pool, &reader).await,
+            (vec!["Second".into(), "First".into()], 2)
+        );
+
+        // フォローを解除するとフィードから消える
+        unfollow_user(
+            State(pool.clone()),
+            Path(followee.username.clone()),
+            Extension(reader.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(feed_titles(&pool, &reader).await, (vec![], 0));
+    }
+}
@@ -0,0 +1,103 @@
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    response::Html,
+};
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, threads::ThreadResponse},
+    repositories::ThreadsRepo,
+};
+
+#[derive(Template)]
+#[template(path = "threads/view.html")]
+struct ThreadHtml<'a> {
+    thread: &'a ThreadResponse,
+    author_name: &'a str,
+}
+
+/// スレッドをHTMLで表示する
+///
+/// 外部のページやプレビューに埋め込むための、タイトル・投稿者・本文だけの簡素なページです。
+/// 本文は一覧や詳細と同じく、許可したタグのみのHTMLに変換します。同一オリジンのフレームにのみ埋め込めます。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/html",
+    params(
+        ("id" = String, Path, description = "Thread ID (UUID or short ID)")
+    ),
+    responses(
+        (status = 200, description = "Thread as an HTML page", content_type = "text/html"),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads"
+)]
+pub async fn get_thread_html(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id_or_short_id): Path<String>,
+) -> Result<Html<String>, AppError> {
+    let id = threads.resolve_id(&id_or_short_id).await?;
+    let thread = ThreadResponse::from(threads.find(id).await?.ok_or(AppError::NotFound)?);
+
+    let author_name = thread
+        .user
+        .display_name
+        .as_deref()
+        .unwrap_or(&thread.user.username);
+    let html = ThreadHtml {
+        thread: &thread,
+        author_name,
+    }
+    .render()
+    .map_err(|e| AppError::Internal(format!("Thread HTML render error: {}", e)))?;
+
+    Ok(Html(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_thread, create_test_user, test_state};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_本文を変換したhtmlを返しタイトルはエスケープする(
+        pool: PgPool,
+    ) {
+        // 本文はMarkdownを変換したHTMLで返し、タイトルはエスケープすることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(
+            &pool,
+            user.id,
+            "<b>Title</b>",
+            "**bold** <script>alert(1)</script>",
+        )
+        .await;
+
+        let Html(html) = get_thread_html(
+            State(test_state(&pool).threads),
+            Path(thread_id.to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>&lt;b&gt;Title&lt;/b&gt;</h1>"));
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは404(pool: PgPool) {
+        // 存在しないスレッドのHTMLは404になることを確認
+        let result = get_thread_html(
+            State(test_state(&pool).threads),
+            Path(uuid::Uuid::new_v4().to_string()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
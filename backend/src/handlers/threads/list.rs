@@ -2,65 +2,121 @@ use axum::{
     extract::{Query, State},
     Json,
 };
-use sqlx::PgPool;
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
 
 use super::models::ThreadQuery;
 use crate::{
     error::AppError,
+    middleware::MaybeUser,
     models::{
-        common::PaginatedResponse,
-        threads::{ThreadListResponse, ThreadResponse, ThreadWithUser},
+        common::{ErrorResponse, PaginatedResponse},
+        threads::{ThreadFilter, ThreadListResponse, ThreadResponse, ThreadSort, TopWindow},
+        users::SortPreferences,
     },
+    pagination::{Pagination, Threads},
+    repositories::{ThreadsRepo, UsersRepo},
 };
 
+/// スレッド一覧を取得
+///
+/// `sort`・`window` を省略した場合、ログイン中はユーザー設定の既定値を使い、
+/// 未設定の場合は新しい順（`new`）・全期間（`all`）になります。
+/// 明示的に指定したパラメータが常に優先されます。
+///
+/// ログイン中はブロックしているユーザーのスレッドを除きます（`include_blocked=true` で含める）。
+/// `filter=unread` の場合は最後に読んだ後に動きが無いスレッドを除きます（ログイン中のみ）。
 #[utoipa::path(
     get,
     path = "/api/threads",
     params(
-        ("page" = Option<u32>, Query, description = "Page number (default: 1)"),
-        ("limit" = Option<u32>, Query, description = "Number of items per page (default: 20)")
+        Pagination<Threads>,
+        ("sort" = Option<ThreadSort>, Query, description = "Sort order: new, top or active (default: user setting, then new)"),
+        ("window" = Option<TopWindow>, Query, description = "Time window for top: day, week, month, year or all (default: user setting, then all)"),
+        ("include_blocked" = Option<bool>, Query, description = "Include threads by users you have blocked (default: false)"),
+        ("filter" = Option<ThreadFilter>, Query, description = "unread: exclude threads with no activity since you last read them (requires authentication)")
     ),
     responses(
-        (status = 200, description = "List of threads", body = ThreadListResponse)
+        (status = 200, description = "List of threads", body = ThreadListResponse),
+        (status = 400, description = "filter=unread without authentication", body = ErrorResponse)
     ),
     tag = "threads"
 )]
 pub async fn get_threads(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(users): State<Arc<dyn UsersRepo>>,
+    MaybeUser(current_user): MaybeUser,
+    pagination: Pagination<Threads>,
     Query(query): Query<ThreadQuery>,
 ) -> Result<Json<ThreadListResponse>, AppError> {
-    let page = query.page.unwrap_or(1);
-    let limit = query.limit.unwrap_or(20);
-    let offset = (page - 1) * limit;
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    // 省略されたパラメータがある場合のみユーザー設定を参照する
+    let preferences = match &current_user {
+        Some(user) if query.sort.is_none() || query.window.is_none() => {
+            users.sort_preferences(user.id).await?
+        }
+        _ => SortPreferences::default(),
+    };
+    let (sort, window) = preferences.thread_sort(query.sort, query.window);
+
+    // 集計期間はスコア順の場合のみ適用する
+    let since = match sort {
+        ThreadSort::New | ThreadSort::Active => None,
+        ThreadSort::Top => window.since(Utc::now()),
+    };
+
+    // ブロックしているユーザーのスレッドは除く
+    let hidden_authors: Vec<Uuid> = match &current_user {
+        Some(user) if !query.include_blocked.unwrap_or(false) => {
+            users.blocked_user_ids(user.id).await?.into_iter().collect()
+        }
+        _ => Vec::new(),
+    };
+
+    // 既読のスレッドを除く（既読位置はログイン中のユーザーにしか無い）
+    let unread_for = match (query.filter, &current_user) {
+        (None, _) => None,
+        (Some(ThreadFilter::Unread), Some(user)) => Some(user.id),
+        (Some(ThreadFilter::Unread), None) => {
+            return Err(AppError::BadRequest(
+                "filter=unread requires authentication".to_string(),
+            ))
+        }
+    };
 
     // Get total count
-    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM threads")
-        .fetch_one(&pool)
-        .await?;
+    let total = threads.count(since, &hidden_authors, unread_for).await?;
 
     // Get threads with user information and comment count
-    let threads = sqlx::query_as::<_, ThreadWithUser>(
-        r#"
-        SELECT 
-            t.id, t.title, t.content, t.created_at, t.updated_at,
-            t.upvote_count, t.downvote_count,
-            u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
-            COUNT(c.id)::bigint as comment_count
-        FROM threads t
-        JOIN users u ON t.user_id = u.id
-        LEFT JOIN comments c ON t.id = c.thread_id
-        GROUP BY t.id, t.upvote_count, t.downvote_count, u.id, u.username, u.display_name, u.avatar_url
-        ORDER BY t.created_at DESC
-        LIMIT $1 OFFSET $2
-        "#
-    )
-    .bind(limit as i64)
-    .bind(offset as i64)
-    .fetch_all(&pool)
-    .await?;
-
-    let thread_responses: Vec<ThreadResponse> =
-        threads.into_iter().map(ThreadResponse::from).collect();
+    let list = threads
+        .list(
+            limit as i64,
+            offset,
+            sort,
+            since,
+            &hidden_authors,
+            unread_for,
+        )
+        .await?;
+
+    let mut thread_responses: Vec<ThreadResponse> =
+        list.into_iter().map(ThreadResponse::from).collect();
+
+    // 認証済みの場合はブックマークしているかを付与
+    if let Some(user) = &current_user {
+        let ids: Vec<Uuid> = thread_responses.iter().map(|t| t.id).collect();
+        let bookmarked = threads.bookmarked_ids(user.id, &ids).await?;
+        for thread in &mut thread_responses {
+            thread.bookmarked = Some(bookmarked.contains(&thread.id));
+        }
+    }
 
     let paginated_response = PaginatedResponse::new(thread_responses, total as u64, page, limit);
 
@@ -72,20 +128,44 @@ pub async fn get_threads(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::comments::{create::create_comment, delete::delete_comment};
     use crate::handlers::threads::test_utils::{create_second_thread, seed_test_data};
+    use crate::models::comments::CommentSort;
+    use crate::models::comments::CreateCommentRequest;
+    use crate::models::moderation::ModerationDeleteQuery;
+    use crate::models::User;
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+    use crate::test_utils::{noop_notifier, test_config, test_state, test_tokens};
+    use axum::{extract::Path, Extension};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    fn page_of(page: Option<u32>, limit: Option<u32>) -> Pagination<Threads> {
+        Pagination::new(page, limit, None, &test_config().content_limits)
+    }
 
     #[sqlx::test]
     async fn test_get_threads(pool: PgPool) {
         // テストデータを準備（ユニークな識別子を使用）
         seed_test_data(&pool, "threads_test").await;
+        let state = test_state(&pool);
 
         // テスト実行: スレッド一覧を取得
         let query = ThreadQuery {
-            page: Some(1),
-            limit: Some(10),
+            sort: None,
+            window: None,
+            include_blocked: None,
+            filter: None,
         };
 
-        let result = get_threads(State(pool.clone()), Query(query)).await;
+        let result = get_threads(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            MaybeUser(None),
+            page_of(Some(1), Some(10)),
+            Query(query),
+        )
+        .await;
 
         // アサーション
         assert!(result.is_ok(), "get_threads should return Ok");
@@ -112,23 +192,41 @@ mod tests {
         )
         .await;
 
+        let state = test_state(&pool);
+
         // ページングテスト: リミット1で1ページ目を取得
         let query1 = ThreadQuery {
-            page: Some(1),
-            limit: Some(1),
+            sort: None,
+            window: None,
+            include_blocked: None,
+            filter: None,
         };
-        let result1 = get_threads(State(pool.clone()), Query(query1))
-            .await
-            .unwrap();
+        let result1 = get_threads(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            MaybeUser(None),
+            page_of(Some(1), Some(1)),
+            Query(query1),
+        )
+        .await
+        .unwrap();
 
         // ページングテスト: リミット1で2ページ目を取得
         let query2 = ThreadQuery {
-            page: Some(2),
-            limit: Some(1),
+            sort: None,
+            window: None,
+            include_blocked: None,
+            filter: None,
         };
-        let result2 = get_threads(State(pool.clone()), Query(query2))
-            .await
-            .unwrap();
+        let result2 = get_threads(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            MaybeUser(None),
+            page_of(Some(2), Some(1)),
+            Query(query2),
+        )
+        .await
+        .unwrap();
 
         // アサーション
         assert_eq!(
@@ -146,4 +244,335 @@ mod tests {
             "Threads on different pages should be different"
         );
     }
+
+    // 古いスレッドに高評価を付け、新しい順とスコア順で先頭が変わるようにする
+    async fn seed_fake_threads(repos: &FakeRepos) -> (Uuid, Uuid) {
+        let author = fake_user(true);
+        let older = repos.threads.insert(&author, "Older");
+        let newer = repos.threads.insert(&author, "Newer");
+        repos
+            .threads
+            .set_vote(fake_user(true).id, older, "upvote")
+            .await
+            .unwrap();
+        (older, newer)
+    }
+
+    async fn first_thread_id(
+        repos: &FakeRepos,
+        current_user: Option<User>,
+        sort: Option<ThreadSort>,
+    ) -> Uuid {
+        let response = get_threads(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            MaybeUser(current_user),
+            page_of(None, None),
+            Query(ThreadQuery {
+                sort,
+                window: None,
+                include_blocked: None,
+                filter: None,
+            }),
+        )
+        .await
+        .unwrap();
+        response.0.threads.data[0].id
+    }
+
+    #[tokio::test]
+    async fn test_並び順_未ログインはサーバーの既定値を使う() {
+        // ユーザー設定が無い場合は新しい順になることを確認
+        let repos = FakeRepos::default();
+        let (_, newer) = seed_fake_threads(&repos).await;
+
+        assert_eq!(first_thread_id(&repos, None, None).await, newer);
+    }
+
+    #[tokio::test]
+    async fn test_並び順_ログイン中はユーザー設定を使う() {
+        // パラメータを省略した場合はユーザー設定のスコア順が適用されることを確認
+        let repos = FakeRepos::default();
+        let (older, _) = seed_fake_threads(&repos).await;
+        let user = fake_user(true);
+        repos.users.set_sort_preferences(
+            user.id,
+            SortPreferences {
+                default_sort: Some(ThreadSort::Top),
+                default_window: Some(TopWindow::Week),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(first_thread_id(&repos, Some(user), None).await, older);
+    }
+
+    #[tokio::test]
+    async fn test_並び順_明示的なパラメータはユーザー設定より優先される() {
+        // ユーザー設定がスコア順でも、sort=newを指定すれば新しい順になることを確認
+        let repos = FakeRepos::default();
+        let (_, newer) = seed_fake_threads(&repos).await;
+        let user = fake_user(true);
+        repos.users.set_sort_preferences(
+            user.id,
+            SortPreferences {
+                default_sort: Some(ThreadSort::Top),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            first_thread_id(&repos, Some(user), Some(ThreadSort::New)).await,
+            newer
+        );
+    }
+
+    #[tokio::test]
+    async fn test_並び順_最近の動きがある順() {
+        // 古いスレッドでも最終アクティビティ日時が新しければ先頭になることを確認
+        let repos = FakeRepos::default();
+        let (older, newer) = seed_fake_threads(&repos).await;
+        repos
+            .threads
+            .set_last_activity(older, Utc::now() + chrono::Duration::minutes(5));
+
+        assert_eq!(
+            first_thread_id(&repos, None, Some(ThreadSort::Active)).await,
+            older
+        );
+        assert_eq!(
+            first_thread_id(&repos, None, Some(ThreadSort::New)).await,
+            newer
+        );
+    }
+
+    async fn thread_titles(
+        repos: &FakeRepos,
+        current_user: Option<User>,
+        include_blocked: Option<bool>,
+    ) -> (Vec<String>, u64) {
+        let Json(response) = get_threads(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            MaybeUser(current_user),
+            page_of(None, None),
+            Query(ThreadQuery {
+                sort: None,
+                window: None,
+                include_blocked,
+                filter: None,
+            }),
+        )
+        .await
+        .unwrap();
+        (
+            response.threads.data.into_iter().map(|t| t.title).collect(),
+            response.threads.total,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_ブロックしているユーザーのスレッドは一覧に含まれない() {
+        // ブロックしているユーザーのスレッドは一覧に含まれないことを確認
+        // include_blocked=trueを指定した場合と、他のユーザー・未ログインでは含まれる
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let blocked = fake_user(true);
+        repos.threads.insert(&fake_user(true), "Visible");
+        repos.threads.insert(&blocked, "Blocked");
+        repos.users.block(user.id, blocked.id);
+
+        assert_eq!(
+            thread_titles(&repos, Some(user.clone()), None).await,
+            (vec!["Visible".into()], 1)
+        );
+        assert_eq!(thread_titles(&repos, Some(user), Some(true)).await.1, 2);
+        assert_eq!(
+            thread_titles(&repos, Some(fake_user(true)), None).await.1,
+            2
+        );
+        assert_eq!(thread_titles(&repos, None, None).await.1, 2);
+    }
+
+    async fn active_thread_ids(state: &crate::state::AppState) -> Vec<Uuid> {
+        let Json(response) = get_threads(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            MaybeUser(None),
+            page_of(None, None),
+            Query(ThreadQuery {
+                sort: Some(ThreadSort::Active),
+                window: None,
+                include_blocked: None,
+                filter: None,
+            }),
+        )
+        .await
+        .unwrap();
+        response
+            .threads
+            .data
+            .iter()
+            .map(|thread| thread.id)
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn test_最近の動きがある順はコメントの作成と削除で変わる(
+        pool: PgPool,
+    ) {
+        // コメントが付くと先頭になり、そのコメントを削除すると元の順に戻ることを確認
+        let user = crate::test_utils::create_test_user(&pool, true).await;
+        let older = crate::test_utils::create_test_thread(&pool, user.id, "Older", "Content").await;
+        let newer = crate::test_utils::create_test_thread(&pool, user.id, "Newer", "Content").await;
+        let state = test_state(&pool);
+
+        assert_eq!(active_thread_ids(&state).await, vec![newer, older]);
+
+        let (_, _, Json(comment)) = create_comment(
+            State(pool.clone()),
+            State(noop_notifier()),
+            State(state.config.clone()),
+            State(test_tokens()),
+            Path(older),
+            Extension(user.clone()),
+            Json(CreateCommentRequest {
+                content: "bump".to_string(),
+                parent_id: None,
+                client_nonce: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(active_thread_ids(&state).await, vec![older, newer]);
+
+        delete_comment(
+            State(pool.clone()),
+            Path(comment.id),
+            Extension(user),
+            Query(ModerationDeleteQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(active_thread_ids(&state).await, vec![newer, older]);
+    }
+
+    #[test]
+    fn test_並び順の優先順位() {
+        // パラメータ > ユーザー設定 > サーバーの既定値の順に項目ごとに決まることを確認
+        let preferences = SortPreferences {
+            default_sort: Some(ThreadSort::Top),
+            default_window: None,
+            default_comment_sort: Some(CommentSort::New),
+        };
+
+        assert_eq!(
+            preferences.thread_sort(None, None),
+            (ThreadSort::Top, TopWindow::All)
+        );
+        assert_eq!(
+            preferences.thread_sort(Some(ThreadSort::New), Some(TopWindow::Day)),
+            (ThreadSort::New, TopWindow::Day)
+        );
+        assert_eq!(preferences.comment_sort(None), CommentSort::New);
+        assert_eq!(
+            preferences.comment_sort(Some(CommentSort::Old)),
+            CommentSort::Old
+        );
+        assert_eq!(
+            SortPreferences::default().thread_sort(None, None),
+            (ThreadSort::New, TopWindow::All)
+        );
+    }
+
+    async fn unread_threads(
+        state: &crate::state::AppState,
+        current_user: Option<User>,
+        limit: u32,
+    ) -> Result<(Vec<String>, u64), AppError> {
+        let Json(response) = get_threads(
+            State(state.threads.clone()),
+            State(state.users.clone()),
+            MaybeUser(current_user),
+            page_of(None, Some(limit)),
+            Query(ThreadQuery {
+                sort: None,
+                window: None,
+                include_blocked: None,
+                filter: Some(ThreadFilter::Unread),
+            }),
+        )
+        .await?;
+        let mut titles: Vec<String> = response.threads.data.into_iter().map(|t| t.title).collect();
+        titles.sort();
+        Ok((titles, response.threads.total))
+    }
+
+    #[sqlx::test]
+    async fn test_未読のみ_既読のスレッドを除き件数にも反映する(
+        pool: PgPool,
+    ) {
+        // 読んだ後に動きが無いスレッドだけを除き、他のユーザーの既読位置は影響しないことを確認
+        let state = test_state(&pool);
+        let reader = crate::test_utils::create_test_user(&pool, true).await;
+        let other = crate::test_utils::create_test_user(&pool, true).await;
+        let author = crate::test_utils::create_test_user(&pool, true).await;
+        let read = crate::test_utils::create_test_thread(&pool, author.id, "Read", "c").await;
+        let stale = crate::test_utils::create_test_thread(&pool, author.id, "Stale", "c").await;
+        let never = crate::test_utils::create_test_thread(&pool, author.id, "Never", "c").await;
+
+        let now = Utc::now();
+        let save = |user_id: Uuid, thread_id: Uuid, at| {
+            let threads = state.threads.clone();
+            async move {
+                threads
+                    .save_read_position(user_id, thread_id, None, at)
+                    .await
+                    .unwrap();
+            }
+        };
+        save(reader.id, read, now + chrono::Duration::minutes(1)).await;
+        // 読んだ後にコメントが付いたスレッドは未読のまま
+        save(reader.id, stale, now - chrono::Duration::hours(1)).await;
+        save(other.id, never, now + chrono::Duration::minutes(1)).await;
+
+        assert_eq!(
+            unread_threads(&state, Some(reader.clone()), 20)
+                .await
+                .unwrap(),
+            (vec!["Never".to_string(), "Stale".to_string()], 2)
+        );
+
+        // ページングしても合計は絞り込み後の件数
+        let (titles, total) = unread_threads(&state, Some(reader), 1).await.unwrap();
+        assert_eq!((titles.len(), total), (1, 2));
+
+        assert_eq!(
+            unread_threads(&state, Some(other), 20).await.unwrap(),
+            (vec!["Read".to_string(), "Stale".to_string()], 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_未読のみ_未ログインでは400を返す() {
+        // 未ログインで未読のみを指定すると400になることを確認
+        let repos = FakeRepos::default();
+        repos.threads.insert(&fake_user(true), "Thread");
+
+        let result = get_threads(
+            State(repos.threads_repo()),
+            State(repos.users_repo()),
+            MaybeUser(None),
+            page_of(None, None),
+            Query(ThreadQuery {
+                sort: None,
+                window: None,
+                include_blocked: None,
+                filter: Some(ThreadFilter::Unread),
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
 }
@@ -0,0 +1,184 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, threads::ThreadResponse, User},
+    repositories::ThreadsRepo,
+};
+
+/// スレッドをロックする
+///
+/// ロック中のスレッドには新しいコメントを投稿できず、既存のコメントも編集できません（閲覧は可能）。
+/// 投稿者本人とモデレーター・管理者のみ実行できます。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{id}/lock",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Thread locked", body = ThreadResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn lock_thread(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<ThreadResponse>, AppError> {
+    set_locked(threads.as_ref(), id, &current_user, true).await
+}
+
+/// スレッドのロックを解除する
+///
+/// 投稿者本人とモデレーター・管理者のみ実行できます。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{id}/unlock",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Thread unlocked", body = ThreadResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unlock_thread(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<ThreadResponse>, AppError> {
+    set_locked(threads.as_ref(), id, &current_user, false).await
+}
+
+async fn set_locked(
+    threads: &dyn ThreadsRepo,
+    id: Uuid,
+    current_user: &User,
+    locked: bool,
+) -> Result<Json<ThreadResponse>, AppError> {
+    let thread = threads
+        .find_without_comment_count(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if thread.user_id != current_user.id && !current_user.role.can_moderate() {
+        return Err(AppError::Forbidden);
+    }
+
+    threads.set_locked(id, locked).await?;
+
+    tracing::info!(
+        thread_id = %id,
+        user_id = %current_user.id,
+        locked,
+        "Thread lock changed"
+    );
+
+    let thread = threads.find(id).await?.ok_or(AppError::NotFound)?;
+
+    Ok(Json(ThreadResponse::from(thread)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserRole;
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+
+    #[tokio::test]
+    async fn test_投稿者はスレッドをロックできる() {
+        // 投稿者は自分のスレッドをロックできることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Title");
+
+        let Json(thread) = lock_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(author.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(thread.locked);
+
+        let Json(thread) = unlock_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(author),
+        )
+        .await
+        .unwrap();
+        assert!(!thread.locked);
+    }
+
+    #[tokio::test]
+    async fn test_投稿者以外はロックできない() {
+        // 一般ユーザーは他人のスレッドをロックできず、存在しないスレッドは404になることを確認
+        let repos = FakeRepos::default();
+        let thread_id = repos.threads.insert(&fake_user(true), "Title");
+
+        let result = lock_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(fake_user(true)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Forbidden)));
+        let thread = repos.threads.find(thread_id).await.unwrap().unwrap();
+        assert!(thread.locked_at.is_none());
+
+        let result = lock_thread(
+            State(repos.threads_repo()),
+            Path(Uuid::new_v4()),
+            Extension(fake_user(true)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_モデレーターは他人のスレッドのロックを解除できる() {
+        // モデレーターは他人のスレッドのロックを解除できることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..fake_user(true)
+        };
+        let thread_id = repos.threads.insert(&author, "Title");
+
+        let _ = lock_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(author),
+        )
+        .await
+        .unwrap();
+        let Json(thread) = unlock_thread(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(moderator),
+        )
+        .await
+        .unwrap();
+        assert!(!thread.locked);
+    }
+}
@@ -1,18 +1,21 @@
+pub mod bookmark;
+pub mod contributors;
 pub mod create;
 pub mod delete;
+pub mod events;
 pub mod detail;
+pub mod exists;
+pub mod feed;
+pub mod html;
 pub mod list;
+pub mod lock;
 pub mod models;
 pub mod ogp;
+pub mod presence;
+pub mod preview;
+pub mod read_position;
+pub mod report;
+pub mod revisions;
 pub mod test_utils;
 pub mod update;
 pub mod vote;
-
-// ハンドラー関数を再エクスポート
-pub use create::create_thread;
-pub use delete::delete_thread;
-pub use detail::get_thread;
-pub use list::get_threads;
-pub use ogp::get_thread_ogp_image;
-pub use update::update_thread;
-pub use vote::vote_thread;
@@ -1,7 +1,13 @@
 use serde::Deserialize;
 
+use crate::models::threads::{ThreadFilter, ThreadSort, TopWindow};
+
 #[derive(Deserialize)]
 pub struct ThreadQuery {
-    pub page: Option<u32>,
-    pub limit: Option<u32>,
+    pub sort: Option<ThreadSort>,
+    pub window: Option<TopWindow>,
+    /// ブロックしているユーザーのスレッドも含める
+    pub include_blocked: Option<bool>,
+    /// `unread` の場合は既読のスレッドを除く（ログイン中のみ）
+    pub filter: Option<ThreadFilter>,
 }
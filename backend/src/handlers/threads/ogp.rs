@@ -1,24 +1,24 @@
 use axum::{
     extract::{Path, State},
-    http::{header, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::Response,
 };
-use image::{ImageBuffer, Rgb, RgbImage};
-use imageproc::{
-    drawing::{draw_filled_rect_mut, draw_text_mut},
-    rect::Rect,
-};
-use rusttype::{Font, Scale};
-use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::threads::ThreadWithUser;
+use crate::repositories::ThreadsRepo;
+use crate::utils::http_cache::{self, Conditional};
+use crate::utils::ogp::{OgpRenderer, IMAGE_REVISION};
+use crate::utils::ogp_cache::{OgpCacheKey, OgpImageCache};
 
 /// スレッドのOGP画像を生成
 ///
 /// 指定されたスレッドIDに基づいてOGP画像を生成します。
-/// 画像にはスレッドのタイトルと投稿者名が含まれます。
+/// 画像にはスレッドのタイトル・投稿者名・スコア・コメント数が含まれます。
+/// スコアとコメント数は10単位に丸めた値が変わったときだけ生成し直すため、
+/// タイトル・投稿者名と丸めた値から強いETagを作り、
+/// If-None-Match が一致する場合は画像を生成せずに 304 を返します。
 #[utoipa::path(
     get,
     path = "/api/threads/{thread_id}/ogp.png",
@@ -26,255 +26,50 @@ use crate::models::threads::ThreadWithUser;
         ("thread_id" = Uuid, Path, description = "スレッドID")
     ),
     responses(
-        (status = 200, description = "OGP画像", content_type = "image/png"),
+        (status = 200, description = "OGP画像", content_type = "image/png",
+            headers(("ETag" = String, description = "画像の強いETag"))),
+        (status = 304, description = "If-None-Match の画像から変更なし"),
         (status = 404, description = "スレッドが見つかりません")
     ),
     tag = "threads"
 )]
 pub async fn get_thread_ogp_image(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(ogp_images): State<Arc<OgpImageCache>>,
     Path(thread_id): Path<Uuid>,
-) -> Result<Response> {
-    // データベースからスレッド情報を取得（投稿者情報も含む）
-    let thread = sqlx::query_as::<_, ThreadWithUser>(
-        r#"
-        SELECT 
-            t.id, t.title, t.content, t.created_at, t.updated_at,
-            t.upvote_count, t.downvote_count,
-            u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
-            0::bigint as comment_count
-        FROM threads t
-        JOIN users u ON t.user_id = u.id
-        WHERE t.id = $1
-        "#
-    )
-    .bind(thread_id)
-    .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound)?;
+    headers: HeaderMap,
+) -> Result<Conditional<Response>> {
+    // データベースからスレッド情報を取得（投稿者情報とコメント数も含む）
+    let thread = threads
+        .find(thread_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+    let score = i64::from(thread.upvote_count) - i64::from(thread.downvote_count);
+    let comment_count = thread.comment_count.unwrap_or(0);
 
     // タイトルとユーザー名から絵文字を除去してOGP画像を生成
     let clean_title = remove_emojis(&thread.title);
-    let image_data = generate_ogp_image(&clean_title, &thread.username)?;
-
-    // 画像データをPNG形式でレスポンスとして返す（24時間キャッシュ設定）
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "public, max-age=86400") // 24時間キャッシュ
-        .body(image_data.into())
-        .map_err(|_| AppError::Internal("Response build error".to_string()))?;
-
-    Ok(response)
-}
-
-/// OGP画像を生成する関数
-fn generate_ogp_image(title: &str, username: &str) -> Result<Vec<u8>> {
-    // OGP推奨サイズ（1200x630）で画像を作成
-    const WIDTH: u32 = 1200;
-    const HEIGHT: u32 = 630;
-    const BORDER_WIDTH: u32 = 32; // オレンジ枠線の太さ
-
-    // カラーパレット定義（TailwindCSS準拠）
-    let background_color = Rgb([255, 255, 255]); // 白背景
-    let border_color = Rgb([234, 88, 12]); // オレンジ枠線（text-orange-600）
-    let text_color = Rgb([0, 0, 0]); // 黒文字
-    let username_color = Rgb([107, 114, 128]); // グレー文字
-    let brand_color = Rgb([234, 88, 12]); // ブランド名（オレンジ）
-
-    // 白背景の画像を作成
-    let mut image: RgbImage = ImageBuffer::from_pixel(WIDTH, HEIGHT, background_color);
-
-    // 日本語フォント（Noto Sans JP）を読み込み
-    let font_data = include_bytes!("../../static/fonts/NotoSansJP-SemiBold.ttf");
-    let font = Font::try_from_bytes(font_data as &[u8])
-        .ok_or_else(|| AppError::Internal("Failed to load font".to_string()))?;
-
-    // 画像四辺にオレンジの枠線を描画
-    // 上辺
-    draw_filled_rect_mut(
-        &mut image,
-        Rect::at(0, 0).of_size(WIDTH, BORDER_WIDTH),
-        border_color,
-    );
-    // 下辺
-    draw_filled_rect_mut(
-        &mut image,
-        Rect::at(0, (HEIGHT - BORDER_WIDTH) as i32).of_size(WIDTH, BORDER_WIDTH),
-        border_color,
-    );
-    // 左辺
-    draw_filled_rect_mut(
-        &mut image,
-        Rect::at(0, 0).of_size(BORDER_WIDTH, HEIGHT),
-        border_color,
+    let text = format!("{}\0{}\0{}", IMAGE_REVISION, clean_title, thread.username);
+    let key = OgpCacheKey::new(&text, score, comment_count);
+    let etag = http_cache::strong_etag(
+        format!("{}\0{}\0{}", text, key.score_bucket, key.comment_bucket).as_bytes(),
     );
-    // 右辺
-    draw_filled_rect_mut(
-        &mut image,
-        Rect::at((WIDTH - BORDER_WIDTH) as i32, 0).of_size(BORDER_WIDTH, HEIGHT),
-        border_color,
-    );
-
-    // スレッドタイトルを画像上部に描画（長い場合は自動改行、最大4行）
-    let title_scale = Scale { x: 80.0, y: 80.0 }; // タイトル用フォントサイズ
-    let max_title_width = WIDTH - 200; // 左右マージン100pxずつ確保
-    let wrapped_title = wrap_text(title, &font, title_scale, max_title_width);
-
-    let mut y_offset = 90; // タイトル開始位置（上マージン）
-    for line in wrapped_title.iter().take(4) {
-        // 最大4行まで表示
-        draw_text_mut(
-            &mut image,
-            text_color,
-            100, // 左マージン
-            y_offset as i32,
-            title_scale,
-            &font,
-            line,
-        );
-        y_offset += 85; // 行間隔
-    }
-
-    // 左下にユーザー名を描画（@マーク付きで表示）
-    let username_scale = Scale { x: 58.0, y: 58.0 }; // ユーザー名用フォントサイズ
-    let username_with_at = format!("@{}", username);
-    draw_text_mut(
-        &mut image,
-        username_color,
-        100,                   // 左マージン
-        (HEIGHT - 140) as i32, // 下から140px上
-        username_scale,
-        &font,
-        &username_with_at,
-    );
-
-    // 右下にサイト名「みんなの話題」を描画
-    let brand_scale = Scale { x: 58.0, y: 58.0 }; // ブランド名用フォントサイズ
-    let brand_text = "みんなの話題";
-
-    // ブランド名のテキスト幅を計算して右寄せ配置
-    let glyphs: Vec<_> = font
-        .layout(brand_text, brand_scale, rusttype::point(0.0, 0.0))
-        .collect();
-
-    let text_width = glyphs
-        .last()
-        .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
-        .unwrap_or(0.0) as u32;
-
-    let brand_x = WIDTH - text_width - 100; // 右マージン100px確保
-    draw_text_mut(
-        &mut image,
-        brand_color,
-        brand_x as i32,
-        (HEIGHT - 140) as i32, // 下から140px上
-        brand_scale,
-        &font,
-        brand_text,
-    );
-
-    // 生成した画像をPNG形式のバイト配列にエンコード
-    let mut buffer = Vec::new();
-    image
-        .write_to(
-            &mut std::io::Cursor::new(&mut buffer),
-            image::ImageOutputFormat::Png,
-        )
-        .map_err(|_| AppError::Internal("PNG encoding error".to_string()))?;
-
-    Ok(buffer)
-}
-
-/// テキストを指定幅に合わせて自動改行する関数
-fn wrap_text(text: &str, font: &Font, scale: Scale, max_width: u32) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_line = String::new();
-
-    // 単語ごとに分割して処理
-    for word in text.split_whitespace() {
-        let test_line = if current_line.is_empty() {
-            word.to_string()
-        } else {
-            format!("{} {}", current_line, word)
-        };
-
-        let width = text_width(font, scale, &test_line);
-
-        if width <= max_width as f32 {
-            // 幅内に収まる場合は行に追加
-            current_line = test_line;
-        } else {
-            // 幅を超える場合は改行
-            if !current_line.is_empty() {
-                lines.push(current_line);
-            }
-            current_line = word.to_string();
-            let current_width = text_width(font, scale, &current_line);
-
-            // 単語が非常に長い場合（URLなど）は強制的に文字単位で折り返し
-            if current_width > max_width as f32 {
-                let broken = break_long_word(&current_line, font, scale, max_width);
-                lines.extend(broken.into_iter());
-                current_line.clear();
-            }
-        }
-    }
-
-    // 最後の行を追加
-    if !current_line.is_empty() {
-        lines.push(current_line);
-    }
-
-    // 空の場合は「無題」を表示
-    if lines.is_empty() {
-        lines.push("無題".to_string());
-    }
-
-    lines
-}
-
-/// テキストの描画幅を計算する関数
-fn text_width(font: &Font, scale: Scale, text: &str) -> f32 {
-    font.layout(text, scale, rusttype::point(0.0, 0.0))
-        .last()
-        .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
-        .unwrap_or(0.0)
-}
 
-/// 長すぎる単語を文字単位で強制改行する関数（URL対応）
-fn break_long_word(word: &str, font: &Font, scale: Scale, max_width: u32) -> Vec<String> {
-    let mut result = Vec::new();
-    let mut buffer = String::new();
-
-    for c in word.chars() {
-        let single_char_width = text_width(font, scale, &c.to_string());
-
-        // 1文字でもmax_widthを超える場合は、その文字だけで1行にする
-        if single_char_width > max_width as f32 {
-            if !buffer.is_empty() {
-                result.push(buffer.clone());
-                buffer.clear();
-            }
-            result.push(c.to_string());
-            continue;
-        }
-
-        buffer.push(c);
-        if text_width(font, scale, &buffer) > max_width as f32 {
-            buffer.pop(); // 最後の文字を削除
-            if !buffer.is_empty() {
-                result.push(buffer.clone());
-            }
-            buffer = c.to_string(); // 新しい行で削除した文字から開始
-        }
-    }
-
-    if !buffer.is_empty() {
-        result.push(buffer);
-    }
-
-    result
+    Conditional::new(&headers, etag, || {
+        // 生成済みの画像があれば使い回す（タイトルを変更するか、スコア・コメント数が大きく変わると生成し直す）
+        let (image_data, cache) = ogp_images.get_or_generate(thread.id, key, || {
+            OgpRenderer::shared()?.render(&clean_title, &thread.username, score, comment_count)
+        })?;
+        tracing::debug!(%thread_id, ?cache, "OGP image");
+
+        // 画像データをPNG形式でレスポンスとして返す（24時間キャッシュ設定）
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "image/png")
+            .header(header::CACHE_CONTROL, "public, max-age=86400") // 24時間キャッシュ
+            .body(image_data.into())
+            .map_err(|_| AppError::Internal("Response build error".to_string()))
+    })
 }
 
 /// テキストから絵文字を除去する関数
@@ -319,7 +114,17 @@ fn is_emoji(c: char) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::seed_test_data;
+    use crate::{
+        handlers::threads::{
+            update::update_thread,
+            vote::{vote_thread, VoteRequest},
+        },
+        models::{threads::UpdateThreadRequest, User},
+        state::AppState,
+        test_utils::{create_test_thread, create_test_user, seed_test_data, test_state},
+    };
+    use axum::{extract::Extension, response::IntoResponse, Json};
+    use sqlx::PgPool;
 
     #[sqlx::test]
     async fn test_正常なスレッドで_ogp_画像が生成される(pool: PgPool) {
@@ -327,11 +132,17 @@ mod tests {
         let (_user_id, thread_id) = seed_test_data(&pool, "ogp_test").await;
 
         // スレッドIDを指定してOGP画像生成APIを呼び出し
-        let response = get_thread_ogp_image(State(pool), Path(thread_id)).await;
+        let response = get_thread_ogp_image(
+            State(test_state(&pool).threads),
+            State(Arc::new(OgpImageCache::default())),
+            Path(thread_id),
+            HeaderMap::new(),
+        )
+        .await;
 
         // レスポンスが正常に返されることを確認
         assert!(response.is_ok());
-        let response = response.unwrap();
+        let response = response.unwrap().into_response();
         assert_eq!(response.status(), StatusCode::OK);
 
         // Content-TypeがPNG画像であることを確認
@@ -345,73 +156,141 @@ mod tests {
         let non_existent_id = Uuid::new_v4();
 
         // 存在しないスレッドIDでOGP画像生成を試行
-        let response = get_thread_ogp_image(State(pool), Path(non_existent_id)).await;
+        let response = get_thread_ogp_image(
+            State(test_state(&pool).threads),
+            State(Arc::new(OgpImageCache::default())),
+            Path(non_existent_id),
+            HeaderMap::new(),
+        )
+        .await;
 
         // NotFoundエラーが返されることを確認
-        assert!(response.is_err());
-        match response.unwrap_err() {
-            AppError::NotFound => {} // 期待される結果
+        match response {
+            Err(AppError::NotFound) => {} // 期待される結果
             _ => panic!("NotFoundエラーが期待されましたが、異なるエラーが発生しました"),
         }
     }
 
-    #[test]
-    fn test_基本的な_ogp_画像が正常に生成される() {
-        // 通常の長さのタイトルとユーザー名でテスト
-        let title = "これは非常に長いタイトルのテストです";
-        let username = "testuser";
-
-        // OGP画像生成を実行
-        let result = generate_ogp_image(title, username);
-
-        // 画像生成が成功することを確認
-        match &result {
-            Ok(_) => {}
-            Err(e) => panic!("OGP画像生成に失敗しました: {:?}", e),
-        }
+    #[sqlx::test]
+    async fn test_etagが一致すれば画像を生成せずに304を返す(pool: PgPool) {
+        // ETagが一致すれば画像を生成せずに304を返すことを確認
+        let (_user_id, thread_id) = seed_test_data(&pool, "ogp_etag").await;
+        let state = test_state(&pool);
+        let fetch = |headers: HeaderMap| {
+            get_thread_ogp_image(
+                State(state.threads.clone()),
+                State(state.ogp_images.clone()),
+                Path(thread_id),
+                headers,
+            )
+        };
 
-        let image_data = result.unwrap();
-        assert!(!image_data.is_empty());
+        let first = fetch(HeaderMap::new()).await.unwrap().into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[header::ETAG].clone();
+        assert!(!etag.to_str().unwrap().starts_with("W/"));
+
+        let mut conditional = HeaderMap::new();
+        conditional.insert(header::IF_NONE_MATCH, etag.clone());
+        let second = fetch(conditional.clone()).await.unwrap();
+        assert!(matches!(second, Conditional::NotModified { .. }));
+        let second = second.into_response();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag);
+        let body = axum::body::to_bytes(second.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        // タイトルが変わると ETag も変わる
+        sqlx::query("UPDATE threads SET title = 'Renamed', updated_at = NOW() WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let third = fetch(conditional).await.unwrap().into_response();
+        assert_eq!(third.status(), StatusCode::OK);
+        assert_ne!(third.headers()[header::ETAG], etag);
+    }
 
-        // 生成されたデータがPNG形式であることを確認（マジックバイト）
-        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+    /// 画像のバイト列を取得する
+    async fn image_bytes(state: &AppState, thread_id: Uuid) -> Vec<u8> {
+        let response = get_thread_ogp_image(
+            State(state.threads.clone()),
+            State(state.ogp_images.clone()),
+            Path(thread_id),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
     }
 
-    #[test]
-    fn test_非常に長いタイトルでも画像生成が成功する() {
-        // 複数行に折り返しが必要な長いタイトルでテスト
-        let title = "これは非常に長いタイトルのテストで、複数行に分かれることを期待しています。折り返し機能が正しく動作するかを確認するためのテストケースです。";
-        let username = "very_long_username_test";
+    #[sqlx::test]
+    async fn test_生成した画像を使い回しタイトルを変更すると生成し直す(
+        pool: PgPool,
+    ) {
+        // 生成した画像を使い回し、タイトルを変更すると生成し直すことを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "元のタイトル", "Content").await;
+        let state = test_state(&pool);
+
+        let first = image_bytes(&state, thread_id).await;
+        let second = image_bytes(&state, thread_id).await;
+        assert_eq!(first, second);
+
+        let _ = update_thread(
+            State(state.threads.clone()),
+            State(state.config.clone()),
+            State(state.ogp_images.clone()),
+            Path(thread_id),
+            Extension(author),
+            Json(UpdateThreadRequest {
+                title: Some("まったく別のタイトル".to_string()),
+                content: None,
+            }),
+        )
+        .await
+        .unwrap();
 
-        // 長いタイトルでも画像生成が成功することを確認
-        let result = generate_ogp_image(title, username);
+        let renamed = image_bytes(&state, thread_id).await;
+        assert_ne!(renamed, first);
+    }
 
-        assert!(result.is_ok());
-        let image_data = result.unwrap();
-        assert!(!image_data.is_empty());
+    #[sqlx::test]
+    async fn test_スコアが大きく変わったときだけ生成し直す(pool: PgPool) {
+        // スコアが大きく変わったときだけOGP画像を生成し直すことを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "投票されるスレッド", "Content").await;
+        let state = test_state(&pool);
+        let vote = |voter: User| {
+            vote_thread(
+                State(state.threads.clone()),
+                Path(thread_id),
+                Extension(voter),
+                Json(VoteRequest {
+                    vote_type: "upvote".to_string(),
+                }),
+            )
+        };
 
-        // PNG形式で出力されることを確認
-        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
-    }
+        let first = image_bytes(&state, thread_id).await;
 
-    #[test]
-    fn test_テキストが指定幅で正しく折り返される() {
-        // 日本語フォントを読み込み
-        let font_data = include_bytes!("../../static/fonts/NotoSansJP-SemiBold.ttf");
-        let font = Font::try_from_bytes(font_data as &[u8]).unwrap();
-
-        // 折り返しテスト用の長いテキスト
-        let text = "これは非常に長いタイトルのテストです。複数行に分かれることを期待しています。";
-        let scale = Scale { x: 36.0, y: 36.0 };
-        let max_width = 600;
-
-        // テキスト折り返し処理を実行
-        let lines = wrap_text(text, &font, scale, max_width);
-
-        // 折り返し結果が正しく生成されることを確認
-        assert!(!lines.is_empty());
-        // 長いテキストは少なくとも1行以上に分かれることを確認
-        assert!(lines.len() >= 1);
+        // 2票ではスコアを丸めた値が変わらず、同じ画像を返す
+        for _ in 0..2 {
+            let _ = vote(create_test_user(&pool, true).await).await.unwrap();
+        }
+        assert_eq!(image_bytes(&state, thread_id).await, first);
+
+        // さらに15票でスコアが17になり、生成し直す
+        for _ in 0..15 {
+            let _ = vote(create_test_user(&pool, true).await).await.unwrap();
+        }
+        assert_ne!(image_bytes(&state, thread_id).await, first);
     }
 
     #[test]
@@ -451,21 +330,4 @@ mod tests {
         assert!(!is_emoji('1')); // 数字
         assert!(!is_emoji('!')); // 記号
     }
-
-    #[test]
-    fn test_絵文字入りタイトルでも_ogp_画像が生成される() {
-        // 絵文字が混在するタイトルとユーザー名でテスト
-        let title = "テスト投稿です😀🎉 いい感じ！";
-        let username = "testuser";
-
-        // 絵文字が含まれていても画像生成が成功することを確認
-        let result = generate_ogp_image(title, username);
-
-        assert!(result.is_ok());
-        let image_data = result.unwrap();
-        assert!(!image_data.is_empty());
-
-        // PNG形式で正しく生成されることを確認
-        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
-    }
 }
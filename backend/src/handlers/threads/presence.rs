@@ -0,0 +1,159 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use std::{sync::Arc, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        threads::{PresenceRequest, PresenceResponse},
+        User,
+    },
+    repositories::ThreadsRepo,
+    utils::{
+        presence::{self, PresenceRegistry},
+        thread_events::ThreadEvents,
+    },
+};
+
+/// スレッドの閲覧を知らせる（ハートビート）
+///
+/// 閲覧中は10秒程度ごとに送ってください。30秒送らなかったユーザーは閲覧者から外れます。
+/// 閲覧者が変わると `GET /api/threads/{id}/events` に `presence_changed` を配信します（同じスレッドにつき3秒に1回まで）。
+/// データベースには保存しません。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{id}/presence",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    request_body = PresenceRequest,
+    responses(
+        (status = 200, description = "Current viewers of the thread", body = PresenceResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(("bearer_auth" = []))
+)]
+pub async fn update_presence(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(registry): State<Arc<PresenceRegistry>>,
+    State(events): State<Arc<ThreadEvents>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<PresenceRequest>,
+) -> Result<Json<PresenceResponse>, AppError> {
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let (current, changed) = registry.heartbeat(
+        id,
+        current_user.id,
+        &current_user.username,
+        payload.anonymous.unwrap_or(false),
+        Instant::now(),
+    );
+    if let Some(snapshot) = changed {
+        presence::broadcast(&events, id, snapshot);
+    }
+
+    Ok(Json(PresenceResponse::from(current)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::fakes::{fake_user, FakeRepos},
+        utils::{presence::PRESENCE_CHANGED, thread_events::ThreadEvent},
+    };
+    use serde_json::json;
+
+    async fn heartbeat(
+        repos: &FakeRepos,
+        registry: &Arc<PresenceRegistry>,
+        events: &Arc<ThreadEvents>,
+        thread_id: Uuid,
+        user: &User,
+        anonymous: bool,
+    ) -> Result<PresenceResponse, AppError> {
+        let Json(response) = update_presence(
+            State(repos.threads_repo()),
+            State(registry.clone()),
+            State(events.clone()),
+            Path(thread_id),
+            Extension(user.clone()),
+            Json(PresenceRequest {
+                anonymous: Some(anonymous),
+            }),
+        )
+        .await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_閲覧者の変化をまとめて配信する() {
+        // 閲覧者の変化がまとめて配信されることを確認
+        let repos = FakeRepos::default();
+        let registry = Arc::new(PresenceRegistry::new());
+        let events = Arc::new(ThreadEvents::new());
+        let thread_id = repos.threads.insert(&fake_user(true), "Thread");
+        let mut subscriber = events.subscribe(thread_id);
+        let alice = fake_user(true);
+        let bob = fake_user(true);
+
+        let response = heartbeat(&repos, &registry, &events, thread_id, &alice, false)
+            .await
+            .unwrap();
+        assert_eq!(response.viewer_count, 1);
+        assert_eq!(response.usernames, vec![alice.username.clone()]);
+
+        // 直後の変化は配信せず、レスポンスには反映する。匿名の閲覧者は人数にのみ含める
+        let response = heartbeat(&repos, &registry, &events, thread_id, &bob, true)
+            .await
+            .unwrap();
+        assert_eq!(response.viewer_count, 2);
+        assert_eq!(response.usernames, vec![alice.username.clone()]);
+
+        assert_eq!(
+            subscriber.try_recv().unwrap(),
+            ThreadEvent {
+                kind: PRESENCE_CHANGED,
+                data: json!({
+                    "thread_id": thread_id,
+                    "viewer_count": 1,
+                    "usernames": [alice.username],
+                }),
+            }
+        );
+        assert!(subscriber.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_存在しないスレッドは404() {
+        // 存在しないスレッドの閲覧者は404になることを確認
+        let repos = FakeRepos::default();
+        let registry = Arc::new(PresenceRegistry::new());
+        let events = Arc::new(ThreadEvents::new());
+        let thread_id = Uuid::new_v4();
+        let mut subscriber = events.subscribe(thread_id);
+
+        let result = heartbeat(
+            &repos,
+            &registry,
+            &events,
+            thread_id,
+            &fake_user(true),
+            false,
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+        assert!(subscriber.try_recv().is_err());
+    }
+}
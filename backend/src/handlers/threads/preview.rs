@@ -0,0 +1,209 @@
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::create::prepare_thread;
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        moderation::UserMutedErrorResponse,
+        threads::{CreateThreadRequest, ThreadPreviewResponse, ThreadResponse, ThreadWithUser},
+        User,
+    },
+    repositories::UsersRepo,
+    utils::tag_rules::TagRuleCache,
+};
+
+/// スレッドのプレビュー
+///
+/// スレッドを作成した場合と同じ検証・自動タグ付け・警告の判定を行い、保存せずに結果を返します。
+#[utoipa::path(
+    post,
+    path = "/api/threads/preview",
+    request_body = CreateThreadRequest,
+    responses(
+        (status = 200, description = "Thread as it would be created. Nothing is saved", body = ThreadPreviewResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "User is muted", body = UserMutedErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn preview_thread(
+    State(users): State<Arc<dyn UsersRepo>>,
+    State(tag_rules): State<Arc<TagRuleCache>>,
+    State(config): State<Arc<Config>>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<CreateThreadRequest>,
+) -> Result<Json<ThreadPreviewResponse>, AppError> {
+    let prepared =
+        prepare_thread(users.as_ref(), &tag_rules, &config, &current_user, &payload).await?;
+
+    // 作成直後のスレッドと同じ内容（IDは未定のため nil、短いIDは空）
+    let now = Utc::now();
+    let mut thread = ThreadResponse::from(ThreadWithUser {
+        id: Uuid::nil(),
+        short_id: String::new(),
+        title: payload.title,
+        content: payload.content,
+        created_at: now,
+        updated_at: now,
+        upvote_count: 0,
+        downvote_count: 0,
+        locked_at: None,
+        user_id: current_user.id,
+        username: current_user.username,
+        user_display_name: current_user.display_name,
+        user_avatar_url: current_user.avatar_url,
+        comment_count: Some(0),
+        revision_count: 0,
+    });
+    thread.warnings = prepared.warnings;
+
+    Ok(Json(ThreadPreviewResponse {
+        preview: true,
+        thread,
+        tags: prepared.tags,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::threads::create::create_thread,
+        repositories::ThreadsRepo,
+        test_utils::{
+            self,
+            fakes::{fake_user, FakeRepos},
+            test_config, test_state,
+        },
+        utils::soft_limits,
+    };
+    use sqlx::PgPool;
+
+    fn request() -> CreateThreadRequest {
+        CreateThreadRequest {
+            title: format!("Rust {}", "a".repeat(295)),
+            content: Some("Goroutine and async".to_string()),
+            client_nonce: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_プレビューは作成した結果と一致し何も保存しない(
+        pool: PgPool,
+    ) {
+        // スレッドのプレビューは作成した結果と一致し、何も保存しないことを確認
+        let user = test_utils::create_test_user(&pool, true).await;
+        let rust = test_utils::create_test_tag(&pool, "Rust").await;
+        let go = test_utils::create_test_tag(&pool, "Go").await;
+        test_utils::create_test_tag_rule(&pool, "rust", "title", rust.id).await;
+        test_utils::create_test_tag_rule(&pool, "goroutine", "content", go.id).await;
+        let state = test_state(&pool);
+
+        let Json(preview) = preview_thread(
+            State(state.users.clone()),
+            State(state.tag_rules.clone()),
+            State(state.config.clone()),
+            Extension(user.clone()),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+
+        let thread_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM threads")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(thread_count, 0);
+
+        let (_, _, Json(created)) = create_thread(
+            State(state.threads),
+            State(state.users),
+            State(state.tag_rules),
+            State(state.notifier),
+            State(state.config),
+            Extension(user),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+
+        assert!(preview.preview);
+        let thread = &preview.thread;
+        assert_eq!(thread.id, Uuid::nil());
+        assert_eq!(thread.title, created.title);
+        assert_eq!(thread.content, created.content);
+        assert_eq!(thread.user.id, created.user.id);
+        assert_eq!(thread.user.username, created.user.username);
+        assert_eq!(thread.comment_count, created.comment_count);
+        assert_eq!(thread.locked, created.locked);
+        assert_eq!(
+            thread.warnings.iter().map(|w| &w.code).collect::<Vec<_>>(),
+            created.warnings.iter().map(|w| &w.code).collect::<Vec<_>>()
+        );
+        assert_eq!(thread.warnings[0].code, soft_limits::TITLE_NEAR_LIMIT);
+
+        // プレビューのタグは作成したスレッドに付いたタグと同じ
+        let mut previewed: Vec<Uuid> = preview.tags.iter().map(|tag| tag.id).collect();
+        let mut attached: Vec<Uuid> =
+            sqlx::query_scalar("SELECT tag_id FROM thread_tags WHERE thread_id = $1")
+                .bind(created.id)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        previewed.sort();
+        attached.sort();
+        assert_eq!(previewed, attached);
+        assert_eq!(previewed.len(), 2);
+        assert!(preview.tags.iter().any(|tag| tag.name == "Rust"));
+    }
+
+    #[tokio::test]
+    async fn test_作成と同じ検証でエラーになる() {
+        // スレッドのプレビューは作成と同じ検証でエラーになることを確認
+        let repos = FakeRepos::default();
+        let preview = |user: User, title: &str| {
+            preview_thread(
+                State(repos.users_repo()),
+                State(repos.tag_rules.clone()),
+                State(Arc::new(test_config())),
+                Extension(user),
+                Json(CreateThreadRequest {
+                    title: title.to_string(),
+                    content: None,
+                    client_nonce: None,
+                }),
+            )
+        };
+
+        assert!(matches!(
+            preview(fake_user(true), "").await,
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            preview(fake_user(false), "Title").await,
+            Err(AppError::EmailVerificationRequired)
+        ));
+
+        let muted = fake_user(true);
+        repos
+            .users
+            .mute(muted.id, Utc::now() + chrono::Duration::hours(1));
+        assert!(matches!(
+            preview(muted, "Title").await,
+            Err(AppError::UserMuted(_))
+        ));
+        assert_eq!(repos.threads.count(None, &[], None).await.unwrap(), 0);
+    }
+}
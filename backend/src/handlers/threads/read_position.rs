@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        threads::{ReadPositionResponse, UpdateReadPositionRequest},
+        User,
+    },
+    repositories::{CommentsRepo, ThreadsRepo},
+};
+
+/// スレッドの既読位置を保存
+///
+/// 指定したコメントまで（省略時は現在時刻まで）を既読として記録します。
+#[utoipa::path(
+    put,
+    path = "/api/threads/{id}/read-position",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    request_body = UpdateReadPositionRequest,
+    responses(
+        (status = 200, description = "Read position saved", body = ReadPositionResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_read_position(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(comments): State<Arc<dyn CommentsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<UpdateReadPositionRequest>,
+) -> Result<Json<ReadPositionResponse>, AppError> {
+    // スレッド存在確認
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    // コメント指定時はそのコメントの投稿日時までを既読とする
+    let last_read_at = match payload.last_read_comment_id {
+        Some(comment_id) => comments
+            .created_at_in_thread(comment_id, id)
+            .await?
+            .ok_or_else(|| {
+                AppError::BadRequest(
+                    "Comment not found or does not belong to this thread".to_string(),
+                )
+            })?,
+        None => Utc::now(),
+    };
+
+    // 既読位置を保存（存在する場合は更新）
+    let position = threads
+        .save_read_position(
+            current_user.id,
+            id,
+            payload.last_read_comment_id,
+            last_read_at,
+        )
+        .await?;
+
+    let unread_comment_count = comments.count_unread(id, Some(&position)).await?;
+
+    Ok(Json(ReadPositionResponse {
+        thread_id: id,
+        last_read_comment_id: position.last_read_comment_id,
+        last_read_at: position.last_read_at,
+        unread_comment_count,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_comment, create_test_user, seed_test_data, test_state};
+    use chrono::DateTime;
+    use sqlx::PgPool;
+
+    // 実データベースを使って既読位置を保存する
+    async fn save_to_db(
+        pool: &PgPool,
+        thread_id: Uuid,
+        user: User,
+        last_read_comment_id: Option<Uuid>,
+    ) -> Result<Json<ReadPositionResponse>, AppError> {
+        let state = test_state(pool);
+        update_read_position(
+            State(state.threads),
+            State(state.comments),
+            Path(thread_id),
+            Extension(user),
+            Json(UpdateReadPositionRequest {
+                last_read_comment_id,
+            }),
+        )
+        .await
+    }
+
+    // コメントの投稿日時を指定した値に変更する
+    async fn set_comment_created_at(pool: &PgPool, comment_id: Uuid, created_at: DateTime<Utc>) {
+        sqlx::query("UPDATE comments SET created_at = $1 WHERE id = $2")
+            .bind(created_at)
+            .bind(comment_id)
+            .execute(pool)
+            .await
+            .expect("Failed to update comment created_at");
+    }
+
+    #[sqlx::test]
+    async fn test_既読位置を保存すると未読数が計算される(pool: PgPool) {
+        // 2件目のコメントまで読んだ場合、3件目以降が未読になることを確認
+        let (author_id, thread_id) = seed_test_data(&pool, "read_position").await;
+        let user = create_test_user(&pool, true).await;
+
+        let base = Utc::now() - chrono::Duration::minutes(10);
+        let mut comment_ids = Vec::new();
+        for i in 0..4 {
+            let comment_id =
+                create_test_comment(&pool, author_id, thread_id, &format!("Comment {}", i), None)
+                    .await;
+            set_comment_created_at(&pool, comment_id, base + chrono::Duration::minutes(i)).await;
+            comment_ids.push(comment_id);
+        }
+
+        let result = save_to_db(&pool, thread_id, user.clone(), Some(comment_ids[1]))
+            .await
+            .unwrap();
+
+        assert_eq!(result.0.last_read_comment_id, Some(comment_ids[1]));
+        assert_eq!(result.0.unread_comment_count, 2);
+
+        // 再度保存すると上書きされる
+        let result = save_to_db(&pool, thread_id, user.clone(), Some(comment_ids[3]))
+            .await
+            .unwrap();
+        assert_eq!(result.0.unread_comment_count, 0);
+
+        let rows: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM thread_read_positions WHERE user_id = $1 AND thread_id = $2",
+        )
+        .bind(user.id)
+        .bind(thread_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_既読位置が無い場合は全コメントが未読になる(pool: PgPool) {
+        // 既読位置が無いユーザーは全件が未読として数えられることを確認
+        let (author_id, thread_id) = seed_test_data(&pool, "read_position_none").await;
+        create_test_comment(&pool, author_id, thread_id, "Comment 1", None).await;
+        create_test_comment(&pool, author_id, thread_id, "Comment 2", None).await;
+
+        let unread = test_state(&pool)
+            .comments
+            .count_unread(thread_id, None)
+            .await
+            .unwrap();
+        assert_eq!(unread, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_別スレッドのコメントは既読位置に指定できない(pool: PgPool) {
+        // 他のスレッドのコメントIDを指定するとBadRequestになることを確認
+        let (author_id, thread_id) = seed_test_data(&pool, "read_position_other").await;
+        let (_, other_thread_id) = seed_test_data(&pool, "read_position_other2").await;
+        let other_comment =
+            create_test_comment(&pool, author_id, other_thread_id, "Other", None).await;
+        let user = create_test_user(&pool, true).await;
+
+        let result = save_to_db(&pool, thread_id, user, Some(other_comment)).await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドの既読位置は保存できない(pool: PgPool) {
+        // 存在しないスレッドIDを指定するとNotFoundになることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = save_to_db(&pool, Uuid::new_v4(), user, None).await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
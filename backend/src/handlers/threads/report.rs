@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        reports::{CreateReportRequest, ReportResponse, ReportTargetType},
+        User,
+    },
+    utils::reports,
+};
+
+/// スレッドを通報する
+///
+/// 同じスレッドを通報できるのは1回のみです。
+#[utoipa::path(
+    post,
+    path = "/api/threads/{id}/report",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    request_body = CreateReportRequest,
+    responses(
+        (status = 201, description = "Thread reported", body = ReportResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse),
+        (status = 409, description = "Already reported", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn report_thread(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<CreateReportRequest>,
+) -> Result<(StatusCode, Json<ReportResponse>), AppError> {
+    payload.validate()?;
+
+    let report = reports::create(
+        &pool,
+        current_user.id,
+        ReportTargetType::Thread,
+        id,
+        &payload,
+    )
+    .await?;
+
+    tracing::info!(
+        report_id = %report.id,
+        thread_id = %id,
+        reporter_id = %current_user.id,
+        "Thread reported"
+    );
+
+    Ok((StatusCode::CREATED, Json(ReportResponse::from(report))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::reports::{ReportReason, ReportStatus},
+        test_utils::{create_test_thread, create_test_user},
+    };
+
+    fn report_request(reason: ReportReason, detail: Option<&str>) -> Json<CreateReportRequest> {
+        Json(CreateReportRequest {
+            reason,
+            detail: detail.map(str::to_string),
+        })
+    }
+
+    #[sqlx::test]
+    async fn test_同じスレッドを二度通報すると409になる(pool: PgPool) {
+        // 同じスレッドを二度通報すると409になることを確認
+        let author = create_test_user(&pool, true).await;
+        let reporter = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Title", "Content").await;
+
+        let (status, Json(report)) = report_thread(
+            State(pool.clone()),
+            Path(thread_id),
+            Extension(reporter.clone()),
+            report_request(ReportReason::Spam, Some("  広告です  ")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(report.target_type, ReportTargetType::Thread);
+        assert_eq!(report.target_id, thread_id);
+        assert_eq!(report.status, ReportStatus::Open);
+        assert_eq!(report.detail.as_deref(), Some("広告です"));
+
+        // 理由を変えても同じユーザーからの通報は1回のみ
+        let duplicate = report_thread(
+            State(pool.clone()),
+            Path(thread_id),
+            Extension(reporter),
+            report_request(ReportReason::Abuse, None),
+        )
+        .await;
+        assert!(matches!(
+            duplicate,
+            Err(AppError::UniqueViolation {
+                code: "REPORT_DUPLICATE",
+                ..
+            })
+        ));
+
+        // 他のユーザーは通報できる
+        let result = report_thread(
+            State(pool.clone()),
+            Path(thread_id),
+            Extension(other),
+            report_request(ReportReason::Spam, None),
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドは通報できない(pool: PgPool) {
+        // 存在しないスレッドは通報できず404になることを確認
+        let reporter = create_test_user(&pool, true).await;
+
+        let result = report_thread(
+            State(pool.clone()),
+            Path(Uuid::new_v4()),
+            Extension(reporter),
+            report_request(ReportReason::Other, None),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
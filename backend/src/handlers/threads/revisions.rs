@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, threads::ThreadRevisionListResponse, User},
+    repositories::ThreadsRepo,
+};
+
+/// スレッドの編集履歴
+///
+/// 更新される前のタイトル・本文を古い順に返します（スレッドごとに新しい方から 20 件まで）。
+/// 投稿者本人とモデレーター・管理者のみ取得できます。
+#[utoipa::path(
+    get,
+    path = "/api/threads/{id}/revisions",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Thread revisions, oldest first", body = ThreadRevisionListResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_thread_revisions(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<ThreadRevisionListResponse>, AppError> {
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+    if !current_user.role.can_moderate() && !threads.is_owned_by(id, current_user.id).await? {
+        return Err(AppError::Forbidden);
+    }
+
+    let revisions = threads.revisions(id).await?;
+
+    Ok(Json(ThreadRevisionListResponse { revisions }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{threads::MAX_REVISIONS, UserRole},
+        repositories::PgThreadsRepo,
+        test_utils::{
+            create_test_thread, create_test_user,
+            fakes::{fake_user, FakeRepos},
+            test_tokens,
+        },
+    };
+    use sqlx::PgPool;
+
+    async fn revisions(
+        threads: Arc<dyn ThreadsRepo>,
+        id: Uuid,
+        user: &User,
+    ) -> Result<Vec<String>, AppError> {
+        let Json(response) =
+            get_thread_revisions(State(threads), Path(id), Extension(user.clone())).await?;
+        Ok(response.revisions.into_iter().map(|r| r.title).collect())
+    }
+
+    #[sqlx::test]
+    async fn test_更新のたびに更新前の版を古い順に残す(pool: PgPool) {
+        // 更新のたびに更新前の版が古い順に残ることを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "First", "Content").await;
+        let threads: Arc<dyn ThreadsRepo> =
+            Arc::new(PgThreadsRepo::new(pool.clone(), test_tokens()));
+
+        threads
+            .update(thread_id, author.id, Some("Second"), None)
+            .await
+            .unwrap();
+        threads
+            .update(thread_id, author.id, Some("Third"), Some("Edited"))
+            .await
+            .unwrap();
+
+        let Json(response) = get_thread_revisions(
+            State(threads.clone()),
+            Path(thread_id),
+            Extension(author.clone()),
+        )
+        .await
+        .unwrap();
+        let versions: Vec<(&str, Option<&str>)> = response
+            .revisions
+            .iter()
+            .map(|r| (r.title.as_str(), r.content.as_deref()))
+            .collect();
+        assert_eq!(
+            versions,
+            [("First", Some("Content")), ("Second", Some("Content"))]
+        );
+        assert!(response
+            .revisions
+            .iter()
+            .all(|r| r.edited_by == Some(author.id)));
+
+        let thread = threads.find(thread_id).await.unwrap().unwrap();
+        assert_eq!(thread.title, "Third");
+        assert_eq!(thread.revision_count, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_上限を超えた編集履歴は古い版から削除する(pool: PgPool) {
+        // 上限を超えた編集履歴は古い版から削除されることを確認
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "0", "Content").await;
+        let threads: Arc<dyn ThreadsRepo> =
+            Arc::new(PgThreadsRepo::new(pool.clone(), test_tokens()));
+
+        for i in 1..=MAX_REVISIONS + 2 {
+            threads
+                .update(thread_id, author.id, Some(&i.to_string()), None)
+                .await
+                .unwrap();
+        }
+
+        let titles = revisions(threads, thread_id, &author).await.unwrap();
+        let expected: Vec<String> = (2..MAX_REVISIONS + 2).map(|i| i.to_string()).collect();
+        assert_eq!(titles, expected);
+    }
+
+    #[tokio::test]
+    async fn test_投稿者とモデレーター以外は取得できない() {
+        // 編集履歴は投稿者とモデレーター以外は取得できないことを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Before");
+        repos
+            .threads_repo()
+            .update(thread_id, author.id, Some("After"), None)
+            .await
+            .unwrap();
+
+        let other = fake_user(true);
+        assert!(matches!(
+            revisions(repos.threads_repo(), thread_id, &other).await,
+            Err(AppError::Forbidden)
+        ));
+
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..other
+        };
+        assert_eq!(
+            revisions(repos.threads_repo(), thread_id, &moderator)
+                .await
+                .unwrap(),
+            ["Before"]
+        );
+        assert!(matches!(
+            revisions(repos.threads_repo(), Uuid::new_v4(), &author).await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
@@ -2,17 +2,20 @@ use axum::{
     extract::{Extension, Path, State},
     Json,
 };
-use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
     models::{
         common::ErrorResponse,
-        threads::{ThreadResponse, ThreadWithUser, UpdateThreadRequest},
+        threads::{ThreadResponse, UpdateThreadRequest},
         User,
     },
+    repositories::ThreadsRepo,
+    utils::{ogp_cache::OgpImageCache, soft_limits},
 };
 
 #[utoipa::path(
@@ -23,7 +26,7 @@ use crate::{
     ),
     request_body = UpdateThreadRequest,
     responses(
-        (status = 200, description = "Thread updated successfully", body = ThreadResponse),
+        (status = 200, description = "Thread updated successfully. The previous title and content are kept as a revision. `warnings` lists updated fields close to their limits", body = ThreadResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 403, description = "Forbidden", body = ErrorResponse),
@@ -35,7 +38,9 @@ use crate::{
     )
 )]
 pub async fn update_thread(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    State(config): State<Arc<Config>>,
+    State(ogp_images): State<Arc<OgpImageCache>>,
     Path(id): Path<Uuid>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<UpdateThreadRequest>,
@@ -44,15 +49,7 @@ pub async fn update_thread(
     payload.validate()?;
 
     // Check if thread exists and user owns it
-    let existing_thread = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1 AND user_id = $2)",
-    )
-    .bind(id)
-    .bind(current_user.id)
-    .fetch_one(&pool)
-    .await?;
-
-    if !existing_thread {
+    if !threads.is_owned_by(id, current_user.id).await? {
         return Err(AppError::NotFound);
     }
 
@@ -60,41 +57,177 @@ pub async fn update_thread(
         return Err(AppError::BadRequest("No fields to update".to_string()));
     }
 
-    // Update thread with simplified query
-    let _updated_thread = sqlx::query(
-        r#"
-        UPDATE threads 
-        SET 
-            title = COALESCE($2, title),
-            content = COALESCE($3, content),
-            updated_at = NOW()
-        WHERE id = $1
-        "#,
-    )
-    .bind(id)
-    .bind(payload.title.as_ref())
-    .bind(payload.content.as_ref())
-    .execute(&pool)
-    .await?;
+    threads
+        .update(
+            id,
+            current_user.id,
+            payload.title.as_deref(),
+            payload.content.as_deref(),
+        )
+        .await?;
+
+    // OGP画像にはタイトルを描くため、生成済みの画像を捨てる
+    if payload.title.is_some() {
+        ogp_images.invalidate(id);
+    }
 
     // Fetch user information and comment count
-    let thread_with_user = sqlx::query_as::<_, ThreadWithUser>(
-        r#"
-        SELECT 
-            t.id, t.title, t.content, t.created_at, t.updated_at,
-            t.upvote_count, t.downvote_count,
-            u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
-            COUNT(c.id)::bigint as comment_count
-        FROM threads t
-        JOIN users u ON t.user_id = u.id
-        LEFT JOIN comments c ON t.id = c.thread_id
-        WHERE t.id = $1
-        GROUP BY t.id, t.upvote_count, t.downvote_count, u.id, u.username, u.display_name, u.avatar_url
-        "#
-    )
-    .bind(id)
-    .fetch_one(&pool)
-    .await?;
+    let thread_with_user = threads.find(id).await?.ok_or(AppError::NotFound)?;
+
+    let mut response = ThreadResponse::from(thread_with_user);
+    response.warnings = soft_limits::thread_warnings(
+        payload.title.as_deref(),
+        payload.content.as_deref(),
+        config.soft_limit_warning_percent,
+    );
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        fakes::{fake_user, FakeRepos},
+        test_config,
+    };
+
+    fn title_update(title: &str) -> UpdateThreadRequest {
+        UpdateThreadRequest {
+            title: Some(title.to_string()),
+            content: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_投稿者はスレッドを更新できる() {
+        // 指定した項目のみ更新され、更新後のスレッドが返されることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Before");
+
+        let Json(response) = update_thread(
+            State(repos.threads_repo()),
+            State(Arc::new(test_config())),
+            State(Arc::new(OgpImageCache::default())),
+            Path(thread_id),
+            Extension(author),
+            Json(title_update("After")),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.id, thread_id);
+        assert_eq!(response.title, "After");
+        assert!(response.warnings.is_empty());
+    }
 
-    Ok(Json(ThreadResponse::from(thread_with_user)))
+    #[tokio::test]
+    async fn test_更新した項目が上限に近ければ警告を付ける() {
+        // 本文だけを更新した場合はタイトルを確認しない
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, &"a".repeat(300));
+
+        let Json(response) = update_thread(
+            State(repos.threads_repo()),
+            State(Arc::new(test_config())),
+            State(Arc::new(OgpImageCache::default())),
+            Path(thread_id),
+            Extension(author),
+            Json(UpdateThreadRequest {
+                title: None,
+                content: Some("a".repeat(38_000)),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let codes: Vec<&str> = response.warnings.iter().map(|w| w.code.as_str()).collect();
+        assert_eq!(codes, [soft_limits::CONTENT_NEAR_LIMIT]);
+    }
+
+    #[tokio::test]
+    async fn test_他人のスレッドは更新できない() {
+        // 投稿者以外はスレッドの存在を明かさずNotFoundとすることを確認
+        let repos = FakeRepos::default();
+        let thread_id = repos.threads.insert(&fake_user(true), "Original");
+
+        let result = update_thread(
+            State(repos.threads_repo()),
+            State(Arc::new(test_config())),
+            State(Arc::new(OgpImageCache::default())),
+            Path(thread_id),
+            Extension(fake_user(true)),
+            Json(title_update("Hijacked")),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+        let thread = repos.threads.find(thread_id).await.unwrap().unwrap();
+        assert_eq!(thread.title, "Original");
+    }
+
+    #[tokio::test]
+    async fn test_更新項目が無い場合はエラーになる() {
+        // タイトルも本文も指定されていない場合はBadRequestになることを確認
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Title");
+
+        let result = update_thread(
+            State(repos.threads_repo()),
+            State(Arc::new(test_config())),
+            State(Arc::new(OgpImageCache::default())),
+            Path(thread_id),
+            Extension(author),
+            Json(UpdateThreadRequest {
+                title: None,
+                content: None,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_更新でも文字数の上限と空白だけの入力を検証する() {
+        // 更新でも作成と同じく文字数の上限と空白だけの入力を検証することを確認
+        // 作成時と同じ上限で、タイトルは前後の空白を除き、本文の改行はLFに揃えて保存する
+        let repos = FakeRepos::default();
+        let author = fake_user(true);
+        let thread_id = repos.threads.insert(&author, "Title");
+        let update = |body: serde_json::Value| {
+            update_thread(
+                State(repos.threads_repo()),
+                State(Arc::new(test_config())),
+                State(Arc::new(OgpImageCache::default())),
+                Path(thread_id),
+                Extension(author.clone()),
+                Json(serde_json::from_value::<UpdateThreadRequest>(body).unwrap()),
+            )
+        };
+
+        let title = "a".repeat(300);
+        let Json(response) = update(serde_json::json!({
+            "title": format!("\n{} ", title),
+            "content": format!("{}\r\n", "a".repeat(39_999)),
+        }))
+        .await
+        .unwrap();
+        assert_eq!(response.title, title);
+        assert!(response.content.unwrap().ends_with("a\n"));
+
+        for body in [
+            serde_json::json!({ "title": "a".repeat(301) }),
+            serde_json::json!({ "title": "   " }),
+            serde_json::json!({ "content": "a".repeat(40_001) }),
+            serde_json::json!({ "content": " \r\n\t" }),
+        ] {
+            assert!(matches!(update(body).await, Err(AppError::Validation(_))));
+        }
+        let thread = repos.threads.find(thread_id).await.unwrap().unwrap();
+        assert_eq!(thread.title, title);
+    }
 }
@@ -1,50 +1,69 @@
 use axum::{
     extract::{Extension, Path, State},
-    http::StatusCode,
     Json,
 };
-use serde::Deserialize;
-use sqlx::PgPool;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
     error::AppError,
+    events::{self, VoteAction},
     models::{common::ErrorResponse, User},
+    repositories::{threads::VoteChange, ThreadsRepo},
 };
 
 #[derive(Deserialize, ToSchema)]
 pub struct VoteRequest {
-    pub vote_type: String, // "upvote" or "downvote"
+    /// `upvote` または `downvote`（同じ投票をもう一度送っても変わらない）
+    #[schema(example = "upvote")]
+    pub vote_type: String,
 }
 
+/// 投票後の状態（クライアントはこの値に合わせて表示を更新する）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VoteResponse {
+    /// 現在の投票（取り消した場合は null）
+    pub vote_type: Option<String>,
+    pub upvote_count: i32,
+    pub downvote_count: i32,
+}
+
+/// スレッドに投票する
+///
+/// 投票は1つの SQL で行うため、同じ投票が同時に送られても（連打など）結果は1件の投票になります。
+/// 取り消す場合は `DELETE /api/threads/{id}/vote` を使います。
 #[utoipa::path(
     post,
     path = "/api/threads/{id}/vote",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
     request_body = VoteRequest,
     responses(
-        (status = 200, description = "Voted successfully", body = ErrorResponse),
+        (status = 200, description = "Vote added, changed or already in place", body = VoteResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Email verification required", body = ErrorResponse),
         (status = 404, description = "Thread not found", body = ErrorResponse)
     ),
     tag = "threads",
     security(("bearer_auth" = []))
 )]
 pub async fn vote_thread(
-    State(pool): State<PgPool>,
+    State(threads): State<Arc<dyn ThreadsRepo>>,
     Path(id): Path<Uuid>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<VoteRequest>,
-) -> Result<StatusCode, AppError> {
-    // スレッド存在確認
-    let exists =
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
-            .bind(id)
-            .fetch_optional(&pool)
-            .await?;
+) -> Result<Json<VoteResponse>, AppError> {
+    // メール認証が完了しているか確認
+    if !current_user.email_verified {
+        return Err(AppError::EmailVerificationRequired);
+    }
 
-    if !exists.unwrap_or(false) {
+    // スレッド存在確認
+    if !threads.exists(id).await? {
         return Err(AppError::NotFound);
     }
     // upvote/downvote以外はエラー
@@ -52,52 +71,88 @@ pub async fn vote_thread(
         return Err(AppError::BadRequest("Invalid vote_type".to_string()));
     }
 
-    // 既存投票取得
-    let existing = sqlx::query_scalar::<_, String>(
-        "SELECT vote_type FROM votes WHERE user_id = $1 AND thread_id = $2",
-    )
-    .bind(current_user.id)
-    .bind(id)
-    .fetch_optional(&pool)
-    .await?;
-
-    if let Some(current) = existing {
-        if current == payload.vote_type {
-            // 同じ投票なら削除（トグル）
-            sqlx::query("DELETE FROM votes WHERE user_id = $1 AND thread_id = $2")
-                .bind(current_user.id)
-                .bind(id)
-                .execute(&pool)
-                .await?;
-            return Ok(StatusCode::NO_CONTENT);
-        } else {
-            // 種類が違う場合は更新
-            sqlx::query(
-                "UPDATE votes SET vote_type = $1, updated_at = NOW() WHERE user_id = $2 AND thread_id = $3"
-            )
-            .bind(&payload.vote_type)
-            .bind(current_user.id)
-            .bind(id)
-            .execute(&pool)
-            .await?;
-            return Ok(StatusCode::OK);
-        }
-    } else {
-        // 新規投票
-        sqlx::query("INSERT INTO votes (user_id, thread_id, vote_type) VALUES ($1, $2, $3)")
-            .bind(current_user.id)
-            .bind(id)
-            .bind(&payload.vote_type)
-            .execute(&pool)
-            .await?;
-        return Ok(StatusCode::OK);
+    let action = match threads
+        .set_vote(current_user.id, id, &payload.vote_type)
+        .await?
+    {
+        VoteChange::Added => Some(VoteAction::Added),
+        VoteChange::Changed => Some(VoteAction::Changed),
+        VoteChange::Unchanged => None,
+    };
+    if let Some(action) = action {
+        events::emit(&events::ThreadVoted {
+            thread_id: id,
+            user_id: current_user.id,
+            vote_type: payload.vote_type.clone(),
+            action,
+        });
     }
+
+    vote_response(threads.as_ref(), id, Some(payload.vote_type)).await
+}
+
+/// スレッドへの投票を取り消す
+///
+/// 投票していない場合も同じ結果を返します。
+#[utoipa::path(
+    delete,
+    path = "/api/threads/{id}/vote",
+    params(
+        ("id" = Uuid, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 200, description = "Vote removed (or there was no vote)", body = VoteResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Thread not found", body = ErrorResponse)
+    ),
+    tag = "threads",
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_vote(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<VoteResponse>, AppError> {
+    if !threads.exists(id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    if let Some(vote_type) = threads.delete_vote(current_user.id, id).await? {
+        events::emit(&events::ThreadVoted {
+            thread_id: id,
+            user_id: current_user.id,
+            vote_type,
+            action: VoteAction::Removed,
+        });
+    }
+
+    vote_response(threads.as_ref(), id, None).await
+}
+
+async fn vote_response(
+    threads: &dyn ThreadsRepo,
+    id: Uuid,
+    vote_type: Option<String>,
+) -> Result<Json<VoteResponse>, AppError> {
+    let thread = threads
+        .find_without_comment_count(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(VoteResponse {
+        vote_type,
+        upvote_count: thread.upvote_count,
+        downvote_count: thread.downvote_count,
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::User;
+    use crate::events::capture::CapturedEvents;
+    use crate::models::{User, UserRole};
+    use crate::test_utils::fakes::{fake_user, FakeRepos};
+    use crate::test_utils::test_state;
     use axum::extract::State;
     use axum::Extension;
     use axum::Json;
@@ -108,15 +163,24 @@ mod tests {
     async fn setup_user_and_thread(pool: &PgPool) -> (User, Uuid) {
         let user_id = Uuid::new_v4();
         let thread_id = Uuid::new_v4();
-        sqlx::query!(
-            "INSERT INTO users (id, username, email, email_verified) VALUES ($1, 'voteuser', 'vote@example.com', true)",
-            user_id
-        ).execute(pool).await.unwrap();
-        sqlx::query!(
-            "INSERT INTO threads (id, user_id, title, upvote_count, downvote_count) VALUES ($1, $2, 'vote thread', 0, 0)",
-            thread_id,
-            user_id
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, username, email, email_verified)
+            VALUES ($1, 'voteuser', 'vote@example.com', true)
+            "#,
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO threads (id, user_id, title, upvote_count, downvote_count)
+            VALUES ($1, $2, 'vote thread', 0, 0)
+            "#,
         )
+        .bind(thread_id)
+        .bind(user_id)
         .execute(pool)
         .await
         .unwrap();
@@ -126,6 +190,8 @@ mod tests {
             email: "vote@example.com".to_string(),
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
             email_verified: true,
             email_verified_at: Some(chrono::Utc::now()),
             verification_token: None,
@@ -134,6 +200,10 @@ mod tests {
             password_reset_token_expires_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            role: UserRole::User,
+            banned_at: None,
+            banned_until: None,
+            ban_reason: None,
         };
         (user, thread_id)
     }
@@ -146,7 +216,7 @@ mod tests {
             vote_type: "upvote".to_string(),
         };
         let result = vote_thread(
-            State(pool.clone()),
+            State(test_state(&pool).threads),
             Path(thread_id),
             Extension(user.clone()),
             Json(req),
@@ -160,33 +230,38 @@ mod tests {
     }
 
     #[sqlx::test]
-    async fn test_同じ投票タイプでトグル削除できること(pool: PgPool) {
-        // upvote→upvoteで削除
+    async fn test_同じ投票を繰り返しても投票は変わらず取り消しで削除できること(
+        pool: PgPool,
+    ) {
+        // upvote→upvoteでも投票は1件のままで、DELETEで削除できることを確認
         let (user, thread_id) = setup_user_and_thread(&pool).await;
-        // 1回目upvote
-        let req = VoteRequest {
-            vote_type: "upvote".to_string(),
-        };
-        vote_thread(
-            State(pool.clone()),
-            Path(thread_id),
-            Extension(user.clone()),
-            Json(req),
-        )
-        .await
-        .unwrap();
-        // 2回目upvote（削除）
-        let req2 = VoteRequest {
-            vote_type: "upvote".to_string(),
-        };
-        let res = vote_thread(
-            State(pool.clone()),
-            Path(thread_id),
-            Extension(user.clone()),
-            Json(req2),
-        )
-        .await;
-        assert!(res.is_ok());
+        for _ in 0..2 {
+            let Json(response) = vote_thread(
+                State(test_state(&pool).threads),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(VoteRequest {
+                    vote_type: "upvote".to_string(),
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.vote_type.as_deref(), Some("upvote"));
+            assert_eq!((response.upvote_count, response.downvote_count), (1, 0));
+        }
+
+        // 取り消しは何度行っても同じ結果になる
+        for _ in 0..2 {
+            let Json(response) = remove_vote(
+                State(test_state(&pool).threads),
+                Path(thread_id),
+                Extension(user.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.vote_type, None);
+            assert_eq!((response.upvote_count, response.downvote_count), (0, 0));
+        }
         // DBに投票がないこと
         let count: i64 =
             sqlx::query_scalar("SELECT COUNT(*) FROM votes WHERE user_id = $1 AND thread_id = $2")
@@ -205,8 +280,8 @@ mod tests {
         let req = VoteRequest {
             vote_type: "upvote".to_string(),
         };
-        vote_thread(
-            State(pool.clone()),
+        let _ = vote_thread(
+            State(test_state(&pool).threads),
             Path(thread_id),
             Extension(user.clone()),
             Json(req),
@@ -217,7 +292,7 @@ mod tests {
             vote_type: "downvote".to_string(),
         };
         let res = vote_thread(
-            State(pool.clone()),
+            State(test_state(&pool).threads),
             Path(thread_id),
             Extension(user.clone()),
             Json(req2),
@@ -242,7 +317,7 @@ mod tests {
             vote_type: "invalid".to_string(),
         };
         let res = vote_thread(
-            State(pool.clone()),
+            State(test_state(&pool).threads),
             Path(thread_id),
             Extension(user),
             Json(req),
@@ -255,16 +330,24 @@ mod tests {
     async fn test_存在しないスレッドはエラーになること(pool: PgPool) {
         // 存在しないスレッドID
         let user_id = Uuid::new_v4();
-        sqlx::query!(
-            "INSERT INTO users (id, username, email, email_verified) VALUES ($1, 'nouser', 'nouser@example.com', true)",
-            user_id
-        ).execute(&pool).await.unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, username, email, email_verified)
+            VALUES ($1, 'nouser', 'nouser@example.com', true)
+            "#,
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
         let user = User {
             id: user_id,
             username: "nouser".to_string(),
             email: "nouser@example.com".to_string(),
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
             email_verified: true,
             email_verified_at: Some(chrono::Utc::now()),
             verification_token: None,
@@ -273,12 +356,16 @@ mod tests {
             password_reset_token_expires_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            role: UserRole::User,
+            banned_at: None,
+            banned_until: None,
+            ban_reason: None,
         };
         let req = VoteRequest {
             vote_type: "upvote".to_string(),
         };
         let res = vote_thread(
-            State(pool.clone()),
+            State(test_state(&pool).threads),
             Path(Uuid::new_v4()),
             Extension(user),
             Json(req),
@@ -286,4 +373,131 @@ mod tests {
         .await;
         assert!(res.is_err());
     }
+
+    #[sqlx::test]
+    async fn test_同じ投票を同時に送っても投票は1件になること(pool: PgPool) {
+        // 連打で同じ投票が同時に届いても、どちらも成功して投票は1件のみになることを確認
+        let (user, thread_id) = setup_user_and_thread(&pool).await;
+        let vote = |user: User| {
+            vote_thread(
+                State(test_state(&pool).threads),
+                Path(thread_id),
+                Extension(user),
+                Json(VoteRequest {
+                    vote_type: "upvote".to_string(),
+                }),
+            )
+        };
+
+        let (first, second) = tokio::join!(vote(user.clone()), vote(user));
+
+        for result in [first, second] {
+            assert_eq!(result.unwrap().vote_type.as_deref(), Some("upvote"));
+        }
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM votes WHERE thread_id = $1")
+            .bind(thread_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        let upvotes: i32 = sqlx::query_scalar("SELECT upvote_count FROM threads WHERE id = $1")
+            .bind(thread_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(upvotes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_インメモリ実装で投票の切り替えができること() {
+        // 新規投票→種類の変更→同じ種類（変わらない）→取り消しの順に状態が変わることを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "vote thread");
+        let vote = |vote_type: &str| {
+            vote_thread(
+                State(repos.threads_repo()),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(VoteRequest {
+                    vote_type: vote_type.to_string(),
+                }),
+            )
+        };
+
+        let counts = |response: VoteResponse| (response.upvote_count, response.downvote_count);
+
+        assert_eq!(counts(vote("upvote").await.unwrap().0), (1, 0));
+        assert_eq!(
+            repos.threads.vote_of(user.id, thread_id).as_deref(),
+            Some("upvote")
+        );
+
+        assert_eq!(counts(vote("downvote").await.unwrap().0), (0, 1));
+        assert_eq!(counts(vote("downvote").await.unwrap().0), (0, 1));
+        assert_eq!(
+            repos.threads.vote_of(user.id, thread_id).as_deref(),
+            Some("downvote")
+        );
+
+        let Json(removed) = remove_vote(
+            State(repos.threads_repo()),
+            Path(thread_id),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(removed.vote_type, None);
+        assert_eq!(counts(removed), (0, 0));
+        assert_eq!(repos.threads.vote_of(user.id, thread_id), None);
+
+        assert!(matches!(
+            vote("sideways").await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_投票時に操作の種類をイベントとして出力する() {
+        // 追加・変更・取り消しがthread_votedイベントのactionとして区別されることを確認
+        let (captured, _guard) = CapturedEvents::start();
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let thread_id = repos.threads.insert(&fake_user(true), "vote thread");
+
+        // 変わらなかった投票はイベントにしない
+        for vote_type in ["upvote", "downvote", "downvote"] {
+            let _ = vote_thread(
+                State(repos.threads_repo()),
+                Path(thread_id),
+                Extension(user.clone()),
+                Json(VoteRequest {
+                    vote_type: vote_type.to_string(),
+                }),
+            )
+            .await
+            .unwrap();
+        }
+        for _ in 0..2 {
+            let _ = remove_vote(
+                State(repos.threads_repo()),
+                Path(thread_id),
+                Extension(user.clone()),
+            )
+            .await
+            .unwrap();
+        }
+
+        let events = captured.events();
+        let actions: Vec<&str> = events
+            .iter()
+            .map(|e| e["payload"]["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(actions, ["added", "changed", "removed"]);
+        assert!(events
+            .iter()
+            .all(|e| e["kind"] == "thread_voted"
+                && e["payload"]["thread_id"] == thread_id.to_string()));
+        assert!(!captured.raw().contains(&user.email));
+    }
 }
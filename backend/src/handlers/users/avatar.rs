@@ -0,0 +1,367 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+};
+use image::{ImageBuffer, Rgb, RgbImage};
+use imageproc::drawing::draw_text_mut;
+use rusttype::Scale;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{users::AvatarQuery, User},
+    utils::{
+        avatar_proxy::{AvatarProxy, DEFAULT_THUMBNAIL_SIZE, THUMBNAIL_SIZES},
+        drawing::{self, TextMeasurer},
+    },
+};
+
+/// アバター画像のサイズ（正方形）
+const AVATAR_SIZE: u32 = 256;
+/// イニシャル用フォントサイズ（AVATAR_SIZE の画像に対する大きさ）
+const INITIAL_FONT_SIZE: f32 = 144.0;
+
+/// ユーザーのデフォルトアバター画像を取得
+///
+/// アバター画像を設定していないユーザー向けに、ユーザーIDから決まる背景色と
+/// ユーザー名の頭文字を描画したPNG画像を返します。
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/avatar.png",
+    params(
+        ("username" = String, Path, description = "ユーザー名")
+    ),
+    responses(
+        (status = 200, description = "アバター画像", content_type = "image/png"),
+        (status = 404, description = "ユーザーが見つかりません")
+    ),
+    tag = "users"
+)]
+pub async fn get_user_avatar(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+) -> Result<Response> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(&username)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let image_data = generate_initial_avatar(user.id, &user.username, AVATAR_SIZE)?;
+
+    // 同じユーザーには常に同じ画像を返すため長めにキャッシュする（7日間）
+    png_response(image_data, "public, max-age=604800")
+}
+
+/// ユーザーのアバター画像を縮小して取得
+///
+/// `avatar_url` の画像をサーバーで取得し、指定したサイズの正方形のPNGに縮小して返します。
+/// 縮小した画像はURLとサイズごとにメモリにキャッシュします。
+/// アバターが未設定の場合や画像を取得できない場合は、同じサイズの自動生成アバターを返します。
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/avatar",
+    params(
+        ("username" = String, Path, description = "ユーザー名"),
+        AvatarQuery
+    ),
+    responses(
+        (status = 200, description = "アバター画像", content_type = "image/png"),
+        (status = 400, description = "対応していないサイズ"),
+        (status = 404, description = "ユーザーが見つかりません")
+    ),
+    tag = "users"
+)]
+pub async fn get_avatar_thumbnail(
+    State(pool): State<PgPool>,
+    State(avatars): State<Arc<AvatarProxy>>,
+    Path(username): Path<String>,
+    Query(query): Query<AvatarQuery>,
+) -> Result<Response> {
+    let size = query.size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+    if !THUMBNAIL_SIZES.contains(&size) {
+        return Err(AppError::BadRequest(format!(
+            "size must be one of {:?}",
+            THUMBNAIL_SIZES
+        )));
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(&username)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if let Some(url) = &user.avatar_url {
+        if let Some(image) = avatars.thumbnail(url, size).await {
+            // アバターを変更するとURLも変わるため長めにキャッシュする（1日）
+            return png_response(image.to_vec(), "public, max-age=86400");
+        }
+    }
+
+    // 取得できなかった画像は後で取得し直せるよう、代わりの画像は短めにキャッシュする（1時間）
+    let image_data = generate_initial_avatar(user.id, &user.username, size)?;
+    let cache_control = if user.avatar_url.is_some() {
+        "public, max-age=3600"
+    } else {
+        "public, max-age=604800"
+    };
+    png_response(image_data, cache_control)
+}
+
+/// PNG画像のレスポンスを作成する関数
+fn png_response(image_data: Vec<u8>, cache_control: &'static str) -> Result<Response> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(image_data.into())
+        .map_err(|_| AppError::Internal("Response build error".to_string()))
+}
+
+/// ユーザーIDとユーザー名から指定サイズのイニシャルアバター画像を生成する関数
+fn generate_initial_avatar(user_id: Uuid, username: &str, size: u32) -> Result<Vec<u8>> {
+    let background_color = avatar_color(user_id);
+    let text_color = Rgb([255, 255, 255]); // 白文字
+
+    let mut image: RgbImage = ImageBuffer::from_pixel(size, size, background_color);
+
+    let font = drawing::font()?;
+    let initial = initial_of(username);
+
+    // 文字の大きさは画像のサイズに比例させる
+    let font_size = INITIAL_FONT_SIZE * size as f32 / AVATAR_SIZE as f32;
+    let scale = Scale::uniform(font_size);
+
+    // イニシャルを画像の中央に配置
+    let text_width = TextMeasurer::new(font, scale).width(&initial);
+    let v_metrics = font.v_metrics(scale);
+    let text_height = v_metrics.ascent - v_metrics.descent;
+    let x = (size as f32 - text_width) / 2.0;
+    let y = (size as f32 - text_height) / 2.0;
+
+    draw_text_mut(
+        &mut image, text_color, x as i32, y as i32, scale, font, &initial,
+    );
+
+    drawing::encode_png(&image)
+}
+
+/// ユーザー名の先頭文字を大文字にして返す関数
+fn initial_of(username: &str) -> String {
+    username
+        .chars()
+        .next()
+        .map(|c| c.to_uppercase().collect())
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// ユーザーIDのハッシュから背景色を決定する関数
+///
+/// 色相のみをハッシュから決め、彩度と明度は白文字が読みやすい値に固定します。
+fn avatar_color(user_id: Uuid) -> Rgb<u8> {
+    let hash = Sha256::digest(user_id.as_bytes());
+    let hue = u16::from_be_bytes([hash[0], hash[1]]) % 360;
+    hsl_to_rgb(hue as f32, 0.55, 0.45)
+}
+
+/// HSL色空間からRGBに変換する関数
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let to_u8 = |v: f32| ((v + m) * 255.0).round() as u8;
+
+    Rgb([to_u8(r), to_u8(g), to_u8(b)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::seed_test_user,
+        utils::avatar_proxy::{AvatarFetcher, RemoteImage},
+    };
+    use async_trait::async_trait;
+    use axum::body::Bytes;
+
+    const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+    /// 固定の画像（取得できない場合は None）を返す
+    struct StubFetcher(Option<Vec<u8>>);
+
+    #[async_trait]
+    impl AvatarFetcher for StubFetcher {
+        async fn fetch(&self, _url: &str) -> Result<RemoteImage> {
+            let body = self
+                .0
+                .clone()
+                .ok_or_else(|| AppError::Internal("fetch failed".to_string()))?;
+            Ok(RemoteImage {
+                content_type: Some("image/png".to_string()),
+                body: Bytes::from(body),
+            })
+        }
+    }
+
+    async fn thumbnail(
+        pool: &PgPool,
+        image: Option<Vec<u8>>,
+        size: Option<u32>,
+    ) -> Result<Response> {
+        get_avatar_thumbnail(
+            State(pool.clone()),
+            State(Arc::new(AvatarProxy::new(Arc::new(StubFetcher(image))))),
+            Path("testuser_avatar".to_string()),
+            Query(AvatarQuery { size }),
+        )
+        .await
+    }
+
+    async fn decode(response: Response) -> image::DynamicImage {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        image::load_from_memory(&body).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_設定したアバターを指定サイズに縮小して返す(pool: PgPool) {
+        // 設定したアバターが指定したサイズに縮小して返されることを確認
+        let user_id = seed_test_user(&pool, "avatar").await;
+        sqlx::query("UPDATE users SET avatar_url = 'https://example.com/a.png' WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let original = generate_initial_avatar(Uuid::new_v4(), "x", AVATAR_SIZE).unwrap();
+
+        let response = thumbnail(&pool, Some(original.clone()), Some(32))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=86400"
+        );
+        assert_eq!(decode(response).await.width(), 32);
+
+        // 省略時は64
+        let response = thumbnail(&pool, Some(original), None).await.unwrap();
+        assert_eq!(decode(response).await.width(), DEFAULT_THUMBNAIL_SIZE);
+
+        // 取得できない場合は同じサイズの自動生成アバターを短めにキャッシュする
+        let response = thumbnail(&pool, None, Some(128)).await.unwrap();
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=3600"
+        );
+        assert_eq!(decode(response).await.width(), 128);
+    }
+
+    #[sqlx::test]
+    async fn test_アバター未設定や対応していないサイズ(pool: PgPool) {
+        // アバター未設定の場合は自動生成の画像を返し、対応していないサイズは400になることを確認
+        seed_test_user(&pool, "avatar").await;
+
+        let response = thumbnail(&pool, None, Some(256)).await.unwrap();
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=604800"
+        );
+        assert_eq!(decode(response).await.width(), 256);
+
+        assert!(matches!(
+            thumbnail(&pool, None, Some(100)).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_ユーザー名でアバター画像が取得できる(pool: PgPool) {
+        // 存在するユーザーのアバター画像がPNGで返され、長期キャッシュが設定されることを確認
+        seed_test_user(&pool, "avatar").await;
+
+        let response = get_user_avatar(State(pool), Path("testuser_avatar".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=604800"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[0..8], &PNG_SIGNATURE);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーは404になる(pool: PgPool) {
+        // 存在しないユーザーのアバターは404になることを確認
+        let result = get_user_avatar(State(pool), Path("no_such_user".to_string())).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[test]
+    fn test_同じユーザーからは同一の画像が生成される() {
+        // 同じユーザーIDとユーザー名からは常に同じバイト列が生成されることを確認
+        let user_id = Uuid::new_v4();
+
+        let first = generate_initial_avatar(user_id, "alice", AVATAR_SIZE).unwrap();
+        let second = generate_initial_avatar(user_id, "alice", AVATAR_SIZE).unwrap();
+
+        assert_eq!(&first[0..8], &PNG_SIGNATURE);
+        assert_eq!(first, second);
+
+        // PNGとして読み込めることを確認
+        let decoded = image::load_from_memory(&first).unwrap();
+        assert_eq!(decoded.width(), AVATAR_SIZE);
+        assert_eq!(decoded.height(), AVATAR_SIZE);
+    }
+
+    #[test]
+    fn test_背景色はユーザーidから決定される() {
+        // 同じIDは同じ色、異なるIDは概ね異なる色になることを確認
+        let user_id = Uuid::new_v4();
+        assert_eq!(avatar_color(user_id), avatar_color(user_id));
+
+        let colors: std::collections::HashSet<_> =
+            (0..20).map(|_| avatar_color(Uuid::new_v4()).0).collect();
+        assert!(colors.len() > 1);
+    }
+
+    #[test]
+    fn test_イニシャルが正しく取得される() {
+        // ユーザー名の先頭の文字が大文字のイニシャルになることを確認
+        assert_eq!(initial_of("alice"), "A");
+        assert_eq!(initial_of("たろう"), "た");
+        assert_eq!(initial_of(""), "?");
+    }
+
+    #[test]
+    fn test_hslからrgbへ変換される() {
+        // HSLの色がRGBに正しく変換されることを確認
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), Rgb([255, 0, 0]));
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), Rgb([0, 255, 0]));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), Rgb([0, 0, 255]));
+    }
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{common::ErrorResponse, users::BlockListResponse, User},
+    utils::{follows, user_blocks},
+};
+
+/// ユーザーをブロック
+///
+/// ブロックしたユーザーのスレッドは一覧に表示されず、コメントは `[blocked user]` として表示されます。
+/// 既にブロックしている場合も 200 を返します。自分自身はブロックできません。
+#[utoipa::path(
+    post,
+    path = "/api/users/{username}/block",
+    params(
+        ("username" = String, Path, description = "Username to block")
+    ),
+    responses(
+        (status = 200, description = "User blocked (or already blocked)"),
+        (status = 400, description = "Cannot block yourself", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn block_user(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    let blocked = follows::find_user(&pool, &username).await?;
+
+    user_blocks::block(&pool, current_user.id, blocked.id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// ユーザーのブロックを解除
+///
+/// ブロックしていない場合も 204 を返します。
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}/block",
+    params(
+        ("username" = String, Path, description = "Username to unblock")
+    ),
+    responses(
+        (status = 204, description = "Unblocked (or was not blocked)"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn unblock_user(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    let blocked = follows::find_user(&pool, &username).await?;
+
+    user_blocks::unblock(&pool, current_user.id, blocked.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// ブロックしているユーザーの一覧
+///
+/// 新しくブロックした順に返します。
+#[utoipa::path(
+    get,
+    path = "/api/users/me/blocks",
+    responses(
+        (status = 200, description = "Blocked users, most recently blocked first", body = BlockListResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn list_blocks(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<BlockListResponse>, AppError> {
+    let blocks = user_blocks::list(&pool, current_user.id)
+        .await?
+        .into_iter()
+        .map(|user| user.with_default_avatar(&config.api_url))
+        .collect();
+
+    Ok(Json(BlockListResponse { blocks }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_user, test_config};
+
+    async fn block(pool: &PgPool, blocker: &User, blocked: &User) -> Result<StatusCode, AppError> {
+        block_user(
+            State(pool.clone()),
+            Path(blocked.username.clone()),
+            Extension(blocker.clone()),
+        )
+        .await
+    }
+
+    async fn blocked_usernames(pool: &PgPool, user: &User) -> Vec<String> {
+        let Json(response) = list_blocks(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+        response.blocks.into_iter().map(|b| b.username).collect()
+    }
+
+    #[sqlx::test]
+    async fn test_ブロックしたユーザーが新しい順に一覧に含まれる(
+        pool: PgPool,
+    ) {
+        // ブロックしたユーザーが新しい順に一覧に含まれることを確認
+        let user = create_test_user(&pool, true).await;
+        let first = create_test_user(&pool, true).await;
+        let second = create_test_user(&pool, true).await;
+
+        block(&pool, &user, &first).await.unwrap();
+        block(&pool, &user, &second).await.unwrap();
+        // 再度ブロックしても変わらない
+        assert_eq!(block(&pool, &user, &first).await.unwrap(), StatusCode::OK);
+        // 他のユーザーのブロックは含まない
+        block(&pool, &first, &second).await.unwrap();
+
+        assert_eq!(
+            blocked_usernames(&pool, &user).await,
+            vec![second.username.clone(), first.username.clone()]
+        );
+
+        // 解除すると一覧から消える（ブロックしていなくても 204）
+        for _ in 0..2 {
+            let status = unblock_user(
+                State(pool.clone()),
+                Path(second.username.clone()),
+                Extension(user.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::NO_CONTENT);
+        }
+        assert_eq!(
+            blocked_usernames(&pool, &user).await,
+            vec![first.username.clone()]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_自分自身はブロックできない(pool: PgPool) {
+        // 自分自身はブロックできないことを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = block(&pool, &user, &user).await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{extract::State, Extension, Json};
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        threads::ThreadResponse,
+        User,
+    },
+    pagination::{Pagination, Threads},
+    repositories::ThreadsRepo,
+};
+
+/// ブックマークしたスレッドの一覧
+///
+/// 新しくブックマークした順に返します。
+#[utoipa::path(
+    get,
+    path = "/api/users/me/bookmarks",
+    params(Pagination<Threads>),
+    responses(
+        (status = 200, description = "Bookmarked threads, most recently bookmarked first", body = PaginatedResponse<ThreadResponse>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_bookmarks(
+    State(threads): State<Arc<dyn ThreadsRepo>>,
+    Extension(current_user): Extension<User>,
+    pagination: Pagination<Threads>,
+) -> Result<Json<PaginatedResponse<ThreadResponse>>, AppError> {
+    let Pagination {
+        page,
+        limit,
+        offset,
+        ..
+    } = pagination;
+
+    let total = threads.count_bookmarks(current_user.id).await?;
+    let bookmarks = threads
+        .list_bookmarks(current_user.id, limit as i64, offset)
+        .await?
+        .into_iter()
+        .map(|thread| ThreadResponse {
+            bookmarked: Some(true),
+            ..ThreadResponse::from(thread)
+        })
+        .collect();
+
+    Ok(Json(PaginatedResponse::new(
+        bookmarks,
+        total as u64,
+        page,
+        limit,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::threads::bookmark::{bookmark_thread, unbookmark_thread},
+        test_utils::{
+            fakes::{fake_user, FakeRepos},
+            test_config,
+        },
+    };
+    use axum::extract::Path;
+    use uuid::Uuid;
+
+    fn page_of(page: u32, limit: u32) -> Pagination<Threads> {
+        Pagination::new(Some(page), Some(limit), None, &test_config().content_limits)
+    }
+
+    async fn titles(
+        repos: &FakeRepos,
+        user: &User,
+        pagination: Pagination<Threads>,
+    ) -> (Vec<String>, u64) {
+        let Json(response) = list_bookmarks(
+            State(repos.threads_repo()),
+            Extension(user.clone()),
+            pagination,
+        )
+        .await
+        .unwrap();
+        assert!(response.data.iter().all(|t| t.bookmarked == Some(true)));
+        (
+            response.data.into_iter().map(|t| t.title).collect(),
+            response.total,
+        )
+    }
+
+    async fn bookmark(repos: &FakeRepos, id: Uuid, user: &User) {
+        bookmark_thread(
+            State(repos.threads_repo()),
+            Path(id),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ブックマークしたスレッドを新しい順に返す() {
+        // ブックマークしたスレッドが新しい順に返されることを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let author = fake_user(true);
+        let first = repos.threads.insert(&author, "First");
+        let second = repos.threads.insert(&author, "Second");
+        let third = repos.threads.insert(&author, "Third");
+        repos.threads.insert(&author, "Not bookmarked");
+
+        bookmark(&repos, second, &user).await;
+        bookmark(&repos, first, &user).await;
+        bookmark(&repos, third, &user).await;
+        // 他のユーザーのブックマークは含まない
+        bookmark(&repos, first, &author).await;
+
+        assert_eq!(
+            titles(&repos, &user, page_of(1, 20)).await,
+            (vec!["Third".into(), "First".into(), "Second".into()], 3)
+        );
+        assert_eq!(
+            titles(&repos, &user, page_of(2, 2)).await,
+            (vec!["Second".into()], 3)
+        );
+
+        // 外したスレッドは一覧から消える
+        unbookmark_thread(
+            State(repos.threads_repo()),
+            Path(first),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            titles(&repos, &user, page_of(1, 20)).await,
+            (vec!["Third".into(), "Second".into()], 2)
+        );
+    }
+}
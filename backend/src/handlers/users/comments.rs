@@ -1,21 +1,18 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Path, State},
     Json,
 };
-use serde::Deserialize;
 use sqlx::PgPool;
-use utoipa::{IntoParams, ToSchema};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{error::AppError, models::common::ErrorResponse};
-
-#[derive(Debug, Deserialize, ToSchema, IntoParams)]
-pub struct PaginationParams {
-    pub limit: Option<u32>,
-    pub offset: Option<u32>,
-}
+use crate::{
+    error::AppError,
+    models::common::ErrorResponse,
+    pagination::{Pagination, UserListings},
+};
 
-#[derive(Debug, serde::Serialize, ToSchema)]
+#[derive(Debug, serde::Serialize, ToSchema, sqlx::FromRow)]
 pub struct CommentListItem {
     pub id: Uuid,
     pub content: String,
@@ -39,7 +36,7 @@ pub struct PathParams {
     path = "/api/users/{user_id}/comments",
     params(
         ("user_id" = Uuid, Path, description = "ユーザーID"),
-        PaginationParams
+        Pagination<UserListings>
     ),
     responses(
         (status = 200, description = "コメント一覧の取得に成功", body = Vec<CommentListItem>),
@@ -50,39 +47,31 @@ pub struct PathParams {
 pub async fn get_user_comments(
     State(pool): State<PgPool>,
     Path(PathParams { user_id }): Path<PathParams>,
-    Query(params): Query<PaginationParams>,
+    pagination: Pagination<UserListings>,
 ) -> Result<Json<Vec<CommentListItem>>, AppError> {
-    // デフォルト値の設定
-    let limit = params.limit.unwrap_or(10);
-    let offset = params.offset.unwrap_or(0);
-
     // ユーザーが存在するか確認
-    let user_exists = sqlx::query!(
-        "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as exists",
-        user_id
-    )
-    .fetch_one(&pool)
-    .await?
-    .exists
-    .unwrap_or(false);
+    let user_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
 
     if !user_exists {
         return Err(AppError::NotFound);
     }
 
     // ユーザーIDに基づいてコメントを取得
-    let comments = sqlx::query_as!(
-        CommentListItem,
+    let comments = sqlx::query_as::<_, CommentListItem>(
         r#"
         SELECT
             c.id,
             c.content,
-            c.created_at as "created_at!",
-            c.updated_at as "updated_at!",
-            c.user_id as "author_id!",
-            u.username as "author_username!",
-            c.thread_id as "thread_id!",
-            t.title as "thread_title!",
+            c.created_at,
+            c.updated_at,
+            c.user_id AS author_id,
+            u.username AS author_username,
+            c.thread_id,
+            t.title AS thread_title,
             c.parent_id
         FROM
             comments c
@@ -92,15 +81,17 @@ pub async fn get_user_comments(
             threads t ON c.thread_id = t.id
         WHERE
             c.user_id = $1
+            AND c.deleted_at IS NULL
+            AND NOT c.removed_by_moderator
         ORDER BY
             c.created_at DESC
         LIMIT $2
         OFFSET $3
         "#,
-        user_id,
-        limit as i64,
-        offset as i64
     )
+    .bind(user_id)
+    .bind(pagination.limit as i64)
+    .bind(pagination.offset)
     .fetch_all(&pool)
     .await?;
 
@@ -110,10 +101,18 @@ pub async fn get_user_comments(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::{create_test_comment, create_test_thread, seed_test_user};
-    use axum::extract::{Path, Query, State};
+    use crate::test_utils::{create_test_comment, create_test_thread, seed_test_user, test_config};
+    use axum::extract::{Path, State};
     use sqlx::PgPool;
 
+    fn page_of(
+        page: Option<u32>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Pagination<UserListings> {
+        Pagination::new(page, limit, offset, &test_config().content_limits)
+    }
+
     #[sqlx::test]
     async fn test_get_user_comments_success(pool: PgPool) -> Result<(), AppError> {
         // ユーザーが作成したコメントの一覧を正常に取得できるかテスト
@@ -143,10 +142,7 @@ mod tests {
         let result = get_user_comments(
             State(pool),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(10),
-                offset: Some(0),
-            }),
+            page_of(None, Some(10), Some(0)),
         )
         .await?;
 
@@ -187,10 +183,7 @@ mod tests {
         let result1 = get_user_comments(
             State(pool.clone()),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(2),
-                offset: Some(0),
-            }),
+            page_of(None, Some(2), Some(0)),
         )
         .await?;
 
@@ -198,10 +191,7 @@ mod tests {
         let result2 = get_user_comments(
             State(pool.clone()),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(2),
-                offset: Some(2),
-            }),
+            page_of(None, Some(2), Some(2)),
         )
         .await?;
 
@@ -232,10 +222,7 @@ mod tests {
         let result = get_user_comments(
             State(pool),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(10),
-                offset: Some(0),
-            }),
+            page_of(None, Some(10), Some(0)),
         )
         .await?;
 
@@ -258,14 +245,51 @@ mod tests {
             Path(PathParams {
                 user_id: non_existent_user_id,
             }),
-            Query(PaginationParams {
-                limit: Some(10),
-                offset: Some(0),
-            }),
+            page_of(None, Some(10), Some(0)),
         )
         .await;
 
         // NotFoundエラーが返されることを確認
         assert!(matches!(result, Err(AppError::NotFound)));
     }
+
+    #[sqlx::test]
+    async fn test_削除されたコメントは一覧に含めない(pool: PgPool) {
+        // 削除・非表示にされたコメントはユーザーのコメント一覧に含まれないことを確認
+        let user_id = seed_test_user(&pool, "comments_deleted_test").await;
+        let thread_id = create_test_thread(&pool, user_id, "Thread", "Content").await;
+        let visible = create_test_comment(&pool, user_id, thread_id, "Visible", None).await;
+        let reply = create_test_comment(&pool, user_id, thread_id, "Reply", Some(visible)).await;
+        let deleted = create_test_comment(&pool, user_id, thread_id, "Deleted", None).await;
+        let removed = create_test_comment(&pool, user_id, thread_id, "Removed", None).await;
+        sqlx::query("UPDATE comments SET deleted_at = NOW() WHERE id = $1")
+            .bind(deleted)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE comments SET removed_by_moderator = true, removal_reason = 'spam' WHERE id = $1")
+            .bind(removed)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let Json(comments) = get_user_comments(
+            State(pool),
+            Path(PathParams { user_id }),
+            page_of(None, Some(10), Some(0)),
+        )
+        .await
+        .unwrap();
+
+        let mut ids: Vec<Uuid> = comments.iter().map(|c| c.id).collect();
+        ids.sort();
+        let mut expected = vec![visible, reply];
+        expected.sort();
+        assert_eq!(ids, expected);
+        let reply = comments.iter().find(|c| c.id == reply).unwrap();
+        assert_eq!(reply.parent_id, Some(visible));
+        assert_eq!(reply.thread_id, thread_id);
+        assert_eq!(reply.thread_title, "Thread");
+        assert_eq!(reply.author_username, "testuser_comments_deleted_test");
+    }
 }
@@ -1,8 +1,16 @@
-use axum::{extract::Extension, response::Json};
+use axum::{
+    extract::{Extension, State},
+    response::Json,
+};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     error::AppError,
-    models::{common::ErrorResponse, users::UserResponse, User},
+    models::{common::ErrorResponse, moderation::MuteStatus, users::UserResponse, User},
+    utils::user_mute,
 };
 
 #[utoipa::path(
@@ -18,7 +26,59 @@ use crate::{
     )
 )]
 pub async fn get_current_user(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
     Extension(current_user): Extension<User>,
 ) -> Result<Json<UserResponse>, AppError> {
-    Ok(Json(UserResponse::from(current_user)))
+    // ミュート中であれば本人に期限を伝える
+    let mute = user_mute::active_mute(&pool, current_user.id, Utc::now()).await?;
+
+    let mut response = UserResponse::new(current_user, &config.api_url);
+    response.mute = mute.map(MuteStatus::from);
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_user, test_config};
+
+    #[sqlx::test]
+    async fn test_ミュート中の場合はミュート状態が含まれる(pool: PgPool) {
+        // ミュート中のユーザーにだけミュート状態が含まれることを確認
+        // ミュートされていないユーザーにはミュート状態が含まれない
+        let user = create_test_user(&pool, true).await;
+        let response = get_current_user(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(response.0.mute.is_none());
+
+        // 有効なミュートがある場合は期限と理由が返される
+        sqlx::query(
+            r#"
+            INSERT INTO user_mutes (user_id, reason, expires_at)
+            VALUES ($1, 'spam', NOW() + INTERVAL '1 hour')
+            "#,
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let response = get_current_user(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+        )
+        .await
+        .unwrap();
+        let mute = response.0.mute.expect("mute should be present");
+        assert_eq!(mute.reason.as_deref(), Some("spam"));
+        assert!(mute.expires_at > Utc::now());
+    }
 }
@@ -1,34 +1,43 @@
 use axum::{
-    extract::{Extension, State},
+    extract::{Extension, Query, State},
     http::StatusCode,
 };
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use tracing::error;
+use uuid::Uuid;
 
 use crate::{
     error::AppError,
-    models::{common::ErrorResponse, User},
+    models::{
+        common::ErrorResponse,
+        users::{self, DeleteUserQuery},
+        User,
+    },
+    utils::thread_activity,
 };
 
 /// Delete the current user account
 ///
-/// Delete the authenticated user's account and all associated data.
+/// 既定では個人情報を消去してアカウントを匿名化し、スレッド・コメントは投稿者を `[deleted]` として残します。
+/// `?purge=true` の場合はスレッド・コメントを含めてすべて削除します。
 #[utoipa::path(
     delete,
     path = "/api/users/me",
     tag = "users",
+    params(DeleteUserQuery),
     responses(
         (status = 200, description = "User successfully deleted"),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
-        ("bearer" = [])
+        ("bearer_auth" = [])
     )
 )]
 pub async fn delete_user(
     State(pool): State<PgPool>,
     Extension(user): Extension<User>,
+    Query(query): Query<DeleteUserQuery>,
 ) -> Result<StatusCode, AppError> {
     // Start a transaction to ensure all operations succeed or fail together
     let mut tx = pool.begin().await.map_err(|e| {
@@ -37,13 +46,13 @@ pub async fn delete_user(
     })?;
 
     // Delete user credentials
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM user_credentials
         WHERE user_id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -52,13 +61,13 @@ pub async fn delete_user(
     })?;
 
     // Delete OAuth accounts
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM oauth_accounts
         WHERE user_id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -67,13 +76,13 @@ pub async fn delete_user(
     })?;
 
     // Delete refresh tokens
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM refresh_tokens
         WHERE user_id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -81,14 +90,43 @@ pub async fn delete_user(
         AppError::Internal(e.to_string())
     })?;
 
+    if query.purge {
+        purge_content(&mut tx, &user).await?;
+    } else {
+        anonymize(&mut tx, &user).await?;
+    }
+
+    // Commit the transaction
+    tx.commit().await.map_err(|e| {
+        error!("Failed to commit transaction: {}", e);
+        AppError::Internal(e.to_string())
+    })?;
+
+    // Return 200 OK status
+    Ok(StatusCode::OK)
+}
+
+/// スレッド・コメントを含めてユーザーを削除する
+async fn purge_content(tx: &mut PgConnection, user: &User) -> Result<(), AppError> {
+    // コメントを削除する他のスレッドは、最終アクティビティ日時を後で計算し直す
+    let commented_thread_ids =
+        sqlx::query_scalar::<_, Uuid>("SELECT DISTINCT thread_id FROM comments WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Failed to fetch commented threads: {}", e);
+                AppError::Internal(e.to_string())
+            })?;
+
     // Delete comments
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM comments
         WHERE user_id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -97,13 +135,13 @@ pub async fn delete_user(
     })?;
 
     // Delete threads
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM threads
         WHERE user_id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -111,14 +149,16 @@ pub async fn delete_user(
         AppError::Internal(e.to_string())
     })?;
 
+    thread_activity::recompute(&mut *tx, &commented_thread_ids).await?;
+
     // Delete user
-    sqlx::query!(
+    sqlx::query(
         r#"
         DELETE FROM users
         WHERE id = $1
         "#,
-        user.id
     )
+    .bind(user.id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -126,36 +166,122 @@ pub async fn delete_user(
         AppError::Internal(e.to_string())
     })?;
 
-    // Commit the transaction
-    tx.commit().await.map_err(|e| {
-        error!("Failed to commit transaction: {}", e);
+    Ok(())
+}
+
+/// 個人情報を消去し、スレッド・コメントは残したままユーザーを匿名化する
+///
+/// 他のユーザーの議論に穴が開かないよう、投稿とその投票は残します。
+/// ユーザーの行も残し、ユーザー名は `[deleted]` として表示される値に置き換えます。
+async fn anonymize(tx: &mut PgConnection, user: &User) -> Result<(), AppError> {
+    // 本人にしか意味の無いデータを削除
+    for sql in [
+        "DELETE FROM follows WHERE follower_id = $1 OR followee_id = $1",
+        "DELETE FROM user_blocks WHERE blocker_id = $1 OR blocked_id = $1",
+        "DELETE FROM bookmarks WHERE user_id = $1",
+        "DELETE FROM saved_searches WHERE user_id = $1",
+        "DELETE FROM notifications WHERE user_id = $1",
+        "DELETE FROM thread_read_positions WHERE user_id = $1",
+        "DELETE FROM user_settings WHERE user_id = $1",
+        "DELETE FROM username_history WHERE user_id = $1",
+        "DELETE FROM email_change_requests WHERE user_id = $1",
+        "DELETE FROM email_verification_tokens WHERE user_id = $1",
+        "DELETE FROM magic_link_tokens WHERE user_id = $1",
+        "DELETE FROM device_confirmation_tokens WHERE user_id = $1",
+    ] {
+        sqlx::query(sql)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Failed to delete personal data: {}", e);
+                AppError::Internal(e.to_string())
+            })?;
+    }
+
+    // メールアドレスとユーザー名は一意のため、IDから作った値に置き換える
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET username = $2,
+            email = $3,
+            display_name = NULL,
+            avatar_url = NULL,
+            bio = NULL,
+            website_url = NULL,
+            pending_email = NULL,
+            verification_token = NULL,
+            verification_token_expires_at = NULL,
+            password_reset_token = NULL,
+            password_reset_token_expires_at = NULL,
+            role = 'user',
+            deleted_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(user.id)
+    .bind(users::anonymized_username(user.id))
+    .bind(format!("deleted+{}@invalid", user.id.simple()))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to anonymize user: {}", e);
         AppError::Internal(e.to_string())
     })?;
 
-    // Return 200 OK status
-    Ok(StatusCode::OK)
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        handlers::comments::quote::get_comment_quote,
+        models::{threads::ThreadResponse, UserRole},
+        test_utils::{
+            create_test_comment, create_test_thread, create_test_user, test_config, test_state,
+        },
+    };
+    use axum::{extract::Path, Json};
+    use std::sync::Arc;
     use uuid::Uuid;
 
+    /// 退会するユーザーのスレッドと、他のユーザーのスレッドへのコメントを作る
+    async fn seed_discussion(pool: &PgPool) -> (User, Uuid, Uuid, Uuid) {
+        let leaving = create_test_user(pool, true).await;
+        let other = create_test_user(pool, true).await;
+        let own_thread = create_test_thread(pool, leaving.id, "Leaving", "Content").await;
+        let other_thread = create_test_thread(pool, other.id, "Staying", "Content").await;
+        let comment = create_test_comment(pool, leaving.id, other_thread, "Reply", None).await;
+        create_test_comment(pool, other.id, other_thread, "Another reply", None).await;
+        (leaving, own_thread, other_thread, comment)
+    }
+
+    async fn find_thread(pool: &PgPool, id: Uuid) -> Option<ThreadResponse> {
+        test_state(pool)
+            .threads
+            .find(id)
+            .await
+            .unwrap()
+            .map(ThreadResponse::from)
+    }
+
     #[sqlx::test]
     async fn test_delete_user_success(pool: PgPool) {
         // Create a test user directly in the database for testing
         let user_id = Uuid::new_v4();
 
-        sqlx::query!(
+        sqlx::query(
             r#"
             INSERT INTO users (id, username, email, email_verified, created_at, updated_at)
             VALUES ($1, $2, $3, $4, NOW(), NOW())
             "#,
-            user_id,
-            "testdelete",
-            "testdelete@example.com",
-            false
         )
+        .bind(user_id)
+        .bind("testdelete")
+        .bind("testdelete@example.com")
+        .bind(false)
         .execute(&pool)
         .await
         .unwrap();
@@ -167,6 +293,8 @@ mod tests {
             email: "testdelete@example.com".to_string(),
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
             email_verified: false,
             email_verified_at: None,
             verification_token: None,
@@ -175,24 +303,113 @@ mod tests {
             password_reset_token_expires_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            role: UserRole::User,
+            banned_at: None,
+            banned_until: None,
+            ban_reason: None,
         };
 
         // Delete the user
-        let response = delete_user(State(pool.clone()), Extension(user)).await;
+        let response = delete_user(
+            State(pool.clone()),
+            Extension(user),
+            Query(DeleteUserQuery { purge: true }),
+        )
+        .await;
         assert!(response.is_ok());
 
         // Verify user no longer exists
-        let user_exists = sqlx::query!(
-            r#"
-            SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as "exists!"
-            "#,
-            user_id
+        let user_exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        assert!(!user_exists);
+    }
+
+    #[sqlx::test]
+    async fn test_既定では匿名化して投稿を残す(pool: PgPool) {
+        // 既定では個人情報と認証情報は消え、スレッド・コメントは投稿者を [deleted] として残ることを確認
+        let (user, own_thread, other_thread, comment) = seed_discussion(&pool).await;
+
+        let status = delete_user(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Query(DeleteUserQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+
+        let (username, email, deleted): (String, String, bool) = sqlx::query_as(
+            "SELECT username, email, deleted_at IS NOT NULL FROM users WHERE id = $1",
         )
+        .bind(user.id)
         .fetch_one(&pool)
         .await
-        .unwrap()
-        .exists;
+        .unwrap();
+        assert!(deleted);
+        assert_eq!(username, users::anonymized_username(user.id));
+        assert_ne!(email, user.email);
 
-        assert!(!user_exists);
+        let credentials: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM user_credentials WHERE user_id = $1")
+                .bind(user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(credentials, 0);
+
+        // 発行済みのトークンでは認証できない
+        assert!(test_state(&pool)
+            .auth
+            .find_user(user.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let thread = find_thread(&pool, own_thread).await.unwrap();
+        assert_eq!(thread.user.username, users::DELETED_USERNAME);
+        assert_eq!(thread.user.display_name, None);
+
+        // 他のユーザーのスレッドのコメント数は変わらない
+        let thread = find_thread(&pool, other_thread).await.unwrap();
+        assert_eq!(thread.comment_count, 2);
+
+        let Json(quote) = get_comment_quote(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(comment.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(quote.author_username, users::DELETED_USERNAME);
+    }
+
+    #[sqlx::test]
+    async fn test_purgeを指定すると投稿も削除する(pool: PgPool) {
+        // purgeを指定すると投稿も削除されることを確認
+        let (user, own_thread, other_thread, _) = seed_discussion(&pool).await;
+
+        delete_user(
+            State(pool.clone()),
+            Extension(user.clone()),
+            Query(DeleteUserQuery { purge: true }),
+        )
+        .await
+        .unwrap();
+
+        assert!(find_thread(&pool, own_thread).await.is_none());
+        let thread = find_thread(&pool, other_thread).await.unwrap();
+        assert_eq!(thread.comment_count, 1);
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(!exists);
     }
 }
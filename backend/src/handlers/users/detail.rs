@@ -3,12 +3,19 @@ use axum::{
     response::Json,
 };
 use sqlx::PgPool;
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     error::AppError,
-    models::{common::ErrorResponse, users::PublicUserResponse, User},
+    models::{common::ErrorResponse, users::PublicUserResponse},
+    utils::{follows, user_stats, username_history},
 };
 
+/// ユーザーのプロフィールを取得
+///
+/// フォロワー数・フォロー中のユーザー数と、スレッド数・コメント数・カルマを含みます。
+/// 変更前のユーザー名を指定した場合は現在のユーザーを返し、`canonical_username` に現在のユーザー名を含めます。
 #[utoipa::path(
     get,
     path = "/api/users/{username}",
@@ -16,28 +23,43 @@ use crate::{
         ("username" = String, Path, description = "Username to lookup")
     ),
     responses(
-        (status = 200, description = "User profile found", body = PublicUserResponse),
+        (status = 200, description = "User profile found (`canonical_username` is set when looked up by a previous username)", body = PublicUserResponse),
         (status = 404, description = "User not found", body = ErrorResponse)
     ),
     tag = "users"
 )]
 pub async fn get_user_by_username(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
     Path(username): Path<String>,
 ) -> Result<Json<PublicUserResponse>, AppError> {
-    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
-        .bind(username)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let (user, renamed) = match follows::find_user(&pool, &username).await {
+        Ok(user) => (user, false),
+        Err(AppError::NotFound) => {
+            let user = username_history::find_renamed_user(&pool, &username)
+                .await?
+                .ok_or(AppError::NotFound)?;
+            (user, true)
+        }
+        Err(err) => return Err(err),
+    };
+    let counts = follows::counts(&pool, user.id).await?;
+    let stats = user_stats::stats(&pool, user.id).await?;
 
-    Ok(Json(PublicUserResponse::from(user)))
+    let mut response = PublicUserResponse::new(user, counts, stats, &config.api_url);
+    if renamed {
+        response.canonical_username = Some(response.username.clone());
+    }
+
+    Ok(Json(response))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::{create_test_thread, seed_test_user};
+    use crate::test_utils::{
+        create_test_comment, create_test_thread, create_test_user, seed_test_user, test_config,
+    };
     use axum::extract::{Path, State};
     use sqlx::PgPool;
     use uuid::Uuid;
@@ -54,7 +76,12 @@ mod tests {
             .await?;
 
         // APIエンドポイントを呼び出す
-        let response = get_user_by_username(State(pool), Path(username.clone())).await?;
+        let response = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path(username.clone()),
+        )
+        .await?;
 
         let user_response = response.0;
 
@@ -73,13 +100,39 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_get_user_by_username_default_avatar(pool: PgPool) -> Result<(), AppError> {
+        // アバター未設定のユーザーには自動生成アバターのURLが返されることを確認
+        seed_test_user(&pool, "default_avatar").await;
+
+        let response = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path("testuser_default_avatar".to_string()),
+        )
+        .await?;
+
+        let avatar_url = response.0.avatar_url.expect("avatar_url should be present");
+        assert_eq!(
+            avatar_url,
+            "http://localhost:8000/api/users/testuser_default_avatar/avatar.png"
+        );
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn test_get_user_by_username_not_found(pool: PgPool) -> Result<(), anyhow::Error> {
         // 存在しないユーザー名の場合、NotFoundエラーが返されることをテスト
         let non_existent_username = format!("non_existent_user_{}", Uuid::new_v4());
 
         // APIエンドポイントを呼び出す
-        let result = get_user_by_username(State(pool), Path(non_existent_username)).await;
+        let result = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path(non_existent_username),
+        )
+        .await;
 
         // NotFoundエラーが返されることを確認
         assert!(matches!(result, Err(AppError::NotFound)));
@@ -103,7 +156,9 @@ mod tests {
             .await?;
 
         // APIエンドポイントを呼び出す
-        let response = get_user_by_username(State(pool), Path(username)).await?;
+        let response =
+            get_user_by_username(State(pool), State(Arc::new(test_config())), Path(username))
+                .await?;
 
         let user_response = response.0;
 
@@ -146,4 +201,165 @@ mod tests {
 
         Ok(())
     }
+
+    /// スレッドに投票する（賛成票は upvote、反対票は downvote）
+    async fn vote(pool: &PgPool, thread_id: Uuid, vote_type: &str) {
+        let voter = create_test_user(pool, true).await;
+        sqlx::query("INSERT INTO votes (user_id, thread_id, vote_type) VALUES ($1, $2, $3)")
+            .bind(voter.id)
+            .bind(thread_id)
+            .bind(vote_type)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_get_user_by_username_stats(pool: PgPool) -> Result<(), AppError> {
+        // スレッド数・コメント数・カルマが数えられることを確認
+        let user = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let first = create_test_thread(&pool, user.id, "First", "Content").await;
+        let second = create_test_thread(&pool, user.id, "Second", "Content").await;
+        let others_thread = create_test_thread(&pool, other.id, "Other", "Content").await;
+
+        create_test_comment(&pool, user.id, others_thread, "Comment", None).await;
+        create_test_comment(&pool, user.id, first, "Comment", None).await;
+        // 削除・非表示にされたコメントは数えない
+        let deleted = create_test_comment(&pool, user.id, first, "Deleted", None).await;
+        let removed = create_test_comment(&pool, user.id, first, "Removed", None).await;
+        sqlx::query("UPDATE comments SET deleted_at = NOW() WHERE id = $1")
+            .bind(deleted)
+            .execute(&pool)
+            .await?;
+        sqlx::query("UPDATE comments SET removed_by_moderator = true, removal_reason = 'spam' WHERE id = $1")
+            .bind(removed)
+            .execute(&pool)
+            .await?;
+        // 他のユーザーのコメントは数えない
+        create_test_comment(&pool, other.id, first, "Reply", None).await;
+
+        // 賛成3・反対1（+2）と反対1（-1）でカルマは1。他のユーザーのスレッドへの投票は含まない
+        for vote_type in ["upvote", "upvote", "upvote", "downvote"] {
+            vote(&pool, first, vote_type).await;
+        }
+        vote(&pool, second, "downvote").await;
+        vote(&pool, others_thread, "upvote").await;
+
+        let Json(profile) = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path(user.username),
+        )
+        .await?;
+
+        assert_eq!(profile.thread_count, 2);
+        assert_eq!(profile.comment_count, 2);
+        assert_eq!(profile.karma, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_get_user_by_username_stats_without_posts(pool: PgPool) -> Result<(), AppError> {
+        // 投稿の無いユーザーはすべて0になることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let Json(profile) = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path(user.username),
+        )
+        .await?;
+
+        assert_eq!(
+            (profile.thread_count, profile.comment_count, profile.karma),
+            (0, 0, 0)
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_変更前のユーザー名で現在のユーザーを取得できる(
+        pool: PgPool,
+    ) -> Result<(), AppError> {
+        // 変更前のユーザー名で現在のユーザーを取得できることを確認
+        let user = create_test_user(&pool, true).await;
+        let old_username = user.username.clone();
+        let _ = crate::handlers::users::update_profile::update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            axum::Extension(user.clone()),
+            Json(crate::models::users::UpdateProfileRequest {
+                username: Some("renamed_user".to_string()),
+                display_name: None,
+                avatar_url: None,
+                bio: None,
+                website_url: None,
+            }),
+        )
+        .await?;
+
+        let Json(profile) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(old_username),
+        )
+        .await?;
+        assert_eq!(profile.id, user.id);
+        assert_eq!(profile.username, "renamed_user");
+        assert_eq!(profile.canonical_username.as_deref(), Some("renamed_user"));
+
+        // 現在のユーザー名で取得した場合は含まない
+        let Json(profile) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path("renamed_user".to_string()),
+        )
+        .await?;
+        assert_eq!(profile.canonical_username, None);
+        let json = serde_json::to_value(&profile)?;
+        assert!(json.get("canonical_username").is_none());
+
+        // 使われたことの無いユーザー名は404のまま
+        let result = get_user_by_username(
+            State(pool),
+            State(Arc::new(test_config())),
+            Path("never_used".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_ユーザー名の大文字・小文字を区別せずに取得できる(
+        pool: PgPool,
+    ) -> Result<(), AppError> {
+        // ユーザー名の大文字・小文字を区別せずに取得できることを確認
+        let user = create_test_user(&pool, true).await;
+        let username = format!("Mixed_{}", &user.id.simple().to_string()[..8]);
+        sqlx::query("UPDATE users SET username = $2 WHERE id = $1")
+            .bind(user.id)
+            .bind(&username)
+            .execute(&pool)
+            .await?;
+
+        for lookup in [username.to_lowercase(), username.to_uppercase()] {
+            let Json(profile) = get_user_by_username(
+                State(pool.clone()),
+                State(Arc::new(test_config())),
+                Path(lookup),
+            )
+            .await?;
+            assert_eq!(profile.id, user.id);
+            // 表示用の大文字・小文字はそのまま
+            assert_eq!(profile.username, username);
+            assert_eq!(profile.canonical_username, None);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,292 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{auth::MessageResponse, common::ErrorResponse, users::UserResponse, User},
+    utils::email_change,
+};
+
+/// メールアドレスの変更を確定する
+///
+/// 新しいアドレスに届いた確認リンクのトークンを、変更をリクエストした本人として送ります。
+#[utoipa::path(
+    post,
+    path = "/api/users/me/email/confirm/{token}",
+    params(
+        ("token" = String, Path, description = "新しいアドレスに届いた確認トークン")
+    ),
+    responses(
+        (status = 200, description = "Email changed", body = UserResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Email already exists", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn confirm_email_change(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Extension(current_user): Extension<User>,
+    Path(token): Path<String>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = email_change::confirm_change(&pool, current_user.id, &token).await?;
+
+    Ok(Json(UserResponse::new(user, &config.api_url)))
+}
+
+/// メールアドレスの変更を取り消す
+///
+/// 旧アドレスに届いたお知らせのリンクから、ログインせずに使えます。
+/// 確認前なら変更を中止し、確認後なら旧アドレスに戻したうえで、すべてのセッションを無効にします。
+#[utoipa::path(
+    post,
+    path = "/api/users/email/revert/{token}",
+    params(
+        ("token" = String, Path, description = "旧アドレスに届いた取り消しトークン")
+    ),
+    responses(
+        (status = 200, description = "Email change reverted", body = MessageResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+        (status = 409, description = "The old email is now used by another account", body = ErrorResponse)
+    ),
+    tag = "users"
+)]
+pub async fn revert_email_change(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+) -> Result<Json<MessageResponse>, AppError> {
+    email_change::revert_change(&pool, &token).await?;
+
+    Ok(Json(MessageResponse {
+        message: "The email change has been reverted. Please log in again.".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_test_user, test_config},
+        utils::{
+            email_change::EmailChangeTokens, token_generator::RandomTokenGenerator,
+            token_hash::hash_refresh_token,
+        },
+    };
+
+    // 変更をリクエストしてメールで届くトークンを返す
+    async fn request(pool: &PgPool, user: &User, new_email: &str) -> EmailChangeTokens {
+        let mut tx = pool.begin().await.unwrap();
+        let tokens = email_change::request_change(
+            &test_config(),
+            &RandomTokenGenerator,
+            user,
+            new_email,
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+        tokens
+    }
+
+    async fn fetch_user(pool: &PgPool, user_id: uuid::Uuid) -> (String, Option<String>) {
+        sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT email, pending_email FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_確認トークンでメールアドレスが変更される(pool: PgPool) {
+        // 確認トークンを使うとメールアドレスが変更されることを確認
+        let user = create_test_user(&pool, false).await;
+        let tokens = request(&pool, &user, "changed@example.com").await;
+
+        let Json(response) = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(tokens.confirm_token.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.email, "changed@example.com");
+        // 確認リンクを開けたので新しいアドレスは確認済み
+        assert!(response.email_verified);
+        assert_eq!(
+            fetch_user(&pool, user.id).await,
+            ("changed@example.com".to_string(), None)
+        );
+
+        // 同じトークンは二度使えない
+        let again = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(tokens.confirm_token),
+        )
+        .await;
+        assert!(matches!(again, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_他のユーザーの確認トークンは使えない(pool: PgPool) {
+        // 他のユーザーの確認トークンではメールアドレスを変更できないことを確認
+        let user = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let tokens = request(&pool, &user, "changed@example.com").await;
+
+        let result = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(other.clone()),
+            Path(tokens.confirm_token),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!(fetch_user(&pool, user.id).await.0, user.email);
+        assert_eq!(fetch_user(&pool, other.id).await.0, other.email);
+    }
+
+    #[sqlx::test]
+    async fn test_新しいリクエストで以前の確認トークンは無効になる(
+        pool: PgPool,
+    ) {
+        // 新しい変更リクエストで以前の確認トークンが無効になることを確認
+        let user = create_test_user(&pool, true).await;
+        let first = request(&pool, &user, "first@example.com").await;
+        let _second = request(&pool, &user, "second@example.com").await;
+
+        let result = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(first.confirm_token),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!(
+            fetch_user(&pool, user.id).await,
+            (user.email.clone(), Some("second@example.com".to_string()))
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_確認前に取り消すと変更が中止される(pool: PgPool) {
+        // 確認前に取り消すとメールアドレスの変更が中止されることを確認
+        let user = create_test_user(&pool, true).await;
+        let tokens = request(&pool, &user, "changed@example.com").await;
+
+        let result = revert_email_change(State(pool.clone()), Path(tokens.revert_token)).await;
+        assert!(result.is_ok());
+        assert_eq!(fetch_user(&pool, user.id).await, (user.email.clone(), None));
+
+        // 取り消し後は確認トークンも使えない
+        let confirm = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(tokens.confirm_token),
+        )
+        .await;
+        assert!(matches!(confirm, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_確認後に取り消すと旧アドレスに戻りセッションが無効になる(
+        pool: PgPool,
+    ) {
+        // 確認後に取り消すと旧アドレスに戻り、セッションが無効になることを確認
+        let user = create_test_user(&pool, true).await;
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, NOW() + INTERVAL '7 days')
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_refresh_token("session_token"))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let tokens = request(&pool, &user, "attacker@example.com").await;
+        let _ = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(tokens.confirm_token),
+        )
+        .await
+        .unwrap();
+
+        let result =
+            revert_email_change(State(pool.clone()), Path(tokens.revert_token.clone())).await;
+        assert!(result.is_ok());
+        assert_eq!(fetch_user(&pool, user.id).await, (user.email.clone(), None));
+
+        let active: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND revoked = false",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(active, 0);
+
+        // 取り消しトークンは一度しか使えない
+        let again = revert_email_change(State(pool.clone()), Path(tokens.revert_token)).await;
+        assert!(matches!(again, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れのトークンは使えない(pool: PgPool) {
+        // 期限切れの確認トークンではメールアドレスを変更できないことを確認
+        let user = create_test_user(&pool, true).await;
+        let tokens = request(&pool, &user, "changed@example.com").await;
+        sqlx::query(
+            r#"
+            UPDATE email_change_requests
+            SET confirm_expires_at = NOW() - INTERVAL '1 minute',
+                revert_expires_at = NOW() - INTERVAL '1 minute'
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let confirm = confirm_email_change(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Path(tokens.confirm_token),
+        )
+        .await;
+        assert!(matches!(confirm, Err(AppError::BadRequest(_))));
+
+        let revert = revert_email_change(State(pool.clone()), Path(tokens.revert_token)).await;
+        assert!(matches!(revert, Err(AppError::BadRequest(_))));
+
+        assert_eq!(
+            fetch_user(&pool, user.id).await,
+            (user.email.clone(), Some("changed@example.com".to_string()))
+        );
+    }
+}
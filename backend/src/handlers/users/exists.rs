@@ -0,0 +1,145 @@
+use axum::{
+    extract::{Path, State},
+    response::Response,
+};
+use sqlx::PgPool;
+
+use crate::{error::AppError, models::common::Existence, utils::http_cache};
+
+/// ユーザーが存在するかを確認
+///
+/// プロフィールページと404ページのどちらを表示するかの判定用です。本文は返さず、ステータスのみで答えます。
+/// 変更前のユーザー名は、プロフィールの取得と同じく現在のユーザーとして 200 を返します。
+/// 退会したユーザーは 410 を返します。結果は短時間キャッシュできます。
+#[utoipa::path(
+    head,
+    path = "/api/users/{username}",
+    params(
+        ("username" = String, Path, description = "Username to lookup")
+    ),
+    responses(
+        (status = 200, description = "User exists"),
+        (status = 404, description = "User not found"),
+        (status = 410, description = "User deleted their account")
+    ),
+    tag = "users"
+)]
+pub async fn user_exists(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+) -> Result<Response, AppError> {
+    let existence = existence(&pool, &username).await?;
+
+    Ok(http_cache::existence_response(existence))
+}
+
+// 統計やフォロー数は読まず、ユーザー名の索引だけで判定する
+async fn existence(pool: &PgPool, username: &str) -> Result<Existence, AppError> {
+    let (deleted, renamed) = sqlx::query_as::<_, (Option<bool>, bool)>(
+        r#"
+        SELECT
+            (SELECT deleted_at IS NOT NULL FROM users WHERE LOWER(username) = LOWER($1)),
+            EXISTS(
+                SELECT 1
+                FROM username_history h
+                JOIN users u ON u.id = h.user_id
+                WHERE LOWER(h.username) = LOWER($1) AND u.deleted_at IS NULL
+            )
+        "#,
+    )
+    .bind(username)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(match (deleted, renamed) {
+        (Some(false), _) | (None, true) => Existence::Present,
+        (Some(true), _) => Existence::Tombstoned,
+        (None, false) => Existence::Missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{app::spawn_app, create_test_thread, create_test_user},
+        utils::username_history,
+    };
+    use axum::http::{header, Method, StatusCode};
+    use chrono::Utc;
+
+    #[sqlx::test]
+    async fn test_ユーザーの存在をステータスのみで返す(pool: PgPool) {
+        // ユーザーの存在がステータスのみで返されることを確認
+        let user = create_test_user(&pool, true).await;
+        let renamed = create_test_user(&pool, true).await;
+        username_history::record(
+            &mut pool.acquire().await.unwrap(),
+            renamed.id,
+            "old_name_for_head",
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        let deleted = create_test_user(&pool, true).await;
+        sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
+            .bind(deleted.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for (username, expected) in [
+            (user.username.to_uppercase(), StatusCode::OK),
+            ("old_name_for_head".to_string(), StatusCode::OK),
+            (deleted.username.clone(), StatusCode::GONE),
+            ("no_such_user".to_string(), StatusCode::NOT_FOUND),
+        ] {
+            let response = user_exists(State(pool.clone()), Path(username.clone()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected, "{}", username);
+            assert!(response.headers().contains_key(header::CACHE_CONTROL));
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty());
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_headはgetのハンドラーに流れない(pool: PgPool) {
+        // HEADのリクエストがGETのハンドラーに流れないことを確認
+        // GET は削除されたリソースに 404 を返すため、410 が返れば HEAD 専用のハンドラーが応答している
+        let app = spawn_app(&pool);
+        let deleted = create_test_user(&pool, true).await;
+        sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
+            .bind(deleted.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let author = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, author.id, "Thread", "Content").await;
+
+        let response = app
+            .request(
+                Method::HEAD,
+                &format!("/api/users/{}", deleted.username),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::GONE);
+        assert!(response.body.is_null());
+
+        let response = app
+            .request(
+                Method::HEAD,
+                &format!("/api/threads/{}", thread_id),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.is_null());
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Extension, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{common::ErrorResponse, User},
+    utils::account_export,
+};
+
+/// アカウントデータのエクスポート
+///
+/// プロフィール・スレッド・コメント・投票・連携している外部ログインのプロバイダー名を
+/// `schema_version` と `generated_at` 付きのJSONとして逐次出力します。
+/// パスワードのハッシュやトークンは含めません。同じユーザーは1時間に1回まで実行できます。
+#[utoipa::path(
+    get,
+    path = "/api/users/me/export",
+    responses(
+        (status = 200, description = "Account data as JSON (`schema_version`, `generated_at`, `profile`, `oauth_providers`, `threads`, `comments`, `votes`)", content_type = "application/json", body = Object),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 429, description = "Already exported within the last hour", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn export_account_data(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Extension(current_user): Extension<User>,
+) -> Result<Response, AppError> {
+    let headers = [
+        (header::CONTENT_TYPE, "application/json".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            account_export::content_disposition(&current_user.username),
+        ),
+    ];
+    let body = account_export::json_body(pool, current_user, config.api_url.clone());
+    Ok((headers, body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        create_test_comment, create_test_thread, create_test_user, test_config,
+    };
+    use axum::{body::to_bytes, http::StatusCode};
+
+    #[sqlx::test]
+    async fn test_プロフィール・投稿・投票をエクスポートし認証情報は含めない(
+        pool: PgPool,
+    ) {
+        // プロフィール・投稿・投票がエクスポートされ、認証情報は含まれないことを確認
+        let user = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "My thread", "Body").await;
+        let other_thread_id = create_test_thread(&pool, other.id, "Other", "Body").await;
+        let comment_id =
+            create_test_comment(&pool, user.id, other_thread_id, "My comment", None).await;
+        create_test_comment(&pool, other.id, thread_id, "Not mine", None).await;
+        sqlx::query("INSERT INTO votes (user_id, thread_id, vote_type) VALUES ($1, $2, 'upvote')")
+            .bind(user.id)
+            .bind(other_thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_accounts (user_id, provider, provider_user_id, access_token, refresh_token)
+            VALUES ($1, 'google', 'google-user-1', 'secret-access-token', 'secret-refresh-token')
+            "#,
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let password_hash: String =
+            sqlx::query_scalar("SELECT password_hash FROM user_credentials WHERE user_id = $1")
+                .bind(user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let response = export_account_data(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"{}-export.json\"", user.username)
+        );
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let raw = String::from_utf8(body.to_vec()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&raw).unwrap();
+
+        assert_eq!(json["schema_version"], account_export::SCHEMA_VERSION);
+        assert!(json["generated_at"].is_string());
+        assert_eq!(json["profile"]["id"], user.id.to_string());
+        assert_eq!(json["profile"]["email"], user.email);
+        assert_eq!(json["oauth_providers"], serde_json::json!(["google"]));
+
+        let threads = json["threads"].as_array().unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0]["id"], thread_id.to_string());
+        assert_eq!(threads[0]["title"], "My thread");
+
+        let comments = json["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0]["id"], comment_id.to_string());
+        assert_eq!(comments[0]["content"], "My comment");
+
+        let votes = json["votes"].as_array().unwrap();
+        assert_eq!(votes.len(), 1);
+        assert_eq!(votes[0]["thread_id"], other_thread_id.to_string());
+        assert_eq!(votes[0]["vote_type"], "upvote");
+
+        for secret in [
+            password_hash.as_str(),
+            "password_hash",
+            "secret-access-token",
+            "secret-refresh-token",
+            "google-user-1",
+            "verification_token",
+        ] {
+            assert!(
+                !raw.contains(secret),
+                "export should not contain {}",
+                secret
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_投稿が無いユーザーは空の一覧になる(pool: PgPool) {
+        // 投稿が無いユーザーのエクスポートは空の一覧になることを確認
+        let user = create_test_user(&pool, false).await;
+
+        let response = export_account_data(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+        )
+        .await
+        .unwrap();
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        for key in ["oauth_providers", "threads", "comments", "votes"] {
+            assert_eq!(json[key], serde_json::json!([]), "{} should be empty", key);
+        }
+    }
+}
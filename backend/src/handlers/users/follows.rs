@@ -0,0 +1,293 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::{
+        common::{ErrorResponse, PaginatedResponse},
+        users::FollowUserResponse,
+        User,
+    },
+    pagination::{Pagination, UserListings},
+    utils::follows,
+};
+
+/// ユーザーをフォロー
+///
+/// 既にフォローしている場合も 200 を返します。自分自身はフォローできません。
+#[utoipa::path(
+    post,
+    path = "/api/users/{username}/follow",
+    params(
+        ("username" = String, Path, description = "Username to follow")
+    ),
+    responses(
+        (status = 200, description = "User followed (or already followed)"),
+        (status = 400, description = "Cannot follow yourself", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn follow_user(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    let followee = follows::find_user(&pool, &username).await?;
+
+    follows::follow(&pool, current_user.id, followee.id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// ユーザーのフォローを解除
+///
+/// フォローしていない場合も 204 を返します。
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}/follow",
+    params(
+        ("username" = String, Path, description = "Username to unfollow")
+    ),
+    responses(
+        (status = 204, description = "Unfollowed (or was not following)"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn unfollow_user(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    let followee = follows::find_user(&pool, &username).await?;
+
+    follows::unfollow(&pool, current_user.id, followee.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// フォロワーの一覧
+///
+/// 新しくフォローされた順に返します。`total` がフォロワー数です。
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/followers",
+    params(
+        ("username" = String, Path, description = "Username"),
+        Pagination<UserListings>
+    ),
+    responses(
+        (status = 200, description = "Followers, most recently followed first", body = PaginatedResponse<FollowUserResponse>),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users"
+)]
+pub async fn get_followers(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(username): Path<String>,
+    pagination: Pagination<UserListings>,
+) -> Result<Json<PaginatedResponse<FollowUserResponse>>, AppError> {
+    let user = follows::find_user(&pool, &username).await?;
+    let counts = follows::counts(&pool, user.id).await?;
+    let followers =
+        follows::list_followers(&pool, user.id, pagination.limit as i64, pagination.offset)
+            .await?
+            .into_iter()
+            .map(|user| user.with_default_avatar(&config.api_url))
+            .collect();
+
+    Ok(Json(PaginatedResponse::new(
+        followers,
+        counts.followers as u64,
+        pagination.page,
+        pagination.limit,
+    )))
+}
+
+/// フォロー中のユーザーの一覧
+///
+/// 新しくフォローした順に返します。`total` がフォロー中のユーザー数です。
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/following",
+    params(
+        ("username" = String, Path, description = "Username"),
+        Pagination<UserListings>
+    ),
+    responses(
+        (status = 200, description = "Followed users, most recently followed first", body = PaginatedResponse<FollowUserResponse>),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    tag = "users"
+)]
+pub async fn get_following(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path(username): Path<String>,
+    pagination: Pagination<UserListings>,
+) -> Result<Json<PaginatedResponse<FollowUserResponse>>, AppError> {
+    let user = follows::find_user(&pool, &username).await?;
+    let counts = follows::counts(&pool, user.id).await?;
+    let following =
+        follows::list_following(&pool, user.id, pagination.limit as i64, pagination.offset)
+            .await?
+            .into_iter()
+            .map(|user| user.with_default_avatar(&config.api_url))
+            .collect();
+
+    Ok(Json(PaginatedResponse::new(
+        following,
+        counts.following as u64,
+        pagination.page,
+        pagination.limit,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::users::detail::get_user_by_username,
+        test_utils::{create_test_user, test_config},
+    };
+
+    fn page_of(page: u32, limit: u32) -> Pagination<UserListings> {
+        Pagination::new(Some(page), Some(limit), None, &test_config().content_limits)
+    }
+
+    async fn follow(
+        pool: &PgPool,
+        follower: &User,
+        followee: &User,
+    ) -> Result<StatusCode, AppError> {
+        follow_user(
+            State(pool.clone()),
+            Path(followee.username.clone()),
+            Extension(follower.clone()),
+        )
+        .await
+    }
+
+    fn usernames(response: Json<PaginatedResponse<FollowUserResponse>>) -> (Vec<String>, u64) {
+        let Json(response) = response;
+        (
+            response.data.into_iter().map(|u| u.username).collect(),
+            response.total,
+        )
+    }
+
+    #[sqlx::test]
+    async fn test_フォローするとフォロワー数とフォロー数に反映される(
+        pool: PgPool,
+    ) {
+        // フォローするとフォロワー数とフォロー数に反映されることを確認
+        let alice = create_test_user(&pool, true).await;
+        let bob = create_test_user(&pool, true).await;
+        let carol = create_test_user(&pool, true).await;
+
+        follow(&pool, &alice, &carol).await.unwrap();
+        follow(&pool, &bob, &carol).await.unwrap();
+        follow(&pool, &alice, &bob).await.unwrap();
+        // 同じユーザーを再度フォローしても変わらない
+        assert_eq!(follow(&pool, &alice, &carol).await.unwrap(), StatusCode::OK);
+
+        let Json(profile) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(carol.username.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!((profile.follower_count, profile.following_count), (2, 0));
+
+        let Json(profile) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(alice.username.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!((profile.follower_count, profile.following_count), (0, 2));
+
+        // フォロワーは新しくフォローされた順
+        let followers = get_followers(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(carol.username.clone()),
+            page_of(1, 20),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            usernames(followers),
+            (vec![bob.username.clone(), alice.username.clone()], 2)
+        );
+
+        let following = get_following(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(alice.username.clone()),
+            page_of(1, 1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(usernames(following), (vec![bob.username.clone()], 2));
+
+        // フォローを解除すると数が減る（フォローしていなくても 204）
+        for _ in 0..2 {
+            let status = unfollow_user(
+                State(pool.clone()),
+                Path(carol.username.clone()),
+                Extension(alice.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::NO_CONTENT);
+        }
+        let Json(profile) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(carol.username.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(profile.follower_count, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_自分自身はフォローできない(pool: PgPool) {
+        // 自分自身はフォローできないことを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = follow(&pool, &user, &user).await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないユーザーはフォローできない(pool: PgPool) {
+        // 存在しないユーザーはフォローできないことを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = follow_user(
+            State(pool.clone()),
+            Path("no_such_user".to_string()),
+            Extension(user),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
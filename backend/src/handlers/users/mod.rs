@@ -1,15 +1,16 @@
+pub mod avatar;
+pub mod blocks;
+pub mod bookmarks;
 pub mod comments;
 pub mod current_user;
 pub mod delete;
 pub mod detail;
+pub mod email_change;
+pub mod exists;
+pub mod export;
+pub mod follows;
+pub mod preferences;
+pub mod saved_searches;
 pub mod threads;
 pub mod update_email;
 pub mod update_profile;
-
-pub use comments::get_user_comments;
-pub use current_user::get_current_user;
-pub use delete::delete_user;
-pub use detail::get_user_by_username;
-pub use threads::get_user_threads;
-pub use update_email::update_email;
-pub use update_profile::update_profile;
@@ -0,0 +1,239 @@
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        preferences::{PreferencesDocument, PreferencesImportResponse},
+        User,
+    },
+    repositories::UsersRepo,
+    utils::preferences_transfer,
+};
+
+/// 設定のエクスポート
+///
+/// 並び順の既定値・ブロック・フォロー・保存した検索条件を、別のインスタンスに取り込める形式で返します。
+/// ユーザーはユーザー名で表します。
+#[utoipa::path(
+    get,
+    path = "/api/users/me/preferences/export",
+    responses(
+        (status = 200, description = "Versioned preferences document", body = PreferencesDocument),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn export_preferences(
+    State(pool): State<PgPool>,
+    State(users): State<Arc<dyn UsersRepo>>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<PreferencesDocument>, AppError> {
+    let settings = users.sort_preferences(current_user.id).await?;
+    let document = preferences_transfer::export(&pool, current_user.id, settings).await?;
+
+    Ok(Json(document))
+}
+
+/// 設定のインポート
+///
+/// エクスポートした文書を取り込みます。並び順の既定値は文書の内容で置き換え、
+/// ブロック・フォロー・保存した検索条件は既存のものに追加します（同じ文書を再度取り込んでも変わりません）。
+/// このインスタンスにいないユーザーや上限を超えた検索条件は取り込まず、`skipped` に理由とともに返します。
+#[utoipa::path(
+    post,
+    path = "/api/users/me/preferences/import",
+    request_body = PreferencesDocument,
+    responses(
+        (status = 200, description = "Preferences imported", body = PreferencesImportResponse),
+        (status = 400, description = "Unsupported version or too many entries", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(("bearer_auth" = []))
+)]
+pub async fn import_preferences(
+    State(pool): State<PgPool>,
+    Extension(current_user): Extension<User>,
+    Json(document): Json<PreferencesDocument>,
+) -> Result<Json<PreferencesImportResponse>, AppError> {
+    let report = preferences_transfer::import(&pool, current_user.id, &document).await?;
+
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            preferences::{PortableSavedSearch, SkipReason, SkippedEntry, SkippedKind},
+            threads::ThreadSort,
+        },
+        test_utils::{create_test_user, test_state},
+        utils::{follows, saved_searches, user_blocks},
+    };
+
+    async fn export(pool: &PgPool, user: &User) -> PreferencesDocument {
+        let state = test_state(pool);
+        let Json(document) = export_preferences(
+            State(pool.clone()),
+            State(state.users.clone()),
+            Extension(user.clone()),
+        )
+        .await
+        .unwrap();
+        document
+    }
+
+    async fn import(
+        pool: &PgPool,
+        user: &User,
+        document: PreferencesDocument,
+    ) -> Result<PreferencesImportResponse, AppError> {
+        let Json(report) =
+            import_preferences(State(pool.clone()), Extension(user.clone()), Json(document))
+                .await?;
+        Ok(report)
+    }
+
+    #[sqlx::test]
+    async fn test_エクスポートした設定を別のユーザーに取り込める(
+        pool: PgPool,
+    ) {
+        // エクスポートした設定を別のユーザーに取り込めることを確認
+        let source = create_test_user(&pool, true).await;
+        let target = create_test_user(&pool, true).await;
+        let blocked = create_test_user(&pool, true).await;
+        let followee = create_test_user(&pool, true).await;
+        user_blocks::block(&pool, source.id, blocked.id)
+            .await
+            .unwrap();
+        follows::follow(&pool, source.id, followee.id)
+            .await
+            .unwrap();
+        saved_searches::create(&pool, source.id, "Rust axum", true, true)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO user_settings (user_id, default_sort) VALUES ($1, 'top')")
+            .bind(source.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let document = export(&pool, &source).await;
+        assert_eq!(document.version, preferences_transfer::VERSION);
+        assert_eq!(document.settings.default_sort, Some(ThreadSort::Top));
+        assert_eq!(document.blocks, vec![blocked.username.clone()]);
+        assert_eq!(document.follows, vec![followee.username.clone()]);
+        assert_eq!(
+            document.saved_searches,
+            vec![PortableSavedSearch {
+                query: "rust axum".to_string(),
+                notify: true,
+                email: true,
+            }]
+        );
+
+        // JSONを経由しても同じ内容になる
+        let json = serde_json::to_string(&document).unwrap();
+        let document: PreferencesDocument = serde_json::from_str(&json).unwrap();
+
+        let report = import(&pool, &target, document.clone()).await.unwrap();
+        assert_eq!(
+            report,
+            PreferencesImportResponse {
+                blocks_added: 1,
+                follows_added: 1,
+                saved_searches_added: 1,
+                skipped: vec![],
+            }
+        );
+
+        let imported = export(&pool, &target).await;
+        assert_eq!(imported.settings, document.settings);
+        assert_eq!(imported.blocks, document.blocks);
+        assert_eq!(imported.follows, document.follows);
+        assert_eq!(imported.saved_searches, document.saved_searches);
+
+        // 同じ文書をもう一度取り込んでも何も増えない
+        let report = import(&pool, &target, document).await.unwrap();
+        assert_eq!(report, PreferencesImportResponse::default());
+        assert_eq!(export(&pool, &target).await.blocks.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_見つからないユーザーは取り込まずに報告する(pool: PgPool) {
+        // 見つからないユーザーは取り込まずに報告することを確認
+        let user = create_test_user(&pool, true).await;
+        let known = create_test_user(&pool, true).await;
+        let mut document = export(&pool, &user).await;
+        document.blocks = vec![
+            "ghost_user".to_string(),
+            known.username.clone(),
+            user.username.clone(),
+        ];
+        document.follows = vec![known.username.clone(), "another_ghost".to_string()];
+        document.saved_searches = vec![PortableSavedSearch {
+            query: "   ".to_string(),
+            notify: false,
+            email: false,
+        }];
+
+        let report = import(&pool, &user, document).await.unwrap();
+
+        assert_eq!(report.blocks_added, 1);
+        assert_eq!(report.follows_added, 1);
+        assert_eq!(report.saved_searches_added, 0);
+        let skipped = |kind, value: &str, reason| SkippedEntry {
+            kind,
+            value: value.to_string(),
+            reason,
+        };
+        assert_eq!(
+            report.skipped,
+            vec![
+                skipped(SkippedKind::Block, "ghost_user", SkipReason::UnknownUser),
+                skipped(
+                    SkippedKind::Block,
+                    &user.username,
+                    SkipReason::SelfReference
+                ),
+                skipped(
+                    SkippedKind::Follow,
+                    "another_ghost",
+                    SkipReason::UnknownUser
+                ),
+                skipped(SkippedKind::SavedSearch, "   ", SkipReason::InvalidQuery),
+            ]
+        );
+        assert_eq!(
+            user_blocks::blocked_ids(&pool, user.id).await.unwrap(),
+            [known.id].into_iter().collect()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_対応していないバージョンは取り込まない(pool: PgPool) {
+        // 対応していないバージョンの設定は取り込まないことを確認
+        let user = create_test_user(&pool, true).await;
+        let known = create_test_user(&pool, true).await;
+        let mut document = export(&pool, &user).await;
+        document.version = preferences_transfer::VERSION + 1;
+        document.blocks = vec![known.username.clone()];
+
+        let result = import(&pool, &user, document).await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert!(user_blocks::blocked_ids(&pool, user.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
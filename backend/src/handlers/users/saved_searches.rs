@@ -0,0 +1,208 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::ErrorResponse,
+        saved_searches::{CreateSavedSearchRequest, SavedSearchListResponse, SavedSearchResponse},
+        User,
+    },
+    utils::saved_searches,
+};
+
+/// 検索条件を保存する
+///
+/// `notify` を有効にすると、保存した後に作成されたスレッドのうち検索語に一致するものが通知されます。
+#[utoipa::path(
+    post,
+    path = "/api/users/me/saved-searches",
+    request_body = CreateSavedSearchRequest,
+    responses(
+        (status = 201, description = "Search saved", body = SavedSearchResponse),
+        (status = 400, description = "Bad request or too many saved searches", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Search already saved", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_saved_search(
+    State(pool): State<PgPool>,
+    Extension(current_user): Extension<User>,
+    Json(payload): Json<CreateSavedSearchRequest>,
+) -> Result<(StatusCode, Json<SavedSearchResponse>), AppError> {
+    payload.validate()?;
+
+    let search = saved_searches::create(
+        &pool,
+        current_user.id,
+        &payload.query,
+        payload.notify.unwrap_or(false),
+        payload.email.unwrap_or(false),
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(SavedSearchResponse::from(search))))
+}
+
+/// 保存した検索条件の一覧
+#[utoipa::path(
+    get,
+    path = "/api/users/me/saved-searches",
+    responses(
+        (status = 200, description = "Saved searches", body = SavedSearchListResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_saved_searches(
+    State(pool): State<PgPool>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<SavedSearchListResponse>, AppError> {
+    let saved_searches = saved_searches::list(&pool, current_user.id)
+        .await?
+        .into_iter()
+        .map(SavedSearchResponse::from)
+        .collect();
+
+    Ok(Json(SavedSearchListResponse { saved_searches }))
+}
+
+/// 保存した検索条件を削除する
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/saved-searches/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Saved search ID")
+    ),
+    responses(
+        (status = 204, description = "Saved search deleted"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Saved search not found", body = ErrorResponse)
+    ),
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_saved_search(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Extension(current_user): Extension<User>,
+) -> Result<StatusCode, AppError> {
+    saved_searches::delete(&pool, current_user.id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+
+    fn request(
+        query: &str,
+        notify: Option<bool>,
+        email: Option<bool>,
+    ) -> Json<CreateSavedSearchRequest> {
+        Json(CreateSavedSearchRequest {
+            query: query.to_string(),
+            notify,
+            email,
+        })
+    }
+
+    #[sqlx::test]
+    async fn test_検索条件を保存して一覧_削除できる(pool: PgPool) {
+        // 検索語は正規化して保存され、メール通知は通知が有効な場合のみ有効になることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let (status, Json(saved)) = create_saved_search(
+            State(pool.clone()),
+            Extension(user.clone()),
+            request("  Rust  Axum ", Some(true), Some(true)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(saved.query, "rust axum");
+        assert!(saved.notify);
+        assert!(saved.email);
+
+        let (_, Json(silent)) = create_saved_search(
+            State(pool.clone()),
+            Extension(user.clone()),
+            request("tokio", None, Some(true)),
+        )
+        .await
+        .unwrap();
+        assert!(!silent.notify);
+        assert!(!silent.email);
+
+        let Json(list) = list_saved_searches(State(pool.clone()), Extension(user.clone()))
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = list.saved_searches.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![saved.id, silent.id]);
+
+        let status =
+            delete_saved_search(State(pool.clone()), Path(saved.id), Extension(user.clone()))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let Json(list) = list_saved_searches(State(pool.clone()), Extension(user))
+            .await
+            .unwrap();
+        assert_eq!(list.saved_searches.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_他のユーザーの検索条件は削除できない(pool: PgPool) {
+        // 他のユーザーの検索条件は削除できないことを確認
+        let owner = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let (_, Json(saved)) = create_saved_search(
+            State(pool.clone()),
+            Extension(owner.clone()),
+            request("rust", None, None),
+        )
+        .await
+        .unwrap();
+
+        let result =
+            delete_saved_search(State(pool.clone()), Path(saved.id), Extension(other)).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let Json(list) = list_saved_searches(State(pool.clone()), Extension(owner))
+            .await
+            .unwrap();
+        assert_eq!(list.saved_searches.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_空の検索語は保存できない(pool: PgPool) {
+        // 空の検索語は保存できないことを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = create_saved_search(
+            State(pool.clone()),
+            Extension(user),
+            request("   ", None, None),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
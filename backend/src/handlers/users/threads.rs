@@ -7,15 +7,19 @@ use sqlx::PgPool;
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use crate::{error::AppError, models::common::ErrorResponse};
+use crate::{
+    error::AppError,
+    models::{common::ErrorResponse, threads::UserThreadSort},
+    pagination::{Pagination, UserListings},
+};
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
-pub struct PaginationParams {
-    pub limit: Option<u32>,
-    pub offset: Option<u32>,
+pub struct UserThreadsQuery {
+    /// 並び順: new（新しい順）、top（スコア順）、comments（コメント数順）。省略時は new
+    pub sort: Option<UserThreadSort>,
 }
 
-#[derive(Debug, serde::Serialize, ToSchema)]
+#[derive(Debug, serde::Serialize, ToSchema, sqlx::FromRow)]
 pub struct ThreadListItem {
     pub id: Uuid,
     pub title: String,
@@ -25,6 +29,8 @@ pub struct ThreadListItem {
     pub author_id: Uuid,
     pub author_username: String,
     pub comment_count: i64,
+    /// 高評価数 - 低評価数
+    pub score: i64,
 }
 
 #[derive(serde::Deserialize)]
@@ -38,7 +44,8 @@ pub struct PathParams {
     path = "/api/users/{user_id}/threads",
     params(
         ("user_id" = Uuid, Path, description = "ユーザーID"),
-        PaginationParams
+        Pagination<UserListings>,
+        UserThreadsQuery
     ),
     responses(
         (status = 200, description = "スレッド一覧の取得に成功", body = Vec<ThreadListItem>),
@@ -49,25 +56,24 @@ pub struct PathParams {
 pub async fn get_user_threads(
     State(pool): State<PgPool>,
     Path(PathParams { user_id }): Path<PathParams>,
-    Query(params): Query<PaginationParams>,
+    pagination: Pagination<UserListings>,
+    Query(params): Query<UserThreadsQuery>,
 ) -> Result<Json<Vec<ThreadListItem>>, AppError> {
-    // デフォルト値の設定
-    let limit = params.limit.unwrap_or(10);
-    let offset = params.offset.unwrap_or(0);
+    let sort = params.sort.unwrap_or_default();
 
     // ユーザーIDに基づいてスレッドを取得
-    let threads = sqlx::query_as!(
-        ThreadListItem,
+    let threads = sqlx::query_as::<_, ThreadListItem>(
         r#"
         SELECT
             t.id,
             t.title,
             t.content,
-            t.created_at as "created_at!",
-            t.updated_at as "updated_at!",
-            t.user_id as "author_id!",
-            u.username as "author_username!",
-            COALESCE(COUNT(c.id), 0)::bigint as "comment_count!"
+            t.created_at,
+            t.updated_at,
+            t.user_id AS author_id,
+            u.username AS author_username,
+            COALESCE(COUNT(c.id), 0)::bigint AS comment_count,
+            (t.upvote_count - t.downvote_count)::bigint AS score
         FROM
             threads t
         JOIN
@@ -79,14 +85,17 @@ pub async fn get_user_threads(
         GROUP BY
             t.id, u.username
         ORDER BY
+            CASE WHEN $4 = 'top' THEN t.upvote_count - t.downvote_count END DESC,
+            CASE WHEN $4 = 'comments' THEN COUNT(c.id) END DESC,
             t.created_at DESC
         LIMIT $2
         OFFSET $3
         "#,
-        user_id,
-        limit as i64,
-        offset as i64
     )
+    .bind(user_id)
+    .bind(pagination.limit as i64)
+    .bind(pagination.offset)
+    .bind(sort.as_str())
     .fetch_all(&pool)
     .await?;
 
@@ -96,10 +105,18 @@ pub async fn get_user_threads(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::{create_test_thread, seed_test_user};
+    use crate::test_utils::{create_test_comment, create_test_thread, seed_test_user, test_config};
     use axum::extract::{Path, Query, State};
     use sqlx::PgPool;
 
+    fn page_of(
+        page: Option<u32>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Pagination<UserListings> {
+        Pagination::new(page, limit, offset, &test_config().content_limits)
+    }
+
     #[sqlx::test]
     async fn test_get_user_threads_success(pool: PgPool) -> Result<(), AppError> {
         // ユーザーが作成したスレッドの一覧を正常に取得できるかテスト
@@ -130,10 +147,8 @@ mod tests {
         let result = get_user_threads(
             State(pool),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(10),
-                offset: Some(0),
-            }),
+            page_of(None, Some(10), Some(0)),
+            Query(UserThreadsQuery { sort: None }),
         )
         .await?;
 
@@ -169,10 +184,8 @@ mod tests {
         let result1 = get_user_threads(
             State(pool.clone()),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(2),
-                offset: Some(0),
-            }),
+            page_of(None, Some(2), Some(0)),
+            Query(UserThreadsQuery { sort: None }),
         )
         .await?;
 
@@ -180,10 +193,8 @@ mod tests {
         let result2 = get_user_threads(
             State(pool.clone()),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(2),
-                offset: Some(2),
-            }),
+            page_of(None, Some(2), Some(2)),
+            Query(UserThreadsQuery { sort: None }),
         )
         .await?;
 
@@ -205,6 +216,100 @@ mod tests {
         Ok(())
     }
 
+    // 指定した並び順でタイトルの一覧を取得する
+    async fn titles_sorted_by(pool: &PgPool, user_id: Uuid, sort: UserThreadSort) -> Vec<String> {
+        let result = get_user_threads(
+            State(pool.clone()),
+            Path(PathParams { user_id }),
+            page_of(None, Some(10), Some(0)),
+            Query(UserThreadsQuery { sort: Some(sort) }),
+        )
+        .await
+        .unwrap();
+
+        result.0.into_iter().map(|t| t.title).collect()
+    }
+
+    async fn vote(pool: &PgPool, user_id: Uuid, thread_id: Uuid, vote_type: &str) {
+        sqlx::query("INSERT INTO votes (user_id, thread_id, vote_type) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(thread_id)
+            .bind(vote_type)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_get_user_threads_sort(pool: PgPool) {
+        // 新しい順・スコア順・コメント数順で並び替えられることを確認
+        let user_id = seed_test_user(&pool, "sort_test").await;
+        let voter1 = seed_test_user(&pool, "sort_voter1").await;
+        let voter2 = seed_test_user(&pool, "sort_voter2").await;
+
+        let oldest = create_test_thread(&pool, user_id, "oldest", "content").await;
+        let middle = create_test_thread(&pool, user_id, "middle", "content").await;
+        let newest = create_test_thread(&pool, user_id, "newest", "content").await;
+        for (thread_id, minutes) in [(oldest, 30), (middle, 20), (newest, 10)] {
+            sqlx::query(
+                "UPDATE threads SET created_at = NOW() - make_interval(mins => $2) WHERE id = $1",
+            )
+            .bind(thread_id)
+            .bind(minutes)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // スコア: oldest = 2, middle = -1, newest = 0
+        vote(&pool, voter1, oldest, "upvote").await;
+        vote(&pool, voter2, oldest, "upvote").await;
+        vote(&pool, voter1, middle, "downvote").await;
+
+        // コメント数: middle = 2, newest = 1, oldest = 0
+        create_test_comment(&pool, voter1, middle, "comment", None).await;
+        create_test_comment(&pool, voter2, middle, "comment", None).await;
+        create_test_comment(&pool, voter1, newest, "comment", None).await;
+
+        assert_eq!(
+            titles_sorted_by(&pool, user_id, UserThreadSort::New).await,
+            vec!["newest", "middle", "oldest"]
+        );
+        assert_eq!(
+            titles_sorted_by(&pool, user_id, UserThreadSort::Top).await,
+            vec!["oldest", "newest", "middle"]
+        );
+        assert_eq!(
+            titles_sorted_by(&pool, user_id, UserThreadSort::Comments).await,
+            vec!["middle", "newest", "oldest"]
+        );
+
+        // スコアはスレッド一覧と同じ集計値
+        let result = get_user_threads(
+            State(pool.clone()),
+            Path(PathParams { user_id }),
+            page_of(None, None, None),
+            Query(UserThreadsQuery {
+                sort: Some(UserThreadSort::Top),
+            }),
+        )
+        .await
+        .unwrap();
+        let scores: Vec<i64> = result.0.iter().map(|t| t.score).collect();
+        assert_eq!(scores, vec![2, 0, -1]);
+    }
+
+    #[test]
+    fn test_get_user_threads_invalid_sort() {
+        // 未知の並び順はクエリの解析時に拒否されることを確認
+        let uri: axum::http::Uri = "/api/users/x/threads?sort=random".parse().unwrap();
+        assert!(Query::<UserThreadsQuery>::try_from_uri(&uri).is_err());
+
+        let uri: axum::http::Uri = "/api/users/x/threads?sort=comments".parse().unwrap();
+        let Query(params) = Query::<UserThreadsQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(params.sort, Some(UserThreadSort::Comments));
+    }
+
     #[sqlx::test]
     async fn test_get_user_threads_empty(pool: PgPool) -> Result<(), AppError> {
         // ユーザーがスレッドを作成していない場合、空の配列が返されることをテスト
@@ -214,10 +319,8 @@ mod tests {
         let result = get_user_threads(
             State(pool),
             Path(PathParams { user_id }),
-            Query(PaginationParams {
-                limit: Some(10),
-                offset: Some(0),
-            }),
+            page_of(None, Some(10), Some(0)),
+            Query(UserThreadsQuery { sort: None }),
         )
         .await?;
 
@@ -5,13 +5,15 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::sync::Arc;
 use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
     models::{common::ErrorResponse, User},
-    utils::email_sender,
+    utils::{email_change, email_sender, token_generator::TokenGenerator},
 };
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -25,23 +27,29 @@ pub struct UpdateEmailResponse {
     message: String,
 }
 
+/// メールアドレスの変更をリクエストする
+///
+/// 新しいアドレスに確認リンクを送り、`POST /api/users/me/email/confirm/{token}` で確認されるまでは
+/// 現在のアドレスを使い続けます。現在のアドレスには、72時間有効な取り消しリンク付きのお知らせを送ります。
 #[utoipa::path(
     put,
     path = "/api/users/me/email",
     request_body = UpdateEmailRequest,
     responses(
-        (status = 200, description = "Email update verification sent", body = UpdateEmailResponse),
+        (status = 200, description = "Confirmation sent to the new address and a notice with a revert link sent to the current address", body = UpdateEmailResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 409, description = "Email already exists", body = ErrorResponse)
     ),
     tag = "users",
     security(
-        ("jwt_auth" = [])
+        ("bearer_auth" = [])
     )
 )]
 pub async fn update_email(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    State(generator): State<Arc<dyn TokenGenerator>>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<UpdateEmailRequest>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -56,53 +64,44 @@ pub async fn update_email(
     }
 
     // Check if email is taken by another user
-    let existing_user =
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE email = $1 AND id != $2")
-            .bind(&payload.email)
-            .bind(current_user.id)
-            .fetch_one(&pool)
-            .await?;
+    let existing_user = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM users WHERE LOWER(email) = LOWER($1) AND id != $2",
+    )
+    .bind(&payload.email)
+    .bind(current_user.id)
+    .fetch_one(&pool)
+    .await?;
 
     if existing_user > 0 {
         return Err(AppError::Conflict("Email already exists".to_string()));
     }
 
-    // Start transaction to update email and create verification token
+    // 変更の保留と確認・お知らせメールの追加は同じトランザクションで行う
     let mut tx = pool.begin().await?;
 
-    // Update user email and reset verification status
-    let updated_user = sqlx::query_as::<_, User>(
-        r#"
-        UPDATE users 
-        SET 
-            email = $2,
-            email_verified = false,
-            email_verified_at = NULL,
-            updated_at = NOW()
-        WHERE id = $1
-        RETURNING *
-        "#,
+    let tokens = email_change::request_change(
+        &config,
+        generator.as_ref(),
+        &current_user,
+        &payload.email,
+        &mut tx,
+    )
+    .await?;
+    email_sender::queue_email_change_emails(
+        &config,
+        &current_user,
+        &payload.email,
+        &tokens,
+        &mut tx,
     )
-    .bind(current_user.id)
-    .bind(&payload.email)
-    .fetch_one(&mut *tx)
     .await?;
 
-    // Generate verification token and prepare for email sending
-    let verification_token = email_sender::start_verification_flow(&updated_user, &mut tx).await?;
-
-    // Commit transaction
     tx.commit().await?;
 
-    // Send verification email asynchronously
-    tokio::spawn(async move {
-        let _ = email_sender::send_verification_email(&updated_user, &verification_token).await;
-    });
-
     Ok((
         StatusCode::OK,
         Json(UpdateEmailResponse {
-            message: "Email updated. Verification email has been sent to your new email address."
+            message: "A confirmation link has been sent to your new email address. Your current email stays active until the change is confirmed."
                 .to_string(),
         }),
     ))
@@ -113,7 +112,12 @@ mod tests {
     use super::*;
     use axum::extract::State;
 
-    use crate::test_utils::seed_test_user;
+    use crate::{
+        test_utils::{
+            fakes::SeededTokenGenerator, seed_test_user, test_config, test_tokens, TEST_TOKEN_SEED,
+        },
+        utils::token_hash::hash_refresh_token,
+    };
 
     #[sqlx::test]
     async fn test_update_email_success(pool: PgPool) {
@@ -133,7 +137,14 @@ mod tests {
         };
 
         // APIを実行
-        let result = update_email(State(pool.clone()), Extension(user), Json(request)).await;
+        let result = update_email(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         // 結果を確認
         if let Err(ref e) = result {
@@ -148,11 +159,47 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(updated_user.email, "test_new@example.com".to_string());
-        assert!(!updated_user.email_verified);
-        assert!(updated_user.email_verified_at.is_none());
-        assert!(updated_user.verification_token.is_some());
-        assert!(updated_user.verification_token_expires_at.is_some());
+        // 確認されるまでは現在のメールアドレスのまま
+        assert_eq!(updated_user.email, "test_email_update_test@example.com");
+        let pending_email = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT pending_email FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending_email.as_deref(), Some("test_new@example.com"));
+
+        // 新しいアドレスに確認メール、現在のアドレスにお知らせが送信待ちになっていることを確認
+        let queued = sqlx::query_as::<_, (String, String)>(
+            "SELECT to_email, html_body FROM email_outbox WHERE status = 'pending' ORDER BY to_email",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let recipients: Vec<&str> = queued.iter().map(|(to, _)| to.as_str()).collect();
+        assert_eq!(
+            recipients,
+            vec!["test_email_update_test@example.com", "test_new@example.com"]
+        );
+
+        // 同じシードで同じ順（確認・取り消し）に生成したトークンがメールに記載され、ハッシュ値が保存されている
+        let expected = SeededTokenGenerator::new(TEST_TOKEN_SEED);
+        let confirm_token = expected.secure_token();
+        let revert_token = expected.secure_token();
+        assert!(queued[0].1.contains(&revert_token));
+        assert!(!queued[0].1.contains(&confirm_token));
+        assert!(queued[1].1.contains(&confirm_token));
+
+        let (confirm_hash, revert_hash) = sqlx::query_as::<_, (String, String)>(
+            "SELECT confirm_token_hash, revert_token_hash FROM email_change_requests WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(confirm_hash, hash_refresh_token(&confirm_token));
+        assert_eq!(revert_hash, hash_refresh_token(&revert_token));
     }
 
     #[sqlx::test]
@@ -173,7 +220,14 @@ mod tests {
         };
 
         // APIを実行
-        let result = update_email(State(pool.clone()), Extension(user), Json(request)).await;
+        let result = update_email(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            State(test_tokens()),
+            Extension(user),
+            Json(request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err());
@@ -2,18 +2,26 @@ use axum::{
     extract::{Extension, State},
     response::Json,
 };
+use chrono::Utc;
 use sqlx::PgPool;
+use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     error::AppError,
     models::{
         common::ErrorResponse,
         users::{UpdateProfileRequest, UserResponse},
         User,
     },
+    utils::{db_error, username_history},
 };
 
+/// プロフィールを更新
+///
+/// 指定した項目のみ更新します。`bio`・`website_url` は空文字列を送ると削除します。
+/// ユーザー名は14日に1回まで変更でき、他のユーザーが30日以内に使っていたユーザー名には変更できません。
 #[utoipa::path(
     put,
     path = "/api/users/me",
@@ -22,7 +30,8 @@ use crate::{
         (status = 200, description = "Profile updated successfully", body = UserResponse),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
-        (status = 409, description = "Username already exists", body = ErrorResponse)
+        (status = 409, description = "Username already exists or was recently used by another user", body = ErrorResponse),
+        (status = 429, description = "Username was changed within the last 14 days", body = ErrorResponse)
     ),
     tag = "users",
     security(
@@ -31,16 +40,24 @@ use crate::{
 )]
 pub async fn update_profile(
     State(pool): State<PgPool>,
+    State(config): State<Arc<Config>>,
     Extension(current_user): Extension<User>,
     Json(payload): Json<UpdateProfileRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
     // Validate input
     payload.validate()?;
 
+    let now = Utc::now();
+    // 現在と同じユーザー名の指定は変更として扱わない
+    let new_username = payload
+        .username
+        .as_ref()
+        .filter(|username| **username != current_user.username);
+
     // Check if username is taken by another user
-    if let Some(ref username) = payload.username {
+    if let Some(username) = new_username {
         let existing_user = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM users WHERE username = $1 AND id != $2",
+            "SELECT COUNT(*) FROM users WHERE LOWER(username) = LOWER($1) AND id != $2",
         )
         .bind(username)
         .bind(current_user.id)
@@ -50,14 +67,28 @@ pub async fn update_profile(
         if existing_user > 0 {
             return Err(AppError::Conflict("Username already exists".to_string()));
         }
+
+        // 他のユーザーが最近まで使っていたユーザー名は、なりすましを防ぐため使えない
+        if username_history::is_reserved(&pool, username, Some(current_user.id), now).await? {
+            return Err(AppError::Conflict(
+                "Username was recently used by another user".to_string(),
+            ));
+        }
     }
 
-    if payload.username.is_none() && payload.display_name.is_none() && payload.avatar_url.is_none()
+    if payload.username.is_none()
+        && payload.display_name.is_none()
+        && payload.avatar_url.is_none()
+        && payload.bio.is_none()
+        && payload.website_url.is_none()
     {
         return Err(AppError::BadRequest("No fields to update".to_string()));
     }
 
+    let mut tx = pool.begin().await?;
+
     // Update user with simplified query
+    // bio・website_url は省略時は変更せず、空文字列の場合はNULLにする
     let updated_user = sqlx::query_as::<_, User>(
         r#"
         UPDATE users 
@@ -65,6 +96,8 @@ pub async fn update_profile(
             username = COALESCE($2, username),
             display_name = COALESCE($3, display_name),
             avatar_url = COALESCE($4, avatar_url),
+            bio = CASE WHEN $5::text IS NULL THEN bio ELSE NULLIF($5, '') END,
+            website_url = CASE WHEN $6::text IS NULL THEN website_url ELSE NULLIF($6, '') END,
             updated_at = NOW()
         WHERE id = $1
         RETURNING *
@@ -74,18 +107,40 @@ pub async fn update_profile(
     .bind(payload.username.as_ref())
     .bind(payload.display_name.as_ref())
     .bind(payload.avatar_url.as_ref())
-    .fetch_one(&pool)
-    .await?;
+    .bind(payload.bio.as_ref())
+    .bind(payload.website_url.as_ref())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    if new_username.is_some() {
+        // 更新で行をロックした後に確認し、同時リクエストで間隔をすり抜けないようにする
+        if let Some(seconds) =
+            username_history::change_wait_seconds(&mut *tx, current_user.id, now).await?
+        {
+            return Err(AppError::TooManyRequests(seconds));
+        }
+        username_history::record(&mut tx, current_user.id, &current_user.username, now).await?;
+    }
 
-    Ok(Json(UserResponse::from(updated_user)))
+    tx.commit().await?;
+
+    Ok(Json(UserResponse::new(updated_user, &config.api_url)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::extract::State;
+    use axum::{extract::State, http::StatusCode, response::IntoResponse};
 
-    use crate::test_utils::seed_test_user;
+    use crate::{
+        handlers::users::detail::get_user_by_username,
+        test_utils::{
+            create_test_user, reserve_test_username, seed_test_user, test_config,
+            TEST_RESERVED_USERNAME,
+        },
+    };
+    use axum::extract::Path;
 
     #[sqlx::test]
     async fn test_update_profile_success(pool: PgPool) {
@@ -104,11 +159,18 @@ mod tests {
             username: Some("updated_username".to_string()),
             display_name: Some("Updated Display Name".to_string()),
             avatar_url: Some("https://example.com/avatar.png".to_string()),
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // レスポンスを検証
         assert!(result.is_ok(), "update_profile should return Ok");
@@ -150,11 +212,18 @@ mod tests {
             username: Some(existing_username),
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(
@@ -186,11 +255,18 @@ mod tests {
             username: None,
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for empty update");
@@ -219,11 +295,18 @@ mod tests {
             username: None,
             display_name: None,
             avatar_url: Some("invalid-url".to_string()),
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for invalid URL");
@@ -252,11 +335,18 @@ mod tests {
             username: Some("abc".to_string()), // 3文字のユーザー名（最小は4文字必要）
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for invalid username");
@@ -268,6 +358,48 @@ mod tests {
         }
     }
 
+    #[sqlx::test]
+    async fn test_update_profile_reserved_username(pool: PgPool) {
+        // 登録時と同じく、ルートと衝突する語・大文字を含む語・環境変数で追加した語には変更できないことを確認
+        reserve_test_username();
+        let user = create_test_user(&pool, true).await;
+
+        for username in ["me", "Settings", TEST_RESERVED_USERNAME] {
+            let update_request = UpdateProfileRequest {
+                username: Some(username.to_string()),
+                display_name: None,
+                avatar_url: None,
+                bio: None,
+                website_url: None,
+            };
+
+            let result = update_profile(
+                State(pool.clone()),
+                State(Arc::new(test_config())),
+                Extension(user.clone()),
+                Json(update_request),
+            )
+            .await;
+
+            let Err(AppError::Validation(errors)) = result else {
+                panic!("Expected Validation error for {}", username);
+            };
+            assert_eq!(
+                errors.field_errors()["username"][0].code,
+                "username_reserved",
+                "{} should be rejected as reserved",
+                username
+            );
+        }
+
+        let current = sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(current, user.username);
+    }
+
     #[sqlx::test]
     async fn test_update_profile_username_too_long(pool: PgPool) {
         // テストユーザーを作成
@@ -286,11 +418,18 @@ mod tests {
             username: Some(long_username),
             display_name: None,
             avatar_url: None,
+            bio: None,
+            website_url: None,
         };
 
         // ハンドラを直接呼び出し
-        let result =
-            update_profile(State(pool.clone()), Extension(user), Json(update_request)).await;
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(update_request),
+        )
+        .await;
 
         // エラーが返されることを確認
         assert!(result.is_err(), "Should return error for username too long");
@@ -301,4 +440,278 @@ mod tests {
             _ => panic!("Expected Validation error"),
         }
     }
+
+    #[sqlx::test]
+    async fn test_update_profile_concurrent_same_username(pool: PgPool) {
+        // 2人のユーザーが同時に同じユーザー名へ変更した場合、片方は500ではなく409になることを確認
+        let first_id = seed_test_user(&pool, "profile_race_1").await;
+        let second_id = seed_test_user(&pool, "profile_race_2").await;
+
+        let fetch = |id| {
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+        };
+        let first_user = fetch(first_id).await.expect("Failed to get test user");
+        let second_user = fetch(second_id).await.expect("Failed to get test user");
+
+        let request = || UpdateProfileRequest {
+            username: Some("race_username".to_string()),
+            display_name: None,
+            avatar_url: None,
+            bio: None,
+            website_url: None,
+        };
+
+        let (first, second) = tokio::join!(
+            update_profile(
+                State(pool.clone()),
+                State(Arc::new(test_config())),
+                Extension(first_user),
+                Json(request())
+            ),
+            update_profile(
+                State(pool.clone()),
+                State(Arc::new(test_config())),
+                Extension(second_user),
+                Json(request())
+            ),
+        );
+
+        let err = match (first, second) {
+            (Ok(_), Err(err)) | (Err(err), Ok(_)) => err,
+            other => panic!("Expected exactly one failure, got {:?}", other),
+        };
+        assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+    }
+
+    fn profile_request(bio: Option<&str>, website_url: Option<&str>) -> UpdateProfileRequest {
+        UpdateProfileRequest {
+            username: None,
+            display_name: None,
+            avatar_url: None,
+            bio: bio.map(str::to_string),
+            website_url: website_url.map(str::to_string),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile_bio_and_website(pool: PgPool) {
+        // 自己紹介とWebサイトを設定できることを確認
+        let user = create_test_user(&pool, true).await;
+
+        // 自己紹介とウェブサイトを設定すると、自己紹介はHTMLにも変換される
+        let Json(updated) = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(
+                Some("**Rust** が好きです"),
+                Some("https://example.com"),
+            )),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.bio.as_deref(), Some("**Rust** が好きです"));
+        assert!(updated
+            .bio_html
+            .as_deref()
+            .unwrap()
+            .contains("<strong>Rust</strong>"));
+        assert_eq!(updated.website_url.as_deref(), Some("https://example.com"));
+
+        // 省略した項目は変わらない
+        let Json(updated) = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(None, Some("http://example.org"))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.bio.as_deref(), Some("**Rust** が好きです"));
+        assert_eq!(updated.website_url.as_deref(), Some("http://example.org"));
+
+        // 公開プロフィールにも含まれる
+        let Json(public) = get_user_by_username(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Path(user.username.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(public.bio.as_deref(), Some("**Rust** が好きです"));
+        assert_eq!(public.website_url.as_deref(), Some("http://example.org"));
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile_clear_bio_and_website(pool: PgPool) {
+        // 自己紹介とWebサイトを空にすると未設定に戻ることを確認
+        let user = create_test_user(&pool, true).await;
+        let _ = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(Some("Hello"), Some("https://example.com"))),
+        )
+        .await
+        .unwrap();
+
+        // 空文字列を送ると削除する
+        let Json(cleared) = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(profile_request(Some(""), Some(""))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cleared.bio, None);
+        assert_eq!(cleared.bio_html, None);
+        assert_eq!(cleared.website_url, None);
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile_bio_too_long(pool: PgPool) {
+        // 長すぎる自己紹介はバリデーションエラーになることを確認
+        let user = create_test_user(&pool, true).await;
+
+        // 500文字までは受け付ける（マルチバイト文字も1文字として数える）
+        let at_limit = "あ".repeat(500);
+        let _ = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(Some(&at_limit), None)),
+        )
+        .await
+        .unwrap();
+
+        let too_long = "あ".repeat(501);
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user),
+            Json(profile_request(Some(&too_long), None)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile_javascript_website_rejected(pool: PgPool) {
+        // javascript: のWebサイトは拒否されることを確認
+        let user = create_test_user(&pool, true).await;
+
+        let result = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(None, Some("javascript:alert(1)"))),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        // 保存されていない
+        let website_url =
+            sqlx::query_scalar::<_, Option<String>>("SELECT website_url FROM users WHERE id = $1")
+                .bind(user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(website_url, None);
+    }
+
+    fn rename_request(username: &str) -> UpdateProfileRequest {
+        UpdateProfileRequest {
+            username: Some(username.to_string()),
+            display_name: None,
+            avatar_url: None,
+            bio: None,
+            website_url: None,
+        }
+    }
+
+    async fn rename(pool: &PgPool, user: &User, username: &str) -> Result<UserResponse, AppError> {
+        let Json(updated) = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(rename_request(username)),
+        )
+        .await?;
+        Ok(updated)
+    }
+
+    /// ユーザー名の変更履歴の日時を `days` 日前にずらす
+    async fn age_username_history(pool: &PgPool, user_id: uuid::Uuid, days: i64) {
+        sqlx::query(
+            "UPDATE username_history SET changed_at = changed_at - make_interval(days => $2) WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(days as i32)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_変更前のユーザー名は30日間他のユーザーが使えない(
+        pool: PgPool,
+    ) {
+        // 変更前のユーザー名は30日間他のユーザーが使えないことを確認
+        let user = create_test_user(&pool, true).await;
+        let squatter = create_test_user(&pool, true).await;
+        let old_username = user.username.clone();
+        rename(&pool, &user, "new_handle").await.unwrap();
+
+        let result = rename(&pool, &squatter, &old_username).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+
+        // 30日を過ぎると使える
+        age_username_history(&pool, user.id, 31).await;
+        let updated = rename(&pool, &squatter, &old_username).await.unwrap();
+        assert_eq!(updated.username, old_username);
+    }
+
+    #[sqlx::test]
+    async fn test_ユーザー名は14日に1回まで変更できる(pool: PgPool) {
+        // ユーザー名は14日に1回までしか変更できないことを確認
+        let user = create_test_user(&pool, true).await;
+        let original = user.username.clone();
+        let user = User {
+            username: rename(&pool, &user, "first_rename").await.unwrap().username,
+            ..user
+        };
+
+        let result = rename(&pool, &user, "second_rename").await;
+        let Err(AppError::TooManyRequests(seconds)) = result else {
+            panic!("Expected TooManyRequests, got {:?}", result);
+        };
+        assert!(seconds > 13 * 24 * 60 * 60);
+        // 変更は元に戻り、履歴も増えない
+        let (username, history) = sqlx::query_as::<_, (String, i64)>(
+            "SELECT username, (SELECT COUNT(*) FROM username_history WHERE user_id = $1) FROM users WHERE id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((username.as_str(), history), ("first_rename", 1));
+
+        // ユーザー名以外の項目は変更できる
+        let _ = update_profile(
+            State(pool.clone()),
+            State(Arc::new(test_config())),
+            Extension(user.clone()),
+            Json(profile_request(Some("bio"), None)),
+        )
+        .await
+        .unwrap();
+
+        // 14日を過ぎると変更でき、自分が使っていたユーザー名にも戻せる
+        age_username_history(&pool, user.id, 15).await;
+        let updated = rename(&pool, &user, &original).await.unwrap();
+        assert_eq!(updated.username, original);
+    }
 }
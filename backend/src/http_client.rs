@@ -0,0 +1,202 @@
+// 外部サービス（Mailgun など）へのリクエストに使うHTTPクライアント
+//
+// 接続を使い回せるよう起動時に1つだけ作成して AppState で共有します。
+// タイムアウト・User-Agent・プロキシの設定はここでまとめて行い、呼び出し側では作成しません。
+use reqwest::{Client, IntoUrl, Method, Proxy, RequestBuilder};
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// 外部サービスに送る User-Agent（サービス名/バージョン）
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// 共有するHTTPクライアント
+///
+/// 内部の `reqwest::Client` は接続プールを共有しているため、`clone` しても同じプールを使います。
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+}
+
+impl HttpClient {
+    /// HTTP_TIMEOUT_SECONDS・HTTP_CONNECT_TIMEOUT_SECONDS・HTTP_PROXY_URL の設定で作成する
+    pub fn new(config: &Config) -> Result<Self, reqwest::Error> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.http_timeout_seconds))
+            .connect_timeout(Duration::from_secs(config.http_connect_timeout_seconds));
+        if let Some(proxy) = &config.http_proxy_url {
+            builder = builder.proxy(Proxy::all(proxy.expose())?);
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+        })
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// このリクエストだけ既定とは異なるタイムアウトを使う
+    ///
+    /// 応答の遅いサービスに長めの、画面の表示を待たせる呼び出しに短めのタイムアウトを指定するために使います。
+    pub fn with_timeout(&self, timeout: Duration) -> TimedClient<'_> {
+        TimedClient {
+            http: self,
+            timeout,
+        }
+    }
+}
+
+/// タイムアウトを上書きしてリクエストを組み立てる（`HttpClient::with_timeout`）
+pub struct TimedClient<'a> {
+    http: &'a HttpClient,
+    timeout: Duration,
+}
+
+impl TimedClient<'_> {
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.http.request(method, url).timeout(self.timeout)
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use axum::{http::HeaderMap, routing::get, Router};
+
+    /// User-Agent を返すエンドポイントと、応答に1秒かかるエンドポイントを持つサーバーを起動する
+    async fn spawn_server() -> String {
+        let app = Router::new()
+            .route(
+                "/user-agent",
+                get(|headers: HeaderMap| async move {
+                    headers["user-agent"].to_str().unwrap().to_string()
+                }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    "slow"
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn client(timeout_seconds: u64) -> HttpClient {
+        HttpClient::new(&Config {
+            http_timeout_seconds: timeout_seconds,
+            ..test_config()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_サービス名とバージョンのuser_agentを送る() {
+        // サービス名とバージョンのUser-Agentを送ることを確認
+        let base_url = spawn_server().await;
+
+        let user_agent = client(10)
+            .get(format!("{}/user-agent", base_url))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(user_agent, USER_AGENT);
+        assert!(user_agent.starts_with("minwada-api/"));
+    }
+
+    #[tokio::test]
+    async fn test_リクエストごとにタイムアウトを上書きできる() {
+        // リクエストごとにタイムアウトを上書きできることを確認
+        let base_url = spawn_server().await;
+        let http = client(10);
+
+        let err = http
+            .with_timeout(Duration::from_millis(100))
+            .get(format!("{}/slow", base_url))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout());
+
+        // 上書きしないリクエストは既定のタイムアウトを使う
+        let body = http
+            .get(format!("{}/slow", base_url))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "slow");
+    }
+
+    #[tokio::test]
+    async fn test_既定のタイムアウトを超えるとエラーになる() {
+        // 既定のタイムアウトを超えるとエラーになることを確認
+        let base_url = spawn_server().await;
+        let http = HttpClient {
+            client: Client::builder()
+                .user_agent(USER_AGENT)
+                .timeout(Duration::from_millis(100))
+                .build()
+                .unwrap(),
+        };
+        let err = http
+            .get(format!("{}/slow", base_url))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout());
+
+        // 既定より長いタイムアウトで上書きすると、そのリクエストだけ長く待つ
+        let body = http
+            .with_timeout(Duration::from_secs(5))
+            .get(format!("{}/slow", base_url))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "slow");
+    }
+
+    #[test]
+    fn test_不正なプロキシは作成時にエラーになる() {
+        // 不正なプロキシは作成時にエラーになることを確認
+        let result = HttpClient::new(&Config {
+            http_proxy_url: Some("not a url".to_string().into()),
+            ..test_config()
+        });
+
+        assert!(result.is_err());
+    }
+}
@@ -1,73 +1,48 @@
 mod auth;
 mod config;
+mod cors;
+mod db;
 mod email;
 mod error;
+mod events;
 mod handlers;
+mod http_client;
 mod middleware;
 mod models;
+mod pagination;
+mod panic;
+mod rate_limit;
+mod repositories;
 mod routes;
+mod side_effects;
+mod state;
+mod telemetry;
 mod test_utils;
 mod utils;
 mod validations;
 
 use std::net::SocketAddr;
 
-use axum::{
-    http::{
-        header::{AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method,
-    },
-    Router,
-};
+use axum::Router;
 use tower::ServiceBuilder;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::{info, Level};
-use utoipa::OpenApi;
-use utoipa_axum::router::OpenApiRouter;
+use tower_http::trace::TraceLayer;
+use tracing::info;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::{config::Config, routes::create_routes};
+use crate::{
+    config::{Config, DbConnectMode},
+    state::AppState,
+};
 
+/// スキーマ・タグ・認証方式など、OpenAPIドキュメントの共通の定義
+///
+/// パスは `routes.rs` でハンドラーをルーターに登録すると追加されるため、ここには書きません。
 #[derive(OpenApi)]
 #[openapi(
-    paths(
-        // Auth endpoints
-        handlers::auth::register::register,
-        handlers::auth::login::login,
-        handlers::auth::logout::logout,
-        handlers::auth::refresh_token::refresh_token,
-        handlers::auth::google_auth::google_auth,
-        handlers::auth::google_callback::google_callback,
-        handlers::auth::change_password::change_password,
-        handlers::auth::verify_email::verify_email,
-        handlers::auth::verify_email::resend_verification,
-        handlers::auth::request_password_reset::request_password_reset,
-        handlers::auth::reset_password::reset_password,
-
-        // Thread endpoints
-        handlers::threads::list::get_threads,
-        handlers::threads::create::create_thread,
-        handlers::threads::detail::get_thread,
-        handlers::threads::update::update_thread,
-        handlers::threads::delete::delete_thread,
-        handlers::threads::vote::vote_thread,
-        handlers::threads::ogp::get_thread_ogp_image,
-
-        // Comment endpoints
-        handlers::comments::list::get_comments,
-        handlers::comments::create::create_comment,
-        handlers::comments::update::update_comment,
-        handlers::comments::delete::delete_comment,
-
-        // User endpoints
-        handlers::users::current_user::get_current_user,
-        handlers::users::update_profile::update_profile,
-        handlers::users::update_email::update_email,
-        handlers::users::detail::get_user_by_username,
-        handlers::users::delete::delete_user,
-        handlers::users::threads::get_user_threads,
-        handlers::users::comments::get_user_comments,
-    ),
     components(
         schemas(
             // Auth DTOs
@@ -77,8 +52,11 @@ use crate::{config::Config, routes::create_routes};
             models::auth::AuthResponse,
             models::auth::RefreshTokenRequest,
             models::auth::RequestPasswordResetRequest,
+            models::auth::MagicLinkRequest,
             models::auth::ResetPasswordRequest,
             models::auth::MessageResponse,
+            models::auth::SessionResponse,
+            models::auth::SessionListResponse,
 
             // Thread DTOs
             models::threads::CreateThreadRequest,
@@ -86,6 +64,19 @@ use crate::{config::Config, routes::create_routes};
             models::threads::ThreadResponse,
             models::threads::ThreadListResponse,
             models::threads::ThreadUser,
+            models::threads::UpdateReadPositionRequest,
+            models::threads::ReadPositionResponse,
+            models::threads::PresenceRequest,
+            models::threads::PresenceResponse,
+            models::threads::ThreadSort,
+            models::threads::TopWindow,
+            models::threads::ThreadFilter,
+            models::threads::ThreadContributor,
+            models::threads::ThreadContributorsResponse,
+            models::threads::ThreadRevision,
+            models::threads::ThreadRevisionListResponse,
+            handlers::threads::vote::VoteRequest,
+            handlers::threads::vote::VoteResponse,
             models::common::PaginatedResponse<models::threads::ThreadResponse>,
 
             // Comment DTOs
@@ -94,11 +85,89 @@ use crate::{config::Config, routes::create_routes};
             models::comments::CommentResponse,
             models::comments::CommentUser,
             models::comments::CommentListResponse,
+            models::comments::CommentPagination,
+            models::comments::CommentSort,
+            models::comments::CommentQuoteResponse,
+            models::comments::CommentRemovalReason,
 
             // User DTOs
             models::users::UserResponse,
             models::users::PublicUserResponse,
+            models::users::FollowUserResponse,
+            models::common::PaginatedResponse<models::users::FollowUserResponse>,
+            models::users::BlockedUserResponse,
+            models::users::BlockListResponse,
+            models::UserRole,
+            models::users::AdminUserResponse,
+            models::users::AdminUserListResponse,
+            models::common::PaginatedResponse<models::users::AdminUserResponse>,
             models::users::UpdateProfileRequest,
+            models::preferences::PreferencesDocument,
+            models::preferences::PreferenceSettings,
+            models::preferences::PortableSavedSearch,
+            models::preferences::PreferencesImportResponse,
+            models::preferences::SkippedEntry,
+            models::preferences::SkippedKind,
+            models::preferences::SkipReason,
+            models::saved_searches::CreateSavedSearchRequest,
+            models::saved_searches::SavedSearchResponse,
+            models::saved_searches::SavedSearchListResponse,
+
+            // Moderation DTOs
+            models::moderation::MuteUserRequest,
+            models::moderation::UserMuteResponse,
+            models::moderation::MuteStatus,
+            models::moderation::UserMutedErrorResponse,
+            models::moderation::BanUserRequest,
+            models::moderation::UserBanStatus,
+            models::moderation::UserBanResponse,
+            models::moderation::UserBannedErrorResponse,
+
+            // Report DTOs
+            models::reports::ReportReason,
+            models::reports::ReportTargetType,
+            models::reports::ReportStatus,
+            models::reports::ReportOutcome,
+            models::reports::CreateReportRequest,
+            models::reports::ResolveReportRequest,
+            models::reports::ReportResponse,
+            models::reports::ReportListResponse,
+            models::common::PaginatedResponse<models::reports::ReportResponse>,
+
+            // Tag DTOs
+            models::tags::RenameTagRequest,
+            models::tags::TagResponse,
+            models::tags::TagMergeResponse,
+            models::tag_rules::TagRuleField,
+            models::tag_rules::CreateTagRuleRequest,
+            models::tag_rules::UpdateTagRuleRequest,
+            models::tag_rules::TagRuleResponse,
+            models::tag_rules::TagRuleListResponse,
+
+            // Audit Log DTOs
+            models::audit_log::AuditLogFormat,
+            models::audit_log::AuditLogResponse,
+            models::audit_log::AuditLogListResponse,
+            models::common::PaginatedResponse<models::audit_log::AuditLogResponse>,
+
+            // Email Outbox DTOs
+            models::email_outbox::EmailOutboxStatus,
+            models::email_outbox::EmailOutboxResponse,
+            models::email_outbox::EmailOutboxListResponse,
+            models::common::PaginatedResponse<models::email_outbox::EmailOutboxResponse>,
+
+            // Meta DTOs
+            config::EmailMode,
+            models::meta::AdminMetaResponse,
+            models::meta::AdminConfigResponse,
+            models::meta::EffectiveSettings,
+
+            // Maintenance DTOs
+            models::maintenance::PurgeTokensResponse,
+
+            // Health DTOs
+            handlers::health::ready::DatabaseStatus,
+            handlers::health::ready::ReadinessResponse,
 
             // Common DTOs
             models::common::ErrorResponse,
@@ -108,76 +177,227 @@ use crate::{config::Config, routes::create_routes};
         (name = "auth", description = "Authentication endpoints"),
         (name = "threads", description = "Thread management"),
         (name = "comments", description = "Comment management"),
-        (name = "users", description = "User management")
+        (name = "users", description = "User management"),
+        (name = "tags", description = "Thread tags"),
+        (name = "admin", description = "Administration and moderation"),
+        (name = "health", description = "Health checks")
     ),
     info(
         title = "minwada internal API",
         version = "1.0.0",
         description = "A Reddit-like discussion platform API built with Rust and axum"
-    )
+    ),
+    modifiers(&SecurityAddon)
 )]
 struct ApiDoc;
 
+/// ハンドラーの `security(("bearer_auth" = []))` が参照する認証方式を登録する
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// ルーターと、設定された件数の既定値と上限を書き込んだOpenAPIドキュメント
+fn build_app(state: AppState) -> (Router, utoipa::openapi::OpenApi) {
+    let content_limits = state.config.content_limits.clone();
+    let (router, mut openapi) = routes::create_routes_with_openapi(state);
+    pagination::PageSizeDocs(&content_limits).modify(&mut openapi);
+    (router, openapi)
+}
+
+/// `--dump-openapi <path>`: OpenAPIドキュメントをファイルに書き出す（フロントエンドのAPIクライアントの生成用）
+fn dump_openapi(config: &Config, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // ルーターを組み立てるだけなので、データベースには接続しない
+    let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy(config.database_url.expose())?;
+    let (_, openapi) = build_app(AppState::new(pool, config.clone()));
+
+    let path = std::path::Path::new(path);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&openapi)?)?;
+    println!("OpenAPI JSON written to {}", path.display());
+    Ok(())
+}
+
+/// `reindex-search`: 既存のスレッドの検索用の列をバッチごとに埋め、検索用のインデックスを作成する
+///
+/// 中断しても再実行すれば未処理のスレッドから再開します。
+async fn reindex_search(pool: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    let total = utils::thread_search::pending_count(pool).await?;
+    println!("Backfilling search vectors for {} threads", total);
+    let processed = utils::thread_search::backfill(
+        pool,
+        utils::thread_search::BACKFILL_BATCH_SIZE,
+        |processed| println!("  {}/{} threads", processed, total),
+    )
+    .await?;
+    println!("Backfilled {} threads", processed);
+
+    println!("Creating search index concurrently");
+    utils::thread_search::create_index_concurrently(pool).await?;
+    println!("Search index is ready");
+    Ok(())
+}
+
+/// `migrate-credentials`: 旧形式のパスワードハッシュをバッチごとに確認し、移行するか次のログイン時に作り直すよう印を付ける
+///
+/// 中断しても再実行すれば未処理の行から再開します。
+async fn migrate_credentials(pool: &sqlx::PgPool) -> Result<(), error::AppError> {
+    let before = utils::credentials::report(pool).await?;
+    println!("Checking {} legacy credentials", before.legacy);
+    let result = utils::credentials::migrate_legacy(
+        pool,
+        utils::credentials::MIGRATE_BATCH_SIZE,
+        |result| {
+            println!(
+                "  {} upgraded, {} marked for rehash",
+                result.upgraded, result.marked
+            )
+        },
+    )
+    .await?;
+    println!(
+        "Upgraded {} credentials and marked {} for rehash on next login",
+        result.upgraded, result.marked
+    );
+    Ok(())
+}
+
+/// `data-migrate`: 登録されているデータの移行を番号の小さい順に、バッチごとに実行する
+///
+/// 中断しても再実行すれば、最後にコミットしたバッチの続きから再開します。
+async fn data_migrate(pool: &sqlx::PgPool) -> Result<(), error::AppError> {
+    let runs = utils::data_migrations::run(
+        pool,
+        utils::data_migrations::MIGRATIONS,
+        utils::data_migrations::BATCH_SIZE,
+        |migration, processed| {
+            println!(
+                "  {} {}: {} rows",
+                migration.version(),
+                migration.name(),
+                processed
+            )
+        },
+    )
+    .await?;
+    for run in runs {
+        if run.already_completed {
+            println!("{} {}: already completed", run.version, run.name);
+        } else {
+            println!("{} {}: processed {} rows", run.version, run.name, run.processed);
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    unsafe { backtrace_on_stack_overflow::enable() };
+    // Load configuration
+    // 設定の誤りはリクエストの処理中ではなく起動時に、すべてまとめて表示する
+    let config = Config::from_env()?;
+    config.validate()?;
 
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(Level::DEBUG)
-        .init();
+    telemetry::init(&config)?;
+    panic::install_hook();
 
-    // Load configuration
-    let config = Config::from_env()?;
+    // シグナルハンドラーを差し替える unsafe な処理のため、明示的に有効にしたデバッグビルドでのみ使う
+    if config.stack_overflow_backtrace {
+        unsafe { backtrace_on_stack_overflow::enable() };
+    }
+    events::set_enabled(config.domain_events_enabled);
+
+    // ドキュメントの書き出しはデータベースに接続せずに実行して終了する
+    if std::env::args().nth(1).as_deref() == Some("--dump-openapi") {
+        let path = std::env::args()
+            .nth(2)
+            .ok_or("Usage: minwada-api --dump-openapi <path>")?;
+        return dump_openapi(&config, &path);
+    }
 
     info!("Starting minwada API server");
-    info!("Database URL: {}", config.database_url);
+    info!("Database: {}", config.database_target());
     info!("Server will run on {}:{}", config.host, config.port);
+    info!("Email mode: {:?}", config.email_mode);
 
     // Database connection
-    let pool = sqlx::PgPool::connect(&config.database_url).await?;
+    let pool = db::connect(&config).await?;
 
     // Run migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    // lazy モードでは起動時に接続しないため、マイグレーションは別途 `sqlx migrate run` で適用する
+    if config.db_connect_mode == DbConnectMode::Eager {
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        info!("Database connected and migrations applied");
+    } else {
+        info!("Database will be connected lazily; skipping migrations on startup");
+    }
 
-    info!("Database connected and migrations applied");
+    // サブコマンドはサーバーを起動せずに実行して終了する
+    if let Some(command) = std::env::args().nth(1) {
+        return match command.as_str() {
+            "reindex-search" => Ok(reindex_search(&pool).await?),
+            "migrate-credentials" => Ok(migrate_credentials(&pool).await?),
+            "data-migrate" => Ok(data_migrate(&pool).await?),
+            _ => Err(format!("Unknown command: {} (available: reindex-search, migrate-credentials, data-migrate, --dump-openapi <path>)", command).into()),
+        };
+    }
 
-    // Write OpenAPI documentation to file
-    let openapi_json = serde_json::to_string_pretty(&ApiDoc::openapi())?;
+    // 保持期間を過ぎた監査ログのアーカイブ
+    utils::audit_log::spawn_retention_job(pool.clone(), &config);
 
-    // Create directory if it doesn't exist
-    let openapi_dir = std::path::Path::new("./static");
-    if !openapi_dir.exists() {
-        std::fs::create_dir_all(openapi_dir)?;
-    }
+    // 保存した検索条件に一致した新しいスレッドの通知
+    utils::saved_searches::spawn_job(pool.clone(), &config);
 
-    // Write the OpenAPI JSON to a file
-    std::fs::write("./static/openapi.json", openapi_json)?;
-    info!("📄 OpenAPI JSON file written to ./static/openapi.json");
+    // 期限切れトークンの削除
+    utils::token_purge::spawn_job(pool.clone(), &config);
 
     // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(config.cors_origin.parse::<HeaderValue>()?)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE])
-        .allow_credentials(true);
-
-    let (router, _api) = OpenApiRouter::with_openapi(ApiDoc::openapi()).split_for_parts();
+    let cors = cors::layer(config.cors_origins.clone());
 
     // Serve static files
     let static_files_service = tower_http::services::ServeDir::new("./static");
     let static_files_router = Router::new().nest_service("/static", static_files_service);
 
     // Build the application router
-    let router = router
-        .merge(create_routes(pool.clone()))
+    let state = AppState::new(pool.clone(), config.clone());
+
+    // 閲覧者のハートビートが途絶えたスレッドへの配信
+    utils::presence::spawn_job(state.presence.clone(), state.thread_events.clone());
+
+    // アウトボックスに積まれたメールの送信・再試行
+    utils::email_outbox::spawn_worker(pool.clone(), state.email.clone(), &config);
+
+    // OpenAPIドキュメントはファイルに書き出さずに配信する（読み取り専用のファイルシステムでも起動できるように）
+    let (api_router, openapi) = build_app(state);
+
+    let router = api_router
         .merge(static_files_router)
-        .merge(SwaggerUi::new("/swagger-ui").url("/static/openapi.json", ApiDoc::openapi()))
+        .merge(SwaggerUi::new("/swagger-ui").url("/api/openapi.json", openapi))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
                 .layer(cors),
         );
+    // 呼び出し元のトレースにつなげるため、トレースを送る場合のみ一番外側でリクエストのスパンを作る
+    let router = if config.otel_exporter_otlp_endpoint.is_some() {
+        router.layer(axum::middleware::from_fn(telemetry::trace_context_middleware))
+    } else {
+        router
+    };
 
     // Server address
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -190,7 +410,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Start the server
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, router.into_make_service()).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
+    telemetry::shutdown();
     Ok(())
 }
@@ -1,17 +1,120 @@
 use axum::body::Body;
-use axum::{extract::State, http::Request, middleware::Next, response::Response};
-use sqlx::PgPool;
+use axum::{
+    extract::{FromRef, FromRequestParts, State},
+    http::{
+        header::{
+            CONTENT_SECURITY_POLICY, REFERRER_POLICY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+        },
+        request::Parts,
+        HeaderMap, HeaderName, HeaderValue, Request,
+    },
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use std::{convert::Infallible, sync::Arc};
+use uuid::Uuid;
 
-use crate::{auth::jwt::verify_jwt_token, config::Config, error::AppError, models::User};
+use crate::{
+    auth::jwt::JwtService,
+    error::AppError,
+    models::{auth::Claims, User, UserRole},
+    repositories::AuthRepo,
+};
+
+/// トークンの検証とユーザーの読み込みに使う状態
+///
+/// `auth_middleware` と `MaybeUser` は、`AppState` からこれを取り出して認証します。
+#[derive(Clone)]
+pub struct Authenticator {
+    pub auth: Arc<dyn AuthRepo>,
+    pub jwt: Arc<JwtService>,
+}
 
 pub async fn auth_middleware(
-    State(pool): State<PgPool>,
+    State(authenticator): State<Authenticator>,
     mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, AppError> {
-    // Authorizationヘッダー取得
-    let headers = request.headers();
+    let (user, claims) = authenticate(&authenticator, request.headers()).await?;
+
+    request.extensions_mut().insert(user);
+    request.extensions_mut().insert(claims);
+
+    Ok(next.run(request).await)
+}
+
+/// 指定した権限以上のユーザーのみ通すミドルウェア
+/// `auth_middleware` の後に `from_fn_with_state(UserRole::Admin, require_role)` のように適用し、
+/// 権限が足りないユーザーには403を返します。権限はトークンではなく、リクエストごとに読み込んだユーザーから判定します
+pub async fn require_role(
+    State(required): State<UserRole>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let user = request
+        .extensions()
+        .get::<User>()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    if user.role < required {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 利用停止中のユーザーによる書き込み（GET・HEADなどの安全なメソッド以外のリクエスト）を拒否するミドルウェア
+/// `auth_middleware` の内側に置き、閲覧は利用停止中でも許可します
+pub async fn reject_banned_writes(
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !request.method().is_safe() {
+        if let Some(ban) = request
+            .extensions()
+            .get::<User>()
+            .and_then(|user| user.active_ban(Utc::now()))
+        {
+            return Err(AppError::UserBanned(ban));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 認証が任意のルート用のエクストラクター
+/// 有効なトークンがあればそのユーザーを、無い・無効な場合（期限切れや退会したユーザーなど）は None を返し、401 にはしません。
+/// `auth_middleware` を通ったルートでは、読み込み済みのユーザーをそのまま使います
+pub struct MaybeUser(pub Option<User>);
+
+impl<S> FromRequestParts<S> for MaybeUser
+where
+    S: Send + Sync,
+    Authenticator: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<User>() {
+            return Ok(Self(Some(user.clone())));
+        }
+
+        let authenticator = Authenticator::from_ref(state);
+        let user = authenticate(&authenticator, &parts.headers)
+            .await
+            .ok()
+            .map(|(user, _)| user);
 
+        Ok(Self(user))
+    }
+}
+
+async fn authenticate(
+    authenticator: &Authenticator,
+    headers: &HeaderMap,
+) -> Result<(User, Claims), AppError> {
+    // Authorizationヘッダー取得
     let auth_header = headers
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
@@ -21,18 +124,341 @@ pub async fn auth_middleware(
         })?;
 
     // トークン検証
-    let config = Config::from_env()?;
-    let claims = verify_jwt_token(auth_header, &config.jwt_secret)?;
+    let claims = authenticator.jwt.decode(auth_header)?;
 
     // ユーザー取得
-    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-        .bind(uuid::Uuid::parse_str(&claims.sub)?)
-        .fetch_optional(&pool)
+    let user = authenticator
+        .auth
+        .find_user(uuid::Uuid::parse_str(&claims.sub)?)
         .await?
         .ok_or_else(|| AppError::Unauthorized("User not found".to_string()))?;
 
-    request.extensions_mut().insert(user);
-    request.extensions_mut().insert(claims);
+    Ok((user, claims))
+}
 
-    Ok(next.run(request).await)
+/// ルートグループごとのセキュリティヘッダー設定
+#[derive(Debug, Clone, Copy)]
+pub struct SecurityHeaderPolicy {
+    pub frame_options: &'static str,
+    pub content_security_policy: &'static str,
+}
+
+impl SecurityHeaderPolicy {
+    /// JSON APIや画像を返すルート向けの設定（フレーム埋め込みを一切許可しない）
+    pub const API: Self = Self {
+        frame_options: "DENY",
+        content_security_policy: "default-src 'none'; frame-ancestors 'none'",
+    };
+
+    /// HTMLを返すルート向けの設定（メールプレビューやスレッドのHTML表示など、同一オリジンでの埋め込みを許可）
+    pub const HTML: Self = Self {
+        frame_options: "SAMEORIGIN",
+        content_security_policy: "default-src 'none'; style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; frame-ancestors 'self'; base-uri 'none'; form-action 'none'",
+    };
+}
+
+/// 標準的なセキュリティヘッダーを付与するミドルウェア
+/// ハンドラー側で明示的に設定したヘッダーは上書きしません
+pub async fn security_headers_middleware(
+    State(policy): State<SecurityHeaderPolicy>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    headers
+        .entry(X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    headers
+        .entry(REFERRER_POLICY)
+        .or_insert(HeaderValue::from_static("strict-origin-when-cross-origin"));
+    headers
+        .entry(X_FRAME_OPTIONS)
+        .or_insert(HeaderValue::from_static(policy.frame_options));
+    headers
+        .entry(CONTENT_SECURITY_POLICY)
+        .or_insert(HeaderValue::from_static(policy.content_security_policy));
+
+    response
+}
+
+/// リクエストIDを受け渡すヘッダー
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// リクエストごとのID（ログとエラーレスポンスの突き合わせ用）
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// リクエストIDを割り当てるミドルウェア
+/// クライアントが `X-Request-Id` を送った場合はそれを使い、無ければ生成してレスポンスにも付与します
+pub async fn request_id_middleware(mut request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        fakes::{fake_user, FakeRepos},
+        test_jwt,
+    };
+    use axum::{http::StatusCode, middleware::from_fn_with_state, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    fn test_router(policy: SecurityHeaderPolicy) -> Router {
+        Router::new()
+            .route(
+                "/json",
+                get(|| async { Json(serde_json::json!({"ok": true})) }),
+            )
+            .route(
+                "/html",
+                get(|| async { axum::response::Html("<p>preview</p>") }),
+            )
+            .layer(from_fn_with_state(policy, security_headers_middleware))
+    }
+
+    async fn get_headers(router: Router, uri: &str) -> HeaderMap {
+        let response = router
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_jsonエンドポイントにセキュリティヘッダーが付与される() {
+        // API向け設定ではセキュリティヘッダーが付き、フレーム埋め込みを禁止することを確認
+        let headers = get_headers(test_router(SecurityHeaderPolicy::API), "/json").await;
+
+        assert_eq!(headers.get(X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        assert_eq!(
+            headers.get(REFERRER_POLICY).unwrap(),
+            "strict-origin-when-cross-origin"
+        );
+        assert_eq!(headers.get(X_FRAME_OPTIONS).unwrap(), "DENY");
+        assert!(headers
+            .get(CONTENT_SECURITY_POLICY)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .contains("frame-ancestors 'none'"));
+    }
+
+    #[tokio::test]
+    async fn test_htmlエンドポイントでは同一オリジンのフレームを許可する() {
+        // HTML向け設定ではSAMEORIGINと制限付きCSPが付与されることを確認
+        let headers = get_headers(test_router(SecurityHeaderPolicy::HTML), "/html").await;
+
+        assert_eq!(headers.get(X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        assert_eq!(headers.get(X_FRAME_OPTIONS).unwrap(), "SAMEORIGIN");
+        let csp = headers
+            .get(CONTENT_SECURITY_POLICY)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(csp.contains("default-src 'none'"));
+        assert!(csp.contains("frame-ancestors 'self'"));
+    }
+
+    fn bearer(user: &User) -> HeaderMap {
+        bearer_expiring_in(user, 15)
+    }
+
+    fn bearer_expiring_in(user: &User, minutes: i64) -> HeaderMap {
+        let jwt = test_jwt();
+        let token = jwt
+            .encode(&jwt.claims(
+                &user.id.to_string(),
+                &user.username,
+                &user.email,
+                user.role,
+                &uuid::Uuid::new_v4().to_string(),
+                minutes,
+            ))
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_トークンのユーザーが存在する場合のみ認証される() {
+        // 削除済みなどでユーザーが見つからないトークンは401になることを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let missing = fake_user(true);
+        repos.auth.add_user(&user);
+
+        let (authenticated, claims) = authenticate(&repos.authenticator(), &bearer(&user))
+            .await
+            .unwrap();
+        assert_eq!(authenticated.id, user.id);
+        assert_eq!(claims.sub, user.id.to_string());
+
+        let result = authenticate(&repos.authenticator(), &bearer(&missing)).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_状態のjwtサービスの発行者で検証する() {
+        // 別のデプロイ向けの設定を渡した場合、このデプロイで発行したトークンは受け付けないことを確認
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        repos.auth.add_user(&user);
+        let config = crate::config::Config {
+            jwt_issuer: "other-deployment".to_string(),
+            ..crate::test_utils::test_config()
+        };
+        let other = Authenticator {
+            jwt: Arc::new(JwtService::from_config(&config)),
+            ..repos.authenticator()
+        };
+
+        let result = authenticate(&other, &bearer(&user)).await;
+        assert!(matches!(result, Err(AppError::Jwt(_))));
+    }
+
+    #[tokio::test]
+    async fn test_任意認証では無効なトークンを匿名として扱う() {
+        // 任意認証では無効なトークンを匿名として扱うことを確認
+        // ヘッダーが無い・期限切れ・退会などでユーザーが見つからない場合も401にせず、匿名として処理を続ける
+        let repos = FakeRepos::default();
+        let user = fake_user(true);
+        let removed = fake_user(true);
+        repos.auth.add_user(&user);
+        let router = Router::new()
+            .route(
+                "/public",
+                get(|MaybeUser(user): MaybeUser| async move {
+                    user.map(|user| user.username).unwrap_or_default()
+                }),
+            )
+            .with_state(repos.authenticator());
+
+        for (headers, expected) in [
+            (HeaderMap::new(), ""),
+            (bearer(&user), user.username.as_str()),
+            (bearer_expiring_in(&user, -60), ""),
+            (bearer(&removed), ""),
+        ] {
+            let mut request = Request::builder()
+                .uri("/public")
+                .body(Body::empty())
+                .unwrap();
+            *request.headers_mut() = headers;
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, expected.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_管理者以外は管理者ルートにアクセスできない() {
+        // 認証済みでも管理者でなければ403、管理者なら通過することを確認
+        let repos = FakeRepos::default();
+        let admin = User {
+            role: UserRole::Admin,
+            ..fake_user(true)
+        };
+        let moderator = User {
+            role: UserRole::Moderator,
+            ..fake_user(true)
+        };
+        let member = fake_user(true);
+        for user in [&admin, &moderator, &member] {
+            repos.auth.add_user(user);
+        }
+        let router = Router::new()
+            .route("/admin", get(|| async { "ok" }))
+            .route_layer(from_fn_with_state(UserRole::Admin, require_role))
+            .route_layer(from_fn_with_state(repos.authenticator(), auth_middleware));
+
+        for (user, expected) in [
+            (&admin, StatusCode::OK),
+            (&moderator, StatusCode::FORBIDDEN),
+            (&member, StatusCode::FORBIDDEN),
+        ] {
+            let mut request = Request::builder()
+                .uri("/admin")
+                .body(Body::empty())
+                .unwrap();
+            *request.headers_mut() = bearer(user);
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_利用停止中のユーザーは閲覧のみできる() {
+        // 利用停止中は書き込みがUSER_BANNEDの403になり、期限切れの利用停止は無視されることを確認
+        let repos = FakeRepos::default();
+        let banned = User {
+            banned_at: Some(Utc::now()),
+            banned_until: Some(Utc::now() + chrono::Duration::hours(1)),
+            ban_reason: Some("spam".to_string()),
+            ..fake_user(true)
+        };
+        let expired = User {
+            banned_at: Some(Utc::now() - chrono::Duration::hours(2)),
+            banned_until: Some(Utc::now() - chrono::Duration::hours(1)),
+            ..fake_user(true)
+        };
+        for user in [&banned, &expired] {
+            repos.auth.add_user(user);
+        }
+        let router = Router::new()
+            .route("/posts", get(|| async { "ok" }).post(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn(reject_banned_writes))
+            .route_layer(from_fn_with_state(repos.authenticator(), auth_middleware));
+
+        for (user, method, expected) in [
+            (&banned, "GET", StatusCode::OK),
+            (&banned, "POST", StatusCode::FORBIDDEN),
+            (&expired, "POST", StatusCode::OK),
+        ] {
+            let mut request = Request::builder()
+                .method(method)
+                .uri("/posts")
+                .body(Body::empty())
+                .unwrap();
+            *request.headers_mut() = bearer(user);
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), expected);
+
+            if expected == StatusCode::FORBIDDEN {
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body["code"], "USER_BANNED");
+                assert_eq!(body["ban_reason"], "spam");
+                assert!(body["banned_until"].is_string());
+            }
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::common::PaginatedResponse;
+
+// Request DTOs
+
+/// 監査ログの出力形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditLogFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct AuditLogQuery {
+    /// 操作を行った管理者のID
+    pub actor_id: Option<Uuid>,
+    /// 操作の種類（例: `user.mute`）
+    pub action: Option<String>,
+    /// 操作対象の種類（例: `user`）
+    pub target_type: Option<String>,
+    /// 操作対象のID
+    pub target_id: Option<Uuid>,
+    /// この日時以降の操作（含む）
+    pub from: Option<DateTime<Utc>>,
+    /// この日時より前の操作（含まない）
+    pub to: Option<DateTime<Utc>>,
+    /// `csv` を指定するとページングせずに全件をCSVで出力します
+    #[serde(default)]
+    #[param(inline)]
+    pub format: AuditLogFormat,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogListResponse {
+    #[schema(value_type = PaginatedResponse<AuditLogResponse>)]
+    pub entries: PaginatedResponse<AuditLogResponse>,
+}
+
+// Database entities
+
+/// 監査ログの行（details はJSONBをテキストとして取得）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    pub details: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditLog> for AuditLogResponse {
+    fn from(log: AuditLog) -> Self {
+        Self {
+            id: log.id,
+            actor_id: log.actor_id,
+            action: log.action,
+            target_type: log.target_type,
+            target_id: log.target_id,
+            details: serde_json::from_str(&log.details).unwrap_or(serde_json::Value::Null),
+            created_at: log.created_at,
+        }
+    }
+}
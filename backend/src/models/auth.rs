@@ -4,6 +4,7 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
+use super::{moderation::UserBanStatus, UserRole};
 use crate::validations::username;
 
 // Request DTOs
@@ -34,7 +35,9 @@ pub struct LoginRequest {
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct RefreshTokenRequest {
-    pub refresh_token: String,
+    /// Cookieモードでは省略可能（Cookieから読み取る）
+    #[serde(default)]
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -48,6 +51,10 @@ pub struct ChangePasswordRequest {
         message = "New password must be between 8 and 100 characters"
     ))]
     pub new_password: String,
+
+    /// 変更を行ったセッションはログインしたままにする（他のセッションはすべて終了する）
+    #[serde(default)]
+    pub keep_current_session: bool,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -56,6 +63,12 @@ pub struct RequestPasswordResetRequest {
     pub email: String,
 }
 
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MagicLinkRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+}
+
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ResetPasswordRequest {
     #[validate(length(
@@ -71,10 +84,15 @@ pub struct ResetPasswordRequest {
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
-    pub refresh_token: String,
+    /// Cookieモードではレスポンスに含まれない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
     pub token_type: String,
     pub expires_in: i64,
     pub user: UserInfo,
+    /// 利用停止中の場合のみ含まれる（ログインはできるが書き込みはできない）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ban: Option<UserBanStatus>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -98,6 +116,22 @@ pub struct MessageResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub is_current: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
 // JWT Claims
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -108,6 +142,11 @@ pub struct Claims {
     pub exp: usize,       // Expiration time
     pub iat: usize,       // Issued at
     pub iss: String,      // Issuer
+    pub aud: String,      // Audience (deployment)
+    #[serde(default)]
+    pub sid: Option<String>, // Session ID (refresh token ID)
+    #[serde(default)]
+    pub role: UserRole, // Role at issue time (authorization re-reads it from the database)
 }
 
 // OAuth DTOs
@@ -1,39 +1,143 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
+use super::{common::ApiWarning, users};
+use crate::{utils::markdown, validations::content};
+
+/// トップレベルのコメントの並び順（返信は常に古い順）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentSort {
+    /// 古い順
+    #[default]
+    Old,
+    /// 新しい順
+    New,
+    /// 返信を含めて最後に投稿があった順（`last_activity_at` の新しい順）
+    Active,
+}
+
+impl CommentSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentSort::Old => "old",
+            CommentSort::New => "new",
+            CommentSort::Active => "active",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "old" => Some(CommentSort::Old),
+            "new" => Some(CommentSort::New),
+            "active" => Some(CommentSort::Active),
+            _ => None,
+        }
+    }
+}
+
+/// 投稿者が削除したコメントの表示上の本文
+pub const DELETED_CONTENT: &str = "[deleted]";
+
+/// モデレーターが非表示にしたコメントの表示上の本文
+pub const REMOVED_CONTENT: &str = "[removed]";
+
+/// 閲覧者がブロックしているユーザーのコメントの表示上の本文
+pub const BLOCKED_CONTENT: &str = "[blocked user]";
+
+/// モデレーターがコメントを非表示にする理由の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentRemovalReason {
+    Spam,
+    /// 嫌がらせ・誹謗中傷など
+    Abuse,
+    /// スレッドと関係の無い内容
+    OffTopic,
+    /// 個人情報の掲載
+    PersonalInfo,
+    Other,
+}
+
+impl CommentRemovalReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentRemovalReason::Spam => "spam",
+            CommentRemovalReason::Abuse => "abuse",
+            CommentRemovalReason::OffTopic => "off_topic",
+            CommentRemovalReason::PersonalInfo => "personal_info",
+            CommentRemovalReason::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "spam" => Some(CommentRemovalReason::Spam),
+            "abuse" => Some(CommentRemovalReason::Abuse),
+            "off_topic" => Some(CommentRemovalReason::OffTopic),
+            "personal_info" => Some(CommentRemovalReason::PersonalInfo),
+            "other" => Some(CommentRemovalReason::Other),
+            _ => None,
+        }
+    }
+}
+
 // Request DTOs
 
+/// 返信を入れ子にできる深さ（トップレベルのコメントが 1）
+pub const MAX_DEPTH: i32 = 4;
+
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateCommentRequest {
-    #[validate(length(
-        min = 1,
-        max = 1000,
-        message = "Content must be between 1 and 1000 characters"
-    ))]
+    /// 1〜10,000 文字（空白だけの本文は不可。改行は LF に揃えて保存する）
+    #[serde(deserialize_with = "content::deserialize_content")]
+    #[validate(custom(function = "content::validate_comment_content"))]
     pub content: String,
 
     pub parent_id: Option<Uuid>,
+
+    /// クライアントが付ける任意の識別子（64 文字まで。保存せず、作成時のレスポンスと通知にそのまま含める）
+    #[serde(default)]
+    #[validate(length(max = 64, message = "client_nonce must be at most 64 characters"))]
+    pub client_nonce: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateCommentRequest {
-    #[validate(length(
-        min = 1,
-        max = 1000,
-        message = "Content must be between 1 and 1000 characters"
-    ))]
+    /// 1〜10,000 文字（空白だけの本文は不可。改行は LF に揃えて保存する）
+    #[serde(deserialize_with = "content::deserialize_content")]
+    #[validate(custom(function = "content::validate_comment_content"))]
     pub content: String,
 }
 
+/// モデレーターによるコメントの非表示
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RemoveCommentQuery {
+    /// 理由の分類（投稿者への通知に含まれる）
+    pub reason: CommentRemovalReason,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct CommentListQuery {
+    /// トップレベルのコメントの並び順（省略時はログインユーザーの設定、未設定ならold）
+    pub sort: Option<CommentSort>,
+    /// ブロックしているユーザーのコメントも本文を隠さずに返す（省略時は false）
+    pub include_blocked: Option<bool>,
+}
+
 // Response DTOs
 
 #[derive(Debug, Serialize, ToSchema, Clone)]
 pub struct CommentResponse {
     pub id: Uuid,
+    /// パーマリンク用の短いID
+    pub short_id: String,
     pub content: String,
+    /// 本文の Markdown を変換した HTML（許可したタグのみ。レスポンスを作るたびに変換する）
+    pub content_html: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user: CommentUser,
@@ -41,6 +145,34 @@ pub struct CommentResponse {
     #[schema(no_recursion)]
     pub replies: Vec<CommentResponse>,
     pub reply_count: u64,
+    /// 投稿者が削除したコメント（返信を残すため `[deleted]` として表示する）
+    pub deleted: bool,
+    /// モデレーターが非表示にしたコメント（`[removed]` として表示する）
+    pub removed_by_moderator: bool,
+    /// 閲覧者がブロックしているユーザーのコメント（`[blocked user]` として表示する）
+    pub blocked: bool,
+    /// 投稿者が本文を編集したか
+    pub edited: bool,
+    /// 投稿者が最後に本文を編集した日時
+    pub edited_at: Option<DateTime<Utc>>,
+    /// 返信を含めてツリー内で最も新しい投稿の日時（一覧のトップレベルのコメントのみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity_at: Option<DateTime<Utc>>,
+    /// 作成リクエストで指定された `client_nonce`（作成時のレスポンスのみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_nonce: Option<String>,
+    /// 作成・更新時の警告（上限に近い文字数・返信できない深さなど）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ApiWarning>,
+}
+
+/// 作成前のプレビュー（保存はしない）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentPreviewResponse {
+    /// 常に true（作成したコメントと区別するため）
+    pub preview: bool,
+    /// 作成した場合のコメント（ID・短いIDは未定のため空）
+    pub comment: CommentResponse,
 }
 
 #[derive(Debug, Serialize, ToSchema, Clone)]
@@ -51,10 +183,29 @@ pub struct CommentUser {
     pub avatar_url: Option<String>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentQuoteResponse {
+    pub markdown: String,
+    pub author_username: String,
+    pub comment_url: String,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct CommentListResponse {
     pub comments: Vec<CommentResponse>,
     pub total_count: u64,
+    /// ページング指定時のみ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<CommentPagination>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentPagination {
+    pub page: u32,
+    pub limit: u32,
+    /// トップレベルのコメント数
+    pub total_root_comments: u64,
+    pub total_pages: u32,
 }
 
 // Database query result structs
@@ -63,6 +214,7 @@ pub struct CommentListResponse {
 pub struct CommentWithUser {
     // Comment fields
     pub id: Uuid,
+    pub short_id: String,
     // pub thread_id: Uuid,
     pub content: String,
     pub parent_id: Option<Uuid>,
@@ -74,24 +226,43 @@ pub struct CommentWithUser {
     pub username: String,
     pub user_display_name: Option<String>,
     pub user_avatar_url: Option<String>,
+
+    // Tombstone fields（一覧以外のクエリでは省略できる）
+    #[sqlx(default)]
+    pub deleted: bool,
+    #[sqlx(default)]
+    pub removed_by_moderator: bool,
+    /// 投稿者が最後に編集した日時（一覧・更新以外のクエリでは省略できる）
+    #[sqlx(default)]
+    pub edited_at: Option<DateTime<Utc>>,
 }
 
 impl CommentWithUser {
     pub fn to_response(self) -> CommentResponse {
         CommentResponse {
             id: self.id,
+            short_id: self.short_id,
+            content_html: markdown::to_html(&self.content),
             content: self.content,
             created_at: self.created_at,
             updated_at: self.updated_at,
             user: CommentUser {
                 id: self.user_id,
-                username: self.username,
+                username: users::display_username(self.username),
                 display_name: self.user_display_name,
                 avatar_url: self.user_avatar_url,
             },
             parent_id: self.parent_id,
             replies: Vec::new(), // Will be populated by the service
             reply_count: 0,      // Will be populated by the service
+            deleted: self.deleted,
+            removed_by_moderator: self.removed_by_moderator,
+            blocked: false,
+            edited: self.edited_at.is_some(),
+            edited_at: self.edited_at,
+            last_activity_at: None, // Will be populated by the service
+            client_nonce: None,
+            warnings: Vec::new(),
         }
     }
 }
@@ -7,6 +7,25 @@ pub struct ErrorResponse {
     pub status: u16,
 }
 
+/// 処理は成功したが利用者に知らせたいこと（上限に近い文字数など）
+///
+/// 作成・更新のレスポンスに `warnings` として含めます。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ApiWarning {
+    /// 警告の種類（`TITLE_NEAR_LIMIT` など）
+    pub code: String,
+    pub message: String,
+}
+
+/// リソースが存在するか（HEAD による存在確認の結果）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Existence {
+    Present,
+    /// 削除された記録が残っている（退会したユーザー、モデレーターが削除したスレッド）
+    Tombstoned,
+    Missing,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct SuccessResponse {
     pub message: String,
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::common::PaginatedResponse;
+
+/// アウトボックスの送信状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailOutboxStatus {
+    /// 送信待ち（再試行待ちを含む）
+    Pending,
+    Sent,
+    /// 再試行の上限に達した
+    Failed,
+}
+
+impl EmailOutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailOutboxStatus::Pending => "pending",
+            EmailOutboxStatus::Sent => "sent",
+            EmailOutboxStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(EmailOutboxStatus::Pending),
+            "sent" => Some(EmailOutboxStatus::Sent),
+            "failed" => Some(EmailOutboxStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+// Request DTOs
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct EmailOutboxQuery {
+    /// 送信状態で絞り込む
+    #[param(inline)]
+    pub status: Option<EmailOutboxStatus>,
+}
+
+// Response DTOs
+
+/// アウトボックスの行（本文には認証用のトークンが含まれるため返さない）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmailOutboxResponse {
+    pub id: Uuid,
+    pub to_email: String,
+    pub subject: String,
+    pub status: EmailOutboxStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmailOutboxListResponse {
+    #[schema(value_type = PaginatedResponse<EmailOutboxResponse>)]
+    pub entries: PaginatedResponse<EmailOutboxResponse>,
+}
+
+// Database entities
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailOutboxEntry {
+    pub id: Uuid,
+    pub to_email: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<EmailOutboxEntry> for EmailOutboxResponse {
+    fn from(entry: EmailOutboxEntry) -> Self {
+        Self {
+            id: entry.id,
+            to_email: entry.to_email,
+            subject: entry.subject,
+            status: EmailOutboxStatus::parse(&entry.status).unwrap_or(EmailOutboxStatus::Pending),
+            attempts: entry.attempts,
+            next_attempt_at: entry.next_attempt_at,
+            last_error: entry.last_error,
+            sent_at: entry.sent_at,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Response DTOs
+
+/// 期限切れトークンの削除件数
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PurgeTokensResponse {
+    /// 有効期限から30日以上経過して削除したリフレッシュトークン
+    pub refresh_tokens: u64,
+    /// 有効期限から30日以上経過して削除したメール認証トークン
+    pub email_verification_tokens: u64,
+    /// 有効期限から30日以上経過して削除したマジックリンクのトークン
+    pub magic_link_tokens: u64,
+    /// 有効期限から30日以上経過して削除した端末の確認トークン
+    pub device_confirmation_tokens: u64,
+    /// 有効期限が切れて消去したパスワードリセットのトークン
+    pub password_reset_tokens: u64,
+    /// 有効期限が切れて消去した旧形式のメール認証トークン（users.verification_token）
+    pub verification_tokens: u64,
+}
+
+/// パスワードハッシュの形式ごとのアカウント数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CredentialReport {
+    /// 現在のハッシュの形式
+    pub hash_version: i16,
+    /// 現在の形式のアカウント
+    pub current: i64,
+    /// 旧形式のままで、`migrate-credentials` がまだ確認していないアカウント
+    pub legacy: i64,
+    /// 次のログイン時にハッシュを作り直すアカウント
+    pub pending_rehash: i64,
+}
@@ -0,0 +1,44 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::{Config, EmailMode};
+
+// Response DTOs
+
+/// 運用中の設定のうち、管理者が確認できるもの
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminMetaResponse {
+    /// メールの送信モード（EMAIL_MODE）
+    pub email_mode: EmailMode,
+    /// `allowlist` モードで送信を許可する宛先のパターン（EMAIL_ALLOWLIST）
+    pub email_allowlist: Vec<String>,
+}
+
+/// 運用中の設定の全体（秘密の値は `[REDACTED]` に置き換える）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminConfigResponse {
+    /// 環境変数から読み込んだ設定
+    #[schema(value_type = Object)]
+    pub config: Config,
+    /// 設定と定数から決まる値
+    pub effective: EffectiveSettings,
+}
+
+/// 設定と定数から決まる値（トークンの有効期間・メールの送信モードなど）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveSettings {
+    /// アクセストークンの有効期間（分）
+    pub access_token_expires_in_minutes: i64,
+    /// リフレッシュトークンの有効期間（秒）
+    pub refresh_token_expires_in_seconds: i64,
+    /// メール認証のトークンの有効期間（時間。EMAIL_VERIFICATION_TOKEN_EXPIRES_IN）
+    pub email_verification_token_expires_in_hours: i64,
+    /// パスワードリセットのトークンの有効期間（時間。PASSWORD_RESET_TOKEN_EXPIRES_IN）
+    pub password_reset_token_expires_in_hours: i64,
+    /// マジックリンクの有効期間（分）
+    pub magic_link_expires_in_minutes: i64,
+    /// メールの送信モード（EMAIL_MODE）
+    pub email_mode: EmailMode,
+    /// 実際にメールを送信するか（`dry_run` の場合は送信せずにログへ出力する）
+    pub email_delivery_enabled: bool,
+}
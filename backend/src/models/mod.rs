@@ -1,14 +1,58 @@
+pub mod audit_log;
 pub mod auth;
 pub mod comments;
 pub mod common;
+pub mod email_outbox;
+pub mod maintenance;
+pub mod meta;
+pub mod moderation;
+pub mod preferences;
+pub mod reports;
+pub mod saved_searches;
+pub mod tag_rules;
+pub mod tags;
 pub mod threads;
 pub mod users;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+/// ユーザーの権限（後の値ほど強い）
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    User,
+    /// 他のユーザーのスレッド・コメントを削除できる
+    Moderator,
+    /// モデレーターの権限に加えて管理者用APIを使える
+    Admin,
+}
+
+impl UserRole {
+    /// 他のユーザーの投稿を削除したり、スレッドをロックしたりできるか
+    pub fn can_moderate(&self) -> bool {
+        *self >= UserRole::Moderator
+    }
+}
+
 // Database entities
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
@@ -18,6 +62,8 @@ pub struct User {
     pub email: String,
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub website_url: Option<String>,
     pub email_verified: bool,
     pub email_verified_at: Option<DateTime<Utc>>,
     pub verification_token: Option<String>,
@@ -26,6 +72,27 @@ pub struct User {
     pub password_reset_token_expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub role: UserRole,
+    pub banned_at: Option<DateTime<Utc>>,
+    /// 利用停止の期限（無期限の場合はNone）
+    pub banned_until: Option<DateTime<Utc>>,
+    pub ban_reason: Option<String>,
+}
+
+impl User {
+    /// `now` の時点で利用停止中であれば、その内容を返す（期限を過ぎた利用停止は無効）
+    pub fn active_ban(&self, now: DateTime<Utc>) -> Option<moderation::UserBanStatus> {
+        self.banned_at?;
+        if self.banned_until.is_some_and(|until| until <= now) {
+            return None;
+        }
+
+        Some(moderation::UserBanStatus {
+            reason: self.ban_reason.clone(),
+            banned_until: self.banned_until,
+            permanent: self.banned_until.is_none(),
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -33,7 +100,12 @@ pub struct UserCredentials {
     pub id: Uuid,
     pub user_id: Uuid,
     pub password_hash: String,
-    pub salt: String,
+    /// 旧形式（hash_version = 1）の行のみ。現在の形式ではソルトはハッシュの文字列にのみ含まれる
+    pub salt: Option<String>,
+    /// パスワードハッシュの形式（`auth::password::HASH_VERSION` 未満は旧形式）
+    pub hash_version: i16,
+    /// 次のログイン時にハッシュを作り直す
+    pub needs_rehash: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -60,6 +132,9 @@ pub struct RefreshToken {
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub revoked: bool,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
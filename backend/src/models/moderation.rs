@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+// Request DTOs
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MuteUserRequest {
+    /// ミュートする期間（分）。最大365日
+    #[validate(range(
+        min = 1,
+        max = 525600,
+        message = "Duration must be between 1 minute and 365 days"
+    ))]
+    pub duration_minutes: i64,
+
+    #[validate(length(max = 500, message = "Reason must be less than 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct BanUserRequest {
+    /// 利用停止の期間（分）。省略した場合は無期限
+    #[validate(range(
+        min = 1,
+        max = 525600,
+        message = "Duration must be between 1 minute and 365 days"
+    ))]
+    pub duration_minutes: Option<i64>,
+
+    #[validate(length(max = 500, message = "Reason must be less than 500 characters"))]
+    pub reason: Option<String>,
+}
+
+/// モデレーターが他のユーザーの投稿を削除する際の理由（モデレーションログに記録する）
+#[derive(Debug, Default, Deserialize, Validate, IntoParams)]
+pub struct ModerationDeleteQuery {
+    #[validate(length(max = 500, message = "Reason must be less than 500 characters"))]
+    pub reason: Option<String>,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserMuteResponse {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub muted_by: Option<Uuid>,
+    pub reason: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 本人向けのミュート状態
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MuteStatus {
+    pub reason: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 利用停止の内容（ログイン時のレスポンスにも含まれる）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct UserBanStatus {
+    pub reason: Option<String>,
+    /// 期限（無期限の場合はnull）
+    pub banned_until: Option<DateTime<Utc>>,
+    pub permanent: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserBanResponse {
+    pub user_id: Uuid,
+    /// 利用停止中でなければnull
+    pub ban: Option<UserBanStatus>,
+}
+
+/// 利用停止中のユーザーが書き込みを行おうとした場合のエラーレスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserBannedErrorResponse {
+    pub error: String,
+    pub status: u16,
+    /// 常に `USER_BANNED`
+    pub code: String,
+    pub ban_reason: Option<String>,
+    /// 期限（無期限の場合はnull）
+    pub banned_until: Option<DateTime<Utc>>,
+}
+
+/// ミュート中のユーザーが投稿しようとした場合のエラーレスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserMutedErrorResponse {
+    pub error: String,
+    pub status: u16,
+    /// 常に `USER_MUTED`
+    pub code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// Database entities
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserMute {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub muted_by: Option<Uuid>,
+    pub reason: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<UserMute> for UserMuteResponse {
+    fn from(mute: UserMute) -> Self {
+        Self {
+            id: mute.id,
+            user_id: mute.user_id,
+            muted_by: mute.muted_by,
+            reason: mute.reason,
+            expires_at: mute.expires_at,
+            created_at: mute.created_at,
+        }
+    }
+}
+
+impl From<UserMute> for MuteStatus {
+    fn from(mute: UserMute) -> Self {
+        Self {
+            reason: mute.reason,
+            expires_at: mute.expires_at,
+        }
+    }
+}
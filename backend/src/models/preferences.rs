@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    comments::CommentSort,
+    threads::{ThreadSort, TopWindow},
+    users::SortPreferences,
+};
+
+// 別のインスタンスへ持ち運べる設定（ユーザーはIDではなくユーザー名で表す）
+
+/// エクスポート・インポートする設定の文書
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PreferencesDocument {
+    /// 文書の形式のバージョン（インポート時に対応していないバージョンは400）
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub settings: PreferenceSettings,
+    /// ブロックしているユーザーのユーザー名
+    #[serde(default)]
+    pub blocks: Vec<String>,
+    /// フォロー中のユーザーのユーザー名
+    #[serde(default)]
+    pub follows: Vec<String>,
+    #[serde(default)]
+    pub saved_searches: Vec<PortableSavedSearch>,
+}
+
+/// 並び順の既定値（未設定の項目はnull）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PreferenceSettings {
+    pub default_sort: Option<ThreadSort>,
+    pub default_window: Option<TopWindow>,
+    pub default_comment_sort: Option<CommentSort>,
+}
+
+impl From<SortPreferences> for PreferenceSettings {
+    fn from(preferences: SortPreferences) -> Self {
+        Self {
+            default_sort: preferences.default_sort,
+            default_window: preferences.default_window,
+            default_comment_sort: preferences.default_comment_sort,
+        }
+    }
+}
+
+/// 保存した検索条件（実行状況は含めない）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PortableSavedSearch {
+    pub query: String,
+    #[serde(default)]
+    pub notify: bool,
+    #[serde(default)]
+    pub email: bool,
+}
+
+/// 取り込まなかった項目の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SkippedKind {
+    Block,
+    Follow,
+    SavedSearch,
+}
+
+/// 取り込まなかった理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// このインスタンスにいない（退会した）ユーザー
+    UnknownUser,
+    /// 自分自身
+    SelfReference,
+    /// 空の検索語
+    InvalidQuery,
+    /// 保存できる検索条件の上限に達した
+    LimitReached,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SkippedEntry {
+    pub kind: SkippedKind,
+    /// ユーザー名または検索語
+    pub value: String,
+    pub reason: SkipReason,
+}
+
+/// インポートの結果（既に設定済みの項目は追加件数に含めない）
+#[derive(Debug, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PreferencesImportResponse {
+    pub blocks_added: u64,
+    pub follows_added: u64,
+    pub saved_searches_added: u64,
+    pub skipped: Vec<SkippedEntry>,
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::common::PaginatedResponse;
+
+/// 通報の理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+    Spam,
+    /// 嫌がらせ・誹謗中傷など
+    Abuse,
+    /// その他（`detail` に内容を書く）
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Abuse => "abuse",
+            ReportReason::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "spam" => Some(ReportReason::Spam),
+            "abuse" => Some(ReportReason::Abuse),
+            "other" => Some(ReportReason::Other),
+            _ => None,
+        }
+    }
+}
+
+/// 通報の対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTargetType {
+    Thread,
+    Comment,
+}
+
+impl ReportTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportTargetType::Thread => "thread",
+            ReportTargetType::Comment => "comment",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "thread" => Some(ReportTargetType::Thread),
+            "comment" => Some(ReportTargetType::Comment),
+            _ => None,
+        }
+    }
+}
+
+/// 通報の対応状況
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    /// 未対応
+    Open,
+    Resolved,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(ReportStatus::Open),
+            "resolved" => Some(ReportStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// 通報への対応結果（通報したユーザーに通知する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportOutcome {
+    /// 削除・非表示などの対応をした
+    Actioned,
+    /// 違反は見つからなかった
+    NoViolation,
+}
+
+impl ReportOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportOutcome::Actioned => "actioned",
+            ReportOutcome::NoViolation => "no_violation",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "actioned" => Some(ReportOutcome::Actioned),
+            "no_violation" => Some(ReportOutcome::NoViolation),
+            _ => None,
+        }
+    }
+}
+
+// Request DTOs
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateReportRequest {
+    pub reason: ReportReason,
+    /// 補足（任意）
+    #[validate(length(max = 1000, message = "Detail must be at most 1000 characters"))]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResolveReportRequest {
+    pub outcome: ReportOutcome,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ReportQuery {
+    /// 対応状況で絞り込む
+    #[param(inline)]
+    pub status: Option<ReportStatus>,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportResponse {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub target_type: ReportTargetType,
+    pub target_id: Uuid,
+    pub reason: ReportReason,
+    pub detail: Option<String>,
+    pub status: ReportStatus,
+    /// 対応結果（未対応の場合はnull）
+    pub outcome: Option<ReportOutcome>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportListResponse {
+    #[schema(value_type = PaginatedResponse<ReportResponse>)]
+    pub reports: PaginatedResponse<ReportResponse>,
+}
+
+// Database entities
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Report {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub reason: String,
+    pub detail: Option<String>,
+    pub status: String,
+    pub outcome: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Report> for ReportResponse {
+    fn from(report: Report) -> Self {
+        Self {
+            id: report.id,
+            reporter_id: report.reporter_id,
+            target_type: ReportTargetType::parse(&report.target_type)
+                .unwrap_or(ReportTargetType::Thread),
+            target_id: report.target_id,
+            reason: ReportReason::parse(&report.reason).unwrap_or(ReportReason::Other),
+            detail: report.detail,
+            status: ReportStatus::parse(&report.status).unwrap_or(ReportStatus::Open),
+            outcome: report.outcome.as_deref().and_then(ReportOutcome::parse),
+            resolved_by: report.resolved_by,
+            resolved_at: report.resolved_at,
+            created_at: report.created_at,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+// Request DTOs
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateSavedSearchRequest {
+    /// 検索語（空白区切りのすべての語を含むスレッドに一致）
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Query must be between 1 and 100 characters"
+    ))]
+    pub query: String,
+    /// 新しく一致したスレッドを通知する（省略時は false）
+    pub notify: Option<bool>,
+    /// 通知をメールでも受け取る（`notify` が true の場合のみ。省略時は false）
+    pub email: Option<bool>,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedSearchResponse {
+    pub id: Uuid,
+    pub query: String,
+    pub notify: bool,
+    pub email: bool,
+    /// この日時までに作成されたスレッドは通知済み
+    pub last_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedSearchListResponse {
+    pub saved_searches: Vec<SavedSearchResponse>,
+}
+
+// Database entities
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub query: String,
+    pub notify: bool,
+    pub email: bool,
+    pub last_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<SavedSearch> for SavedSearchResponse {
+    fn from(search: SavedSearch) -> Self {
+        Self {
+            id: search.id,
+            query: search.query,
+            notify: search.notify,
+            email: search.email,
+            last_run_at: search.last_run_at,
+            created_at: search.created_at,
+        }
+    }
+}
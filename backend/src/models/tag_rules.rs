@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// 自動タグ付けルールで照合する項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TagRuleField {
+    Title,
+    /// 本文（本文の無いスレッドには一致しない）
+    Content,
+}
+
+impl TagRuleField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagRuleField::Title => "title",
+            TagRuleField::Content => "content",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "title" => Some(TagRuleField::Title),
+            "content" => Some(TagRuleField::Content),
+            _ => None,
+        }
+    }
+}
+
+// Request DTOs
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateTagRuleRequest {
+    /// 大文字・小文字を区別しない部分一致で照合する文字列
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Pattern must be between 1 and 100 characters"
+    ))]
+    pub pattern: String,
+    pub match_field: TagRuleField,
+    /// 一致したスレッドに付けるタグ
+    pub tag_id: Uuid,
+    /// 省略時は有効
+    pub enabled: Option<bool>,
+}
+
+/// 指定された項目のみ更新する
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateTagRuleRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Pattern must be between 1 and 100 characters"
+    ))]
+    pub pattern: Option<String>,
+    pub match_field: Option<TagRuleField>,
+    pub tag_id: Option<Uuid>,
+    pub enabled: Option<bool>,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagRuleResponse {
+    pub id: Uuid,
+    pub pattern: String,
+    pub match_field: TagRuleField,
+    pub tag_id: Uuid,
+    pub tag_slug: String,
+    pub tag_name: String,
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagRuleListResponse {
+    pub rules: Vec<TagRuleResponse>,
+}
+
+// Database entities
+
+/// タグのスラッグ・名前付きのルール
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TagRule {
+    pub id: Uuid,
+    pub pattern: String,
+    pub match_field: String,
+    pub tag_id: Uuid,
+    pub tag_slug: String,
+    pub tag_name: String,
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<TagRule> for TagRuleResponse {
+    fn from(rule: TagRule) -> Self {
+        Self {
+            id: rule.id,
+            pattern: rule.pattern,
+            match_field: TagRuleField::parse(&rule.match_field).unwrap_or(TagRuleField::Title),
+            tag_id: rule.tag_id,
+            tag_slug: rule.tag_slug,
+            tag_name: rule.tag_name,
+            enabled: rule.enabled,
+            created_by: rule.created_by,
+            created_at: rule.created_at,
+            updated_at: rule.updated_at,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+// Request DTOs
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RenameTagRequest {
+    /// 新しいタグ名。スラッグはこの名前から再生成されます
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Tag name must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+}
+
+// Response DTOs
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagResponse {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    /// このタグが付いたスレッド数
+    pub thread_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// タグ統合の結果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagMergeResponse {
+    /// 統合先のタグ（統合後の状態）
+    pub tag: TagResponse,
+    /// 統合元から付け替えたスレッド数（統合先のタグが既に付いていたスレッドは含まない）
+    pub moved_threads: u64,
+}
+
+// Database entities
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TagResponse {
+    pub fn new(tag: Tag, thread_count: i64) -> Self {
+        Self {
+            id: tag.id,
+            slug: tag.slug,
+            name: tag.name,
+            thread_count,
+            created_at: tag.created_at,
+            updated_at: tag.updated_at,
+        }
+    }
+}
@@ -1,52 +1,235 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-use super::common::PaginatedResponse;
+use super::{
+    comments::CommentUser,
+    common::{ApiWarning, PaginatedResponse},
+    users,
+};
+use crate::{
+    utils::{markdown, presence::PresenceSnapshot},
+    validations::content,
+};
+
+/// スレッド一覧の並び順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadSort {
+    /// 新しい順
+    #[default]
+    New,
+    /// スコア（高評価数 - 低評価数）の高い順
+    Top,
+    /// 最近の動きがある順（スレッドの作成日時と最新のコメントの作成日時のうち新しい方）
+    Active,
+}
+
+impl ThreadSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreadSort::New => "new",
+            ThreadSort::Top => "top",
+            ThreadSort::Active => "active",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new" => Some(ThreadSort::New),
+            "top" => Some(ThreadSort::Top),
+            "active" => Some(ThreadSort::Active),
+            _ => None,
+        }
+    }
+}
+
+/// スレッド一覧の絞り込み（ログイン中のみ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadFilter {
+    /// 最後に読んだ後に動きが無いスレッドを除く（既読位置が最終アクティビティ日時以降のスレッド）
+    Unread,
+}
+
+/// ユーザーのスレッド一覧（プロフィール）の並び順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UserThreadSort {
+    /// 新しい順
+    #[default]
+    New,
+    /// スコア（高評価数 - 低評価数）の高い順。スレッド一覧の `top` と同じ集計値を使う
+    Top,
+    /// コメント数の多い順
+    Comments,
+}
+
+impl UserThreadSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserThreadSort::New => "new",
+            UserThreadSort::Top => "top",
+            UserThreadSort::Comments => "comments",
+        }
+    }
+}
+
+/// スコア順で集計する期間
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TopWindow {
+    Day,
+    Week,
+    Month,
+    Year,
+    /// 全期間
+    #[default]
+    All,
+}
+
+impl TopWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TopWindow::Day => "day",
+            TopWindow::Week => "week",
+            TopWindow::Month => "month",
+            TopWindow::Year => "year",
+            TopWindow::All => "all",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "day" => Some(TopWindow::Day),
+            "week" => Some(TopWindow::Week),
+            "month" => Some(TopWindow::Month),
+            "year" => Some(TopWindow::Year),
+            "all" => Some(TopWindow::All),
+            _ => None,
+        }
+    }
+
+    /// 集計対象とするスレッドの作成日時の下限（全期間の場合はNone）
+    pub fn since(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            TopWindow::Day => Some(now - Duration::days(1)),
+            TopWindow::Week => Some(now - Duration::weeks(1)),
+            TopWindow::Month => Some(now - Duration::days(30)),
+            TopWindow::Year => Some(now - Duration::days(365)),
+            TopWindow::All => None,
+        }
+    }
+}
+
+/// スレッドごとに残す編集履歴の件数（超えた場合は古い版から削除する）
+pub const MAX_REVISIONS: i64 = 20;
 
 // Request DTOs
 
+/// タイトルは前後の空白を取り除き、本文は改行を LF に揃えてから検証・保存する
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateThreadRequest {
-    #[validate(length(
-        min = 1,
-        max = 50,
-        message = "Title must be between 1 and 50 characters"
-    ))]
+    /// 前後の空白を除いて 1〜300 文字
+    #[serde(deserialize_with = "content::deserialize_title")]
+    #[validate(custom(function = "content::validate_thread_title"))]
     pub title: String,
 
-    #[validate(length(max = 1000, message = "Content must be less than 1000 characters"))]
+    /// 40,000 文字まで（空白だけの本文は不可）
+    #[serde(default, deserialize_with = "content::deserialize_content_optional")]
+    #[validate(custom(function = "content::validate_thread_content_optional"))]
     pub content: Option<String>,
+
+    /// クライアントが付ける任意の識別子（64 文字まで。保存せず、作成時のレスポンスにそのまま含める）
+    #[serde(default)]
+    #[validate(length(max = 64, message = "client_nonce must be at most 64 characters"))]
+    pub client_nonce: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateThreadRequest {
-    #[validate(length(
-        min = 1,
-        max = 50,
-        message = "Title must be between 1 and 50 characters"
-    ))]
+    /// 前後の空白を除いて 1〜300 文字
+    #[serde(default, deserialize_with = "content::deserialize_title_optional")]
+    #[validate(custom(function = "content::validate_thread_title_optional"))]
     pub title: Option<String>,
 
-    #[validate(length(max = 1000, message = "Content must be less than 1000 characters"))]
+    /// 40,000 文字まで（空白だけの本文は不可）
+    #[serde(default, deserialize_with = "content::deserialize_content_optional")]
+    #[validate(custom(function = "content::validate_thread_content_optional"))]
     pub content: Option<String>,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateReadPositionRequest {
+    /// 最後に読んだコメントのID（省略時は現在時刻までを既読とする）
+    pub last_read_comment_id: Option<Uuid>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PresenceRequest {
+    /// 人数にのみ含め、ユーザー名を他の閲覧者に表示しない（省略時は false）
+    pub anonymous: Option<bool>,
+}
+
 // Response DTOs
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ThreadResponse {
     pub id: Uuid,
+    /// パーマリンク用の短いID
+    pub short_id: String,
     pub title: String,
     pub content: Option<String>,
+    /// 本文の Markdown を変換した HTML（許可したタグのみ。レスポンスを作るたびに変換する）
+    pub content_html: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user: ThreadUser,
     pub comment_count: u64,
+    /// 残っている編集履歴の件数（`GET /api/threads/{id}/revisions`）
+    pub revision_count: u64,
     pub upvote_count: i32,
     pub downvote_count: i32,
+    /// ロック中は新しいコメントを投稿できない
+    pub locked: bool,
+    /// 認証済みユーザーが最後に読んだコメントのID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_read_comment_id: Option<Uuid>,
+    /// 認証済みユーザーの未読コメント数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unread_comment_count: Option<u64>,
+    /// 認証済みユーザーがブックマークしているか
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bookmarked: Option<bool>,
+    /// 認証済みユーザーの投票（`upvote` / `downvote`。詳細のみ。投票していない場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_vote: Option<String>,
+    /// 作成リクエストで指定された `client_nonce`（作成時のレスポンスのみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_nonce: Option<String>,
+    /// 作成・更新時の警告（上限に近い文字数など）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ApiWarning>,
+}
+
+/// 作成前のプレビュー（保存はしない）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadPreviewResponse {
+    /// 常に true（作成したスレッドと区別するため）
+    pub preview: bool,
+    /// 作成した場合のスレッド（ID・短いIDは未定のため空）
+    pub thread: ThreadResponse,
+    /// 自動タグ付けルールで付くタグ
+    pub tags: Vec<ThreadPreviewTag>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadPreviewTag {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -57,6 +240,65 @@ pub struct ThreadUser {
     pub avatar_url: Option<String>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadPositionResponse {
+    pub thread_id: Uuid,
+    pub last_read_comment_id: Option<Uuid>,
+    pub last_read_at: DateTime<Utc>,
+    pub unread_comment_count: u64,
+}
+
+/// スレッドを閲覧中のユーザー
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PresenceResponse {
+    /// 匿名で閲覧しているユーザーを含む人数
+    pub viewer_count: u64,
+    /// 匿名でない閲覧者のユーザー名（名前順に最大5件）
+    pub usernames: Vec<String>,
+}
+
+impl From<PresenceSnapshot> for PresenceResponse {
+    fn from(snapshot: PresenceSnapshot) -> Self {
+        Self {
+            viewer_count: snapshot.viewer_count as u64,
+            usernames: snapshot.usernames,
+        }
+    }
+}
+
+/// スレッドでよくコメントしているユーザー
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadContributor {
+    pub user: CommentUser,
+    /// 削除・非表示にされたコメントを除いたコメント数
+    pub comment_count: i64,
+    /// 最初にコメントした日時（コメント数が同じ場合はこれが早い順）
+    pub first_commented_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadContributorsResponse {
+    pub contributors: Vec<ThreadContributor>,
+}
+
+/// スレッドの過去の版（更新される前のタイトル・本文）
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ThreadRevision {
+    pub id: Uuid,
+    pub title: String,
+    pub content: Option<String>,
+    /// この版を置き換えた更新を行ったユーザー（退会した場合は null）
+    pub edited_by: Option<Uuid>,
+    /// 更新された日時
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadRevisionListResponse {
+    /// 古い順
+    pub revisions: Vec<ThreadRevision>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ThreadListResponse {
     #[schema(value_type = PaginatedResponse<ThreadResponse>)]
@@ -65,16 +307,24 @@ pub struct ThreadListResponse {
 
 // Database query result structs
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct ThreadReadPosition {
+    pub last_read_comment_id: Option<Uuid>,
+    pub last_read_at: DateTime<Utc>,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct ThreadWithUser {
     // Thread fields
     pub id: Uuid,
+    pub short_id: String,
     pub title: String,
     pub content: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub upvote_count: i32,
     pub downvote_count: i32,
+    pub locked_at: Option<DateTime<Utc>>,
 
     // User fields
     pub user_id: Uuid,
@@ -84,25 +334,39 @@ pub struct ThreadWithUser {
 
     // Comment count
     pub comment_count: Option<i64>,
+
+    // 編集履歴の件数（作成直後のスレッドなどでは省略できる）
+    #[sqlx(default)]
+    pub revision_count: i64,
 }
 
 impl From<ThreadWithUser> for ThreadResponse {
     fn from(thread: ThreadWithUser) -> Self {
         Self {
             id: thread.id,
+            short_id: thread.short_id,
             title: thread.title,
+            content_html: thread.content.as_deref().map(markdown::to_html),
             content: thread.content,
             created_at: thread.created_at,
             updated_at: thread.updated_at,
             user: ThreadUser {
                 id: thread.user_id,
-                username: thread.username,
+                username: users::display_username(thread.username),
                 display_name: thread.user_display_name,
                 avatar_url: thread.user_avatar_url,
             },
             comment_count: thread.comment_count.unwrap_or(0) as u64,
+            revision_count: thread.revision_count as u64,
             upvote_count: thread.upvote_count,
             downvote_count: thread.downvote_count,
+            locked: thread.locked_at.is_some(),
+            last_read_comment_id: None,
+            unread_comment_count: None,
+            bookmarked: None,
+            user_vote: None,
+            client_nonce: None,
+            warnings: Vec::new(),
         }
     }
 }
@@ -1,10 +1,48 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::validations::username;
+use super::{
+    comments::CommentSort,
+    common::PaginatedResponse,
+    moderation::MuteStatus,
+    threads::{ThreadSort, TopWindow},
+};
+use crate::{
+    utils::markdown,
+    validations::{content, profile, username},
+};
+
+/// ユーザーごとの並び順の既定値（未設定の項目はNone）
+///
+/// 一覧取得時はクエリパラメータ > ユーザー設定 > サーバーの既定値の順に優先します。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortPreferences {
+    pub default_sort: Option<ThreadSort>,
+    pub default_window: Option<TopWindow>,
+    pub default_comment_sort: Option<CommentSort>,
+}
+
+impl SortPreferences {
+    /// スレッド一覧の並び順と集計期間を決める
+    pub fn thread_sort(
+        &self,
+        sort: Option<ThreadSort>,
+        window: Option<TopWindow>,
+    ) -> (ThreadSort, TopWindow) {
+        (
+            sort.or(self.default_sort).unwrap_or_default(),
+            window.or(self.default_window).unwrap_or_default(),
+        )
+    }
+
+    /// コメント一覧の並び順を決める
+    pub fn comment_sort(&self, sort: Option<CommentSort>) -> CommentSort {
+        sort.or(self.default_comment_sort).unwrap_or_default()
+    }
+}
 
 // Request DTOs
 
@@ -18,6 +56,37 @@ pub struct UpdateProfileRequest {
 
     #[validate(url(message = "Avatar URL must be a valid URL"))]
     pub avatar_url: Option<String>,
+
+    /// 自己紹介（Markdown。500文字まで。空文字列で削除）
+    #[serde(default, deserialize_with = "content::deserialize_content_optional")]
+    #[validate(length(max = 500, message = "Bio must be 500 characters or less"))]
+    pub bio: Option<String>,
+
+    /// ウェブサイトのURL（http / https のみ。空文字列で削除）
+    #[validate(custom(function = "profile::website_url_optional_validator"))]
+    pub website_url: Option<String>,
+}
+
+/// アバター画像の縮小
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct AvatarQuery {
+    /// 正方形の一辺のピクセル数（32 / 64 / 128 / 256。省略時は 64）
+    pub size: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct AdminUserQuery {
+    /// 権限で絞り込む
+    #[param(inline)]
+    pub role: Option<UserRole>,
+}
+
+/// 退会
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct DeleteUserQuery {
+    /// スレッド・コメントも削除する（省略時は false で、投稿者を `[deleted]` として投稿を残す）
+    #[serde(default)]
+    pub purge: bool,
 }
 
 // Response DTOs
@@ -29,10 +98,37 @@ pub struct UserResponse {
     pub email: String,
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
+    /// 自己紹介（Markdown）
+    pub bio: Option<String>,
+    /// 自己紹介を表示用に変換したHTML
+    pub bio_html: Option<String>,
+    pub website_url: Option<String>,
     pub email_verified: bool,
     pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: UserRole,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 本人がミュート中の場合のみ含まれる
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mute: Option<MuteStatus>,
+}
+
+/// 管理者向けのユーザー一覧の行
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct AdminUserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub role: UserRole,
+    pub email_verified: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserListResponse {
+    #[schema(value_type = PaginatedResponse<AdminUserResponse>)]
+    pub users: PaginatedResponse<AdminUserResponse>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -41,35 +137,169 @@ pub struct PublicUserResponse {
     pub username: String,
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
+    /// 自己紹介（Markdown）
+    pub bio: Option<String>,
+    /// 自己紹介を表示用に変換したHTML
+    pub bio_html: Option<String>,
+    pub website_url: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// このユーザーをフォローしているユーザーの数
+    pub follower_count: u64,
+    /// このユーザーがフォローしているユーザーの数
+    pub following_count: u64,
+    /// 投稿したスレッドの数
+    pub thread_count: u64,
+    /// 投稿したコメントの数（削除・非表示にされたコメントを除く）
+    pub comment_count: u64,
+    /// スレッドが受けた賛成票から反対票を引いた数の合計
+    pub karma: i64,
+    /// 変更前のユーザー名で取得した場合の現在のユーザー名（クライアントはこちらへ移動する）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_username: Option<String>,
 }
 
-use crate::models::User;
+/// フォロワー・フォロー中のユーザーの数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FollowCounts {
+    pub followers: i64,
+    pub following: i64,
+}
+
+/// プロフィールに表示する投稿の統計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct UserStats {
+    pub thread_count: i64,
+    pub comment_count: i64,
+    pub karma: i64,
+}
+
+/// フォロワー・フォロー中の一覧の行
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct FollowUserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    /// フォローした日時
+    pub followed_at: DateTime<Utc>,
+}
+
+/// ブロックしているユーザー
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct BlockedUserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    /// ブロックした日時
+    pub blocked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BlockListResponse {
+    pub blocks: Vec<BlockedUserResponse>,
+}
+
+use crate::models::{User, UserRole};
+
+/// 退会して匿名化されたユーザーの表示上のユーザー名
+pub const DELETED_USERNAME: &str = "[deleted]";
+
+/// 匿名化したユーザーの行に設定するユーザー名
+///
+/// ユーザー名は一意のためIDを付けます。`[` は通常のユーザー名に使えないため、他のユーザーと重なりません。
+pub fn anonymized_username(user_id: Uuid) -> String {
+    format!("{}{}", DELETED_USERNAME, user_id.simple())
+}
+
+/// 表示用のユーザー名（匿名化されたユーザーは `[deleted]` にする）
+pub fn display_username(username: String) -> String {
+    if username.starts_with(DELETED_USERNAME) {
+        DELETED_USERNAME.to_string()
+    } else {
+        username
+    }
+}
+
+/// アバター画像が未設定のユーザー向けの自動生成アバターのURL（`api_url` は `Config::api_url`）
+pub fn default_avatar_url(api_url: &str, username: &str) -> String {
+    format!("{}/api/users/{}/avatar.png", api_url, username)
+}
+
+impl UserResponse {
+    pub fn new(user: User, api_url: &str) -> Self {
+        let avatar_url = user
+            .avatar_url
+            .unwrap_or_else(|| default_avatar_url(api_url, &user.username));
 
-impl From<User> for UserResponse {
-    fn from(user: User) -> Self {
         Self {
             id: user.id,
             username: user.username,
             email: user.email,
             display_name: user.display_name,
-            avatar_url: user.avatar_url,
+            avatar_url: Some(avatar_url),
+            bio_html: user.bio.as_deref().map(markdown::to_html),
+            bio: user.bio,
+            website_url: user.website_url,
             email_verified: user.email_verified,
             email_verified_at: user.email_verified_at,
+            role: user.role,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            mute: None,
         }
     }
 }
 
-impl From<User> for PublicUserResponse {
-    fn from(user: User) -> Self {
+impl PublicUserResponse {
+    pub fn new(user: User, counts: FollowCounts, stats: UserStats, api_url: &str) -> Self {
+        let avatar_url = user
+            .avatar_url
+            .unwrap_or_else(|| default_avatar_url(api_url, &user.username));
+
         Self {
             id: user.id,
             username: user.username,
             display_name: user.display_name,
-            avatar_url: user.avatar_url,
+            avatar_url: Some(avatar_url),
+            bio_html: user.bio.as_deref().map(markdown::to_html),
+            bio: user.bio,
+            website_url: user.website_url,
             created_at: user.created_at,
+            follower_count: counts.followers as u64,
+            following_count: counts.following as u64,
+            thread_count: stats.thread_count as u64,
+            comment_count: stats.comment_count as u64,
+            karma: stats.karma,
+            canonical_username: None,
+        }
+    }
+}
+
+impl BlockedUserResponse {
+    /// アバター画像が未設定の場合は自動生成アバターのURLにする
+    pub fn with_default_avatar(self, api_url: &str) -> Self {
+        let avatar_url = self
+            .avatar_url
+            .unwrap_or_else(|| default_avatar_url(api_url, &self.username));
+
+        Self {
+            avatar_url: Some(avatar_url),
+            ..self
+        }
+    }
+}
+
+impl FollowUserResponse {
+    /// アバター画像が未設定の場合は自動生成アバターのURLにする
+    pub fn with_default_avatar(self, api_url: &str) -> Self {
+        let avatar_url = self
+            .avatar_url
+            .unwrap_or_else(|| default_avatar_url(api_url, &self.username));
+
+        Self {
+            avatar_url: Some(avatar_url),
+            ..self
         }
     }
 }
@@ -0,0 +1,329 @@
+// 一覧APIのページング
+//
+// 1ページあたりの件数の既定値と上限はエンドポイントのグループごとに `ContentLimits` で設定し、
+// `Pagination<G>` エクストラクターがクエリの `page`・`limit`（・`offset`）をその範囲に収めます。
+// OpenAPIドキュメントの説明にも、起動時の設定値を `PageSizeDocs` で書き込みます。
+use std::{marker::PhantomData, sync::Arc};
+
+use axum::{
+    extract::{FromRef, FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterBuilder, ParameterIn},
+        schema::{KnownFormat, ObjectBuilder, SchemaFormat, Type},
+        OpenApi, Required,
+    },
+    IntoParams, Modify,
+};
+
+use crate::{
+    config::{Config, ContentLimits, PageSize},
+    error::AppError,
+};
+
+/// ページングの設定を共有するエンドポイントのグループ
+pub trait PageGroup {
+    /// このグループの件数の設定
+    fn page_size(limits: &ContentLimits) -> PageSize;
+
+    /// `page` の代わりに `offset` で開始位置を指定できるか
+    const ACCEPTS_OFFSET: bool = false;
+}
+
+/// スレッド一覧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threads;
+
+/// スレッドのコメント一覧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comments;
+
+/// ユーザーごとのスレッド・コメント一覧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserListings;
+
+/// 管理用の一覧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdminListings;
+
+impl PageGroup for Threads {
+    fn page_size(limits: &ContentLimits) -> PageSize {
+        limits.threads
+    }
+}
+
+impl PageGroup for Comments {
+    fn page_size(limits: &ContentLimits) -> PageSize {
+        limits.comments
+    }
+}
+
+impl PageGroup for UserListings {
+    fn page_size(limits: &ContentLimits) -> PageSize {
+        limits.user_listings
+    }
+
+    const ACCEPTS_OFFSET: bool = true;
+}
+
+impl PageGroup for AdminListings {
+    fn page_size(limits: &ContentLimits) -> PageSize {
+        limits.admin_listings
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawPagination {
+    page: Option<u32>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+/// 設定の範囲に収めたページング
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination<G> {
+    /// 1から始まるページ番号
+    pub page: u32,
+    /// 1ページあたりの件数（1以上、設定の上限以下）
+    pub limit: u32,
+    /// 読み飛ばす件数（`offset` の指定が無ければ `page` から計算する）
+    pub offset: i64,
+    /// `page` か `limit` が明示的に指定されたか
+    pub explicit: bool,
+    group: PhantomData<G>,
+}
+
+impl<G: PageGroup> Pagination<G> {
+    /// クエリの値を設定の範囲に収める
+    ///
+    /// `limit` は 1 から上限までに切り詰め、省略時は既定値を使います。`page` は 1 未満なら 1 にします。
+    pub fn new(
+        page: Option<u32>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        limits: &ContentLimits,
+    ) -> Self {
+        let size = G::page_size(limits);
+        let explicit = page.is_some() || limit.is_some();
+        let page = page.unwrap_or(1).max(1);
+        let limit = limit.unwrap_or(size.default).clamp(1, size.max);
+        let offset = match offset.filter(|_| G::ACCEPTS_OFFSET) {
+            Some(offset) => offset as i64,
+            None => (page - 1) as i64 * limit as i64,
+        };
+
+        Self {
+            page,
+            limit,
+            offset,
+            explicit,
+            group: PhantomData,
+        }
+    }
+}
+
+impl<S, G> FromRequestParts<S> for Pagination<G>
+where
+    S: Send + Sync,
+    Arc<Config>: FromRef<S>,
+    G: PageGroup,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<RawPagination>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| AppError::BadRequest(err.body_text()))?;
+        let config = Arc::<Config>::from_ref(state);
+
+        Ok(Self::new(
+            raw.page,
+            raw.limit,
+            raw.offset,
+            &config.content_limits,
+        ))
+    }
+}
+
+/// ページングのパラメーターの説明（件数は `PageSizeDocs` が設定値で置き換える）
+const LIMIT_DESCRIPTION: &str = "Number of items per page";
+
+impl<G: PageGroup> IntoParams for Pagination<G> {
+    fn into_params(parameter_in_provider: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        let parameter = |name: &str, description: &str| {
+            ParameterBuilder::new()
+                .name(name)
+                .parameter_in(parameter_in_provider().unwrap_or(ParameterIn::Query))
+                .required(Required::False)
+                .description(Some(description))
+                .schema(Some(
+                    ObjectBuilder::new()
+                        .schema_type(Type::Integer)
+                        .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int32)))
+                        .minimum(Some(if name == "offset" { 0 } else { 1 })),
+                ))
+                .build()
+        };
+
+        let mut params = vec![
+            parameter("page", "Page number (default: 1)"),
+            parameter("limit", LIMIT_DESCRIPTION),
+        ];
+        if G::ACCEPTS_OFFSET {
+            params.push(parameter(
+                "offset",
+                "Number of items to skip (takes precedence over page)",
+            ));
+        }
+        params
+    }
+}
+
+/// パスからページングのグループの設定を求める
+fn page_size_for_path(path: &str, limits: &ContentLimits) -> Option<PageSize> {
+    if path.starts_with("/api/admin/") {
+        Some(limits.admin_listings)
+    } else if path.starts_with("/api/users/") {
+        Some(limits.user_listings)
+    } else if path.starts_with("/api/threads/") && path.ends_with("/comments") {
+        Some(limits.comments)
+    } else if path == "/api/threads" {
+        Some(limits.threads)
+    } else {
+        None
+    }
+}
+
+/// OpenAPIドキュメントの `limit` の説明に、設定された既定値と上限を書き込む
+pub struct PageSizeDocs<'a>(pub &'a ContentLimits);
+
+impl Modify for PageSizeDocs<'_> {
+    fn modify(&self, openapi: &mut OpenApi) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let Some(size) = page_size_for_path(path, self.0) else {
+                continue;
+            };
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                let params = operation.parameters.iter_mut().flatten();
+                for param in params.filter(|param| param.name == "limit") {
+                    param.description = Some(format!(
+                        "{} (default: {}, max: {}; larger values are clamped)",
+                        LIMIT_DESCRIPTION, size.default, size.max
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_config, test_openapi};
+    use axum::{body::Body, http::Request};
+
+    fn custom_limits() -> ContentLimits {
+        ContentLimits {
+            threads: PageSize {
+                default: 10,
+                max: 25,
+            },
+            ..ContentLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_省略時は設定の既定値を使う() {
+        // 省略時は設定の既定値を使うことを確認
+        let pagination = Pagination::<Threads>::new(None, None, None, &custom_limits());
+
+        assert_eq!(pagination.page, 1);
+        assert_eq!(pagination.limit, 10);
+        assert_eq!(pagination.offset, 0);
+        assert!(!pagination.explicit);
+    }
+
+    #[test]
+    fn test_上限を超えるlimitは設定の上限に切り詰められる() {
+        // 上限を超えるlimitは設定の上限に切り詰められることを確認
+        let limits = custom_limits();
+
+        let pagination = Pagination::<Threads>::new(Some(3), Some(1000), None, &limits);
+        assert_eq!(pagination.limit, 25);
+        assert_eq!(pagination.offset, 50);
+
+        let pagination = Pagination::<Threads>::new(Some(0), Some(0), None, &limits);
+        assert_eq!(pagination.page, 1);
+        assert_eq!(pagination.limit, 1);
+
+        // 他のグループは既定の設定のまま
+        let pagination = Pagination::<AdminListings>::new(None, Some(1000), None, &limits);
+        assert_eq!(pagination.limit, 100);
+    }
+
+    #[test]
+    fn test_offsetはユーザーの一覧でのみ使われる() {
+        // offsetはユーザーの一覧でのみ使われることを確認
+        let limits = ContentLimits::default();
+
+        let pagination = Pagination::<UserListings>::new(Some(2), Some(5), Some(3), &limits);
+        assert_eq!(pagination.offset, 3);
+
+        let pagination = Pagination::<Threads>::new(Some(2), Some(5), Some(3), &limits);
+        assert_eq!(pagination.offset, 5);
+    }
+
+    #[tokio::test]
+    async fn test_エクストラクターは設定を読み込んでクエリを切り詰める() {
+        // エクストラクターが設定を読み込んでクエリを切り詰めることを確認
+        let config = Arc::new(crate::config::Config {
+            content_limits: custom_limits(),
+            ..test_config()
+        });
+        let (mut parts, _) = Request::builder()
+            .uri("/api/threads?page=2&limit=50")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+
+        let pagination = Pagination::<Threads>::from_request_parts(&mut parts, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(pagination.page, 2);
+        assert_eq!(pagination.limit, 25);
+        assert!(pagination.explicit);
+    }
+
+    #[tokio::test]
+    async fn test_ドキュメントに設定された件数が記載される() {
+        // ドキュメントに設定された件数が記載されることを確認
+        let mut openapi = test_openapi();
+        PageSizeDocs(&custom_limits()).modify(&mut openapi);
+
+        let limit_description = |path: &str| {
+            openapi.paths.paths[path]
+                .get
+                .as_ref()
+                .unwrap()
+                .parameters
+                .iter()
+                .flatten()
+                .find(|param| param.name == "limit")
+                .and_then(|param| param.description.clone())
+                .unwrap()
+        };
+        assert!(limit_description("/api/threads").contains("default: 10, max: 25"));
+        assert!(limit_description("/api/admin/users").contains("default: 50, max: 100"));
+        assert!(limit_description("/api/users/{user_id}/threads").contains("default: 10, max: 100"));
+    }
+}
@@ -0,0 +1,155 @@
+// ハンドラー内のパニックの扱い
+//
+// パニックしたリクエストは接続を切らずに `AppError` と同じ形式の500を返し、
+// パニックの内容とバックトレースを ERROR で記録したうえで件数をカウントします。
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::FutureExt;
+use serde_json::json;
+
+use crate::middleware::RequestId;
+
+static PANICS: AtomicU64 = AtomicU64::new(0);
+
+/// 起動してからハンドラーでパニックした件数（`panics_total`）
+pub fn panic_count() -> u64 {
+    PANICS.load(Ordering::Relaxed)
+}
+
+/// パニック発生時にメッセージと発生箇所、バックトレースを ERROR で記録するフックを設定する
+/// フックはパニックしたスレッドで実行されるため、リクエストのスパン内で記録されます
+pub fn install_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        tracing::error!(
+            "Panic at {}: {}\n{}",
+            location,
+            payload_message(info.payload()),
+            Backtrace::force_capture()
+        );
+    }));
+}
+
+/// ハンドラーのパニックを捕捉して500のJSONを返すミドルウェア
+/// `request_id_middleware` の内側に置き、本文にリクエストIDを含めます
+pub async fn catch_panic_middleware(request: Request<Body>, next: Next) -> Response {
+    let request_id = request.extensions().get::<RequestId>().cloned();
+
+    match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+        Ok(response) => response,
+        Err(payload) => {
+            PANICS.fetch_add(1, Ordering::Relaxed);
+            let request_id = request_id.map(|id| id.0);
+            tracing::error!(
+                request_id = request_id.as_deref().unwrap_or("-"),
+                "Handler panicked: {}",
+                payload_message(payload.as_ref())
+            );
+
+            let status = StatusCode::INTERNAL_SERVER_ERROR;
+            let mut body = json!({
+                "error": "Internal server error",
+                "status": status.as_u16()
+            });
+            if let Some(request_id) = request_id {
+                body["request_id"] = json!(request_id);
+            }
+            (status, Json(body)).into_response()
+        }
+    }
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "non-string panic payload"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{request_id_middleware, REQUEST_ID_HEADER};
+    use axum::{middleware::from_fn, routing::get, Router};
+    use tower::ServiceExt;
+
+    // テスト専用のパニックするハンドラー
+    async fn panicking_handler() -> &'static str {
+        panic!("deliberate panic")
+    }
+
+    fn test_router() -> Router {
+        Router::new()
+            .route("/panic", get(panicking_handler))
+            .route("/ok", get(|| async { "ok" }))
+            .layer(from_fn(catch_panic_middleware))
+            .layer(from_fn(request_id_middleware))
+    }
+
+    #[tokio::test]
+    async fn test_パニックしたリクエストはjsonの500を返す() {
+        // パニックしたリクエストはJSONの500を返すことを確認
+        let before = panic_count();
+
+        let response = test_router()
+            .oneshot(
+                Request::builder()
+                    .uri("/panic")
+                    .header(REQUEST_ID_HEADER, "req-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers().get(REQUEST_ID_HEADER).unwrap(),
+            "req-123"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "error": "Internal server error",
+                "status": 500,
+                "request_id": "req-123"
+            })
+        );
+        assert!(panic_count() > before);
+    }
+
+    #[tokio::test]
+    async fn test_パニックしないリクエストはそのまま返す() {
+        // パニックしないリクエストはそのまま返すことを確認
+        let response = test_router()
+            .oneshot(Request::builder().uri("/ok").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        // リクエストIDが無い場合は生成して返す
+        assert!(response.headers().get(REQUEST_ID_HEADER).is_some());
+    }
+}
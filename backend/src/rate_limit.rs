@@ -0,0 +1,465 @@
+// ログインやパスワードリセットなどの連続リクエストを制限するレートリミッター
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{self, Body},
+    extract::{ConnectInfo, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use dashmap::DashMap;
+
+use crate::{error::AppError, models::User, utils::request_info};
+
+/// メールアドレス抽出のために読み込むリクエストボディの上限（バイト）
+const MAX_INSPECTED_BODY_BYTES: usize = 64 * 1024;
+
+/// 期限切れエントリを掃除する閾値（エントリ数）
+const PURGE_THRESHOLD: usize = 10_000;
+
+struct RateLimitEntry {
+    count: u32,
+    window_start: Instant,
+}
+
+/// 固定ウィンドウ方式のインメモリカウンター
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    entries: DashMap<String, RateLimitEntry>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            entries: DashMap::new(),
+        }
+    }
+
+    /// キーに対するリクエストを記録する
+    /// 上限を超えている場合は次に許可されるまでの時間を返す
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if self.entries.len() > PURGE_THRESHOLD {
+            self.purge_expired(now);
+        }
+
+        let mut entry = self
+            .entries
+            .entry(key.to_string())
+            .or_insert(RateLimitEntry {
+                count: 0,
+                window_start: now,
+            });
+
+        // ウィンドウが過ぎていればカウントをリセット
+        let elapsed = now.saturating_duration_since(entry.window_start);
+        if elapsed >= self.window {
+            entry.count = 0;
+            entry.window_start = now;
+        }
+
+        if entry.count >= self.max_requests {
+            let elapsed = now.saturating_duration_since(entry.window_start);
+            return Err(self.window.saturating_sub(elapsed));
+        }
+
+        entry.count += 1;
+        Ok(())
+    }
+
+    /// ウィンドウが終了したエントリを削除する
+    fn purge_expired(&self, now: Instant) {
+        self.entries
+            .retain(|_, entry| now.saturating_duration_since(entry.window_start) < self.window);
+    }
+}
+
+/// 回数を数える単位
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RateLimitKey {
+    Ip,
+    IpAndEmail,
+    /// 認証済みのユーザー（認証ミドルウェアの内側で使う）
+    User,
+}
+
+/// ルートごとのレートリミット設定
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: Arc<RateLimiter>,
+    key: RateLimitKey,
+    /// X-Forwarded-For を信頼するリバースプロキシ
+    trusted_proxies: Arc<[IpAddr]>,
+}
+
+impl RateLimit {
+    fn new(max_requests: u32, window: Duration, key: RateLimitKey) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(max_requests, window)),
+            key,
+            trusted_proxies: Arc::from([]),
+        }
+    }
+
+    /// 接続元がこれらのプロキシの場合に限り、X-Forwarded-For からクライアントのIPアドレスを決める
+    pub fn trusting(mut self, proxies: &[IpAddr]) -> Self {
+        self.trusted_proxies = Arc::from(proxies);
+        self
+    }
+
+    /// IPアドレスのみをキーにする
+    pub fn per_ip(max_requests: u32, window: Duration) -> Self {
+        Self::new(max_requests, window, RateLimitKey::Ip)
+    }
+
+    /// IPアドレスに加えてリクエストボディのメールアドレスもキーにする
+    pub fn per_ip_and_email(max_requests: u32, window: Duration) -> Self {
+        Self::new(max_requests, window, RateLimitKey::IpAndEmail)
+    }
+
+    /// 認証済みのユーザーをキーにする（ユーザーが無い場合はIPアドレス）
+    pub fn per_user(max_requests: u32, window: Duration) -> Self {
+        Self::new(max_requests, window, RateLimitKey::User)
+    }
+}
+
+pub async fn rate_limit_middleware(
+    State(rate_limit): State<RateLimit>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    // クライアントが書き換えられるヘッダーではなく、接続元のアドレスを基準にする
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| {
+            request_info::trusted_client_ip(
+                addr.ip(),
+                request.headers(),
+                &rate_limit.trusted_proxies,
+            )
+            .to_string()
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let user_id = request.extensions().get::<User>().map(|user| user.id);
+    let mut keys = match (rate_limit.key, user_id) {
+        (RateLimitKey::User, Some(user_id)) => vec![format!("user:{}", user_id)],
+        _ => vec![format!("ip:{}", ip)],
+    };
+
+    // メールアドレスを取得するためにボディを読み込み、ハンドラー用に組み立て直す
+    let request = if rate_limit.key == RateLimitKey::IpAndEmail {
+        let (parts, body) = request.into_parts();
+        let bytes = body::to_bytes(body, MAX_INSPECTED_BODY_BYTES)
+            .await
+            .map_err(|_| AppError::BadRequest("Request body too large".to_string()))?;
+
+        if let Some(email) = extract_email(&bytes) {
+            keys.push(format!("email:{}", email));
+        }
+
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        request
+    };
+
+    for key in &keys {
+        if let Err(retry_after) = rate_limit.limiter.check(key) {
+            tracing::warn!("Rate limit exceeded for {}", key);
+            return Err(AppError::TooManyRequests(retry_after_seconds(retry_after)));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn extract_email(bytes: &[u8]) -> Option<String> {
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .ok()?
+        .get("email")?
+        .as_str()
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| !email.is_empty())
+}
+
+/// Retry-Afterヘッダー用に秒単位へ切り上げる
+fn retry_after_seconds(duration: Duration) -> u64 {
+    let secs = duration.as_secs();
+    if duration.subsec_nanos() > 0 {
+        secs + 1
+    } else {
+        secs.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header::RETRY_AFTER, StatusCode},
+        middleware::from_fn_with_state,
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn test_router(rate_limit: RateLimit) -> Router {
+        Router::new()
+            .route("/login", post(|body: String| async move { body }))
+            .layer(from_fn_with_state(rate_limit, rate_limit_middleware))
+    }
+
+    // `peer` から直接接続したリクエスト
+    async fn post_login(router: &Router, peer: &str, email: &str) -> Response {
+        post_login_via(router, peer, None, email).await
+    }
+
+    async fn post_login_via(
+        router: &Router,
+        peer: &str,
+        forwarded_for: Option<&str>,
+        email: &str,
+    ) -> Response {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/login")
+            .header("Content-Type", "application/json");
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("X-Forwarded-For", forwarded_for);
+        }
+        let mut request = request
+            .body(Body::from(
+                serde_json::json!({ "email": email, "password": "password" }).to_string(),
+            ))
+            .unwrap();
+        let peer = SocketAddr::new(peer.parse().unwrap(), 40000);
+        request.extensions_mut().insert(ConnectInfo(peer));
+
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    #[test]
+    fn test_上限までは許可され超えると拒否される() {
+        // 上限回数までは許可され、それ以降は残り時間が返ることを確認
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(limiter.check_at("key", now).is_ok());
+        assert!(limiter.check_at("key", now).is_ok());
+
+        let retry_after = limiter
+            .check_at("key", now + Duration::from_secs(10))
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(50));
+
+        // 別のキーは影響を受けない
+        assert!(limiter.check_at("other", now).is_ok());
+    }
+
+    #[test]
+    fn test_ウィンドウ経過後は再び許可される() {
+        // ウィンドウが過ぎるとカウントがリセットされることを確認
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(limiter.check_at("key", now).is_ok());
+        assert!(limiter.check_at("key", now).is_err());
+        assert!(limiter
+            .check_at("key", now + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_期限切れのエントリが削除される() {
+        // 掃除処理でウィンドウ外のエントリのみ削除されることを確認
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+
+        limiter.check_at("old", now).unwrap();
+        limiter
+            .check_at("new", now + Duration::from_secs(30))
+            .unwrap();
+        limiter.purge_expired(now + Duration::from_secs(61));
+
+        assert!(!limiter.entries.contains_key("old"));
+        assert!(limiter.entries.contains_key("new"));
+    }
+
+    #[test]
+    fn test_retry_afterは秒単位に切り上げられる() {
+        // Retry-Afterの秒数は切り上げられることを確認
+        assert_eq!(retry_after_seconds(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_seconds(Duration::from_secs(30)), 30);
+        assert_eq!(retry_after_seconds(Duration::ZERO), 1);
+    }
+
+    #[tokio::test]
+    async fn test_上限を超えると429を返しウィンドウ経過後に成功する() {
+        // 上限超過で429とRetry-Afterを返し、ウィンドウ経過後は再び通過することを確認
+        let router = test_router(RateLimit::per_ip(2, Duration::from_millis(200)));
+
+        for _ in 0..2 {
+            let response = post_login(&router, "203.0.113.1", "user@example.com").await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = post_login(&router, "203.0.113.1", "user@example.com").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "1");
+
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 429);
+        assert!(json["error"].is_string());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        let response = post_login(&router, "203.0.113.1", "user@example.com").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_メールアドレス単位でも制限される() {
+        // IPアドレスを変えても同じメールアドレスへの試行は制限されることを確認
+        let router = test_router(RateLimit::per_ip_and_email(2, Duration::from_secs(60)));
+
+        assert_eq!(
+            post_login(&router, "203.0.113.1", "User@Example.com")
+                .await
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_login(&router, "203.0.113.2", "user@example.com")
+                .await
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_login(&router, "203.0.113.3", "user@example.com")
+                .await
+                .status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // 別のメールアドレスは影響を受けない
+        assert_eq!(
+            post_login(&router, "203.0.113.3", "other@example.com")
+                .await
+                .status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_x_forwarded_forを偽装しても回数はリセットされない() {
+        // 信頼するプロキシを経由しない接続では、ヘッダーを変えても接続元のアドレスで数えることを確認
+        let router = test_router(RateLimit::per_ip(2, Duration::from_secs(60)));
+
+        for spoofed in ["198.51.100.1", "198.51.100.2"] {
+            let response =
+                post_login_via(&router, "203.0.113.1", Some(spoofed), "user@example.com").await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = post_login_via(
+            &router,
+            "203.0.113.1",
+            Some("198.51.100.3"),
+            "user@example.com",
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_信頼するプロキシ経由では右端の信頼できないアドレスで数える() {
+        // 信頼するプロキシ経由では、右端の信頼できないアドレスで数えることを確認
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let router = test_router(RateLimit::per_ip(1, Duration::from_secs(60)).trusting(&[proxy]));
+
+        // クライアントが書いた左側の値を変えても同じクライアントとして数える
+        let response = post_login_via(
+            &router,
+            "10.0.0.1",
+            Some("198.51.100.1, 203.0.113.9"),
+            "user@example.com",
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = post_login_via(
+            &router,
+            "10.0.0.1",
+            Some("198.51.100.2, 203.0.113.9"),
+            "user@example.com",
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // プロキシの後ろの別のクライアントは影響を受けない
+        let response = post_login_via(
+            &router,
+            "10.0.0.1",
+            Some("203.0.113.10"),
+            "user@example.com",
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_ボディがハンドラーに引き継がれる() {
+        // メールアドレス抽出後もハンドラーは元のボディを受け取れることを確認
+        let router = test_router(RateLimit::per_ip_and_email(5, Duration::from_secs(60)));
+
+        let response = post_login(&router, "203.0.113.1", "user@example.com").await;
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["email"], "user@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_ユーザー単位で制限される() {
+        // IPアドレスを変えても同じユーザーは制限され、別のユーザーは影響を受けないことを確認
+        let rate_limit = RateLimit::per_user(1, Duration::from_secs(60));
+        let alice = crate::test_utils::fakes::fake_user(true);
+        let bob = crate::test_utils::fakes::fake_user(true);
+        let get = |user: &User, ip: &str| {
+            let router = Router::new()
+                .route("/export", axum::routing::get(|| async { "ok" }))
+                .layer(from_fn_with_state(
+                    rate_limit.clone(),
+                    rate_limit_middleware,
+                ));
+            let mut request = Request::builder()
+                .uri("/export")
+                .header("X-Forwarded-For", ip)
+                .body(Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(user.clone());
+            async move { router.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(get(&alice, "203.0.113.1").await, StatusCode::OK);
+        assert_eq!(
+            get(&alice, "203.0.113.2").await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(get(&bob, "203.0.113.1").await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::User};
+
+/// 認証・認可の判定に必要なアクセス
+#[async_trait]
+pub trait AuthRepo: Send + Sync {
+    /// トークンの `sub` に対応するユーザーを取得
+    async fn find_user(&self, user_id: Uuid) -> Result<Option<User>, AppError>;
+}
+
+pub struct PgAuthRepo {
+    pool: PgPool,
+}
+
+impl PgAuthRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuthRepo for PgAuthRepo {
+    async fn find_user(&self, user_id: Uuid) -> Result<Option<User>, AppError> {
+        // 退会したユーザーのトークンは有効期限内でも受け付けない
+        let user =
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL")
+                .bind(user_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(user)
+    }
+}
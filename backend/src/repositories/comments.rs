@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::threads::ThreadReadPosition};
+
+/// コメントへのアクセス
+#[async_trait]
+pub trait CommentsRepo: Send + Sync {
+    /// スレッドに属するコメントの投稿日時を取得
+    async fn created_at_in_thread(
+        &self,
+        comment_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, AppError>;
+
+    /// 既読位置より後に投稿されたコメント数を数える
+    /// 既読位置が無い場合はスレッドの全コメントを未読とする
+    async fn count_unread(
+        &self,
+        thread_id: Uuid,
+        position: Option<&ThreadReadPosition>,
+    ) -> Result<u64, AppError>;
+}
+
+pub struct PgCommentsRepo {
+    pool: PgPool,
+}
+
+impl PgCommentsRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CommentsRepo for PgCommentsRepo {
+    async fn created_at_in_thread(
+        &self,
+        comment_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
+        let created_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT created_at FROM comments WHERE id = $1 AND thread_id = $2",
+        )
+        .bind(comment_id)
+        .bind(thread_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(created_at)
+    }
+
+    async fn count_unread(
+        &self,
+        thread_id: Uuid,
+        position: Option<&ThreadReadPosition>,
+    ) -> Result<u64, AppError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM comments
+            WHERE thread_id = $1
+              AND ($2::timestamptz IS NULL OR created_at > $2)
+            "#,
+        )
+        .bind(thread_id)
+        .bind(position.map(|p| p.last_read_at))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u64)
+    }
+}
@@ -0,0 +1,14 @@
+// ドメインごとのデータアクセス
+//
+// ハンドラーはここで定義するトレイト越しにデータベースへアクセスします。
+// 本番では PgPool を使う実装を、純粋なロジックのテストでは
+// `test_utils::fakes` のインメモリ実装を差し込みます。
+pub mod auth;
+pub mod comments;
+pub mod threads;
+pub mod users;
+
+pub use auth::{AuthRepo, PgAuthRepo};
+pub use comments::{CommentsRepo, PgCommentsRepo};
+pub use threads::{PgThreadsRepo, ThreadsRepo};
+pub use users::{PgUsersRepo, UsersRepo};
@@ -0,0 +1,721 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::{collections::HashSet, sync::Arc};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        common::Existence,
+        threads::{ThreadReadPosition, ThreadRevision, ThreadSort, ThreadWithUser, MAX_REVISIONS},
+        User,
+    },
+    utils::{db_error, moderation_log, short_id, tags, token_generator::TokenGenerator},
+};
+
+/// `ThreadsRepo::set_vote` で投票がどう変わったか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteChange {
+    Added,
+    Changed,
+    /// 同じ種類の投票が既にあった
+    Unchanged,
+}
+
+/// スレッドと、スレッドに紐づく投票・既読位置へのアクセス
+#[async_trait]
+pub trait ThreadsRepo: Send + Sync {
+    /// UUIDまたは短いIDからスレッドIDを取得
+    async fn resolve_id(&self, id_or_short_id: &str) -> Result<Uuid, AppError>;
+
+    /// `since` 以降に作成されたスレッドを数える（Noneの場合は全件）
+    ///
+    /// `hidden_authors` のユーザーが投稿したスレッドと、`unread_for` のユーザーが既読のスレッドは数えません。
+    async fn count(
+        &self,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<i64, AppError>;
+
+    /// 指定された並び順で投稿者情報とコメント数付きで取得
+    ///
+    /// `hidden_authors` のユーザーが投稿したスレッドと、`unread_for` のユーザーが既読のスレッドは除きます。
+    /// 既読は既読位置の日時が最終アクティビティ日時以降であることを指します。
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+        sort: ThreadSort,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<Vec<ThreadWithUser>, AppError>;
+
+    /// 投稿者情報とコメント数付きで取得
+    async fn find(&self, id: Uuid) -> Result<Option<ThreadWithUser>, AppError>;
+
+    /// 投稿者情報付きで取得（コメント数は数えず0とする）
+    async fn find_without_comment_count(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<ThreadWithUser>, AppError>;
+
+    async fn exists(&self, id: Uuid) -> Result<bool, AppError>;
+
+    /// 存在するか、モデレーターに削除されたか（投稿者情報やコメント数は読まない）
+    async fn existence(&self, id: Uuid) -> Result<Existence, AppError>;
+
+    async fn is_owned_by(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError>;
+
+    /// スレッドを作成し、指定されたタグを付ける（存在しないタグは無視する）
+    async fn create(
+        &self,
+        author: &User,
+        title: &str,
+        content: Option<&str>,
+        tag_ids: &[Uuid],
+    ) -> Result<ThreadWithUser, AppError>;
+
+    /// 更新前のタイトル・本文を編集履歴に残し、指定された項目のみ更新する
+    ///
+    /// 編集履歴は `MAX_REVISIONS` 件を超えた分を古い方から削除します。
+    async fn update(
+        &self,
+        id: Uuid,
+        edited_by: Uuid,
+        title: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<(), AppError>;
+
+    /// 編集履歴を古い順に取得
+    async fn revisions(&self, id: Uuid) -> Result<Vec<ThreadRevision>, AppError>;
+
+    /// ロック・ロック解除する（既にロック中の場合はロックした日時を変えない）
+    async fn set_locked(&self, id: Uuid, locked: bool) -> Result<(), AppError>;
+
+    /// 投稿者本人のスレッドを削除し、削除できたかを返す
+    async fn delete_owned(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError>;
+
+    /// モデレーターとしてスレッドを削除してモデレーションログに記録し、削除できたかを返す
+    async fn delete_as_moderator(
+        &self,
+        id: Uuid,
+        moderator_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<bool, AppError>;
+
+    async fn find_vote(&self, user_id: Uuid, thread_id: Uuid) -> Result<Option<String>, AppError>;
+
+    /// 投票する（既に投票している場合は種類を変える）
+    ///
+    /// 同時に呼ばれても投票は1件になるよう、読み取りと書き込みを1つの文で行います。
+    async fn set_vote(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        vote_type: &str,
+    ) -> Result<VoteChange, AppError>;
+
+    /// 投票を取り消し、取り消した投票の種類を返す（投票していない場合はNone）
+    async fn delete_vote(&self, user_id: Uuid, thread_id: Uuid)
+        -> Result<Option<String>, AppError>;
+
+    async fn read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<ThreadReadPosition>, AppError>;
+
+    /// 既読位置を保存（存在する場合は更新）
+    async fn save_read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        last_read_comment_id: Option<Uuid>,
+        last_read_at: DateTime<Utc>,
+    ) -> Result<ThreadReadPosition, AppError>;
+
+    /// ブックマークする（既にブックマーク済みの場合は何もしない）
+    async fn add_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError>;
+
+    /// ブックマークを外す（ブックマークしていない場合は何もしない）
+    async fn remove_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError>;
+
+    /// `thread_ids` のうちブックマークしているスレッドのID
+    async fn bookmarked_ids(
+        &self,
+        user_id: Uuid,
+        thread_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError>;
+
+    async fn count_bookmarks(&self, user_id: Uuid) -> Result<i64, AppError>;
+
+    /// ブックマークしたスレッドを新しくブックマークした順に取得
+    async fn list_bookmarks(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ThreadWithUser>, AppError>;
+}
+
+pub struct PgThreadsRepo {
+    pool: PgPool,
+    /// 作成時の短いIDの生成
+    tokens: Arc<dyn TokenGenerator>,
+}
+
+impl PgThreadsRepo {
+    pub fn new(pool: PgPool, tokens: Arc<dyn TokenGenerator>) -> Self {
+        Self { pool, tokens }
+    }
+}
+
+#[async_trait]
+impl ThreadsRepo for PgThreadsRepo {
+    async fn resolve_id(&self, id_or_short_id: &str) -> Result<Uuid, AppError> {
+        short_id::resolve_thread_id(&self.pool, id_or_short_id).await
+    }
+
+    async fn count(
+        &self,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<i64, AppError> {
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM threads t
+            WHERE ($1::timestamptz IS NULL OR t.created_at >= $1)
+              AND t.user_id <> ALL($2)
+              AND ($3::uuid IS NULL OR NOT EXISTS (
+                  SELECT 1 FROM thread_read_positions rp
+                  WHERE rp.user_id = $3 AND rp.thread_id = t.id
+                    AND rp.last_read_at >= t.last_activity_at
+              ))
+            "#,
+        )
+        .bind(since)
+        .bind(hidden_authors)
+        .bind(unread_for)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(total)
+    }
+
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+        sort: ThreadSort,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<Vec<ThreadWithUser>, AppError> {
+        let order_by = match sort {
+            ThreadSort::New => "t.created_at DESC",
+            ThreadSort::Top => "(t.upvote_count - t.downvote_count) DESC, t.created_at DESC",
+            ThreadSort::Active => "t.last_activity_at DESC, t.created_at DESC",
+        };
+        let threads = sqlx::query_as::<_, ThreadWithUser>(&format!(
+            r#"
+            SELECT 
+                t.id, t.short_id, t.title, t.content, t.created_at, t.updated_at,
+                t.upvote_count, t.downvote_count, t.locked_at,
+                u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
+                COUNT(c.id)::bigint as comment_count,
+                (SELECT COUNT(*) FROM thread_revisions r WHERE r.thread_id = t.id) as revision_count
+            FROM threads t
+            JOIN users u ON t.user_id = u.id
+            LEFT JOIN comments c ON t.id = c.thread_id
+            WHERE ($3::timestamptz IS NULL OR t.created_at >= $3)
+              AND t.user_id <> ALL($4)
+              AND ($5::uuid IS NULL OR NOT EXISTS (
+                  SELECT 1 FROM thread_read_positions rp
+                  WHERE rp.user_id = $5 AND rp.thread_id = t.id
+                    AND rp.last_read_at >= t.last_activity_at
+              ))
+            GROUP BY t.id, t.upvote_count, t.downvote_count, u.id, u.username, u.display_name, u.avatar_url
+            ORDER BY {}
+            LIMIT $1 OFFSET $2
+            "#,
+            order_by
+        ))
+        .bind(limit)
+        .bind(offset)
+        .bind(since)
+        .bind(hidden_authors)
+        .bind(unread_for)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(threads)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<ThreadWithUser>, AppError> {
+        let thread = sqlx::query_as::<_, ThreadWithUser>(
+            r#"
+            SELECT 
+                t.id, t.short_id, t.title, t.content, t.created_at, t.updated_at,
+                t.upvote_count, t.downvote_count, t.locked_at,
+                u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
+                COUNT(c.id)::bigint as comment_count,
+                (SELECT COUNT(*) FROM thread_revisions r WHERE r.thread_id = t.id) as revision_count
+            FROM threads t
+            JOIN users u ON t.user_id = u.id
+            LEFT JOIN comments c ON t.id = c.thread_id
+            WHERE t.id = $1
+            GROUP BY t.id, t.upvote_count, t.downvote_count, u.id, u.username, u.display_name, u.avatar_url
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(thread)
+    }
+
+    async fn find_without_comment_count(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<ThreadWithUser>, AppError> {
+        let thread = sqlx::query_as::<_, ThreadWithUser>(
+            r#"
+            SELECT 
+                t.id, t.short_id, t.title, t.content, t.created_at, t.updated_at,
+                t.upvote_count, t.downvote_count, t.locked_at,
+                u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
+                0::bigint as comment_count,
+                (SELECT COUNT(*) FROM thread_revisions r WHERE r.thread_id = t.id) as revision_count
+            FROM threads t
+            JOIN users u ON t.user_id = u.id
+            WHERE t.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(thread)
+    }
+
+    async fn exists(&self, id: Uuid) -> Result<bool, AppError> {
+        let exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1)")
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(exists)
+    }
+
+    async fn existence(&self, id: Uuid) -> Result<Existence, AppError> {
+        let (exists, deleted) = sqlx::query_as::<_, (bool, bool)>(
+            r#"
+            SELECT
+                EXISTS(SELECT 1 FROM threads WHERE id = $1),
+                EXISTS(SELECT 1 FROM moderation_log WHERE action = $2 AND target_id = $1)
+            "#,
+        )
+        .bind(id)
+        .bind(moderation_log::actions::THREAD_DELETE)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(match (exists, deleted) {
+            (true, _) => Existence::Present,
+            (false, true) => Existence::Tombstoned,
+            (false, false) => Existence::Missing,
+        })
+    }
+
+    async fn is_owned_by(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let owned = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM threads WHERE id = $1 AND user_id = $2)",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(owned)
+    }
+
+    async fn create(
+        &self,
+        author: &User,
+        title: &str,
+        content: Option<&str>,
+        tag_ids: &[Uuid],
+    ) -> Result<ThreadWithUser, AppError> {
+        // スラッグは作成時のタイトルから生成し、編集しても変えない
+        let slug = tags::slugify(title);
+        // 短いIDが衝突した場合は再生成して再試行
+        // タグは同じ文で付けるため、スレッドだけが作成されることはない
+        let generate_short_id = || self.tokens.short_id();
+        let thread = short_id::insert_with_short_id(generate_short_id, |short_id| {
+            sqlx::query_as::<_, ThreadWithUser>(
+                r#"
+                WITH inserted AS (
+                    INSERT INTO threads (user_id, title, content, short_id, slug)
+                    VALUES ($1, $2, $3, $7, $9)
+                    RETURNING id, short_id, title, content, created_at, updated_at
+                ),
+                tagged AS (
+                    INSERT INTO thread_tags (thread_id, tag_id)
+                    SELECT inserted.id, tags.id
+                    FROM inserted, tags
+                    WHERE tags.id = ANY($8)
+                )
+                SELECT
+                    id, short_id, title, content, created_at, updated_at,
+                    0 as upvote_count, 0 as downvote_count, NULL::timestamptz as locked_at,
+                    $1 as user_id, $4 as username, $5 as user_display_name, $6 as user_avatar_url,
+                    0::bigint as comment_count
+                FROM inserted
+                "#,
+            )
+            .bind(author.id)
+            .bind(title)
+            .bind(content)
+            .bind(&author.username)
+            .bind(&author.display_name)
+            .bind(&author.avatar_url)
+            .bind(short_id)
+            .bind(tag_ids)
+            .bind(&slug)
+            .fetch_one(&self.pool)
+        })
+        .await?;
+
+        Ok(thread)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        edited_by: Uuid,
+        title: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<(), AppError> {
+        // 編集履歴と更新は同じトランザクションで行う
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO thread_revisions (thread_id, title, content, edited_by)
+            SELECT id, title, content, $2 FROM threads WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(edited_by)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM thread_revisions
+            WHERE thread_id = $1
+              AND id NOT IN (
+                SELECT id FROM thread_revisions
+                WHERE thread_id = $1
+                ORDER BY created_at DESC, id DESC
+                LIMIT $2
+              )
+            "#,
+        )
+        .bind(id)
+        .bind(MAX_REVISIONS)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE threads 
+            SET 
+                title = COALESCE($2, title),
+                content = COALESCE($3, content),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(title)
+        .bind(content)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn revisions(&self, id: Uuid) -> Result<Vec<ThreadRevision>, AppError> {
+        let revisions = sqlx::query_as::<_, ThreadRevision>(
+            r#"
+            SELECT id, title, content, edited_by, created_at
+            FROM thread_revisions
+            WHERE thread_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    async fn set_locked(&self, id: Uuid, locked: bool) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE threads
+            SET locked_at = CASE WHEN $2 THEN COALESCE(locked_at, NOW()) ELSE NULL END
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(locked)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_owned(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let deleted_rows = sqlx::query("DELETE FROM threads WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(deleted_rows.rows_affected() > 0)
+    }
+
+    async fn delete_as_moderator(
+        &self,
+        id: Uuid,
+        moderator_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let author_id =
+            sqlx::query_scalar::<_, Uuid>("DELETE FROM threads WHERE id = $1 RETURNING user_id")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(author_id) = author_id else {
+            return Ok(false);
+        };
+
+        moderation_log::record(
+            &mut *tx,
+            moderator_id,
+            moderation_log::actions::THREAD_DELETE,
+            "thread",
+            id,
+            author_id,
+            reason,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn find_vote(&self, user_id: Uuid, thread_id: Uuid) -> Result<Option<String>, AppError> {
+        let vote_type = sqlx::query_scalar::<_, String>(
+            "SELECT vote_type FROM votes WHERE user_id = $1 AND thread_id = $2",
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(vote_type)
+    }
+
+    async fn set_vote(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        vote_type: &str,
+    ) -> Result<VoteChange, AppError> {
+        // 同時に挿入された場合は先の挿入の完了を待ってから更新に切り替わる
+        // 種類が同じなら更新せず行を返さない。挿入した行は xmax が 0 になる
+        let inserted = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO votes (user_id, thread_id, vote_type)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, thread_id) DO UPDATE
+            SET vote_type = EXCLUDED.vote_type, updated_at = NOW()
+            WHERE votes.vote_type <> EXCLUDED.vote_type
+            RETURNING xmax = 0
+            "#,
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .bind(vote_type)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_error::map_db_error)?;
+
+        Ok(match inserted {
+            Some(true) => VoteChange::Added,
+            Some(false) => VoteChange::Changed,
+            None => VoteChange::Unchanged,
+        })
+    }
+
+    async fn delete_vote(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<String>, AppError> {
+        let vote_type = sqlx::query_scalar::<_, String>(
+            "DELETE FROM votes WHERE user_id = $1 AND thread_id = $2 RETURNING vote_type",
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(vote_type)
+    }
+
+    async fn read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<ThreadReadPosition>, AppError> {
+        let position = sqlx::query_as::<_, ThreadReadPosition>(
+            r#"
+            SELECT last_read_comment_id, last_read_at
+            FROM thread_read_positions
+            WHERE user_id = $1 AND thread_id = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(position)
+    }
+
+    async fn save_read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        last_read_comment_id: Option<Uuid>,
+        last_read_at: DateTime<Utc>,
+    ) -> Result<ThreadReadPosition, AppError> {
+        let position = sqlx::query_as::<_, ThreadReadPosition>(
+            r#"
+            INSERT INTO thread_read_positions (user_id, thread_id, last_read_comment_id, last_read_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, thread_id) DO UPDATE
+            SET last_read_comment_id = EXCLUDED.last_read_comment_id,
+                last_read_at = EXCLUDED.last_read_at,
+                updated_at = NOW()
+            RETURNING last_read_comment_id, last_read_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .bind(last_read_comment_id)
+        .bind(last_read_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(position)
+    }
+
+    async fn add_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO bookmarks (user_id, thread_id)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id, thread_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(thread_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM bookmarks WHERE user_id = $1 AND thread_id = $2")
+            .bind(user_id)
+            .bind(thread_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn bookmarked_ids(
+        &self,
+        user_id: Uuid,
+        thread_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT thread_id FROM bookmarks WHERE user_id = $1 AND thread_id = ANY($2)",
+        )
+        .bind(user_id)
+        .bind(thread_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn count_bookmarks(&self, user_id: Uuid) -> Result<i64, AppError> {
+        let total =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM bookmarks WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(total)
+    }
+
+    async fn list_bookmarks(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ThreadWithUser>, AppError> {
+        let threads = sqlx::query_as::<_, ThreadWithUser>(
+            r#"
+            SELECT 
+                t.id, t.short_id, t.title, t.content, t.created_at, t.updated_at,
+                t.upvote_count, t.downvote_count, t.locked_at,
+                u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
+                (SELECT COUNT(*) FROM comments c WHERE c.thread_id = t.id) as comment_count,
+                (SELECT COUNT(*) FROM thread_revisions r WHERE r.thread_id = t.id) as revision_count
+            FROM bookmarks b
+            JOIN threads t ON t.id = b.thread_id
+            JOIN users u ON t.user_id = u.id
+            WHERE b.user_id = $1
+            ORDER BY b.created_at DESC, t.id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(threads)
+    }
+}
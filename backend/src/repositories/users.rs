@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        comments::CommentSort,
+        moderation::UserMute,
+        threads::{ThreadSort, TopWindow},
+        users::SortPreferences,
+    },
+    utils::{user_blocks, user_mute},
+};
+
+/// ユーザーへのアクセス
+#[async_trait]
+pub trait UsersRepo: Send + Sync {
+    /// 指定時刻に有効なミュートを取得
+    async fn active_mute(
+        &self,
+        user_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserMute>, AppError>;
+
+    /// 一覧の並び順の既定値を取得（設定が無い場合はすべてNone）
+    async fn sort_preferences(&self, user_id: Uuid) -> Result<SortPreferences, AppError>;
+
+    /// ブロックしているユーザーのID
+    async fn blocked_user_ids(&self, user_id: Uuid) -> Result<HashSet<Uuid>, AppError>;
+
+    /// ミュート中のユーザーの投稿を拒否する
+    async fn ensure_not_muted(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<(), AppError> {
+        match self.active_mute(user_id, now).await? {
+            Some(mute) => Err(AppError::UserMuted(mute.expires_at)),
+            None => Ok(()),
+        }
+    }
+}
+
+pub struct PgUsersRepo {
+    pool: PgPool,
+}
+
+impl PgUsersRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UsersRepo for PgUsersRepo {
+    async fn active_mute(
+        &self,
+        user_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserMute>, AppError> {
+        user_mute::active_mute(&self.pool, user_id, now).await
+    }
+
+    async fn sort_preferences(&self, user_id: Uuid) -> Result<SortPreferences, AppError> {
+        let row = sqlx::query_as::<_, (Option<String>, Option<String>, Option<String>)>(
+            "SELECT default_sort, default_window, default_comment_sort FROM user_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((sort, window, comment_sort)) = row else {
+            return Ok(SortPreferences::default());
+        };
+
+        Ok(SortPreferences {
+            default_sort: sort.as_deref().and_then(ThreadSort::parse),
+            default_window: window.as_deref().and_then(TopWindow::parse),
+            default_comment_sort: comment_sort.as_deref().and_then(CommentSort::parse),
+        })
+    }
+
+    async fn blocked_user_ids(&self, user_id: Uuid) -> Result<HashSet<Uuid>, AppError> {
+        user_blocks::blocked_ids(&self.pool, user_id).await
+    }
+}
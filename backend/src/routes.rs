@@ -1,81 +1,175 @@
-use axum::{
-    middleware,
-    routing::{delete, get, post, put},
-    Router,
+// ルーティング
+//
+// ハンドラーは `routes!` で登録し、`#[utoipa::path]` に書いたメソッドとパスでルーティングします。
+// ルーターに登録したハンドラーだけがOpenAPIドキュメントに載るため、両者が食い違うことはありません。
+use axum::{middleware, Router};
+use std::time::Duration;
+use utoipa::OpenApi;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
 };
-use sqlx::PgPool;
 
-use crate::{handlers, middleware::auth_middleware};
+use crate::{
+    handlers,
+    middleware::{
+        auth_middleware, reject_banned_writes, request_id_middleware, require_role,
+        security_headers_middleware, SecurityHeaderPolicy,
+    },
+    models::UserRole,
+    panic::catch_panic_middleware,
+    rate_limit::{rate_limit_middleware, RateLimit},
+    state::AppState,
+    ApiDoc,
+};
 
-pub fn create_routes(pool: PgPool) -> Router {
-    Router::new()
-        // API prefix
-        .nest("/api", api_routes(pool))
+pub fn create_routes(state: AppState) -> Router {
+    create_routes_with_openapi(state).0
 }
 
-fn api_routes(pool: PgPool) -> Router {
-    Router::new()
-        .nest("/auth", auth_routes(pool.clone()))
-        .nest("/threads", thread_routes(pool.clone()))
-        .nest("/comments", comment_routes(pool.clone()))
-        .nest("/users", user_routes(pool.clone()))
-        .with_state(pool)
+/// ルーターと、登録したハンドラーから組み立てたOpenAPIドキュメントを返す
+pub fn create_routes_with_openapi(state: AppState) -> (Router, utoipa::openapi::OpenApi) {
+    #[cfg(feature = "test-endpoints")]
+    let test_routes = state.config.enable_test_endpoints.then(|| {
+        tracing::warn!("Test endpoints (/api/_test/*) are enabled");
+        handlers::testing::routes().with_state(state.clone())
+    });
+
+    let (router, openapi) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        // ヘルスチェック
+        .routes(routes!(handlers::health::ready::readyz))
+        .merge(auth_routes(&state))
+        .merge(thread_routes(&state))
+        .merge(comment_routes(&state))
+        .merge(user_routes(&state))
+        .merge(tag_routes())
+        .merge(feed_routes())
+        .merge(html_routes(&state))
+        .merge(admin_routes(&state))
+        .merge(moderator_routes(&state))
+        .with_state(state)
+        .split_for_parts();
+
+    // E2Eテスト用のルートはドキュメントに載せない
+    #[cfg(feature = "test-endpoints")]
+    let router = match test_routes {
+        Some(test_routes) => router.merge(test_routes),
+        None => router,
+    };
+
+    let router = router
+        // パニックした場合もセキュリティヘッダーとリクエストIDを付けて返す
+        .layer(middleware::from_fn(catch_panic_middleware))
+        .layer(middleware::from_fn_with_state(
+            SecurityHeaderPolicy::API,
+            security_headers_middleware,
+        ))
+        .layer(middleware::from_fn(request_id_middleware));
+
+    (router, openapi)
 }
 
-fn auth_routes(pool: PgPool) -> Router<PgPool> {
-    let auth_protected_routes = Router::new()
-        .route("/change-password", post(handlers::auth::change_password))
-        .route(
-            "/resend-verification",
-            post(handlers::auth::resend_verification),
-        )
+fn auth_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    let config = &state.config;
+    // ログインはIPアドレスとメールアドレスの両方で試行回数を制限
+    let login_rate_limit = RateLimit::per_ip_and_email(
+        config.login_rate_limit_max_attempts,
+        Duration::from_secs(config.login_rate_limit_window_seconds),
+    )
+    .trusting(&config.trusted_proxies);
+    let password_reset_rate_limit = RateLimit::per_ip(
+        config.password_reset_rate_limit_max_requests,
+        Duration::from_secs(config.password_reset_rate_limit_window_seconds),
+    )
+    .trusting(&config.trusted_proxies);
+    // マジックリンクもメール送信を伴うため、パスワードリセットと同じ上限を適用
+    let magic_link_rate_limit = RateLimit::per_ip_and_email(
+        config.password_reset_rate_limit_max_requests,
+        Duration::from_secs(config.password_reset_rate_limit_window_seconds),
+    )
+    .trusting(&config.trusted_proxies);
+
+    let auth_protected_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::auth::change_password::change_password))
+        .routes(routes!(handlers::auth::verify_email::resend_verification))
+        .routes(routes!(handlers::auth::sessions::list_sessions))
+        .routes(routes!(handlers::auth::sessions::revoke_session))
+        .routes(routes!(handlers::auth::logout::logout_all))
         .route_layer(middleware::from_fn_with_state(
-            pool.clone(),
+            state.authenticator(),
             auth_middleware,
         ));
 
-    Router::new()
-        .route("/register", post(handlers::auth::register))
-        .route("/login", post(handlers::auth::login))
-        .route("/logout", post(handlers::auth::logout))
-        .route("/refresh", post(handlers::auth::refresh_token))
-        .route("/google", get(handlers::auth::google_auth))
-        .route("/google/callback", get(handlers::auth::google_callback))
-        .route("/verify-email/{token}", post(handlers::auth::verify_email))
-        .route(
-            "/password-reset/request",
-            post(handlers::auth::request_password_reset),
+    OpenApiRouter::new()
+        .routes(routes!(handlers::auth::register::register))
+        .routes(
+            routes!(handlers::auth::login::login).layer(middleware::from_fn_with_state(
+                login_rate_limit,
+                rate_limit_middleware,
+            )),
+        )
+        .routes(routes!(handlers::auth::logout::logout))
+        .routes(
+            routes!(handlers::auth::magic_link::request_magic_link).layer(
+                middleware::from_fn_with_state(magic_link_rate_limit, rate_limit_middleware),
+            ),
         )
-        .route(
-            "/password-reset/{token}",
-            post(handlers::auth::reset_password),
+        .routes(routes!(handlers::auth::magic_link::login_with_magic_link))
+        .routes(routes!(handlers::auth::confirm_device::confirm_device))
+        .routes(routes!(handlers::auth::refresh_token::refresh_token))
+        .routes(routes!(handlers::auth::google_auth::google_auth))
+        .routes(routes!(handlers::auth::google_callback::google_callback))
+        .routes(routes!(handlers::auth::verify_email::verify_email))
+        .routes(
+            routes!(handlers::auth::request_password_reset::request_password_reset).layer(
+                middleware::from_fn_with_state(password_reset_rate_limit, rate_limit_middleware),
+            ),
         )
+        .routes(routes!(handlers::auth::reset_password::reset_password))
         .merge(auth_protected_routes)
 }
 
-fn thread_routes(pool: PgPool) -> Router<PgPool> {
-    // 認証不要のルート
-    let public_routes = Router::new()
-        .route("/", get(handlers::threads::get_threads))
-        .route("/{id}", get(handlers::threads::get_thread))
-        .route("/{thread_id}/ogp.png", get(handlers::threads::get_thread_ogp_image))
-        .route(
-            "/{thread_id}/comments",
-            get(handlers::comments::get_comments),
-        );
+fn thread_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // 認証不要のルート（ログイン中のユーザー向けの情報は `MaybeUser` で付与する）
+    let public_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::threads::list::get_threads))
+        .routes(routes!(
+            handlers::threads::detail::get_thread,
+            handlers::threads::exists::thread_exists
+        ))
+        .routes(routes!(handlers::threads::ogp::get_thread_ogp_image))
+        .routes(routes!(
+            handlers::threads::contributors::get_thread_contributors
+        ))
+        .routes(routes!(handlers::threads::events::get_thread_events))
+        .routes(routes!(handlers::comments::list::get_comments));
 
     // 認証が必要なルート
-    let auth_routes = Router::new()
-        .route("/", post(handlers::threads::create_thread))
-        .route("/{id}", put(handlers::threads::update_thread))
-        .route("/{id}", delete(handlers::threads::delete_thread))
-        .route(
-            "/{thread_id}/comments",
-            post(handlers::comments::create_comment),
-        )
-        .route("/{id}/vote", post(handlers::threads::vote_thread))
+    let auth_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::threads::create::create_thread))
+        .routes(routes!(handlers::threads::preview::preview_thread))
+        .routes(routes!(handlers::threads::update::update_thread))
+        .routes(routes!(handlers::threads::revisions::get_thread_revisions))
+        .routes(routes!(handlers::threads::delete::delete_thread))
+        .routes(routes!(handlers::comments::create::create_comment))
+        .routes(routes!(handlers::comments::preview::preview_comment))
+        .routes(routes!(handlers::threads::vote::vote_thread))
+        .routes(routes!(handlers::threads::vote::remove_vote))
+        .routes(routes!(handlers::threads::report::report_thread))
+        .routes(routes!(handlers::comments::export::export_comments))
+        .routes(routes!(handlers::threads::lock::lock_thread))
+        .routes(routes!(handlers::threads::lock::unlock_thread))
+        .routes(routes!(
+            handlers::threads::read_position::update_read_position
+        ))
+        .routes(routes!(handlers::threads::bookmark::bookmark_thread))
+        .routes(routes!(handlers::threads::bookmark::unbookmark_thread))
+        .routes(routes!(handlers::threads::presence::update_presence))
+        .routes(routes!(handlers::threads::feed::get_feed))
+        // 利用停止中のユーザーは閲覧のみ可能
+        .route_layer(middleware::from_fn(reject_banned_writes))
         .route_layer(middleware::from_fn_with_state(
-            pool.clone(),
+            state.authenticator(),
             auth_middleware,
         ));
 
@@ -83,37 +177,563 @@ fn thread_routes(pool: PgPool) -> Router<PgPool> {
     public_routes.merge(auth_routes)
 }
 
-fn comment_routes(pool: PgPool) -> Router<PgPool> {
-    Router::new()
-        .route("/{id}", put(handlers::comments::update_comment))
-        .route("/{id}", delete(handlers::comments::delete_comment))
+fn comment_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // 認証不要のルート
+    let public_routes =
+        OpenApiRouter::new().routes(routes!(handlers::comments::quote::get_comment_quote));
+
+    // 認証が必要なルート
+    let auth_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::comments::update::update_comment))
+        .routes(routes!(handlers::comments::delete::delete_comment))
+        .routes(routes!(handlers::comments::report::report_comment))
+        // 利用停止中のユーザーは閲覧のみ可能
+        .route_layer(middleware::from_fn(reject_banned_writes))
         .route_layer(middleware::from_fn_with_state(
-            pool.clone(),
+            state.authenticator(),
             auth_middleware,
-        ))
+        ));
+
+    // マージして返す
+    public_routes.merge(auth_routes)
 }
 
-fn user_routes(pool: PgPool) -> Router<PgPool> {
+fn user_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // エクスポートは重いため、同じユーザーは一定時間に1回まで
+    let data_export_rate_limit = RateLimit::per_user(
+        1,
+        Duration::from_secs(state.config.data_export_rate_limit_window_seconds),
+    );
+
     // 認証が必要なルート
-    let auth_routes = Router::new()
-        .route("/me", get(handlers::users::get_current_user))
-        .route("/me", put(handlers::users::update_profile))
-        .route("/me", delete(handlers::users::delete_user))
-        .route("/me/email", put(handlers::users::update_email))
+    let auth_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::users::current_user::get_current_user))
+        .routes(routes!(handlers::users::update_profile::update_profile))
+        .routes(routes!(handlers::users::delete::delete_user))
+        .routes(routes!(handlers::users::update_email::update_email))
+        .routes(routes!(handlers::users::email_change::confirm_email_change))
+        .routes(
+            routes!(handlers::users::export::export_account_data).layer(
+                middleware::from_fn_with_state(data_export_rate_limit, rate_limit_middleware),
+            ),
+        )
+        .routes(routes!(handlers::users::preferences::export_preferences))
+        .routes(routes!(handlers::users::preferences::import_preferences))
+        .routes(routes!(
+            handlers::users::saved_searches::list_saved_searches
+        ))
+        .routes(routes!(
+            handlers::users::saved_searches::create_saved_search
+        ))
+        .routes(routes!(
+            handlers::users::saved_searches::delete_saved_search
+        ))
+        .routes(routes!(handlers::users::bookmarks::list_bookmarks))
+        .routes(routes!(handlers::users::follows::follow_user))
+        .routes(routes!(handlers::users::follows::unfollow_user))
+        .routes(routes!(handlers::users::blocks::block_user))
+        .routes(routes!(handlers::users::blocks::unblock_user))
+        .routes(routes!(handlers::users::blocks::list_blocks))
+        // 利用停止中のユーザーは閲覧のみ可能
+        .route_layer(middleware::from_fn(reject_banned_writes))
         .route_layer(middleware::from_fn_with_state(
-            pool.clone(),
+            state.authenticator(),
             auth_middleware,
         ));
 
     // 認証不要のルート
-    let public_routes = Router::new()
-        .route("/{username}", get(handlers::users::get_user_by_username))
-        .route("/{user_id}/threads", get(handlers::users::get_user_threads))
-        .route(
-            "/{user_id}/comments",
-            get(handlers::users::get_user_comments),
-        );
+    let public_routes = OpenApiRouter::new()
+        .routes(routes!(
+            handlers::users::detail::get_user_by_username,
+            handlers::users::exists::user_exists
+        ))
+        .routes(routes!(handlers::users::email_change::revert_email_change))
+        .routes(routes!(handlers::users::avatar::get_user_avatar))
+        .routes(routes!(handlers::users::avatar::get_avatar_thumbnail))
+        .routes(routes!(handlers::users::threads::get_user_threads))
+        .routes(routes!(handlers::users::comments::get_user_comments))
+        .routes(routes!(handlers::users::follows::get_followers))
+        .routes(routes!(handlers::users::follows::get_following));
 
     // マージして返す
     auth_routes.merge(public_routes)
 }
+
+fn tag_routes() -> OpenApiRouter<AppState> {
+    // 認証不要のルート
+    OpenApiRouter::new().routes(routes!(handlers::tags::detail::get_tag))
+}
+
+fn feed_routes() -> OpenApiRouter<AppState> {
+    // 認証不要のルート
+    OpenApiRouter::new()
+        .routes(routes!(handlers::feeds::tag::get_tag_feed))
+        .routes(routes!(handlers::feeds::author::get_author_feed))
+        .routes(routes!(
+            handlers::feeds::thread_comments::get_thread_comments_feed
+        ))
+        .routes(routes!(
+            handlers::feeds::user_comments::get_user_comments_feed
+        ))
+}
+
+fn html_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // 認証不要のルート
+    let public_routes =
+        OpenApiRouter::new().routes(routes!(handlers::threads::html::get_thread_html));
+
+    // 管理者のみアクセス可能なルート
+    let admin_routes = OpenApiRouter::new()
+        .routes(routes!(handlers::admin::email_preview::preview_email))
+        .route_layer(middleware::from_fn_with_state(
+            UserRole::Admin,
+            require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.authenticator(),
+            auth_middleware,
+        ));
+
+    // HTMLを返すため、同一オリジンでの埋め込みを許可し、CSPで読み込めるものを制限する
+    // （ルーター全体の API 向けの設定より先に付与するため、こちらが優先される）
+    public_routes
+        .merge(admin_routes)
+        .layer(middleware::from_fn_with_state(
+            SecurityHeaderPolicy::HTML,
+            security_headers_middleware,
+        ))
+}
+
+fn admin_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // 管理者のみアクセス可能なルート（認証後に管理者権限を確認）
+    OpenApiRouter::new()
+        .routes(routes!(handlers::admin::audit_log::get_audit_log))
+        .routes(routes!(handlers::admin::email_outbox::get_email_outbox))
+        .routes(routes!(handlers::admin::meta::get_meta))
+        .routes(routes!(handlers::admin::meta::get_config))
+        .routes(routes!(handlers::admin::maintenance::purge_tokens))
+        .routes(routes!(handlers::admin::maintenance::get_credential_report))
+        .routes(routes!(handlers::admin::mute_user::mute_user))
+        .routes(routes!(handlers::admin::ban_user::ban_user))
+        .routes(routes!(handlers::admin::ban_user::unban_user))
+        .routes(routes!(
+            handlers::admin::force_password_reset::force_password_reset
+        ))
+        .routes(routes!(handlers::admin::rename_tag::rename_tag))
+        .routes(routes!(handlers::admin::merge_tags::merge_tags))
+        .routes(routes!(handlers::admin::list_users::list_users))
+        .routes(routes!(handlers::admin::tag_rules::list_tag_rules))
+        .routes(routes!(handlers::admin::tag_rules::create_tag_rule))
+        .routes(routes!(handlers::admin::tag_rules::update_tag_rule))
+        .routes(routes!(handlers::admin::tag_rules::delete_tag_rule))
+        .routes(routes!(handlers::admin::restore::restore_thread))
+        .routes(routes!(handlers::admin::restore::restore_comment))
+        .route_layer(middleware::from_fn_with_state(
+            UserRole::Admin,
+            require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.authenticator(),
+            auth_middleware,
+        ))
+}
+
+fn moderator_routes(state: &AppState) -> OpenApiRouter<AppState> {
+    // モデレーター・管理者がアクセス可能なルート（認証後に権限を確認）
+    OpenApiRouter::new()
+        .routes(routes!(handlers::admin::reports::list_reports))
+        .routes(routes!(handlers::admin::remove_comment::remove_comment))
+        .routes(routes!(handlers::admin::reports::resolve_report))
+        .route_layer(middleware::from_fn_with_state(
+            UserRole::Moderator,
+            require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.authenticator(),
+            auth_middleware,
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use serde_json::json;
+    use sqlx::PgPool;
+    use utoipa::OpenApi;
+
+    use crate::{
+        test_utils::{
+            app::{spawn_app, Persona},
+            create_test_comment, create_test_thread, create_test_user,
+            route_access::{Access, Rejection, ROUTES},
+            test_openapi,
+        },
+        ApiDoc,
+    };
+
+    // OpenAPIドキュメントに載っているルート（メソッド、パス）
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let mut routes = BTreeSet::new();
+        for (path, item) in test_openapi().paths.paths {
+            for (method, operation) in [
+                ("GET", &item.get),
+                ("HEAD", &item.head),
+                ("POST", &item.post),
+                ("PUT", &item.put),
+                ("DELETE", &item.delete),
+                ("PATCH", &item.patch),
+            ] {
+                if operation.is_some() {
+                    routes.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    #[tokio::test]
+    async fn test_すべてのルートの権限が分類されている() {
+        // すべてのルートの権限が route_access に分類されていることを確認
+        // 新しいルートを追加したら test_utils/route_access.rs にも追加する
+        let documented = documented_routes();
+        let classified: BTreeSet<(String, String)> = ROUTES
+            .iter()
+            .map(|route| (route.method.to_string(), route.path.to_string()))
+            .collect();
+
+        let unclassified: Vec<_> = documented.difference(&classified).collect();
+        assert!(
+            unclassified.is_empty(),
+            "権限が分類されていないルートがあります。test_utils/route_access.rs の ROUTES に追加してください: {:?}",
+            unclassified
+        );
+        let unknown: Vec<_> = classified.difference(&documented).collect();
+        assert!(
+            unknown.is_empty(),
+            "ROUTES にドキュメントされていないルートがあります: {:?}",
+            unknown
+        );
+        assert_eq!(classified.len(), ROUTES.len(), "ROUTES に重複があります");
+    }
+
+    #[test]
+    fn test_bearer認証のセキュリティスキームが登録されている() {
+        // Bearer認証のセキュリティスキームが登録されていることを確認
+        let openapi = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let scheme = &openapi["components"]["securitySchemes"]["bearer_auth"];
+        assert_eq!(scheme["type"], "http");
+        assert_eq!(scheme["scheme"], "bearer");
+        assert_eq!(scheme["bearerFormat"], "JWT");
+    }
+
+    #[tokio::test]
+    async fn test_ルーターに登録したハンドラーがドキュメントに載る() {
+        // ルーターに登録したハンドラーがドキュメントに載ることを確認
+        let openapi = serde_json::to_value(test_openapi()).unwrap();
+
+        assert!(openapi["paths"]["/api/threads/{id}/vote"]["post"].is_object());
+        assert!(openapi["paths"]["/api/threads/{thread_id}/ogp.png"]["get"].is_object());
+        assert!(openapi["components"]["schemas"]["VoteRequest"].is_object());
+        // ヘルスチェックもドキュメントに載る
+        assert!(openapi["paths"]["/readyz"]["get"].is_object());
+        // ApiDoc には共通の定義だけを書き、パスは載せない
+        assert!(ApiDoc::openapi().paths.paths.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_認証が必要なルートは登録されたセキュリティスキームを参照する() {
+        // 認証が必要なルートは登録されたセキュリティスキームを参照することを確認
+        let openapi = serde_json::to_value(test_openapi()).unwrap();
+        let schemes = openapi["components"]["securitySchemes"]
+            .as_object()
+            .unwrap();
+
+        let mut failures = Vec::new();
+        for route in ROUTES {
+            let operation = &openapi["paths"][route.path][route.method.to_lowercase()];
+            let referenced: Vec<&String> = operation["security"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|requirement| requirement.as_object())
+                .flat_map(|requirement| requirement.keys())
+                .collect();
+
+            // 登録されていない名前を参照すると、Swagger UI やクライアントで認証情報が送られない
+            for name in &referenced {
+                if !schemes.contains_key(name.as_str()) {
+                    failures.push(format!(
+                        "{} {}: unknown scheme {}",
+                        route.method, route.path, name
+                    ));
+                }
+            }
+            if route.access != Access::Public
+                && !referenced.iter().any(|name| *name == "bearer_auth")
+            {
+                failures.push(format!(
+                    "{} {}: missing bearer_auth",
+                    route.method, route.path
+                ));
+            }
+        }
+
+        assert!(
+            failures.is_empty(),
+            "セキュリティの指定が誤っているルートがあります:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_すべてのルートが権限に応じて認証_認可される(pool: PgPool) {
+        // すべてのルートが権限に応じて認証・認可されることを確認
+        // 匿名・メール未認証・メール認証済み・モデレーター・管理者で全ルートを呼び、
+        // 認証・認可による拒否が分類表の通りであることを確認する（その他のエラーは問わない）
+        let app = spawn_app(&pool);
+
+        let mut failures = Vec::new();
+        for route in ROUTES {
+            let method = Method::from_bytes(route.method.as_bytes()).unwrap();
+            let body = match method {
+                Method::GET | Method::HEAD => None,
+                _ => Some(route.body.unwrap_or("{}")),
+            };
+
+            for persona in Persona::ALL {
+                // 退会などでユーザーの状態が変わるため、ルートごとに作り直す
+                let token = app.token_for(persona).await;
+                let response = app
+                    .request(method.clone(), &route.sample_path(), token.as_deref(), body)
+                    .await;
+
+                let expected = route.access.expected_rejection(persona);
+                let actual = Rejection::of(&response);
+                if actual != expected {
+                    failures.push(format!(
+                        "{} {} as {:?}: expected {:?}, got {:?} ({} {})",
+                        route.method,
+                        route.path,
+                        persona,
+                        expected,
+                        actual,
+                        response.status,
+                        response.body
+                    ));
+                }
+            }
+        }
+
+        assert!(
+            failures.is_empty(),
+            "分類表と異なる認証・認可の結果になったルートがあります:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_登録からコメントの投稿までルーターを通して行える(
+        pool: PgPool,
+    ) {
+        // 登録からコメントの投稿までルーターを通して行えることを確認
+        let app = spawn_app(&pool);
+
+        let author = app.register("router_author").await;
+        app.verify_email(author.id).await;
+        let token = app.login(&author.email, &author.password).await;
+
+        let body = json!({ "title": "Router thread", "content": "Hello" });
+        let response = app
+            .request(
+                Method::POST,
+                "/api/threads",
+                Some(&token),
+                Some(&body.to_string()),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        assert_eq!(response.body["user"]["username"], author.username);
+        let thread_id = response.body["id"].as_str().unwrap().to_string();
+
+        // 登録時に発行されたトークンもそのまま使える
+        let body = json!({ "content": "First comment" });
+        let response = app
+            .request(
+                Method::POST,
+                &format!("/api/threads/{}/comments", thread_id),
+                Some(&author.access_token),
+                Some(&body.to_string()),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        assert_eq!(response.body["content"], "First comment");
+
+        let response = app
+            .request(
+                Method::GET,
+                &format!("/api/threads/{}", thread_id),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["comment_count"], 1);
+    }
+
+    #[sqlx::test]
+    async fn test_トークンが無いとスレッドを作成できない(pool: PgPool) {
+        // トークンが無いとスレッドを作成できないことを確認
+        let app = spawn_app(&pool);
+
+        let body = json!({ "title": "Anonymous thread" });
+        let response = app
+            .request(Method::POST, "/api/threads", None, Some(&body.to_string()))
+            .await;
+
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        let threads = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM threads WHERE title = $1")
+            .bind("Anonymous thread")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(threads, 0);
+    }
+
+    #[cfg(not(feature = "test-endpoints"))]
+    #[sqlx::test]
+    async fn test_通常のビルドにはe2eテスト用のルートが含まれない(
+        pool: PgPool,
+    ) {
+        // 通常のビルドにはe2eテスト用のルートが含まれないことを確認
+        use crate::{
+            config::Config,
+            test_utils::{app::spawn_app_with_config, test_config},
+        };
+
+        // 設定で有効にしても、フィーチャーを有効にしていなければ登録されない
+        let config = Config {
+            enable_test_endpoints: true,
+            ..test_config()
+        };
+        let app = spawn_app_with_config(&pool, config);
+
+        let response = app
+            .request(Method::POST, "/api/_test/users", None, Some("{}"))
+            .await;
+
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_コメントのフィードはコメント一覧と別のルートで応答する(
+        pool: PgPool,
+    ) {
+        // コメントのフィードがコメント一覧とは別のルートで応答することを確認
+        let app = spawn_app(&pool);
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        create_test_comment(&pool, user.id, thread_id, "Comment", None).await;
+
+        for path in [
+            format!("/api/threads/{}/comments.atom", thread_id),
+            format!("/api/users/{}/comments.atom", user.username),
+        ] {
+            let response = app.request(Method::GET, &path, None, None).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", path);
+            assert!(response.headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("application/atom+xml"));
+        }
+
+        // JSON のコメント一覧はそのまま
+        let response = app
+            .request(
+                Method::GET,
+                &format!("/api/threads/{}/comments", thread_id),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.is_object() || response.body.is_array());
+    }
+
+    #[sqlx::test]
+    async fn test_htmlを返すルートだけ同一オリジンのフレームを許可する(
+        pool: PgPool,
+    ) {
+        // HTMLを返すルートだけが同一オリジンのフレームを許可することを確認
+        let app = spawn_app(&pool);
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let admin_token = app.token_for(Persona::Admin).await;
+
+        for (path, token) in [
+            (format!("/api/threads/{}/html", thread_id), None),
+            (
+                "/api/admin/email-preview/password-reset".to_string(),
+                admin_token.as_deref(),
+            ),
+        ] {
+            let response = app.request(Method::GET, &path, token, None).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", path);
+            assert!(response.headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html"));
+            assert_eq!(response.headers[header::X_FRAME_OPTIONS], "SAMEORIGIN");
+            let csp = response.headers[header::CONTENT_SECURITY_POLICY]
+                .to_str()
+                .unwrap();
+            assert!(csp.contains("frame-ancestors 'self'"), "{}", csp);
+            assert_eq!(response.headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        }
+
+        // JSON のルートは API 向けの設定のまま
+        let response = app
+            .request(
+                Method::GET,
+                &format!("/api/threads/{}", thread_id),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.headers[header::X_FRAME_OPTIONS], "DENY");
+        assert!(response.headers[header::CONTENT_SECURITY_POLICY]
+            .to_str()
+            .unwrap()
+            .contains("frame-ancestors 'none'"));
+    }
+
+    #[sqlx::test]
+    async fn test_ルーター全体にcorsのレイヤーが適用される(pool: PgPool) {
+        // ルーター全体にCORSのレイヤーが適用されることを確認
+        let app = spawn_app(&pool);
+
+        let response = app
+            .send(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/api/threads")
+                    .header(header::ORIGIN, "http://localhost:3000")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:3000"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// 投稿・登録に付随する副作用（通知・アウトボックスへの追加など）の実行方針
+//
+// 副作用が失敗しても本来の書き込み（スレッド・コメント・ユーザーの作成）は失敗させず、
+// 警告ログと失敗件数のカウンターに記録したうえで、レスポンスに `X-Degraded: true` を付けます。
+use std::{
+    error::Error,
+    fmt::Display,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use uuid::Uuid;
+
+use crate::models::comments::CommentRemovalReason;
+
+/// 副作用の一部が失敗したことを示すレスポンスヘッダー
+pub const DEGRADED_HEADER: &str = "x-degraded";
+
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// 起動してから失敗した副作用の件数
+pub fn failure_count() -> u64 {
+    FAILURES.load(Ordering::Relaxed)
+}
+
+/// 通知の内容
+#[derive(Debug, Clone)]
+pub enum Notification {
+    UserRegistered {
+        user_id: Uuid,
+    },
+    ThreadCreated {
+        thread_id: Uuid,
+        user_id: Uuid,
+    },
+    CommentCreated {
+        comment_id: Uuid,
+        thread_id: Uuid,
+        parent_id: Option<Uuid>,
+        user_id: Uuid,
+        /// 作成リクエストの `client_nonce`（同じユーザーの別タブが重複を除くため）
+        client_nonce: Option<String>,
+    },
+    /// モデレーターがコメントを非表示にした（投稿者への通知）
+    CommentRemoved {
+        comment_id: Uuid,
+        thread_id: Uuid,
+        user_id: Uuid,
+        reason: CommentRemovalReason,
+        /// 投稿ルールのページのURL
+        rules_url: String,
+    },
+}
+
+impl Notification {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::UserRegistered { .. } => "user_registered",
+            Notification::ThreadCreated { .. } => "thread_created",
+            Notification::CommentCreated { .. } => "comment_created",
+            Notification::CommentRemoved { .. } => "comment_removed",
+        }
+    }
+}
+
+/// 投稿・登録時に同期的に呼ばれる通知先
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, notification: &Notification)
+        -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// 何もしない通知先（通知の連携先が無い場合の既定）
+pub struct NoopNotifier;
+
+#[async_trait]
+impl Notifier for NoopNotifier {
+    async fn notify(
+        &self,
+        _notification: &Notification,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// 1リクエスト分の副作用の実行結果
+pub struct SideEffects {
+    notifier: Arc<dyn Notifier>,
+    degraded: bool,
+}
+
+impl SideEffects {
+    pub fn new(notifier: Arc<dyn Notifier>) -> Self {
+        Self {
+            notifier,
+            degraded: false,
+        }
+    }
+
+    /// 副作用を実行し、失敗した場合は警告を記録して続行する
+    pub async fn run<F, E>(&mut self, name: &'static str, effect: F)
+    where
+        F: Future<Output = Result<(), E>>,
+        E: Display,
+    {
+        if let Err(e) = effect.await {
+            FAILURES.fetch_add(1, Ordering::Relaxed);
+            self.degraded = true;
+            tracing::warn!(side_effect = name, error = %e, "Side effect failed, continuing");
+        }
+    }
+
+    /// 通知先に通知する（失敗しても続行する）
+    pub async fn notify(&mut self, notification: Notification) {
+        let notifier = self.notifier.clone();
+        self.run(notification.kind(), async move {
+            notifier.notify(&notification).await
+        })
+        .await;
+    }
+
+    /// レスポンスに付けるヘッダー（失敗した副作用が無ければ空）
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if self.degraded {
+            headers.insert(DEGRADED_HEADER, HeaderValue::from_static("true"));
+        }
+        headers
+    }
+}
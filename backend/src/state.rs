@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use sqlx::PgPool;
+
+use crate::{
+    auth::jwt::JwtService,
+    config::Config,
+    email::{self, EmailSender},
+    http_client::HttpClient,
+    middleware::Authenticator,
+    repositories::{
+        AuthRepo, CommentsRepo, PgAuthRepo, PgCommentsRepo, PgThreadsRepo, PgUsersRepo,
+        ThreadsRepo, UsersRepo,
+    },
+    side_effects::{NoopNotifier, Notifier},
+    utils::{
+        avatar_proxy::AvatarProxy,
+        ogp_cache::OgpImageCache,
+        presence::PresenceRegistry,
+        tag_rules::TagRuleCache,
+        thread_events::ThreadEvents,
+        token_generator::{RandomTokenGenerator, TokenGenerator},
+    },
+};
+
+/// ルーター全体で共有する状態
+///
+/// ハンドラーは `State<PgPool>` や `State<Arc<Config>>` のように
+/// 必要なものだけを取り出して使います。
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub config: Arc<Config>,
+    /// アクセストークンの発行と検証
+    pub jwt: Arc<JwtService>,
+    pub email: Arc<dyn EmailSender>,
+    /// 外部サービスへのリクエストに使う共有のHTTPクライアント
+    pub http: HttpClient,
+    pub threads: Arc<dyn ThreadsRepo>,
+    pub comments: Arc<dyn CommentsRepo>,
+    pub users: Arc<dyn UsersRepo>,
+    pub auth: Arc<dyn AuthRepo>,
+    pub tag_rules: Arc<TagRuleCache>,
+    /// 生成したOGP画像のキャッシュ
+    pub ogp_images: Arc<OgpImageCache>,
+    /// 外部のアバター画像の縮小とキャッシュ
+    pub avatars: Arc<AvatarProxy>,
+    pub notifier: Arc<dyn Notifier>,
+    /// スレッドを閲覧中のユーザー（メモリ上にのみ保持する）
+    pub presence: Arc<PresenceRegistry>,
+    /// スレッドごとのライブイベント（SSE）の配信先
+    pub thread_events: Arc<ThreadEvents>,
+    pub tokens: Arc<dyn TokenGenerator>,
+}
+
+impl AppState {
+    pub fn new(pool: PgPool, config: Config) -> Self {
+        // プロキシの設定は Config::validate で確認済み
+        let http = HttpClient::new(&config).expect("Failed to build HTTP client");
+        let ogp_images = Arc::new(OgpImageCache::from_config(&config));
+        let avatars = Arc::new(AvatarProxy::from_config(&config));
+        let tokens: Arc<dyn TokenGenerator> = Arc::new(RandomTokenGenerator);
+
+        Self {
+            email: Arc::from(email::get_email_sender(&config, &http)),
+            http,
+            jwt: Arc::new(JwtService::from_config(&config)),
+            config: Arc::new(config),
+            threads: Arc::new(PgThreadsRepo::new(pool.clone(), tokens.clone())),
+            comments: Arc::new(PgCommentsRepo::new(pool.clone())),
+            users: Arc::new(PgUsersRepo::new(pool.clone())),
+            auth: Arc::new(PgAuthRepo::new(pool.clone())),
+            tag_rules: Arc::new(TagRuleCache::new(pool.clone())),
+            ogp_images,
+            avatars,
+            notifier: Arc::new(NoopNotifier),
+            presence: Arc::new(PresenceRegistry::new()),
+            thread_events: Arc::new(ThreadEvents::new()),
+            tokens,
+            pool,
+        }
+    }
+
+    /// 認証ミドルウェアに渡す状態
+    pub fn authenticator(&self) -> Authenticator {
+        Authenticator {
+            auth: self.auth.clone(),
+            jwt: self.jwt.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for Authenticator {
+    fn from_ref(state: &AppState) -> Self {
+        state.authenticator()
+    }
+}
@@ -0,0 +1,187 @@
+// ログとトレースの出力
+//
+// ログは常に標準出力へ書き、`OTEL_EXPORTER_OTLP_ENDPOINT` が設定されている場合のみスパンをOTLPで送ります。
+// 未設定の場合はOpenTelemetryのレイヤーもミドルウェアも組み込まないため、処理は増えません。
+// リクエストのスパンは受け取った `traceparent` ヘッダーを親にするため、フロントエンドからのトレースと1つにつながり、
+// 処理中に作られたスパン（データベースの問い合わせなど）はその子として送られます。
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    propagation::{Extractor, TextMapPropagator},
+    trace::TraceError,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+use tracing::{field::Empty, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::config::Config;
+
+/// ログの出力を設定し、OTLPのエンドポイントが設定されていればトレースの送信も始める
+pub fn init(config: &Config) -> Result<(), TraceError> {
+    let otel_layer = match &config.otel_exporter_otlp_endpoint {
+        Some(endpoint) => Some(
+            tracing_opentelemetry::layer()
+                .with_tracer(otlp_tracer(endpoint, &config.otel_service_name)?),
+        ),
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::DEBUG)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
+        .init();
+
+    Ok(())
+}
+
+/// 送信待ちのスパンを送りきる（トレースを送っていない場合は何もしない）
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+fn otlp_tracer(endpoint: &str, service_name: &str) -> Result<trace::Tracer, TraceError> {
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(trace::config().with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )])))
+        .install_batch(runtime::Tokio)
+}
+
+/// リクエストごとのスパンを作るミドルウェア
+/// W3C Trace Context の `traceparent` ヘッダーがあれば、そのトレースの子スパンにします。
+/// トレースを送る場合のみ、ルーター全体の一番外側に置きます
+pub async fn trace_context_middleware(request: Request<Body>, next: Next) -> Response {
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+    let span = tracing::info_span!(
+        "HTTP request",
+        otel.kind = "server",
+        http.request.method = %request.method(),
+        url.path = %request.uri().path(),
+        http.response.status_code = Empty,
+    );
+    span.set_parent(parent);
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    response
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Router};
+    use opentelemetry::trace::{SpanId, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::{export::trace::SpanData, testing::trace::InMemorySpanExporter};
+    use tower::ServiceExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    /// テスト用のエクスポーターに送ったスパンを返す
+    async fn export_spans(traceparent: Option<&str>) -> Vec<SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = trace::TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/threads",
+                get(|| async {
+                    tracing::info_span!("db.query").in_scope(|| {});
+                    "ok"
+                }),
+            )
+            .layer(axum::middleware::from_fn(trace_context_middleware));
+        let mut request = Request::get("/threads");
+        if let Some(traceparent) = traceparent {
+            request = request.header("traceparent", traceparent);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(guard);
+        provider.force_flush();
+        exporter.get_finished_spans().unwrap()
+    }
+
+    fn find<'a>(spans: &'a [SpanData], name: &str) -> &'a SpanData {
+        spans.iter().find(|span| span.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_traceparentヘッダーのトレースをリクエストのスパンの親にする() {
+        // traceparent ヘッダーのトレースをリクエストのスパンの親にすることを確認
+        let spans = export_spans(Some(&format!("00-{}-{}-01", TRACE_ID, PARENT_SPAN_ID))).await;
+
+        let request_span = find(&spans, "HTTP request");
+        assert_eq!(
+            request_span.span_context.trace_id(),
+            TraceId::from_hex(TRACE_ID).unwrap()
+        );
+        assert_eq!(
+            request_span.parent_span_id,
+            SpanId::from_hex(PARENT_SPAN_ID).unwrap()
+        );
+
+        // ハンドラー内のスパンはリクエストのスパンの子になる
+        let query_span = find(&spans, "db.query");
+        assert_eq!(
+            query_span.span_context.trace_id(),
+            request_span.span_context.trace_id()
+        );
+        assert_eq!(
+            query_span.parent_span_id,
+            request_span.span_context.span_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_traceparentヘッダーが無いか不正な場合は新しいトレースを始める() {
+        // traceparent ヘッダーが無いか不正な場合は新しいトレースを始めることを確認
+        for traceparent in [None, Some("00-invalid-01")] {
+            let spans = export_spans(traceparent).await;
+
+            let request_span = find(&spans, "HTTP request");
+            assert!(request_span.span_context.is_valid());
+            assert_ne!(
+                request_span.span_context.trace_id(),
+                TraceId::from_hex(TRACE_ID).unwrap()
+            );
+            assert_eq!(request_span.parent_span_id, SpanId::INVALID);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// ルーター全体（ミドルウェアを含む）に対してリクエストを送るテスト用のハーネス
+//
+// main.rs と同じく CORS のレイヤーを重ねるため、本番と同じ順でミドルウェアが適用される。
+// 登録・ログインも実際のエンドポイントを通して行い、発行されたトークンでリクエストを送れる。
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use super::{create_test_user, test_config, test_jwt, test_state_with_config};
+use crate::{
+    config::Config,
+    models::{User, UserRole},
+    routes::create_routes,
+};
+
+/// リクエストを送るユーザーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Persona {
+    /// トークン無し
+    Anonymous,
+    /// メール認証が済んでいない一般ユーザー
+    Unverified,
+    /// メール認証済みの一般ユーザー
+    Verified,
+    Moderator,
+    Admin,
+}
+
+impl Persona {
+    pub const ALL: [Persona; 5] = [
+        Persona::Anonymous,
+        Persona::Unverified,
+        Persona::Verified,
+        Persona::Moderator,
+        Persona::Admin,
+    ];
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// JSON以外（画像など）の場合はNull
+    pub body: serde_json::Value,
+}
+
+/// 登録エンドポイントから作成したユーザー
+pub struct RegisteredUser {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    /// 登録時に発行されたアクセストークン
+    pub access_token: String,
+}
+
+pub struct TestApp {
+    pub pool: PgPool,
+    router: Router,
+}
+
+/// テスト用の設定でルーター全体を組み立てる
+pub fn spawn_app(pool: &PgPool) -> TestApp {
+    spawn_app_with_config(pool, test_config())
+}
+
+/// 設定を指定してルーター全体を組み立てる
+pub fn spawn_app_with_config(pool: &PgPool, config: Config) -> TestApp {
+    let cors_origins = config.cors_origins.clone();
+    let router =
+        create_routes(test_state_with_config(pool, config)).layer(crate::cors::layer(cors_origins));
+
+    TestApp {
+        pool: pool.clone(),
+        router,
+    }
+}
+
+impl TestApp {
+    /// 指定した種類のユーザーを作成し、そのアクセストークンを返す（Anonymousの場合はNone）
+    pub async fn token_for(&self, persona: Persona) -> Option<String> {
+        let (verified, role) = match persona {
+            Persona::Anonymous => return None,
+            Persona::Unverified => (false, UserRole::User),
+            Persona::Verified => (true, UserRole::User),
+            Persona::Moderator => (true, UserRole::Moderator),
+            Persona::Admin => (true, UserRole::Admin),
+        };
+
+        let user = create_test_user(&self.pool, verified).await;
+        let user =
+            sqlx::query_as::<_, User>("UPDATE users SET role = $2 WHERE id = $1 RETURNING *")
+                .bind(user.id)
+                .bind(role)
+                .fetch_one(&self.pool)
+                .await
+                .expect("Failed to set user role");
+
+        Some(bearer_token(&user))
+    }
+
+    /// JSONのボディ（任意）とトークン（任意）を付けてリクエストを送る
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+        body: Option<&str>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        self.send(request).await
+    }
+
+    /// ボディを読まずにレスポンスを返す（SSEなど、終わらないボディを少しずつ読む場合）
+    pub async fn open(&self, request: Request<Body>) -> Response {
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+
+    /// 組み立てたリクエストをそのまま送る（任意のヘッダーを付けたい場合）
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        TestResponse {
+            status,
+            headers,
+            body: serde_json::from_slice(&bytes).unwrap_or_default(),
+        }
+    }
+
+    /// 登録エンドポイントからユーザーを作成する
+    ///
+    /// メールアドレスとパスワードはユーザー名から決まります。作成直後はメール認証が済んでいません。
+    pub async fn register(&self, username: &str) -> RegisteredUser {
+        let email = format!("{}@example.com", username);
+        let password = "password123".to_string();
+        let body = json!({
+            "username": username,
+            "email": email,
+            "password": password,
+        });
+
+        let response = self
+            .request(
+                Method::POST,
+                "/api/auth/register",
+                None,
+                Some(&body.to_string()),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+
+        RegisteredUser {
+            id: response.body["user"]["id"]
+                .as_str()
+                .unwrap()
+                .parse()
+                .unwrap(),
+            username: username.to_string(),
+            email,
+            password,
+            access_token: response.body["access_token"].as_str().unwrap().to_string(),
+        }
+    }
+
+    /// メール認証を済ませた状態にする（認証メールのリンクを開く操作の代わり）
+    pub async fn verify_email(&self, user_id: Uuid) {
+        sqlx::query(
+            "UPDATE users SET email_verified = true, email_verified_at = NOW() WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .expect("Failed to verify email");
+    }
+
+    /// ログインエンドポイントからアクセストークンを取得する
+    pub async fn login(&self, email: &str, password: &str) -> String {
+        let body = json!({ "email": email, "password": password });
+
+        let response = self
+            .request(
+                Method::POST,
+                "/api/auth/login",
+                None,
+                Some(&body.to_string()),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        response.body["access_token"].as_str().unwrap().to_string()
+    }
+}
+
+/// ユーザーの有効なアクセストークンを発行する
+pub fn bearer_token(user: &User) -> String {
+    let jwt = test_jwt();
+    jwt.encode(&jwt.claims(
+        &user.id.to_string(),
+        &user.username,
+        &user.email,
+        user.role,
+        &Uuid::new_v4().to_string(),
+        15,
+    ))
+    .unwrap()
+}
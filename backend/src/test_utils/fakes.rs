@@ -0,0 +1,805 @@
+// データベースを使わないハンドラーテスト用のインメモリ実装
+//
+// バリデーション・権限チェック・レスポンスの組み立てなど、純粋なロジックの確認に使います。
+// SQLそのものの確認は引き続き `#[sqlx::test]` の結合テストで行います。
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::Authenticator,
+    models::{
+        common::Existence,
+        moderation::UserMute,
+        threads::{ThreadReadPosition, ThreadRevision, ThreadSort, ThreadWithUser, MAX_REVISIONS},
+        users::SortPreferences,
+        User, UserRole,
+    },
+    repositories::{threads::VoteChange, AuthRepo, CommentsRepo, ThreadsRepo, UsersRepo},
+    side_effects::{NoopNotifier, Notification, Notifier},
+    utils::{
+        short_id,
+        tag_rules::TagRuleCache,
+        token_generator::{RandomTokenGenerator, TokenGenerator},
+    },
+};
+
+// テスト用のユーザーを作成する関数（データベースには保存しない）
+pub fn fake_user(email_verified: bool) -> User {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    User {
+        id,
+        username: format!("fakeuser_{}", id.simple()),
+        email: format!("fake_{}@example.com", id.simple()),
+        display_name: None,
+        avatar_url: None,
+        bio: None,
+        website_url: None,
+        email_verified,
+        email_verified_at: email_verified.then_some(now),
+        verification_token: None,
+        verification_token_expires_at: None,
+        password_reset_token: None,
+        password_reset_token_expires_at: None,
+        created_at: now,
+        updated_at: now,
+        role: UserRole::User,
+        banned_at: None,
+        banned_until: None,
+        ban_reason: None,
+    }
+}
+
+/// 全リポジトリのインメモリ実装
+#[derive(Default)]
+pub struct FakeRepos {
+    pub threads: Arc<InMemoryThreadsRepo>,
+    pub comments: Arc<InMemoryCommentsRepo>,
+    pub users: Arc<InMemoryUsersRepo>,
+    pub auth: Arc<InMemoryAuthRepo>,
+    pub tag_rules: Arc<TagRuleCache>,
+}
+
+impl FakeRepos {
+    pub fn threads_repo(&self) -> Arc<dyn ThreadsRepo> {
+        self.threads.clone()
+    }
+
+    pub fn comments_repo(&self) -> Arc<dyn CommentsRepo> {
+        self.comments.clone()
+    }
+
+    pub fn users_repo(&self) -> Arc<dyn UsersRepo> {
+        self.users.clone()
+    }
+
+    pub fn auth_repo(&self) -> Arc<dyn AuthRepo> {
+        self.auth.clone()
+    }
+
+    /// テスト用の `JwtService` で検証する認証の状態
+    pub fn authenticator(&self) -> Authenticator {
+        Authenticator {
+            auth: self.auth_repo(),
+            jwt: super::test_jwt(),
+        }
+    }
+
+    pub fn notifier(&self) -> Arc<dyn Notifier> {
+        Arc::new(NoopNotifier)
+    }
+}
+
+#[derive(Clone)]
+struct StoredThread {
+    id: Uuid,
+    short_id: String,
+    title: String,
+    content: Option<String>,
+    author: User,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    last_activity_at: DateTime<Utc>,
+    locked_at: Option<DateTime<Utc>>,
+}
+
+impl StoredThread {
+    fn to_thread_with_user(&self, data: &ThreadsData) -> ThreadWithUser {
+        let count = |vote_type: &str| {
+            data.votes
+                .iter()
+                .filter(|((_, thread_id), v)| *thread_id == self.id && v.as_str() == vote_type)
+                .count() as i32
+        };
+
+        ThreadWithUser {
+            id: self.id,
+            short_id: self.short_id.clone(),
+            title: self.title.clone(),
+            content: self.content.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            upvote_count: count("upvote"),
+            downvote_count: count("downvote"),
+            locked_at: self.locked_at,
+            user_id: self.author.id,
+            username: self.author.username.clone(),
+            user_display_name: self.author.display_name.clone(),
+            user_avatar_url: self.author.avatar_url.clone(),
+            comment_count: Some(0),
+            revision_count: data.revisions.get(&self.id).map_or(0, Vec::len) as i64,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ThreadsData {
+    threads: Vec<StoredThread>,
+    // (user_id, thread_id) -> vote_type
+    votes: HashMap<(Uuid, Uuid), String>,
+    // (user_id, thread_id) -> (last_read_comment_id, last_read_at)
+    read_positions: HashMap<(Uuid, Uuid), (Option<Uuid>, DateTime<Utc>)>,
+    // (thread_id, moderator_id, reason)
+    moderation_log: Vec<(Uuid, Uuid, Option<String>)>,
+    // thread_id -> tag_ids
+    thread_tags: HashMap<Uuid, Vec<Uuid>>,
+    // thread_id -> 編集履歴（古い順）
+    revisions: HashMap<Uuid, Vec<ThreadRevision>>,
+    // (user_id, thread_id)（ブックマークした順）
+    bookmarks: Vec<(Uuid, Uuid)>,
+}
+
+impl ThreadsData {
+    /// 最後に読んだ後にスレッドに動きが無いか
+    fn is_read(&self, user_id: Uuid, thread: &StoredThread) -> bool {
+        self.read_positions
+            .get(&(user_id, thread.id))
+            .is_some_and(|&(_, last_read_at)| last_read_at >= thread.last_activity_at)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryThreadsRepo {
+    data: Mutex<ThreadsData>,
+    /// 投稿者情報付きの取得（`find`）が呼ばれた回数
+    find_calls: AtomicUsize,
+}
+
+impl InMemoryThreadsRepo {
+    /// スレッドを直接追加してIDを返す
+    pub fn insert(&self, author: &User, title: &str) -> Uuid {
+        let now = Utc::now();
+        let thread = StoredThread {
+            id: Uuid::new_v4(),
+            short_id: RandomTokenGenerator.short_id(),
+            title: title.to_string(),
+            content: None,
+            author: author.clone(),
+            created_at: now,
+            updated_at: now,
+            last_activity_at: now,
+            locked_at: None,
+        };
+        let id = thread.id;
+        self.data.lock().unwrap().threads.push(thread);
+        id
+    }
+
+    pub fn vote_of(&self, user_id: Uuid, thread_id: Uuid) -> Option<String> {
+        self.data
+            .lock()
+            .unwrap()
+            .votes
+            .get(&(user_id, thread_id))
+            .cloned()
+    }
+
+    /// コメントの作成・削除で変わる最終アクティビティ日時を設定する
+    pub fn set_last_activity(&self, thread_id: Uuid, at: DateTime<Utc>) {
+        let mut data = self.data.lock().unwrap();
+        if let Some(thread) = data.threads.iter_mut().find(|t| t.id == thread_id) {
+            thread.last_activity_at = at;
+        }
+    }
+
+    /// 投稿者情報付きの取得（重いクエリ）が呼ばれた回数
+    pub fn find_calls(&self) -> usize {
+        self.find_calls.load(Ordering::SeqCst)
+    }
+
+    /// モデレーターによる削除の記録（スレッドID、モデレーターID、理由）
+    pub fn moderation_log(&self) -> Vec<(Uuid, Uuid, Option<String>)> {
+        self.data.lock().unwrap().moderation_log.clone()
+    }
+
+    /// スレッド作成時に付けられたタグID
+    pub fn tags_of(&self, thread_id: Uuid) -> Vec<Uuid> {
+        self.data
+            .lock()
+            .unwrap()
+            .thread_tags
+            .get(&thread_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl ThreadsRepo for InMemoryThreadsRepo {
+    async fn resolve_id(&self, id_or_short_id: &str) -> Result<Uuid, AppError> {
+        let data = self.data.lock().unwrap();
+        data.threads
+            .iter()
+            .find(|t| t.id.to_string() == id_or_short_id || t.short_id == id_or_short_id)
+            .map(|t| t.id)
+            .or_else(|| Uuid::parse_str(id_or_short_id).ok())
+            .ok_or(AppError::NotFound)
+    }
+
+    async fn count(
+        &self,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<i64, AppError> {
+        let data = self.data.lock().unwrap();
+        Ok(data
+            .threads
+            .iter()
+            .filter(|t| since.is_none_or(|since| t.created_at >= since))
+            .filter(|t| !hidden_authors.contains(&t.author.id))
+            .filter(|t| unread_for.is_none_or(|user_id| !data.is_read(user_id, t)))
+            .count() as i64)
+    }
+
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+        sort: ThreadSort,
+        since: Option<DateTime<Utc>>,
+        hidden_authors: &[Uuid],
+        unread_for: Option<Uuid>,
+    ) -> Result<Vec<ThreadWithUser>, AppError> {
+        let data = self.data.lock().unwrap();
+        let mut threads: Vec<(ThreadWithUser, DateTime<Utc>)> = data
+            .threads
+            .iter()
+            .filter(|t| since.is_none_or(|since| t.created_at >= since))
+            .filter(|t| !hidden_authors.contains(&t.author.id))
+            .filter(|t| unread_for.is_none_or(|user_id| !data.is_read(user_id, t)))
+            .map(|t| (t.to_thread_with_user(&data), t.last_activity_at))
+            .collect();
+        let score = |t: &ThreadWithUser| t.upvote_count - t.downvote_count;
+        match sort {
+            ThreadSort::New => threads.sort_by_key(|(t, _)| std::cmp::Reverse(t.created_at)),
+            ThreadSort::Top => threads.sort_by(|(a, _), (b, _)| {
+                score(b)
+                    .cmp(&score(a))
+                    .then(b.created_at.cmp(&a.created_at))
+            }),
+            ThreadSort::Active => threads.sort_by(|(a, a_activity), (b, b_activity)| {
+                b_activity
+                    .cmp(a_activity)
+                    .then(b.created_at.cmp(&a.created_at))
+            }),
+        }
+
+        Ok(threads
+            .into_iter()
+            .map(|(thread, _)| thread)
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<ThreadWithUser>, AppError> {
+        self.find_calls.fetch_add(1, Ordering::SeqCst);
+        let data = self.data.lock().unwrap();
+        Ok(data
+            .threads
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.to_thread_with_user(&data)))
+    }
+
+    async fn find_without_comment_count(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<ThreadWithUser>, AppError> {
+        self.find(id).await
+    }
+
+    async fn exists(&self, id: Uuid) -> Result<bool, AppError> {
+        Ok(self.data.lock().unwrap().threads.iter().any(|t| t.id == id))
+    }
+
+    async fn existence(&self, id: Uuid) -> Result<Existence, AppError> {
+        let data = self.data.lock().unwrap();
+        Ok(if data.threads.iter().any(|t| t.id == id) {
+            Existence::Present
+        } else if data
+            .moderation_log
+            .iter()
+            .any(|(thread_id, ..)| *thread_id == id)
+        {
+            Existence::Tombstoned
+        } else {
+            Existence::Missing
+        })
+    }
+
+    async fn is_owned_by(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .threads
+            .iter()
+            .any(|t| t.id == id && t.author.id == user_id))
+    }
+
+    async fn create(
+        &self,
+        author: &User,
+        title: &str,
+        content: Option<&str>,
+        tag_ids: &[Uuid],
+    ) -> Result<ThreadWithUser, AppError> {
+        let id = self.insert(author, title);
+        let mut data = self.data.lock().unwrap();
+        let thread = data.threads.iter_mut().find(|t| t.id == id).unwrap();
+        thread.content = content.map(str::to_string);
+        let thread = thread.clone();
+        data.thread_tags.insert(id, tag_ids.to_vec());
+
+        Ok(thread.to_thread_with_user(&data))
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        edited_by: Uuid,
+        title: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<(), AppError> {
+        let mut data = self.data.lock().unwrap();
+        let Some(thread) = data.threads.iter_mut().find(|t| t.id == id) else {
+            return Ok(());
+        };
+        let revision = ThreadRevision {
+            id: Uuid::new_v4(),
+            title: thread.title.clone(),
+            content: thread.content.clone(),
+            edited_by: Some(edited_by),
+            created_at: Utc::now(),
+        };
+        if let Some(title) = title {
+            thread.title = title.to_string();
+        }
+        if let Some(content) = content {
+            thread.content = Some(content.to_string());
+        }
+        thread.updated_at = Utc::now();
+
+        let revisions = data.revisions.entry(id).or_default();
+        revisions.push(revision);
+        let excess = revisions.len().saturating_sub(MAX_REVISIONS as usize);
+        revisions.drain(..excess);
+        Ok(())
+    }
+
+    async fn revisions(&self, id: Uuid) -> Result<Vec<ThreadRevision>, AppError> {
+        let data = self.data.lock().unwrap();
+        Ok(data.revisions.get(&id).cloned().unwrap_or_default())
+    }
+
+    async fn set_locked(&self, id: Uuid, locked: bool) -> Result<(), AppError> {
+        let mut data = self.data.lock().unwrap();
+        if let Some(thread) = data.threads.iter_mut().find(|t| t.id == id) {
+            thread.locked_at = if locked {
+                thread.locked_at.or(Some(Utc::now()))
+            } else {
+                None
+            };
+        }
+        Ok(())
+    }
+
+    async fn delete_owned(&self, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.threads.len();
+        data.threads
+            .retain(|t| !(t.id == id && t.author.id == user_id));
+        Ok(data.threads.len() < before)
+    }
+
+    async fn delete_as_moderator(
+        &self,
+        id: Uuid,
+        moderator_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.threads.len();
+        data.threads.retain(|t| t.id != id);
+        if data.threads.len() == before {
+            return Ok(false);
+        }
+        data.moderation_log
+            .push((id, moderator_id, reason.map(str::to_string)));
+        Ok(true)
+    }
+
+    async fn find_vote(&self, user_id: Uuid, thread_id: Uuid) -> Result<Option<String>, AppError> {
+        Ok(self.vote_of(user_id, thread_id))
+    }
+
+    async fn set_vote(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        vote_type: &str,
+    ) -> Result<VoteChange, AppError> {
+        let previous = self
+            .data
+            .lock()
+            .unwrap()
+            .votes
+            .insert((user_id, thread_id), vote_type.to_string());
+        Ok(match previous {
+            None => VoteChange::Added,
+            Some(previous) if previous == vote_type => VoteChange::Unchanged,
+            Some(_) => VoteChange::Changed,
+        })
+    }
+
+    async fn delete_vote(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<String>, AppError> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .votes
+            .remove(&(user_id, thread_id)))
+    }
+
+    async fn read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<ThreadReadPosition>, AppError> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .read_positions
+            .get(&(user_id, thread_id))
+            .map(|&(last_read_comment_id, last_read_at)| ThreadReadPosition {
+                last_read_comment_id,
+                last_read_at,
+            }))
+    }
+
+    async fn save_read_position(
+        &self,
+        user_id: Uuid,
+        thread_id: Uuid,
+        last_read_comment_id: Option<Uuid>,
+        last_read_at: DateTime<Utc>,
+    ) -> Result<ThreadReadPosition, AppError> {
+        self.data
+            .lock()
+            .unwrap()
+            .read_positions
+            .insert((user_id, thread_id), (last_read_comment_id, last_read_at));
+
+        Ok(ThreadReadPosition {
+            last_read_comment_id,
+            last_read_at,
+        })
+    }
+
+    async fn add_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError> {
+        let mut data = self.data.lock().unwrap();
+        if !data.bookmarks.contains(&(user_id, thread_id)) {
+            data.bookmarks.push((user_id, thread_id));
+        }
+        Ok(())
+    }
+
+    async fn remove_bookmark(&self, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError> {
+        self.data
+            .lock()
+            .unwrap()
+            .bookmarks
+            .retain(|&bookmark| bookmark != (user_id, thread_id));
+        Ok(())
+    }
+
+    async fn bookmarked_ids(
+        &self,
+        user_id: Uuid,
+        thread_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .bookmarks
+            .iter()
+            .filter(|(u, t)| *u == user_id && thread_ids.contains(t))
+            .map(|&(_, thread_id)| thread_id)
+            .collect())
+    }
+
+    async fn count_bookmarks(&self, user_id: Uuid) -> Result<i64, AppError> {
+        let data = self.data.lock().unwrap();
+        Ok(data.bookmarks.iter().filter(|(u, _)| *u == user_id).count() as i64)
+    }
+
+    async fn list_bookmarks(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ThreadWithUser>, AppError> {
+        let data = self.data.lock().unwrap();
+        Ok(data
+            .bookmarks
+            .iter()
+            .rev()
+            .filter(|(u, _)| *u == user_id)
+            .filter_map(|(_, thread_id)| data.threads.iter().find(|t| t.id == *thread_id))
+            .map(|t| t.to_thread_with_user(&data))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryCommentsRepo {
+    // (comment_id, thread_id, created_at)
+    comments: Mutex<Vec<(Uuid, Uuid, DateTime<Utc>)>>,
+}
+
+impl InMemoryCommentsRepo {
+    /// コメントを直接追加してIDを返す
+    pub fn insert(&self, thread_id: Uuid, created_at: DateTime<Utc>) -> Uuid {
+        let id = Uuid::new_v4();
+        self.comments
+            .lock()
+            .unwrap()
+            .push((id, thread_id, created_at));
+        id
+    }
+}
+
+#[async_trait]
+impl CommentsRepo for InMemoryCommentsRepo {
+    async fn created_at_in_thread(
+        &self,
+        comment_id: Uuid,
+        thread_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
+        Ok(self
+            .comments
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, t, _)| *id == comment_id && *t == thread_id)
+            .map(|(_, _, created_at)| *created_at))
+    }
+
+    async fn count_unread(
+        &self,
+        thread_id: Uuid,
+        position: Option<&ThreadReadPosition>,
+    ) -> Result<u64, AppError> {
+        let since = position.map(|p| p.last_read_at);
+        Ok(self
+            .comments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, t, created_at)| {
+                *t == thread_id && since.is_none_or(|since| *created_at > since)
+            })
+            .count() as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryUsersRepo {
+    mutes: Mutex<Vec<UserMute>>,
+    sort_preferences: Mutex<HashMap<Uuid, SortPreferences>>,
+    // blocker_id -> blocked_ids
+    blocks: Mutex<HashMap<Uuid, HashSet<Uuid>>>,
+}
+
+impl InMemoryUsersRepo {
+    /// ユーザーをミュートする
+    pub fn mute(&self, user_id: Uuid, expires_at: DateTime<Utc>) {
+        self.mutes.lock().unwrap().push(UserMute {
+            id: Uuid::new_v4(),
+            user_id,
+            muted_by: None,
+            reason: None,
+            expires_at,
+            created_at: Utc::now(),
+        });
+    }
+
+    /// 一覧の並び順の既定値を設定する
+    pub fn set_sort_preferences(&self, user_id: Uuid, preferences: SortPreferences) {
+        self.sort_preferences
+            .lock()
+            .unwrap()
+            .insert(user_id, preferences);
+    }
+
+    /// ユーザーをブロックする
+    pub fn block(&self, blocker_id: Uuid, blocked_id: Uuid) {
+        self.blocks
+            .lock()
+            .unwrap()
+            .entry(blocker_id)
+            .or_default()
+            .insert(blocked_id);
+    }
+}
+
+#[async_trait]
+impl UsersRepo for InMemoryUsersRepo {
+    async fn active_mute(
+        &self,
+        user_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserMute>, AppError> {
+        Ok(self
+            .mutes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|mute| mute.user_id == user_id)
+            .max_by_key(|mute| mute.created_at)
+            .filter(|mute| mute.expires_at > now)
+            .cloned())
+    }
+
+    async fn sort_preferences(&self, user_id: Uuid) -> Result<SortPreferences, AppError> {
+        Ok(self
+            .sort_preferences
+            .lock()
+            .unwrap()
+            .get(&user_id)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    async fn blocked_user_ids(&self, user_id: Uuid) -> Result<HashSet<Uuid>, AppError> {
+        Ok(self
+            .blocks
+            .lock()
+            .unwrap()
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryAuthRepo {
+    users: Mutex<HashMap<Uuid, User>>,
+}
+
+impl InMemoryAuthRepo {
+    pub fn add_user(&self, user: &User) {
+        self.users.lock().unwrap().insert(user.id, user.clone());
+    }
+}
+
+#[async_trait]
+impl AuthRepo for InMemoryAuthRepo {
+    async fn find_user(&self, user_id: Uuid) -> Result<Option<User>, AppError> {
+        Ok(self.users.lock().unwrap().get(&user_id).cloned())
+    }
+}
+
+/// 常に失敗する通知先（通知の連携先が停止している場合のテスト用）
+#[derive(Default)]
+pub struct FailingNotifier {
+    attempts: AtomicUsize,
+}
+
+impl FailingNotifier {
+    /// これまでに通知を試みた回数
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl Notifier for FailingNotifier {
+    async fn notify(
+        &self,
+        _notification: &Notification,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        Err("notifier is unavailable".into())
+    }
+}
+
+/// 受け取った通知を記録する通知先
+#[derive(Default)]
+pub struct RecordingNotifier {
+    notifications: Mutex<Vec<Notification>>,
+}
+
+impl RecordingNotifier {
+    /// これまでに受け取った通知
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.notifications.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    async fn notify(
+        &self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.notifications
+            .lock()
+            .unwrap()
+            .push(notification.clone());
+        Ok(())
+    }
+}
+
+/// 固定のシードの乱数でトークンを生成する実装
+/// 同じシードで同じ順に生成すれば、毎回同じトークンになります
+pub struct SeededTokenGenerator {
+    rng: Mutex<StdRng>,
+}
+
+impl SeededTokenGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl TokenGenerator for SeededTokenGenerator {
+    fn secure_token(&self) -> String {
+        format!("{:032x}", self.rng.lock().unwrap().gen::<u128>())
+    }
+
+    fn alphanumeric_token(&self, len: usize) -> String {
+        let mut rng = self.rng.lock().unwrap();
+        (0..len)
+            .map(|_| char::from(rng.sample(Alphanumeric)))
+            .collect()
+    }
+
+    fn short_id(&self) -> String {
+        short_id::encode_base62(self.rng.lock().unwrap().gen())
+    }
+}
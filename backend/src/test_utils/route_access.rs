@@ -0,0 +1,318 @@
+// すべてのルートに必要な権限の一覧（認証・認可の網羅テストで使う唯一の分類表）
+//
+// ルートを追加したら、ここにも必要な権限とともに追加してください。
+// OpenAPIドキュメントに載っているルートがここに無い場合、`routes.rs` のテストが失敗します。
+use super::app::{Persona, TestResponse};
+
+/// ルートを呼び出すのに必要な権限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// 認証不要（トークンがあれば使うルートも含む）
+    Public,
+    /// ログインしていれば良い（メール認証は不要）
+    Authenticated,
+    /// メール認証済みのユーザーのみ
+    Verified,
+    /// モデレーター・管理者のみ
+    Moderator,
+    /// 管理者のみ
+    Admin,
+}
+
+/// 認証・認可による拒否の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// トークンが無い（401）
+    Unauthenticated,
+    /// メール認証が済んでいない（403 `EMAIL_VERIFICATION_REQUIRED`）
+    Unverified,
+    /// 権限が足りない（403）
+    Forbidden,
+}
+
+impl Access {
+    /// 指定した種類のユーザーがこのルートを呼んだ場合に期待する拒否（拒否されない場合はNone）
+    pub fn expected_rejection(self, persona: Persona) -> Option<Rejection> {
+        match (self, persona) {
+            (Access::Public, _) => None,
+            (_, Persona::Anonymous) => Some(Rejection::Unauthenticated),
+            (Access::Authenticated, _) => None,
+            (Access::Verified, Persona::Unverified) => Some(Rejection::Unverified),
+            (Access::Verified, _) => None,
+            (Access::Moderator, Persona::Unverified | Persona::Verified) => {
+                Some(Rejection::Forbidden)
+            }
+            (Access::Moderator, _) => None,
+            (Access::Admin, Persona::Admin) => None,
+            (Access::Admin, _) => Some(Rejection::Forbidden),
+        }
+    }
+}
+
+impl Rejection {
+    /// レスポンスが認証・認可による拒否であれば、その種類を返す
+    ///
+    /// 存在しないリソースへの404や、ボディの不足による422などは拒否として扱いません。
+    pub fn of(response: &TestResponse) -> Option<Self> {
+        let error = response.body["error"].as_str();
+        match (response.status.as_u16(), response.body["code"].as_str()) {
+            (401, None) if error == Some("Missing or invalid Authorization header") => {
+                Some(Rejection::Unauthenticated)
+            }
+            (403, Some("EMAIL_VERIFICATION_REQUIRED")) => Some(Rejection::Unverified),
+            (403, None) if error == Some("Forbidden") => Some(Rejection::Forbidden),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RouteSpec {
+    pub method: &'static str,
+    /// OpenAPIドキュメントと同じ表記のパス
+    pub path: &'static str,
+    pub access: Access,
+    /// ハンドラーの権限確認まで到達させるためのボディ（省略時は `{}`）
+    pub body: Option<&'static str>,
+}
+
+const fn route(method: &'static str, path: &'static str, access: Access) -> RouteSpec {
+    RouteSpec {
+        method,
+        path,
+        access,
+        body: None,
+    }
+}
+
+const fn route_with_body(
+    method: &'static str,
+    path: &'static str,
+    access: Access,
+    body: &'static str,
+) -> RouteSpec {
+    RouteSpec {
+        method,
+        path,
+        access,
+        body: Some(body),
+    }
+}
+
+pub const ROUTES: &[RouteSpec] = &[
+    // ヘルスチェック
+    route("GET", "/readyz", Access::Public),
+    // 認証
+    route("POST", "/api/auth/register", Access::Public),
+    route("POST", "/api/auth/login", Access::Public),
+    route("POST", "/api/auth/logout", Access::Public),
+    route("POST", "/api/auth/magic-link", Access::Public),
+    route("POST", "/api/auth/magic-link/{token}", Access::Public),
+    route("POST", "/api/auth/confirm-device/{token}", Access::Public),
+    route("POST", "/api/auth/refresh", Access::Public),
+    route("GET", "/api/auth/google", Access::Public),
+    route("GET", "/api/auth/google/callback", Access::Public),
+    route("POST", "/api/auth/verify-email/{token}", Access::Public),
+    route("POST", "/api/auth/password-reset/request", Access::Public),
+    route("POST", "/api/auth/password-reset/{token}", Access::Public),
+    route("POST", "/api/auth/change-password", Access::Authenticated),
+    route(
+        "POST",
+        "/api/auth/resend-verification",
+        Access::Authenticated,
+    ),
+    route("GET", "/api/auth/sessions", Access::Authenticated),
+    route("DELETE", "/api/auth/sessions/{id}", Access::Authenticated),
+    route("POST", "/api/auth/logout_all", Access::Authenticated),
+    // スレッド
+    route("GET", "/api/threads", Access::Public),
+    route("GET", "/api/threads/{id}", Access::Public),
+    route("HEAD", "/api/threads/{id}", Access::Public),
+    route("GET", "/api/threads/{id}/html", Access::Public),
+    route("GET", "/api/threads/{thread_id}/ogp.png", Access::Public),
+    route("GET", "/api/threads/{id}/contributors", Access::Public),
+    route("GET", "/api/threads/{thread_id}/comments", Access::Public),
+    route_with_body(
+        "POST",
+        "/api/threads",
+        Access::Verified,
+        r#"{"title": "Title"}"#,
+    ),
+    route_with_body(
+        "POST",
+        "/api/threads/preview",
+        Access::Verified,
+        r#"{"title": "Title"}"#,
+    ),
+    route("PUT", "/api/threads/{id}", Access::Authenticated),
+    route("GET", "/api/threads/{id}/revisions", Access::Authenticated),
+    route("DELETE", "/api/threads/{id}", Access::Authenticated),
+    route_with_body(
+        "POST",
+        "/api/threads/{thread_id}/comments",
+        Access::Verified,
+        r#"{"content": "Comment"}"#,
+    ),
+    route_with_body(
+        "POST",
+        "/api/threads/{thread_id}/comments/preview",
+        Access::Verified,
+        r#"{"content": "Comment"}"#,
+    ),
+    route_with_body(
+        "POST",
+        "/api/threads/{id}/vote",
+        Access::Verified,
+        r#"{"vote_type": "upvote"}"#,
+    ),
+    route("DELETE", "/api/threads/{id}/vote", Access::Authenticated),
+    route("POST", "/api/threads/{id}/report", Access::Authenticated),
+    route(
+        "GET",
+        "/api/threads/{id}/comments/export.csv",
+        Access::Authenticated,
+    ),
+    route("POST", "/api/threads/{id}/lock", Access::Authenticated),
+    route("POST", "/api/threads/{id}/unlock", Access::Authenticated),
+    route(
+        "PUT",
+        "/api/threads/{id}/read-position",
+        Access::Authenticated,
+    ),
+    route("POST", "/api/threads/{id}/bookmark", Access::Authenticated),
+    route("POST", "/api/threads/{id}/presence", Access::Authenticated),
+    route("GET", "/api/threads/{id}/events", Access::Public),
+    route(
+        "DELETE",
+        "/api/threads/{id}/bookmark",
+        Access::Authenticated,
+    ),
+    route("GET", "/api/feed", Access::Authenticated),
+    // コメント
+    route("GET", "/api/comments/{id}/quote", Access::Public),
+    route("PUT", "/api/comments/{id}", Access::Authenticated),
+    route("DELETE", "/api/comments/{id}", Access::Authenticated),
+    route("POST", "/api/comments/{id}/report", Access::Authenticated),
+    // ユーザー
+    route("GET", "/api/users/{username}", Access::Public),
+    route("HEAD", "/api/users/{username}", Access::Public),
+    route("GET", "/api/users/{username}/avatar.png", Access::Public),
+    route("GET", "/api/users/{username}/avatar", Access::Public),
+    route("GET", "/api/users/{user_id}/threads", Access::Public),
+    route("GET", "/api/users/{user_id}/comments", Access::Public),
+    route("GET", "/api/users/{username}/followers", Access::Public),
+    route("GET", "/api/users/{username}/following", Access::Public),
+    route("POST", "/api/users/email/revert/{token}", Access::Public),
+    route("GET", "/api/users/me", Access::Authenticated),
+    route("PUT", "/api/users/me", Access::Authenticated),
+    route("DELETE", "/api/users/me", Access::Authenticated),
+    route("GET", "/api/users/me/export", Access::Authenticated),
+    route(
+        "GET",
+        "/api/users/me/preferences/export",
+        Access::Authenticated,
+    ),
+    route(
+        "POST",
+        "/api/users/me/preferences/import",
+        Access::Authenticated,
+    ),
+    route("PUT", "/api/users/me/email", Access::Authenticated),
+    route(
+        "POST",
+        "/api/users/me/email/confirm/{token}",
+        Access::Authenticated,
+    ),
+    route("GET", "/api/users/me/saved-searches", Access::Authenticated),
+    route_with_body(
+        "POST",
+        "/api/users/me/saved-searches",
+        Access::Authenticated,
+        r#"{"query": "rust"}"#,
+    ),
+    route(
+        "DELETE",
+        "/api/users/me/saved-searches/{id}",
+        Access::Authenticated,
+    ),
+    route("GET", "/api/users/me/bookmarks", Access::Authenticated),
+    route(
+        "POST",
+        "/api/users/{username}/follow",
+        Access::Authenticated,
+    ),
+    route(
+        "DELETE",
+        "/api/users/{username}/follow",
+        Access::Authenticated,
+    ),
+    route("POST", "/api/users/{username}/block", Access::Authenticated),
+    route(
+        "DELETE",
+        "/api/users/{username}/block",
+        Access::Authenticated,
+    ),
+    route("GET", "/api/users/me/blocks", Access::Authenticated),
+    // タグ
+    route("GET", "/api/tags/{slug}", Access::Public),
+    // フィード
+    route("GET", "/api/feeds/tags/{file}", Access::Public),
+    route("GET", "/api/feeds/users/{file}", Access::Public),
+    route("GET", "/api/threads/{id}/comments.atom", Access::Public),
+    route("GET", "/api/users/{username}/comments.atom", Access::Public),
+    // 通報への対応
+    route("GET", "/api/admin/reports", Access::Moderator),
+    route_with_body(
+        "POST",
+        "/api/admin/reports/{id}/resolve",
+        Access::Moderator,
+        r#"{"outcome": "actioned"}"#,
+    ),
+    route("DELETE", "/api/admin/comments/{id}", Access::Moderator),
+    // 管理
+    route("GET", "/api/admin/audit-log", Access::Admin),
+    route("GET", "/api/admin/email-outbox", Access::Admin),
+    route("GET", "/api/admin/meta", Access::Admin),
+    route("GET", "/api/admin/config", Access::Admin),
+    route("GET", "/api/admin/email-preview/{template}", Access::Admin),
+    route("POST", "/api/admin/maintenance/purge-tokens", Access::Admin),
+    route("GET", "/api/admin/maintenance/credentials", Access::Admin),
+    route("GET", "/api/admin/users", Access::Admin),
+    route("POST", "/api/admin/users/{id}/mute", Access::Admin),
+    route("POST", "/api/admin/users/{id}/ban", Access::Admin),
+    route("POST", "/api/admin/users/{id}/unban", Access::Admin),
+    route(
+        "POST",
+        "/api/admin/users/{id}/force-password-reset",
+        Access::Admin,
+    ),
+    route("PUT", "/api/admin/tags/{slug}", Access::Admin),
+    route(
+        "POST",
+        "/api/admin/tags/{slug}/merge-into/{target}",
+        Access::Admin,
+    ),
+    route("GET", "/api/admin/tag-rules", Access::Admin),
+    route("POST", "/api/admin/tag-rules", Access::Admin),
+    route("PUT", "/api/admin/tag-rules/{id}", Access::Admin),
+    route("DELETE", "/api/admin/tag-rules/{id}", Access::Admin),
+    route("POST", "/api/admin/threads/{id}/restore", Access::Admin),
+    route("POST", "/api/admin/comments/{id}/restore", Access::Admin),
+];
+
+impl RouteSpec {
+    /// パスパラメーターを存在しないIDに置き換えたパス
+    pub fn sample_path(&self) -> String {
+        self.path
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with('{') {
+                    uuid::Uuid::new_v4().to_string()
+                } else {
+                    segment.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
@@ -0,0 +1,232 @@
+// 本人のアカウントデータのエクスポート（プロフィール・投稿・投票をJSONで逐次出力する）
+use std::io;
+
+use axum::body::Body;
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, TryStreamExt};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::models::{users::UserResponse, User};
+
+/// 出力するJSONの形式のバージョン（項目を変更した場合は上げる）
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// まとめて送信する件数
+const CHUNK_ITEMS: usize = 100;
+
+/// エクスポートする1スレッド分
+#[derive(Debug, Serialize, FromRow)]
+pub struct ExportedThread {
+    pub id: Uuid,
+    pub short_id: String,
+    pub title: String,
+    pub content: Option<String>,
+    pub upvote_count: i32,
+    pub downvote_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// エクスポートする1コメント分（本人が削除したコメントは本文が残っていないため含めない）
+#[derive(Debug, Serialize, FromRow)]
+pub struct ExportedComment {
+    pub id: Uuid,
+    pub short_id: String,
+    pub thread_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub content: String,
+    pub removed_by_moderator: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// エクスポートする1投票分
+#[derive(Debug, Serialize, FromRow)]
+pub struct ExportedVote {
+    pub thread_id: Uuid,
+    /// "upvote" または "downvote"
+    pub vote_type: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 一覧より前に出力するフィールド
+#[derive(Serialize)]
+struct ExportHeader<'a> {
+    schema_version: u32,
+    generated_at: DateTime<Utc>,
+    profile: &'a UserResponse,
+    /// 連携している外部ログインのプロバイダー名（トークンは含めない）
+    oauth_providers: &'a [String],
+}
+
+/// ダウンロード時の `Content-Disposition` ヘッダーの値
+///
+/// ユーザー名は英数字・`_`・`-` のみのため、そのままファイル名に使います。
+pub fn content_disposition(username: &str) -> String {
+    format!("attachment; filename=\"{}-export.json\"", username)
+}
+
+enum ExportError {
+    /// クライアントが切断した
+    Closed,
+    Failed(String),
+}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(err: sqlx::Error) -> Self {
+        ExportError::Failed(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(err: serde_json::Error) -> Self {
+        ExportError::Failed(err.to_string())
+    }
+}
+
+/// 出力をためて `CHUNK_ITEMS` 件ごとに送信する
+struct ChunkWriter {
+    tx: mpsc::Sender<Result<String, io::Error>>,
+    chunk: String,
+    items: usize,
+}
+
+impl ChunkWriter {
+    fn push_str(&mut self, json: &str) {
+        self.chunk.push_str(json);
+    }
+
+    async fn item_written(&mut self) -> Result<(), ExportError> {
+        self.items += 1;
+        if self.items < CHUNK_ITEMS {
+            return Ok(());
+        }
+        self.flush().await
+    }
+
+    async fn flush(&mut self) -> Result<(), ExportError> {
+        self.items = 0;
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        self.tx
+            .send(Ok(std::mem::take(&mut self.chunk)))
+            .await
+            .map_err(|_| ExportError::Closed)
+    }
+
+    /// 一覧を `,"name":[...]` として書き出す
+    async fn write_array<T, S>(&mut self, name: &str, mut rows: S) -> Result<(), ExportError>
+    where
+        T: Serialize,
+        S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+    {
+        self.push_str(&format!(",\"{}\":[", name));
+        let mut first = true;
+        while let Some(row) = rows.try_next().await? {
+            if !first {
+                self.push_str(",");
+            }
+            first = false;
+            self.push_str(&serde_json::to_string(&row)?);
+            self.item_written().await?;
+        }
+        self.push_str("]");
+        Ok(())
+    }
+}
+
+/// 本人のアカウントデータをJSONとして逐次出力するレスポンスボディを作成する
+///
+/// 投稿の多いユーザーでも全件をメモリに載せないよう、別タスクで行を読みながら書き出します。
+/// パスワードのハッシュや外部ログインのトークンなどの認証情報は含めません。
+pub fn json_body(pool: PgPool, user: User, api_url: String) -> Body {
+    let (tx, rx) = mpsc::channel::<Result<String, io::Error>>(4);
+
+    tokio::spawn(async move {
+        let mut writer = ChunkWriter {
+            tx: tx.clone(),
+            chunk: String::new(),
+            items: 0,
+        };
+        if let Err(ExportError::Failed(err)) =
+            write_export(&pool, user, &api_url, &mut writer).await
+        {
+            tracing::error!("Failed to export account data: {}", err);
+            let _ = tx.send(Err(io::Error::other(err))).await;
+        }
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+async fn write_export(
+    pool: &PgPool,
+    user: User,
+    api_url: &str,
+    writer: &mut ChunkWriter,
+) -> Result<(), ExportError> {
+    let user_id = user.id;
+    let oauth_providers = sqlx::query_scalar::<_, String>(
+        "SELECT provider FROM oauth_accounts WHERE user_id = $1 ORDER BY provider",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut header = serde_json::to_string(&ExportHeader {
+        schema_version: SCHEMA_VERSION,
+        generated_at: Utc::now(),
+        profile: &UserResponse::new(user, api_url),
+        oauth_providers: &oauth_providers,
+    })?;
+    // 閉じ括弧を外し、続けて一覧を書き出す
+    header.pop();
+    writer.push_str(&header);
+
+    let threads = sqlx::query_as::<_, ExportedThread>(
+        r#"
+        SELECT id, short_id, title, content, upvote_count, downvote_count, created_at, updated_at
+        FROM threads
+        WHERE user_id = $1
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(user_id)
+    .fetch(pool);
+    writer.write_array("threads", threads).await?;
+
+    let comments = sqlx::query_as::<_, ExportedComment>(
+        r#"
+        SELECT id, short_id, thread_id, parent_id, content, removed_by_moderator,
+               created_at, updated_at
+        FROM comments
+        WHERE user_id = $1 AND deleted_at IS NULL
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(user_id)
+    .fetch(pool);
+    writer.write_array("comments", comments).await?;
+
+    let votes = sqlx::query_as::<_, ExportedVote>(
+        r#"
+        SELECT thread_id, vote_type, created_at, updated_at
+        FROM votes
+        WHERE user_id = $1
+        ORDER BY created_at, thread_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch(pool);
+    writer.write_array("votes", votes).await?;
+
+    writer.push_str("}");
+    writer.flush().await
+}
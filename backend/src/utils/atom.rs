@@ -0,0 +1,331 @@
+// スレッドとコメントの Atom フィード（RFC 4287）
+//
+// タグごと・投稿者ごとのスレッドのフィードと、スレッドごと・ユーザーごとのコメントのフィードで共通の取得と描画を行います。
+// スレッドのページ送りは RFC 5005 の `first`・`previous`・`next` リンクで、一覧APIと同じ `page`・`limit` を使います。
+// コメントはページ送りせず、最新の `COMMENT_FEED_LIMIT` 件を返信の階層を無視して新しい順に載せます。
+// 最新のエントリーの更新日時を Last-Modified に設定し、If-Modified-Since 以降に変わっていなければ 304 を返します。
+// スレッドには下書きや非表示の状態が無く、削除されたスレッドは残らないため、一覧APIと同じスレッドを載せます。
+// コメントは投稿者が削除したものとモデレーターが非表示にしたものを載せません。
+use askama::Template;
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::users,
+    pagination::{Pagination, Threads},
+    utils::http_cache::{http_date, not_modified_since},
+};
+
+/// フィードのファイル名の拡張子
+pub const FEED_EXTENSION: &str = ".atom";
+
+/// コメントのフィードに載せる件数
+pub const COMMENT_FEED_LIMIT: i64 = 50;
+
+/// フィードをキャッシュできる秒数
+pub const FEED_MAX_AGE_SECONDS: u32 = 300;
+
+const CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+
+/// フィードに載せるスレッドの絞り込み
+#[derive(Debug, Clone, Copy)]
+pub enum FeedFilter {
+    Tag(Uuid),
+    Author(Uuid),
+}
+
+/// フィードに載せるコメントの絞り込み
+#[derive(Debug, Clone, Copy)]
+pub enum CommentFeedFilter {
+    Thread(Uuid),
+    Author(Uuid),
+}
+
+/// フィード全体の情報
+pub struct Feed {
+    pub title: String,
+    /// 1ページ目のフィードのURL（ページ番号は付けない）
+    pub self_url: String,
+    /// フィードに対応するフロントエンドのページ
+    pub alternate_url: String,
+    /// エントリーのリンクに使うフロントエンドのURL
+    pub frontend_url: String,
+    /// フィードのID（1ページ目のURLと同じ）
+    pub id: String,
+}
+
+impl Feed {
+    /// `path` は `/api/feeds/...` のようなAPIのパス
+    pub fn new(config: &Config, title: String, path: &str, alternate_url: String) -> Self {
+        let self_url = format!("{}{}", config.api_url, path);
+        Self {
+            title,
+            id: self_url.clone(),
+            self_url,
+            alternate_url,
+            frontend_url: config.frontend_url.clone(),
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct FeedEntry {
+    id: Uuid,
+    title: String,
+    /// フロントエンドでのパス（`/threads/...`）
+    path: String,
+    content: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    username: String,
+    user_display_name: Option<String>,
+}
+
+impl FeedEntry {
+    fn author_name(&self) -> &str {
+        match self.user_display_name.as_deref() {
+            Some(display_name) => display_name,
+            // 退会したユーザーは匿名化したユーザー名を出さない
+            None if self.username.starts_with(users::DELETED_USERNAME) => users::DELETED_USERNAME,
+            None => &self.username,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "feeds/atom.xml")]
+struct AtomTemplate<'a> {
+    feed: &'a Feed,
+    self_url: String,
+    previous_url: Option<String>,
+    next_url: Option<String>,
+    updated: String,
+    entries: Vec<FeedEntry>,
+}
+
+/// `{name}.atom` から `name` を取り出す（拡張子が違う場合は404）
+pub fn strip_feed_extension(file: &str) -> Result<&str, AppError> {
+    file.strip_suffix(FEED_EXTENSION)
+        .filter(|name| !name.is_empty())
+        .ok_or(AppError::NotFound)
+}
+
+/// 新しい順に1ページ分のスレッドを取得し、Atom フィードのレスポンスを作成する
+pub async fn feed_response(
+    pool: &PgPool,
+    request_headers: &HeaderMap,
+    feed: &Feed,
+    filter: FeedFilter,
+    pagination: &Pagination<Threads>,
+) -> Result<Response, AppError> {
+    let (tag_id, user_id) = match filter {
+        FeedFilter::Tag(id) => (Some(id), None),
+        FeedFilter::Author(id) => (None, Some(id)),
+    };
+
+    // 次のページがあるか判定するため1件多く取得する
+    let mut entries = sqlx::query_as::<_, FeedEntry>(
+        r#"
+        SELECT
+            t.id, t.title, '/threads/' || t.id AS path, t.content, t.created_at, t.updated_at,
+            u.username, u.display_name AS user_display_name
+        FROM threads t
+        JOIN users u ON u.id = t.user_id
+        WHERE ($1::uuid IS NULL OR t.id IN (SELECT thread_id FROM thread_tags WHERE tag_id = $1))
+          AND ($2::uuid IS NULL OR t.user_id = $2)
+        ORDER BY t.created_at DESC, t.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(tag_id)
+    .bind(user_id)
+    .bind(pagination.limit as i64 + 1)
+    .bind(pagination.offset)
+    .fetch_all(pool)
+    .await?;
+
+    let has_next = entries.len() > pagination.limit as usize;
+    entries.truncate(pagination.limit as usize);
+
+    let url_of = |page: u32| page_url(feed, pagination, page);
+    render(
+        request_headers,
+        AtomTemplate {
+            feed,
+            self_url: url_of(pagination.page),
+            previous_url: (pagination.page > 1).then(|| url_of(pagination.page - 1)),
+            next_url: has_next.then(|| url_of(pagination.page + 1)),
+            updated: String::new(),
+            entries,
+        },
+    )
+}
+
+/// 新しい順に最新のコメントを取得し、Atom フィードのレスポンスを作成する
+pub async fn comment_feed_response(
+    pool: &PgPool,
+    request_headers: &HeaderMap,
+    feed: &Feed,
+    filter: CommentFeedFilter,
+) -> Result<Response, AppError> {
+    let (thread_id, user_id) = match filter {
+        CommentFeedFilter::Thread(id) => (Some(id), None),
+        CommentFeedFilter::Author(id) => (None, Some(id)),
+    };
+
+    // リンクはスレッドのページ内のコメント（`comment-{id}`）を指す
+    let entries = sqlx::query_as::<_, FeedEntry>(
+        r#"
+        SELECT
+            c.id, 'Re: ' || t.title AS title,
+            '/threads/' || c.thread_id || '#comment-' || c.id AS path,
+            c.content, c.created_at, c.updated_at,
+            u.username, u.display_name AS user_display_name
+        FROM comments c
+        JOIN threads t ON t.id = c.thread_id
+        JOIN users u ON u.id = c.user_id
+        WHERE c.deleted_at IS NULL
+          AND NOT c.removed_by_moderator
+          AND ($1::uuid IS NULL OR c.thread_id = $1)
+          AND ($2::uuid IS NULL OR c.user_id = $2)
+        ORDER BY c.created_at DESC, c.id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(thread_id)
+    .bind(user_id)
+    .bind(COMMENT_FEED_LIMIT)
+    .fetch_all(pool)
+    .await?;
+
+    render(
+        request_headers,
+        AtomTemplate {
+            feed,
+            self_url: feed.self_url.clone(),
+            previous_url: None,
+            next_url: None,
+            updated: String::new(),
+            entries,
+        },
+    )
+}
+
+/// フィードを描画する（`updated` はエントリーから設定する）
+fn render(
+    request_headers: &HeaderMap,
+    mut template: AtomTemplate<'_>,
+) -> Result<Response, AppError> {
+    let cache_control = format!("public, max-age={}", FEED_MAX_AGE_SECONDS);
+
+    let last_modified = template.entries.iter().map(|entry| entry.updated_at).max();
+    if let Some(last_modified) = last_modified {
+        if not_modified_since(request_headers, last_modified) {
+            return Ok((
+                StatusCode::NOT_MODIFIED,
+                [
+                    (header::LAST_MODIFIED, http_date(last_modified)),
+                    (header::CACHE_CONTROL, cache_control.parse().unwrap()),
+                ],
+            )
+                .into_response());
+        }
+    }
+
+    template.updated = last_modified.unwrap_or_else(Utc::now).to_rfc3339();
+    let body = template
+        .render()
+        .map_err(|e| AppError::Internal(format!("Feed render error: {}", e)))?;
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, CONTENT_TYPE.to_string()),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        body,
+    )
+        .into_response();
+    if let Some(last_modified) = last_modified {
+        response
+            .headers_mut()
+            .insert(header::LAST_MODIFIED, http_date(last_modified));
+    }
+    Ok(response)
+}
+
+/// 指定したページのURL（1ページ目はページ番号を付けない。`limit` は指定された場合のみ引き継ぐ）
+fn page_url(feed: &Feed, pagination: &Pagination<Threads>, page: u32) -> String {
+    let mut params = Vec::new();
+    if page > 1 {
+        params.push(format!("page={}", page));
+    }
+    if pagination.explicit {
+        params.push(format!("limit={}", pagination.limit));
+    }
+    if params.is_empty() {
+        feed.self_url.clone()
+    } else {
+        format!("{}?{}", feed.self_url, params.join("&"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn test_拡張子を取り除く() {
+        // フィードの拡張子を取り除くことを確認
+        assert_eq!(strip_feed_extension("rust.atom").unwrap(), "rust");
+        assert!(matches!(
+            strip_feed_extension("rust.rss"),
+            Err(AppError::NotFound)
+        ));
+        assert!(matches!(
+            strip_feed_extension(".atom"),
+            Err(AppError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_ページのurl() {
+        // フィードのページのURLを確認
+        let config = test_config();
+        let feed = Feed::new(
+            &config,
+            "Rust".to_string(),
+            "/api/feeds/tags/rust.atom",
+            "http://localhost:3000/tags/rust".to_string(),
+        );
+        assert_eq!(
+            feed.self_url,
+            "http://localhost:8000/api/feeds/tags/rust.atom"
+        );
+        let limits = &config.content_limits;
+
+        let default = Pagination::<Threads>::new(None, None, None, limits);
+        assert_eq!(page_url(&feed, &default, 1), feed.self_url);
+        assert_eq!(
+            page_url(&feed, &default, 2),
+            format!("{}?page=2", feed.self_url)
+        );
+
+        let explicit = Pagination::<Threads>::new(Some(2), Some(5), None, limits);
+        assert_eq!(
+            page_url(&feed, &explicit, 1),
+            format!("{}?limit=5", feed.self_url)
+        );
+        assert_eq!(
+            page_url(&feed, &explicit, 3),
+            format!("{}?page=3&limit=5", feed.self_url)
+        );
+    }
+}
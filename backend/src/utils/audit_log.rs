@@ -0,0 +1,480 @@
+// 管理者操作の監査ログの記録・検索・CSV出力・アーカイブ
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration as StdDuration,
+};
+
+use axum::body::Body;
+use chrono::{DateTime, Duration, Utc};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::Value;
+use sqlx::{Executor, PgPool, Postgres, QueryBuilder};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::audit_log::{AuditLog, AuditLogQuery, AuditLogResponse},
+    utils::csv,
+};
+
+/// 監査ログに記録する操作の種類
+pub mod actions {
+    pub const USER_MUTE: &str = "user.mute";
+    pub const USER_BAN: &str = "user.ban";
+    pub const USER_UNBAN: &str = "user.unban";
+    pub const USER_FORCE_PASSWORD_RESET: &str = "user.force_password_reset";
+    pub const TAG_RENAME: &str = "tag.rename";
+    pub const TAG_MERGE: &str = "tag.merge";
+    pub const TAG_RULE_CREATE: &str = "tag_rule.create";
+    pub const TAG_RULE_UPDATE: &str = "tag_rule.update";
+    pub const TAG_RULE_DELETE: &str = "tag_rule.delete";
+    pub const TOKENS_PURGE: &str = "maintenance.purge_tokens";
+    pub const COMMENT_REMOVE: &str = "comment.remove";
+    pub const COMMENT_RESTORE: &str = "comment.restore";
+}
+
+/// CSV出力・アーカイブで一度に読み込む行数
+const BATCH_SIZE: i64 = 500;
+
+/// 保持期間を過ぎたログをアーカイブする間隔
+const RETENTION_JOB_INTERVAL: StdDuration = StdDuration::from_secs(24 * 60 * 60);
+
+const SELECT_COLUMNS: &str =
+    "SELECT id, actor_id, action, target_type, target_id, details::text AS details, created_at FROM admin_audit_logs";
+
+pub const CSV_HEADER: &str = "id,created_at,actor_id,action,target_type,target_id,details\r\n";
+
+/// 監査ログを記録する
+pub async fn record<'e, E>(
+    executor: E,
+    actor_id: Uuid,
+    action: &str,
+    target_type: &str,
+    target_id: Option<Uuid>,
+    details: &Value,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO admin_audit_logs (actor_id, action, target_type, target_id, details)
+        VALUES ($1, $2, $3, $4, $5::jsonb)
+        "#,
+    )
+    .bind(actor_id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(details.to_string())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// 検索条件
+#[derive(Debug, Default, Clone)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl From<&AuditLogQuery> for AuditLogFilter {
+    fn from(query: &AuditLogQuery) -> Self {
+        Self {
+            actor_id: query.actor_id,
+            action: query.action.clone(),
+            target_type: query.target_type.clone(),
+            target_id: query.target_id,
+            from: query.from,
+            to: query.to,
+        }
+    }
+}
+
+impl AuditLogFilter {
+    /// 指定された条件のみを WHERE 句に追加する（各条件はインデックスの先頭列に対応）
+    fn push_where(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push(" WHERE TRUE");
+        if let Some(actor_id) = self.actor_id {
+            builder.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(action) = &self.action {
+            builder.push(" AND action = ").push_bind(action.clone());
+        }
+        if let Some(target_type) = &self.target_type {
+            builder
+                .push(" AND target_type = ")
+                .push_bind(target_type.clone());
+        }
+        if let Some(target_id) = self.target_id {
+            builder.push(" AND target_id = ").push_bind(target_id);
+        }
+        if let Some(from) = self.from {
+            builder.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = self.to {
+            builder.push(" AND created_at < ").push_bind(to);
+        }
+    }
+}
+
+/// 条件に一致する件数を取得
+pub async fn count(pool: &PgPool, filter: &AuditLogFilter) -> Result<i64, AppError> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM admin_audit_logs");
+    filter.push_where(&mut builder);
+
+    let total = builder.build_query_scalar::<i64>().fetch_one(pool).await?;
+
+    Ok(total)
+}
+
+/// 条件に一致するログを新しい順に取得
+pub async fn search(
+    pool: &PgPool,
+    filter: &AuditLogFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AuditLog>, AppError> {
+    let mut builder = QueryBuilder::new(SELECT_COLUMNS);
+    filter.push_where(&mut builder);
+    builder
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let logs = builder.build_query_as::<AuditLog>().fetch_all(pool).await?;
+
+    Ok(logs)
+}
+
+/// (created_at, id) より古いログを新しい順に取得（キーセットページング）
+async fn fetch_batch_before(
+    pool: &PgPool,
+    filter: &AuditLogFilter,
+    cursor: Option<(DateTime<Utc>, Uuid)>,
+) -> Result<Vec<AuditLog>, AppError> {
+    let mut builder = QueryBuilder::new(SELECT_COLUMNS);
+    filter.push_where(&mut builder);
+    if let Some((created_at, id)) = cursor {
+        builder
+            .push(" AND (created_at, id) < (")
+            .push_bind(created_at)
+            .push(", ")
+            .push_bind(id)
+            .push(")");
+    }
+    builder
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(BATCH_SIZE);
+
+    let logs = builder.build_query_as::<AuditLog>().fetch_all(pool).await?;
+
+    Ok(logs)
+}
+
+/// 監査ログ1行分のCSVを生成する（改行はCRLF）
+pub fn csv_row(log: &AuditLog) -> String {
+    let fields = [
+        log.id.to_string(),
+        log.created_at.to_rfc3339(),
+        log.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+        log.action.clone(),
+        log.target_type.clone(),
+        log.target_id.map(|id| id.to_string()).unwrap_or_default(),
+        log.details.clone(),
+    ];
+
+    csv::row(&fields)
+}
+
+/// 条件に一致するログをCSVとして逐次出力するレスポンスボディを作成する
+///
+/// 全件をメモリに載せないよう、別タスクでキーセットページングしながら書き出します。
+pub fn csv_body(pool: PgPool, filter: AuditLogFilter) -> Body {
+    let (tx, rx) = mpsc::channel::<Result<String, io::Error>>(4);
+
+    tokio::spawn(async move {
+        if tx.send(Ok(CSV_HEADER.to_string())).await.is_err() {
+            return;
+        }
+
+        let mut cursor = None;
+        loop {
+            let batch = match fetch_batch_before(&pool, &filter, cursor).await {
+                Ok(batch) => batch,
+                Err(err) => {
+                    tracing::error!("Failed to export audit log: {:?}", err);
+                    let _ = tx.send(Err(io::Error::other(err.to_string()))).await;
+                    return;
+                }
+            };
+
+            let Some(last) = batch.last() else {
+                return;
+            };
+            cursor = Some((last.created_at, last.id));
+
+            let chunk: String = batch.iter().map(csv_row).collect();
+            // クライアントが切断した場合は中断
+            if tx.send(Ok(chunk)).await.is_err() {
+                return;
+            }
+            if (batch.len() as i64) < BATCH_SIZE {
+                return;
+            }
+        }
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+/// 圧縮NDJSONとしてアーカイブファイルを書き出す
+///
+/// 書き込み途中のファイルが残らないよう、一時ファイルに書き出してから名前を変更します。
+fn write_archive(path: &Path, logs: Vec<AuditLog>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("gz.tmp");
+    let file = File::create(&tmp_path)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    for log in logs {
+        serde_json::to_writer(&mut encoder, &AuditLogResponse::from(log))?;
+        encoder.write_all(b"\n")?;
+    }
+    let file = encoder
+        .finish()?
+        .into_inner()
+        .map_err(|err| err.into_error())?;
+    file.sync_all()?;
+
+    std::fs::rename(&tmp_path, path)
+}
+
+/// cutoff より古いログをアーカイブファイルに書き出してから削除する
+///
+/// 書き出しに失敗した場合は削除しません。アーカイブしたファイルのパスを返します。
+pub async fn archive_expired(
+    pool: &PgPool,
+    archive_dir: &Path,
+    cutoff: DateTime<Utc>,
+) -> Result<Option<PathBuf>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // 削除までの間に他のジョブが同じ行を扱わないようロックする
+    let logs = sqlx::query_as::<_, AuditLog>(&format!(
+        "{} WHERE created_at < $1 ORDER BY created_at ASC, id ASC FOR UPDATE",
+        SELECT_COLUMNS
+    ))
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if logs.is_empty() {
+        return Ok(None);
+    }
+
+    let ids: Vec<Uuid> = logs.iter().map(|log| log.id).collect();
+    let path = archive_dir.join(format!(
+        "audit-log-{}-{}.ndjson.gz",
+        cutoff.format("%Y%m%dT%H%M%SZ"),
+        Uuid::new_v4().simple()
+    ));
+
+    let archive_path = path.clone();
+    tokio::task::spawn_blocking(move || write_archive(&archive_path, logs))
+        .await
+        .map_err(|err| AppError::Internal(err.to_string()))?
+        .map_err(|err| AppError::Internal(format!("Failed to write audit log archive: {}", err)))?;
+
+    sqlx::query("DELETE FROM admin_audit_logs WHERE id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        archived = ids.len(),
+        path = %path.display(),
+        "Archived expired audit log entries"
+    );
+
+    Ok(Some(path))
+}
+
+/// 保持期間を過ぎたログを定期的にアーカイブするジョブを起動する
+pub fn spawn_retention_job(pool: PgPool, config: &Config) {
+    let retention = Duration::days(config.audit_log_retention_days);
+    let archive_dir = PathBuf::from(&config.audit_log_archive_dir);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_JOB_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = archive_expired(&pool, &archive_dir, Utc::now() - retention).await {
+                tracing::error!("Audit log retention job failed: {:?}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+    use flate2::read::GzDecoder;
+    use serde_json::json;
+    use std::io::Read;
+
+    async fn insert_log(pool: &PgPool, actor_id: Uuid, action: &str, created_at: DateTime<Utc>) {
+        sqlx::query(
+            r#"
+            INSERT INTO admin_audit_logs (actor_id, action, target_type, target_id, details, created_at)
+            VALUES ($1, $2, 'user', $1, '{"note": "test"}', $3)
+            "#,
+        )
+        .bind(actor_id)
+        .bind(action)
+        .bind(created_at)
+        .execute(pool)
+        .await
+        .expect("Failed to insert audit log");
+    }
+
+    fn sample_log(action: &str, details: &str) -> AuditLog {
+        AuditLog {
+            id: Uuid::nil(),
+            actor_id: None,
+            action: action.to_string(),
+            target_type: "user".to_string(),
+            target_id: None,
+            details: details.to_string(),
+            created_at: DateTime::parse_from_rfc3339("2025-06-09T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_csvの行はjsonの詳細を1フィールドとして出力する() {
+        // CSVの行ではJSONの詳細が1つのフィールドとして出力されることを確認
+        let row = csv_row(&sample_log("user.mute", r#"{"reason": "spam, ads"}"#));
+
+        assert_eq!(
+            row,
+            "00000000-0000-0000-0000-000000000000,2025-06-09T00:00:00+00:00,,user.mute,user,,\"{\"\"reason\"\": \"\"spam, ads\"\"}\"\r\n"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_条件に一致するログのみ検索される(pool: PgPool) {
+        // 実行者・操作・期間で絞り込めることを確認
+        let admin = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let now = Utc::now();
+        insert_log(&pool, admin.id, actions::USER_MUTE, now - Duration::days(2)).await;
+        insert_log(&pool, admin.id, "user.unmute", now - Duration::days(1)).await;
+        insert_log(&pool, other.id, actions::USER_MUTE, now).await;
+
+        let by_actor = AuditLogFilter {
+            actor_id: Some(admin.id),
+            ..Default::default()
+        };
+        assert_eq!(count(&pool, &by_actor).await.unwrap(), 2);
+
+        let by_action_and_range = AuditLogFilter {
+            action: Some(actions::USER_MUTE.to_string()),
+            from: Some(now - Duration::days(3)),
+            to: Some(now - Duration::hours(1)),
+            ..Default::default()
+        };
+        let logs = search(&pool, &by_action_and_range, 10, 0).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].actor_id, Some(admin.id));
+    }
+
+    #[sqlx::test]
+    async fn test_保持期間を過ぎたログはアーカイブしてから削除される(
+        pool: PgPool,
+    ) {
+        // 古いログは圧縮NDJSONに書き出された後でテーブルから削除され、新しいログは残ることを確認
+        let admin = create_test_user(&pool, true).await;
+        let now = Utc::now();
+        insert_log(&pool, admin.id, "old.first", now - Duration::days(400)).await;
+        insert_log(&pool, admin.id, "old.second", now - Duration::days(380)).await;
+        insert_log(&pool, admin.id, "recent", now - Duration::days(1)).await;
+
+        let archive_dir = std::env::temp_dir().join(format!("audit-log-test-{}", Uuid::new_v4()));
+        let path = archive_expired(&pool, &archive_dir, now - Duration::days(365))
+            .await
+            .expect("archive should succeed")
+            .expect("archive file should be created");
+
+        // アーカイブの内容を確認
+        let mut ndjson = String::new();
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut ndjson)
+            .unwrap();
+        let entries: Vec<Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["action"], "old.first");
+        assert_eq!(entries[1]["action"], "old.second");
+        assert_eq!(entries[0]["details"], json!({ "note": "test" }));
+
+        // テーブルには新しいログのみ残る
+        let remaining = sqlx::query_scalar::<_, String>("SELECT action FROM admin_audit_logs")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, vec!["recent".to_string()]);
+
+        // 対象がなければファイルは作成されない
+        let path = archive_expired(&pool, &archive_dir, now - Duration::days(365))
+            .await
+            .unwrap();
+        assert!(path.is_none());
+
+        std::fs::remove_dir_all(&archive_dir).ok();
+    }
+
+    #[sqlx::test]
+    async fn test_アーカイブの書き出しに失敗した場合は削除しない(
+        pool: PgPool,
+    ) {
+        // 書き出し先がファイルでディレクトリを作成できない場合、ログが残ることを確認
+        let admin = create_test_user(&pool, true).await;
+        insert_log(&pool, admin.id, "old", Utc::now() - Duration::days(400)).await;
+
+        let blocker = std::env::temp_dir().join(format!("audit-log-blocker-{}", Uuid::new_v4()));
+        std::fs::write(&blocker, b"not a directory").unwrap();
+
+        let result = archive_expired(&pool, &blocker, Utc::now() - Duration::days(365)).await;
+        assert!(result.is_err());
+
+        let remaining = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM admin_audit_logs")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1);
+
+        std::fs::remove_file(&blocker).ok();
+    }
+}
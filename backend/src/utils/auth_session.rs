@@ -0,0 +1,82 @@
+// ログイン成功時のトークン発行（リフレッシュトークンの保存とアクセストークンの生成）
+use axum::http::HeaderMap;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::jwt::{JwtService, ACCESS_TOKEN_EXPIRES_IN_MINUTES},
+    config::Config,
+    error::AppError,
+    models::{
+        auth::{AuthResponse, UserInfo},
+        User,
+    },
+    utils::{
+        refresh_token_cookie, request_info, token_generator::TokenGenerator,
+        token_hash::hash_refresh_token,
+    },
+};
+
+/// 新しいセッションを作成し、認証レスポンスを返す
+///
+/// 返却するヘッダーにはCookieモードの場合のSet-Cookieが含まれます。
+pub async fn issue_session(
+    pool: &PgPool,
+    config: &Config,
+    jwt: &JwtService,
+    tokens: &dyn TokenGenerator,
+    user: User,
+    headers: &HeaderMap,
+) -> Result<(HeaderMap, AuthResponse), AppError> {
+    // Generate tokens
+    let refresh_token = tokens.secure_token();
+    let refresh_token_hash = hash_refresh_token(&refresh_token);
+
+    // Store refresh token
+    let session_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at, last_used_at, user_agent, ip_address)
+        VALUES ($1, $2, NOW() + INTERVAL '7 days', NOW(), $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(user.id)
+    .bind(&refresh_token_hash)
+    .bind(request_info::user_agent(headers))
+    .bind(request_info::client_ip(headers))
+    .fetch_one(pool)
+    .await?;
+
+    let access_token = jwt.encode(&jwt.claims(
+        &user.id.to_string(),
+        &user.username,
+        &user.email,
+        user.role,
+        &session_id.to_string(),
+        ACCESS_TOKEN_EXPIRES_IN_MINUTES,
+    ))?;
+
+    let (cookie_headers, refresh_token) =
+        refresh_token_cookie::deliver_refresh_token(config, refresh_token);
+
+    let ban = user.active_ban(Utc::now());
+    let response = AuthResponse {
+        access_token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: 900, // 15 minutes in seconds
+        user: UserInfo {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            display_name: user.display_name,
+            avatar_url: user.avatar_url,
+            email_verified: user.email_verified,
+            created_at: user.created_at,
+        },
+        ban,
+    };
+
+    Ok((cookie_headers, response))
+}
@@ -0,0 +1,522 @@
+// 外部のアバター画像の中継と縮小
+//
+// avatar_url は任意の外部URLのため、フロントエンドが直接読み込むと閲覧者のIPアドレスが第三者に送られ、
+// コメント一覧でも元の大きさの画像を読み込むことになります。
+// APIが画像を取得して正方形に縮小し、URLとサイズごとにメモリ上にキャッシュして返します。
+// 同じ画像への同時のリクエストでは、取得と縮小を1回だけ行います。
+//
+// 利用者が指定したURLへサーバーから接続するため、サーバー内部のアドレスには接続しません。
+// URLに書かれたIPアドレスに加えて、名前解決の結果とリダイレクト先も確認します。
+use async_trait::async_trait;
+use axum::body::Bytes;
+use image::{
+    imageops::FilterType,
+    io::{Limits, Reader as ImageReader},
+    ImageOutputFormat,
+};
+use reqwest::{header::CONTENT_TYPE, redirect::Policy, Client, Url};
+use std::{
+    io::Cursor,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    config::Config,
+    error::{AppError, Result},
+    http_client::USER_AGENT,
+    utils::cache::Cache,
+};
+
+/// 縮小できるサイズ（正方形の一辺のピクセル数）
+pub const THUMBNAIL_SIZES: [u32; 4] = [32, 64, 128, 256];
+/// `size` を省略した場合のサイズ
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 64;
+/// 取得する画像の最大サイズ（バイト）
+pub const MAX_IMAGE_BYTES: usize = 2 * 1024 * 1024;
+/// 画像の取得を待つ最大時間
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// 読み込む画像の最大の幅・高さ（小さなファイルで巨大な画像を展開させないため）
+const MAX_IMAGE_DIMENSION: u32 = 4096;
+/// メモリ上に保存する縮小画像の最大件数
+const MAX_CACHED_THUMBNAILS: usize = 1000;
+/// 縮小画像をキャッシュする期間（元の画像が差し替えられた場合に取得し直すため）
+const THUMBNAIL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 取得した画像
+#[derive(Debug, Clone)]
+pub struct RemoteImage {
+    pub content_type: Option<String>,
+    pub body: Bytes,
+}
+
+/// 外部の画像を取得する（テストでは固定の画像を返すものに差し替える）
+#[async_trait]
+pub trait AvatarFetcher: Send + Sync {
+    /// `MAX_IMAGE_BYTES` を超える場合や `FETCH_TIMEOUT` 以内に取得できない場合はエラーを返す
+    async fn fetch(&self, url: &str) -> Result<RemoteImage>;
+}
+
+/// HTTPで画像を取得する
+///
+/// 共有の `HttpClient` は使わず、確認した接続先のアドレスに固定したクライアントをリクエストごとに作成します。
+/// 縮小した画像はキャッシュするため、接続を使い回せなくても取得の回数は多くなりません。
+/// プロキシを経由すると接続先のアドレスを確認できないため、HTTP_PROXY_URL は使いません。
+pub struct HttpAvatarFetcher {
+    connect_timeout: Duration,
+}
+
+impl HttpAvatarFetcher {
+    /// HTTP_CONNECT_TIMEOUT_SECONDS の設定で作成する
+    pub fn new(config: &Config) -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(config.http_connect_timeout_seconds),
+        }
+    }
+
+    /// リダイレクトに従わず、ホスト名を `addrs` に固定したクライアントを作成する
+    fn client(&self, host: Option<(&str, &[SocketAddr])>) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(FETCH_TIMEOUT)
+            .connect_timeout(self.connect_timeout)
+            .redirect(Policy::none())
+            .no_proxy();
+        if let Some((domain, addrs)) = host {
+            builder = builder.resolve_to_addrs(domain, addrs);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// ホスト名を名前解決し、公開されたアドレスだけを返す（無い場合はエラー）
+async fn resolve_public_addrs(host: &str) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<_> = tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(|err| AppError::BadRequest(format!("Failed to resolve {}: {}", host, err)))?
+        .filter(|addr| is_public_ip(addr.ip()))
+        .collect();
+    if addrs.is_empty() {
+        return Err(AppError::BadRequest(format!(
+            "{} does not resolve to a public address",
+            host
+        )));
+    }
+
+    Ok(addrs)
+}
+
+#[async_trait]
+impl AvatarFetcher for HttpAvatarFetcher {
+    async fn fetch(&self, url: &str) -> Result<RemoteImage> {
+        let too_large = || AppError::BadRequest(format!("Image exceeds {} bytes", MAX_IMAGE_BYTES));
+
+        let parsed =
+            Url::parse(url).map_err(|err| AppError::BadRequest(format!("Invalid URL: {}", err)))?;
+        // 確認したアドレスにそのまま接続し、確認後に名前解決の結果が変わっても内部のアドレスには接続しない
+        let client = match parsed.domain() {
+            Some(domain) => self.client(Some((domain, &resolve_public_addrs(domain).await?)))?,
+            None => self.client(None)?,
+        };
+
+        let mut response = client.get(parsed).send().await?.error_for_status()?;
+        // リダイレクト（3xx）はエラーにならないため、成功の応答か確認する
+        if !response.status().is_success() {
+            return Err(AppError::BadRequest(format!(
+                "Unexpected status: {}",
+                response.status()
+            )));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_IMAGE_BYTES as u64)
+        {
+            return Err(too_large());
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        // Content-Length が無い・偽っている場合に備えて、読み込みながら上限を確認する
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_IMAGE_BYTES {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(RemoteImage {
+            content_type,
+            body: Bytes::from(body),
+        })
+    }
+}
+
+/// 外部のアバター画像を縮小して返す（縮小した画像はメモリ上にキャッシュする）
+pub struct AvatarProxy {
+    fetcher: Arc<dyn AvatarFetcher>,
+    thumbnails: Cache<(String, u32), Bytes>,
+}
+
+impl AvatarProxy {
+    pub fn new(fetcher: Arc<dyn AvatarFetcher>) -> Self {
+        Self {
+            fetcher,
+            thumbnails: Cache::new(MAX_CACHED_THUMBNAILS, Some(THUMBNAIL_TTL)),
+        }
+    }
+
+    /// HTTPで画像を取得する（`HttpAvatarFetcher`）
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(Arc::new(HttpAvatarFetcher::new(config)))
+    }
+
+    /// `url` の画像を `size` ピクセル四方に縮小したPNGを返す
+    ///
+    /// 取得できない場合や画像ではない場合は `None` を返します（呼び出し側で代わりの画像を返す）。
+    /// 失敗は一時的な場合があるため、キャッシュしません。
+    pub async fn thumbnail(&self, url: &str, size: u32) -> Option<Bytes> {
+        if !is_fetchable_url(url) {
+            return None;
+        }
+
+        self.thumbnails
+            .get_or_compute((url.to_string(), size), || self.fetch_thumbnail(url, size))
+            .await
+            .ok()
+    }
+
+    /// 画像を取得して縮小する（失敗した理由はログに記録する）
+    async fn fetch_thumbnail(&self, url: &str, size: u32) -> std::result::Result<Bytes, ()> {
+        let remote = match self.fetcher.fetch(url).await {
+            Ok(remote) => remote,
+            Err(err) => {
+                tracing::debug!(url, "Failed to fetch avatar: {}", err);
+                return Err(());
+            }
+        };
+        if !remote
+            .content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.starts_with("image/"))
+        {
+            tracing::debug!(url, content_type = ?remote.content_type, "Avatar is not an image");
+            return Err(());
+        }
+
+        // 画像の展開と縮小はCPUを使うため、非同期のワーカーを止めないよう別スレッドで行う
+        let resized = tokio::task::spawn_blocking(move || resize_square(&remote.body, size)).await;
+        match resized {
+            Ok(Ok(image)) => Ok(Bytes::from(image)),
+            Ok(Err(err)) => {
+                tracing::debug!(url, "Failed to resize avatar: {}", err);
+                Err(())
+            }
+            Err(err) => {
+                tracing::warn!(url, "Avatar resize task failed: {}", err);
+                Err(())
+            }
+        }
+    }
+}
+
+/// 取得してよいURLか（http(s) のみ。サーバー内部のアドレスは取得しない）
+fn is_fetchable_url(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    match url.host_str() {
+        None | Some("localhost") => false,
+        Some(host) => match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(ip) => is_public_ip(ip),
+            // ホスト名は接続時に名前解決の結果を確認する（`HttpAvatarFetcher`）
+            Err(_) => true,
+        },
+    }
+}
+
+/// インターネット上の公開されたアドレスか（ループバック・プライベート・リンクローカルなどは除く）
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            // ::ffff:127.0.0.1 のようなIPv4射影アドレスはIPv4として確認する
+            Some(ipv4) => is_public_ipv4(ipv4),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // 0.0.0.0/8（このネットワーク）
+        || first == 0
+        // 100.64.0.0/10（キャリアグレードNAT）
+        || (first == 100 && (second & 0xc0) == 64))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7（ユニークローカルアドレス）
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10（リンクローカルアドレス）
+        || (first & 0xffc0) == 0xfe80)
+}
+
+/// 画像の中央を正方形に切り抜いて `size` ピクセル四方に縮小し、PNGにエンコードする
+fn resize_square(data: &[u8], size: u32) -> Result<Vec<u8>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|err| AppError::BadRequest(format!("Unknown image format: {}", err)))?;
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|err| AppError::BadRequest(format!("Invalid image: {}", err)))?;
+
+    let mut buffer = Vec::new();
+    image
+        .resize_to_fill(size, size, FilterType::Lanczos3)
+        .write_to(&mut Cursor::new(&mut buffer), ImageOutputFormat::Png)
+        .map_err(|_| AppError::Internal("PNG encoding error".to_string()))?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, RgbImage};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 固定の応答を返し、呼び出された回数を数える
+    struct StubFetcher {
+        content_type: &'static str,
+        body: Vec<u8>,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AvatarFetcher for StubFetcher {
+        async fn fetch(&self, _url: &str) -> Result<RemoteImage> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(RemoteImage {
+                content_type: Some(self.content_type.to_string()),
+                body: Bytes::from(self.body.clone()),
+            })
+        }
+    }
+
+    fn stub(content_type: &'static str, body: Vec<u8>) -> Arc<StubFetcher> {
+        Arc::new(StubFetcher {
+            content_type,
+            body,
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    /// 横長のPNG画像
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image: RgbImage = ImageBuffer::from_pixel(width, height, Rgb([200, 30, 30]));
+        let mut buffer = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buffer), ImageOutputFormat::Png)
+            .unwrap();
+        buffer
+    }
+
+    const URL: &str = "https://example.com/avatar.png";
+
+    #[tokio::test]
+    async fn test_正方形に縮小しurlとサイズごとにキャッシュする() {
+        // 正方形に縮小し、URLとサイズごとにキャッシュすることを確認
+        let fetcher = stub("image/png", png(300, 200));
+        let proxy = AvatarProxy::new(fetcher.clone());
+
+        let small = proxy.thumbnail(URL, 32).await.unwrap();
+        let decoded = image::load_from_memory(&small).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (32, 32));
+
+        // 同じURLとサイズは取得し直さない
+        assert_eq!(proxy.thumbnail(URL, 32).await.unwrap(), small);
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 1);
+
+        // サイズが異なる場合は別の画像になる
+        let large = proxy.thumbnail(URL, 128).await.unwrap();
+        assert_eq!(image::load_from_memory(&large).unwrap().width(), 128);
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_同じ画像への同時のリクエストでは1回だけ取得する() {
+        // 同じ画像への同時のリクエストでは1回だけ取得することを確認
+        let fetcher = stub("image/png", png(100, 100));
+        let proxy = AvatarProxy::new(fetcher.clone());
+
+        let (a, b, c) = tokio::join!(
+            proxy.thumbnail(URL, 64),
+            proxy.thumbnail(URL, 64),
+            proxy.thumbnail(URL, 64)
+        );
+        assert!(a.is_some() && a == b && b == c);
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_画像以外や壊れた画像は縮小せずキャッシュもしない() {
+        // 画像以外や壊れた画像は縮小せず、キャッシュもしないことを確認
+        let html = stub("text/html", b"<html></html>".to_vec());
+        let proxy = AvatarProxy::new(html.clone());
+        assert!(proxy.thumbnail(URL, 64).await.is_none());
+        assert!(proxy.thumbnail(URL, 64).await.is_none());
+        assert_eq!(html.calls.load(Ordering::SeqCst), 2);
+
+        let broken = AvatarProxy::new(stub("image/png", b"not a png".to_vec()));
+        assert!(broken.thumbnail(URL, 64).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_内部のアドレスは取得しない() {
+        // 内部のアドレスは取得しないことを確認
+        let fetcher = stub("image/png", png(10, 10));
+        let proxy = AvatarProxy::new(fetcher.clone());
+
+        for url in [
+            "http://localhost/avatar.png",
+            "http://127.0.0.1/avatar.png",
+            "http://10.0.0.1/avatar.png",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/avatar.png",
+            "http://[fd00::1]/avatar.png",
+            "http://[fe80::1]/avatar.png",
+            "http://[::ffff:127.0.0.1]/avatar.png",
+            "http://100.64.0.1/avatar.png",
+            "file:///etc/passwd",
+            "not a url",
+        ] {
+            assert!(proxy.thumbnail(url, 64).await.is_none(), "{}", url);
+        }
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_公開されたアドレスだけを許可する() {
+        // 公開されたアドレスだけを許可することを確認
+        for ip in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.100.100.200",
+            "0.0.0.0",
+            "224.0.0.1",
+            "::1",
+            "::",
+            "fc00::1",
+            "fdff:ffff::1",
+            "fe80::1",
+            "febf::1",
+            "ff02::1",
+            "::ffff:10.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    /// 大きな画像・小さな画像・小さな画像へのリダイレクトを返すサーバーを起動する
+    async fn spawn_server() -> std::net::SocketAddr {
+        use axum::{response::Redirect, routing::get, Router};
+
+        let app = Router::new()
+            .route(
+                "/large",
+                get(|| async {
+                    (
+                        [("content-type", "image/png")],
+                        vec![0u8; MAX_IMAGE_BYTES + 1],
+                    )
+                }),
+            )
+            .route(
+                "/small",
+                get(|| async { ([("content-type", "image/png")], png(10, 10)) }),
+            )
+            .route("/redirect", get(|| async { Redirect::to("/small") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        addr
+    }
+
+    fn http_fetcher() -> HttpAvatarFetcher {
+        HttpAvatarFetcher::new(&crate::test_utils::test_config())
+    }
+
+    #[tokio::test]
+    async fn test_上限を超える画像は取得を打ち切る() {
+        // 上限を超える画像は取得を打ち切ることを確認
+        let addr = spawn_server().await;
+        let fetcher = http_fetcher();
+
+        assert!(fetcher
+            .fetch(&format!("http://{}/large", addr))
+            .await
+            .is_err());
+
+        let small = fetcher
+            .fetch(&format!("http://{}/small", addr))
+            .await
+            .unwrap();
+        assert_eq!(small.content_type.as_deref(), Some("image/png"));
+        assert_eq!(small.body, png(10, 10));
+    }
+    #[tokio::test]
+    async fn test_リダイレクトには従わない() {
+        // リダイレクトには従わないことを確認
+        let addr = spawn_server().await;
+
+        // リダイレクト先の /small は取得できるが、リダイレクトを経由すると取得しない
+        let result = http_fetcher()
+            .fetch(&format!("http://{}/redirect", addr))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_内部のアドレスに解決されるホスト名には接続しない() {
+        // 内部のアドレスに解決されるホスト名には接続しないことを確認
+        let addr = spawn_server().await;
+
+        // 127.0.0.1 には直接接続できるが、localhost は名前解決の結果がループバックのため接続しない
+        let result = http_fetcher()
+            .fetch(&format!("http://localhost:{}/small", addr.port()))
+            .await;
+        assert!(result.is_err());
+    }
+}
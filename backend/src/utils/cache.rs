@@ -0,0 +1,365 @@
+// プロセス内のキャッシュ
+//
+// 件数の上限（最も長く使われていない値から捨てる）と有効期限を持つキーごとのキャッシュです。
+// `get_or_compute` は同じキーの計算を同時に1つだけ行い、計算中に来た呼び出しはその結果を待ちます
+// （有効期限切れの直後に同じ値の読み込みが一斉に走るのを防ぐ）。
+// 計算に失敗した場合はキャッシュせず、待っていた呼び出しのうち次の1つが計算し直します。
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub struct Cache<K, V> {
+    max_entries: usize,
+    ttl: Option<Duration>,
+    state: Mutex<State<K, V>>,
+}
+
+struct State<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    // 計算中のキー（同じキーの計算はこのロックを取ってから行う）
+    in_flight: HashMap<K, Arc<tokio::sync::Mutex<()>>>,
+    // 最後に使った順を判別するため、使うたびに増やす
+    clock: u64,
+    // 計算中に捨てられたかを判別するため、捨てるたびに増やす
+    generation: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    expires_at: Option<Instant>,
+    last_used: u64,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// `max_entries` 件まで保存し、`ttl` を過ぎた値は使わない（`None` の場合は捨てるまで使う）
+    pub fn new(max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            max_entries,
+            ttl,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                in_flight: HashMap::new(),
+                clock: 0,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// 有効期限内の値を返す
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(key)?;
+        if entry
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Instant::now())
+        {
+            state.entries.remove(key);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.value.clone())
+    }
+
+    /// 値を保存する（上限を超えた場合は最も長く使われていない値を捨てる）
+    pub fn insert(&self, key: K, value: V) {
+        let mut state = self.state.lock().unwrap();
+        self.insert_locked(&mut state, key, value);
+    }
+
+    /// キャッシュした値を返す（無い場合は `compute` で計算して保存する）
+    ///
+    /// 同じキーの計算は同時に1つだけ行い、他の呼び出しは計算が終わるのを待って同じ値を返します。
+    /// 計算中に `invalidate` された場合、計算した値は返しますが保存しません。
+    pub async fn get_or_compute<F, Fut, E>(&self, key: K, compute: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+
+        let flight = {
+            let mut state = self.state.lock().unwrap();
+            state.in_flight.entry(key.clone()).or_default().clone()
+        };
+        let result = {
+            let _guard = flight.lock().await;
+            // 待っている間に他の呼び出しが計算した値を使う
+            match self.get(&key) {
+                Some(value) => Ok(value),
+                None => {
+                    let generation = self.state.lock().unwrap().generation;
+                    let result = compute().await;
+                    if let Ok(value) = &result {
+                        let mut state = self.state.lock().unwrap();
+                        if state.generation == generation {
+                            self.insert_locked(&mut state, key.clone(), value.clone());
+                        }
+                    }
+                    result
+                }
+            }
+        };
+
+        // 待っている呼び出しが無ければ計算中のキーから外す（マップと自分の2つだけが参照している）
+        // 次に確認する呼び出しが正しく数えられるよう、自分の参照はロックを持ったまま手放す
+        let mut state = self.state.lock().unwrap();
+        if Arc::strong_count(&flight) == 2 {
+            state.in_flight.remove(&key);
+        }
+        drop(flight);
+        drop(state);
+
+        result
+    }
+
+    /// キーの値を捨てる（元のデータを変更したときに呼ぶ）
+    pub fn invalidate(&self, key: &K) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.entries.remove(key);
+    }
+
+    fn insert_locked(&self, state: &mut State<K, V>, key: K, value: V) {
+        if self.max_entries == 0 {
+            return;
+        }
+        state.clock += 1;
+        let entry = Entry {
+            value,
+            expires_at: self.ttl.map(|ttl| Instant::now() + ttl),
+            last_used: state.clock,
+        };
+        state.entries.insert(key, entry);
+
+        // 期限切れの値を先に捨て、それでも上限を超える場合は最も長く使われていない値から捨てる
+        if state.entries.len() > self.max_entries {
+            let now = Instant::now();
+            state
+                .entries
+                .retain(|_, entry| entry.expires_at.is_none_or(|expires_at| expires_at > now));
+        }
+        while state.entries.len() > self.max_entries {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn compute_with(
+        calls: &AtomicU32,
+        value: &'static str,
+    ) -> Result<&'static str, &'static str> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(value)
+    }
+
+    fn len<K, V>(cache: &Cache<K, V>) -> usize {
+        cache.state.lock().unwrap().entries.len()
+    }
+
+    #[tokio::test]
+    async fn test_保存した値を計算せずに返す() {
+        // 保存した値は計算せずに返すことを確認
+        let cache = Cache::new(10, Some(Duration::from_secs(60)));
+        let calls = AtomicU32::new(0);
+
+        let first = cache
+            .get_or_compute("key", || compute_with(&calls, "first"))
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_compute("key", || compute_with(&calls, "second"))
+            .await
+            .unwrap();
+
+        assert_eq!((first, second), ("first", "first"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&"key"), Some("first"));
+        assert_eq!(cache.get(&"other"), None);
+    }
+
+    #[tokio::test]
+    async fn test_有効期限を過ぎた値は計算し直す() {
+        // 有効期限を過ぎた値は計算し直すことを確認
+        let cache = Cache::new(10, Some(Duration::from_millis(20)));
+        let calls = AtomicU32::new(0);
+
+        cache
+            .get_or_compute("key", || compute_with(&calls, "before"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(cache.get(&"key"), None);
+
+        let value = cache
+            .get_or_compute("key", || compute_with(&calls, "after"))
+            .await
+            .unwrap();
+        assert_eq!(value, "after");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_上限を超えると最も長く使われていない値から捨てる() {
+        // 上限を超えると最も長く使われていない値から捨てることを確認
+        let cache = Cache::new(2, None);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        // a を使ったので、次に捨てられるのは b
+        assert_eq!(cache.get(&"a"), Some(1));
+        cache.insert("c", 3);
+
+        assert_eq!(len(&cache), 2);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"c"), Some(3));
+
+        // 上限が 0 の場合は保存しない
+        let disabled = Cache::new(0, None);
+        disabled.insert("a", 1);
+        assert_eq!(disabled.get(&"a"), None);
+    }
+
+    #[test]
+    fn test_上限を超えると期限切れの値を先に捨てる() {
+        // 上限を超えると期限切れの値を先に捨てることを確認
+        let cache = Cache::new(2, Some(Duration::from_millis(60)));
+        cache.insert("a", 1);
+        std::thread::sleep(Duration::from_millis(40));
+        cache.insert("b", 2);
+        // a の方が最近使われているが、先に期限が切れる
+        assert_eq!(cache.get(&"a"), Some(1));
+        std::thread::sleep(Duration::from_millis(40));
+        cache.insert("c", 3);
+
+        let state = cache.state.lock().unwrap();
+        assert!(!state.entries.contains_key("a"));
+        assert!(state.entries.contains_key("b"));
+        assert!(state.entries.contains_key("c"));
+    }
+
+    #[tokio::test]
+    async fn test_同じキーの計算は同時に1つだけ行う() {
+        // 同じキーの計算は同時に1つだけ行うことを確認
+        let cache = Arc::new(Cache::new(10, None));
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let cache = cache.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    cache
+                        .get_or_compute("key", || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok::<_, ()>(42)
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Ok(42));
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // 計算が終わったキーは計算中のキーに残らない
+        assert!(cache.state.lock().unwrap().in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_計算に失敗した場合は保存せず次の呼び出しで計算し直す() {
+        // 計算に失敗した場合は保存せず、次の呼び出しで計算し直すことを確認
+        let cache = Cache::new(10, None);
+        let calls = AtomicU32::new(0);
+
+        let result = cache
+            .get_or_compute("key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<u32, _>("unavailable")
+            })
+            .await;
+        assert_eq!(result, Err("unavailable"));
+        assert_eq!(cache.get(&"key"), None);
+
+        let result = cache
+            .get_or_compute("key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, &str>(1)
+            })
+            .await;
+        assert_eq!(result, Ok(1));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_捨てた値は計算し直す() {
+        // 捨てた値は計算し直すことを確認
+        let cache = Cache::new(10, None);
+        let calls = AtomicU32::new(0);
+        cache.insert("a", "a");
+        cache.insert("b", "b");
+
+        cache.invalidate(&"a");
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some("b"));
+        let value = cache
+            .get_or_compute("a", || compute_with(&calls, "recomputed"))
+            .await
+            .unwrap();
+        assert_eq!(value, "recomputed");
+    }
+
+    #[tokio::test]
+    async fn test_計算中に捨てられた値は保存しない() {
+        // 計算中に捨てられた値は保存しないことを確認
+        let cache = Arc::new(Cache::new(10, None));
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let task = {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                cache
+                    .get_or_compute("key", || async move {
+                        started_tx.send(()).unwrap();
+                        release_rx.await.unwrap();
+                        Ok::<_, ()>("stale")
+                    })
+                    .await
+            })
+        };
+        started_rx.await.unwrap();
+        cache.invalidate(&"key");
+        release_tx.send(()).unwrap();
+
+        // 計算した値は返すが、古い可能性があるため保存しない
+        assert_eq!(task.await.unwrap(), Ok("stale"));
+        assert_eq!(cache.get(&"key"), None);
+    }
+}
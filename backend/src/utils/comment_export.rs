@@ -0,0 +1,201 @@
+// スレッドのコメントのCSVエクスポート
+use std::io;
+
+use axum::body::Body;
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use sqlx::{FromRow, PgPool};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        comments::{DELETED_CONTENT, REMOVED_CONTENT},
+        users,
+    },
+    utils::{csv, tags},
+};
+
+/// CSVのヘッダー行
+pub const CSV_HEADER: &str = "id,author_username,parent_id,depth,created_at,content\r\n";
+
+/// まとめて送信する行数
+const CHUNK_ROWS: usize = 100;
+
+/// エクスポートする1コメント分の行
+#[derive(Debug, Clone, FromRow)]
+pub struct ExportedComment {
+    pub id: Uuid,
+    pub author_username: String,
+    pub parent_id: Option<Uuid>,
+    /// ルートのコメントを0とした階層の深さ
+    pub depth: i32,
+    pub created_at: DateTime<Utc>,
+    pub content: String,
+}
+
+/// コメント1行分のCSVを生成する
+pub fn csv_row(comment: &ExportedComment) -> String {
+    csv::row(&[
+        comment.id.to_string(),
+        users::display_username(comment.author_username.clone()),
+        comment
+            .parent_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        comment.depth.to_string(),
+        comment.created_at.to_rfc3339(),
+        comment.content.clone(),
+    ])
+}
+
+/// ダウンロード時のファイル名（スレッドのタイトルから生成したスラッグを使う）
+///
+/// スラッグが空になる場合はスレッドIDを使います。
+pub fn file_name(thread_id: Uuid, title: &str) -> String {
+    let slug = tags::slugify(title);
+    if slug.is_empty() {
+        format!("{}-comments.csv", thread_id)
+    } else {
+        format!("{}-comments.csv", slug)
+    }
+}
+
+/// `Content-Disposition` ヘッダーの値
+///
+/// 日本語のファイル名に対応するため `filename*` にUTF-8でエンコードした名前を指定し、
+/// 対応していないクライアント向けに `filename` にはスレッドIDを使った名前を指定します。
+pub fn content_disposition(thread_id: Uuid, title: &str) -> String {
+    let name = file_name(thread_id, title);
+    format!(
+        "attachment; filename=\"{}-comments.csv\"; filename*=UTF-8''{}.csv",
+        thread_id,
+        tags::encode_slug(name.trim_end_matches(".csv"))
+    )
+}
+
+/// スレッドのコメントを投稿順にCSVとして逐次出力するレスポンスボディを作成する
+///
+/// コメント数の多いスレッドでも全件をメモリに載せないよう、別タスクで行を読みながら書き出します。
+/// 削除・非表示になったコメントの本文はコメント一覧と同じく `[deleted]`・`[removed]` に置き換えます。
+pub fn csv_body(pool: PgPool, thread_id: Uuid) -> Body {
+    let (tx, rx) = mpsc::channel::<Result<String, io::Error>>(4);
+
+    tokio::spawn(async move {
+        if tx.send(Ok(CSV_HEADER.to_string())).await.is_err() {
+            return;
+        }
+
+        let mut rows = sqlx::query_as::<_, ExportedComment>(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT id, user_id, parent_id, content, created_at, deleted_at,
+                       removed_by_moderator, 0 AS depth
+                FROM comments
+                WHERE thread_id = $1 AND parent_id IS NULL
+                UNION ALL
+                SELECT c.id, c.user_id, c.parent_id, c.content, c.created_at, c.deleted_at,
+                       c.removed_by_moderator, tree.depth + 1
+                FROM comments c
+                JOIN tree ON c.parent_id = tree.id
+            )
+            SELECT tree.id, u.username AS author_username, tree.parent_id, tree.depth,
+                   tree.created_at,
+                   CASE
+                       WHEN tree.removed_by_moderator THEN $2
+                       WHEN tree.deleted_at IS NOT NULL THEN $3
+                       ELSE tree.content
+                   END AS content
+            FROM tree
+            JOIN users u ON u.id = tree.user_id
+            ORDER BY tree.created_at, tree.id
+            "#,
+        )
+        .bind(thread_id)
+        .bind(REMOVED_CONTENT)
+        .bind(DELETED_CONTENT)
+        .fetch(&pool);
+
+        let mut chunk = String::new();
+        let mut chunk_rows = 0;
+        loop {
+            match rows.try_next().await {
+                Ok(Some(comment)) => {
+                    chunk.push_str(&csv_row(&comment));
+                    chunk_rows += 1;
+                    if chunk_rows < CHUNK_ROWS {
+                        continue;
+                    }
+                }
+                Ok(None) => {
+                    if !chunk.is_empty() {
+                        let _ = tx.send(Ok(chunk)).await;
+                    }
+                    return;
+                }
+                Err(err) => {
+                    tracing::error!("Failed to export comments: {:?}", err);
+                    let _ = tx.send(Err(io::Error::other(err.to_string()))).await;
+                    return;
+                }
+            }
+
+            // クライアントが切断した場合は中断
+            if tx.send(Ok(std::mem::take(&mut chunk))).await.is_err() {
+                return;
+            }
+            chunk_rows = 0;
+        }
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_comment(content: &str) -> ExportedComment {
+        ExportedComment {
+            id: Uuid::nil(),
+            author_username: "taro".to_string(),
+            parent_id: None,
+            depth: 0,
+            created_at: DateTime::parse_from_rfc3339("2025-06-21T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_本文の改行と引用符はエスケープして保持される() {
+        // 本文の改行と引用符はエスケープして保持されることを確認
+        let row = csv_row(&sample_comment("Q: \"AMA\"とは？\n答え, です"));
+
+        assert_eq!(
+            row,
+            "00000000-0000-0000-0000-000000000000,taro,,0,2025-06-21T00:00:00+00:00,\"Q: \"\"AMA\"\"とは？\n答え, です\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_ファイル名はタイトルのスラッグから生成される() {
+        // ファイル名はタイトルのスラッグから生成されることを確認
+        let id = Uuid::nil();
+        assert_eq!(
+            file_name(id, "Rust AMA 2025!"),
+            "rust-ama-2025-comments.csv"
+        );
+        assert_eq!(
+            file_name(id, "!!!"),
+            "00000000-0000-0000-0000-000000000000-comments.csv"
+        );
+        assert_eq!(
+            content_disposition(id, "質問 箱"),
+            "attachment; filename=\"00000000-0000-0000-0000-000000000000-comments.csv\"; filename*=UTF-8''%E8%B3%AA%E5%95%8F-%E7%AE%B1-comments.csv"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+// パスワードハッシュの形式の移行
+//
+// 以前はソルトを salt 列にも保存していましたが、ソルトはハッシュの文字列（PHC 形式）に含まれるため
+// 現在は使っていません。旧形式の行（hash_version = 1）は次の手順で移行します。
+// 1. `minwada-api migrate-credentials` が旧形式の行を確認し、現在の形式と同じパラメーターのハッシュは
+//    そのまま hash_version を上げ、異なるハッシュは次のログイン時に作り直すよう印を付ける
+// 2. ログイン時に平文のパスワードが分かった時点でハッシュを作り直す
+// 移行は行をロックしてから、作り直しはハッシュが変わっていないことを条件に更新するため、
+// 並行して実行しても新しいパスワードを上書きしません。
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    auth::password::{self, HASH_VERSION},
+    error::AppError,
+    models::{maintenance::CredentialReport, UserCredentials},
+};
+
+/// `migrate-credentials` で1回のトランザクションで確認する行数
+pub const MIGRATE_BATCH_SIZE: i64 = 500;
+
+/// `migrate_legacy` の結果
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MigrateResult {
+    /// 現在の形式と同じパラメーターだったため、そのまま hash_version を上げた行
+    pub upgraded: u64,
+    /// 次のログイン時にハッシュを作り直すよう印を付けた行
+    pub marked: u64,
+}
+
+/// 新しいパスワードのハッシュを現在の形式として保存する
+pub async fn save_password_hash<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE user_credentials
+        SET password_hash = $1, salt = NULL, hash_version = $2, needs_rehash = false, updated_at = NOW()
+        WHERE user_id = $3
+        "#,
+    )
+    .bind(password_hash)
+    .bind(HASH_VERSION)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// 旧形式のハッシュを、検証に成功したパスワードから作り直す
+///
+/// 作り直した場合は `true` を返します。検証してから更新するまでの間にパスワードが変更された場合は何もしません。
+pub async fn rehash_if_needed(
+    pool: &PgPool,
+    credentials: &UserCredentials,
+    password: &str,
+) -> Result<bool, AppError> {
+    if credentials.hash_version >= HASH_VERSION && !credentials.needs_rehash {
+        return Ok(false);
+    }
+
+    let password_hash = password::hash_password(password)?;
+    let updated = sqlx::query(
+        r#"
+        UPDATE user_credentials
+        SET password_hash = $1, salt = NULL, hash_version = $2, needs_rehash = false, updated_at = NOW()
+        WHERE user_id = $3 AND password_hash = $4
+        "#,
+    )
+    .bind(&password_hash)
+    .bind(HASH_VERSION)
+    .bind(credentials.user_id)
+    .bind(&credentials.password_hash)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(updated > 0)
+}
+
+/// 旧形式の行をバッチごとに確認して移行する（ハッシュは作り直さない）
+///
+/// バッチごとにコミットするため、中断しても再実行すれば未処理の行から再開します。
+/// ログイン中の利用者が更新している行は待たずに飛ばします（ログイン時に移行される）。
+pub async fn migrate_legacy(
+    pool: &PgPool,
+    batch_size: i64,
+    mut on_progress: impl FnMut(MigrateResult),
+) -> Result<MigrateResult, AppError> {
+    let mut result = MigrateResult::default();
+    loop {
+        let mut tx = pool.begin().await?;
+        let rows = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT user_id, password_hash FROM user_credentials
+            WHERE hash_version < $1 AND NOT needs_rehash
+            ORDER BY user_id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(HASH_VERSION)
+        .bind(batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+        if rows.is_empty() {
+            return Ok(result);
+        }
+
+        let (current, outdated): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .partition(|(_, hash)| password::is_current_hash(hash));
+        let current: Vec<Uuid> = current.into_iter().map(|(user_id, _)| user_id).collect();
+        let outdated: Vec<Uuid> = outdated.into_iter().map(|(user_id, _)| user_id).collect();
+
+        result.upgraded += sqlx::query(
+            "UPDATE user_credentials SET salt = NULL, hash_version = $1 WHERE user_id = ANY($2)",
+        )
+        .bind(HASH_VERSION)
+        .bind(&current)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        result.marked +=
+            sqlx::query("UPDATE user_credentials SET needs_rehash = true WHERE user_id = ANY($1)")
+                .bind(&outdated)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        tx.commit().await?;
+        on_progress(result);
+    }
+}
+
+/// 形式ごとのアカウント数を集計する
+pub async fn report(pool: &PgPool) -> Result<CredentialReport, AppError> {
+    let (current, legacy, pending_rehash) = sqlx::query_as::<_, (i64, i64, i64)>(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE hash_version >= $1 AND NOT needs_rehash),
+            COUNT(*) FILTER (WHERE hash_version < $1 AND NOT needs_rehash),
+            COUNT(*) FILTER (WHERE needs_rehash)
+        FROM user_credentials
+        "#,
+    )
+    .bind(HASH_VERSION)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(CredentialReport {
+        hash_version: HASH_VERSION,
+        current,
+        legacy,
+        pending_rehash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+    use argon2::{
+        password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+        Algorithm, Argon2, Params, Version,
+    };
+
+    /// 旧形式の行にする（`hash` を指定しない場合は現在と同じパラメーターでハッシュ化する）
+    async fn make_legacy(pool: &PgPool, user_id: Uuid, hash: Option<String>) -> String {
+        let hash = hash.unwrap_or_else(|| password::hash_password("password123").unwrap());
+        sqlx::query(
+            "UPDATE user_credentials SET password_hash = $1, salt = 'legacy_salt', hash_version = 1 WHERE user_id = $2",
+        )
+        .bind(&hash)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        hash
+    }
+
+    /// 現在とは異なるパラメーターのハッシュ
+    fn outdated_hash() -> String {
+        let params = Params::new(Params::DEFAULT_M_COST, 1, Params::DEFAULT_P_COST, None).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(b"password123", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string()
+    }
+
+    async fn credentials(pool: &PgPool, user_id: Uuid) -> UserCredentials {
+        sqlx::query_as::<_, UserCredentials>("SELECT * FROM user_credentials WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_移行は同じパラメーターの行を更新し異なる行に印を付ける(
+        pool: PgPool,
+    ) {
+        // 移行では同じパラメーターの行を更新し、異なる行には印を付けることを確認
+        // 現在の形式のアカウント
+        create_test_user(&pool, true).await;
+        let upgradable = create_test_user(&pool, true).await;
+        let outdated = create_test_user(&pool, true).await;
+        make_legacy(&pool, upgradable.id, None).await;
+        let outdated_hash = make_legacy(&pool, outdated.id, Some(outdated_hash())).await;
+
+        let before = report(&pool).await.unwrap();
+        assert_eq!(
+            (before.current, before.legacy, before.pending_rehash),
+            (1, 2, 0)
+        );
+
+        let mut progress = Vec::new();
+        let result = migrate_legacy(&pool, 1, |result| progress.push(result))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            MigrateResult {
+                upgraded: 1,
+                marked: 1
+            }
+        );
+        assert_eq!(progress.len(), 2);
+
+        let upgraded = credentials(&pool, upgradable.id).await;
+        assert_eq!(upgraded.hash_version, HASH_VERSION);
+        assert_eq!(upgraded.salt, None);
+        // ハッシュは作り直さない
+        let marked = credentials(&pool, outdated.id).await;
+        assert!(marked.needs_rehash);
+        assert_eq!(marked.password_hash, outdated_hash);
+
+        let after = report(&pool).await.unwrap();
+        assert_eq!(
+            (after.current, after.legacy, after.pending_rehash),
+            (2, 0, 1)
+        );
+
+        // 再実行しても印を付けた行は対象にならない
+        let again = migrate_legacy(&pool, 1, |_| {}).await.unwrap();
+        assert_eq!(again, MigrateResult::default());
+    }
+
+    #[sqlx::test]
+    async fn test_検証したパスワードから旧形式のハッシュを作り直す(
+        pool: PgPool,
+    ) {
+        // 検証したパスワードから旧形式のハッシュを作り直すことを確認
+        let user = create_test_user(&pool, true).await;
+        make_legacy(&pool, user.id, Some(outdated_hash())).await;
+        let legacy = credentials(&pool, user.id).await;
+
+        assert!(rehash_if_needed(&pool, &legacy, "password123")
+            .await
+            .unwrap());
+
+        let rehashed = credentials(&pool, user.id).await;
+        assert_eq!(rehashed.hash_version, HASH_VERSION);
+        assert_eq!(rehashed.salt, None);
+        assert!(password::is_current_hash(&rehashed.password_hash));
+        assert!(password::verify_password("password123", &rehashed.password_hash).unwrap());
+
+        // 現在の形式の行は作り直さない
+        assert!(!rehash_if_needed(&pool, &rehashed, "password123")
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_検証後にパスワードが変更された場合は上書きしない(
+        pool: PgPool,
+    ) {
+        // 検証の後でパスワードが変更された場合はハッシュを上書きしないことを確認
+        let user = create_test_user(&pool, true).await;
+        make_legacy(&pool, user.id, None).await;
+        let legacy = credentials(&pool, user.id).await;
+
+        let changed = password::hash_password("new_password456").unwrap();
+        save_password_hash(&pool, user.id, &changed).await.unwrap();
+
+        assert!(!rehash_if_needed(&pool, &legacy, "password123")
+            .await
+            .unwrap());
+        assert_eq!(credentials(&pool, user.id).await.password_hash, changed);
+    }
+}
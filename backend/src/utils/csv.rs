@@ -0,0 +1,84 @@
+// CSV出力（監査ログ・コメントのエクスポート）で共通のエスケープ処理
+use std::borrow::Cow;
+
+/// CSVの1フィールドをエスケープする
+///
+/// カンマ・ダブルクォート・改行を含む場合はダブルクォートで囲み、
+/// 表計算ソフトで数式として解釈される先頭文字には `'` を付けます。
+pub fn escape(field: &str) -> Cow<'_, str> {
+    let formula_like = field.starts_with(['=', '+', '-', '@', '\t', '\r']);
+    let needs_quotes = field.contains([',', '"', '\n', '\r']);
+
+    if !formula_like && !needs_quotes {
+        return Cow::Borrowed(field);
+    }
+
+    let field = if formula_like {
+        Cow::Owned(format!("'{}", field))
+    } else {
+        Cow::Borrowed(field)
+    };
+
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        field
+    }
+}
+
+/// 各フィールドをエスケープして1行分のCSVを生成する（改行はCRLF）
+pub fn row<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut row = fields
+        .iter()
+        .map(|field| escape(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_特殊文字を含まないフィールドはそのまま出力される() {
+        // 特殊文字を含まないフィールドはそのまま出力されることを確認
+        assert_eq!(escape("user.mute"), "user.mute");
+        assert!(matches!(escape("user.mute"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_カンマ_引用符_改行を含むフィールドは引用符で囲まれる() {
+        // カンマ・引用符・改行を含むフィールドは引用符で囲まれることを確認
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("line1\nline2"), "\"line1\nline2\"");
+        assert_eq!(escape("line1\r\nline2"), "\"line1\r\nline2\"");
+    }
+
+    #[test]
+    fn test_数式として解釈されるフィールドは無害化される() {
+        // 数式として解釈されるフィールドは無害化されることを確認
+        assert_eq!(escape("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(escape("+1"), "'+1");
+        assert_eq!(escape("-1"), "'-1");
+        assert_eq!(escape("@SUM(A1)"), "'@SUM(A1)");
+    }
+
+    #[test]
+    fn test_日本語はそのまま出力される() {
+        // 日本語はそのまま出力されることを確認
+        assert_eq!(escape("こんにちは、世界"), "こんにちは、世界");
+        assert_eq!(
+            escape("質問です。\n「引用」, です"),
+            "\"質問です。\n「引用」, です\""
+        );
+    }
+
+    #[test]
+    fn test_行はフィールドをカンマで連結しcrlfで終わる() {
+        // 行はフィールドをカンマで連結し、CRLFで終わることを確認
+        assert_eq!(row(&["a", "b,c", ""]), "a,\"b,c\",\r\n");
+    }
+}
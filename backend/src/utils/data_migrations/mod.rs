@@ -0,0 +1,384 @@
+// データの移行（スキーマの変更に合わせて既存の行を書き換える処理）
+//
+// スキーマの変更は sqlx のマイグレーション（SQL）で行い、既存の行の書き換えは Rust の関数として
+// `MIGRATIONS` に登録して `minwada-api data-migrate` で実行します。
+// 大きなテーブルを1つのトランザクションで書き換えないよう、移行は `batch_size` 行ずつ処理し、
+// バッチの書き換えと data_migrations テーブルの進捗（最後に処理した行のキー）を同じトランザクションでコミットします。
+// そのため途中で中断しても、再実行すれば最後にコミットしたバッチの続きから処理し、同じ行を二度処理しません。
+// 完了した移行は再実行しても何もしません。
+//
+// 移行を追加する手順
+// 1. 新しい列などはSQLのマイグレーションで追加する（既存の行は NULL のままにしておく）
+// 2. 新しく作成・更新される行には、アプリケーション側で値を設定する
+// 3. `DataMigration` を実装し、これまでより大きい番号で `MIGRATIONS` の末尾に追加する
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+
+mod thread_slugs;
+
+/// `data-migrate` で1回のトランザクションで処理する行数
+pub const BATCH_SIZE: i64 = 500;
+
+/// 登録されている移行（番号の小さい順に実行する）
+pub static MIGRATIONS: &[&dyn DataMigration] = &[&thread_slugs::ThreadSlugs];
+
+/// 1回のバッチで処理した結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    /// 処理した行数
+    pub rows: u64,
+    /// 最後に処理した行のキー（次のバッチはこの続きから処理する）
+    pub cursor: String,
+}
+
+/// 既存の行を書き換える移行
+#[async_trait]
+pub trait DataMigration: Send + Sync {
+    /// 実行順を決める番号（data_migrations.version に記録するため、一度使った番号は変えない）
+    fn version(&self) -> i64;
+
+    /// 進捗の表示と記録に使う名前
+    fn name(&self) -> &'static str;
+
+    /// `cursor` より後の行を最大 `batch_size` 行処理する。処理する行が無ければ None を返す
+    ///
+    /// 呼び出し側のトランザクションの中で実行されるため、コミットしないでください。
+    /// 行はキーの順に処理し、`cursor` にはそのキーを文字列にして返します。
+    async fn run_batch(
+        &self,
+        conn: &mut PgConnection,
+        cursor: Option<&str>,
+        batch_size: i64,
+    ) -> Result<Option<Batch>, AppError>;
+}
+
+/// 移行ごとの実行結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationRun {
+    pub version: i64,
+    pub name: &'static str,
+    /// 今回処理した行数
+    pub processed: u64,
+    /// 前回までに完了していたため何もしなかった
+    pub already_completed: bool,
+}
+
+/// 登録されている移行を番号の小さい順に実行する
+///
+/// `on_progress` には、バッチをコミットするたびに移行とその移行でこれまでに処理した行数を渡します。
+/// 同時に実行された場合は、進捗の行をロックして1バッチずつ交互に処理します。
+pub async fn run(
+    pool: &PgPool,
+    migrations: &[&dyn DataMigration],
+    batch_size: i64,
+    mut on_progress: impl FnMut(&dyn DataMigration, i64),
+) -> Result<Vec<MigrationRun>, AppError> {
+    if let Some(pair) = migrations
+        .windows(2)
+        .find(|pair| pair[0].version() >= pair[1].version())
+    {
+        return Err(AppError::Internal(format!(
+            "Data migrations must be registered in ascending version order: {} ({}) is followed by {} ({})",
+            pair[0].version(),
+            pair[0].name(),
+            pair[1].version(),
+            pair[1].name()
+        )));
+    }
+
+    let mut runs = Vec::with_capacity(migrations.len());
+    for &migration in migrations {
+        runs.push(run_one(pool, migration, batch_size, &mut on_progress).await?);
+    }
+    Ok(runs)
+}
+
+async fn run_one(
+    pool: &PgPool,
+    migration: &dyn DataMigration,
+    batch_size: i64,
+    on_progress: &mut impl FnMut(&dyn DataMigration, i64),
+) -> Result<MigrationRun, AppError> {
+    register(pool, migration).await?;
+
+    let mut run = MigrationRun {
+        version: migration.version(),
+        name: migration.name(),
+        processed: 0,
+        already_completed: false,
+    };
+    loop {
+        let mut tx = pool.begin().await?;
+        let (cursor, completed) = sqlx::query_as::<_, (Option<String>, bool)>(
+            r#"
+            SELECT cursor, completed_at IS NOT NULL FROM data_migrations
+            WHERE version = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(migration.version())
+        .fetch_one(&mut *tx)
+        .await?;
+        if completed {
+            run.already_completed = run.processed == 0;
+            return Ok(run);
+        }
+
+        let Some(batch) = migration
+            .run_batch(&mut tx, cursor.as_deref(), batch_size)
+            .await?
+        else {
+            sqlx::query(
+                "UPDATE data_migrations SET completed_at = NOW(), updated_at = NOW() WHERE version = $1",
+            )
+            .bind(migration.version())
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(run);
+        };
+
+        let processed = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE data_migrations
+            SET cursor = $1, processed = processed + $2, updated_at = NOW()
+            WHERE version = $3
+            RETURNING processed
+            "#,
+        )
+        .bind(&batch.cursor)
+        .bind(batch.rows as i64)
+        .bind(migration.version())
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        run.processed += batch.rows;
+        on_progress(migration, processed);
+    }
+}
+
+/// 進捗の行を作る（既にある場合は同じ移行であることを確かめる）
+async fn register(pool: &PgPool, migration: &dyn DataMigration) -> Result<(), AppError> {
+    let name = sqlx::query_scalar::<_, String>(
+        r#"
+        WITH inserted AS (
+            INSERT INTO data_migrations (version, name) VALUES ($1, $2)
+            ON CONFLICT (version) DO NOTHING
+            RETURNING name
+        )
+        SELECT name FROM inserted
+        UNION ALL
+        SELECT name FROM data_migrations WHERE version = $1
+        LIMIT 1
+        "#,
+    )
+    .bind(migration.version())
+    .bind(migration.name())
+    .fetch_one(pool)
+    .await?;
+
+    if name != migration.name() {
+        return Err(AppError::Internal(format!(
+            "Data migration version {} is already recorded as {}, not {}",
+            migration.version(),
+            name,
+            migration.name()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// テスト用のテーブルの行の touched を1ずつ増やす移行
+    struct TouchItems {
+        /// この回数目のバッチで処理した後に失敗する（中断を再現する）
+        fail_on_batch: Option<usize>,
+        batches: AtomicUsize,
+    }
+
+    impl TouchItems {
+        fn new(fail_on_batch: Option<usize>) -> Self {
+            Self {
+                fail_on_batch,
+                batches: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DataMigration for TouchItems {
+        fn version(&self) -> i64 {
+            1
+        }
+
+        fn name(&self) -> &'static str {
+            "touch_items"
+        }
+
+        async fn run_batch(
+            &self,
+            conn: &mut PgConnection,
+            cursor: Option<&str>,
+            batch_size: i64,
+        ) -> Result<Option<Batch>, AppError> {
+            let after: i64 = cursor.map_or(0, |cursor| cursor.parse().unwrap());
+            let ids = sqlx::query_scalar::<_, i64>(
+                r#"
+                UPDATE data_migration_test_items SET touched = touched + 1
+                WHERE id IN (
+                    SELECT id FROM data_migration_test_items
+                    WHERE id > $1 ORDER BY id LIMIT $2
+                )
+                RETURNING id
+                "#,
+            )
+            .bind(after)
+            .bind(batch_size)
+            .fetch_all(&mut *conn)
+            .await?;
+
+            let batch = self.batches.fetch_add(1, Ordering::SeqCst) + 1;
+            if Some(batch) == self.fail_on_batch {
+                return Err(AppError::Internal("interrupted".to_string()));
+            }
+
+            Ok(ids.iter().max().map(|last| Batch {
+                rows: ids.len() as u64,
+                cursor: last.to_string(),
+            }))
+        }
+    }
+
+    async fn seed_items(pool: &PgPool, count: i64) {
+        sqlx::query(
+            "CREATE TABLE data_migration_test_items (id BIGINT PRIMARY KEY, touched INT NOT NULL DEFAULT 0)",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO data_migration_test_items (id) SELECT generate_series(1, $1)")
+            .bind(count)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn touched(pool: &PgPool) -> Vec<i32> {
+        sqlx::query_scalar("SELECT touched FROM data_migration_test_items ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn status(pool: &PgPool) -> (Option<String>, i64, bool) {
+        sqlx::query_as(
+            "SELECT cursor, processed, completed_at IS NOT NULL FROM data_migrations WHERE version = 1",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_登録されている移行は番号の小さい順に並んでいる() {
+        // 登録されている移行が番号の小さい順に並んでいることを確認
+        assert!(MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version() < pair[1].version()));
+    }
+
+    #[sqlx::test]
+    async fn test_完了した移行は再実行しても何もしない(pool: PgPool) {
+        // 完了したデータ移行は再実行しても何もしないことを確認
+        seed_items(&pool, 7).await;
+        let migration = TouchItems::new(None);
+
+        let mut progress = Vec::new();
+        let runs = run(&pool, &[&migration], 3, |_, processed| {
+            progress.push(processed)
+        })
+        .await
+        .unwrap();
+        assert_eq!(progress, [3, 6, 7]);
+        assert_eq!(
+            runs,
+            [MigrationRun {
+                version: 1,
+                name: "touch_items",
+                processed: 7,
+                already_completed: false,
+            }]
+        );
+        assert_eq!(status(&pool).await, (Some("7".to_string()), 7, true));
+
+        let runs = run(&pool, &[&migration], 3, |_, _| panic!("no batches"))
+            .await
+            .unwrap();
+        assert_eq!((runs[0].processed, runs[0].already_completed), (0, true));
+        assert_eq!(touched(&pool).await, [1; 7]);
+    }
+
+    #[sqlx::test]
+    async fn test_中断した移行は最後にコミットしたバッチの続きから再開する(
+        pool: PgPool,
+    ) {
+        // 中断したデータ移行は最後にコミットしたバッチの続きから再開することを確認
+        seed_items(&pool, 10).await;
+
+        // 3回目のバッチで失敗すると、そのバッチの書き換えは進捗と一緒に取り消される
+        let interrupted = run(&pool, &[&TouchItems::new(Some(3))], 3, |_, _| {}).await;
+        assert!(matches!(interrupted, Err(AppError::Internal(_))));
+        assert_eq!(touched(&pool).await, [1, 1, 1, 1, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(status(&pool).await, (Some("6".to_string()), 6, false));
+
+        let runs = run(&pool, &[&TouchItems::new(None)], 3, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(runs[0].processed, 4);
+        assert_eq!(touched(&pool).await, [1; 10]);
+        assert_eq!(status(&pool).await, (Some("10".to_string()), 10, true));
+    }
+
+    #[sqlx::test]
+    async fn test_番号の順に並んでいないか番号を使い回した移行は実行しない(
+        pool: PgPool,
+    ) {
+        // 番号の順に並んでいないか番号を使い回したデータ移行は実行しないことを確認
+        seed_items(&pool, 1).await;
+
+        struct Renamed;
+        #[async_trait]
+        impl DataMigration for Renamed {
+            fn version(&self) -> i64 {
+                1
+            }
+            fn name(&self) -> &'static str {
+                "renamed"
+            }
+            async fn run_batch(
+                &self,
+                _: &mut PgConnection,
+                _: Option<&str>,
+                _: i64,
+            ) -> Result<Option<Batch>, AppError> {
+                Ok(None)
+            }
+        }
+
+        let migration = TouchItems::new(None);
+        let unordered = run(&pool, &[&migration, &Renamed], 3, |_, _| {}).await;
+        assert!(matches!(unordered, Err(AppError::Internal(_))));
+        assert_eq!(touched(&pool).await, [0]);
+
+        run(&pool, &[&migration], 3, |_, _| {}).await.unwrap();
+        let reused = run(&pool, &[&Renamed], 3, |_, _| {}).await;
+        assert!(matches!(reused, Err(AppError::Internal(_))));
+    }
+}
@@ -0,0 +1,130 @@
+// threads.slug を追加する前からあるスレッドに、タイトルからスラッグを設定する
+//
+// 新しいスレッドには作成時に設定するため、ここでは slug が NULL の行だけを書き換えます。
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{Batch, DataMigration};
+use crate::{error::AppError, utils::tags};
+
+pub struct ThreadSlugs;
+
+#[async_trait]
+impl DataMigration for ThreadSlugs {
+    fn version(&self) -> i64 {
+        20250629000000
+    }
+
+    fn name(&self) -> &'static str {
+        "backfill_thread_slugs"
+    }
+
+    async fn run_batch(
+        &self,
+        conn: &mut PgConnection,
+        cursor: Option<&str>,
+        batch_size: i64,
+    ) -> Result<Option<Batch>, AppError> {
+        let after = cursor.map(Uuid::parse_str).transpose()?;
+        let rows = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT id, title FROM threads
+            WHERE $1::uuid IS NULL OR id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(batch_size)
+        .fetch_all(&mut *conn)
+        .await?;
+        let Some((last, _)) = rows.last() else {
+            return Ok(None);
+        };
+        let cursor = last.to_string();
+
+        let (ids, slugs): (Vec<Uuid>, Vec<String>) = rows
+            .iter()
+            .map(|(id, title)| (*id, tags::slugify(title)))
+            .unzip();
+        sqlx::query(
+            r#"
+            UPDATE threads SET slug = batch.slug
+            FROM UNNEST($1::uuid[], $2::text[]) AS batch(id, slug)
+            WHERE threads.id = batch.id AND threads.slug IS NULL
+            "#,
+        )
+        .bind(&ids)
+        .bind(&slugs)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(Some(Batch {
+            rows: rows.len() as u64,
+            cursor,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        repositories::{PgThreadsRepo, ThreadsRepo},
+        test_utils::{create_test_thread, create_test_user, test_tokens},
+        utils::data_migrations,
+    };
+    use sqlx::PgPool;
+
+    async fn slug_of(pool: &PgPool, id: Uuid) -> Option<String> {
+        sqlx::query_scalar("SELECT slug FROM threads WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_スラッグが無いスレッドにタイトルからスラッグを設定する(
+        pool: PgPool,
+    ) {
+        // スラッグが無いスレッドにタイトルからスラッグが設定されることを確認
+        let user = create_test_user(&pool, true).await;
+        // 列を追加する前からあるスレッドと同じく slug は NULL
+        let mut legacy = Vec::new();
+        for (title, expected) in [
+            ("Hello World", "hello-world"),
+            ("Rust_と SQL!", "rust-と-sql"),
+            ("!!!", ""),
+        ] {
+            let id = create_test_thread(&pool, user.id, title, "Content").await;
+            assert_eq!(slug_of(&pool, id).await, None);
+            legacy.push((id, expected));
+        }
+        // 作成時に設定したスラッグはタイトルを編集した後も書き換えない
+        let threads = PgThreadsRepo::new(pool.clone(), test_tokens());
+        let created = threads
+            .create(&user, "Created Title", None, &[])
+            .await
+            .unwrap()
+            .id;
+        threads
+            .update(created, user.id, Some("Edited"), None)
+            .await
+            .unwrap();
+
+        let runs = data_migrations::run(&pool, &[&ThreadSlugs], 2, |_, _| {})
+            .await
+            .unwrap();
+
+        assert_eq!(runs[0].processed, 4);
+        for (id, expected) in legacy {
+            assert_eq!(slug_of(&pool, id).await.as_deref(), Some(expected));
+        }
+        assert_eq!(
+            slug_of(&pool, created).await.as_deref(),
+            Some("created-title")
+        );
+    }
+}
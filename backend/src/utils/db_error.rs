@@ -0,0 +1,228 @@
+// データベースの制約違反をAppErrorに変換する
+//
+// 事前チェックをすり抜けた同時リクエストが一意制約・外部キー制約に違反した場合に、
+// 500ではなく409/404として返すために使う
+use crate::error::AppError;
+
+/// 制約違反の種類と違反した制約名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintViolation<'a> {
+    Unique(&'a str),
+    ForeignKey(&'a str),
+}
+
+/// sqlxのエラーが一意制約・外部キー制約の違反であれば、その種類と制約名を返す
+pub fn constraint_violation(err: &sqlx::Error) -> Option<ConstraintViolation<'_>> {
+    let sqlx::Error::Database(db_err) = err else {
+        return None;
+    };
+    let constraint = db_err.constraint().unwrap_or_default();
+
+    if db_err.is_unique_violation() {
+        Some(ConstraintViolation::Unique(constraint))
+    } else if db_err.is_foreign_key_violation() {
+        Some(ConstraintViolation::ForeignKey(constraint))
+    } else {
+        None
+    }
+}
+
+/// 一意制約ごとのエラーコードとメッセージ
+fn unique_violation_detail(constraint: &str) -> (&'static str, &'static str) {
+    match constraint {
+        "users_username_key" | "users_username_lower_key" => {
+            ("USERNAME_TAKEN", "Username already exists")
+        }
+        "users_email_key" | "users_email_lower_key" => ("EMAIL_TAKEN", "Email already exists"),
+        "user_thread_unique" => ("VOTE_CONFLICT", "Vote was changed by another request"),
+        "tags_slug_key" => ("TAG_SLUG_TAKEN", "A tag with this slug already exists"),
+        "reports_reporter_target_key" => {
+            ("REPORT_DUPLICATE", "You have already reported this content")
+        }
+        "saved_searches_user_query_key" => (
+            "SAVED_SEARCH_DUPLICATE",
+            "You have already saved this search",
+        ),
+        "oauth_accounts_user_id_provider_key" => {
+            ("OAUTH_ACCOUNT_LINKED", "OAuth account already linked")
+        }
+        _ => ("DUPLICATE_ENTRY", "Resource already exists"),
+    }
+}
+
+/// 外部キー制約ごとのエラーコード（参照先の種類で判別する）
+fn foreign_key_violation_code(constraint: &str) -> &'static str {
+    if constraint.ends_with("_thread_id_fkey") {
+        "THREAD_NOT_FOUND"
+    } else if constraint.ends_with("_parent_id_fkey") || constraint.ends_with("_parent_thread_fkey")
+    {
+        "PARENT_COMMENT_NOT_FOUND"
+    } else if constraint.ends_with("_comment_id_fkey") {
+        "COMMENT_NOT_FOUND"
+    } else if constraint.ends_with("user_id_fkey") || constraint.ends_with("muted_by_fkey") {
+        "USER_NOT_FOUND"
+    } else if constraint.ends_with("_tag_id_fkey") {
+        "TAG_NOT_FOUND"
+    } else {
+        "REFERENCE_NOT_FOUND"
+    }
+}
+
+/// 制約違反を409/404のAppErrorに変換する。それ以外のエラーはDatabaseエラーのまま返す
+pub fn map_db_error(err: sqlx::Error) -> AppError {
+    match constraint_violation(&err) {
+        Some(ConstraintViolation::Unique(constraint)) => {
+            let (code, message) = unique_violation_detail(constraint);
+            AppError::UniqueViolation { code, message }
+        }
+        Some(ConstraintViolation::ForeignKey(constraint)) => {
+            AppError::ForeignKeyViolation(foreign_key_violation_code(constraint))
+        }
+        None => AppError::Database(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_thread, create_test_user, seed_test_user};
+    use axum::{http::StatusCode, response::IntoResponse};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn insert_user(pool: &PgPool, username: &str, email: &str) -> Result<(), AppError> {
+        sqlx::query("INSERT INTO users (username, email) VALUES ($1, $2)")
+            .bind(username)
+            .bind(email)
+            .execute(pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(())
+    }
+
+    async fn insert_vote(pool: &PgPool, user_id: Uuid, thread_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("INSERT INTO votes (user_id, thread_id, vote_type) VALUES ($1, $2, 'upvote')")
+            .bind(user_id)
+            .bind(thread_id)
+            .execute(pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(())
+    }
+
+    fn status_of(err: AppError) -> StatusCode {
+        err.into_response().status()
+    }
+
+    #[sqlx::test]
+    async fn test_同時登録のユーザー名重複は409になる(pool: PgPool) {
+        // 同じユーザー名での同時INSERTは片方だけが成功し、もう片方はUSERNAME_TAKENになることを確認
+        let (first, second) = tokio::join!(
+            insert_user(&pool, "race_user", "race1@example.com"),
+            insert_user(&pool, "race_user", "race2@example.com"),
+        );
+
+        let err = match (first, second) {
+            (Ok(()), Err(err)) | (Err(err), Ok(())) => err,
+            other => panic!("Expected exactly one failure, got {:?}", other),
+        };
+        assert_eq!(err.code(), Some("USERNAME_TAKEN"));
+        assert_eq!(status_of(err), StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test]
+    async fn test_同時登録のメールアドレス重複は409になる(pool: PgPool) {
+        // 同じメールアドレスでの同時INSERTは片方だけが成功し、もう片方はEMAIL_TAKENになることを確認
+        let (first, second) = tokio::join!(
+            insert_user(&pool, "race_email_1", "race@example.com"),
+            insert_user(&pool, "race_email_2", "race@example.com"),
+        );
+
+        let err = match (first, second) {
+            (Ok(()), Err(err)) | (Err(err), Ok(())) => err,
+            other => panic!("Expected exactly one failure, got {:?}", other),
+        };
+        assert_eq!(err.code(), Some("EMAIL_TAKEN"));
+        assert_eq!(status_of(err), StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test]
+    async fn test_同時投票の重複は409になる(pool: PgPool) {
+        // 同じユーザー・スレッドへの同時投票は片方だけが成功することを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Race", "content").await;
+
+        let (first, second) = tokio::join!(
+            insert_vote(&pool, user.id, thread_id),
+            insert_vote(&pool, user.id, thread_id),
+        );
+
+        let err = match (first, second) {
+            (Ok(()), Err(err)) | (Err(err), Ok(())) => err,
+            other => panic!("Expected exactly one failure, got {:?}", other),
+        };
+        assert_eq!(err.code(), Some("VOTE_CONFLICT"));
+        assert_eq!(status_of(err), StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test]
+    async fn test_存在しないスレッドへの投票は404になる(pool: PgPool) {
+        // 削除済みのスレッドへの投票は外部キー違反としてTHREAD_NOT_FOUNDになることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Deleted", "content").await;
+        sqlx::query("DELETE FROM threads WHERE id = $1")
+            .bind(thread_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = insert_vote(&pool, user.id, thread_id)
+            .await
+            .expect_err("vote for deleted thread should fail");
+
+        assert_eq!(err.code(), Some("THREAD_NOT_FOUND"));
+        assert_eq!(status_of(err), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_別スレッドの親コメントへのリプライは404になる(pool: PgPool) {
+        // 親コメントが別のスレッドに属する場合は外部キー違反になることを確認
+        let user_id = seed_test_user(&pool, "cross_thread_reply").await;
+        let thread_a = create_test_thread(&pool, user_id, "A", "content").await;
+        let thread_b = create_test_thread(&pool, user_id, "B", "content").await;
+        let parent_id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO comments (thread_id, user_id, content) VALUES ($1, $2, 'parent') RETURNING id",
+        )
+        .bind(thread_a)
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let err = sqlx::query(
+            "INSERT INTO comments (thread_id, user_id, content, parent_id) VALUES ($1, $2, 'reply', $3)",
+        )
+        .bind(thread_b)
+        .bind(user_id)
+        .bind(parent_id)
+        .execute(&pool)
+        .await
+        .map_err(map_db_error)
+        .expect_err("reply across threads should fail");
+
+        assert_eq!(err.code(), Some("PARENT_COMMENT_NOT_FOUND"));
+        assert_eq!(status_of(err), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_制約違反以外はdatabaseエラーのまま(pool: PgPool) {
+        // 制約違反以外のエラーは従来通り500として扱われることを確認
+        let err = sqlx::query("SELECT * FROM no_such_table")
+            .execute(&pool)
+            .await
+            .map_err(map_db_error)
+            .expect_err("query should fail");
+
+        assert!(matches!(err, AppError::Database(_)));
+    }
+}
@@ -0,0 +1,103 @@
+// OGP画像やアバター画像の生成で共有する描画ユーティリティ
+use std::{collections::HashMap, sync::RwLock};
+
+use image::RgbImage;
+use lazy_static::lazy_static;
+use rusttype::{Font, GlyphId, Scale};
+
+use crate::error::{AppError, Result};
+
+lazy_static! {
+    // 日本語フォント（Noto Sans JP）はリクエストごとに読み込まず使い回す
+    static ref NOTO_SANS_JP: Option<Font<'static>> = Font::try_from_bytes(include_bytes!(
+        "../static/fonts/NotoSansJP-SemiBold.ttf"
+    ) as &[u8]);
+}
+
+/// 読み込み済みの日本語フォントを取得
+pub fn font() -> Result<&'static Font<'static>> {
+    NOTO_SANS_JP
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("Failed to load font".to_string()))
+}
+
+/// 画像をPNG形式のバイト配列にエンコード
+pub fn encode_png(image: &RgbImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut buffer),
+            image::ImageOutputFormat::Png,
+        )
+        .map_err(|_| AppError::Internal("PNG encoding error".to_string()))?;
+
+    Ok(buffer)
+}
+
+/// フォントとサイズごとに文字の送り幅をキャッシュしてテキスト幅を計算する
+///
+/// `Font::layout` と同じ順序で字詰め（カーニング）と送り幅を加算するため、
+/// 計算結果はレイアウトした場合と一致します。
+pub struct TextMeasurer<'a> {
+    font: &'a Font<'a>,
+    scale: Scale,
+    advances: RwLock<HashMap<char, (GlyphId, f32)>>,
+}
+
+impl<'a> TextMeasurer<'a> {
+    pub fn new(font: &'a Font<'a>, scale: Scale) -> Self {
+        Self {
+            font,
+            scale,
+            advances: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 文字のグリフIDと送り幅を取得（未計算の場合はキャッシュに追加）
+    fn glyph(&self, c: char) -> (GlyphId, f32) {
+        if let Some(cached) = self
+            .advances
+            .read()
+            .ok()
+            .and_then(|advances| advances.get(&c).copied())
+        {
+            return cached;
+        }
+
+        let glyph = self.font.glyph(c).scaled(self.scale);
+        let metrics = (glyph.id(), glyph.h_metrics().advance_width);
+        if let Ok(mut advances) = self.advances.write() {
+            advances.insert(c, metrics);
+        }
+        metrics
+    }
+
+    /// 直前の文字に続けて1文字配置し、新しい幅を返す
+    pub fn advance(&self, width: &mut LineWidth, c: char) -> f32 {
+        let (id, advance_width) = self.glyph(c);
+        if let Some(last) = width.last_glyph {
+            width.caret += self.font.pair_kerning(self.scale, last, id);
+        }
+        let text_width = width.caret + advance_width;
+        width.caret = text_width;
+        width.last_glyph = Some(id);
+        text_width
+    }
+
+    /// テキストの描画幅を計算
+    pub fn width(&self, text: &str) -> f32 {
+        let mut line = LineWidth::default();
+        let mut width = 0.0;
+        for c in text.chars() {
+            width = self.advance(&mut line, c);
+        }
+        width
+    }
+}
+
+/// 1行分の幅を逐次計算するための状態
+#[derive(Default)]
+pub struct LineWidth {
+    caret: f32,
+    last_glyph: Option<GlyphId>,
+}
@@ -0,0 +1,227 @@
+// メールアドレス変更の確認と取り消し
+//
+// 変更は新しいアドレスで確認されるまで users.pending_email に保持し、現在のアドレスはそのまま使う。
+// 旧アドレスに送る取り消しリンクは、確認前なら変更を中止し、確認後なら旧アドレスに戻す。
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::User,
+    utils::{db_error, token_generator::TokenGenerator, token_hash::hash_refresh_token},
+};
+
+/// 旧アドレスに送る取り消しリンクの有効期間（時間）
+pub const REVERT_EXPIRES_IN_HOURS: i64 = 72;
+
+/// メールで送る平文のトークン（保存するのはハッシュ値のみ）
+#[derive(Debug)]
+pub struct EmailChangeTokens {
+    pub confirm_token: String,
+    pub revert_token: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct EmailChangeRequest {
+    id: Uuid,
+    user_id: Uuid,
+    old_email: String,
+    new_email: String,
+    confirmed_at: Option<DateTime<Utc>>,
+}
+
+fn invalid_token() -> AppError {
+    AppError::BadRequest("無効または期限切れのトークンです".to_string())
+}
+
+/// メールアドレスの変更をリクエストする
+///
+/// 確認前の以前のリクエストは無効になります。メールの送信は呼び出し側で同じトランザクションに追加します。
+pub async fn request_change(
+    config: &Config,
+    generator: &dyn TokenGenerator,
+    user: &User,
+    new_email: &str,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<EmailChangeTokens, AppError> {
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        UPDATE email_change_requests
+        SET superseded_at = $2
+        WHERE user_id = $1
+          AND confirmed_at IS NULL
+          AND reverted_at IS NULL
+          AND superseded_at IS NULL
+        "#,
+    )
+    .bind(user.id)
+    .bind(now)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query("UPDATE users SET pending_email = $2, updated_at = $3 WHERE id = $1")
+        .bind(user.id)
+        .bind(new_email)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+    let tokens = EmailChangeTokens {
+        confirm_token: generator.secure_token(),
+        revert_token: generator.secure_token(),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_change_requests (
+            user_id, old_email, new_email,
+            confirm_token_hash, confirm_expires_at,
+            revert_token_hash, revert_expires_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(user.id)
+    .bind(&user.email)
+    .bind(new_email)
+    .bind(hash_refresh_token(&tokens.confirm_token))
+    .bind(now + Duration::hours(config.email_verification_token_expiry_hours))
+    .bind(hash_refresh_token(&tokens.revert_token))
+    .bind(now + Duration::hours(REVERT_EXPIRES_IN_HOURS))
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(tokens)
+}
+
+/// 新しいアドレスに届いた確認トークンで変更を確定し、更新後のユーザーを返す
+///
+/// 確認リンクを開けたことで新しいアドレスの所有が確認できるため、確認済みとして扱います。
+pub async fn confirm_change(pool: &PgPool, user_id: Uuid, token: &str) -> Result<User, AppError> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let request = sqlx::query_as::<_, EmailChangeRequest>(
+        r#"
+        SELECT id, user_id, old_email, new_email, confirmed_at
+        FROM email_change_requests
+        WHERE confirm_token_hash = $1
+          AND user_id = $2
+          AND confirmed_at IS NULL
+          AND reverted_at IS NULL
+          AND superseded_at IS NULL
+          AND confirm_expires_at > $3
+        FOR UPDATE
+        "#,
+    )
+    .bind(hash_refresh_token(token))
+    .bind(user_id)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(invalid_token)?;
+
+    // リクエスト後に他のユーザーが同じアドレスを使い始めた場合は一意制約で409になる
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET email = $2,
+            pending_email = NULL,
+            email_verified = true,
+            email_verified_at = $3,
+            updated_at = $3
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(request.user_id)
+    .bind(&request.new_email)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error::map_db_error)?;
+
+    sqlx::query("UPDATE email_change_requests SET confirmed_at = $2 WHERE id = $1")
+        .bind(request.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+/// 旧アドレスに届いた取り消しトークンで変更を取り消し、ユーザーIDを返す
+///
+/// 確認前なら保留中の変更を中止し、確認後なら旧アドレスに戻します。
+/// どちらの場合もセッションが乗っ取られている可能性があるため、すべてのリフレッシュトークンを無効にします。
+pub async fn revert_change(pool: &PgPool, token: &str) -> Result<Uuid, AppError> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let request = sqlx::query_as::<_, EmailChangeRequest>(
+        r#"
+        SELECT id, user_id, old_email, new_email, confirmed_at
+        FROM email_change_requests
+        WHERE revert_token_hash = $1
+          AND reverted_at IS NULL
+          AND superseded_at IS NULL
+          AND revert_expires_at > $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(hash_refresh_token(token))
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(invalid_token)?;
+
+    if request.confirmed_at.is_some() {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET email = $2,
+                pending_email = NULL,
+                email_verified = true,
+                email_verified_at = $3,
+                updated_at = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(request.user_id)
+        .bind(&request.old_email)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error::map_db_error)?;
+    } else {
+        sqlx::query(
+            "UPDATE users SET pending_email = NULL, updated_at = $3 WHERE id = $1 AND pending_email = $2",
+        )
+        .bind(request.user_id)
+        .bind(&request.new_email)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE email_change_requests SET reverted_at = $2 WHERE id = $1")
+        .bind(request.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE refresh_tokens SET revoked = true WHERE user_id = $1 AND revoked = false")
+        .bind(request.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(request.user_id)
+}
@@ -0,0 +1,302 @@
+// メール送信のアウトボックス
+//
+// ハンドラーは業務データと同じトランザクションで送信待ちの行を追加し、
+// バックグラウンドのワーカーが送信・再試行する。送信に失敗してもメールは失われない。
+use std::{sync::Arc, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    email::{EmailMessage, EmailSender},
+    error::AppError,
+    models::email_outbox::{EmailOutboxEntry, EmailOutboxStatus},
+};
+
+/// 送信を試みる最大回数。これを超えると `failed` になる
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// 1回のポーリングで処理する最大件数
+const BATCH_SIZE: i64 = 20;
+
+/// 最初の再試行までの待ち時間（以降は試行ごとに2倍）
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+
+/// 送信待ちのメールを追加する
+///
+/// 業務データの更新と同じトランザクションで呼び出すと、コミットされた場合にだけ送信されます。
+pub async fn enqueue<'e, E>(executor: E, message: &EmailMessage) -> Result<Uuid, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO email_outbox (to_email, subject, html_body, text_body)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(&message.to)
+    .bind(&message.subject)
+    .bind(&message.html_body)
+    .bind(&message.text_body)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(id)
+}
+
+/// attempts 回失敗した後、次の試行までの待ち時間（30秒・1分・2分・4分…）
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    Duration::seconds(BASE_RETRY_DELAY_SECONDS * 2_i64.pow(exponent))
+}
+
+/// 送信時刻を過ぎた送信待ちのメールを送信し、処理した件数を返す
+///
+/// 失敗したメールは指数バックオフで再試行を予約し、MAX_ATTEMPTS 回失敗すると `failed` にします。
+/// 複数のワーカーが同時に動いても同じ行を二重に送らないよう、ロック済みの行は読み飛ばします。
+pub async fn deliver_due(
+    pool: &PgPool,
+    sender: &dyn EmailSender,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let entries = sqlx::query_as::<_, EmailOutboxEntry>(
+        r#"
+        SELECT * FROM email_outbox
+        WHERE status = 'pending' AND next_attempt_at <= $1
+        ORDER BY next_attempt_at ASC
+        LIMIT $2
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for entry in &entries {
+        let message = EmailMessage {
+            to: entry.to_email.clone(),
+            subject: entry.subject.clone(),
+            html_body: entry.html_body.clone(),
+            text_body: entry.text_body.clone(),
+        };
+
+        match sender.send_email(message).await {
+            Ok(()) => {
+                sqlx::query(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = 'sent', attempts = attempts + 1, sent_at = $2,
+                        last_error = NULL, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(entry.id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            }
+            Err(err) => {
+                let attempts = entry.attempts + 1;
+                let status = if attempts >= MAX_ATTEMPTS {
+                    EmailOutboxStatus::Failed
+                } else {
+                    EmailOutboxStatus::Pending
+                };
+
+                tracing::warn!(
+                    outbox_id = %entry.id,
+                    attempts,
+                    status = status.as_str(),
+                    "Failed to send outbox email: {}",
+                    err
+                );
+
+                sqlx::query(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = $2, attempts = $3, next_attempt_at = $4,
+                        last_error = $5, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(entry.id)
+                .bind(status.as_str())
+                .bind(attempts)
+                .bind(now + retry_delay(attempts))
+                .bind(err.to_string())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(entries.len())
+}
+
+/// 送信状態で絞り込んだ件数
+pub async fn count(pool: &PgPool, status: Option<EmailOutboxStatus>) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM email_outbox WHERE ($1::text IS NULL OR status = $1)",
+    )
+    .bind(status.map(|s| s.as_str()))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// 送信状態で絞り込み、新しい順に返す
+pub async fn search(
+    pool: &PgPool,
+    status: Option<EmailOutboxStatus>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<EmailOutboxEntry>, AppError> {
+    let entries = sqlx::query_as::<_, EmailOutboxEntry>(
+        r#"
+        SELECT * FROM email_outbox
+        WHERE ($1::text IS NULL OR status = $1)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(status.map(|s| s.as_str()))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+/// 送信待ちのメールを定期的に送信するワーカーを起動する
+pub fn spawn_worker(pool: PgPool, sender: Arc<dyn EmailSender>, config: &Config) {
+    let poll_interval = StdDuration::from_secs(config.email_outbox_poll_interval_seconds);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            interval.tick().await;
+            // 1バッチで処理しきれなかった分は続けて処理する
+            loop {
+                match deliver_due(&pool, sender.as_ref(), Utc::now()).await {
+                    Ok(processed) if processed as i64 == BATCH_SIZE => continue,
+                    Ok(_) => break,
+                    Err(err) => {
+                        tracing::error!("Email outbox worker failed: {:?}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::mock::MockSender;
+    use chrono::SubsecRound;
+
+    fn test_message() -> EmailMessage {
+        EmailMessage {
+            to: "user@example.com".to_string(),
+            subject: "Subject".to_string(),
+            html_body: "<p>Body</p>".to_string(),
+            text_body: Some("Body".to_string()),
+        }
+    }
+
+    async fn fetch_entry(pool: &PgPool, id: Uuid) -> EmailOutboxEntry {
+        sqlx::query_as::<_, EmailOutboxEntry>("SELECT * FROM email_outbox WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_再試行の間隔は指数的に伸びる() {
+        // 再試行の間隔が指数的に伸びることを確認
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(3), Duration::seconds(120));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+    }
+
+    #[sqlx::test]
+    async fn test_送信に成功するとsentになる(pool: PgPool) {
+        // 送信に成功したメールがsentになることを確認
+        let sender = MockSender::new();
+        let id = enqueue(&pool, &test_message()).await.unwrap();
+
+        let processed = deliver_due(&pool, &sender, Utc::now()).await.unwrap();
+        assert_eq!(processed, 1);
+
+        let entry = fetch_entry(&pool, id).await;
+        assert_eq!(entry.status, "sent");
+        assert_eq!(entry.attempts, 1);
+        assert!(entry.sent_at.is_some());
+
+        let sent = sender.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "user@example.com");
+        assert_eq!(sent[0].text_body.as_deref(), Some("Body"));
+
+        // 送信済みのメールは再送しない
+        let processed = deliver_due(&pool, &sender, Utc::now()).await.unwrap();
+        assert_eq!(processed, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_失敗したメールはバックオフ後に再試行され上限でfailedになる(
+        pool: PgPool,
+    ) {
+        // 失敗したメールはバックオフ後に再試行され、上限に達するとfailedになることを確認
+        let sender = MockSender::failing();
+        let id = enqueue(&pool, &test_message()).await.unwrap();
+        // DBに保存される精度（マイクロ秒）に揃えて比較する
+        let mut now = Utc::now().trunc_subsecs(6);
+
+        for attempt in 1..MAX_ATTEMPTS {
+            assert_eq!(deliver_due(&pool, &sender, now).await.unwrap(), 1);
+
+            let entry = fetch_entry(&pool, id).await;
+            assert_eq!(entry.status, "pending");
+            assert_eq!(entry.attempts, attempt);
+            assert_eq!(entry.next_attempt_at, now + retry_delay(attempt));
+            assert!(entry.last_error.is_some());
+
+            // 待ち時間が過ぎるまでは再試行しない
+            assert_eq!(
+                deliver_due(
+                    &pool,
+                    &sender,
+                    now + retry_delay(attempt) - Duration::seconds(1)
+                )
+                .await
+                .unwrap(),
+                0
+            );
+            now = entry.next_attempt_at;
+        }
+
+        assert_eq!(deliver_due(&pool, &sender, now).await.unwrap(), 1);
+        let entry = fetch_entry(&pool, id).await;
+        assert_eq!(entry.status, "failed");
+        assert_eq!(entry.attempts, MAX_ATTEMPTS);
+
+        // failed になったメールはそれ以上送信しない
+        let later = now + Duration::days(1);
+        assert_eq!(deliver_due(&pool, &sender, later).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,56 @@
+use sqlx::{Postgres, Transaction};
+
+use super::frontend_link;
+use crate::config::Config;
+use crate::email::templates::{render_email, EmailContext, EmailTemplate, Expiry};
+use crate::error::AppError;
+use crate::models::User;
+use crate::utils::{
+    email_change::{EmailChangeTokens, REVERT_EXPIRES_IN_HOURS},
+    email_outbox,
+};
+
+// メールアドレス変更の確認メール（新しいアドレス宛て）と、取り消しリンク付きのお知らせ（旧アドレス宛て）を
+// アウトボックスに追加する
+pub async fn queue_email_change_emails(
+    config: &Config,
+    user: &User,
+    new_email: &str,
+    tokens: &EmailChangeTokens,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), AppError> {
+    let confirm_url = frontend_link(
+        config,
+        &config.email_change_confirm_path,
+        &tokens.confirm_token,
+    );
+    let confirm = render_email(
+        EmailTemplate::EmailChangeConfirm,
+        &EmailContext {
+            to: new_email,
+            username: &user.username,
+            action_url: &confirm_url,
+            expires_in: Expiry::Hours(config.email_verification_token_expiry_hours),
+        },
+    )?;
+
+    let revert_url = frontend_link(
+        config,
+        &config.email_change_revert_path,
+        &tokens.revert_token,
+    );
+    let notice = render_email(
+        EmailTemplate::EmailChangeNotice,
+        &EmailContext {
+            to: &user.email,
+            username: &user.username,
+            action_url: &revert_url,
+            expires_in: Expiry::Hours(REVERT_EXPIRES_IN_HOURS),
+        },
+    )?;
+
+    email_outbox::enqueue(&mut **tx, &confirm).await?;
+    email_outbox::enqueue(&mut **tx, &notice).await?;
+
+    Ok(())
+}
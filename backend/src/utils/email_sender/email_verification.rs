@@ -1,124 +1,175 @@
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
-use crate::email::{get_email_sender, EmailMessage};
+use super::frontend_link;
+use crate::config::Config;
+use crate::email::{
+    templates::{render_email, EmailContext, EmailTemplate, Expiry},
+    EmailMessage, EmailSender,
+};
 use crate::error::AppError;
 use crate::models::User;
-use crate::utils::email_verification;
+use crate::utils::{email_outbox, email_verification, token_generator::TokenGenerator};
+
+// メール検証用のURLを組み立てる
+fn verification_url(config: &Config, verification_token: &str) -> String {
+    frontend_link(config, &config.email_verification_path, verification_token)
+}
+
+// メール検証用メールを組み立てる
+fn verification_message(
+    config: &Config,
+    user: &User,
+    verification_token: &str,
+) -> Result<EmailMessage, AppError> {
+    let verification_url = verification_url(config, verification_token);
+
+    render_email(
+        EmailTemplate::Verification,
+        &EmailContext {
+            to: &user.email,
+            username: &user.username,
+            action_url: &verification_url,
+            expires_in: Expiry::Hours(config.email_verification_token_expiry_hours),
+        },
+    )
+}
 
 // メール検証用メール送信関数
 pub async fn send_verification_email(
+    config: &Config,
+    email_sender: &dyn EmailSender,
     user: &User,
     verification_token: &str,
 ) -> Result<(), AppError> {
-    let frontend_url =
-        std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let verification_path =
-        std::env::var("EMAIL_VERIFICATION_PATH").unwrap_or_else(|_| "/verify-email".to_string());
-
-    let verification_url = format!(
-        "{}{}/{}",
-        frontend_url, verification_path, verification_token
-    );
-
-    let html_body = format!(
-        r#"
-        <h1>メールアドレスの確認</h1>
-        <p>こんにちは、{}さん</p>
-        <p>以下のリンクをクリックしてメールアドレスを確認してください：</p>
-        <p><a href="{}">メールアドレスを確認する</a></p>
-        <p>このリンクは24時間後に期限切れになります。</p>
-        "#,
-        user.username, verification_url
-    );
-
-    let text_body = format!(
-        r#"
-        メールアドレスの確認
-
-        こんにちは、{}さん
-
-        以下のリンクをクリックしてメールアドレスを確認してください：
-
-        {}
-
-        このリンクは24時間後に期限切れになります。
-        "#,
-        user.username, verification_url
-    );
-
-    let message = EmailMessage {
-        to: user.email.clone(),
-        subject: "メールアドレスの確認".to_string(),
-        html_body,
-        text_body: Some(text_body),
-    };
-
-    let email_sender = get_email_sender();
     email_sender
-        .send_email(message)
+        .send_email(verification_message(config, user, verification_token)?)
         .await
         .map_err(|e| AppError::Internal(e.to_string()))
 }
 
+// メール検証用メールをアウトボックスに追加する（トランザクションのコミット後にワーカーが送信）
+pub async fn queue_verification_email(
+    config: &Config,
+    user: &User,
+    verification_token: &str,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), AppError> {
+    email_outbox::enqueue(
+        &mut **tx,
+        &verification_message(config, user, verification_token)?,
+    )
+    .await?;
+
+    Ok(())
+}
+
 // メール確認フロー開始
 pub async fn start_verification_flow(
+    config: &Config,
+    tokens: &dyn TokenGenerator,
     user: &User,
     tx: &mut Transaction<'_, Postgres>,
 ) -> Result<String, AppError> {
     // トークンの生成と保存
-    let verification_token = email_verification::create_verification_token(user.id, tx).await?;
+    let verification_token =
+        email_verification::create_verification_token(config, tokens, user.id, &user.email, tx)
+            .await?;
 
     Ok(verification_token)
 }
 
-// 検証メール再送信
-pub async fn resend_verification_email(user_id: Uuid, pool: &PgPool) -> Result<(), AppError> {
-    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e))?;
-
-    // ユーザー情報の取得
-    let user = sqlx::query_as!(
-        User,
-        r#"
-        SELECT
-            id,
-            username,
-            email,
-            display_name,
-            avatar_url,
-            email_verified::bool as "email_verified!",
-            email_verified_at,
-            verification_token,
-            verification_token_expires_at,
-            password_reset_token,
-            password_reset_token_expires_at,
-            created_at as "created_at!",
-            updated_at as "updated_at!"
-        FROM users
-        WHERE id = $1
-        "#,
-        user_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| AppError::Database(e))?;
+// 再送信用の検証トークンを発行
+// 確認済みの場合やクールダウン中の場合はエラーを返す
+pub async fn issue_resend_token(
+    config: &Config,
+    tokens: &dyn TokenGenerator,
+    user_id: Uuid,
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> Result<(User, String), AppError> {
+    let mut tx = pool.begin().await?;
+
+    // 同時リクエストでクールダウンをすり抜けないよう、ユーザーの行をロックする
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
 
     // 既にメール確認済みの場合はエラー
     if user.email_verified {
-        return Err(AppError::BadRequest(
-            "このメールアドレスは既に確認済みです。".to_string(),
-        ));
+        return Err(AppError::EmailAlreadyVerified);
     }
 
-    // 新しい検証トークンの生成
-    let verification_token =
-        email_verification::create_verification_token(user.id, &mut tx).await?;
+    // 前回の送信から間もない場合や、1日の上限に達している場合はエラー
+    let sent_at = email_verification::recent_verification_sends(&mut tx, user.id, now).await?;
+    if let Some(seconds) = email_verification::resend_wait_seconds(&sent_at, now) {
+        return Err(AppError::VerificationResendCooldown(seconds));
+    }
+
+    // 新しい検証トークンの生成（以前のトークンも有効期限までは使える）
+    let verification_token = email_verification::create_verification_token(
+        config,
+        tokens,
+        user.id,
+        &user.email,
+        &mut tx,
+    )
+    .await?;
 
     // トランザクションのコミット
-    tx.commit().await.map_err(|e| AppError::Database(e))?;
+    tx.commit().await?;
+
+    Ok((user, verification_token))
+}
+
+// 検証メール再送信
+pub async fn resend_verification_email(
+    config: &Config,
+    email_sender: &dyn EmailSender,
+    tokens: &dyn TokenGenerator,
+    user_id: Uuid,
+    pool: &PgPool,
+) -> Result<(), AppError> {
+    let (user, verification_token) =
+        issue_resend_token(config, tokens, user_id, pool, Utc::now()).await?;
 
     // メール送信
-    send_verification_email(&user, &verification_token).await?;
+    send_verification_email(config, email_sender, &user, &verification_token).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        email::mock::MockSender,
+        test_utils::{fakes::fake_user, test_config},
+    };
+
+    #[tokio::test]
+    async fn test_検証メールの本文に検証urlが含まれる() {
+        // 送信先がユーザーのメールアドレスで、HTML・テキストの両方にトークン付きURLが含まれることを確認
+        let sender = MockSender::new();
+        let user = fake_user(false);
+
+        let config = test_config();
+        send_verification_email(&config, &sender, &user, "verification-token-123")
+            .await
+            .unwrap();
+
+        let sent = sender.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+
+        let url = verification_url(&config, "verification-token-123");
+        assert_eq!(
+            url,
+            "http://localhost:3000/verify-email/verification-token-123"
+        );
+        assert!(sent[0].html_body.contains(&format!("href=\"{}\"", url)));
+        assert!(sent[0].text_body.as_deref().unwrap().contains(&url));
+    }
+}
@@ -0,0 +1,34 @@
+use super::frontend_link;
+use crate::config::Config;
+use crate::email::{
+    templates::{render_email, EmailContext, EmailTemplate, Expiry},
+    EmailSender,
+};
+use crate::error::AppError;
+use crate::models::User;
+use crate::utils::magic_link::MAGIC_LINK_EXPIRES_IN_MINUTES;
+
+// マジックリンク（パスワード不要のログイン）用メール送信関数
+pub async fn send_magic_link_email(
+    config: &Config,
+    email_sender: &dyn EmailSender,
+    user: &User,
+    token: &str,
+) -> Result<(), AppError> {
+    let magic_link_url = frontend_link(config, &config.magic_link_path, token);
+
+    let message = render_email(
+        EmailTemplate::MagicLink,
+        &EmailContext {
+            to: &user.email,
+            username: &user.username,
+            action_url: &magic_link_url,
+            expires_in: Expiry::Minutes(MAGIC_LINK_EXPIRES_IN_MINUTES),
+        },
+    )?;
+
+    email_sender
+        .send_email(message)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
@@ -1,7 +1,20 @@
+mod email_change;
 mod email_verification;
+mod magic_link;
 mod password_reset;
 
+pub use email_change::queue_email_change_emails;
+#[cfg(test)]
+pub use email_verification::issue_resend_token;
 pub use email_verification::{
-    resend_verification_email, send_verification_email, start_verification_flow,
+    queue_verification_email, resend_verification_email, start_verification_flow,
 };
+pub use magic_link::send_magic_link_email;
 pub use password_reset::send_password_reset_email;
+
+use crate::config::Config;
+
+// フロントエンドのURLにパスとトークンをつなげる
+fn frontend_link(config: &Config, path: &str, token: &str) -> String {
+    format!("{}{}/{}", config.frontend_url, path, token)
+}
@@ -1,53 +1,31 @@
-use crate::email::{get_email_sender, EmailMessage};
+use super::frontend_link;
+use crate::config::Config;
+use crate::email::{
+    templates::{render_email, EmailContext, EmailTemplate, Expiry},
+    EmailSender,
+};
 use crate::error::AppError;
 use crate::models::User;
 
 // パスワードリセット用メール送信関数
-pub async fn send_password_reset_email(user: &User, reset_token: &str) -> Result<(), AppError> {
-    let frontend_url =
-        std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let reset_path =
-        std::env::var("PASSWORD_RESET_PATH").unwrap_or_else(|_| "/reset-password".to_string());
+pub async fn send_password_reset_email(
+    config: &Config,
+    email_sender: &dyn EmailSender,
+    user: &User,
+    reset_token: &str,
+) -> Result<(), AppError> {
+    let reset_url = frontend_link(config, &config.password_reset_path, reset_token);
 
-    let reset_url = format!("{}{}/{}", frontend_url, reset_path, reset_token);
+    let message = render_email(
+        EmailTemplate::PasswordReset,
+        &EmailContext {
+            to: &user.email,
+            username: &user.username,
+            action_url: &reset_url,
+            expires_in: Expiry::Hours(config.password_reset_token_expiry_hours),
+        },
+    )?;
 
-    let html_body = format!(
-        r#"
-        <h1>パスワードリセットのリクエスト</h1>
-        <p>こんにちは、{}さん</p>
-        <p>パスワードリセットのリクエストを受け付けました。以下のリンクをクリックして新しいパスワードを設定してください：</p>
-        <p><a href="{}">パスワードをリセットする</a></p>
-        <p>このリンクは1時間後に期限切れになります。</p>
-        <p>このリクエストにお心当たりがない場合は、このメールを無視していただいて構いません。アカウントは安全です。</p>
-        "#,
-        user.username, reset_url
-    );
-
-    let text_body = format!(
-        r#"
-        パスワードリセットのリクエスト
-        
-        こんにちは、{}さん
-        
-        パスワードリセットのリクエストを受け付けました。以下のリンクをクリックして新しいパスワードを設定してください：
-        
-        {}
-        
-        このリンクは1時間後に期限切れになります。
-        
-        このリクエストにお心当たりがない場合は、このメールを無視していただいて構いません。アカウントは安全です。
-        "#,
-        user.username, reset_url
-    );
-
-    let message = EmailMessage {
-        to: user.email.clone(),
-        subject: "パスワードリセットのリクエスト".to_string(),
-        html_body,
-        text_body: Some(text_body),
-    };
-
-    let email_sender = get_email_sender();
     email_sender
         .send_email(message)
         .await
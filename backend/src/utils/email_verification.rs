@@ -1,104 +1,195 @@
-use chrono::{Duration, Utc};
-use rand::{distributions::Alphanumeric, Rng};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{
+    config::Config,
+    error::AppError,
+    utils::{token_generator::TokenGenerator, token_hash::hash_refresh_token},
+};
 
 pub const TOKEN_LENGTH: usize = 64;
 
-// トークン生成関数
-pub fn generate_verification_token() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(TOKEN_LENGTH)
-        .map(char::from)
-        .collect()
+// トークンの有効期限を計算（EMAIL_VERIFICATION_TOKEN_EXPIRES_IN）
+pub fn calculate_token_expiry(config: &Config) -> chrono::DateTime<Utc> {
+    Utc::now() + Duration::hours(config.email_verification_token_expiry_hours)
 }
 
-// トークンの有効期限を計算
-pub fn calculate_token_expiry() -> chrono::DateTime<Utc> {
-    let expires_in =
-        std::env::var("EMAIL_VERIFICATION_TOKEN_EXPIRES_IN").unwrap_or_else(|_| "24h".to_string());
+/// 検証メールを再送信できる間隔（分）
+pub const RESEND_COOLDOWN_MINUTES: i64 = 5;
 
-    let hours = if expires_in.ends_with('h') {
-        expires_in[..expires_in.len() - 1]
-            .parse::<i64>()
-            .unwrap_or(24)
-    } else {
-        24
-    };
-
-    Utc::now() + Duration::hours(hours)
-}
+/// 24時間あたりに送信できる検証メールの上限
+pub const MAX_VERIFICATION_EMAILS_PER_DAY: usize = 5;
 
 // ユーザーの検証トークンを作成・保存
+// 以前に発行したトークンは有効期限まで使えるよう残しておく
 pub async fn create_verification_token(
+    config: &Config,
+    tokens: &dyn TokenGenerator,
     user_id: Uuid,
+    email: &str,
     tx: &mut Transaction<'_, Postgres>,
 ) -> Result<String, AppError> {
-    let token = generate_verification_token();
-    let expires_at = calculate_token_expiry();
+    let token = tokens.alphanumeric_token(TOKEN_LENGTH);
+    let expires_at = calculate_token_expiry(config);
 
-    sqlx::query!(
+    sqlx::query(
         r#"
-        UPDATE users
-        SET verification_token = $1, verification_token_expires_at = $2
-        WHERE id = $3
+        INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
         "#,
-        token,
-        expires_at,
-        user_id
     )
+    .bind(user_id)
+    .bind(email)
+    .bind(hash_refresh_token(&token))
+    .bind(expires_at)
     .execute(&mut **tx)
-    .await
-    .map_err(|e| AppError::Database(e))?;
+    .await?;
 
     Ok(token)
 }
 
+// 直近24時間に検証メールを送信した時刻を取得
+pub async fn recent_verification_sends(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<DateTime<Utc>>, AppError> {
+    let sent_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+        r#"
+        SELECT created_at
+        FROM email_verification_tokens
+        WHERE user_id = $1 AND created_at > $2
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(now - Duration::hours(24))
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(sent_at)
+}
+
+/// 直近24時間の送信時刻から、次に再送信できるまでの秒数を計算する
+/// 今すぐ送信できる場合は None を返す
+pub fn resend_wait_seconds(sent_at: &[DateTime<Utc>], now: DateTime<Utc>) -> Option<u64> {
+    let mut sent_at = sent_at.to_vec();
+    sent_at.sort();
+
+    // 前回の送信からクールダウン期間が経過するまで
+    let cooldown_until = sent_at
+        .last()
+        .map(|latest| *latest + Duration::minutes(RESEND_COOLDOWN_MINUTES));
+
+    // 1日の上限に達している場合は、古い送信が24時間の枠から外れるまで
+    let daily_limit_until = sent_at
+        .len()
+        .checked_sub(MAX_VERIFICATION_EMAILS_PER_DAY)
+        .map(|index| sent_at[index] + Duration::hours(24));
+
+    let wait_until = cooldown_until.max(daily_limit_until)?;
+    let remaining_ms = (wait_until - now).num_milliseconds();
+    if remaining_ms <= 0 {
+        return None;
+    }
+
+    // 端数は切り上げて、Retry-After の秒数を過ぎれば必ず送信できるようにする
+    Some((remaining_ms as u64).div_ceil(1000))
+}
+
 // メール検証トークンを検証
+// 未使用・有効期限内で、発行時から送信先のメールアドレスが変わっていないトークンのみ受け付ける
 pub async fn verify_email(token: &str, pool: &PgPool) -> Result<Uuid, AppError> {
     let now = Utc::now();
+    let mut tx = pool.begin().await?;
 
-    let result = sqlx::query!(
+    let user_id = sqlx::query_scalar::<_, Uuid>(
         r#"
-        SELECT id
-        FROM users
-        WHERE verification_token = $1
-          AND verification_token_expires_at > $2
-          AND email_verified_at IS NULL
+        UPDATE email_verification_tokens t
+        SET consumed_at = $2
+        FROM users u
+        WHERE t.token_hash = $1
+          AND t.consumed_at IS NULL
+          AND t.expires_at > $2
+          AND u.id = t.user_id
+          AND u.email = t.email
+          AND u.email_verified_at IS NULL
+        RETURNING t.user_id
         "#,
-        token,
-        now
     )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| AppError::Database(e))?;
-
-    match result {
-        Some(user) => {
-            // トークンを消費して検証完了をマーク
-            sqlx::query!(
-                r#"
-                UPDATE users
-                SET email_verified_at = $1,
-                    email_verified = true,
-                    verification_token = NULL,
-                    verification_token_expires_at = NULL
-                WHERE id = $2
-                "#,
-                now,
-                user.id
-            )
-            .execute(pool)
-            .await
-            .map_err(|e| AppError::Database(e))?;
-
-            Ok(user.id)
-        }
-        None => Err(AppError::BadRequest(
-            "無効または期限切れの検証トークンです".to_string(),
-        )),
+    .bind(hash_refresh_token(token))
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("無効または期限切れの検証トークンです".to_string()))?;
+
+    // 検証完了をマーク
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET email_verified_at = $1,
+            email_verified = true,
+            verification_token = NULL,
+            verification_token_expires_at = NULL
+        WHERE id = $2
+        "#,
+    )
+    .bind(now)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes_ago: i64, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::minutes(minutes_ago)
+    }
+
+    #[test]
+    fn test_送信履歴がなければすぐに送信できる() {
+        // 送信履歴が無ければすぐに再送信できることを確認
+        assert_eq!(resend_wait_seconds(&[], Utc::now()), None);
+    }
+
+    #[test]
+    fn test_クールダウン中は残り秒数を返す() {
+        // クールダウン中は残り秒数を返すことを確認
+        let now = Utc::now();
+
+        assert_eq!(resend_wait_seconds(&[at(2, now)], now), Some(180));
+        assert_eq!(resend_wait_seconds(&[at(5, now)], now), None);
+    }
+
+    #[test]
+    fn test_1日の上限に達すると最も古い送信が枠から外れるまで待つ() {
+        // 1日の上限に達すると、最も古い送信が枠から外れるまで待つことを確認
+        let now = Utc::now();
+        let sent_at: Vec<_> = [23 * 60, 600, 300, 120, 60]
+            .into_iter()
+            .map(|minutes| at(minutes, now))
+            .collect();
+
+        // 最も古い送信（23時間前）が24時間の枠から外れるまでの1時間
+        assert_eq!(resend_wait_seconds(&sent_at, now), Some(60 * 60));
+    }
+
+    #[test]
+    fn test_上限未満ならクールダウンのみ判定する() {
+        // 上限未満ならクールダウンのみで判定することを確認
+        let now = Utc::now();
+        let sent_at: Vec<_> = [600, 300, 120, 60]
+            .into_iter()
+            .map(|minutes| at(minutes + 5, now))
+            .collect();
+
+        assert_eq!(resend_wait_seconds(&sent_at, now), None);
     }
 }
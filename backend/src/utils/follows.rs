@@ -0,0 +1,179 @@
+// ユーザーのフォローと、フォロー中のユーザーのスレッドのフィード
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        threads::ThreadWithUser,
+        users::{FollowCounts, FollowUserResponse},
+        User,
+    },
+};
+
+/// ユーザー名からユーザーを取得する（存在しない・退会した場合は404）
+pub async fn find_user(pool: &PgPool, username: &str) -> Result<User, AppError> {
+    sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE LOWER(username) = LOWER($1) AND deleted_at IS NULL",
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// フォローする（既にフォローしている場合は何もしない）
+///
+/// 自分自身はフォローできません。
+pub async fn follow(pool: &PgPool, follower_id: Uuid, followee_id: Uuid) -> Result<(), AppError> {
+    if follower_id == followee_id {
+        return Err(AppError::BadRequest(
+            "You cannot follow yourself".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO follows (follower_id, followee_id)
+        VALUES ($1, $2)
+        ON CONFLICT (follower_id, followee_id) DO NOTHING
+        "#,
+    )
+    .bind(follower_id)
+    .bind(followee_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// フォローを解除する（フォローしていない場合は何もしない）
+pub async fn unfollow(pool: &PgPool, follower_id: Uuid, followee_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM follows WHERE follower_id = $1 AND followee_id = $2")
+        .bind(follower_id)
+        .bind(followee_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// フォロワー・フォロー中のユーザーの数
+pub async fn counts(pool: &PgPool, user_id: Uuid) -> Result<FollowCounts, AppError> {
+    let (followers, following) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM follows WHERE followee_id = $1),
+            (SELECT COUNT(*) FROM follows WHERE follower_id = $1)
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(FollowCounts {
+        followers,
+        following,
+    })
+}
+
+/// フォロワーを新しくフォローされた順に取得
+pub async fn list_followers(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<FollowUserResponse>, AppError> {
+    let users = sqlx::query_as::<_, FollowUserResponse>(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.avatar_url, f.created_at as followed_at
+        FROM follows f
+        JOIN users u ON u.id = f.follower_id
+        WHERE f.followee_id = $1
+        ORDER BY f.created_at DESC, u.id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// フォロー中のユーザーを新しくフォローした順に取得
+pub async fn list_following(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<FollowUserResponse>, AppError> {
+    let users = sqlx::query_as::<_, FollowUserResponse>(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.avatar_url, f.created_at as followed_at
+        FROM follows f
+        JOIN users u ON u.id = f.followee_id
+        WHERE f.follower_id = $1
+        ORDER BY f.created_at DESC, u.id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// フォロー中のユーザーが投稿したスレッドを数える
+pub async fn count_feed(pool: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
+    let total = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM threads t
+        JOIN follows f ON f.followee_id = t.user_id
+        WHERE f.follower_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
+
+/// フォロー中のユーザーが投稿したスレッドを新しい順に取得
+pub async fn list_feed(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ThreadWithUser>, AppError> {
+    let threads = sqlx::query_as::<_, ThreadWithUser>(
+        r#"
+        SELECT
+            t.id, t.short_id, t.title, t.content, t.created_at, t.updated_at,
+            t.upvote_count, t.downvote_count, t.locked_at,
+            u.id as user_id, u.username, u.display_name as user_display_name, u.avatar_url as user_avatar_url,
+            (SELECT COUNT(*) FROM comments c WHERE c.thread_id = t.id) as comment_count,
+            (SELECT COUNT(*) FROM thread_revisions r WHERE r.thread_id = t.id) as revision_count
+        FROM threads t
+        JOIN follows f ON f.followee_id = t.user_id
+        JOIN users u ON t.user_id = u.id
+        WHERE f.follower_id = $1
+        ORDER BY t.created_at DESC, t.id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(threads)
+}
@@ -0,0 +1,216 @@
+// ETag と If-None-Match（または Last-Modified と If-Modified-Since）による条件付きレスポンスのユーティリティ
+//
+// 変わっていないリソースを繰り返し取得するクライアント（ポーリングやクローラー）に
+// 本文を送り直さず 304 Not Modified を返すために使います。
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::models::common::Existence;
+
+/// 弱いETag（`W/"..."`）を作成する
+///
+/// 内容が意味的に同じであればよいレスポンス（JSON など）に使います。
+/// `tag` にはリソースの版を表す値（更新日時や件数の組み合わせ）を渡します。
+pub fn weak_etag(tag: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("W/\"{}\"", hash_hex(tag.as_bytes())))
+        .expect("hex digest is a valid header value")
+}
+
+/// 強いETag（`"..."`）を作成する
+///
+/// バイト単位で同じであることを保証したいレスポンス（画像など）に使います。
+pub fn strong_etag(bytes: &[u8]) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", hash_hex(bytes)))
+        .expect("hex digest is a valid header value")
+}
+
+/// リクエストの If-None-Match が `etag` に一致するか
+///
+/// GET の条件付きリクエストでは弱い比較を使うため、`W/` の有無は区別しません。
+/// `*` やカンマ区切りで複数指定された場合にも対応します。
+pub fn if_none_match(request_headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+    let etag = opaque_tag(etag);
+
+    request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || opaque_tag(candidate) == etag)
+}
+
+/// 存在確認（HEAD）の結果をキャッシュしてよい秒数
+pub const EXISTENCE_MAX_AGE_SECONDS: u32 = 30;
+
+/// 存在確認のレスポンス（200 / 404 / 410、本文なし）
+///
+/// 画面の切り替えのたびに確認されるため、短時間のキャッシュを許可します。
+pub fn existence_response(existence: Existence) -> Response {
+    let status = match existence {
+        Existence::Present => StatusCode::OK,
+        Existence::Tombstoned => StatusCode::GONE,
+        Existence::Missing => StatusCode::NOT_FOUND,
+    };
+    let cache_control = format!("public, max-age={}", EXISTENCE_MAX_AGE_SECONDS);
+
+    (status, [(header::CACHE_CONTROL, cache_control)]).into_response()
+}
+
+/// Last-Modified ヘッダーの値（HTTP-date 形式）を作成する
+pub fn http_date(time: DateTime<Utc>) -> HeaderValue {
+    HeaderValue::from_str(&time.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .expect("formatted date is a valid header value")
+}
+
+/// リクエストの If-Modified-Since 以降に `last_modified` が変わっていないか
+///
+/// HTTP-date は秒単位のため、比較も秒単位で行います。解釈できない値は無視します。
+pub fn not_modified_since(request_headers: &HeaderMap, last_modified: DateTime<Utc>) -> bool {
+    request_headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| last_modified.timestamp() <= since.timestamp())
+}
+
+/// ETag を付けたレスポンス
+///
+/// If-None-Match が一致した場合は `NotModified` として本文を省いた 304 を返します。
+pub enum Conditional<T> {
+    Modified { etag: HeaderValue, body: T },
+    NotModified { etag: HeaderValue },
+}
+
+impl<T> Conditional<T> {
+    /// If-None-Match が一致すれば 304 を、一致しなければ `body` の結果を返す
+    ///
+    /// 本文の作成に時間がかかる場合に、304 を返すときは作成自体を省けるよう `body` は遅延評価します。
+    pub fn new<E>(
+        request_headers: &HeaderMap,
+        etag: HeaderValue,
+        body: impl FnOnce() -> Result<T, E>,
+    ) -> Result<Self, E> {
+        if if_none_match(request_headers, &etag) {
+            return Ok(Self::NotModified { etag });
+        }
+        Ok(Self::Modified {
+            etag,
+            body: body()?,
+        })
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Conditional<T> {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Modified { etag, body } => ([(header::ETAG, etag)], body).into_response(),
+            Self::NotModified { etag } => {
+                (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+            }
+        }
+    }
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    // ヘッダーが長くなりすぎないよう先頭 16 バイトだけ使う
+    digest[..16].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `W/` を除いた ETag の本体
+fn opaque_tag(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(if_none_match: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(if_none_match).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_同じ値からは同じetagが作られる() {
+        // 同じ値からは同じETagが作られることを確認
+        assert_eq!(weak_etag("a"), weak_etag("a"));
+        assert_ne!(weak_etag("a"), weak_etag("b"));
+        assert!(weak_etag("a").to_str().unwrap().starts_with("W/\""));
+        assert!(strong_etag(b"a").to_str().unwrap().starts_with('"'));
+    }
+
+    #[test]
+    fn test_if_none_matchの比較() {
+        // If-None-Match の比較を確認
+        let etag = strong_etag(b"image");
+        let other = strong_etag(b"other").to_str().unwrap().to_string();
+        let value = etag.to_str().unwrap().to_string();
+
+        assert!(if_none_match(&request_with(&value), &etag));
+        // 弱い比較なので W/ の有無は区別しない
+        assert!(if_none_match(&request_with(&format!("W/{}", value)), &etag));
+        assert!(if_none_match(
+            &request_with(&format!("{}, {}", other, value)),
+            &etag
+        ));
+        assert!(if_none_match(&request_with("*"), &etag));
+
+        assert!(!if_none_match(&request_with(&other), &etag));
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+    }
+
+    #[test]
+    fn test_if_modified_sinceの比較() {
+        // If-Modified-Since の比較を確認
+        let last_modified = DateTime::parse_from_rfc3339("2025-06-01T12:00:00.500Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let value = http_date(last_modified);
+        assert_eq!(value, "Sun, 01 Jun 2025 12:00:00 GMT");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MODIFIED_SINCE, value);
+        // 秒未満は切り捨てて比較する
+        assert!(not_modified_since(&headers, last_modified));
+        assert!(!not_modified_since(
+            &headers,
+            last_modified + chrono::Duration::seconds(1)
+        ));
+
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("yesterday"),
+        );
+        assert!(!not_modified_since(&headers, last_modified));
+        assert!(!not_modified_since(&HeaderMap::new(), last_modified));
+    }
+
+    #[test]
+    fn test_一致した場合は本文を作らずに304を返す() {
+        // ETagが一致した場合は本文を作らずに304を返すことを確認
+        let etag = weak_etag("v1");
+        let headers = request_with(etag.to_str().unwrap());
+
+        let response = Conditional::<&str>::new(&headers, etag.clone(), || -> Result<_, ()> {
+            panic!("本文は作成されない")
+        })
+        .unwrap()
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+    }
+}
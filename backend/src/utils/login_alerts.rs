@@ -0,0 +1,192 @@
+// 初めての端末からのログインの通知
+//
+// パスワードが漏れた場合に本人が気付けるよう、過去のセッション（リフレッシュトークン）に無い
+// User-Agent と IP アドレスの組み合わせからログインした場合は、メールとアプリ内の通知で知らせます。
+// NEW_DEVICE_CONFIRMATION が有効な場合は、メールのリンクで端末を確認するまでトークンを発行しません。
+// 比較する履歴はリフレッシュトークンが削除される（token_purge）までの期間に限られます。
+use axum::http::HeaderMap;
+use chrono::Utc;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    email::templates::{render_device_confirmation, render_new_sign_in, Expiry, NewSignInContext},
+    error::AppError,
+    models::User,
+    utils::{
+        email_outbox, request_info, token_generator::TokenGenerator, token_hash::hash_refresh_token,
+    },
+};
+
+/// 端末を確認するリンクの有効期間（分）
+pub const DEVICE_CONFIRMATION_EXPIRES_IN_MINUTES: i64 = 15;
+
+/// ヘッダーが無い場合にメールに表示する値
+const UNKNOWN: &str = "不明";
+
+/// ログインした端末（セッションに保存する User-Agent と IP アドレス）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignInDevice {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl SignInDevice {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            user_agent: request_info::user_agent(headers),
+            ip_address: request_info::client_ip(headers),
+        }
+    }
+
+    fn email_context<'a>(&'a self, user: &'a User, signed_in_at: &'a str) -> NewSignInContext<'a> {
+        NewSignInContext {
+            to: &user.email,
+            username: &user.username,
+            signed_in_at,
+            user_agent: self.user_agent.as_deref().unwrap_or(UNKNOWN),
+            ip_address: self.ip_address.as_deref().unwrap_or(UNKNOWN),
+        }
+    }
+}
+
+/// メールに表示するログインの日時
+fn signed_in_at() -> String {
+    Utc::now().format("%Y-%m-%d %H:%M (UTC)").to_string()
+}
+
+/// 過去のセッションに無い端末か
+///
+/// セッションが1件も無い場合は初めてのログインで比較できないため `false` を返します。
+pub async fn is_new_device(
+    pool: &PgPool,
+    user_id: Uuid,
+    device: &SignInDevice,
+) -> Result<bool, AppError> {
+    let (has_history, seen) = sqlx::query_as::<_, (bool, bool)>(
+        r#"
+        SELECT
+            EXISTS(SELECT 1 FROM refresh_tokens WHERE user_id = $1),
+            EXISTS(
+                SELECT 1 FROM refresh_tokens
+                WHERE user_id = $1
+                  AND user_agent IS NOT DISTINCT FROM $2
+                  AND ip_address IS NOT DISTINCT FROM $3
+            )
+        "#,
+    )
+    .bind(user_id)
+    .bind(&device.user_agent)
+    .bind(&device.ip_address)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(has_history && !seen)
+}
+
+/// 新しい端末からのログインをアプリ内の通知に追加する
+pub async fn record_sign_in_notification<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    device: &SignInDevice,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, kind, user_agent, ip_address)
+        VALUES ($1, 'new_sign_in', $2, $3)
+        "#,
+    )
+    .bind(user_id)
+    .bind(&device.user_agent)
+    .bind(&device.ip_address)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// 新しい端末からのログインをアプリ内の通知とメールで知らせる
+pub async fn notify_new_sign_in(
+    pool: &PgPool,
+    user: &User,
+    device: &SignInDevice,
+) -> Result<(), AppError> {
+    let signed_in_at = signed_in_at();
+    let message = render_new_sign_in(&device.email_context(user, &signed_in_at))?;
+
+    let mut tx = pool.begin().await?;
+    record_sign_in_notification(&mut *tx, user.id, device).await?;
+    email_outbox::enqueue(&mut *tx, &message).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// 端末を確認するトークンを発行し、確認メールを送信待ちに追加する
+///
+/// 保存するのはハッシュ値のみで、平文のトークンはメールでのみ送信します。
+pub async fn request_device_confirmation(
+    pool: &PgPool,
+    config: &Config,
+    tokens: &dyn TokenGenerator,
+    user: &User,
+    device: &SignInDevice,
+) -> Result<String, AppError> {
+    let token = tokens.secure_token();
+    let signed_in_at = signed_in_at();
+    let message = render_device_confirmation(
+        &device.email_context(user, &signed_in_at),
+        &format!("{}/confirm-device/{}", config.frontend_url, token),
+        Expiry::Minutes(DEVICE_CONFIRMATION_EXPIRES_IN_MINUTES),
+    )?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO device_confirmation_tokens (user_id, token_hash, user_agent, ip_address, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(mins => $5))
+        "#,
+    )
+    .bind(user.id)
+    .bind(hash_refresh_token(&token))
+    .bind(&device.user_agent)
+    .bind(&device.ip_address)
+    .bind(DEVICE_CONFIRMATION_EXPIRES_IN_MINUTES as i32)
+    .execute(&mut *tx)
+    .await?;
+    email_outbox::enqueue(&mut *tx, &message).await?;
+    tx.commit().await?;
+
+    Ok(token)
+}
+
+/// トークンを使用済みにして、ユーザーIDとログインしようとした端末を返す
+/// 期限切れ・使用済み・存在しないトークンはすべて同じエラーになります
+pub async fn consume_device_confirmation(
+    pool: &PgPool,
+    token: &str,
+) -> Result<(Uuid, SignInDevice), AppError> {
+    let row = sqlx::query_as::<_, (Uuid, Option<String>, Option<String>)>(
+        r#"
+        UPDATE device_confirmation_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id, user_agent, ip_address
+        "#,
+    )
+    .bind(hash_refresh_token(token))
+    .fetch_optional(pool)
+    .await?;
+    let (user_id, user_agent, ip_address) = row.ok_or_else(|| {
+        AppError::Unauthorized("Invalid or expired device confirmation".to_string())
+    })?;
+
+    Ok((
+        user_id,
+        SignInDevice {
+            user_agent,
+            ip_address,
+        },
+    ))
+}
@@ -0,0 +1,52 @@
+// マジックリンク（パスワード不要のログイン）用トークンの発行と検証
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    utils::{token_generator::TokenGenerator, token_hash::hash_refresh_token},
+};
+
+/// マジックリンクの有効期間（分）
+pub const MAGIC_LINK_EXPIRES_IN_MINUTES: i64 = 15;
+
+/// マジックリンク用のトークンを発行して保存する
+/// 保存するのはハッシュ値のみで、平文のトークンはメールでのみ送信します
+pub async fn create_magic_link_token(
+    pool: &PgPool,
+    tokens: &dyn TokenGenerator,
+    user_id: Uuid,
+) -> Result<String, AppError> {
+    let token = tokens.secure_token();
+
+    sqlx::query(
+        r#"
+        INSERT INTO magic_link_tokens (user_id, token_hash, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(mins => $3))
+        "#,
+    )
+    .bind(user_id)
+    .bind(hash_refresh_token(&token))
+    .bind(MAGIC_LINK_EXPIRES_IN_MINUTES as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// トークンを使用済みにしてユーザーIDを返す
+/// 期限切れ・使用済み・存在しないトークンはすべて同じエラーになります
+pub async fn consume_magic_link_token(pool: &PgPool, token: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE magic_link_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+    )
+    .bind(hash_refresh_token(token))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized("Invalid or expired magic link".to_string()))
+}
@@ -0,0 +1,206 @@
+// 投稿本文の Markdown を表示用の HTML に変換する
+//
+// 本文は入力されたまま保存し、レスポンスを作るときに毎回 `content_html` を作成します（列には保存しません）。
+// 変換や許可するタグを変えても、保存済みの投稿に移行処理は要りません。
+//
+// 貼り付けられた HTML はタグとして解釈せず文字列として表示し、変換後の HTML も ammonia で
+// 許可したタグ・属性・URLスキームのみに絞ります。リンクには `rel="nofollow noopener noreferrer"` を付けます。
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use linkify::{LinkFinder, LinkKind};
+use pulldown_cmark::{html::push_html, CowStr, Event, LinkType, Options, Parser, Tag};
+
+/// リンクに付ける rel 属性
+pub const LINK_REL: &str = "nofollow noopener noreferrer";
+
+/// 表示を許可するタグ（画像は外部への読み込みを避けるため許可しない）
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "strong",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// リンクに許可するURLスキーム（`javascript:` などは href ごと取り除く）
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+lazy_static! {
+    static ref SANITIZER: ammonia::Builder<'static> = {
+        let mut builder = ammonia::Builder::default();
+        builder
+            .tags(ALLOWED_TAGS.iter().copied().collect())
+            .tag_attributes(HashMap::from([
+                ("a", HashSet::from(["href", "title"])),
+                // フェンスコードブロックの言語（language-rust など）
+                ("code", HashSet::from(["class"])),
+                ("ol", HashSet::from(["start"])),
+            ]))
+            .url_schemes(ALLOWED_URL_SCHEMES.iter().copied().collect())
+            .link_rel(Some(LINK_REL));
+        builder
+    };
+}
+
+/// Markdown を安全な HTML に変換する
+///
+/// 取り消し線と表に対応し、本文中の `http(s)://` で始まるURLはリンクにします。
+pub fn to_html(content: &str) -> String {
+    let parser = Parser::new_ext(
+        content,
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+    );
+
+    let mut events = Vec::new();
+    let mut text = String::new();
+    // リンク・画像・コードブロックの中の URL はリンクにしない
+    let mut no_autolink_depth = 0usize;
+
+    for event in parser {
+        match event {
+            // URL が途中で分かれないよう、続けて現れる文字列はまとめてからリンクを探す
+            Event::Text(value) => text.push_str(&value),
+            // 貼り付けられた HTML はタグとして解釈せず、文字列として表示する
+            Event::Html(value) => text.push_str(&value),
+            event => {
+                flush_text(&mut events, &mut text, no_autolink_depth == 0);
+                match &event {
+                    Event::Start(Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                        no_autolink_depth += 1
+                    }
+                    Event::End(Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                        no_autolink_depth = no_autolink_depth.saturating_sub(1)
+                    }
+                    _ => {}
+                }
+                events.push(event);
+            }
+        }
+    }
+    flush_text(&mut events, &mut text, no_autolink_depth == 0);
+
+    let mut html = String::with_capacity(content.len() * 3 / 2);
+    push_html(&mut html, events.into_iter());
+
+    SANITIZER.clean(&html).to_string()
+}
+
+/// まとめた文字列をイベントとして追加する（`autolink` の場合は URL をリンクにする）
+fn flush_text(events: &mut Vec<Event<'_>>, text: &mut String, autolink: bool) {
+    if text.is_empty() {
+        return;
+    }
+    let text = std::mem::take(text);
+    if !autolink {
+        events.push(Event::Text(CowStr::from(text)));
+        return;
+    }
+
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Url]);
+    for span in finder.spans(&text) {
+        let value = CowStr::from(span.as_str().to_string());
+        match span.kind() {
+            Some(LinkKind::Url) => {
+                let link = Tag::Link(LinkType::Autolink, value.clone(), CowStr::from(""));
+                events.push(Event::Start(link.clone()));
+                events.push(Event::Text(value));
+                events.push(Event::End(link));
+            }
+            _ => events.push(Event::Text(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_スクリプトやイベント属性は実行されない() {
+        // スクリプトやイベント属性は実行されないことを確認
+        let html = to_html("<script>alert(1)</script>\n\nhello <img src=x onerror=alert(1)>");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<img"));
+        // 貼り付けた HTML は文字列として表示される
+        assert!(html.contains("&lt;script&gt;"));
+
+        // javascript: のリンクは href を取り除く
+        let html = to_html("[click](javascript:alert(1))");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("click</a>"));
+
+        let html = to_html("<a href=\"javascript:alert(1)\">x</a>");
+        assert!(!html.contains("<a "));
+    }
+
+    #[test]
+    fn test_リンクにはnofollowを付ける() {
+        // リンクにはnofollowを付けることを確認
+        let html = to_html("[example](https://example.com)");
+        assert!(html.contains("href=\"https://example.com\""));
+        assert!(html.contains(&format!("rel=\"{}\"", LINK_REL)));
+    }
+
+    #[test]
+    fn test_本文中のurlをリンクにする() {
+        // 本文中のURLをリンクにすることを確認
+        let html = to_html("詳しくは https://example.com/path_to?q=1 を参照");
+        assert!(html.contains("href=\"https://example.com/path_to?q=1\""));
+        assert!(html.contains(">https://example.com/path_to?q=1</a>"));
+        assert!(html.contains("詳しくは "));
+
+        // コードの中はリンクにしない
+        let html = to_html("`https://example.com`\n\n```\nhttps://example.com\n```");
+        assert!(!html.contains("<a"));
+    }
+
+    #[test]
+    fn test_フェンスコードブロック() {
+        // フェンスコードブロックを変換し、中身をエスケープすることを確認
+        let html = to_html("```rust\nfn main() { println!(\"<b>\"); }\n```");
+        assert!(html.contains(
+            "<pre><code class=\"language-rust\">fn main() { println!(\"&lt;b&gt;\"); }\n</code></pre>"
+        ));
+    }
+
+    #[test]
+    fn test_日本語の本文() {
+        // 日本語の本文を変換することを確認
+        assert_eq!(
+            to_html("日本語の**テキスト**です。"),
+            "<p>日本語の<strong>テキスト</strong>です。</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_入れ子のリスト() {
+        // 入れ子のリストを変換することを確認
+        let html = to_html("- 一\n  - 二\n    - 三\n- 四\n\n1. a\n   1. b");
+        assert_eq!(html.matches("<ul>").count(), 3);
+        assert_eq!(html.matches("<ol>").count(), 2);
+        assert!(html.contains("<li>三</li>"));
+        assert!(html.contains("<li>b</li>"));
+    }
+}
@@ -1,8 +1,49 @@
+pub mod account_export;
+pub mod atom;
+pub mod audit_log;
+pub mod auth_session;
+pub mod avatar_proxy;
+pub mod cache;
+pub mod comment_export;
 pub mod common;
+pub mod credentials;
+pub mod csv;
+pub mod data_migrations;
+pub mod db_error;
+pub mod drawing;
+pub mod email_change;
+pub mod email_outbox;
 pub mod email_sender;
 pub mod email_verification;
+pub mod follows;
+pub mod http_cache;
+pub mod login_alerts;
+pub mod magic_link;
+pub mod markdown;
+pub mod moderation_log;
+pub mod ogp;
+pub mod ogp_cache;
 pub mod password_reset;
+pub mod presence;
+pub mod preferences_transfer;
+pub mod refresh_token_cookie;
+pub mod reports;
+pub mod saved_searches;
+pub mod request_info;
+pub mod short_id;
+pub mod soft_limits;
+pub mod tag_rules;
+pub mod tags;
+pub mod token_purge;
+pub mod thread_search;
+pub mod thread_activity;
+pub mod thread_events;
+pub mod token_generator;
 pub mod token_hash;
+pub mod user_blocks;
+pub mod user_mute;
+pub mod user_stats;
+pub mod username_history;
 
 // 外部に公開する関数を再エクスポート
 pub use common::generate_secure_token;
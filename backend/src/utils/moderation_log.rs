@@ -0,0 +1,44 @@
+// モデレーターによる投稿削除の記録
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// モデレーションログに記録する操作の種類
+pub mod actions {
+    pub const THREAD_DELETE: &str = "thread.delete";
+    pub const COMMENT_DELETE: &str = "comment.delete";
+}
+
+/// モデレーションログを記録する
+///
+/// 対象の投稿は削除されるため、IDと投稿者のみを残します。
+pub async fn record<'e, E>(
+    executor: E,
+    moderator_id: Uuid,
+    action: &str,
+    target_type: &str,
+    target_id: Uuid,
+    target_author_id: Uuid,
+    reason: Option<&str>,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO moderation_log (moderator_id, action, target_type, target_id, target_author_id, reason)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(moderator_id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(target_author_id)
+    .bind(reason)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,701 @@
+// OGP画像の描画
+//
+// フォントの読み込みと文字幅の計算結果はリクエストごとに作り直さず、`OgpRenderer::shared` で使い回します。
+// タイトルの折り返し（禁則処理を含む）もここで行うため、ハンドラーを介さずにテストできます。
+use image::{ImageBuffer, Rgb, RgbImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut},
+    rect::Rect,
+};
+use lazy_static::lazy_static;
+use rusttype::{Font, Scale};
+
+use crate::error::{AppError, Result};
+use crate::utils::drawing::{self, LineWidth, TextMeasurer};
+
+/// タイトル用フォントサイズ
+const TITLE_SCALE: Scale = Scale { x: 80.0, y: 80.0 };
+/// スコア・コメント数用フォントサイズ
+const STATS_SCALE: Scale = Scale { x: 40.0, y: 40.0 };
+/// ブランド名用フォントサイズ
+const BRAND_SCALE: Scale = Scale { x: 58.0, y: 58.0 };
+/// 右下に表示するサイト名
+const BRAND_TEXT: &str = "みんなの話題";
+/// 画像のデザイン（レイアウト・色・フォント）を変えたら上げる。ETag に含め、古い画像を使わせない
+pub const IMAGE_REVISION: u32 = 2;
+
+lazy_static! {
+    static ref RENDERER: Option<OgpRenderer> = OgpRenderer::new().ok();
+}
+
+/// OGP画像を描画する（フォントとタイトルの文字幅のキャッシュを保持する）
+pub struct OgpRenderer {
+    font: &'static Font<'static>,
+    // タイトル用の文字幅キャッシュ
+    title_measurer: TextMeasurer<'static>,
+    // ブランド名の幅は変わらないため一度だけ計算する
+    brand_text_width: f32,
+}
+
+impl OgpRenderer {
+    fn new() -> Result<Self> {
+        let font = drawing::font()?;
+        Ok(Self {
+            font,
+            title_measurer: TextMeasurer::new(font, TITLE_SCALE),
+            brand_text_width: TextMeasurer::new(font, BRAND_SCALE).width(BRAND_TEXT),
+        })
+    }
+
+    /// プロセス全体で共有する描画処理を取得（初回のみフォントを読み込む）
+    pub fn shared() -> Result<&'static OgpRenderer> {
+        RENDERER
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("Failed to load font".to_string()))
+    }
+
+    /// スレッドのタイトル・投稿者名・スコア・コメント数からOGP画像（PNG）を生成する
+    pub fn render(
+        &self,
+        title: &str,
+        username: &str,
+        score: i64,
+        comment_count: i64,
+    ) -> Result<Vec<u8>> {
+        // OGP推奨サイズ（1200x630）で画像を作成
+        const WIDTH: u32 = 1200;
+        const HEIGHT: u32 = 630;
+        const BORDER_WIDTH: u32 = 32; // オレンジ枠線の太さ
+
+        // カラーパレット定義（TailwindCSS準拠）
+        let background_color = Rgb([255, 255, 255]); // 白背景
+        let border_color = Rgb([234, 88, 12]); // オレンジ枠線（text-orange-600）
+        let text_color = Rgb([0, 0, 0]); // 黒文字
+        let username_color = Rgb([107, 114, 128]); // グレー文字
+        let brand_color = Rgb([234, 88, 12]); // ブランド名（オレンジ）
+
+        // 白背景の画像を作成
+        let mut image: RgbImage = ImageBuffer::from_pixel(WIDTH, HEIGHT, background_color);
+
+        // 読み込み済みの日本語フォント（Noto Sans JP）を使う
+        let font = self.font;
+
+        // 画像四辺にオレンジの枠線を描画
+        // 上辺
+        draw_filled_rect_mut(
+            &mut image,
+            Rect::at(0, 0).of_size(WIDTH, BORDER_WIDTH),
+            border_color,
+        );
+        // 下辺
+        draw_filled_rect_mut(
+            &mut image,
+            Rect::at(0, (HEIGHT - BORDER_WIDTH) as i32).of_size(WIDTH, BORDER_WIDTH),
+            border_color,
+        );
+        // 左辺
+        draw_filled_rect_mut(
+            &mut image,
+            Rect::at(0, 0).of_size(BORDER_WIDTH, HEIGHT),
+            border_color,
+        );
+        // 右辺
+        draw_filled_rect_mut(
+            &mut image,
+            Rect::at((WIDTH - BORDER_WIDTH) as i32, 0).of_size(BORDER_WIDTH, HEIGHT),
+            border_color,
+        );
+
+        // スレッドタイトルを画像上部に描画（長い場合は自動改行、最大4行）
+        let max_title_width = WIDTH - 200; // 左右マージン100pxずつ確保
+        let wrapped_title = self.wrap_title(title, max_title_width);
+
+        let mut y_offset = 90; // タイトル開始位置（上マージン）
+        for line in wrapped_title.iter().take(4) {
+            // 最大4行まで表示
+            draw_text_mut(
+                &mut image,
+                text_color,
+                100, // 左マージン
+                y_offset,
+                TITLE_SCALE,
+                font,
+                line,
+            );
+            y_offset += 85; // 行間隔
+        }
+
+        // ユーザー名の上にスコアとコメント数を描画
+        let stats = format!("スコア {}・コメント {}", score, comment_count);
+        draw_text_mut(
+            &mut image,
+            username_color,
+            100,                   // 左マージン
+            (HEIGHT - 195) as i32, // 下から195px上
+            STATS_SCALE,
+            font,
+            &stats,
+        );
+
+        // 左下にユーザー名を描画（@マーク付きで表示）
+        let username_scale = Scale { x: 58.0, y: 58.0 }; // ユーザー名用フォントサイズ
+        let username_with_at = format!("@{}", username);
+        draw_text_mut(
+            &mut image,
+            username_color,
+            100,                   // 左マージン
+            (HEIGHT - 140) as i32, // 下から140px上
+            username_scale,
+            font,
+            &username_with_at,
+        );
+
+        // 右下にサイト名「みんなの話題」を描画
+        // ブランド名のテキスト幅（計算済み）を使って右寄せ配置
+        let text_width = self.brand_text_width as u32;
+
+        let brand_x = WIDTH - text_width - 100; // 右マージン100px確保
+        draw_text_mut(
+            &mut image,
+            brand_color,
+            brand_x as i32,
+            (HEIGHT - 140) as i32, // 下から140px上
+            BRAND_SCALE,
+            font,
+            BRAND_TEXT,
+        );
+
+        // 生成した画像をPNG形式のバイト配列にエンコード
+        drawing::encode_png(&image)
+    }
+
+    /// タイトルを画像の幅に合わせて折り返す
+    pub fn wrap_title(&self, title: &str, max_width: u32) -> Vec<String> {
+        wrap_text(title, &self.title_measurer, max_width)
+    }
+}
+
+/// 行頭に置かない文字（句読点・閉じ括弧・長音・小書きの仮名など）
+const LINE_START_PROHIBITED: &str =
+    "、。，．・：；？！…‥ー）」』】〕〉》］｝ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮヵヶ";
+
+/// 行末に置かない文字（開き括弧）
+const LINE_END_PROHIBITED: &str = "（「『【〔〈《［｛";
+
+/// 直後で改行しやすい文字（句読点・閉じ括弧）
+const BREAK_AFTER: &str = "、。，．・：；？！）」』】";
+
+fn is_line_start_prohibited(c: char) -> bool {
+    LINE_START_PROHIBITED.contains(c)
+}
+
+fn is_line_end_prohibited(c: char) -> bool {
+    LINE_END_PROHIBITED.contains(c)
+}
+
+fn is_break_after(c: char) -> bool {
+    BREAK_AFTER.contains(c)
+}
+
+/// 改行してよい位置で区切ったテキストの一部
+#[derive(Debug, PartialEq)]
+struct Segment<'a> {
+    text: &'a str,
+    /// 直前に空白があったか（同じ行に続ける場合は空白1つでつなぐ）
+    space_before: bool,
+}
+
+/// 空白・句読点の後ろ・開き括弧の前でテキストを区切る
+///
+/// 区切った直後が行頭に置けない文字の場合は区切りません。
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut result = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut space_before = false;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            if let Some(s) = start.take() {
+                result.push(Segment {
+                    text: &text[s..i],
+                    space_before,
+                });
+            }
+            space_before = true;
+            continue;
+        }
+
+        // 開き括弧は次の行の先頭に送れるよう、その前で区切る
+        if is_line_end_prohibited(c) {
+            if let Some(s) = start.take() {
+                result.push(Segment {
+                    text: &text[s..i],
+                    space_before,
+                });
+                space_before = false;
+            }
+        }
+        let s = *start.get_or_insert(i);
+
+        let next = chars.peek().map(|&(_, next)| next);
+        if is_break_after(c) && !next.is_some_and(is_line_start_prohibited) {
+            let end = i + c.len_utf8();
+            result.push(Segment {
+                text: &text[s..end],
+                space_before,
+            });
+            space_before = false;
+            start = None;
+        }
+    }
+
+    if let Some(s) = start {
+        result.push(Segment {
+            text: &text[s..],
+            space_before,
+        });
+    }
+
+    result
+}
+
+/// テキストを指定幅に合わせて自動改行する関数
+///
+/// 空白や句読点の後ろなど、自然に区切れる位置で優先して改行し、
+/// 1行に収まらない部分のみ文字単位で折り返します。
+fn wrap_text(text: &str, measurer: &TextMeasurer, max_width: u32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current_line = String::new();
+
+    for segment in segments(text) {
+        let test_line = if current_line.is_empty() {
+            segment.text.to_string()
+        } else if segment.space_before {
+            format!("{} {}", current_line, segment.text)
+        } else {
+            format!("{}{}", current_line, segment.text)
+        };
+
+        if measurer.width(&test_line) <= max_width as f32 {
+            // 幅内に収まる場合は行に追加
+            current_line = test_line;
+            continue;
+        }
+
+        // 幅を超える場合は改行
+        if !current_line.is_empty() {
+            lines.push(std::mem::take(&mut current_line));
+        }
+
+        if measurer.width(segment.text) <= max_width as f32 {
+            current_line = segment.text.to_string();
+        } else {
+            // 1行に収まらない部分（長い文やURLなど）は文字単位で折り返し、
+            // 最後の行には続きを詰められるようにする
+            let mut broken = break_long_word(segment.text, measurer, max_width);
+            current_line = broken.pop().unwrap_or_default();
+            lines.extend(broken);
+        }
+    }
+
+    // 最後の行を追加
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+
+    // 空の場合は「無題」を表示
+    if lines.is_empty() {
+        lines.push("無題".to_string());
+    }
+
+    lines
+}
+
+/// 長すぎる単語を文字単位で強制改行する関数（URL対応）
+///
+/// バッファ全体を毎回測り直さず、1文字ずつ幅を加算して判定します。
+/// 行頭に句読点・閉じ括弧が来る場合や、行末に開き括弧が残る場合は、直前の1文字を次の行に送ります。
+fn break_long_word(word: &str, measurer: &TextMeasurer, max_width: u32) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut buffer = String::new();
+    let mut line = LineWidth::default();
+
+    for c in word.chars() {
+        let single_char_width = measurer.advance(&mut LineWidth::default(), c);
+
+        // 1文字でもmax_widthを超える場合は、その文字だけで1行にする
+        if single_char_width > max_width as f32 {
+            if !buffer.is_empty() {
+                result.push(std::mem::take(&mut buffer));
+                line = LineWidth::default();
+            }
+            result.push(c.to_string());
+            continue;
+        }
+
+        if measurer.advance(&mut line, c) > max_width as f32 {
+            // 追加した文字を次の行の先頭に回す
+            let mut carried = String::new();
+            let needs_carry = buffer.chars().last().is_some_and(is_line_end_prohibited)
+                || is_line_start_prohibited(c);
+            if needs_carry && buffer.chars().count() > 1 {
+                if let Some(last) = buffer.pop() {
+                    carried.push(last);
+                }
+            }
+            if !buffer.is_empty() {
+                result.push(std::mem::take(&mut buffer));
+            }
+            line = LineWidth::default();
+            for carried_char in carried.chars() {
+                measurer.advance(&mut line, carried_char);
+            }
+            measurer.advance(&mut line, c);
+            buffer = carried;
+        }
+        buffer.push(c);
+    }
+
+    if !buffer.is_empty() {
+        result.push(buffer);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(title: &str, username: &str) -> Result<Vec<u8>> {
+        OgpRenderer::shared()?.render(title, username, 0, 0)
+    }
+
+    #[test]
+    fn test_続けて描画しても同じフォントを使い回す() {
+        // 続けて描画しても同じフォントを使い回すことを確認
+        let first = OgpRenderer::shared().unwrap();
+        first.render("1回目のタイトル", "testuser", 0, 0).unwrap();
+        let second = OgpRenderer::shared().unwrap();
+        second.render("2回目のタイトル", "testuser", 0, 0).unwrap();
+
+        assert!(std::ptr::eq(first, second));
+        assert!(std::ptr::eq(first.font, second.font));
+        assert!(std::ptr::eq(first.font, drawing::font().unwrap()));
+    }
+
+    #[test]
+    fn test_基本的な_ogp_画像が正常に生成される() {
+        // 通常の長さのタイトルとユーザー名でテスト
+        let title = "これは非常に長いタイトルのテストです";
+        let username = "testuser";
+
+        // OGP画像生成を実行
+        let result = render(title, username);
+
+        // 画像生成が成功することを確認
+        match &result {
+            Ok(_) => {}
+            Err(e) => panic!("OGP画像生成に失敗しました: {:?}", e),
+        }
+
+        let image_data = result.unwrap();
+        assert!(!image_data.is_empty());
+
+        // 生成されたデータがPNG形式であることを確認（マジックバイト）
+        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+    }
+
+    #[test]
+    fn test_非常に長いタイトルでも画像生成が成功する() {
+        // 複数行に折り返しが必要な長いタイトルでテスト
+        let title = "これは非常に長いタイトルのテストで、複数行に分かれることを期待しています。折り返し機能が正しく動作するかを確認するためのテストケースです。";
+        let username = "very_long_username_test";
+
+        // 長いタイトルでも画像生成が成功することを確認
+        let result = render(title, username);
+
+        assert!(result.is_ok());
+        let image_data = result.unwrap();
+        assert!(!image_data.is_empty());
+
+        // PNG形式で出力されることを確認
+        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+    }
+
+    #[test]
+    fn test_テキストが指定幅で正しく折り返される() {
+        // 読み込み済みの日本語フォントを使う
+        let font = drawing::font().unwrap();
+
+        // 折り返しテスト用の長いテキスト
+        let text = "これは非常に長いタイトルのテストです。複数行に分かれることを期待しています。";
+        let scale = Scale { x: 36.0, y: 36.0 };
+        let max_width = 600;
+
+        // テキスト折り返し処理を実行
+        let measurer = TextMeasurer::new(font, scale);
+        let lines = wrap_text(text, &measurer, max_width);
+
+        // 折り返し結果が正しく生成されることを確認
+        assert!(!lines.is_empty());
+    }
+
+    /// 従来どおり`Font::layout`でテキスト幅を計算する（比較用）
+    fn layout_width(font: &Font, scale: Scale, text: &str) -> f32 {
+        font.layout(text, scale, rusttype::point(0.0, 0.0))
+            .last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+            .unwrap_or(0.0)
+    }
+
+    /// 従来のバッファ全体を測り直す折り返し処理（比較用）
+    fn layout_break_long_word(
+        word: &str,
+        font: &Font,
+        scale: Scale,
+        max_width: u32,
+    ) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut buffer = String::new();
+
+        for c in word.chars() {
+            if layout_width(font, scale, &c.to_string()) > max_width as f32 {
+                if !buffer.is_empty() {
+                    result.push(buffer.clone());
+                    buffer.clear();
+                }
+                result.push(c.to_string());
+                continue;
+            }
+
+            buffer.push(c);
+            if layout_width(font, scale, &buffer) > max_width as f32 {
+                buffer.pop();
+                if !buffer.is_empty() {
+                    result.push(buffer.clone());
+                }
+                buffer = c.to_string();
+            }
+        }
+
+        if !buffer.is_empty() {
+            result.push(buffer);
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_キャッシュした文字幅がレイアウト結果と一致する() {
+        // 文字幅キャッシュでの計算結果が従来のレイアウト計算と一致することを確認
+        let font = drawing::font().unwrap();
+        let measurer = TextMeasurer::new(font, TITLE_SCALE);
+
+        for text in [
+            "",
+            "これは非常に長いタイトルのテストです",
+            "テスト投稿です いい感じ！",
+            "https://example.com/path?query=value&AV=To",
+            "みんなの話題",
+        ] {
+            assert_eq!(measurer.width(text), layout_width(font, TITLE_SCALE, text));
+        }
+
+        // ブランド名の幅も従来の計算と一致する
+        assert_eq!(
+            OgpRenderer::shared().unwrap().brand_text_width,
+            layout_width(font, BRAND_SCALE, BRAND_TEXT)
+        );
+    }
+
+    #[test]
+    fn test_長い単語の折り返し位置が従来と一致する() {
+        // 逐次加算での折り返し結果が従来の実装と同じになることを確認
+        let font = drawing::font().unwrap();
+        let measurer = TextMeasurer::new(font, TITLE_SCALE);
+        let max_width = 1000;
+
+        for word in [
+            "https://example.com/very/long/path/that/does/not/fit/in/a/single/line/of/the/ogp/image",
+            "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよ",
+            "WWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWW",
+        ] {
+            assert_eq!(
+                break_long_word(word, &measurer, max_width),
+                layout_break_long_word(word, font, TITLE_SCALE, max_width)
+            );
+        }
+    }
+
+    fn title_measurer() -> TextMeasurer<'static> {
+        TextMeasurer::new(drawing::font().unwrap(), TITLE_SCALE)
+    }
+
+    /// テキストがちょうど収まる幅
+    fn width_of(measurer: &TextMeasurer, text: &str) -> u32 {
+        measurer.width(text).ceil() as u32
+    }
+
+    #[test]
+    fn test_句読点や括弧の位置で区切られる() {
+        // 句読点や括弧の位置で区切られることを確認
+        let pairs: Vec<(&str, bool)> = segments("Rustで始める Web開発。入門「基礎編」です")
+            .into_iter()
+            .map(|segment| (segment.text, segment.space_before))
+            .collect();
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("Rustで始める", false),
+                ("Web開発。", true),
+                ("入門", false),
+                ("「基礎編」", false),
+                ("です", false),
+            ]
+        );
+
+        // 閉じ括弧の直後に句読点が続く場合は句読点の後ろで区切る
+        let texts: Vec<&str> = segments("「はい」。次へ")
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect();
+        assert_eq!(texts, vec!["「はい」。", "次へ"]);
+    }
+
+    #[test]
+    fn test_日本語の文は句点で改行される() {
+        // 日本語の文は句点で改行されることを確認
+        let measurer = title_measurer();
+        let first = "これは非常に長いタイトルのテストです。";
+        let second = "複数行に分かれることを期待しています。";
+        let max_width = width_of(&measurer, first).max(width_of(&measurer, second));
+
+        let lines = wrap_text(&format!("{}{}", first, second), &measurer, max_width);
+
+        assert_eq!(lines, vec![first, second]);
+    }
+
+    #[test]
+    fn test_収まる単語は途中で分割せず次の行に送る() {
+        // 収まる単語は途中で分割せず次の行に送ることを確認
+        // 従来は「今日から始める、プログラ」「ミング」と単語の途中で折り返していた
+        let measurer = title_measurer();
+        let max_width = width_of(&measurer, "今日から始める、プログラ");
+
+        let lines = wrap_text("今日から始める、プログラミング", &measurer, max_width);
+
+        assert_eq!(lines, vec!["今日から始める、", "プログラミング"]);
+    }
+
+    #[test]
+    fn test_行頭に句読点や閉じ括弧を置かない() {
+        // 行頭に句読点や閉じ括弧を置かないことを確認
+        let measurer = title_measurer();
+        let max_width = width_of(&measurer, "あいうえお");
+
+        // 区切れる位置が無い場合も、句点の前の1文字を一緒に次の行へ送る
+        assert_eq!(
+            wrap_text("あいうえお。", &measurer, max_width),
+            vec!["あいうえ", "お。"]
+        );
+        assert_eq!(
+            wrap_text("あいうえおっと", &measurer, max_width),
+            vec!["あいうえ", "おっと"]
+        );
+        // 開き括弧は行末に残さない
+        assert_eq!(
+            wrap_text("あいうえ「お」", &measurer, max_width),
+            vec!["あいうえ", "「お」"]
+        );
+
+        let title =
+            "【速報】新機能「スレッドのタグ付け」を公開しました！使い方・注意点まとめ（随時更新）";
+        for max_width in [300, 450, 600, 1000] {
+            for line in wrap_text(title, &measurer, max_width) {
+                let first = line.chars().next().unwrap();
+                let last = line.chars().last().unwrap();
+                assert!(!is_line_start_prohibited(first), "{:?}", line);
+                assert!(!is_line_end_prohibited(last), "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_日本語と英語が混在するタイトルの折り返し() {
+        // 日本語と英語が混在するタイトルの折り返しを確認
+        let measurer = title_measurer();
+        let max_width = width_of(&measurer, "Rustで始める Web開発。");
+
+        let lines = wrap_text(
+            "Rustで始める Web開発。初心者向けガイド",
+            &measurer,
+            max_width,
+        );
+
+        assert_eq!(lines, vec!["Rustで始める Web開発。", "初心者向けガイド"]);
+    }
+
+    #[test]
+    fn test_urlを含むタイトルの折り返し() {
+        // URLを含むタイトルの折り返しを確認
+        let measurer = title_measurer();
+        let url = "https://example.com/articles/2025/rust-async-programming";
+        let max_width = width_of(&measurer, "https://example.com/");
+
+        let lines = wrap_text(&format!("参考：{} を読んで", url), &measurer, max_width);
+
+        // 「参考：」の後ろで改行し、URLは文字単位で折り返す
+        assert_eq!(lines[0], "参考：");
+        let rest = lines[1..].concat();
+        assert!(rest.starts_with(url), "{:?}", lines);
+        assert_eq!(rest.replacen(url, "", 1).trim(), "を読んで");
+        for line in &lines {
+            assert!(measurer.width(line) <= max_width as f32, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_非常に長いurlのタイトルでも高速に生成される() {
+        // 2000文字のURLタイトルでも1秒未満で画像生成が完了することを確認
+        let title = format!("https://example.com/{}", "a1b2c3".repeat(330));
+        assert!(title.chars().count() >= 2000);
+
+        let started = std::time::Instant::now();
+        let result = render(&title, "testuser");
+        let elapsed = started.elapsed();
+
+        assert!(result.is_ok());
+        assert!(
+            elapsed < std::time::Duration::from_secs(1),
+            "OGP画像生成に時間がかかりすぎています: {:?}",
+            elapsed
+        );
+
+        // 折り返し結果は従来の実装と一致する
+        let font = drawing::font().unwrap();
+        assert_eq!(
+            OgpRenderer::shared().unwrap().wrap_title(&title, 1000),
+            layout_break_long_word(&title, font, TITLE_SCALE, 1000)
+        );
+    }
+
+    #[test]
+    fn test_絵文字入りタイトルでも_ogp_画像が生成される() {
+        // 絵文字が混在するタイトルとユーザー名でテスト
+        let title = "テスト投稿です😀🎉 いい感じ！";
+        let username = "testuser";
+
+        // 絵文字が含まれていても画像生成が成功することを確認
+        let result = render(title, username);
+
+        assert!(result.is_ok());
+        let image_data = result.unwrap();
+        assert!(!image_data.is_empty());
+
+        // PNG形式で正しく生成されることを確認
+        assert_eq!(&image_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+    }
+}
@@ -0,0 +1,522 @@
+// 生成したOGP画像のキャッシュ
+//
+// OGP画像の生成（フォントのラスタライズとPNGエンコード）には数十ミリ秒かかり、
+// SNSのクローラーからは同じスレッドの画像が繰り返し要求されるため、生成した画像を使い回します。
+// メモリ上では件数と合計サイズの上限を超えると最も長く使われていない画像から捨て、
+// OGP_CACHE_DIR を設定した場合はディスクにも書き込んで再起動後も使い回します。
+//
+// 画像にはスコアとコメント数も描くため、キャッシュのキー（`OgpCacheKey`）には描く文字列のハッシュに加えて
+// それらを10単位に丸めた値を含めます。スレッドの更新日時は投票でも変わるため、キーには使いません。少しの投票では生成し直さず、大きく変わったときだけ生成し直します。
+// スコアの変化で生成し直す頻度はスレッドごとに OGP_REGENERATE_INTERVAL_SECONDS までに抑え、
+// 同じスレッドを同時に生成し直す場合は1つだけが生成して残りは前の画像を返します。
+use axum::body::Bytes;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// 画像をどこから取得したか（ログに記録する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// メモリ上のキャッシュ
+    Memory,
+    /// ディスク上のキャッシュ
+    Disk,
+    /// キャッシュに無かったため生成した
+    Miss,
+    /// スコアかコメント数が変わったが、生成し直したばかりか生成中のため前の画像を使った
+    Stale,
+}
+
+/// スコア・コメント数を丸める単位
+const COUNT_BUCKET: i64 = 10;
+
+/// 画像を生成し直すかを決めるキー
+///
+/// タイトルや投稿者名を変えると文字列のハッシュが変わり、スコアかコメント数が10単位で変わると丸めた値が変わります。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OgpCacheKey {
+    pub text_hash: u64,
+    pub score_bucket: i64,
+    pub comment_bucket: i64,
+}
+
+impl OgpCacheKey {
+    /// `text` には画像に描く文字列（タイトル・投稿者名と画像の版）をまとめて渡す
+    pub fn new(text: &str, score: i64, comment_count: i64) -> Self {
+        // 再起動後もディスク上のファイル名が変わらないよう、プロセスに依らないハッシュを使う
+        let digest = Sha256::digest(text.as_bytes());
+        let mut text_hash = [0u8; 8];
+        text_hash.copy_from_slice(&digest[..8]);
+        Self {
+            text_hash: u64::from_be_bytes(text_hash),
+            score_bucket: bucket(score),
+            comment_bucket: bucket(comment_count),
+        }
+    }
+}
+
+/// 最も近い10の倍数に丸める（ちょうど中間の場合は大きい方）
+fn bucket(value: i64) -> i64 {
+    (value + COUNT_BUCKET / 2).div_euclid(COUNT_BUCKET) * COUNT_BUCKET
+}
+
+pub struct OgpImageCache {
+    max_entries: usize,
+    max_bytes: usize,
+    dir: Option<PathBuf>,
+    regenerate_interval: Duration,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    images: HashMap<Uuid, CachedImage>,
+    total_bytes: usize,
+    // 最後に使った順を判別するため、使うたびに増やす
+    clock: u64,
+    // 生成中のスレッド
+    generating: HashSet<Uuid>,
+}
+
+struct CachedImage {
+    key: OgpCacheKey,
+    image: Bytes,
+    last_used: u64,
+    generated_at: Instant,
+}
+
+impl OgpImageCache {
+    pub fn new(max_entries: usize, max_bytes: usize, dir: Option<PathBuf>) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            dir,
+            regenerate_interval: Duration::ZERO,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// スコアかコメント数の変化で同じスレッドの画像を生成し直す間隔の下限を設定する
+    pub fn with_regenerate_interval(mut self, interval: Duration) -> Self {
+        self.regenerate_interval = interval;
+        self
+    }
+
+    /// OGP_CACHE_MAX_ENTRIES・OGP_CACHE_MAX_MB・OGP_CACHE_DIR・OGP_REGENERATE_INTERVAL_SECONDS の設定で作成する
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.ogp_cache_max_entries,
+            config.ogp_cache_max_mb * 1024 * 1024,
+            config.ogp_cache_dir.as_ref().map(PathBuf::from),
+        )
+        .with_regenerate_interval(Duration::from_secs(config.ogp_regenerate_interval_seconds))
+    }
+
+    /// スレッドの画像を返す（キャッシュに無い場合は `generate` で生成して保存する）
+    ///
+    /// タイトルか投稿者名が変わっている場合は、古い画像を使わずに生成し直します。
+    /// スコアかコメント数だけが変わった場合は、前の画像を生成してから間隔の下限を過ぎていないか、
+    /// 他のリクエストが生成中であれば前の画像を返します。
+    pub fn get_or_generate<E>(
+        &self,
+        thread_id: Uuid,
+        key: OgpCacheKey,
+        generate: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<(Bytes, CacheStatus), E> {
+        if let Some(image) = self.get(thread_id, key) {
+            return Ok((image, CacheStatus::Memory));
+        }
+        if let Some(image) = self.read_file(thread_id, key) {
+            self.insert(thread_id, key, image.clone());
+            return Ok((image, CacheStatus::Disk));
+        }
+        let _generating = match self.start_generating(thread_id, key) {
+            Ok(generating) => generating,
+            Err(stale) => return Ok((stale, CacheStatus::Stale)),
+        };
+
+        // 生成中はロックを持たない（同時に生成した場合は後から保存した方が残る）
+        let image = Bytes::from(generate()?);
+        self.write_file(thread_id, key, &image);
+        self.insert(thread_id, key, image.clone());
+        Ok((image, CacheStatus::Miss))
+    }
+
+    /// スレッドの画像を捨てる（タイトルを変更したときに呼ぶ）
+    pub fn invalidate(&self, thread_id: Uuid) {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(removed) = entries.images.remove(&thread_id) {
+                entries.total_bytes -= removed.image.len();
+            }
+        }
+
+        if let Some(dir) = &self.dir {
+            if let Err(err) = remove_files(dir, thread_id) {
+                tracing::warn!(%thread_id, "Failed to remove cached OGP images: {}", err);
+            }
+        }
+    }
+
+    fn get(&self, thread_id: Uuid, key: OgpCacheKey) -> Option<Bytes> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        let cached = entries.images.get_mut(&thread_id)?;
+        if cached.key != key {
+            return None;
+        }
+        cached.last_used = clock;
+        Some(cached.image.clone())
+    }
+
+    /// 生成を始める（前の画像を使う場合はその画像を `Err` で返す）
+    fn start_generating(&self, thread_id: Uuid, key: OgpCacheKey) -> Result<Generating<'_>, Bytes> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        let busy = entries.generating.contains(&thread_id);
+        if let Some(cached) = entries.images.get_mut(&thread_id) {
+            // 描く文字列が同じ（スコアかコメント数だけが変わった）場合のみ前の画像を使える
+            let recent = cached.generated_at.elapsed() < self.regenerate_interval;
+            if cached.key.text_hash == key.text_hash && (busy || recent) {
+                cached.last_used = clock;
+                return Err(cached.image.clone());
+            }
+        }
+
+        // 使える画像が無く他のリクエストが生成中の場合は、このリクエストでも生成する
+        let marked = entries.generating.insert(thread_id);
+        Ok(Generating {
+            cache: self,
+            thread_id,
+            marked,
+        })
+    }
+
+    fn insert(&self, thread_id: Uuid, key: OgpCacheKey, image: Bytes) {
+        // 1枚で上限を超える画像はメモリに置かない
+        if image.len() > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let cached = CachedImage {
+            key,
+            last_used: entries.clock,
+            generated_at: Instant::now(),
+            image,
+        };
+        entries.total_bytes += cached.image.len();
+        if let Some(replaced) = entries.images.insert(thread_id, cached) {
+            entries.total_bytes -= replaced.image.len();
+        }
+
+        // 上限に収まるまで最も長く使われていない画像から捨てる
+        while entries.images.len() > self.max_entries || entries.total_bytes > self.max_bytes {
+            let Some(oldest) = entries
+                .images
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            if let Some(evicted) = entries.images.remove(&oldest) {
+                entries.total_bytes -= evicted.image.len();
+            }
+        }
+    }
+
+    fn read_file(&self, thread_id: Uuid, key: OgpCacheKey) -> Option<Bytes> {
+        let dir = self.dir.as_ref()?;
+        fs::read(file_path(dir, thread_id, key))
+            .ok()
+            .map(Bytes::from)
+    }
+
+    fn write_file(&self, thread_id: Uuid, key: OgpCacheKey, image: &[u8]) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        // 書き込み途中のファイルを読まないよう、一時ファイルに書き込んでから名前を変える
+        let path = file_path(dir, thread_id, key);
+        let tmp = path.with_extension("png.tmp");
+        let result = remove_files(dir, thread_id)
+            .and_then(|_| fs::write(&tmp, image))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if let Err(err) = result {
+            tracing::warn!(%thread_id, "Failed to write cached OGP image: {}", err);
+        }
+    }
+}
+
+/// 生成中の印（生成を終えるか失敗したら外す）
+struct Generating<'a> {
+    cache: &'a OgpImageCache,
+    thread_id: Uuid,
+    // 自分が付けた印のみ外す
+    marked: bool,
+}
+
+impl Drop for Generating<'_> {
+    fn drop(&mut self) {
+        if self.marked {
+            if let Ok(mut entries) = self.cache.entries.lock() {
+                entries.generating.remove(&self.thread_id);
+            }
+        }
+    }
+}
+
+impl Default for OgpImageCache {
+    /// メモリのみに 500 件・50 MB まで保存する
+    fn default() -> Self {
+        Self::new(500, 50 * 1024 * 1024, None)
+    }
+}
+
+/// ディスク上のファイル名（スレッドIDとキャッシュのキーで決める）
+fn file_path(dir: &Path, thread_id: Uuid, key: OgpCacheKey) -> PathBuf {
+    dir.join(format!(
+        "{}-{:016x}-{}-{}.png",
+        thread_id, key.text_hash, key.score_bucket, key.comment_bucket
+    ))
+}
+
+/// スレッドの古い画像を削除する（ディレクトリが無い場合は作成する）
+fn remove_files(dir: &Path, thread_id: Uuid) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let prefix = format!("{}-", thread_id);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// 呼び出された回数を数えながら画像を生成する
+    fn generate<'a>(
+        calls: &'a Cell<u32>,
+        image: &'a [u8],
+    ) -> impl FnOnce() -> Result<Vec<u8>, ()> + 'a {
+        move || {
+            calls.set(calls.get() + 1);
+            Ok(image.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_同じスレッドの2回目は生成せずに同じ画像を返す() {
+        // 同じスレッドの2回目は生成せずに同じ画像を返すことを確認
+        let cache = OgpImageCache::default();
+        let calls = Cell::new(0);
+        let thread_id = Uuid::new_v4();
+        let key = OgpCacheKey::new("title", 0, 0);
+
+        let (first, status) = cache
+            .get_or_generate(thread_id, key, generate(&calls, b"image"))
+            .unwrap();
+        assert_eq!(status, CacheStatus::Miss);
+        let (second, status) = cache
+            .get_or_generate(thread_id, key, generate(&calls, b"other"))
+            .unwrap();
+        assert_eq!(status, CacheStatus::Memory);
+
+        assert_eq!(first, second);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_タイトルが変わると生成し直す() {
+        // タイトルが変わると生成し直すことを確認
+        let cache = OgpImageCache::default();
+        let calls = Cell::new(0);
+        let thread_id = Uuid::new_v4();
+        let key = OgpCacheKey::new("title", 0, 0);
+
+        cache
+            .get_or_generate(thread_id, key, generate(&calls, b"before"))
+            .unwrap();
+        let (image, status) = cache
+            .get_or_generate(
+                thread_id,
+                OgpCacheKey::new("renamed", 0, 0),
+                generate(&calls, b"after"),
+            )
+            .unwrap();
+
+        assert_eq!(status, CacheStatus::Miss);
+        assert_eq!(&image[..], b"after");
+        assert_eq!(calls.get(), 2);
+        // 古い画像は置き換えられる
+        assert_eq!(cache.entries.lock().unwrap().total_bytes, b"after".len());
+    }
+
+    #[test]
+    fn test_件数とサイズの上限を超えると最も長く使われていない画像から捨てる() {
+        // 件数とサイズの上限を超えると最も長く使われていない画像から捨てることを確認
+        let cache = OgpImageCache::new(2, 10, None);
+        let calls = Cell::new(0);
+        let key = OgpCacheKey::new("title", 0, 0);
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache
+            .get_or_generate(a, key, generate(&calls, b"aaa"))
+            .unwrap();
+        cache
+            .get_or_generate(b, key, generate(&calls, b"bbb"))
+            .unwrap();
+        // a を使ったので、次に捨てられるのは b
+        cache
+            .get_or_generate(a, key, generate(&calls, b"aaa"))
+            .unwrap();
+        cache
+            .get_or_generate(c, key, generate(&calls, b"ccc"))
+            .unwrap();
+        assert_eq!(calls.get(), 3);
+        assert!(cache.get(a, key).is_some());
+        assert!(cache.get(b, key).is_none());
+
+        // 合計サイズの上限（10 バイト）を超えた場合も捨てる
+        cache
+            .get_or_generate(b, key, generate(&calls, b"bbbbbbbb"))
+            .unwrap();
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.images.len(), 1);
+        assert_eq!(entries.total_bytes, 8);
+    }
+
+    #[test]
+    fn test_ディスクに書き込んだ画像を再起動後も使う() {
+        // ディスクに書き込んだ画像を再起動後も使うことを確認
+        let dir = std::env::temp_dir().join(format!("ogp-cache-test-{}", Uuid::new_v4()));
+        let calls = Cell::new(0);
+        let thread_id = Uuid::new_v4();
+        let key = OgpCacheKey::new("title", 0, 0);
+
+        OgpImageCache::new(10, 1024, Some(dir.clone()))
+            .get_or_generate(thread_id, key, generate(&calls, b"image"))
+            .unwrap();
+
+        // メモリ上のキャッシュが空の状態でもディスクから読み込む
+        let restarted = OgpImageCache::new(10, 1024, Some(dir.clone()));
+        let (image, status) = restarted
+            .get_or_generate(thread_id, key, generate(&calls, b"other"))
+            .unwrap();
+        assert_eq!(status, CacheStatus::Disk);
+        assert_eq!(&image[..], b"image");
+        assert_eq!(calls.get(), 1);
+
+        // 捨てるとディスクからも削除される
+        restarted.invalidate(thread_id);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        let (_, status) = restarted
+            .get_or_generate(thread_id, key, generate(&calls, b"regenerated"))
+            .unwrap();
+        assert_eq!(status, CacheStatus::Miss);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_スコアとコメント数は10単位に丸めてキーにする() {
+        // スコアとコメント数は10単位に丸めてキーにすることを確認
+        let key = |score, comments| OgpCacheKey::new("title", score, comments);
+
+        assert_eq!(key(0, 0).score_bucket, 0);
+        assert_eq!(key(4, 0).score_bucket, 0);
+        assert_eq!(key(5, 0).score_bucket, 10);
+        assert_eq!(key(15, 0).score_bucket, 20);
+        assert_eq!(key(-4, 0).score_bucket, 0);
+        assert_eq!(key(-6, 0).score_bucket, -10);
+        assert_eq!(key(0, 14).comment_bucket, 10);
+
+        // 少しの変化ではキーが変わらない
+        assert_eq!(key(0, 0), key(2, 3));
+        assert_ne!(key(0, 0), key(15, 0));
+        assert_ne!(key(0, 0), key(0, 15));
+        assert_ne!(key(0, 0), OgpCacheKey::new("renamed", 0, 0));
+    }
+
+    #[test]
+    fn test_スコアの変化では間隔の下限を過ぎるまで前の画像を使う() {
+        // スコアの変化では間隔の下限を過ぎるまで前の画像を使うことを確認
+        let cache =
+            OgpImageCache::default().with_regenerate_interval(std::time::Duration::from_secs(60));
+        let calls = Cell::new(0);
+        let thread_id = Uuid::new_v4();
+        let key = OgpCacheKey::new("title", 0, 0);
+
+        cache
+            .get_or_generate(thread_id, key, generate(&calls, b"before"))
+            .unwrap();
+        let (image, status) = cache
+            .get_or_generate(
+                thread_id,
+                OgpCacheKey::new("title", 20, 0),
+                generate(&calls, b"after"),
+            )
+            .unwrap();
+        assert_eq!(status, CacheStatus::Stale);
+        assert_eq!(&image[..], b"before");
+        assert_eq!(calls.get(), 1);
+
+        // タイトルが変わった場合は間隔に関係なく生成し直す
+        let (image, status) = cache
+            .get_or_generate(
+                thread_id,
+                OgpCacheKey::new("renamed", 20, 0),
+                generate(&calls, b"renamed"),
+            )
+            .unwrap();
+        assert_eq!(status, CacheStatus::Miss);
+        assert_eq!(&image[..], b"renamed");
+    }
+
+    #[test]
+    fn test_同じスレッドの生成中は前の画像を返す() {
+        // 同じスレッドの生成中は前の画像を返すことを確認
+        let cache = OgpImageCache::default();
+        let calls = Cell::new(0);
+        let thread_id = Uuid::new_v4();
+        let key = OgpCacheKey::new("title", 0, 0);
+        let changed = OgpCacheKey::new("title", 20, 0);
+
+        cache
+            .get_or_generate(thread_id, key, generate(&calls, b"before"))
+            .unwrap();
+        let (image, status) = cache
+            .get_or_generate(thread_id, changed, || {
+                // 生成中に来たリクエストは生成せずに前の画像を使う
+                let (image, status) = cache
+                    .get_or_generate(thread_id, changed, generate(&calls, b"concurrent"))
+                    .unwrap();
+                assert_eq!(status, CacheStatus::Stale);
+                assert_eq!(&image[..], b"before");
+                Ok::<_, ()>(b"after".to_vec())
+            })
+            .unwrap();
+
+        assert_eq!(status, CacheStatus::Miss);
+        assert_eq!(&image[..], b"after");
+        assert_eq!(calls.get(), 1);
+        assert!(cache.entries.lock().unwrap().generating.is_empty());
+    }
+}
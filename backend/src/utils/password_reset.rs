@@ -1,55 +1,38 @@
 use chrono::{Duration, Utc};
-use rand::{distributions::Alphanumeric, Rng};
 use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{
+    config::Config, error::AppError, models::User, utils::token_generator::TokenGenerator,
+};
 
 pub const TOKEN_LENGTH: usize = 64;
 
-// トークン生成関数
-pub fn generate_reset_token() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(TOKEN_LENGTH)
-        .map(char::from)
-        .collect()
-}
-
-// トークンの有効期限を計算
-pub fn calculate_token_expiry() -> chrono::DateTime<Utc> {
-    let expires_in =
-        std::env::var("PASSWORD_RESET_TOKEN_EXPIRES_IN").unwrap_or_else(|_| "1h".to_string());
-
-    let hours = if expires_in.ends_with('h') {
-        expires_in[..expires_in.len() - 1]
-            .parse::<i64>()
-            .unwrap_or(1)
-    } else {
-        1
-    };
-
-    Utc::now() + Duration::hours(hours)
+// トークンの有効期限を計算（PASSWORD_RESET_TOKEN_EXPIRES_IN）
+pub fn calculate_token_expiry(config: &Config) -> chrono::DateTime<Utc> {
+    Utc::now() + Duration::hours(config.password_reset_token_expiry_hours)
 }
 
 // ユーザーのリセットトークンを作成・保存
 pub async fn create_reset_token(
+    config: &Config,
+    tokens: &dyn TokenGenerator,
     user_id: Uuid,
     tx: &mut Transaction<'_, Postgres>,
 ) -> Result<String, AppError> {
-    let token = generate_reset_token();
-    let expires_at = calculate_token_expiry();
+    let token = tokens.alphanumeric_token(TOKEN_LENGTH);
+    let expires_at = calculate_token_expiry(config);
 
-    sqlx::query!(
+    sqlx::query(
         r#"
         UPDATE users
         SET password_reset_token = $1, password_reset_token_expires_at = $2
         WHERE id = $3
         "#,
-        token,
-        expires_at,
-        user_id
     )
+    .bind(&token)
+    .bind(expires_at)
+    .bind(user_id)
     .execute(&mut **tx)
     .await
     .map_err(|e| AppError::Database(e))?;
@@ -57,28 +40,120 @@ pub async fn create_reset_token(
     Ok(token)
 }
 
+// パスワードの再設定を必須にする（再設定が完了するまでパスワードでのログインではトークンを発行しない）
+pub async fn require_reset(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Option<User>, AppError> {
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET must_reset_password = true WHERE id = $1 RETURNING *",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(user)
+}
+
+// パスワードの再設定が必須になっているか
+pub async fn is_reset_required(pool: &PgPool, user_id: Uuid) -> Result<bool, AppError> {
+    let required =
+        sqlx::query_scalar::<_, bool>("SELECT must_reset_password FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or(false);
+
+    Ok(required)
+}
+
+// パスワードを再設定したため、再設定の必須を解除する
+pub async fn clear_required_reset(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE users SET must_reset_password = false WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
 // パスワードリセットトークンを検証
 pub async fn verify_reset_token(token: &str, pool: &PgPool) -> Result<Uuid, AppError> {
     let now = Utc::now();
 
-    let result = sqlx::query!(
+    let result = sqlx::query_scalar::<_, Uuid>(
         r#"
         SELECT id
         FROM users
         WHERE password_reset_token = $1
           AND password_reset_token_expires_at > $2
         "#,
-        token,
-        now
     )
+    .bind(token)
+    .bind(now)
     .fetch_optional(pool)
     .await
     .map_err(|e| AppError::Database(e))?;
 
     match result {
-        Some(user) => Ok(user.id),
+        Some(user_id) => Ok(user_id),
         None => Err(AppError::BadRequest(
             "無効または期限切れのリセットトークンです".to_string(),
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_user, test_config, test_tokens};
+
+    async fn issue(pool: &PgPool, tokens: &dyn TokenGenerator, user_id: Uuid) -> String {
+        let mut tx = pool.begin().await.unwrap();
+        let token = create_reset_token(&test_config(), tokens, user_id, &mut tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        token
+    }
+
+    #[sqlx::test]
+    async fn test_発行したトークンでユーザーを特定できる(pool: PgPool) {
+        // 発行したトークンでユーザーを特定できることを確認
+        let user = create_test_user(&pool, true).await;
+        let other = create_test_user(&pool, true).await;
+        let tokens = test_tokens();
+
+        let token = issue(&pool, tokens.as_ref(), user.id).await;
+        issue(&pool, tokens.as_ref(), other.id).await;
+
+        assert_eq!(token.len(), TOKEN_LENGTH);
+        assert_eq!(verify_reset_token(&token, &pool).await.unwrap(), user.id);
+        assert!(matches!(
+            verify_reset_token("unknown-token", &pool).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れのトークンは使えない(pool: PgPool) {
+        // 期限切れのトークンは使えないことを確認
+        let user = create_test_user(&pool, true).await;
+        let token = issue(&pool, test_tokens().as_ref(), user.id).await;
+        sqlx::query(
+            "UPDATE users SET password_reset_token_expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            verify_reset_token(&token, &pool).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
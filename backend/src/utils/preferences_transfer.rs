@@ -0,0 +1,298 @@
+// 設定・ブロック・フォロー・保存した検索条件のエクスポートとインポート
+//
+// 別のインスタンスへ移る際に持ち運べるよう、ユーザーはユーザー名で表す。
+// インポートは既存の設定に追加する形で行い、同じ文書を何度取り込んでも結果は変わらない。
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        preferences::{
+            PortableSavedSearch, PreferenceSettings, PreferencesDocument,
+            PreferencesImportResponse, SkipReason, SkippedEntry, SkippedKind,
+        },
+        users::SortPreferences,
+    },
+    utils::{saved_searches::MAX_SAVED_SEARCHES_PER_USER, thread_search},
+};
+
+/// 文書の形式のバージョン（項目を変更した場合は上げる）
+pub const VERSION: u32 = 1;
+
+/// 1つの一覧で受け付ける最大件数
+pub const MAX_ENTRIES: usize = 1000;
+
+/// 保存できる検索語の最大文字数（保存時の検証と同じ）
+const MAX_QUERY_CHARS: usize = 100;
+
+/// 本人の設定を文書にする（退会したユーザーは含めない）
+pub async fn export(
+    pool: &PgPool,
+    user_id: Uuid,
+    settings: SortPreferences,
+) -> Result<PreferencesDocument, AppError> {
+    let blocks = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT u.username
+        FROM user_blocks b
+        JOIN users u ON u.id = b.blocked_id
+        WHERE b.blocker_id = $1 AND u.deleted_at IS NULL
+        ORDER BY b.created_at, u.username
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let follows = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT u.username
+        FROM follows f
+        JOIN users u ON u.id = f.followee_id
+        WHERE f.follower_id = $1 AND u.deleted_at IS NULL
+        ORDER BY f.created_at, u.username
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let saved_searches = sqlx::query_as::<_, (String, bool, bool)>(
+        "SELECT query, notify, email FROM saved_searches WHERE user_id = $1 ORDER BY created_at, id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(query, notify, email)| PortableSavedSearch {
+        query,
+        notify,
+        email,
+    })
+    .collect();
+
+    Ok(PreferencesDocument {
+        version: VERSION,
+        exported_at: Utc::now(),
+        settings: settings.into(),
+        blocks,
+        follows,
+        saved_searches,
+    })
+}
+
+/// 文書を取り込む
+///
+/// 並び順の既定値は文書の内容で置き換え、ブロック・フォロー・保存した検索条件は追加します。
+/// 見つからないユーザーや上限を超えた検索条件は取り込まず、理由とともに `skipped` に含めます。
+/// すべて1つのトランザクションで行うため、途中で失敗した場合は何も変わりません。
+pub async fn import(
+    pool: &PgPool,
+    user_id: Uuid,
+    document: &PreferencesDocument,
+) -> Result<PreferencesImportResponse, AppError> {
+    if document.version != VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported preferences version {} (expected {})",
+            document.version, VERSION
+        )));
+    }
+    if document.blocks.len() > MAX_ENTRIES
+        || document.follows.len() > MAX_ENTRIES
+        || document.saved_searches.len() > MAX_ENTRIES
+    {
+        return Err(AppError::BadRequest(format!(
+            "Each list can contain at most {} entries",
+            MAX_ENTRIES
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut skipped = Vec::new();
+
+    save_settings(&mut tx, user_id, &document.settings).await?;
+
+    let blocked_ids = resolve_users(
+        &mut tx,
+        user_id,
+        &document.blocks,
+        SkippedKind::Block,
+        &mut skipped,
+    )
+    .await?;
+    let blocks_added = sqlx::query(
+        r#"
+        INSERT INTO user_blocks (blocker_id, blocked_id)
+        SELECT $1, blocked_id FROM UNNEST($2::uuid[]) AS blocked_id
+        ON CONFLICT (blocker_id, blocked_id) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(&blocked_ids)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let followee_ids = resolve_users(
+        &mut tx,
+        user_id,
+        &document.follows,
+        SkippedKind::Follow,
+        &mut skipped,
+    )
+    .await?;
+    let follows_added = sqlx::query(
+        r#"
+        INSERT INTO follows (follower_id, followee_id)
+        SELECT $1, followee_id FROM UNNEST($2::uuid[]) AS followee_id
+        ON CONFLICT (follower_id, followee_id) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(&followee_ids)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let saved_searches_added =
+        import_saved_searches(&mut tx, user_id, &document.saved_searches, &mut skipped).await?;
+
+    tx.commit().await?;
+
+    Ok(PreferencesImportResponse {
+        blocks_added,
+        follows_added,
+        saved_searches_added,
+        skipped,
+    })
+}
+
+async fn save_settings(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    settings: &PreferenceSettings,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_settings (user_id, default_sort, default_window, default_comment_sort)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE SET
+            default_sort = EXCLUDED.default_sort,
+            default_window = EXCLUDED.default_window,
+            default_comment_sort = EXCLUDED.default_comment_sort,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(settings.default_sort.map(|sort| sort.as_str()))
+    .bind(settings.default_window.map(|window| window.as_str()))
+    .bind(settings.default_comment_sort.map(|sort| sort.as_str()))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// ユーザー名をこのインスタンスのユーザーIDにする（見つからないユーザーと自分自身は除く）
+async fn resolve_users(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    usernames: &[String],
+    kind: SkippedKind,
+    skipped: &mut Vec<SkippedEntry>,
+) -> Result<Vec<Uuid>, AppError> {
+    let mut seen = HashSet::new();
+    let usernames: Vec<String> = usernames
+        .iter()
+        .filter(|username| seen.insert(*username))
+        .cloned()
+        .collect();
+
+    let found: HashMap<String, Uuid> = sqlx::query_as::<_, (String, Uuid)>(
+        "SELECT username, id FROM users WHERE username = ANY($1) AND deleted_at IS NULL",
+    )
+    .bind(&usernames)
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut ids = Vec::new();
+    for username in usernames {
+        let reason = match found.get(&username) {
+            Some(&id) if id != user_id => {
+                ids.push(id);
+                continue;
+            }
+            Some(_) => SkipReason::SelfReference,
+            None => SkipReason::UnknownUser,
+        };
+        skipped.push(SkippedEntry {
+            kind,
+            value: username,
+            reason,
+        });
+    }
+
+    Ok(ids)
+}
+
+/// 保存した検索条件を追加し、追加した件数を返す（保存済みの検索語は何もしない）
+async fn import_saved_searches(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    searches: &[PortableSavedSearch],
+    skipped: &mut Vec<SkippedEntry>,
+) -> Result<u64, AppError> {
+    let mut existing: HashSet<String> =
+        sqlx::query_scalar::<_, String>("SELECT query FROM saved_searches WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut added = 0;
+    for search in searches {
+        let skip = |reason| SkippedEntry {
+            kind: SkippedKind::SavedSearch,
+            value: search.query.clone(),
+            reason,
+        };
+        let Some(query) = thread_search::normalize_query(&search.query)
+            .filter(|query| query.chars().count() <= MAX_QUERY_CHARS)
+        else {
+            skipped.push(skip(SkipReason::InvalidQuery));
+            continue;
+        };
+        if existing.contains(&query) {
+            continue;
+        }
+        if existing.len() as i64 >= MAX_SAVED_SEARCHES_PER_USER {
+            skipped.push(skip(SkipReason::LimitReached));
+            continue;
+        }
+
+        added += sqlx::query(
+            r#"
+            INSERT INTO saved_searches (user_id, query, notify, email)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT ON CONSTRAINT saved_searches_user_query_key DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(&query)
+        .bind(search.notify)
+        .bind(search.notify && search.email)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        existing.insert(query);
+    }
+
+    Ok(added)
+}
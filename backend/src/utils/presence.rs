@@ -0,0 +1,302 @@
+// スレッドを閲覧中のユーザー（プレゼンス）
+//
+// 閲覧中のクライアントは定期的にハートビートを送り、途絶えたユーザーは PRESENCE_TTL で期限切れにする。
+// 一時的な情報のためデータベースには書き込まず、プロセスのメモリ上にのみ保持する。
+// 閲覧者の変化はスレッドのイベント（`presence_changed`）として購読中のクライアントに配信する。
+// 閲覧者が変わるたびに配信すると多くなりすぎるため、スレッドごとに
+// BROADCAST_INTERVAL に1回まで、前回の配信から内容が変わった場合にのみ配信する。
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::utils::thread_events::{ThreadEvent, ThreadEvents};
+
+/// 閲覧者の変化を伝えるイベントの種類
+pub const PRESENCE_CHANGED: &str = "presence_changed";
+
+/// ハートビートが途絶えてから閲覧者から外すまでの時間
+pub const PRESENCE_TTL: Duration = Duration::from_secs(30);
+
+/// 同じスレッドの閲覧者の変化を通知する最短の間隔
+pub const BROADCAST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 通知に含めるユーザー名の最大数
+pub const MAX_USERNAMES: usize = 5;
+
+/// 閲覧者の状態
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PresenceSnapshot {
+    /// 匿名で閲覧しているユーザーを含む人数
+    pub viewer_count: usize,
+    /// 匿名でない閲覧者のユーザー名（名前順に最大 `MAX_USERNAMES` 件）
+    pub usernames: Vec<String>,
+}
+
+struct Viewer {
+    username: String,
+    anonymous: bool,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct ThreadPresence {
+    viewers: HashMap<Uuid, Viewer>,
+    /// 最後に通知した内容と日時
+    last_broadcast: Option<(Instant, PresenceSnapshot)>,
+}
+
+impl ThreadPresence {
+    fn expire(&mut self, now: Instant) {
+        self.viewers
+            .retain(|_, viewer| now.duration_since(viewer.last_seen) < PRESENCE_TTL);
+    }
+
+    fn snapshot(&self) -> PresenceSnapshot {
+        let mut usernames: Vec<String> = self
+            .viewers
+            .values()
+            .filter(|viewer| !viewer.anonymous)
+            .map(|viewer| viewer.username.clone())
+            .collect();
+        usernames.sort();
+        usernames.truncate(MAX_USERNAMES);
+
+        PresenceSnapshot {
+            viewer_count: self.viewers.len(),
+            usernames,
+        }
+    }
+
+    /// 前回の通知から内容が変わり、通知の間隔も空いていれば、通知する内容を返す
+    fn take_broadcast(&mut self, now: Instant) -> Option<PresenceSnapshot> {
+        let snapshot = self.snapshot();
+        match &self.last_broadcast {
+            Some((_, last)) if *last == snapshot => return None,
+            Some((at, _)) if now.duration_since(*at) < BROADCAST_INTERVAL => return None,
+            None if snapshot.viewer_count == 0 => return None,
+            _ => {}
+        }
+        self.last_broadcast = Some((now, snapshot.clone()));
+        Some(snapshot)
+    }
+
+    /// 閲覧者がおらず、最後の通知でも0人と伝えてあれば、保持する必要は無い
+    fn is_idle(&self) -> bool {
+        let announced_empty = match &self.last_broadcast {
+            Some((_, last)) => last.viewer_count == 0,
+            None => true,
+        };
+        self.viewers.is_empty() && announced_empty
+    }
+}
+
+/// スレッドごとの閲覧者
+#[derive(Default)]
+pub struct PresenceRegistry {
+    threads: Mutex<HashMap<Uuid, ThreadPresence>>,
+}
+
+impl PresenceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ハートビートを記録する
+    ///
+    /// 現在の閲覧者と、通知が必要な場合はその内容を返します。
+    pub fn heartbeat(
+        &self,
+        thread_id: Uuid,
+        user_id: Uuid,
+        username: &str,
+        anonymous: bool,
+        now: Instant,
+    ) -> (PresenceSnapshot, Option<PresenceSnapshot>) {
+        let mut threads = self.threads.lock().unwrap();
+        let presence = threads.entry(thread_id).or_default();
+        presence.expire(now);
+        presence.viewers.insert(
+            user_id,
+            Viewer {
+                username: username.to_string(),
+                anonymous,
+                last_seen: now,
+            },
+        );
+
+        (presence.snapshot(), presence.take_broadcast(now))
+    }
+
+    /// ハートビートが途絶えた閲覧者を外し、通知が必要なスレッドとその内容を返す
+    ///
+    /// 間隔の制限で通知できなかった変化も、次回以降の呼び出しで通知します。
+    pub fn sweep(&self, now: Instant) -> Vec<(Uuid, PresenceSnapshot)> {
+        let mut threads = self.threads.lock().unwrap();
+        let mut broadcasts = Vec::new();
+        for (thread_id, presence) in threads.iter_mut() {
+            presence.expire(now);
+            if let Some(snapshot) = presence.take_broadcast(now) {
+                broadcasts.push((*thread_id, snapshot));
+            }
+        }
+        threads.retain(|_, presence| !presence.is_idle());
+
+        broadcasts
+    }
+}
+
+/// 閲覧者の変化をスレッドの購読者に配信する
+pub fn broadcast(events: &ThreadEvents, thread_id: Uuid, snapshot: PresenceSnapshot) {
+    events.publish(
+        thread_id,
+        ThreadEvent {
+            kind: PRESENCE_CHANGED,
+            data: json!({
+                "thread_id": thread_id,
+                "viewer_count": snapshot.viewer_count,
+                "usernames": snapshot.usernames,
+            }),
+        },
+    );
+}
+
+/// 期限切れの閲覧者を定期的に外して配信するジョブを起動する
+pub fn spawn_job(registry: Arc<PresenceRegistry>, events: Arc<ThreadEvents>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BROADCAST_INTERVAL);
+        loop {
+            interval.tick().await;
+            for (thread_id, snapshot) in registry.sweep(Instant::now()) {
+                broadcast(&events, thread_id, snapshot);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(viewer_count: usize, usernames: &[&str]) -> PresenceSnapshot {
+        PresenceSnapshot {
+            viewer_count,
+            usernames: usernames.iter().map(|u| u.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_最初のハートビートは通知し_同じ内容は通知しない() {
+        // 最初のハートビートは通知し、同じ内容は通知しないことを確認
+        let registry = PresenceRegistry::new();
+        let thread = Uuid::new_v4();
+        let alice = Uuid::new_v4();
+        let start = Instant::now();
+
+        let (current, broadcast) = registry.heartbeat(thread, alice, "alice", false, start);
+        assert_eq!(current, snapshot(1, &["alice"]));
+        assert_eq!(broadcast, Some(snapshot(1, &["alice"])));
+
+        // 間隔が空いても内容が同じなら通知しない
+        let later = start + BROADCAST_INTERVAL * 2;
+        assert_eq!(
+            registry.heartbeat(thread, alice, "alice", false, later).1,
+            None
+        );
+        assert!(registry.sweep(later).is_empty());
+    }
+
+    #[test]
+    fn test_通知は間隔を空けてまとめて行う() {
+        // 通知は間隔を空けてまとめて行うことを確認
+        let registry = PresenceRegistry::new();
+        let thread = Uuid::new_v4();
+        let start = Instant::now();
+        registry.heartbeat(thread, Uuid::new_v4(), "alice", false, start);
+
+        // 間隔内の変化は通知しない
+        let soon = start + Duration::from_secs(1);
+        assert_eq!(
+            registry
+                .heartbeat(thread, Uuid::new_v4(), "bob", false, soon)
+                .1,
+            None
+        );
+        assert_eq!(
+            registry
+                .heartbeat(thread, Uuid::new_v4(), "carol", false, soon)
+                .1,
+            None
+        );
+
+        // 間隔が空いた後の sweep で最新の内容を1回だけ通知する
+        let after = start + BROADCAST_INTERVAL;
+        assert_eq!(
+            registry.sweep(after),
+            vec![(thread, snapshot(3, &["alice", "bob", "carol"]))]
+        );
+        assert!(registry.sweep(after + BROADCAST_INTERVAL).is_empty());
+    }
+
+    #[test]
+    fn test_ハートビートが途絶えた閲覧者は期限切れになる() {
+        // ハートビートが途絶えた閲覧者は期限切れになることを確認
+        let registry = PresenceRegistry::new();
+        let thread = Uuid::new_v4();
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let start = Instant::now();
+        registry.heartbeat(thread, alice, "alice", false, start);
+        registry.heartbeat(thread, bob, "bob", false, start);
+
+        // alice だけハートビートを続ける
+        let refreshed = start + PRESENCE_TTL / 2;
+        registry.heartbeat(thread, alice, "alice", false, refreshed);
+        assert_eq!(
+            registry.sweep(start + PRESENCE_TTL),
+            vec![(thread, snapshot(1, &["alice"]))]
+        );
+
+        // 全員いなくなると0人と通知し、その後は保持しない
+        let gone = refreshed + PRESENCE_TTL;
+        assert_eq!(registry.sweep(gone), vec![(thread, snapshot(0, &[]))]);
+        assert!(registry.threads.lock().unwrap().is_empty());
+        assert!(registry.sweep(gone + BROADCAST_INTERVAL).is_empty());
+    }
+
+    #[test]
+    fn test_匿名の閲覧者は人数にのみ含め_ユーザー名は5件まで() {
+        // 匿名の閲覧者は人数にのみ含め、ユーザー名は5件までにすることを確認
+        let registry = PresenceRegistry::new();
+        let thread = Uuid::new_v4();
+        let now = Instant::now();
+        for name in ["frank", "erin", "dave", "carol", "bob", "alice"] {
+            registry.heartbeat(thread, Uuid::new_v4(), name, false, now);
+        }
+
+        let (current, _) = registry.heartbeat(thread, Uuid::new_v4(), "hidden", true, now);
+
+        assert_eq!(current.viewer_count, 7);
+        assert_eq!(current.usernames, ["alice", "bob", "carol", "dave", "erin"]);
+    }
+
+    #[test]
+    fn test_スレッドごとに独立して数える() {
+        // スレッドごとに独立して数えることを確認
+        let registry = PresenceRegistry::new();
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let alice = Uuid::new_v4();
+        let now = Instant::now();
+
+        registry.heartbeat(first, alice, "alice", false, now);
+        let (current, broadcast) = registry.heartbeat(second, alice, "alice", true, now);
+
+        assert_eq!(current, snapshot(1, &[]));
+        assert_eq!(broadcast, Some(snapshot(1, &[])));
+    }
+}
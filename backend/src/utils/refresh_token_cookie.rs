@@ -0,0 +1,129 @@
+// リフレッシュトークンをHttpOnly Cookieで受け渡すためのユーティリティ
+use axum::http::{
+    header::{COOKIE, SET_COOKIE},
+    HeaderMap, HeaderValue,
+};
+
+use crate::config::Config;
+
+/// リフレッシュトークンを格納するCookie名
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
+
+/// Cookieを送信するパス（認証API配下のみに限定）
+pub const REFRESH_TOKEN_COOKIE_PATH: &str = "/api/auth";
+
+/// リフレッシュトークンの有効期間（秒）
+pub const REFRESH_TOKEN_MAX_AGE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// リフレッシュトークンを設定するSet-Cookieの値を生成
+pub fn build_refresh_token_cookie(token: &str) -> String {
+    format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        REFRESH_TOKEN_COOKIE_NAME, token, REFRESH_TOKEN_COOKIE_PATH, REFRESH_TOKEN_MAX_AGE_SECONDS
+    )
+}
+
+/// リフレッシュトークンのCookieを削除するSet-Cookieの値を生成
+pub fn build_clear_refresh_token_cookie() -> String {
+    format!(
+        "{}=; Path={}; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+        REFRESH_TOKEN_COOKIE_NAME, REFRESH_TOKEN_COOKIE_PATH
+    )
+}
+
+/// リクエストのCookieヘッダーからリフレッシュトークンを取得
+pub fn read_refresh_token_cookie(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == REFRESH_TOKEN_COOKIE_NAME)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 設定に応じてリフレッシュトークンの返却方法を切り替える
+/// Cookieモードの場合はSet-Cookieヘッダーを返し、レスポンスボディには含めない
+pub fn deliver_refresh_token(config: &Config, token: String) -> (HeaderMap, Option<String>) {
+    let mut headers = HeaderMap::new();
+
+    if config.refresh_token_cookie {
+        if let Ok(value) = HeaderValue::from_str(&build_refresh_token_cookie(&token)) {
+            headers.insert(SET_COOKIE, value);
+        }
+        (headers, None)
+    } else {
+        (headers, Some(token))
+    }
+}
+
+/// Cookieモードの場合にリフレッシュトークンのCookieを削除するヘッダーを返す
+pub fn clear_refresh_token(config: &Config) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if config.refresh_token_cookie {
+        if let Ok(value) = HeaderValue::from_str(&build_clear_refresh_token_cookie()) {
+            headers.insert(SET_COOKIE, value);
+        }
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    fn cookie_config(refresh_token_cookie: bool) -> Config {
+        Config {
+            refresh_token_cookie,
+            ..test_config()
+        }
+    }
+
+    #[test]
+    fn test_cookieモードではボディにトークンを含めない() {
+        // CookieモードではSet-Cookieヘッダーが付与され、ボディ用のトークンはNoneになることを確認
+        let (headers, body_token) = deliver_refresh_token(&cookie_config(true), "abc".to_string());
+
+        assert!(body_token.is_none());
+        let cookie = headers.get(SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(cookie.starts_with("refresh_token=abc;"));
+        assert!(cookie.contains("HttpOnly"));
+        assert!(cookie.contains("Secure"));
+        assert!(cookie.contains("SameSite=Lax"));
+        assert!(cookie.contains("Path=/api/auth"));
+    }
+
+    #[test]
+    fn test_jsonモードではボディにトークンを含める() {
+        // JSONモードではSet-Cookieヘッダーを付けず、トークンをボディで返すことを確認
+        let (headers, body_token) = deliver_refresh_token(&cookie_config(false), "abc".to_string());
+
+        assert_eq!(body_token, Some("abc".to_string()));
+        assert!(headers.get(SET_COOKIE).is_none());
+        assert!(clear_refresh_token(&cookie_config(false))
+            .get(SET_COOKIE)
+            .is_none());
+    }
+
+    #[test]
+    fn test_cookieヘッダーからトークンを読み取れる() {
+        // Cookieヘッダーからリフレッシュトークンを読み取れることを確認
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; refresh_token=xyz; other=1"),
+        );
+        assert_eq!(read_refresh_token_cookie(&headers), Some("xyz".to_string()));
+
+        // Cookieが無い場合や空の場合はNone
+        assert_eq!(read_refresh_token_cookie(&HeaderMap::new()), None);
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_static("refresh_token="));
+        assert_eq!(read_refresh_token_cookie(&headers), None);
+    }
+}
@@ -0,0 +1,169 @@
+// スレッド・コメントの通報の記録と、モデレーターによる対応
+//
+// 通報を対応済みにすると、同じ対象を通報したユーザー全員に対応結果をアプリ内で通知します。
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::reports::{CreateReportRequest, Report, ReportOutcome, ReportStatus, ReportTargetType},
+    utils::db_error,
+};
+
+/// 通報を記録する
+///
+/// 対象が存在しない場合はNotFound、同じユーザーが同じ対象を通報済みの場合は `REPORT_DUPLICATE` を返します。
+pub async fn create(
+    pool: &PgPool,
+    reporter_id: Uuid,
+    target_type: ReportTargetType,
+    target_id: Uuid,
+    request: &CreateReportRequest,
+) -> Result<Report, AppError> {
+    let target_table = match target_type {
+        ReportTargetType::Thread => "threads",
+        ReportTargetType::Comment => "comments",
+    };
+    // 空の補足は記録しない
+    let detail = request
+        .detail
+        .as_deref()
+        .map(str::trim)
+        .filter(|detail| !detail.is_empty());
+
+    let report = sqlx::query_as::<_, Report>(&format!(
+        r#"
+        INSERT INTO reports (reporter_id, target_type, target_id, reason, detail)
+        SELECT $1, $2, $3, $4, $5
+        WHERE EXISTS (SELECT 1 FROM {} WHERE id = $3)
+        RETURNING *
+        "#,
+        target_table
+    ))
+    .bind(reporter_id)
+    .bind(target_type.as_str())
+    .bind(target_id)
+    .bind(request.reason.as_str())
+    .bind(detail)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error::map_db_error)?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(report)
+}
+
+/// 対応状況で絞り込んだ通報の件数（Noneの場合は全件）
+pub async fn count(pool: &PgPool, status: Option<ReportStatus>) -> Result<i64, AppError> {
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM reports WHERE $1::text IS NULL OR status = $1",
+    )
+    .bind(status.map(|status| status.as_str()))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
+
+/// 対応状況で絞り込んだ通報を古い順に取得する（先に届いた通報から対応できるように）
+pub async fn search(
+    pool: &PgPool,
+    status: Option<ReportStatus>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Report>, AppError> {
+    let reports = sqlx::query_as::<_, Report>(
+        r#"
+        SELECT * FROM reports
+        WHERE $1::text IS NULL OR status = $1
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(status.map(|status| status.as_str()))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reports)
+}
+
+/// 未対応の通報を対応済みにし、同じ対象を通報したユーザーに対応結果を通知する
+///
+/// 通報が存在しない場合はNotFound、既に対応済みの場合はConflictを返します。
+pub async fn resolve(
+    pool: &PgPool,
+    id: Uuid,
+    moderator_id: Uuid,
+    outcome: ReportOutcome,
+) -> Result<Report, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let resolved = sqlx::query_as::<_, Report>(
+        r#"
+        UPDATE reports
+        SET status = 'resolved', outcome = $3, resolved_by = $2, resolved_at = NOW()
+        WHERE id = $1 AND status = 'open'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(moderator_id)
+    .bind(outcome.as_str())
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(report) = resolved {
+        let notified =
+            notify_reporters(&mut *tx, &report.target_type, report.target_id, outcome).await?;
+        tx.commit().await?;
+
+        tracing::debug!(report_id = %report.id, notified, "Reporters notified of outcome");
+        return Ok(report);
+    }
+    drop(tx);
+
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM reports WHERE id = $1)")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+    if exists {
+        Err(AppError::Conflict("Report is already resolved".to_string()))
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
+/// 対象を通報したユーザーに対応結果をアプリ内で通知し、通知した人数を返す
+///
+/// 通報したユーザーをまとめて1つの文で追加します。
+/// 同じ対象の対応結果を既に通知したユーザーには通知しません（複数の通報を順に対応した場合も1回）。
+/// 対応したモデレーターは通知に含めません。
+pub async fn notify_reporters<'e>(
+    executor: impl PgExecutor<'e>,
+    target_type: &str,
+    target_id: Uuid,
+    outcome: ReportOutcome,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, kind, report_target_type, report_target_id, report_outcome)
+        SELECT DISTINCT reporter_id, 'report_resolved', target_type, target_id, $3
+        FROM reports
+        WHERE target_type = $1 AND target_id = $2
+        ON CONFLICT (user_id, report_target_type, report_target_id)
+            WHERE kind = 'report_resolved'
+            DO NOTHING
+        "#,
+    )
+    .bind(target_type)
+    .bind(target_id)
+    .bind(outcome.as_str())
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,136 @@
+// リクエストヘッダーからクライアント情報を取得するユーティリティ
+use std::net::IpAddr;
+
+use axum::http::{header::USER_AGENT, HeaderMap};
+
+/// User-Agentヘッダーを取得する
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// クライアントのIPアドレスを取得する（セッション一覧などの表示用）
+/// リバースプロキシ経由を想定し、X-Forwarded-For の先頭または X-Real-IP を使用します
+/// クライアントが自由に書き換えられるため、レートリミットなどの判定には `trusted_client_ip` を使ってください
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("X-Forwarded-For")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .or_else(|| headers.get("X-Real-IP").and_then(|v| v.to_str().ok()))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 偽装できないクライアントのIPアドレスを取得する
+///
+/// 接続元が `trusted_proxies` のいずれかの場合に限り X-Forwarded-For を使い、右から順に信頼するプロキシを
+/// 読み飛ばして最初に現れたアドレスを返します（左側はクライアントが自由に書けるため使わない）。
+/// 接続元が信頼するプロキシでない場合や、ヘッダーから決められない場合は接続元のアドレスを返します。
+pub fn trusted_client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect();
+    for hop in hops.iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) if trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return ip,
+            // 解釈できない値より左は信頼しない
+            Err(_) => break,
+        }
+    }
+    peer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_client_ip_forwarded_for() {
+        // X-Forwarded-For の先頭のアドレスが使われることを確認
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Forwarded-For",
+            HeaderValue::from_static("203.0.113.1, 10.0.0.1"),
+        );
+        headers.insert("X-Real-IP", HeaderValue::from_static("10.0.0.2"));
+
+        assert_eq!(client_ip(&headers), Some("203.0.113.1".to_string()));
+    }
+
+    #[test]
+    fn test_client_ip_missing() {
+        // ヘッダーがない場合はNoneになることを確認
+        let headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers), None);
+        assert_eq!(user_agent(&headers), None);
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn forwarded_for(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_信頼するプロキシ以外からの転送ヘッダーは無視する() {
+        // 信頼するプロキシ以外からの転送ヘッダーは無視することを確認
+        let headers = forwarded_for("198.51.100.7");
+
+        assert_eq!(
+            trusted_client_ip(ip("203.0.113.1"), &headers, &[]),
+            ip("203.0.113.1")
+        );
+        assert_eq!(
+            trusted_client_ip(ip("203.0.113.1"), &headers, &[ip("10.0.0.1")]),
+            ip("203.0.113.1")
+        );
+    }
+
+    #[test]
+    fn test_右から順に信頼するプロキシを読み飛ばす() {
+        // X-Forwarded-Forを右から読み、信頼するプロキシを読み飛ばすことを確認
+        let proxies = [ip("10.0.0.1"), ip("10.0.0.2")];
+
+        // 先頭はクライアントが書いた値のため使わない
+        let headers = forwarded_for("198.51.100.7, 203.0.113.9, 10.0.0.2");
+        assert_eq!(
+            trusted_client_ip(ip("10.0.0.1"), &headers, &proxies),
+            ip("203.0.113.9")
+        );
+
+        // すべて信頼するプロキシ、またはヘッダーが無い場合は接続元
+        let headers = forwarded_for("10.0.0.2");
+        assert_eq!(
+            trusted_client_ip(ip("10.0.0.1"), &headers, &proxies),
+            ip("10.0.0.1")
+        );
+        assert_eq!(
+            trusted_client_ip(ip("10.0.0.1"), &HeaderMap::new(), &proxies),
+            ip("10.0.0.1")
+        );
+
+        // 解釈できない値があれば、それより左は使わない
+        let headers = forwarded_for("203.0.113.9, unknown, 10.0.0.2");
+        assert_eq!(
+            trusted_client_ip(ip("10.0.0.1"), &headers, &proxies),
+            ip("10.0.0.1")
+        );
+    }
+}
@@ -0,0 +1,478 @@
+// 保存した検索条件の管理と、新しく一致したスレッドの通知
+//
+// ジョブは検索条件ごとに last_run_at（前回の実行時刻）より後に作成されたスレッドを検索し、
+// 通知（と必要ならメール）を作成してから last_run_at を今回の実行時刻まで進める。
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    email::templates::{render_saved_search_alert, AlertThread, SavedSearchAlertContext},
+    error::AppError,
+    models::{saved_searches::SavedSearch, User},
+    utils::{db_error, email_outbox, thread_search},
+};
+
+/// 1ユーザーが保存できる検索条件の上限
+pub const MAX_SAVED_SEARCHES_PER_USER: i64 = 20;
+
+/// 1回の実行で処理する検索条件の最大件数
+const BATCH_SIZE: i64 = 100;
+
+/// 通知メールに載せるスレッドの最大件数
+const MAX_THREADS_PER_EMAIL: usize = 20;
+
+/// 検索条件を保存する
+///
+/// 上限に達している場合は400、同じ検索語を保存済みの場合は `SAVED_SEARCH_DUPLICATE` を返します。
+pub async fn create(
+    pool: &PgPool,
+    user_id: Uuid,
+    query: &str,
+    notify: bool,
+    email: bool,
+) -> Result<SavedSearch, AppError> {
+    let query = thread_search::normalize_query(query)
+        .ok_or_else(|| AppError::BadRequest("Query must not be blank".to_string()))?;
+
+    // 上限の確認と追加を1つの文で行い、同時リクエストで上限を超えないようにする
+    let search = sqlx::query_as::<_, SavedSearch>(
+        r#"
+        INSERT INTO saved_searches (user_id, query, notify, email)
+        SELECT $1, $2, $3, $4
+        WHERE (SELECT COUNT(*) FROM saved_searches WHERE user_id = $1) < $5
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(&query)
+    .bind(notify)
+    .bind(notify && email)
+    .bind(MAX_SAVED_SEARCHES_PER_USER)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error::map_db_error)?
+    .ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "You can save at most {} searches",
+            MAX_SAVED_SEARCHES_PER_USER
+        ))
+    })?;
+
+    Ok(search)
+}
+
+/// ユーザーが保存した検索条件を古い順に取得する
+pub async fn list(pool: &PgPool, user_id: Uuid) -> Result<Vec<SavedSearch>, AppError> {
+    let searches = sqlx::query_as::<_, SavedSearch>(
+        "SELECT * FROM saved_searches WHERE user_id = $1 ORDER BY created_at, id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(searches)
+}
+
+/// ユーザーが保存した検索条件を削除する（他のユーザーの検索条件はNotFound）
+pub async fn delete(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM saved_searches WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// 通知が有効な検索条件を実行し、作成した通知の件数を返す
+///
+/// `frontend_url` は通知メール内のスレッドのリンクに使います（`Config::frontend_url`）。
+pub async fn run_due(
+    pool: &PgPool,
+    frontend_url: &str,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM saved_searches
+        WHERE notify AND last_run_at < $1
+        ORDER BY last_run_at
+        LIMIT $2
+        "#,
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut notified = 0;
+    for id in ids {
+        notified += run_search(pool, frontend_url, id, now).await?;
+    }
+
+    Ok(notified)
+}
+
+/// 1件の検索条件を実行する
+///
+/// 通知の作成と last_run_at の更新は同じトランザクションで行うため、
+/// 途中で失敗した場合は次回に同じ範囲をやり直します。
+async fn run_search(
+    pool: &PgPool,
+    frontend_url: &str,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // 他のジョブが実行中の検索条件は読み飛ばす
+    let Some(search) = sqlx::query_as::<_, SavedSearch>(
+        "SELECT * FROM saved_searches WHERE id = $1 AND notify FOR UPDATE SKIP LOCKED",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(0);
+    };
+    if search.last_run_at >= now {
+        return Ok(0);
+    }
+
+    // 前回の実行時刻より後、今回の実行時刻以前に作成された他のユーザーのスレッド
+    // ブロックしているユーザーのスレッドは通知しない
+    let mut builder =
+        QueryBuilder::<Postgres>::new("SELECT t.id, t.title FROM threads t WHERE t.created_at > ");
+    builder
+        .push_bind(search.last_run_at)
+        .push(" AND t.created_at <= ")
+        .push_bind(now)
+        .push(" AND t.user_id <> ")
+        .push_bind(search.user_id)
+        .push(" AND NOT EXISTS (SELECT 1 FROM user_blocks b WHERE b.blocked_id = t.user_id AND b.blocker_id = ")
+        .push_bind(search.user_id)
+        .push(") AND ");
+    thread_search::push_match(&mut builder, &search.query);
+    builder.push(" ORDER BY t.created_at, t.id");
+    let matches = builder
+        .build_query_as::<(Uuid, String)>()
+        .fetch_all(&mut *tx)
+        .await?;
+
+    // 既に通知したスレッドは除く（一意制約で二重に通知しない）
+    let thread_ids: Vec<Uuid> = matches.iter().map(|(id, _)| *id).collect();
+    let notified = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO notifications (user_id, kind, thread_id, saved_search_id)
+        SELECT $1, 'saved_search_match', thread_id, $3
+        FROM UNNEST($2::uuid[]) AS thread_id
+        ON CONFLICT ON CONSTRAINT notifications_saved_search_thread_key DO NOTHING
+        RETURNING thread_id
+        "#,
+    )
+    .bind(search.user_id)
+    .bind(&thread_ids)
+    .bind(search.id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if search.email && !notified.is_empty() {
+        let threads: Vec<AlertThread> = matches
+            .into_iter()
+            .filter(|(id, _)| notified.contains(id))
+            .take(MAX_THREADS_PER_EMAIL)
+            .map(|(id, title)| AlertThread {
+                title,
+                url: format!("{}/threads/{}", frontend_url, id),
+            })
+            .collect();
+        queue_alert_email(&mut tx, &search, &threads).await?;
+    }
+
+    sqlx::query("UPDATE saved_searches SET last_run_at = $2 WHERE id = $1")
+        .bind(search.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(notified.len())
+}
+
+/// 通知メールをアウトボックスに追加する（メールアドレスを確認済みのユーザーのみ）
+async fn queue_alert_email(
+    tx: &mut Transaction<'_, Postgres>,
+    search: &SavedSearch,
+    threads: &[AlertThread],
+) -> Result<(), AppError> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(search.user_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    if !user.email_verified {
+        return Ok(());
+    }
+
+    let message = render_saved_search_alert(&SavedSearchAlertContext {
+        to: &user.email,
+        username: &user.username,
+        query: &search.query,
+        threads,
+    })?;
+    email_outbox::enqueue(&mut **tx, &message).await?;
+
+    Ok(())
+}
+
+/// 保存した検索条件を定期的に実行するジョブを起動する
+pub fn spawn_job(pool: PgPool, config: &Config) {
+    let interval = StdDuration::from_secs(config.saved_search_interval_seconds);
+    let frontend_url = config.frontend_url.clone();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            // 1バッチで処理しきれなかった分は次の実行で処理する
+            match run_due(&pool, &frontend_url, Utc::now()).await {
+                Ok(notified) if notified > 0 => {
+                    tracing::info!(notified, "Saved search notifications created");
+                }
+                Ok(_) => {}
+                Err(err) => tracing::error!("Saved search job failed: {:?}", err),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_thread, create_test_user};
+    use chrono::Duration;
+
+    const FRONTEND_URL: &str = "http://localhost:3000";
+
+    async fn create_thread_at(
+        pool: &PgPool,
+        user_id: Uuid,
+        title: &str,
+        created_at: DateTime<Utc>,
+    ) -> Uuid {
+        let id = create_test_thread(pool, user_id, title, "Content").await;
+        sqlx::query("UPDATE threads SET created_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(created_at)
+            .execute(pool)
+            .await
+            .unwrap();
+        id
+    }
+
+    async fn notified_thread_ids(pool: &PgPool, saved_search_id: Uuid) -> Vec<Uuid> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT thread_id FROM notifications WHERE saved_search_id = $1 ORDER BY created_at",
+        )
+        .bind(saved_search_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn set_last_run_at(pool: &PgPool, id: Uuid, last_run_at: DateTime<Utc>) {
+        sqlx::query("UPDATE saved_searches SET last_run_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(last_run_at)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_同じスレッドは二度通知しない(pool: PgPool) {
+        // 実行のたびにlast_run_atが進み、前回までに一致したスレッドは再び一致しないことを確認
+        let user = create_test_user(&pool, true).await;
+        let author = create_test_user(&pool, true).await;
+        let search = create(&pool, user.id, "Rust axum", true, false)
+            .await
+            .unwrap();
+        let start = Utc::now() - Duration::hours(2);
+        set_last_run_at(&pool, search.id, start).await;
+
+        let first = create_thread_at(
+            &pool,
+            author.id,
+            "rust and axum",
+            start + Duration::minutes(10),
+        )
+        .await;
+        create_thread_at(&pool, author.id, "only rust", start + Duration::minutes(20)).await;
+
+        let first_run = start + Duration::minutes(30);
+        assert_eq!(run_due(&pool, FRONTEND_URL, first_run).await.unwrap(), 1);
+        assert_eq!(notified_thread_ids(&pool, search.id).await, vec![first]);
+
+        let second = create_thread_at(
+            &pool,
+            author.id,
+            "Axum with RUST",
+            first_run + Duration::minutes(10),
+        )
+        .await;
+
+        // 同じ時刻で再実行しても何も起きない
+        assert_eq!(run_due(&pool, FRONTEND_URL, first_run).await.unwrap(), 0);
+
+        let second_run = first_run + Duration::minutes(30);
+        assert_eq!(run_due(&pool, FRONTEND_URL, second_run).await.unwrap(), 1);
+        assert_eq!(
+            notified_thread_ids(&pool, search.id).await,
+            vec![first, second]
+        );
+
+        // 実行時刻を過ぎた後も同じスレッドは一致しない
+        assert_eq!(
+            run_due(&pool, FRONTEND_URL, second_run + Duration::minutes(30))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(notified_thread_ids(&pool, search.id).await.len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn test_watermarkが巻き戻っても通知は重複しない(pool: PgPool) {
+        // 途中で失敗して同じ範囲をやり直した場合も、一意制約で同じスレッドを二度通知しないことを確認
+        let user = create_test_user(&pool, true).await;
+        let author = create_test_user(&pool, true).await;
+        let search = create(&pool, user.id, "axum", true, false).await.unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        set_last_run_at(&pool, search.id, start).await;
+        let thread = create_thread_at(&pool, author.id, "axum", start + Duration::minutes(1)).await;
+
+        let now = start + Duration::minutes(5);
+        assert_eq!(run_due(&pool, FRONTEND_URL, now).await.unwrap(), 1);
+        set_last_run_at(&pool, search.id, start).await;
+        assert_eq!(run_due(&pool, FRONTEND_URL, now).await.unwrap(), 0);
+
+        assert_eq!(notified_thread_ids(&pool, search.id).await, vec![thread]);
+    }
+
+    #[sqlx::test]
+    async fn test_通知が無効な検索条件と自分のスレッドは対象外(pool: PgPool) {
+        // 通知が無効な検索条件と自分のスレッドは通知の対象にならないことを確認
+        let user = create_test_user(&pool, true).await;
+        let author = create_test_user(&pool, true).await;
+        let silent = create(&pool, user.id, "axum", false, false).await.unwrap();
+        let search = create(&pool, user.id, "rust", true, false).await.unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        set_last_run_at(&pool, silent.id, start).await;
+        set_last_run_at(&pool, search.id, start).await;
+        create_thread_at(&pool, author.id, "rust axum", start + Duration::minutes(1)).await;
+        create_thread_at(&pool, user.id, "my rust", start + Duration::minutes(2)).await;
+
+        assert_eq!(
+            run_due(&pool, FRONTEND_URL, start + Duration::minutes(5))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(notified_thread_ids(&pool, silent.id).await.is_empty());
+        assert_eq!(notified_thread_ids(&pool, search.id).await.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_ブロックしているユーザーのスレッドは通知しない(
+        pool: PgPool,
+    ) {
+        // ブロックしているユーザーのスレッドは検索条件に一致しても通知しないことを確認
+        let user = create_test_user(&pool, true).await;
+        let author = create_test_user(&pool, true).await;
+        let blocked = create_test_user(&pool, true).await;
+        crate::utils::user_blocks::block(&pool, user.id, blocked.id)
+            .await
+            .unwrap();
+        let search = create(&pool, user.id, "rust", true, false).await.unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        set_last_run_at(&pool, search.id, start).await;
+        let visible =
+            create_thread_at(&pool, author.id, "rust tips", start + Duration::minutes(1)).await;
+        create_thread_at(&pool, blocked.id, "rust rant", start + Duration::minutes(2)).await;
+
+        assert_eq!(
+            run_due(&pool, FRONTEND_URL, start + Duration::minutes(5))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(notified_thread_ids(&pool, search.id).await, vec![visible]);
+    }
+
+    #[sqlx::test]
+    async fn test_メール通知が有効な場合は通知メールを追加する(pool: PgPool) {
+        // メール通知が有効な検索条件では通知メールがアウトボックスに追加されることを確認
+        let user = create_test_user(&pool, true).await;
+        let author = create_test_user(&pool, true).await;
+        let search = create(&pool, user.id, "axum", true, true).await.unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        set_last_run_at(&pool, search.id, start).await;
+        create_thread_at(&pool, author.id, "Axum 0.8", start + Duration::minutes(1)).await;
+
+        run_due(&pool, FRONTEND_URL, start + Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let (subject, text_body) = sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT subject, text_body FROM email_outbox WHERE to_email = $1",
+        )
+        .bind(&user.email)
+        .fetch_one(&pool)
+        .await
+        .expect("Alert email should be queued");
+        assert_eq!(subject, "「axum」に一致する新しいスレッド");
+        let text_body = text_body.unwrap();
+        assert!(text_body.contains("Axum 0.8"));
+        assert!(text_body.contains("http://localhost:3000/threads/"));
+    }
+
+    #[sqlx::test]
+    async fn test_保存できる検索条件には上限がある(pool: PgPool) {
+        // 上限を超える検索条件は保存できないことを確認
+        let user = create_test_user(&pool, true).await;
+        for i in 0..MAX_SAVED_SEARCHES_PER_USER {
+            create(&pool, user.id, &format!("query {}", i), false, false)
+                .await
+                .unwrap();
+        }
+
+        let result = create(&pool, user.id, "one more", false, false).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_同じ検索語は保存できない(pool: PgPool) {
+        // 空白と大文字・小文字を正規化してから比較し、同じ検索語は保存できないことを確認
+        let user = create_test_user(&pool, true).await;
+        create(&pool, user.id, "rust axum", false, false)
+            .await
+            .unwrap();
+
+        let result = create(&pool, user.id, "  Rust   AXUM ", true, false).await;
+        assert!(matches!(
+            result,
+            Err(AppError::UniqueViolation {
+                code: "SAVED_SEARCH_DUPLICATE",
+                ..
+            })
+        ));
+    }
+}
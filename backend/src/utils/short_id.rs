@@ -0,0 +1,232 @@
+// スレッド・コメントのパーマリンク用の短い公開ID
+use std::future::Future;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    utils::db_error::{self, ConstraintViolation},
+};
+
+/// base62で使用する文字（データベースの generate_short_id() と同じ並び）
+const BASE62_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// u64をbase62で表現した場合の最大長
+pub const SHORT_ID_MAX_LENGTH: usize = 11;
+
+/// 短いIDの衝突時に再試行する最大回数
+pub const MAX_SHORT_ID_ATTEMPTS: usize = 5;
+
+/// 数値をbase62文字列に変換
+pub fn encode_base62(mut value: u64) -> String {
+    let mut chars = Vec::new();
+    loop {
+        chars.push(BASE62_ALPHABET[(value % 62) as usize]);
+        value /= 62;
+        if value == 0 {
+            break;
+        }
+    }
+    chars.reverse();
+    String::from_utf8(chars).expect("base62 alphabet is ASCII")
+}
+
+/// 文字列が短いIDの形式かどうかを判定
+pub fn is_short_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= SHORT_ID_MAX_LENGTH
+        && value.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// UUIDまたは短いIDからスレッドのIDを取得
+pub async fn resolve_thread_id(pool: &PgPool, id_or_short_id: &str) -> Result<Uuid, AppError> {
+    if let Ok(id) = Uuid::parse_str(id_or_short_id) {
+        return Ok(id);
+    }
+    if !is_short_id(id_or_short_id) {
+        return Err(AppError::NotFound);
+    }
+
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM threads WHERE short_id = $1")
+        .bind(id_or_short_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// UUIDまたは短いIDからコメントのIDを取得
+pub async fn resolve_comment_id(pool: &PgPool, id_or_short_id: &str) -> Result<Uuid, AppError> {
+    if let Ok(id) = Uuid::parse_str(id_or_short_id) {
+        return Ok(id);
+    }
+    if !is_short_id(id_or_short_id) {
+        return Err(AppError::NotFound);
+    }
+
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM comments WHERE short_id = $1")
+        .bind(id_or_short_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 短いIDの一意制約違反かどうかを判定
+fn is_short_id_conflict(err: &sqlx::Error) -> bool {
+    matches!(
+        db_error::constraint_violation(err),
+        Some(ConstraintViolation::Unique(constraint)) if constraint.ends_with("short_id_key")
+    )
+}
+
+/// 短いIDを生成して挿入処理を実行し、衝突した場合は新しいIDで再試行する
+///
+/// IDの生成には `TokenGenerator::short_id` を渡します。
+pub async fn insert_with_short_id<T, G, F, Fut>(
+    mut generate: G,
+    mut insert: F,
+) -> Result<T, sqlx::Error>
+where
+    G: FnMut() -> String,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match insert(generate()).await {
+            Err(err) if attempt < MAX_SHORT_ID_ATTEMPTS && is_short_id_conflict(&err) => {
+                tracing::warn!("Short ID collision, retrying (attempt {})", attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_comment, create_test_thread, create_test_user};
+
+    #[test]
+    fn test_base62に変換される() {
+        // 数値がbase62の文字列に変換されることを確認
+        assert_eq!(encode_base62(0), "0");
+        assert_eq!(encode_base62(61), "z");
+        assert_eq!(encode_base62(62), "10");
+        assert_eq!(encode_base62(u64::MAX), "LygHa16AHYF");
+        assert_eq!(encode_base62(u64::MAX).len(), SHORT_ID_MAX_LENGTH);
+    }
+
+    #[test]
+    fn test_短いidの形式が判定される() {
+        // 短いIDの形式を正しく判定することを確認
+        assert!(is_short_id("LygHa16AHYF"));
+        assert!(!is_short_id(""));
+        assert!(!is_short_id("LygHa16AHYF0"));
+        assert!(!is_short_id("abc-def"));
+        assert!(!is_short_id(&Uuid::new_v4().to_string()));
+    }
+
+    async fn insert_thread(
+        pool: &PgPool,
+        user_id: Uuid,
+        short_id: String,
+    ) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(
+            "INSERT INTO threads (user_id, title, short_id) VALUES ($1, 'Thread', $2) RETURNING short_id",
+        )
+        .bind(user_id)
+        .bind(short_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    #[sqlx::test]
+    async fn test_衝突した場合は新しいidで再試行される(pool: PgPool) {
+        // 既存と同じIDを返す生成器を使い、2回目の生成結果で挿入されることを確認
+        let user = create_test_user(&pool, true).await;
+        insert_thread(&pool, user.id, "taken".to_string())
+            .await
+            .unwrap();
+
+        let mut candidates = vec!["fresh".to_string(), "taken".to_string()];
+        let short_id = insert_with_short_id(
+            || candidates.pop().unwrap(),
+            |short_id| insert_thread(&pool, user.id, short_id),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(short_id, "fresh");
+        assert!(candidates.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_衝突が続く場合はエラーになる(pool: PgPool) {
+        // 再試行回数を超えて衝突した場合は一意制約違反を返すことを確認
+        let user = create_test_user(&pool, true).await;
+        insert_thread(&pool, user.id, "taken".to_string())
+            .await
+            .unwrap();
+
+        let mut attempts = 0;
+        let result = insert_with_short_id(
+            || {
+                attempts += 1;
+                "taken".to_string()
+            },
+            |short_id| insert_thread(&pool, user.id, short_id),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, MAX_SHORT_ID_ATTEMPTS);
+    }
+
+    #[sqlx::test]
+    async fn test_uuidと短いidのどちらでも解決できる(pool: PgPool) {
+        // スレッドをUUIDと短いIDのどちらでも解決できることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Thread", "Content").await;
+        let comment_id = create_test_comment(&pool, user.id, thread_id, "Comment", None).await;
+
+        let thread_short_id: String =
+            sqlx::query_scalar("SELECT short_id FROM threads WHERE id = $1")
+                .bind(thread_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let comment_short_id: String =
+            sqlx::query_scalar("SELECT short_id FROM comments WHERE id = $1")
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            resolve_thread_id(&pool, &thread_id.to_string())
+                .await
+                .unwrap(),
+            thread_id
+        );
+        assert_eq!(
+            resolve_thread_id(&pool, &thread_short_id).await.unwrap(),
+            thread_id
+        );
+        assert_eq!(
+            resolve_comment_id(&pool, &comment_short_id).await.unwrap(),
+            comment_id
+        );
+
+        // 存在しない短いIDや不正な形式はNotFound
+        assert!(matches!(
+            resolve_thread_id(&pool, "unknown").await,
+            Err(AppError::NotFound)
+        ));
+        assert!(matches!(
+            resolve_thread_id(&pool, "not-a-valid-id").await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+// 上限に近い投稿への警告
+//
+// 上限を超えると投稿はエラーになりますが、上限の手前では何も知らされないため、
+// 次の編集や返信で初めて上限に気付くことになります。
+// 作成・更新は成功させたまま、レスポンスの `warnings` で上限が近いことを知らせます。
+use crate::{
+    models::{comments, common::ApiWarning},
+    validations::content,
+};
+
+/// スレッドのタイトルが上限に近い
+pub const TITLE_NEAR_LIMIT: &str = "TITLE_NEAR_LIMIT";
+/// スレッド・コメントの本文が上限に近い
+pub const CONTENT_NEAR_LIMIT: &str = "CONTENT_NEAR_LIMIT";
+/// これ以上返信できない深さのコメント
+pub const MAX_DEPTH_REACHED: &str = "MAX_DEPTH_REACHED";
+
+/// 文字数が上限の `warning_percent` % 以上か（validate と同じく文字単位で数える）
+fn is_near_limit(value: &str, max_length: usize, warning_percent: u8) -> bool {
+    value.chars().count() * 100 >= max_length * usize::from(warning_percent)
+}
+
+fn length_warning(
+    code: &str,
+    field: &str,
+    value: &str,
+    max_length: usize,
+    warning_percent: u8,
+) -> Option<ApiWarning> {
+    is_near_limit(value, max_length, warning_percent).then(|| ApiWarning {
+        code: code.to_string(),
+        message: format!(
+            "{} is {} of {} characters",
+            field,
+            value.chars().count(),
+            max_length
+        ),
+    })
+}
+
+/// スレッドの作成・更新時の警告（指定されたフィールドのみ確認する）
+pub fn thread_warnings(
+    title: Option<&str>,
+    content: Option<&str>,
+    warning_percent: u8,
+) -> Vec<ApiWarning> {
+    let title = title.and_then(|title| {
+        length_warning(
+            TITLE_NEAR_LIMIT,
+            "Title",
+            title,
+            content::THREAD_TITLE_MAX_LENGTH,
+            warning_percent,
+        )
+    });
+    let content = content.and_then(|content| {
+        length_warning(
+            CONTENT_NEAR_LIMIT,
+            "Content",
+            content,
+            content::THREAD_CONTENT_MAX_LENGTH,
+            warning_percent,
+        )
+    });
+
+    title.into_iter().chain(content).collect()
+}
+
+/// コメントの作成・更新時の警告
+///
+/// `depth` は作成したコメントの深さ（トップレベルが 1）。更新時は深さが変わらないため `None` を渡します。
+pub fn comment_warnings(content: &str, depth: Option<i32>, warning_percent: u8) -> Vec<ApiWarning> {
+    let content = length_warning(
+        CONTENT_NEAR_LIMIT,
+        "Content",
+        content,
+        content::COMMENT_CONTENT_MAX_LENGTH,
+        warning_percent,
+    );
+    let depth = depth
+        .filter(|depth| *depth >= comments::MAX_DEPTH)
+        .map(|depth| ApiWarning {
+            code: MAX_DEPTH_REACHED.to_string(),
+            message: format!(
+                "Comment is at the maximum nesting depth ({} levels) and cannot be replied to",
+                depth
+            ),
+        });
+
+    content.into_iter().chain(depth).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        comments::{CreateCommentRequest, UpdateCommentRequest},
+        threads::CreateThreadRequest,
+    };
+    use validator::Validate;
+
+    fn codes(warnings: &[ApiWarning]) -> Vec<&str> {
+        warnings.iter().map(|w| w.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_上限の指定割合ちょうどから警告する() {
+        // 上限の指定割合ちょうどから警告することを確認
+        // 300文字の90%は270文字
+        assert!(thread_warnings(Some(&"あ".repeat(269)), None, 90).is_empty());
+        assert_eq!(
+            codes(&thread_warnings(Some(&"あ".repeat(270)), None, 90)),
+            [TITLE_NEAR_LIMIT]
+        );
+        assert_eq!(
+            codes(&thread_warnings(Some(&"a".repeat(300)), None, 90)),
+            [TITLE_NEAR_LIMIT]
+        );
+
+        // 40,000文字の90%は36,000文字
+        assert!(thread_warnings(None, Some(&"a".repeat(35_999)), 90).is_empty());
+        let warnings = thread_warnings(Some("Title"), Some(&"a".repeat(36_000)), 90);
+        assert_eq!(codes(&warnings), [CONTENT_NEAR_LIMIT]);
+        assert_eq!(warnings[0].message, "Content is 36000 of 40000 characters");
+
+        // 10,000文字の80%は8,000文字
+        assert!(comment_warnings(&"a".repeat(7_999), None, 80).is_empty());
+        assert_eq!(
+            codes(&comment_warnings(&"a".repeat(8_000), None, 80)),
+            [CONTENT_NEAR_LIMIT]
+        );
+    }
+
+    #[test]
+    fn test_返信できない深さのコメントに警告する() {
+        // 返信できない深さのコメントに警告することを確認
+        assert!(comment_warnings("Reply", Some(1), 90).is_empty());
+        assert!(comment_warnings("Reply", Some(comments::MAX_DEPTH - 1), 90).is_empty());
+        assert_eq!(
+            codes(&comment_warnings("Reply", Some(comments::MAX_DEPTH), 90)),
+            [MAX_DEPTH_REACHED]
+        );
+        assert_eq!(
+            codes(&comment_warnings(
+                &"a".repeat(content::COMMENT_CONTENT_MAX_LENGTH),
+                Some(comments::MAX_DEPTH),
+                90
+            )),
+            [CONTENT_NEAR_LIMIT, MAX_DEPTH_REACHED]
+        );
+    }
+
+    #[test]
+    fn test_上限の定数がリクエストの検証と一致する() {
+        // 警告に使う上限の定数がリクエストの検証と一致することを確認
+        let thread = |title: String, content: String| CreateThreadRequest {
+            title,
+            content: Some(content),
+            client_nonce: None,
+        };
+        assert!(thread(
+            "a".repeat(content::THREAD_TITLE_MAX_LENGTH),
+            "a".to_string()
+        )
+        .validate()
+        .is_ok());
+        assert!(thread(
+            "a".repeat(content::THREAD_TITLE_MAX_LENGTH + 1),
+            "a".to_string()
+        )
+        .validate()
+        .is_err());
+        assert!(thread(
+            "a".to_string(),
+            "a".repeat(content::THREAD_CONTENT_MAX_LENGTH)
+        )
+        .validate()
+        .is_ok());
+        assert!(thread(
+            "a".to_string(),
+            "a".repeat(content::THREAD_CONTENT_MAX_LENGTH + 1)
+        )
+        .validate()
+        .is_err());
+
+        let comment = |content: String| CreateCommentRequest {
+            content,
+            parent_id: None,
+            client_nonce: None,
+        };
+        assert!(comment("a".repeat(content::COMMENT_CONTENT_MAX_LENGTH))
+            .validate()
+            .is_ok());
+        assert!(comment("a".repeat(content::COMMENT_CONTENT_MAX_LENGTH + 1))
+            .validate()
+            .is_err());
+        assert!(UpdateCommentRequest {
+            content: "a".repeat(content::COMMENT_CONTENT_MAX_LENGTH + 1)
+        }
+        .validate()
+        .is_err());
+    }
+}
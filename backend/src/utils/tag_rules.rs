@@ -0,0 +1,220 @@
+// スレッド作成時の自動タグ付けルールの取得・キャッシュ・照合
+use std::sync::Arc;
+
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::tag_rules::{TagRule, TagRuleField},
+    utils::cache::Cache,
+};
+
+const SELECT_COLUMNS: &str = r#"
+    SELECT
+        r.id, r.pattern, r.match_field, r.tag_id,
+        t.slug AS tag_slug, t.name AS tag_name,
+        r.enabled, r.created_by, r.created_at, r.updated_at
+    FROM tag_rules r
+    JOIN tags t ON t.id = r.tag_id
+"#;
+
+/// すべてのルールを作成順に取得する（タグの上限に達した場合は先に作成されたルールが優先される）
+pub async fn list<'e, E>(executor: E, enabled_only: bool) -> Result<Vec<TagRule>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let rules = sqlx::query_as::<_, TagRule>(&format!(
+        "{} WHERE $1 = false OR r.enabled ORDER BY r.created_at, r.id",
+        SELECT_COLUMNS
+    ))
+    .bind(enabled_only)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rules)
+}
+
+pub async fn find<'e, E>(executor: E, id: Uuid) -> Result<Option<TagRule>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let rule = sqlx::query_as::<_, TagRule>(&format!("{} WHERE r.id = $1", SELECT_COLUMNS))
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+
+    Ok(rule)
+}
+
+/// タイトル・本文に一致した有効なルールを、タグの重複を除いて最大 `cap` 件まで返す
+///
+/// 照合は大文字・小文字を区別しない部分一致です。同じタグのルールが複数一致した場合は先のルールを返します。
+pub fn matching_rules<'a>(
+    rules: &'a [TagRule],
+    title: &str,
+    content: Option<&str>,
+    cap: usize,
+) -> Vec<&'a TagRule> {
+    let title = title.to_lowercase();
+    let content = content.map(str::to_lowercase);
+
+    let mut matched: Vec<&TagRule> = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        if matched.len() >= cap {
+            break;
+        }
+        if matched.iter().any(|m| m.tag_id == rule.tag_id) {
+            continue;
+        }
+
+        let target = match TagRuleField::parse(&rule.match_field) {
+            Some(TagRuleField::Title) => Some(title.as_str()),
+            Some(TagRuleField::Content) => content.as_deref(),
+            None => None,
+        };
+        if target.is_some_and(|target| target.contains(&rule.pattern.to_lowercase())) {
+            matched.push(rule);
+        }
+    }
+    matched
+}
+
+/// 有効なルールのメモリ上のキャッシュ
+///
+/// 最初の参照時に読み込み、管理者がルールを変更したら `invalidate` で次の参照時に読み込み直します。
+/// 同時に参照された場合も読み込みは1回だけ行います。
+/// キャッシュはプロセスごとに持つため、他のプロセスでの変更は再起動まで反映されません。
+pub struct TagRuleCache {
+    // Noneの場合は読み込まず、最初に渡されたルールを使う（テスト用）
+    pool: Option<PgPool>,
+    cached: Cache<(), Arc<Vec<TagRule>>>,
+}
+
+impl TagRuleCache {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool: Some(pool),
+            cached: Cache::new(1, None),
+        }
+    }
+
+    /// データベースを使わずに固定のルールで照合する
+    pub fn with_rules(rules: Vec<TagRule>) -> Self {
+        let cached = Cache::new(1, None);
+        cached.insert((), Arc::new(rules));
+        Self { pool: None, cached }
+    }
+
+    /// キャッシュ済みのルールを返す（未読み込みの場合は読み込む）
+    pub async fn rules(&self) -> Result<Arc<Vec<TagRule>>, AppError> {
+        let Some(pool) = &self.pool else {
+            return Ok(self.cached.get(&()).unwrap_or_default());
+        };
+
+        // 読み込み中にルールが変更された場合は古い内容をキャッシュしない
+        self.cached
+            .get_or_compute((), || async {
+                Ok::<_, AppError>(Arc::new(list(pool, true).await?))
+            })
+            .await
+    }
+
+    /// ルールの変更後に呼び、次の参照時に読み込み直す
+    pub fn invalidate(&self) {
+        self.cached.invalidate(&());
+    }
+}
+
+impl Default for TagRuleCache {
+    fn default() -> Self {
+        Self::with_rules(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn matching_tag_ids(
+        rules: &[TagRule],
+        title: &str,
+        content: Option<&str>,
+        cap: usize,
+    ) -> Vec<Uuid> {
+        matching_rules(rules, title, content, cap)
+            .into_iter()
+            .map(|rule| rule.tag_id)
+            .collect()
+    }
+
+    fn rule(pattern: &str, field: TagRuleField, tag_id: Uuid) -> TagRule {
+        let now = Utc::now();
+        TagRule {
+            id: Uuid::new_v4(),
+            pattern: pattern.to_string(),
+            match_field: field.as_str().to_string(),
+            tag_id,
+            tag_slug: "tag".to_string(),
+            tag_name: "Tag".to_string(),
+            enabled: true,
+            created_by: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_大文字小文字を区別せず部分一致でタグを選ぶ() {
+        // 大文字・小文字を区別せず部分一致でタグを選ぶことを確認
+        let rust = Uuid::new_v4();
+        let go = Uuid::new_v4();
+        let rules = vec![
+            rule("rust", TagRuleField::Title, rust),
+            rule("GOLANG", TagRuleField::Content, go),
+        ];
+
+        assert_eq!(
+            matching_tag_ids(&rules, "Learning RUST", Some("golang too"), 5),
+            vec![rust, go]
+        );
+        // 照合する項目が違えば一致しない
+        assert!(matching_tag_ids(&rules, "golang", Some("rust"), 5).is_empty());
+        // 本文の無いスレッドは本文のルールに一致しない
+        assert_eq!(matching_tag_ids(&rules, "rust", None, 5), vec![rust]);
+    }
+
+    #[test]
+    fn test_無効なルールと重複するタグは除く() {
+        // 無効なルールと重複するタグは除かれることを確認
+        let rust = Uuid::new_v4();
+        let mut disabled = rule("cargo", TagRuleField::Title, Uuid::new_v4());
+        disabled.enabled = false;
+        let rules = vec![
+            rule("rust", TagRuleField::Title, rust),
+            rule("rust", TagRuleField::Content, rust),
+            disabled,
+        ];
+
+        assert_eq!(
+            matching_tag_ids(&rules, "rust and cargo", Some("rust"), 5),
+            vec![rust]
+        );
+    }
+
+    #[test]
+    fn test_上限を超えるタグは先のルールを優先する() {
+        // 上限を超える場合は先のルールのタグが優先されることを確認
+        let tag_ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let rules: Vec<TagRule> = tag_ids
+            .iter()
+            .map(|id| rule("日本語", TagRuleField::Title, *id))
+            .collect();
+
+        assert_eq!(
+            matching_tag_ids(&rules, "日本語の質問", None, 2),
+            tag_ids[..2].to_vec()
+        );
+    }
+}
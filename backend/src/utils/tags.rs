@@ -0,0 +1,134 @@
+// タグ名からのスラッグ生成と、旧スラッグの転送先の記録
+use std::fmt::Write;
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// スラッグの最大文字数（tags.slug の列長）
+pub const MAX_SLUG_CHARS: usize = 64;
+
+/// 1つのスレッドに付けられるタグの上限
+pub const MAX_TAGS_PER_THREAD: usize = 5;
+
+/// 旧スラッグの転送理由
+pub mod redirect_reasons {
+    pub const RENAME: &str = "rename";
+    pub const MERGE: &str = "merge";
+}
+
+/// タグ名からスラッグを生成する
+///
+/// 英字は小文字にし、空白・`-`・`_` の連続は1つの `-` にまとめ、それ以外の記号は取り除きます。
+/// 日本語などの英字以外の文字はそのまま残します。
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_separator = false;
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if pending_separator && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_separator = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            pending_separator = true;
+        }
+    }
+
+    slug.chars()
+        .take(MAX_SLUG_CHARS)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string()
+}
+
+/// スラッグをURLのパスに埋め込めるようにパーセントエンコードする
+pub fn encode_slug(slug: &str) -> String {
+    let mut encoded = String::with_capacity(slug.len());
+    for byte in slug.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+/// タグが付いたスレッド数を数える
+pub async fn thread_count<'e, E>(executor: E, tag_id: Uuid) -> Result<i64, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM thread_tags WHERE tag_id = $1")
+        .bind(tag_id)
+        .fetch_one(executor)
+        .await?;
+
+    Ok(count)
+}
+
+/// 旧スラッグから現在のタグへの転送を記録する（同じ旧スラッグがあれば上書き）
+pub async fn record_redirect<'e, E>(
+    executor: E,
+    old_slug: &str,
+    tag_id: Uuid,
+    reason: &str,
+    created_by: Uuid,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO tag_slug_redirects (old_slug, tag_id, reason, created_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (old_slug) DO UPDATE
+        SET tag_id = EXCLUDED.tag_id,
+            reason = EXCLUDED.reason,
+            created_by = EXCLUDED.created_by,
+            created_at = NOW()
+        "#,
+    )
+    .bind(old_slug)
+    .bind(tag_id)
+    .bind(reason)
+    .bind(created_by)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_タグ名からスラッグを生成する() {
+        // タグ名からスラッグが生成されることを確認
+        assert_eq!(slugify("Rust"), "rust");
+        assert_eq!(slugify("  Web  Development "), "web-development");
+        assert_eq!(slugify("C++ / Go_lang"), "c-go-lang");
+        assert_eq!(slugify("プログラミング 入門"), "プログラミング-入門");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn test_スラッグは最大文字数で切り詰める() {
+        // スラッグは最大文字数で切り詰められることを確認
+        let slug = slugify(&"a ".repeat(100));
+        assert!(slug.chars().count() <= MAX_SLUG_CHARS);
+        assert!(!slug.ends_with('-'));
+    }
+
+    #[test]
+    fn test_英数字以外はパーセントエンコードする() {
+        // 英数字以外はパーセントエンコードされることを確認
+        assert_eq!(encode_slug("web-dev"), "web-dev");
+        assert_eq!(encode_slug("日本"), "%E6%97%A5%E6%9C%AC");
+    }
+}
@@ -0,0 +1,34 @@
+// スレッドの最終アクティビティ日時（threads.last_activity_at）の再計算
+//
+// コメント作成時は作成と同じ文で新しい日時に進めるため、ここではコメント削除時の再計算のみを扱う
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// スレッドの作成日時と残っているコメントの最新の作成日時から、最終アクティビティ日時を計算し直す
+///
+/// 削除されたコメントが最新だった場合に、日時を前のコメント（またはスレッドの作成日時）へ戻すために使います。
+pub async fn recompute<'e, E>(executor: E, thread_ids: &[Uuid]) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        UPDATE threads t
+        SET last_activity_at = GREATEST(
+            t.created_at,
+            COALESCE(
+                (SELECT MAX(c.created_at) FROM comments c WHERE c.thread_id = t.id),
+                t.created_at
+            )
+        )
+        WHERE t.id = ANY($1)
+        "#,
+    )
+    .bind(thread_ids)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
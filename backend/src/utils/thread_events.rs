@@ -0,0 +1,115 @@
+// スレッドごとのライブイベント（Server-Sent Events）
+//
+// 閲覧中のクライアントは `GET /api/threads/{id}/events` でスレッドのイベントを購読する。
+// イベントは購読中のクライアントに届けるだけで保存しないため、購読前のイベントや、
+// 受信が追いつかずに CHANNEL_CAPACITY を超えたイベントは届かない。
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::response::sse::Event;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// 1つのスレッドで受信されるまで保持するイベントの数（超えた分は古いものから捨てる）
+pub const CHANNEL_CAPACITY: usize = 16;
+
+/// スレッドのイベント
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadEvent {
+    /// SSE の `event`（`presence_changed` など）
+    pub kind: &'static str,
+    /// SSE の `data` に JSON で入れる内容
+    pub data: Value,
+}
+
+impl ThreadEvent {
+    pub fn to_sse(&self) -> Event {
+        Event::default()
+            .event(self.kind)
+            .data(self.data.to_string())
+    }
+}
+
+/// スレッドごとのイベントの配信先
+#[derive(Default)]
+pub struct ThreadEvents {
+    channels: Mutex<HashMap<Uuid, broadcast::Sender<ThreadEvent>>>,
+}
+
+impl ThreadEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// スレッドのイベントを購読する
+    pub fn subscribe(&self, thread_id: Uuid) -> broadcast::Receiver<ThreadEvent> {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(thread_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// 購読中のクライアントにイベントを送り、届けた購読の数を返す
+    ///
+    /// 購読がすべて終わったスレッドのチャンネルはここで片付けます。
+    pub fn publish(&self, thread_id: Uuid, event: ThreadEvent) -> usize {
+        let mut channels = self.channels.lock().unwrap();
+        let Some(sender) = channels.get(&thread_id) else {
+            return 0;
+        };
+        match sender.send(event) {
+            Ok(receivers) => receivers,
+            Err(_) => {
+                channels.remove(&thread_id);
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(viewer_count: usize) -> ThreadEvent {
+        ThreadEvent {
+            kind: "presence_changed",
+            data: json!({ "viewer_count": viewer_count }),
+        }
+    }
+
+    #[test]
+    fn test_購読中のクライアントにだけ届く() {
+        // 購読中のクライアントにだけ届くことを確認
+        let events = ThreadEvents::new();
+        let thread = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let mut first = events.subscribe(thread);
+        let mut second = events.subscribe(thread);
+        let mut elsewhere = events.subscribe(other);
+
+        assert_eq!(events.publish(thread, event(2)), 2);
+
+        assert_eq!(first.try_recv().unwrap(), event(2));
+        assert_eq!(second.try_recv().unwrap(), event(2));
+        assert!(elsewhere.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_購読が終わったスレッドのチャンネルは片付ける() {
+        // 購読が終わったスレッドのチャンネルは片付けることを確認
+        let events = ThreadEvents::new();
+        let thread = Uuid::new_v4();
+
+        // 購読の無いスレッドには送らない
+        assert_eq!(events.publish(thread, event(1)), 0);
+        assert!(events.channels.lock().unwrap().is_empty());
+
+        drop(events.subscribe(thread));
+        assert_eq!(events.publish(thread, event(1)), 0);
+        assert!(events.channels.lock().unwrap().is_empty());
+    }
+}
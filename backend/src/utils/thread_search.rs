@@ -0,0 +1,259 @@
+// スレッドの全文検索の条件を組み立てる
+//
+// 検索語は空白で区切り、すべての語をタイトルまたは本文に含むスレッドに一致させる。
+// 保存した検索条件の通知など、スレッドを検索する処理はここで条件を組み立てる。
+//
+// 検索対象は threads.search_vector 列で、新しいスレッドはトリガーで設定される。
+// 列を追加する前からあるスレッドは `reindex-search` コマンド（`backfill`）で埋めるまで NULL のままで、
+// その間は検索に一致しないだけでエラーにはならない。
+use sqlx::{Executor, PgPool, Postgres, QueryBuilder};
+
+/// 検索の対象（threads テーブルの別名 `t` に対する式）
+const SEARCH_DOCUMENT: &str = "t.search_vector";
+
+/// search_vector に設定する値（threads_search_vector_update トリガーと同じ式にする）
+const SEARCH_VECTOR_SQL: &str = "to_tsvector('simple', title || ' ' || COALESCE(content, ''))";
+
+/// バックフィルで1回に更新するスレッド数の既定値
+pub const BACKFILL_BATCH_SIZE: i64 = 1000;
+
+/// トランザクションの外で実行するインデックスの作成・削除（1ファイル1文）
+const CONCURRENT_MIGRATIONS: [&str; 2] = [
+    include_str!("../../migrations/concurrent/20250621000001_index_thread_search_vector.sql"),
+    include_str!(
+        "../../migrations/concurrent/20250621000002_drop_thread_search_expression_index.sql"
+    ),
+];
+
+/// 検索用のGINインデックスの名前
+const SEARCH_INDEX: &str = "idx_threads_search_vector";
+
+/// 検索語を正規化する（前後と連続する空白をまとめ、小文字にする）。語が無ければNone
+pub fn normalize_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// 検索語に一致する条件を追加する（`WHERE` や `AND` の後に続ける）
+pub fn push_match(builder: &mut QueryBuilder<'_, Postgres>, query: &str) {
+    builder
+        .push(SEARCH_DOCUMENT)
+        .push(" @@ plainto_tsquery('simple', ")
+        .push_bind(query.to_string())
+        .push(")");
+}
+
+/// search_vector が未設定のスレッド数
+pub async fn pending_count(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM threads WHERE search_vector IS NULL")
+        .fetch_one(pool)
+        .await
+}
+
+/// search_vector が未設定のスレッドを `batch_size` 件ずつ埋める
+///
+/// バッチごとにコミットするため、途中で中断しても次回は未設定のスレッドから再開します。
+/// `on_progress` にはバッチを終えるたびにそれまでに更新した件数を渡します。
+pub async fn backfill(
+    pool: &PgPool,
+    batch_size: i64,
+    mut on_progress: impl FnMut(u64),
+) -> Result<u64, sqlx::Error> {
+    let mut processed = 0;
+    loop {
+        // 並行して編集されているスレッドは待たずに飛ばす（編集時にトリガーで設定される）
+        let updated = sqlx::query(&format!(
+            r#"
+            UPDATE threads SET search_vector = {}
+            WHERE id IN (
+                SELECT id FROM threads
+                WHERE search_vector IS NULL
+                ORDER BY id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+            SEARCH_VECTOR_SQL
+        ))
+        .bind(batch_size)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(processed);
+        }
+        processed += updated;
+        on_progress(processed);
+    }
+}
+
+/// 検索用のインデックスをテーブルをロックせずに作成する
+///
+/// 前回の作成が途中で失敗して無効なインデックスが残っている場合は、削除してから作り直します。
+pub async fn create_index_concurrently(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let invalid = sqlx::query_scalar::<_, bool>(
+        "SELECT NOT indisvalid FROM pg_index WHERE indexrelid = to_regclass($1)",
+    )
+    .bind(SEARCH_INDEX)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(false);
+    if invalid {
+        pool.execute(format!("DROP INDEX CONCURRENTLY IF EXISTS {}", SEARCH_INDEX).as_str())
+            .await?;
+    }
+
+    // 単純クエリとして1文ずつ送り、暗黙のトランザクションに含めない
+    for sql in CONCURRENT_MIGRATIONS {
+        pool.execute(sql).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_thread, create_test_user};
+    use uuid::Uuid;
+
+    // 列を追加する前からあるスレッドと同じ状態にする
+    async fn clear_search_vectors(pool: &PgPool) {
+        sqlx::query("UPDATE threads SET search_vector = NULL")
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn search(pool: &PgPool, query: &str) -> Vec<Uuid> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT t.id FROM threads t WHERE ");
+        push_match(&mut builder, query);
+        builder
+            .build_query_scalar::<Uuid>()
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_検索語の空白をまとめて小文字にする() {
+        // 検索語の空白をまとめて小文字にすることを確認
+        assert_eq!(
+            normalize_query("  Rust   AXUM\t"),
+            Some("rust axum".to_string())
+        );
+        assert_eq!(normalize_query("   "), None);
+    }
+
+    #[test]
+    fn test_検索語はバインドパラメータとして渡す() {
+        // 検索語はSQLに埋め込まずバインドパラメータとして渡すことを確認
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT t.id FROM threads t WHERE ");
+        push_match(&mut builder, "rust'; DROP TABLE threads; --");
+
+        let sql = builder.sql();
+        assert!(sql.ends_with("@@ plainto_tsquery('simple', $1)"));
+        assert!(!sql.contains("DROP TABLE"));
+    }
+
+    #[sqlx::test]
+    async fn test_新しいスレッドはバックフィルを待たずに検索できる(
+        pool: PgPool,
+    ) {
+        // 新しいスレッドはバックフィルを待たずに検索できることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Rust AMA", "axum").await;
+
+        assert_eq!(search(&pool, "axum").await, vec![thread_id]);
+        assert_eq!(pending_count(&pool).await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_未設定のスレッドはエラーにならず一致しないだけ(
+        pool: PgPool,
+    ) {
+        // 検索用のベクトルが未設定のスレッドはエラーにならず、一致しないだけであることを確認
+        let user = create_test_user(&pool, true).await;
+        let thread_id = create_test_thread(&pool, user.id, "Rust AMA", "axum").await;
+        clear_search_vectors(&pool).await;
+
+        assert!(search(&pool, "axum").await.is_empty());
+
+        backfill(&pool, BACKFILL_BATCH_SIZE, |_| {}).await.unwrap();
+        assert_eq!(search(&pool, "axum").await, vec![thread_id]);
+    }
+
+    #[sqlx::test]
+    async fn test_バックフィルはバッチごとに進捗を報告する(pool: PgPool) {
+        // バックフィルがバッチごとに進捗を報告することを確認
+        let user = create_test_user(&pool, true).await;
+        for i in 0..5 {
+            create_test_thread(&pool, user.id, &format!("Thread {}", i), "content").await;
+        }
+        clear_search_vectors(&pool).await;
+        let updated_at_before: Vec<chrono::DateTime<chrono::Utc>> =
+            sqlx::query_scalar("SELECT updated_at FROM threads ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+
+        let mut progress = Vec::new();
+        let processed = backfill(&pool, 2, |processed| progress.push(processed))
+            .await
+            .unwrap();
+
+        assert_eq!(processed, 5);
+        assert_eq!(progress, vec![2, 4, 5]);
+        assert_eq!(pending_count(&pool).await.unwrap(), 0);
+        // バックフィルでは updated_at を変えない
+        let updated_at_after: Vec<chrono::DateTime<chrono::Utc>> =
+            sqlx::query_scalar("SELECT updated_at FROM threads ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(updated_at_before, updated_at_after);
+    }
+
+    #[sqlx::test]
+    async fn test_バックフィルは中断しても未設定のスレッドから再開する(
+        pool: PgPool,
+    ) {
+        // 中断したバックフィルは未設定のスレッドから再開することを確認
+        let user = create_test_user(&pool, true).await;
+        for i in 0..3 {
+            create_test_thread(&pool, user.id, &format!("Thread {}", i), "content").await;
+        }
+        clear_search_vectors(&pool).await;
+
+        // 1バッチ目の後に中断したのと同じ状態にする
+        sqlx::query(&format!(
+            "UPDATE threads SET search_vector = {} WHERE id = (SELECT id FROM threads ORDER BY id LIMIT 1)",
+            SEARCH_VECTOR_SQL
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending_count(&pool).await.unwrap(), 2);
+
+        let processed = backfill(&pool, 10, |_| {}).await.unwrap();
+
+        assert_eq!(processed, 2);
+        assert_eq!(pending_count(&pool).await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_インデックスを並行して作成できる(pool: PgPool) {
+        // 検索用のインデックスを並行して作成できることを確認
+        create_index_concurrently(&pool).await.unwrap();
+        // 2回目はすでにあるインデックスをそのまま使う
+        create_index_concurrently(&pool).await.unwrap();
+
+        let indexes: Vec<String> = sqlx::query_scalar(
+            "SELECT indexname::TEXT FROM pg_indexes WHERE tablename = 'threads' AND indexname LIKE 'idx_threads_search%'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(indexes, vec![SEARCH_INDEX.to_string()]);
+    }
+}
@@ -0,0 +1,64 @@
+// メールのリンクやセッション、パーマリンクの短いIDに使うトークンの生成
+//
+// 乱数に依存する処理は `AppState` の `TokenGenerator` を通して生成し、
+// テストでは固定のシードの実装に差し替えて、送信したリンクのトークンを検証できるようにします。
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::utils::{generate_secure_token, short_id};
+
+/// トークンの生成
+pub trait TokenGenerator: Send + Sync {
+    /// リフレッシュトークン・マジックリンク・メールアドレス変更などに使う32文字の16進数のトークン
+    fn secure_token(&self) -> String;
+
+    /// メール検証・パスワードリセットに使う `len` 文字の英数字のトークン
+    fn alphanumeric_token(&self, len: usize) -> String;
+
+    /// スレッド・コメントのパーマリンクに使う短いID（ランダムな64bit値のbase62表現）
+    fn short_id(&self) -> String;
+}
+
+/// スレッドごとの乱数生成器を使う本番用の実装
+pub struct RandomTokenGenerator;
+
+impl TokenGenerator for RandomTokenGenerator {
+    fn secure_token(&self) -> String {
+        generate_secure_token()
+    }
+
+    fn alphanumeric_token(&self, len: usize) -> String {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(len)
+            .map(char::from)
+            .collect()
+    }
+
+    fn short_id(&self) -> String {
+        short_id::encode_base62(rand::thread_rng().gen())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_英数字のトークンは指定した長さになる() {
+        // 英数字のトークンが指定した長さになることを確認
+        let token = RandomTokenGenerator.alphanumeric_token(64);
+
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(token, RandomTokenGenerator.alphanumeric_token(64));
+    }
+
+    #[test]
+    fn test_短いidはパーマリンクの形式になる() {
+        // 短いIDがパーマリンクの形式になることを確認
+        let id = RandomTokenGenerator.short_id();
+
+        assert!(short_id::is_short_id(&id));
+        assert_ne!(id, RandomTokenGenerator.short_id());
+    }
+}
@@ -0,0 +1,220 @@
+// 期限切れトークンの定期削除
+//
+// 使われなくなったリフレッシュトークンや、期限切れのメール認証・パスワードリセットのトークンが
+// 溜まり続けないよう、定期的に削除します。
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::{config::Config, error::AppError, models::maintenance::PurgeTokensResponse};
+
+/// 有効期限が切れてから行を削除するまでの猶予日数
+/// 直近に失効したトークンはセッション一覧や再送信のクールダウン判定に使うため残します
+pub const RETENTION_DAYS: i64 = 30;
+
+/// 期限切れのトークンを削除する
+///
+/// リフレッシュトークン・メール認証トークン・マジックリンク・端末の確認トークンは有効期限から `RETENTION_DAYS` 日経過した行を削除し、
+/// `users` テーブルのパスワードリセット・旧形式のメール認証トークンは期限切れになった時点で消去します。
+pub async fn purge_expired(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> Result<PurgeTokensResponse, AppError> {
+    let cutoff = now - Duration::days(RETENTION_DAYS);
+    let mut tx = pool.begin().await?;
+
+    let refresh_tokens = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let email_verification_tokens =
+        sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+    let magic_link_tokens = sqlx::query("DELETE FROM magic_link_tokens WHERE expires_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let device_confirmation_tokens =
+        sqlx::query("DELETE FROM device_confirmation_tokens WHERE expires_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+    let password_reset_tokens = sqlx::query(
+        r#"
+        UPDATE users
+        SET password_reset_token = NULL, password_reset_token_expires_at = NULL
+        WHERE password_reset_token IS NOT NULL AND password_reset_token_expires_at < $1
+        "#,
+    )
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let verification_tokens = sqlx::query(
+        r#"
+        UPDATE users
+        SET verification_token = NULL, verification_token_expires_at = NULL
+        WHERE verification_token IS NOT NULL AND verification_token_expires_at < $1
+        "#,
+    )
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    let result = PurgeTokensResponse {
+        refresh_tokens,
+        email_verification_tokens,
+        magic_link_tokens,
+        device_confirmation_tokens,
+        password_reset_tokens,
+        verification_tokens,
+    };
+    tracing::info!(
+        refresh_tokens,
+        email_verification_tokens,
+        magic_link_tokens,
+        device_confirmation_tokens,
+        password_reset_tokens,
+        verification_tokens,
+        "Purged expired tokens"
+    );
+
+    Ok(result)
+}
+
+/// 期限切れトークンを定期的に削除するジョブを起動する
+/// 失敗してもジョブは止めずに、次の実行で再試行します
+pub fn spawn_job(pool: PgPool, config: &Config) {
+    let interval = StdDuration::from_secs(config.token_purge_interval_seconds);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = purge_expired(&pool, Utc::now()).await {
+                tracing::error!("Token purge job failed: {:?}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+    use uuid::Uuid;
+
+    async fn insert_refresh_token(pool: &PgPool, user_id: Uuid, expires_at: DateTime<Utc>) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO refresh_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to insert refresh token")
+    }
+
+    async fn insert_verification_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Uuid {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+            VALUES ($1, 'test@example.com', $2, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to insert verification token")
+    }
+
+    async fn set_password_reset_token(pool: &PgPool, user_id: Uuid, expires_at: DateTime<Utc>) {
+        sqlx::query(
+            "UPDATE users SET password_reset_token = $2, password_reset_token_expires_at = $3 WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .bind(expires_at)
+        .execute(pool)
+        .await
+        .expect("Failed to set password reset token");
+    }
+
+    async fn ids(pool: &PgPool, table: &str) -> Vec<Uuid> {
+        sqlx::query_scalar(&format!("SELECT id FROM {} ORDER BY id", table))
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_猶予期間を過ぎたトークンのみ削除される(pool: PgPool) {
+        // 猶予期間を過ぎたトークンだけが削除されることを確認
+        let now = Utc::now();
+        let user = create_test_user(&pool, false).await;
+
+        // 31日前に期限切れ → 削除、1日前に期限切れ・有効 → 残す
+        insert_refresh_token(&pool, user.id, now - Duration::days(31)).await;
+        let recent = insert_refresh_token(&pool, user.id, now - Duration::days(1)).await;
+        let active = insert_refresh_token(&pool, user.id, now + Duration::days(7)).await;
+        insert_verification_token(&pool, user.id, now - Duration::days(31)).await;
+        let pending = insert_verification_token(&pool, user.id, now + Duration::hours(1)).await;
+
+        let result = purge_expired(&pool, now).await.unwrap();
+
+        assert_eq!(result.refresh_tokens, 1);
+        assert_eq!(result.email_verification_tokens, 1);
+        let mut expected = vec![recent, active];
+        expected.sort();
+        assert_eq!(ids(&pool, "refresh_tokens").await, expected);
+        assert_eq!(ids(&pool, "email_verification_tokens").await, vec![pending]);
+    }
+
+    #[sqlx::test]
+    async fn test_期限切れのパスワードリセットトークンのみ消去される(
+        pool: PgPool,
+    ) {
+        // 期限切れのパスワードリセットトークンだけが消去されることを確認
+        let now = Utc::now();
+        let expired = create_test_user(&pool, true).await;
+        let valid = create_test_user(&pool, true).await;
+        set_password_reset_token(&pool, expired.id, now - Duration::minutes(1)).await;
+        set_password_reset_token(&pool, valid.id, now + Duration::minutes(30)).await;
+
+        let result = purge_expired(&pool, now).await.unwrap();
+
+        assert_eq!(result.password_reset_tokens, 1);
+        let tokens: Vec<(Uuid, Option<String>)> =
+            sqlx::query_as("SELECT id, password_reset_token FROM users WHERE id = ANY($1)")
+                .bind(vec![expired.id, valid.id])
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        for (id, token) in tokens {
+            assert_eq!(token.is_some(), id == valid.id);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// ユーザーのブロック
+//
+// ブロックしたユーザーのスレッドは一覧から除き、コメントは返信を残したまま本文を隠します。
+// 保存した検索条件の通知にも、ブロックしたユーザーのスレッドは含めません。
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::users::BlockedUserResponse};
+
+/// ブロックする（既にブロックしている場合は何もしない）
+///
+/// 自分自身はブロックできません。
+pub async fn block(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), AppError> {
+    if blocker_id == blocked_id {
+        return Err(AppError::BadRequest(
+            "You cannot block yourself".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_blocks (blocker_id, blocked_id)
+        VALUES ($1, $2)
+        ON CONFLICT (blocker_id, blocked_id) DO NOTHING
+        "#,
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// ブロックを解除する（ブロックしていない場合は何もしない）
+pub async fn unblock(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// ブロックしているユーザーを新しくブロックした順に取得
+pub async fn list(pool: &PgPool, blocker_id: Uuid) -> Result<Vec<BlockedUserResponse>, AppError> {
+    let users = sqlx::query_as::<_, BlockedUserResponse>(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.avatar_url, b.created_at as blocked_at
+        FROM user_blocks b
+        JOIN users u ON u.id = b.blocked_id
+        WHERE b.blocker_id = $1
+        ORDER BY b.created_at DESC, u.id
+        "#,
+    )
+    .bind(blocker_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// ブロックしているユーザーのID
+pub async fn blocked_ids(pool: &PgPool, blocker_id: Uuid) -> Result<HashSet<Uuid>, AppError> {
+    let ids =
+        sqlx::query_scalar::<_, Uuid>("SELECT blocked_id FROM user_blocks WHERE blocker_id = $1")
+            .bind(blocker_id)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(ids.into_iter().collect())
+}
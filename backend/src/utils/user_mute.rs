@@ -0,0 +1,129 @@
+// ユーザーのミュート（一時的な投稿禁止）状態の判定
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::moderation::UserMute};
+
+/// 指定時刻に有効なミュートを取得する
+///
+/// ユーザーの最新のミュートの期限が `now` より後であれば有効とみなします。
+/// 期限切れの判定は時刻の比較のみで行うため、解除のためのジョブは不要です。
+pub async fn active_mute(
+    pool: &PgPool,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<UserMute>, AppError> {
+    let latest = sqlx::query_as::<_, UserMute>(
+        r#"
+        SELECT * FROM user_mutes
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(latest.filter(|mute| mute.expires_at > now))
+}
+
+/// ミュート中のユーザーの投稿を拒否する
+pub async fn ensure_not_muted(
+    pool: &PgPool,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    match active_mute(pool, user_id, now).await? {
+        Some(mute) => Err(AppError::UserMuted(mute.expires_at)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_user;
+    use chrono::{Duration, SubsecRound};
+
+    async fn insert_mute(
+        pool: &PgPool,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+        created_at: DateTime<Utc>,
+    ) {
+        sqlx::query("INSERT INTO user_mutes (user_id, expires_at, created_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(expires_at)
+            .bind(created_at)
+            .execute(pool)
+            .await
+            .expect("Failed to create mute");
+    }
+
+    #[sqlx::test]
+    async fn test_期限前は有効で期限後は無効になる(pool: PgPool) {
+        // 判定時刻を進めると期限を境にミュートが自動的に解除されることを確認
+        let user = create_test_user(&pool, true).await;
+        let now = Utc::now().trunc_subsecs(0);
+        let expires_at = now + Duration::minutes(30);
+        insert_mute(&pool, user.id, expires_at, now).await;
+
+        let mute = active_mute(&pool, user.id, now).await.unwrap();
+        assert_eq!(mute.unwrap().expires_at, expires_at);
+
+        let just_before = expires_at - Duration::seconds(1);
+        assert!(active_mute(&pool, user.id, just_before)
+            .await
+            .unwrap()
+            .is_some());
+
+        assert!(active_mute(&pool, user.id, expires_at)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(
+            ensure_not_muted(&pool, user.id, expires_at + Duration::minutes(1))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_最新のミュートのみが判定に使われる(pool: PgPool) {
+        // 後から期限の短いミュートを登録すると、そちらが優先されることを確認
+        let user = create_test_user(&pool, true).await;
+        let now = Utc::now().trunc_subsecs(0);
+        insert_mute(
+            &pool,
+            user.id,
+            now + Duration::days(7),
+            now - Duration::hours(2),
+        )
+        .await;
+        insert_mute(
+            &pool,
+            user.id,
+            now - Duration::minutes(1),
+            now - Duration::hours(1),
+        )
+        .await;
+
+        assert!(active_mute(&pool, user.id, now).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_ミュート中は投稿が拒否される(pool: PgPool) {
+        // ミュート中のユーザーの投稿がUSER_MUTEDで拒否されることを確認
+        let user = create_test_user(&pool, true).await;
+        let now = Utc::now().trunc_subsecs(0);
+        let expires_at = now + Duration::hours(1);
+        insert_mute(&pool, user.id, expires_at, now).await;
+
+        match ensure_not_muted(&pool, user.id, now).await {
+            Err(AppError::UserMuted(until)) => assert_eq!(until, expires_at),
+            other => panic!("Expected UserMuted, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// プロフィールに表示するユーザーの投稿の統計
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::users::UserStats};
+
+/// スレッド数・コメント数・カルマを1つのクエリで数える
+///
+/// 削除したコメントとモデレーターが非表示にしたコメントは数えません。
+/// カルマはスレッドが受けた賛成票から反対票を引いた数の合計です（コメントへの投票はまだ無い）。
+pub async fn stats(pool: &PgPool, user_id: Uuid) -> Result<UserStats, AppError> {
+    let stats = sqlx::query_as::<_, UserStats>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM threads WHERE user_id = $1) AS thread_count,
+            (
+                SELECT COUNT(*) FROM comments
+                WHERE user_id = $1 AND deleted_at IS NULL AND NOT removed_by_moderator
+            ) AS comment_count,
+            (
+                SELECT COALESCE(SUM(upvote_count - downvote_count), 0)::bigint
+                FROM threads WHERE user_id = $1
+            ) AS karma
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(stats)
+}
@@ -0,0 +1,100 @@
+// ユーザー名の変更履歴
+//
+// 変更前のユーザー名を記録し、古いユーザー名でのプロフィールの取得を現在のユーザーへ案内する。
+// 変更から RESERVATION_DAYS 日間は他のユーザーが古いユーザー名を使えないようにし、なりすましを防ぐ。
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{error::AppError, models::User};
+
+/// 変更前のユーザー名を他のユーザーが使えない期間（日）
+pub const RESERVATION_DAYS: i64 = 30;
+
+/// ユーザー名を変更できる間隔（日）
+pub const CHANGE_INTERVAL_DAYS: i64 = 14;
+
+/// 他のユーザーが `RESERVATION_DAYS` 日以内に使っていたユーザー名か
+///
+/// `user_id` を指定した場合は、そのユーザー自身が使っていたユーザー名は対象外にします。
+pub async fn is_reserved<'e>(
+    executor: impl PgExecutor<'e>,
+    username: &str,
+    user_id: Option<Uuid>,
+    now: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let reserved = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM username_history
+            WHERE LOWER(username) = LOWER($1)
+              AND changed_at > $3
+              AND ($2::uuid IS NULL OR user_id <> $2)
+        )
+        "#,
+    )
+    .bind(username)
+    .bind(user_id)
+    .bind(now - Duration::days(RESERVATION_DAYS))
+    .fetch_one(executor)
+    .await?;
+
+    Ok(reserved)
+}
+
+/// 次にユーザー名を変更できるまでの秒数（今すぐ変更できる場合はNone）
+pub async fn change_wait_seconds<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<u64>, AppError> {
+    let last_changed_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT MAX(changed_at) FROM username_history WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(last_changed_at.and_then(|changed_at| {
+        let wait = changed_at + Duration::days(CHANGE_INTERVAL_DAYS) - now;
+        (wait > Duration::zero()).then(|| wait.num_seconds().max(1) as u64)
+    }))
+}
+
+/// 変更前のユーザー名を記録する
+pub async fn record(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    old_username: &str,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO username_history (user_id, username, changed_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(old_username)
+        .bind(now)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// 古いユーザー名から現在のユーザーを取得する
+///
+/// 複数のユーザーが使っていた場合は、最後に使っていたユーザーを返します。退会したユーザーは含めません。
+pub async fn find_renamed_user(pool: &PgPool, username: &str) -> Result<Option<User>, AppError> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        SELECT u.*
+        FROM username_history h
+        JOIN users u ON u.id = h.user_id
+        WHERE LOWER(h.username) = LOWER($1) AND u.deleted_at IS NULL
+        ORDER BY h.changed_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
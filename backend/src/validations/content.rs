@@ -0,0 +1,168 @@
+// スレッド・コメントの本文とタイトルの検証
+//
+// 文字数は validate と同じく文字単位（`chars()`）で数えます。
+// 改行は保存前に LF に揃えるため、CRLF で送られた本文も LF として数えます。
+use serde::{Deserialize, Deserializer};
+use validator::ValidationError;
+
+/// スレッドのタイトルの最大文字数（前後の空白を除いて数える）
+pub const THREAD_TITLE_MAX_LENGTH: usize = 300;
+
+/// スレッドの本文の最大文字数
+pub const THREAD_CONTENT_MAX_LENGTH: usize = 40_000;
+
+/// コメントの本文の最大文字数
+pub const COMMENT_CONTENT_MAX_LENGTH: usize = 10_000;
+
+/// 改行を LF に揃える（CRLF と単独の CR を LF にする）
+pub fn normalize_newlines(value: &str) -> String {
+    value.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// serdeの deserialize_with で使う関数
+
+/// タイトルの改行を揃え、前後の空白を取り除く
+pub fn deserialize_title<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(normalize_newlines(value.trim()))
+}
+
+/// タイトルの改行を揃え、前後の空白を取り除く（Option）
+pub fn deserialize_title_optional<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|value| normalize_newlines(value.trim())))
+}
+
+/// 本文の改行を揃える
+pub fn deserialize_content<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(normalize_newlines(&value))
+}
+
+/// 本文の改行を揃える（Option）
+pub fn deserialize_content_optional<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|value| normalize_newlines(&value)))
+}
+
+/// スレッドのタイトルの検証（前後の空白を除いて 1〜300 文字）
+pub fn validate_thread_title(title: &str) -> Result<(), ValidationError> {
+    let length = title.trim().chars().count();
+    if length == 0 || length > THREAD_TITLE_MAX_LENGTH {
+        return Err(ValidationError::new("title_length").with_message(
+            format!(
+                "Title must be between 1 and {} characters",
+                THREAD_TITLE_MAX_LENGTH
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// スレッドの本文の検証（40,000 文字まで）
+///
+/// 本文は省略できるため空文字列は受け付けますが、空白だけの本文はエラーにします。
+pub fn validate_thread_content(content: &str) -> Result<(), ValidationError> {
+    if !content.is_empty() && content.trim().is_empty() {
+        return Err(blank_content());
+    }
+    if content.chars().count() > THREAD_CONTENT_MAX_LENGTH {
+        return Err(ValidationError::new("content_too_long").with_message(
+            format!(
+                "Content must be at most {} characters",
+                THREAD_CONTENT_MAX_LENGTH
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// コメントの本文の検証（空白以外を含む 1〜10,000 文字）
+pub fn validate_comment_content(content: &str) -> Result<(), ValidationError> {
+    if content.trim().is_empty() {
+        return Err(blank_content());
+    }
+    if content.chars().count() > COMMENT_CONTENT_MAX_LENGTH {
+        return Err(ValidationError::new("content_length").with_message(
+            format!(
+                "Content must be between 1 and {} characters",
+                COMMENT_CONTENT_MAX_LENGTH
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+fn blank_content() -> ValidationError {
+    ValidationError::new("content_blank").with_message("Content must not be blank".into())
+}
+
+// Optionをサポートするバリデータ関数
+
+pub fn validate_thread_title_optional(title: &Option<String>) -> Result<(), ValidationError> {
+    match title {
+        Some(title) => validate_thread_title(title),
+        None => Ok(()),
+    }
+}
+
+pub fn validate_thread_content_optional(content: &Option<String>) -> Result<(), ValidationError> {
+    match content {
+        Some(content) => validate_thread_content(content),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_改行をlfに揃える() {
+        // 改行をLFに揃えることを確認
+        assert_eq!(normalize_newlines("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize_newlines("改行なし"), "改行なし");
+    }
+
+    #[test]
+    fn test_タイトルは前後の空白を除いて数える() {
+        // タイトルは前後の空白を除いて数えることを確認
+        let max = "あ".repeat(THREAD_TITLE_MAX_LENGTH);
+        assert!(validate_thread_title(&max).is_ok());
+        assert!(validate_thread_title(&format!("  {}\n", max)).is_ok());
+        assert!(validate_thread_title(&format!("{}あ", max)).is_err());
+        assert!(validate_thread_title("a").is_ok());
+        assert!(validate_thread_title("").is_err());
+        assert!(validate_thread_title(" \t\n　").is_err());
+    }
+
+    #[test]
+    fn test_本文の上限と空白だけの本文() {
+        // 本文の上限と空白だけの本文を検証することを確認
+        assert!(validate_thread_content("").is_ok());
+        assert!(validate_thread_content(&"a".repeat(THREAD_CONTENT_MAX_LENGTH)).is_ok());
+        assert!(validate_thread_content(&"a".repeat(THREAD_CONTENT_MAX_LENGTH + 1)).is_err());
+        assert!(validate_thread_content(" \n\n ").is_err());
+
+        assert!(validate_comment_content("a").is_ok());
+        assert!(validate_comment_content(&"あ".repeat(COMMENT_CONTENT_MAX_LENGTH)).is_ok());
+        assert!(validate_comment_content(&"あ".repeat(COMMENT_CONTENT_MAX_LENGTH + 1)).is_err());
+        assert!(validate_comment_content("").is_err());
+        assert!(validate_comment_content("\n \n").is_err());
+    }
+}
@@ -1 +1,3 @@
+pub mod content;
+pub mod profile;
 pub mod username;
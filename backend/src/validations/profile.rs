@@ -0,0 +1,61 @@
+// プロフィールの自己紹介とウェブサイトの検証
+//
+// どちらも空文字列は「削除する」という意味で受け付けます。
+use validator::ValidationError;
+
+/// ウェブサイトに許可するURLスキーム
+const WEBSITE_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// ウェブサイトのURLの検証（http / https のみ。空文字列は削除として受け付ける）
+pub fn validate_website_url(url: &str) -> Result<(), ValidationError> {
+    if url.is_empty() {
+        return Ok(());
+    }
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if WEBSITE_URL_SCHEMES.contains(&parsed.scheme()) && parsed.has_host() => Ok(()),
+        _ => Err(ValidationError::new("website_url")
+            .with_message("Website URL must be a valid http or https URL".into())),
+    }
+}
+
+// Optionをサポートするバリデータ関数
+pub fn website_url_optional_validator(url: &Option<String>) -> Result<(), ValidationError> {
+    match url {
+        Some(url) => validate_website_url(url),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_httpとhttpsのurlは受け付ける() {
+        // httpとhttpsのURLは受け付けることを確認
+        assert!(validate_website_url("https://example.com").is_ok());
+        assert!(validate_website_url("http://example.com/about?lang=ja").is_ok());
+        // 空文字列は削除
+        assert!(validate_website_url("").is_ok());
+    }
+
+    #[test]
+    fn test_http以外のスキームや不正なurlは受け付けない() {
+        // http以外のスキームや不正なURLは受け付けないことを確認
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "ftp://example.com",
+            "mailto:user@example.com",
+            "example.com",
+            "https://",
+        ] {
+            assert!(
+                validate_website_url(url).is_err(),
+                "{} should be rejected",
+                url
+            );
+        }
+    }
+}
@@ -26,7 +26,7 @@ const replyCommentSchema = z.object({
   content: z
     .string()
     .min(1, "返信内容を入力してください")
-    .max(5000, "返信は5000文字以内で入力してください"),
+    .max(10000, "返信は10000文字以内で入力してください"),
 });
 
 type ReplyCommentForm = z.infer<typeof replyCommentSchema>;